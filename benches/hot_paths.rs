@@ -0,0 +1,499 @@
+//! Benchmarks of the import and query hot paths.
+//!
+//! The fixture databases are built once per run into `CARGO_TARGET_TMPDIR`, i.e., below
+//! `target/tmp`, and shared by all benchmarks:
+//!
+//! - the gnomAD exomes GRCh37 v2.1 example from `tests/gnomad-nuclear`, also served as
+//!   for the `/annos/variant` endpoint with and without `--rocksdb-mmap-reads`,
+//! - a TSV database with one SNV every 10 bp on the first 1 Mb of chr1, written with and
+//!   without `--prefix-bloom`, and
+//! - a HelixMtDb-like database with one record at each of the first 100 kb of chr1 for the
+//!   decoding of range queries.
+//!
+//! Run the full benchmarks with `cargo bench --bench hot_paths`, optionally filtered by
+//! name, e.g., `cargo bench --bench hot_paths -- keys`.  `cargo test --benches` (and thus
+//! `cargo test`) as well as `cargo bench --bench hot_paths -- --test` run each benchmark
+//! once as a smoke test.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use annonars::{
+    common::{self, keys, prefix_bloom},
+    gnomad_nuclear, pbs,
+    server::run::{annos_presence, annos_variant, genes_search, AnnoDb, GeneNames, WebServerData},
+    tsv,
+};
+use clap::Parser as _;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+type Db = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
+
+/// Number of base pairs between the variants of the TSV fixture.
+const TSV_STEP: usize = 10;
+/// Length of the region with variants of the TSV fixture, also the length of the range scan.
+const TSV_REGION_LEN: usize = 1_000_000;
+/// Number of records of the range decoding fixture.
+const RANGE_DECODE_LEN: usize = 100_000;
+/// Number of genes to search in, derived from the HGNC fixture.
+const N_GENES: usize = 40_000;
+
+/// The fixture databases.
+struct Fixtures {
+    /// Path of the gnomAD exomes database.
+    path_gnomad_exomes: String,
+    /// gnomAD exomes database.
+    gnomad_exomes: Arc<Db>,
+    /// TSV database.
+    tsv: Arc<Db>,
+    /// TSV database written with `--prefix-bloom`.
+    tsv_prefix_bloom: Arc<Db>,
+    /// HelixMtDb-like database for range decoding.
+    range_decode: Arc<Db>,
+    /// Coding context of the TSV database.
+    tsv_ctx: tsv::coding::Context,
+}
+
+/// Return the path of `path` relative to the crate root.
+fn manifest_path(path: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Return the line of the TSV fixture for the variant at `pos`.
+fn tsv_line(pos: usize) -> String {
+    format!(
+        "chr1\t{}\tA\tG\t0.{:03}\tgene{}\tmissense_variant\t{}",
+        pos,
+        pos % 1000,
+        pos / 10_000,
+        pos % 7
+    )
+}
+
+/// Return the arguments common to all commands.
+fn common_args() -> common::cli::Args {
+    common::cli::Args {
+        verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+        no_progress: true,
+    }
+}
+
+/// Open the database at `path` written with `--prefix-bloom` with the matching options.
+fn open_prefix_bloom(path: &str, cf_name: &str) -> Result<Arc<Db>, anyhow::Error> {
+    let mut options = rocksdb::Options::default();
+    let mut block_opts = rocksdb::BlockBasedOptions::default();
+    prefix_bloom::configure(&mut options, &mut block_opts);
+    options.set_block_based_table_factory(&block_opts);
+    Ok(Arc::new(rocksdb::DB::open_cf_for_read_only(
+        &options,
+        path,
+        ["meta", cf_name],
+        true,
+    )?))
+}
+
+/// Write the range decoding fixture database to `path`.
+fn write_range_decode(path: &Path) -> Result<Arc<Db>, anyhow::Error> {
+    use prost::Message as _;
+
+    let mut options = rocksdb::Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+    let db: Db = rocksdb::DB::open_cf(&options, path, ["meta", "data"])?;
+    let cf_data = db.cf_handle("data").unwrap();
+    let mut batch = rocksdb::WriteBatch::default();
+    for pos in 1..=RANGE_DECODE_LEN as i32 {
+        let record = helixmtdb::pbs::Record {
+            chrom: String::from("1"),
+            pos,
+            ref_allele: String::from("A"),
+            alt_allele: String::from("G"),
+            num_total: 196_554,
+            num_het: pos % 7,
+            num_hom: pos % 11,
+            feature_type: String::from("protein_coding"),
+            gene_name: format!("GENE{}", pos / 1000),
+        };
+        let key: Vec<u8> = keys::Var::from("1", pos, "A", "G").into();
+        batch.put_cf(&cf_data, key, record.encode_to_vec());
+    }
+    db.write(batch)?;
+    drop(cf_data);
+    Ok(Arc::new(db))
+}
+
+/// Build the fixture databases into `dir`.
+fn build_fixtures(dir: &Path) -> Result<Fixtures, anyhow::Error> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let path_gnomad_exomes = dir.join("gnomad-exomes").to_string_lossy().to_string();
+    gnomad_nuclear::cli::import::run(
+        &common_args(),
+        &gnomad_nuclear::cli::import::Args::try_parse_from([
+            "import",
+            "--path-in-vcf",
+            &manifest_path("tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz"),
+            "--path-out-rocksdb",
+            &path_gnomad_exomes,
+            "--gnomad-kind",
+            "exomes",
+            "--gnomad-version",
+            "2.1",
+            "--genome-release",
+            "grch37",
+            "--no-write-spec-yaml",
+        ])?,
+    )?;
+
+    let path_tsv = dir.join("data.tsv");
+    let mut lines = vec![String::from(
+        "CHROM\tPOS\tREF\tALT\tscore\tgene\tconsequence\tcount",
+    )];
+    lines.extend((TSV_STEP..=TSV_REGION_LEN).step_by(TSV_STEP).map(tsv_line));
+    std::fs::write(&path_tsv, lines.join("\n") + "\n")?;
+    let path_tsv = path_tsv.to_string_lossy().to_string();
+    let path_tsv_rocksdb = dir.join("tsv").to_string_lossy().to_string();
+    let path_tsv_prefix_bloom = dir.join("tsv-prefix-bloom").to_string_lossy().to_string();
+    for (path_out_rocksdb, prefix_bloom) in
+        [(&path_tsv_rocksdb, false), (&path_tsv_prefix_bloom, true)]
+    {
+        let mut args = vec![
+            "import",
+            "--genome-release",
+            "grch37",
+            "--path-in-tsv",
+            &path_tsv,
+            "--path-out-rocksdb",
+            path_out_rocksdb,
+            "--db-name",
+            "bench",
+            "--db-version",
+            "0.0.0",
+            "--col-chrom",
+            "CHROM",
+            "--col-start",
+            "POS",
+            "--col-ref",
+            "REF",
+            "--col-alt",
+            "ALT",
+            "--add-default-null-values",
+            "--no-write-spec-yaml",
+        ];
+        if prefix_bloom {
+            args.push("--prefix-bloom");
+        }
+        tsv::cli::import::run(
+            &common_args(),
+            &tsv::cli::import::Args::try_parse_from(args)?,
+        )?;
+    }
+
+    let (gnomad_exomes, _) = gnomad_nuclear::cli::query::open_rocksdb(
+        &path_gnomad_exomes,
+        "gnomad_nuclear_data",
+        "meta",
+    )?;
+    let (tsv, meta) = tsv::cli::query::open_rocksdb(&path_tsv_rocksdb, "tsv_data", "meta")?;
+    let tsv_prefix_bloom = open_prefix_bloom(&path_tsv_prefix_bloom, "tsv_data")?;
+    let range_decode = write_range_decode(&dir.join("range-decode"))?;
+    Ok(Fixtures {
+        path_gnomad_exomes,
+        gnomad_exomes,
+        tsv,
+        tsv_prefix_bloom,
+        range_decode,
+        tsv_ctx: tsv::coding::Context::new(meta.db_infer_config, meta.db_schema),
+    })
+}
+
+/// Return the fixture databases, building them on first use.
+fn fixtures() -> &'static Fixtures {
+    static FIXTURES: OnceLock<Fixtures> = OnceLock::new();
+    FIXTURES.get_or_init(|| {
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("annonars-bench-fixtures");
+        build_fixtures(&dir).expect("could not build fixture databases")
+    })
+}
+
+/// Return the server data with the gnomAD exomes fixture, read with `mmap_reads`.
+fn server_data(mmap_reads: bool) -> Result<WebServerData, anyhow::Error> {
+    let (genome_release, db_info, db) = annonars::server::run::open_anno_db(
+        &fixtures().path_gnomad_exomes,
+        AnnoDb::GnomadExomes,
+        None,
+        mmap_reads,
+    )?;
+    let mut data = WebServerData::default();
+    data.insert_anno_db(genome_release, db_info, db)?;
+    Ok(data)
+}
+
+/// Return the query for a variant of the gnomAD exomes fixture.
+fn gnomad_exomes_query() -> annos_variant::SeqvarsAnnosQuery {
+    annos_variant::SeqvarsAnnosQuery {
+        genome_release: String::from("grch37"),
+        chromosome: String::from("1"),
+        pos: 55_505_599,
+        reference: String::from("C"),
+        alternative: String::from("G"),
+        clinvar_release: None,
+        haplogroup: None,
+    }
+}
+
+/// Return `N_GENES` gene names derived from the HGNC fixture.
+///
+/// The fixture only has a few genes, so they are repeated with numbered symbols and names to
+/// get to about the size of the full HGNC gene list.
+fn gene_names() -> Result<Vec<GeneNames>, anyhow::Error> {
+    let templates = std::fs::read_to_string(manifest_path("tests/genes/hgnc/hgnc_info.jsonl"))?
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let strings = |hgnc: &serde_json::Value, key: &str, i: usize| -> serde_json::Value {
+        hgnc[key]
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(|value| format!("{}{}", value, i))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+            .into()
+    };
+    (0..N_GENES)
+        .map(|i| {
+            let hgnc = &templates[i % templates.len()];
+            let symbol = hgnc["symbol"].as_str().unwrap_or_default();
+            let name = hgnc["name"].as_str().unwrap_or_default();
+            Ok(serde_json::from_value(serde_json::json!({
+                "hgnc_id": format!("HGNC:{}", i + 1),
+                "symbol": format!("{}{}", symbol, i),
+                "name": format!("{} {}", name, i),
+                "alias_symbol": strings(hgnc, "alias_symbol", i),
+                "alias_name": strings(hgnc, "alias_name", i),
+                "prev_symbol": strings(hgnc, "prev_symbol", i),
+                "ensembl_gene_id": null,
+                "ncbi_gene_id": null,
+            }))?)
+        })
+        .collect()
+}
+
+fn bench_keys(c: &mut Criterion) {
+    let var = keys::Var::from("17", 41_197_708, "T", "G");
+    let key: Vec<u8> = var.clone().into();
+
+    let mut group = c.benchmark_group("keys");
+    group.bench_function("encode_var", |b| {
+        b.iter(|| Vec::<u8>::from(black_box(&var).clone()))
+    });
+    group.bench_function("decode_pos", |b| {
+        b.iter(|| keys::Pos::from(black_box(key.as_slice())))
+    });
+    group.bench_function("chrom_name_to_key", |b| {
+        b.iter(|| keys::chrom_name_to_key(black_box("chrX")))
+    });
+    group.finish();
+}
+
+fn bench_tsv_line_to_values(c: &mut Criterion) {
+    let ctx = &fixtures().tsv_ctx;
+    let line = tsv_line(TSV_REGION_LEN / 2);
+
+    c.bench_function("tsv/line_to_values", |b| {
+        b.iter(|| ctx.line_to_values(black_box(&line)).unwrap())
+    });
+}
+
+fn bench_gnomad_decode(c: &mut Criterion) {
+    let db = &fixtures().gnomad_exomes;
+    let cf_data = db.cf_handle("gnomad_nuclear_data").unwrap();
+    let key: Vec<u8> = keys::Var::from("1", 55_505_599, "C", "G").into();
+    let raw_value = db
+        .get_cf(&cf_data, &key)
+        .unwrap()
+        .expect("variant not in gnomAD exomes fixture");
+
+    c.bench_function("gnomad/decode_record", |b| {
+        b.iter(|| {
+            common::decode::decode_protobuf::<pbs::gnomad::gnomad2::Record>(
+                db,
+                "gnomad_nuclear_data",
+                &key,
+                black_box(&raw_value),
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn bench_point_lookup(c: &mut Criterion) {
+    let db = &fixtures().gnomad_exomes;
+    let cf_data = db.cf_handle("gnomad_nuclear_data").unwrap();
+    let found: Vec<u8> = keys::Var::from("1", 55_505_599, "C", "G").into();
+    let missing: Vec<u8> = keys::Var::from("1", 55_505_600, "C", "G").into();
+
+    let mut group = c.benchmark_group("point_lookup");
+    for (name, key) in [("found", &found), ("missing", &missing)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                db.get_pinned_cf(&cf_data, black_box(key))
+                    .unwrap()
+                    .is_some()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_prefix_bloom(c: &mut Criterion) {
+    let fixtures = fixtures();
+    let pos = (TSV_REGION_LEN / 2) as i32;
+    // Every variant is at a multiple of `TSV_STEP`, so the second key is at a position
+    // without variants.
+    let found: Vec<u8> = keys::Var::from("1", pos, "A", "G").into();
+    let missing: Vec<u8> = keys::Var::from("1", pos + 5, "A", "G").into();
+
+    let mut group = c.benchmark_group("prefix_bloom");
+    for (name, db) in [
+        ("without", &fixtures.tsv),
+        ("with", &fixtures.tsv_prefix_bloom),
+    ] {
+        let cf_data = db.cf_handle("tsv_data").unwrap();
+        assert!(db.get_pinned_cf(&cf_data, &found).unwrap().is_some());
+        for (lookup, key) in [("found", &found), ("missing", &missing)] {
+            group.bench_function(format!("{}/{}", name, lookup), |b| {
+                b.iter(|| {
+                    db.get_pinned_cf(&cf_data, black_box(key))
+                        .unwrap()
+                        .is_some()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_range_scan(c: &mut Criterion) {
+    let db = &fixtures().tsv;
+    let cf_data = db.cf_handle("tsv_data").unwrap();
+    let start = keys::Pos::from("1", 1);
+    let stop = keys::Pos::from("1", TSV_REGION_LEN as i32);
+
+    let mut group = c.benchmark_group("range_scan");
+    group.throughput(Throughput::Elements((TSV_REGION_LEN / TSV_STEP) as u64));
+    group.bench_function("tsv_1mb", |b| {
+        b.iter(|| {
+            let mut iter =
+                db.raw_iterator_cf_opt(&cf_data, prefix_bloom::scan_read_options(&start, &stop));
+            iter.seek_to_first();
+            let mut count = 0;
+            while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                black_box((keys::Pos::from(key), value));
+                count += 1;
+                iter.next();
+            }
+            iter.status().unwrap();
+            assert_eq!(count, TSV_REGION_LEN / TSV_STEP);
+        })
+    });
+    group.finish();
+}
+
+fn bench_range_decode(c: &mut Criterion) {
+    let db = &fixtures().range_decode;
+    let start = keys::Pos::from("1", 1);
+    let stop = keys::Pos::from("1", RANGE_DECODE_LEN as i32);
+
+    let mut group = c.benchmark_group("range_decode");
+    group.throughput(Throughput::Elements(RANGE_DECODE_LEN as u64));
+    for threads in [1, 2, 4, 8] {
+        let decoder = RangeDecoder::new(threads).unwrap();
+        group.bench_function(format!("threads_{}", threads), |b| {
+            b.iter(|| {
+                fetch::fetch_pos_protobuf_json_with_decoder::<helixmtdb::pbs::Record>(
+                    db,
+                    "data",
+                    start.clone(),
+                    stop.clone(),
+                    black_box(&decoder),
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_annos_variant(c: &mut Criterion) {
+    let data = server_data(false).expect("could not open gnomAD exomes fixture");
+    let query = gnomad_exomes_query();
+
+    let mut group = c.benchmark_group("annos_variant");
+    group.bench_function("full", |b| {
+        b.iter(|| annos_variant::fetch_annos(&data, black_box(&query)).unwrap())
+    });
+    group.bench_function("presence_only", |b| {
+        b.iter(|| annos_presence::fetch_presence(&data, black_box(&query)).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_mmap_reads(c: &mut Criterion) {
+    let query = gnomad_exomes_query();
+
+    let mut group = c.benchmark_group("mmap_reads");
+    for (name, mmap_reads) in [("block_cache", false), ("mmap", true)] {
+        let data = server_data(mmap_reads).expect("could not open gnomAD exomes fixture");
+        group.bench_function(name, |b| {
+            b.iter(|| annos_variant::fetch_annos(&data, black_box(&query)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_genes_search(c: &mut Criterion) {
+    let gene_names = gene_names().expect("could not build gene names");
+
+    let mut group = c.benchmark_group("genes_search");
+    group.throughput(Throughput::Elements(gene_names.len() as u64));
+    for q in ["TTN", "HGNC:12403", "titin"] {
+        let query = genes_search::GenesSearchQuery {
+            q: q.to_string(),
+            fields: None,
+            case_sensitive: None,
+            include_scores: None,
+        };
+        group.bench_function(q, |b| {
+            b.iter(|| genes_search::search(black_box(&gene_names), &query))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_keys,
+    bench_tsv_line_to_values,
+    bench_gnomad_decode,
+    bench_point_lookup,
+    bench_prefix_bloom,
+    bench_range_scan,
+    bench_range_decode,
+    bench_annos_variant,
+    bench_mmap_reads,
+    bench_genes_search,
+);
+criterion_main!(benches);
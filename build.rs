@@ -6,6 +6,7 @@ use std::{env, path::PathBuf};
 fn main() -> Result<(), anyhow::Error> {
     let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("protos");
     let proto_files = vec![
+        "annonars/alphamissense/base.proto",
         "annonars/clinvar_data/class_by_freq.proto",
         "annonars/clinvar_data/clinvar_public.proto",
         "annonars/clinvar_data/extracted_vars.proto",
@@ -31,6 +32,7 @@ fn main() -> Result<(), anyhow::Error> {
         "annonars/gnomad/vep_gnomad3.proto",
         "annonars/gnomad/vep_gnomad4.proto",
         "annonars/helixmtdb/base.proto",
+        "annonars/regions/bed.proto",
         "annonars/regions/clingen.proto",
     ]
     .iter()
@@ -0,0 +1,393 @@
+//! Import AlphaMissense annotation data.
+
+use std::{collections::BTreeMap, io::BufRead, sync::Arc};
+
+use clap::Parser;
+use indicatif::ParallelProgressIterator;
+use noodles::csi::BinningIndex as _;
+use prost::Message;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{alphamissense, common, tsv::cli::import::par_tbi};
+
+/// Command line arguments for `alphamissense import` sub command.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "import AlphaMissense data into RocksDB", long_about = None)]
+pub struct Args {
+    /// Genome build to use in the build.
+    #[arg(long, value_enum)]
+    pub genome_release: common::cli::GenomeRelease,
+    /// Path to input bgzip-ed and tabix-indexed TSV file, e.g., `AlphaMissense_hg19.tsv.gz`.
+    #[arg(long, required = true)]
+    pub path_in_tsv: String,
+    /// Path to output RocksDB directory.
+    #[arg(long)]
+    pub path_out_rocksdb: String,
+    /// Version of the AlphaMissense data, e.g., `2023`.
+    #[arg(long)]
+    pub db_version: String,
+
+    /// Windows size for TBI-based parallel import.
+    #[arg(long, default_value = "100000")]
+    pub tbi_window_size: usize,
+
+    /// Name of the column family to import into.
+    #[arg(long, default_value = "alphamissense_data")]
+    pub cf_name: String,
+    /// Optional path to RocksDB WAL directory.
+    #[arg(long)]
+    pub path_wal_dir: Option<String>,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Check the header of the AlphaMissense TSV file at `path_in_tsv`.
+///
+/// The header consists of `#` comment lines, the last one giving the column names.  The
+/// `genome` column of the first record must match `genome_release`.
+pub fn check_header(
+    path_in_tsv: &str,
+    genome_release: common::cli::GenomeRelease,
+) -> Result<(), anyhow::Error> {
+    let reader = std::fs::File::open(path_in_tsv)
+        .map(noodles::bgzf::Reader::new)
+        .map(std::io::BufReader::new)?;
+
+    let mut header_line = None;
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') {
+            header_line = Some(line);
+            continue;
+        }
+
+        let header_line =
+            header_line.ok_or_else(|| anyhow::anyhow!("missing header line in {}", path_in_tsv))?;
+        let columns = header_line.split('\t').collect::<Vec<_>>();
+        if columns != alphamissense::pbs::HEADER_COLUMNS {
+            anyhow::bail!(
+                "unexpected header line in {}: {:?}",
+                path_in_tsv,
+                header_line
+            );
+        }
+
+        let expected_genome = match genome_release {
+            common::cli::GenomeRelease::Grch37 => "hg19",
+            common::cli::GenomeRelease::Grch38 => "hg38",
+        };
+        let genome = line.split('\t').nth(4).unwrap_or_default();
+        if genome != expected_genome {
+            anyhow::bail!(
+                "genome {:?} in {} does not match genome release {}",
+                genome,
+                path_in_tsv,
+                genome_release
+            );
+        }
+
+        return Ok(());
+    }
+
+    anyhow::bail!("no records in {}", path_in_tsv)
+}
+
+/// Perform TBI-parallel import of the data.
+fn tsv_import(
+    db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    // Load tabix header and create BGZF reader with tabix index.
+    let tabix_src = format!("{}.tbi", args.path_in_tsv);
+    let index = noodles::tabix::read(tabix_src)?;
+    let header = index.header().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing tabix header")
+    })?;
+    // Build map from canonical chromosome names to index of the name in the header.
+    let canonical_header_chroms = header
+        .reference_sequence_names()
+        .iter()
+        .enumerate()
+        .filter_map(|(tid, chrom)| {
+            let canon_chrom = chrom.strip_prefix("chr").unwrap_or(chrom);
+            if common::cli::is_canonical(canon_chrom) {
+                Some((common::cli::canonicalize(canon_chrom), (tid, chrom.clone())))
+            } else {
+                None
+            }
+        })
+        .collect::<std::collections::HashMap<String, (usize, String)>>();
+
+    // Generate list of regions on canonical chromosomes, limited to those present in header.
+    let windows =
+        common::cli::build_genome_windows(args.genome_release.into(), Some(args.tbi_window_size))?
+            .into_iter()
+            .filter_map(|(window_chrom, begin, end)| {
+                let canon_chrom = common::cli::canonicalize(&window_chrom);
+                canonical_header_chroms
+                    .get(&canon_chrom)
+                    .map(|(tid, header_chrom)| {
+                        let start = noodles::core::Position::try_from(begin + 1)
+                            .expect("could not convert to position");
+                        let stop = noodles::core::Position::try_from(std::cmp::max(begin + 1, end))
+                            .expect("could not convert to position");
+                        (
+                            *tid,
+                            noodles::core::Region::new(header_chrom.clone(), start..=stop),
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+
+    tracing::info!("Loading AlphaMissense TSV file into RocksDB...");
+    let before_loading = std::time::Instant::now();
+    windows
+        .par_iter()
+        .progress_with(common::cli::progress_bar(windows.len()))
+        .map(|window| process_window(&db, args, window))
+        .collect::<Result<Vec<_>, _>>()?;
+    tracing::info!(
+        "... done loading AlphaMissense TSV file into RocksDB in {:?}",
+        before_loading.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Process one window.
+///
+/// The records of all transcripts of a variant are collected into one `RecordList`.  As the
+/// windows do not overlap, all records of a variant are in the same window.
+fn process_window(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    args: &Args,
+    window: &(usize, noodles::core::Region),
+) -> Result<(), anyhow::Error> {
+    let cf_data = db.cf_handle(&args.cf_name).unwrap();
+
+    // Read tabix index.
+    let tabix_src = format!("{}.tbi", args.path_in_tsv);
+    let index = noodles::tabix::read(tabix_src)?;
+    let header = index.header().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing tabix header")
+    })?;
+    let mut reader = std::fs::File::open(&args.path_in_tsv).map(noodles::bgzf::Reader::new)?;
+
+    // Query TBI for chunks.
+    let (tid, region) = window;
+    tracing::debug!("  processing region: {:?}", region);
+    let chunks = index.query(*tid, region.interval())?;
+    let query = noodles::csi::io::Query::new(&mut reader, chunks);
+
+    // Collect the overlapping records by variant.
+    let mut record_lists: BTreeMap<Vec<u8>, alphamissense::pbs::RecordList> = BTreeMap::new();
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
+    for result in query.lines() {
+        let line = result?;
+        if !par_tbi::intersects(header, &line, region)? {
+            continue;
+        }
+
+        let record = alphamissense::pbs::Record::from_tsv_line(&line)?;
+        let var = common::keys::Var::from(
+            &record.chrom,
+            record.pos,
+            &record.ref_allele,
+            &record.alt_allele,
+        );
+        let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+            continue;
+        };
+        let key_buf: Vec<u8> = var.into();
+        tracing::trace!("  record: {:?}", &record);
+        record_lists
+            .entry(key_buf)
+            .or_default()
+            .records
+            .push(record);
+    }
+
+    for (key_buf, record_list) in record_lists {
+        db.put_cf(&cf_data, &key_buf, record_list.encode_to_vec())?;
+    }
+    invalid_alleles.log(&region.to_string());
+
+    Ok(())
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if validation.input_file(&args.path_in_tsv) {
+        validation.tabix_index(&args.path_in_tsv);
+        validation.check(
+            format!("AlphaMissense header of {} is valid", &args.path_in_tsv),
+            check_header(&args.path_in_tsv, args.genome_release),
+        );
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
+/// Implementation of `alphamissense import` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'alphamissense import' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
+    check_header(&args.path_in_tsv, args.genome_release)?;
+
+    // Open the RocksDB for writing.
+    tracing::info!("Opening RocksDB for writing ...");
+    let before_opening_rocksdb = std::time::Instant::now();
+    let options = rocksdb_utils_lookup::tune_options(
+        rocksdb::Options::default(),
+        args.path_wal_dir.as_ref().map(|s| s.as_ref()),
+    );
+    let cf_names = &["meta", &args.cf_name];
+    let db = Arc::new(rocksdb::DB::open_cf_with_opts(
+        &options,
+        common::readlink_f(&args.path_out_rocksdb)?,
+        cf_names
+            .iter()
+            .map(|name| (name.to_string(), options.clone()))
+            .collect::<Vec<_>>(),
+    )?);
+    tracing::info!("  writing meta information");
+    let cf_meta = db.cf_handle("meta").unwrap();
+    db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
+    db.put_cf(
+        &cf_meta,
+        "genome-release",
+        format!("{}", args.genome_release),
+    )?;
+    db.put_cf(&cf_meta, "db-version", &args.db_version)?;
+    tracing::info!(
+        "... done opening RocksDB for writing in {:?}",
+        before_opening_rocksdb.elapsed()
+    );
+
+    tsv_import(db.clone(), args)?;
+
+    tracing::info!("Running RocksDB compaction ...");
+    let before_compaction = std::time::Instant::now();
+    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+    tracing::info!(
+        "... done compacting RocksDB in {:?}",
+        before_compaction.elapsed()
+    );
+
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "alphamissense".to_string(),
+            title: "AlphaMissense".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("AlphaMissense".to_string(), args.db_version.clone())],
+            paths_in: vec![args.path_in_tsv.clone()],
+        },
+    )?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    #[test]
+    fn smoke_test_import_alphamissense() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_tsv: String::from("tests/alphamissense/example/alphamissense.tsv.bgz"),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: String::from("2023"),
+            cf_name: String::from("alphamissense_data"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: false,
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+        };
+
+        run(&common, &args).unwrap();
+
+        let spec =
+            crate::server::run::versions::schema::VersionSpec::from_path(tmp_dir.join("spec.yaml"))
+                .unwrap();
+        assert_eq!(spec.identifier, "annonars/alphamissense:2023+0.0.0-grch37");
+    }
+
+    #[test]
+    fn check_header_wrong_genome_release() {
+        let err = check_header(
+            "tests/alphamissense/example/alphamissense.tsv.bgz",
+            common::cli::GenomeRelease::Grch38,
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("does not match genome release"));
+    }
+
+    /// Dry run must not create the database and must find the uncompressed input file.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_tsv: String::from("tests/alphamissense/example/alphamissense.tsv.bgz"),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: String::from("2023"),
+            cf_name: String::from("alphamissense_data"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: true,
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        // Neither the tabix index nor the header can be read.
+        let args = Args {
+            path_in_tsv: String::from("tests/alphamissense/example/alphamissense.tsv"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 2, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
+}
@@ -0,0 +1,4 @@
+//! Command line interface for AlphaMissense annotation data.
+
+pub mod import;
+pub mod query;
@@ -0,0 +1,319 @@
+//! Query of AlphaMissense annotation data.
+
+use std::sync::Arc;
+
+use crate::{
+    alphamissense,
+    common::{self, cli::extract_chrom, decode::decode_protobuf, keys, spdi},
+    cons::cli::args::vars::ArgsQuery,
+};
+
+/// Command line arguments for `alphamissense query` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "query AlphaMissense data stored in RocksDB", long_about = None)]
+pub struct Args {
+    /// Path to RocksDB directory with data.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Name of the column family to import into.
+    #[arg(long, default_value = "alphamissense_data")]
+    pub cf_name: String,
+    /// Output file (default is stdout == "-").
+    #[arg(long, default_value = "-")]
+    pub out_file: String,
+    /// Output format.
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
+
+    /// Variant or position to query for.
+    #[command(flatten)]
+    pub query: ArgsQuery,
+}
+
+/// Meta information as read from database.
+#[derive(Debug)]
+pub struct Meta {
+    /// Genome release of data in database.
+    pub genome_release: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
+}
+
+/// Open RocksDb given path and column family name for data and metadata.
+pub fn open_rocksdb<P: AsRef<std::path::Path>>(
+    path_rocksdb: P,
+    cf_data: &str,
+    cf_meta: &str,
+) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
+    tracing::info!("Opening RocksDB database ...");
+    let before_open = std::time::Instant::now();
+    let cf_names = &[cf_meta, cf_data];
+    let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
+        &rocksdb::Options::default(),
+        common::readlink_f_existing(&path_rocksdb)?,
+        cf_names,
+        true,
+    )?);
+    tracing::info!("  reading meta information");
+    let meta = {
+        let cf_meta = db.cf_handle(cf_meta).unwrap();
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
+        Meta {
+            genome_release: meta_genome_release,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
+        }
+    };
+
+    tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
+    tracing::info!(
+        "... opening RocksDB database took {:?}",
+        before_open.elapsed()
+    );
+
+    Ok((db, meta))
+}
+
+/// Open RocksDB database from command line arguments.
+pub fn open_rocksdb_from_args(
+    args: &Args,
+) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
+    open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
+}
+
+/// Query for a single variant in the RocksDB database.
+pub fn query_for_variant(
+    variant: &common::spdi::Var,
+    meta: &Meta,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_name: &str,
+) -> Result<Option<alphamissense::pbs::RecordList>, anyhow::Error> {
+    // Split off the genome release (checked) and convert to key as used in database.
+    let query = spdi::Var {
+        sequence: extract_chrom::from_var(variant, Some(&meta.genome_release))?,
+        ..variant.clone()
+    };
+    // Execute query.
+    tracing::debug!("query = {:?}", &query);
+    let key = common::norm::query_key(query, meta.keys_normalized);
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let raw_value = db
+        .get_cf(&cf_data, &key)
+        .map_err(|e| anyhow::anyhow!("problem querying RocksDB: {}", e))?;
+    // Decode via prost.
+    Ok(raw_value
+        .map(|raw_value| {
+            decode_protobuf::<alphamissense::pbs::RecordList>(db, cf_data_name, &key, &raw_value)
+        })
+        .transpose()?)
+}
+
+/// Implementation of `alphamissense query` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'alphamissense query' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+    common::batch::check_unsupported(&args.query, "alphamissense query")?;
+
+    let (db, meta) = open_rocksdb_from_args(args)?;
+    let cf_data = db.cf_handle(&args.cf_name).unwrap();
+
+    // Obtain writer to output.
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "alphamissense",
+        Some(&meta.genome_release),
+    )?;
+
+    tracing::info!("Running query...");
+    let before_query = std::time::Instant::now();
+    if let Some(variant) = args.query.variant.as_ref() {
+        if let Some(record) = query_for_variant(variant, &meta, &db, &args.cf_name)? {
+            out_writer.write(
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &record,
+            )?;
+        } else {
+            tracing::info!("no record found for variant {:?}", &variant);
+        }
+    } else {
+        let (start, stop) = if let Some(position) = args.query.position.as_ref() {
+            let position = spdi::Pos {
+                sequence: extract_chrom::from_pos(position, Some(&meta.genome_release))?,
+                ..position.clone()
+            };
+            (Some(position.clone()), Some(position))
+        } else if let Some(range) = args.query.range.as_ref() {
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
+            let (start, stop) = range.into();
+            (Some(start), Some(stop))
+        } else if args.query.all {
+            (None, None)
+        } else {
+            unreachable!()
+        };
+
+        tracing::debug!("start = {:?}, stop = {:?}", &start, &stop);
+
+        // Obtain iterator and seek to start.
+        let mut iter = db.raw_iterator_cf(&cf_data);
+        if let Some(start) = start {
+            let pos: keys::Pos = start.into();
+            let key: Vec<u8> = pos.into();
+            tracing::debug!("seeking to key {:?}", &key);
+            iter.seek(&key);
+        } else {
+            iter.seek(b"")
+        }
+
+        // Cast stop to `keys::Pos`.
+        let stop = stop.map(|stop| -> keys::Pos { stop.into() });
+        if let Some(stop) = stop.as_ref() {
+            let stop: Vec<u8> = stop.clone().into();
+            tracing::debug!("stop = {:?}", &stop);
+        }
+
+        // Iterate over all variants until we are behind stop.
+        while iter.valid() {
+            if let Some(raw_value) = iter.value() {
+                tracing::trace!("iterator at {:?} => {:?}", &iter.key(), &raw_value);
+                if let Some(stop) = stop.as_ref() {
+                    let iter_key = iter.key().unwrap();
+                    let iter_pos: keys::Pos = iter_key.into();
+
+                    if iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos {
+                        break;
+                    }
+                }
+
+                let iter_key = iter.key().unwrap();
+                let record: alphamissense::pbs::RecordList =
+                    decode_protobuf(&db, &args.cf_name, iter_key, raw_value)?;
+                out_writer.write(&common::output::db_key(iter_key), &record)?;
+                iter.next();
+            } else {
+                break;
+            }
+        }
+    }
+    tracing::info!("... done querying in {:?}", before_query.elapsed());
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    /// Import the example data into a temporary RocksDB and build query arguments for it.
+    fn args(query: ArgsQuery) -> (common::cli::Args, Args, TempDir) {
+        let temp = TempDir::default();
+        let common = common::cli::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = format!("{}", temp.join("rocksdb").display());
+        alphamissense::cli::import::run(
+            &common,
+            &alphamissense::cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_tsv: String::from("tests/alphamissense/example/alphamissense.tsv.bgz"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                db_version: String::from("2023"),
+                tbi_window_size: 1_000_000,
+                cf_name: String::from("alphamissense_data"),
+                path_wal_dir: None,
+                allow_iupac: false,
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        let args = Args {
+            path_rocksdb,
+            cf_name: String::from("alphamissense_data"),
+            out_file: temp.join("out").to_string_lossy().to_string(),
+            output: Default::default(),
+            query,
+        };
+
+        (common, args, temp)
+    }
+
+    #[test]
+    fn smoke_query_all() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            all: true,
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_var_single() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:69094:G:T")?),
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_var_multiple_transcripts() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:861332:G:A")?),
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_pos_single() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            position: Some(spdi::Pos::from_str("GRCh37:1:69094")?),
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_range_find_none() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:1:69093")?),
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+}
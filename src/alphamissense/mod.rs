@@ -0,0 +1,4 @@
+//! Annotation using AlphaMissense per-variant pathogenicity scores.
+
+pub mod cli;
+pub mod pbs;
@@ -0,0 +1,94 @@
+//! Data structures for (de-)serialization as generated by `prost-build`.
+
+pub use crate::pbs::alphamissense::{AmClass, Record, RecordList};
+
+/// Columns of the header line of the AlphaMissense TSV files.
+pub const HEADER_COLUMNS: &[&str] = &[
+    "#CHROM",
+    "POS",
+    "REF",
+    "ALT",
+    "genome",
+    "uniprot_id",
+    "transcript_id",
+    "protein_variant",
+    "am_pathogenicity",
+    "am_class",
+];
+
+impl std::str::FromStr for AmClass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "likely_benign" => AmClass::LikelyBenign,
+            "ambiguous" => AmClass::Ambiguous,
+            "likely_pathogenic" => AmClass::LikelyPathogenic,
+            _ => anyhow::bail!("invalid AlphaMissense class: {:?}", s),
+        })
+    }
+}
+
+impl Record {
+    /// Creates a new `Record` from a line of the AlphaMissense TSV file.
+    pub fn from_tsv_line(line: &str) -> Result<Self, anyhow::Error> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != HEADER_COLUMNS.len() {
+            anyhow::bail!(
+                "expected {} columns but found {} in line {:?}",
+                HEADER_COLUMNS.len(),
+                fields.len(),
+                line
+            );
+        }
+
+        Ok(Record {
+            chrom: fields[0].to_string(),
+            pos: fields[1]
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid position {:?}: {}", fields[1], e))?,
+            ref_allele: fields[2].to_string(),
+            alt_allele: fields[3].to_string(),
+            uniprot_id: fields[5].to_string(),
+            transcript_id: fields[6].to_string(),
+            protein_variant: fields[7].to_string(),
+            am_pathogenicity: fields[8]
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid score {:?}: {}", fields[8], e))?,
+            am_class: fields[9].parse::<AmClass>()? as i32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_from_tsv_line() -> Result<(), anyhow::Error> {
+        let record = Record::from_tsv_line(
+            "chr1\t69094\tG\tT\thg19\tQ8NH21\tENST00000335137.4\tV2L\t0.2937\tlikely_benign",
+        )?;
+
+        assert_eq!(record.chrom, "chr1");
+        assert_eq!(record.pos, 69094);
+        assert_eq!(record.ref_allele, "G");
+        assert_eq!(record.alt_allele, "T");
+        assert_eq!(record.uniprot_id, "Q8NH21");
+        assert_eq!(record.transcript_id, "ENST00000335137.4");
+        assert_eq!(record.protein_variant, "V2L");
+        assert_eq!(record.am_pathogenicity, 0.2937);
+        assert_eq!(record.am_class(), AmClass::LikelyBenign);
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_from_tsv_line_invalid() {
+        assert!(Record::from_tsv_line("chr1\t69094\tG\tT").is_err());
+        assert!(Record::from_tsv_line(
+            "chr1\t69094\tG\tT\thg19\tQ8NH21\tENST00000335137.4\tV2L\t0.2937\tbenign"
+        )
+        .is_err());
+    }
+}
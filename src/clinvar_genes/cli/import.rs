@@ -39,6 +39,12 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Load per-impact JSONL file.
@@ -46,17 +52,10 @@ fn load_per_impact_jsonl(
     path_per_impact_jsonl: &str,
 ) -> Result<indexmap::IndexMap<String, GeneImpactCounts>, anyhow::Error> {
     // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if path_per_impact_jsonl.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            path_per_impact_jsonl,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(path_per_impact_jsonl)?)
-    };
+    let reader = common::open_read_maybe_gz(path_per_impact_jsonl)?;
 
     let mut result = indexmap::IndexMap::new();
 
-    let reader = std::io::BufReader::new(reader);
     for line in reader.lines() {
         let line = line?;
         let record = serde_json::from_str::<GeneImpactCounts>(&line)?;
@@ -71,17 +70,10 @@ fn load_per_frequency_jsonl(
     path_per_impact_jsonl: &str,
 ) -> Result<indexmap::IndexMap<String, GeneCoarseClinsigFrequencyCounts>, anyhow::Error> {
     // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if path_per_impact_jsonl.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            path_per_impact_jsonl,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(path_per_impact_jsonl)?)
-    };
+    let reader = common::open_read_maybe_gz(path_per_impact_jsonl)?;
 
     let mut result = indexmap::IndexMap::new();
 
-    let reader = std::io::BufReader::new(reader);
     for line in reader.lines() {
         let line = line?;
         let record = serde_json::from_str::<GeneCoarseClinsigFrequencyCounts>(&line)?;
@@ -363,12 +355,39 @@ fn jsonl_import(
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+///
+/// The per-gene reports are loaded as in the import, of the variant files only the first
+/// record is parsed.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    validation.read_input(&args.path_per_impact_jsonl, load_per_impact_jsonl);
+    validation.read_input(&args.path_per_frequency_jsonl, load_per_frequency_jsonl);
+    for path_variant_jsonl in &args.paths_variant_jsonl {
+        validation.read_input(
+            path_variant_jsonl,
+            common::dry_run::first_jsonl_record::<ExtractedVcvRecord>,
+        );
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `clinvar-genes import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'clinvar-genes import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -410,6 +429,23 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "clinvar-genes".to_string(),
+            title: "ClinVar genes".to_string(),
+            genome_release: None,
+            created_from: vec![("ClinVar".to_string(), args.spec.data_version())],
+            paths_in: [
+                args.path_per_impact_jsonl.clone(),
+                args.path_per_frequency_jsonl.clone(),
+            ]
+            .into_iter()
+            .chain(args.paths_variant_jsonl.iter().cloned())
+            .collect(),
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -426,6 +462,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_per_impact_jsonl: String::from("tests/clinvar-genes/gene-variant-report.jsonl"),
@@ -439,8 +476,47 @@ mod test {
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("clinvar"),
             path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+    }
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_per_impact_jsonl: String::from("tests/clinvar-genes/gene-variant-report.jsonl"),
+            path_per_frequency_jsonl: String::from(
+                "tests/clinvar-genes/gene-frequency-report.jsonl",
+            ),
+            paths_variant_jsonl: vec![String::from(
+                "tests/clinvar-genes/clinvar-variants-grch37-seqvars.jsonl",
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("clinvar"),
+            path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        // The per-impact and per-frequency reports are swapped.
+        let args = Args {
+            path_per_impact_jsonl: String::from("tests/clinvar-genes/gene-frequency-report.jsonl"),
+            path_per_frequency_jsonl: String::from("tests/clinvar-genes/gene-variant-report.jsonl"),
+            paths_variant_jsonl: vec![String::from("tests/clinvar-genes/bootstrap.sh")],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 3, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
     }
 }
@@ -2,9 +2,96 @@
 
 use std::sync::Arc;
 
-use prost::Message;
+use crate::{
+    common::{self, decode::decode_protobuf},
+    genes::ids::HgncId,
+    pbs::clinvar::per_gene::ClinvarPerGeneRecord,
+};
 
-use crate::{common, pbs::clinvar::per_gene::ClinvarPerGeneRecord};
+/// Argument group for specifying one gene or all genes.
+#[derive(clap::Args, Debug, Clone, Default)]
+#[group(required = true, multiple = false)]
+pub struct ArgsQuery {
+    /// HGNC gene identifier to query for.
+    #[arg(long, group = "query")]
+    pub hgnc_id: Option<HgncId>,
+    /// Query for all genes.
+    #[arg(long, group = "query")]
+    pub all: bool,
+}
+
+/// Coarse clinical significance of the variants of a gene.
+#[derive(clap::ValueEnum, strum::Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum Significance {
+    /// (Likely) pathogenic.
+    Pathogenic,
+    /// Uncertain significance.
+    Uncertain,
+    /// (Likely) benign.
+    Benign,
+}
+
+/// Number of variants of a gene by coarse clinical significance.
+///
+/// The counts are summed over the frequency bins of `per_freq_counts` such that each
+/// variant is counted once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignificanceCounts {
+    /// Number of (likely) pathogenic variants.
+    pub pathogenic: u32,
+    /// Number of variants of uncertain significance.
+    pub uncertain: u32,
+    /// Number of (likely) benign variants.
+    pub benign: u32,
+}
+
+impl SignificanceCounts {
+    /// Aggregate the counts of `record`.
+    pub fn from_record(record: &ClinvarPerGeneRecord) -> Self {
+        record
+            .per_freq_counts
+            .as_ref()
+            .map(|counts| Self {
+                pathogenic: counts.pathogenic_counts.iter().sum(),
+                uncertain: counts.uncertain_counts.iter().sum(),
+                benign: counts.benign_counts.iter().sum(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return the count for `significance`.
+    pub fn get(&self, significance: Significance) -> u32 {
+        match significance {
+            Significance::Pathogenic => self.pathogenic,
+            Significance::Uncertain => self.uncertain,
+            Significance::Benign => self.benign,
+        }
+    }
+}
+
+/// Filters on the aggregated counts of the genes to write.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ArgsFilter {
+    /// Only write genes with at least this number of (likely) pathogenic variants.
+    #[arg(long)]
+    pub min_pathogenic_count: Option<u32>,
+    /// Only write genes with at least one variant of any of the given significances.
+    #[arg(long, value_enum)]
+    pub significance: Vec<Significance>,
+}
+
+impl ArgsFilter {
+    /// Return whether a gene with the given `counts` passes the filters.
+    pub fn passes(&self, counts: &SignificanceCounts) -> bool {
+        counts.pathogenic >= self.min_pathogenic_count.unwrap_or_default()
+            && (self.significance.is_empty()
+                || self
+                    .significance
+                    .iter()
+                    .any(|significance| counts.get(*significance) > 0))
+    }
+}
 
 /// Command line arguments for `clinvar-gene query` sub command.
 #[derive(clap::Parser, Debug, Clone)]
@@ -20,12 +107,15 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
-    /// HGNC gene identifier to query for.
-    #[arg(long)]
-    pub hgnc_id: String,
+    /// Gene(s) to query for.
+    #[command(flatten)]
+    pub query: ArgsQuery,
+    /// Filters on the aggregated counts.
+    #[command(flatten)]
+    pub filter: ArgsFilter,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -39,7 +129,7 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
@@ -59,41 +149,63 @@ pub fn open_rocksdb_from_args(
     open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
 }
 
-/// Print values to `out_writer`.
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &ClinvarPerGeneRecord,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
-    }
-
-    Ok(())
-}
-
 /// Query for one gene annotation record.
 pub fn query_for_gene(
-    hgnc_id: &str,
+    hgnc_id: &HgncId,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<Option<ClinvarPerGeneRecord>, anyhow::Error> {
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
     let raw_value = db
-        .get_cf(cf_data, hgnc_id.as_bytes())
-        .map_err(|e| anyhow::anyhow!("error while querying for HGNC ID {}: {}", hgnc_id, e))?;
-    raw_value
+        .get_cf(&cf_data, hgnc_id.as_str().as_bytes())
+        .map_err(|e| {
+            crate::ErrorCategory::Database.error(format!(
+                "error while querying for HGNC ID {}: {}",
+                hgnc_id, e
+            ))
+        })?;
+    Ok(raw_value
         .map(|raw_value| {
-            ClinvarPerGeneRecord::decode(&mut std::io::Cursor::new(&raw_value)).map_err(|e| {
-                anyhow::anyhow!(
-                    "error while decoding clinvar per gene record for HGNC ID {}: {}",
-                    hgnc_id,
-                    e
-                )
-            })
+            decode_protobuf::<ClinvarPerGeneRecord>(
+                db,
+                cf_data_name,
+                hgnc_id.as_str().as_bytes(),
+                &raw_value,
+            )
         })
-        .transpose()
+        .transpose()?)
+}
+
+/// Write all genes passing `filter` to `out_writer`.
+fn print_all(
+    out_writer: &mut common::output::RecordWriter,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_name: &str,
+    filter: &ArgsFilter,
+) -> Result<(), anyhow::Error> {
+    tracing::info!("dumping all records...");
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let num_keys = db
+        .property_int_value_cf(&cf_data, "rocksdb.estimate-num-keys")?
+        .unwrap_or_default();
+    let pb = common::cli::progress_bar(num_keys as usize);
+
+    let mut count_written = 0;
+    let mut iter = db.raw_iterator_cf(&cf_data);
+    iter.seek(b"");
+    while let (Some(raw_key), Some(raw_value)) = (iter.key(), iter.value()) {
+        let record: ClinvarPerGeneRecord = decode_protobuf(db, cf_data_name, raw_key, raw_value)?;
+        if filter.passes(&SignificanceCounts::from_record(&record)) {
+            out_writer.write(&String::from_utf8_lossy(raw_key), &record)?;
+            count_written += 1;
+        }
+        pb.inc(1);
+        iter.next();
+    }
+    pb.finish_and_clear();
+
+    tracing::info!("... done dumping {} records", count_written);
+    Ok(())
 }
 
 /// Implementation of `gene query` sub command.
@@ -104,23 +216,220 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     // Open the RocksDB database.
     let db = open_rocksdb_from_args(args)?;
-    let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "clinvar-genes",
+        None,
+    )?;
 
     tracing::info!("Running query...");
-    if let Some(record) = query_for_gene(&args.hgnc_id, &db, &cf_data)? {
-        print_record(&mut out_writer, args.out_format, &record)?;
+    if let Some(hgnc_id) = args.query.hgnc_id.as_ref() {
+        match query_for_gene(hgnc_id, &db, &args.cf_name)? {
+            Some(record)
+                if args
+                    .filter
+                    .passes(&SignificanceCounts::from_record(&record)) =>
+            {
+                out_writer.write(hgnc_id.as_str(), &record)?;
+            }
+            Some(_) => tracing::info!("record for HGNC ID {} filtered out", hgnc_id),
+            None => tracing::info!("no record found for HGNC ID {}", hgnc_id),
+        }
+    } else if args.query.all {
+        print_all(&mut out_writer, &db, &args.cf_name, &args.filter)?;
     } else {
-        tracing::info!("no record found for HGNC ID {:?}", args.hgnc_id);
+        unreachable!();
     }
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::pbs::clinvar_data::class_by_freq::GeneCoarseClinsigFrequencyCounts;
+
+    /// Return a record with the given counts per frequency bin.
+    fn record(pathogenic: &[u32], uncertain: &[u32], benign: &[u32]) -> ClinvarPerGeneRecord {
+        ClinvarPerGeneRecord {
+            per_freq_counts: Some(GeneCoarseClinsigFrequencyCounts {
+                hgnc_id: String::from("HGNC:1"),
+                pathogenic_counts: pathogenic.to_vec(),
+                uncertain_counts: uncertain.to_vec(),
+                benign_counts: benign.to_vec(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn significance_counts_from_record() {
+        assert_eq!(
+            SignificanceCounts::from_record(&record(&[3, 2, 0], &[1], &[])),
+            SignificanceCounts {
+                pathogenic: 5,
+                uncertain: 1,
+                benign: 0
+            }
+        );
+        assert_eq!(
+            SignificanceCounts::from_record(&Default::default()),
+            SignificanceCounts::default()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::no_filter(None, &[], true)]
+    #[case::min_pathogenic_met(Some(5), &[], true)]
+    #[case::min_pathogenic_not_met(Some(6), &[], false)]
+    #[case::significance_present(None, &[Significance::Uncertain], true)]
+    #[case::significance_absent(None, &[Significance::Benign], false)]
+    #[case::any_significance(None, &[Significance::Benign, Significance::Pathogenic], true)]
+    #[case::both(Some(6), &[Significance::Pathogenic], false)]
+    fn filter_passes(
+        #[case] min_pathogenic_count: Option<u32>,
+        #[case] significance: &[Significance],
+        #[case] expected: bool,
+    ) {
+        let filter = ArgsFilter {
+            min_pathogenic_count,
+            significance: significance.to_vec(),
+        };
+        let counts = SignificanceCounts {
+            pathogenic: 5,
+            uncertain: 1,
+            benign: 0,
+        };
+
+        assert_eq!(filter.passes(&counts), expected);
+    }
+
+    /// Run `clinvar-genes query` on the example database and return the output lines.
+    fn run_query(query: ArgsQuery, filter: ArgsFilter) -> Result<Vec<String>, anyhow::Error> {
+        let temp = TempDir::default();
+        let common = common::cli::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_rocksdb: String::from("tests/clinvar-genes/clinvar-genes.db"),
+            cf_name: String::from("clinvar-genes"),
+            out_file: temp.join("out").to_string_lossy().to_string(),
+            output: Default::default(),
+            query,
+            filter,
+        };
+
+        run(&common, &args)?;
+
+        Ok(std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    #[test]
+    fn query_all() -> Result<(), anyhow::Error> {
+        let all = ArgsQuery {
+            all: true,
+            ..Default::default()
+        };
+        let db = open_rocksdb(
+            "tests/clinvar-genes/clinvar-genes.db",
+            "clinvar-genes",
+            "meta",
+        )?;
+        let cf_data = db.cf_handle("clinvar-genes").unwrap();
+        let records = db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .map(|item| -> Result<_, anyhow::Error> {
+                let (key, value) = item?;
+                let record: ClinvarPerGeneRecord =
+                    decode_protobuf(&db, "clinvar-genes", &key, &value)?;
+                Ok((String::from_utf8(key.to_vec())?, record))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let min_pathogenic_count = records
+            .iter()
+            .map(|(_, record)| SignificanceCounts::from_record(record).pathogenic)
+            .max()
+            .unwrap_or_default();
+        let expected_filtered = records
+            .iter()
+            .filter(|(_, record)| {
+                SignificanceCounts::from_record(record).pathogenic >= min_pathogenic_count
+            })
+            .map(|(_, record)| serde_json::to_string(record))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let unfiltered = run_query(all.clone(), Default::default())?;
+        let filtered = run_query(
+            all.clone(),
+            ArgsFilter {
+                min_pathogenic_count: Some(min_pathogenic_count),
+                ..Default::default()
+            },
+        )?;
+        let none = run_query(
+            all,
+            ArgsFilter {
+                min_pathogenic_count: Some(min_pathogenic_count + 1),
+                ..Default::default()
+            },
+        )?;
+
+        assert!(!records.is_empty());
+        assert_eq!(unfiltered.len(), records.len());
+        assert!(!filtered.is_empty());
+        assert_eq!(filtered, expected_filtered);
+        assert!(none.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_hgnc_id_filtered() -> Result<(), anyhow::Error> {
+        let db = open_rocksdb(
+            "tests/clinvar-genes/clinvar-genes.db",
+            "clinvar-genes",
+            "meta",
+        )?;
+        let cf_data = db.cf_handle("clinvar-genes").unwrap();
+        let (key, _) = db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .next()
+            .expect("example database must not be empty")?;
+        let hgnc_id: HgncId = String::from_utf8(key.to_vec())?.parse()?;
+        let record = query_for_gene(&hgnc_id, &db, "clinvar-genes")?.unwrap();
+        let pathogenic = SignificanceCounts::from_record(&record).pathogenic;
+        let query = ArgsQuery {
+            hgnc_id: Some(hgnc_id),
+            ..Default::default()
+        };
+
+        let passing = run_query(
+            query.clone(),
+            ArgsFilter {
+                min_pathogenic_count: Some(pathogenic),
+                ..Default::default()
+            },
+        )?;
+        let filtered_out = run_query(
+            query,
+            ArgsFilter {
+                min_pathogenic_count: Some(pathogenic + 1),
+                ..Default::default()
+            },
+        )?;
+
+        assert_eq!(passing, vec![serde_json::to_string(&record)?]);
+        assert!(filtered_out.is_empty());
+
+        Ok(())
+    }
+}
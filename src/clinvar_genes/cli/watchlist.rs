@@ -0,0 +1,428 @@
+//! Watch list of genes with changed (likely) pathogenic ClinVar counts between two releases.
+//!
+//! For each gene of the watch list, the pathogenic and likely pathogenic variants in the
+//! ClinVar genes databases of an old and a new release are counted from the per-impact
+//! counts and compared.  Genes missing from a database have no such variants.  The command
+//! fails with exit code 1 if the counts of any watched gene changed, such that pipelines can
+//! alert on it after writing the report.
+
+use std::io::Write;
+
+use crate::{
+    common, genes::ids::HgncId, pbs::clinvar::per_gene::ClinvarPerGeneRecord, ErrorCategory,
+};
+
+/// Format of the watch list report.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum WatchlistFormat {
+    /// One line per gene.
+    #[default]
+    Tsv,
+    /// One JSON object with the genes and the number of changed genes.
+    Json,
+}
+
+/// Command line arguments for `clinvar-genes watchlist` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(
+    about = "compare (likely) pathogenic ClinVar counts of watched genes between releases",
+    long_about = None
+)]
+pub struct Args {
+    /// Path to the ClinVar genes RocksDB directory of the old release.
+    #[arg(long)]
+    pub path_old_rocksdb: String,
+    /// Path to the ClinVar genes RocksDB directory of the new release.
+    #[arg(long)]
+    pub path_new_rocksdb: String,
+    /// Name of the column family with the data.
+    #[arg(long, default_value = "clinvar-genes")]
+    pub cf_name: String,
+    /// Path to file with one HGNC ID per line, lines starting with `#` are ignored.
+    #[arg(long)]
+    pub path_genes: String,
+    /// Output file (default is stdout == "-").
+    #[arg(long, default_value = "-")]
+    pub out_file: String,
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = WatchlistFormat::default())]
+    pub out_format: WatchlistFormat,
+}
+
+/// Number of (likely) pathogenic variants of a gene, summed over the gene impacts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PathogenicCounts {
+    /// Number of pathogenic variants.
+    pub pathogenic: u32,
+    /// Number of likely pathogenic variants.
+    pub likely_pathogenic: u32,
+}
+
+impl PathogenicCounts {
+    /// Aggregate the counts of `record`, no counts if `None`.
+    pub fn from_record(record: Option<&ClinvarPerGeneRecord>) -> Self {
+        record
+            .and_then(|record| record.per_impact_counts.as_ref())
+            .map(|counts| {
+                counts
+                    .impact_counts
+                    .iter()
+                    .fold(Self::default(), |acc, impact_counts| Self {
+                        pathogenic: acc.pathogenic + impact_counts.count_pathogenic,
+                        likely_pathogenic: acc.likely_pathogenic
+                            + impact_counts.count_likely_pathogenic,
+                    })
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Change of the counts of one watched gene.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GeneDelta {
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// Counts in the old release.
+    pub old: PathogenicCounts,
+    /// Counts in the new release.
+    pub new: PathogenicCounts,
+    /// Change of the number of pathogenic variants.
+    pub delta_pathogenic: i64,
+    /// Change of the number of likely pathogenic variants.
+    pub delta_likely_pathogenic: i64,
+    /// Whether the number of pathogenic or likely pathogenic variants increased.
+    pub increased: bool,
+}
+
+impl GeneDelta {
+    /// Compare the `old` and `new` records of the gene `hgnc_id`.
+    pub fn new(
+        hgnc_id: &HgncId,
+        old: Option<&ClinvarPerGeneRecord>,
+        new: Option<&ClinvarPerGeneRecord>,
+    ) -> Self {
+        let old = PathogenicCounts::from_record(old);
+        let new = PathogenicCounts::from_record(new);
+        let delta_pathogenic = i64::from(new.pathogenic) - i64::from(old.pathogenic);
+        let delta_likely_pathogenic =
+            i64::from(new.likely_pathogenic) - i64::from(old.likely_pathogenic);
+        Self {
+            hgnc_id: hgnc_id.to_string(),
+            old,
+            new,
+            delta_pathogenic,
+            delta_likely_pathogenic,
+            increased: delta_pathogenic > 0 || delta_likely_pathogenic > 0,
+        }
+    }
+
+    /// Return whether any of the counts changed.
+    pub fn changed(&self) -> bool {
+        self.delta_pathogenic != 0 || self.delta_likely_pathogenic != 0
+    }
+}
+
+/// The report for the whole watch list.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Watchlist {
+    /// Changes for each gene, in the order of the watch list.
+    pub genes: Vec<GeneDelta>,
+    /// Number of genes with changed counts.
+    pub num_changed: usize,
+    /// Number of genes with increased counts.
+    pub num_increased: usize,
+}
+
+impl Watchlist {
+    /// Build the report from the changes of the watched `genes`.
+    pub fn new(genes: Vec<GeneDelta>) -> Self {
+        Self {
+            num_changed: genes.iter().filter(|gene| gene.changed()).count(),
+            num_increased: genes.iter().filter(|gene| gene.increased).count(),
+            genes,
+        }
+    }
+}
+
+/// Write `watchlist` as TSV to `writer`.
+pub fn write_tsv<W: Write>(watchlist: &Watchlist, writer: &mut W) -> Result<(), anyhow::Error> {
+    writeln!(
+        writer,
+        "hgnc_id\told_pathogenic\tnew_pathogenic\tdelta_pathogenic\t\
+        old_likely_pathogenic\tnew_likely_pathogenic\tdelta_likely_pathogenic\tincreased"
+    )?;
+    for gene in &watchlist.genes {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            gene.hgnc_id,
+            gene.old.pathogenic,
+            gene.new.pathogenic,
+            gene.delta_pathogenic,
+            gene.old.likely_pathogenic,
+            gene.new.likely_pathogenic,
+            gene.delta_likely_pathogenic,
+            gene.increased,
+        )?;
+    }
+    Ok(())
+}
+
+/// Return the HGNC IDs of the watch list at `path` without duplicates.
+fn read_genes(path: &str) -> Result<Vec<HgncId>, anyhow::Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+    let mut result = Vec::new();
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        result.push(line.parse::<HgncId>().map_err(|e| {
+            ErrorCategory::InputParse.error(format!("invalid gene in {}: {}", path, e))
+        })?);
+    }
+    let mut seen = std::collections::HashSet::new();
+    result.retain(|hgnc_id| seen.insert(hgnc_id.clone()));
+    if result.is_empty() {
+        return Err(ErrorCategory::Usage.error(format!("no genes in {}", path)));
+    }
+    Ok(result)
+}
+
+/// Main entry point for `clinvar-genes watchlist` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'clinvar-genes watchlist' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let hgnc_ids = read_genes(&args.path_genes)?;
+    let old_db = super::query::open_rocksdb(&args.path_old_rocksdb, &args.cf_name, "meta")?;
+    let new_db = super::query::open_rocksdb(&args.path_new_rocksdb, &args.cf_name, "meta")?;
+
+    tracing::info!("Comparing {} genes ...", hgnc_ids.len());
+    let genes = hgnc_ids
+        .iter()
+        .map(|hgnc_id| -> Result<_, anyhow::Error> {
+            let old = super::query::query_for_gene(hgnc_id, &old_db, &args.cf_name)?;
+            let new = super::query::query_for_gene(hgnc_id, &new_db, &args.cf_name)?;
+            Ok(GeneDelta::new(hgnc_id, old.as_ref(), new.as_ref()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let watchlist = Watchlist::new(genes);
+    tracing::info!(
+        "... {} genes changed, {} increased",
+        watchlist.num_changed,
+        watchlist.num_increased
+    );
+
+    let mut out_writer = std::io::BufWriter::new(common::output::open_out_file(&args.out_file)?);
+    match args.out_format {
+        WatchlistFormat::Tsv => write_tsv(&watchlist, &mut out_writer)?,
+        WatchlistFormat::Json => {
+            serde_json::to_writer_pretty(&mut out_writer, &watchlist)?;
+            writeln!(out_writer)?;
+        }
+    }
+    out_writer.flush()?;
+
+    if watchlist.num_changed > 0 {
+        return Err(ErrorCategory::Changed.error(format!(
+            "{} of {} watched genes changed",
+            watchlist.num_changed,
+            watchlist.genes.len()
+        )));
+    }
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::pbs::clinvar_data::gene_impact::{
+        gene_impact_counts::ImpactCounts, GeneImpact, GeneImpactCounts,
+    };
+
+    /// Return a record with the (pathogenic, likely pathogenic) counts of the impacts.
+    fn record(counts: &[(GeneImpact, u32, u32)]) -> ClinvarPerGeneRecord {
+        ClinvarPerGeneRecord {
+            per_impact_counts: Some(GeneImpactCounts {
+                hgnc_id: String::from("HGNC:1100"),
+                impact_counts: counts
+                    .iter()
+                    .map(
+                        |(gene_impact, count_pathogenic, count_likely_pathogenic)| ImpactCounts {
+                            gene_impact: *gene_impact as i32,
+                            count_benign: 7,
+                            count_uncertain_significance: 11,
+                            count_pathogenic: *count_pathogenic,
+                            count_likely_pathogenic: *count_likely_pathogenic,
+                            ..Default::default()
+                        },
+                    )
+                    .collect(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pathogenic_counts_from_record() {
+        assert_eq!(
+            PathogenicCounts::from_record(Some(&record(&[
+                (GeneImpact::MissenseVariant, 3, 1),
+                (GeneImpact::FrameshiftVariant, 2, 0),
+            ]))),
+            PathogenicCounts {
+                pathogenic: 5,
+                likely_pathogenic: 1,
+            }
+        );
+        assert_eq!(
+            PathogenicCounts::from_record(Some(&Default::default())),
+            PathogenicCounts::default()
+        );
+        assert_eq!(
+            PathogenicCounts::from_record(None),
+            PathogenicCounts::default()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::unchanged(Some((3, 1)), Some((3, 1)), (0, 0), false, false)]
+    #[case::pathogenic_increased(Some((3, 1)), Some((4, 1)), (1, 0), true, true)]
+    #[case::likely_pathogenic_increased(Some((3, 1)), Some((3, 3)), (0, 2), true, true)]
+    #[case::decreased(Some((3, 1)), Some((2, 1)), (-1, 0), false, true)]
+    #[case::reclassified(Some((3, 1)), Some((2, 2)), (-1, 1), true, true)]
+    #[case::new_gene(None, Some((1, 0)), (1, 0), true, true)]
+    #[case::removed_gene(Some((1, 0)), None, (-1, 0), false, true)]
+    #[case::missing(None, None, (0, 0), false, false)]
+    fn gene_delta(
+        #[case] old: Option<(u32, u32)>,
+        #[case] new: Option<(u32, u32)>,
+        #[case] deltas: (i64, i64),
+        #[case] increased: bool,
+        #[case] changed: bool,
+    ) -> Result<(), anyhow::Error> {
+        let to_record = |counts: Option<(u32, u32)>| {
+            counts.map(|(pathogenic, likely_pathogenic)| {
+                record(&[(GeneImpact::MissenseVariant, pathogenic, likely_pathogenic)])
+            })
+        };
+        let (old, new) = (to_record(old), to_record(new));
+
+        let delta = GeneDelta::new(&"HGNC:1100".parse()?, old.as_ref(), new.as_ref());
+
+        assert_eq!(
+            (delta.delta_pathogenic, delta.delta_likely_pathogenic),
+            deltas
+        );
+        assert_eq!(delta.increased, increased);
+        assert_eq!(delta.changed(), changed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn watchlist_tsv() -> Result<(), anyhow::Error> {
+        let watchlist = Watchlist::new(vec![
+            GeneDelta::new(
+                &"HGNC:1100".parse()?,
+                Some(&record(&[(GeneImpact::MissenseVariant, 3, 1)])),
+                Some(&record(&[(GeneImpact::MissenseVariant, 4, 1)])),
+            ),
+            GeneDelta::new(
+                &"HGNC:1101".parse()?,
+                Some(&record(&[(GeneImpact::StopGained, 2, 2)])),
+                Some(&record(&[(GeneImpact::StopGained, 2, 2)])),
+            ),
+            GeneDelta::new(
+                &"HGNC:12403".parse()?,
+                Some(&record(&[(GeneImpact::StopGained, 1, 0)])),
+                None,
+            ),
+        ]);
+
+        let mut buf = Vec::new();
+        write_tsv(&watchlist, &mut buf)?;
+
+        assert_eq!((watchlist.num_changed, watchlist.num_increased), (2, 1));
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "hgnc_id\told_pathogenic\tnew_pathogenic\tdelta_pathogenic\t\
+            old_likely_pathogenic\tnew_likely_pathogenic\tdelta_likely_pathogenic\tincreased\n\
+            HGNC:1100\t3\t4\t1\t1\t1\t0\ttrue\n\
+            HGNC:1101\t2\t2\t0\t2\t2\t0\tfalse\n\
+            HGNC:12403\t1\t0\t-1\t0\t0\t0\tfalse\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_genes_from_file() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path = format!("{}", tmp_dir.join("genes.txt").display());
+
+        std::fs::write(&path, "# watched\nHGNC:1100\n\nHGNC:1101\nHGNC:1100\n")?;
+        assert_eq!(read_genes(&path)?, vec!["HGNC:1100", "HGNC:1101"]);
+
+        std::fs::write(&path, "BRCA1\n")?;
+        let err = read_genes(&path).unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::InputParse);
+
+        std::fs::write(&path, "# empty\n")?;
+        let err = read_genes(&path).unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::Usage);
+
+        Ok(())
+    }
+
+    /// Run `clinvar-genes watchlist` with the example database as old and new release.
+    #[test]
+    fn run_unchanged() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = super::super::query::open_rocksdb(
+            "tests/clinvar-genes/clinvar-genes.db",
+            "clinvar-genes",
+            "meta",
+        )?;
+        let cf_data = db.cf_handle("clinvar-genes").unwrap();
+        let (key, _) = db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .next()
+            .expect("example database must not be empty")?;
+        let hgnc_id = String::from_utf8(key.to_vec())?;
+        let path_genes = format!("{}", tmp_dir.join("genes.txt").display());
+        std::fs::write(&path_genes, format!("{}\n", hgnc_id))?;
+        let args = Args {
+            path_old_rocksdb: String::from("tests/clinvar-genes/clinvar-genes.db"),
+            path_new_rocksdb: String::from("tests/clinvar-genes/clinvar-genes.db"),
+            cf_name: String::from("clinvar-genes"),
+            path_genes,
+            out_file: format!("{}", tmp_dir.join("out.json").display()),
+            out_format: WatchlistFormat::Json,
+        };
+
+        run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &args,
+        )?;
+
+        let watchlist: Watchlist = serde_json::from_str(&std::fs::read_to_string(&args.out_file)?)?;
+        assert_eq!(watchlist.genes.len(), 1);
+        assert_eq!(watchlist.genes[0].hgnc_id, hgnc_id);
+        assert_eq!(watchlist.genes[0].old, watchlist.genes[0].new);
+        assert_eq!(watchlist.num_changed, 0);
+
+        Ok(())
+    }
+}
@@ -20,6 +20,11 @@ pub struct Args {
     /// Path to output RocksDB directory.
     #[arg(long)]
     pub path_out_rocksdb: String,
+    /// Version of the ClinVar release, e.g., `2024-01-07`, written to `meta:db-version`.
+    ///
+    /// The server uses this to distinguish more than one ClinVar database per release.
+    #[arg(long)]
+    pub db_version: Option<String>,
 
     /// Name of the column family to import into.
     #[arg(long, default_value = "clinvar")]
@@ -27,9 +32,91 @@ pub struct Args {
     /// Name of the column family for accession lookup.
     #[arg(long, default_value = "clinvar_by_accession")]
     pub cf_name_by_accession: String,
+    /// Also write the submission-level details (SCVs) into the column family
+    /// `--cf-name-details`.
+    #[arg(long)]
+    pub with_details: bool,
+    /// Name of the column family for the submission-level details.
+    #[arg(long, default_value = "clinvar_details")]
+    pub cf_name_details: String,
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Extract the submission-level details from the clinical assertions of `vcv_record`.
+pub fn vcv_details(
+    vcv_record: &crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+) -> crate::pbs::clinvar::minimal::VcvDetails {
+    let submissions = vcv_record
+        .clinical_assertions
+        .iter()
+        .map(|assertion| {
+            let clinvar_accession = assertion.clinvar_accession.as_ref();
+            let classifications = assertion.classifications.as_ref();
+            crate::pbs::clinvar::minimal::SubmissionDetails {
+                accession: clinvar_accession.map(|clinvar_accession| {
+                    crate::pbs::clinvar_data::extracted_vars::VersionedAccession {
+                        accession: clinvar_accession.accession.clone(),
+                        version: clinvar_accession.version,
+                    }
+                }),
+                submitter_identifiers: clinvar_accession
+                    .and_then(|clinvar_accession| clinvar_accession.submitter_identifiers.clone()),
+                classification: classifications.and_then(|classifications| {
+                    classifications
+                        .germline_classification
+                        .clone()
+                        .or_else(|| classifications.oncogenicity_classification.clone())
+                        .or_else(|| {
+                            classifications
+                                .somatic_clinical_impact
+                                .as_ref()
+                                .map(|impact| impact.value.clone())
+                        })
+                }),
+                review_status: classifications
+                    .map(|classifications| classifications.review_status)
+                    .unwrap_or_default(),
+                date_last_evaluated: classifications
+                    .and_then(|classifications| classifications.date_last_evaluated.clone()),
+            }
+        })
+        .collect();
+    crate::pbs::clinvar::minimal::VcvDetails {
+        accession: vcv_record.accession.clone(),
+        submissions,
+    }
+}
+
+/// Add the details of `vcv_record` to the details stored under `key`, if any.
+fn put_details(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_details: &Arc<rocksdb::BoundColumnFamily>,
+    key: &[u8],
+    vcv_record: &crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+) -> Result<(), anyhow::Error> {
+    let mut details = match db.get_cf(cf_details, key)? {
+        Some(data) => crate::pbs::clinvar::minimal::VcvDetailsList::decode(&data[..])?,
+        None => Default::default(),
+    };
+    details.records.push(vcv_details(vcv_record));
+    details.records.sort_by_key(|record| {
+        record
+            .accession
+            .as_ref()
+            .expect("accession is required")
+            .accession
+            .clone()
+    });
+    db.put_cf(cf_details, key, details.encode_to_vec())?;
+    Ok(())
 }
 
 /// Perform import of the JSONL file.
@@ -39,17 +126,12 @@ fn jsonl_import(
 ) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let cf_by_accession = db.cf_handle(&args.cf_name_by_accession).unwrap();
+    let cf_details = args
+        .with_details
+        .then(|| db.cf_handle(&args.cf_name_details).unwrap());
 
     // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if args.path_in_jsonl.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            &args.path_in_jsonl,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(&args.path_in_jsonl)?)
-    };
-
-    let reader = std::io::BufReader::new(reader);
+    let reader = common::open_read_maybe_gz(&args.path_in_jsonl)?;
 
     for line in reader.lines() {
         let line = line?;
@@ -92,7 +174,8 @@ fn jsonl_import(
                 position_vcf as i32,
                 &reference_allele_vcf,
                 &alternate_allele_vcf,
-            );
+            )
+            .trimmed();
             let key: Vec<u8> = var.into();
 
             let data = db
@@ -113,6 +196,9 @@ fn jsonl_import(
                         let rcv = format!("{}.{}", accession.accession, accession.version);
                         db.put_cf(&cf_by_accession, rcv.as_bytes(), &key)?;
                     }
+                    if let Some(cf_details) = cf_details.as_ref() {
+                        put_details(db, cf_details, &key, &vcv_record)?;
+                    }
 
                     let new_record = if let Some(data) = data {
                         let mut record =
@@ -143,12 +229,36 @@ fn jsonl_import(
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+///
+/// The first record of the input file is parsed in the same way as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    validation.read_input(
+        &args.path_in_jsonl,
+        common::dry_run::first_jsonl_record::<
+            crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+        >,
+    );
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `clinvar-minimal import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'clinvar-minimal import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -156,7 +266,10 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
-    let cf_names = &["meta", &args.cf_name, &args.cf_name_by_accession];
+    let mut cf_names = vec!["meta", &args.cf_name, &args.cf_name_by_accession];
+    if args.with_details {
+        cf_names.push(&args.cf_name_details);
+    }
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out_rocksdb)?,
@@ -168,12 +281,16 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
     db.put_cf(
         &cf_meta,
         "genome-release",
         format!("{}", args.genome_release),
     )?;
     db.put_cf(&cf_meta, "db-name", "clinvar-minimal")?;
+    if let Some(db_version) = args.db_version.as_ref() {
+        db.put_cf(&cf_meta, "db-version", db_version)?;
+    }
     tracing::info!(
         "... done opening RocksDB for writing in {:?}",
         before_opening_rocksdb.elapsed()
@@ -189,12 +306,25 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
-    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+    rocksdb_utils_lookup::force_compaction_cf(&db, &cf_names, Some("  "), true)?;
     tracing::info!(
         "... done compacting RocksDB in {:?}",
         before_compaction.elapsed()
     );
 
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "clinvar-minimal".to_string(),
+            title: "ClinVar".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("ClinVar".to_string(), args.spec.data_version())],
+            paths_in: vec![args.path_in_jsonl.clone()],
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -211,20 +341,218 @@ mod test {
     #[case("tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.jsonl")]
     #[case("tests/clinvar-minimal/clinvar-seqvars-grch37-flagged.jsonl")]
     #[case("tests/clinvar-minimal/clinvar-seqvars-grch37-no-unflagged.jsonl")]
+    #[case("tests/clinvar-minimal/clinvar-seqvars-grch37-somatic.jsonl")]
     fn smoke_test_import_jsonl(#[case] path_in_jsonl: &str) {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
             path_in_jsonl: path_in_jsonl.into(),
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: None,
+            cf_name: String::from("clinvar"),
+            cf_name_by_accession: String::from("clinvar_by_accession"),
+            with_details: false,
+            cf_name_details: String::from("clinvar_details"),
+            path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+    }
+
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[test]
+    fn dry_run_import_jsonl() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_jsonl: String::from("tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.jsonl"),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: None,
             cf_name: String::from("clinvar"),
             cf_name_by_accession: String::from("clinvar_by_accession"),
+            with_details: false,
+            cf_name_details: String::from("clinvar_details"),
             path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_jsonl: String::from("tests/clinvar-minimal/bootstrap.sh"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
+
+    #[test]
+    fn import_keeps_somatic_classifications() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_jsonl: String::from(
+                "tests/clinvar-minimal/clinvar-seqvars-grch37-somatic.jsonl",
+            ),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: None,
+            cf_name: String::from("clinvar"),
+            cf_name_by_accession: String::from("clinvar_by_accession"),
+            with_details: false,
+            cf_name_details: String::from("clinvar_details"),
+            path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+        run(&common, &args)?;
+
+        let query_args = crate::clinvar_minimal::cli::query::Args {
+            path_rocksdb: Some(args.path_out_rocksdb.clone()),
+            server_url: None,
+            cf_name: args.cf_name.clone(),
+            cf_name_by_accession: args.cf_name_by_accession.clone(),
+            cf_name_details: args.cf_name_details.clone(),
+            details: false,
+            out_file: format!("{}", tmp_dir.join("out.jsonl").display()),
+            output: Default::default(),
+            query: crate::cons::cli::args::vars::ArgsQuery {
+                accession: Some(String::from("VCV000999999.1")),
+                ..Default::default()
+            },
+            limit: Default::default(),
+            strict: false,
+        };
+        crate::clinvar_minimal::cli::query::run(&common, &query_args)?;
+
+        let out_data = std::fs::read_to_string(&query_args.out_file)?;
+        let value: serde_json::Value = serde_json::from_str(out_data.trim())?;
+        let classifications = &value["classifications"];
+        assert_eq!(
+            classifications["oncogenicityClassification"]["description"],
+            "Oncogenic"
+        );
+        assert_eq!(
+            classifications["somaticClinicalImpact"]["description"],
+            "Tier I - Strong"
+        );
+        assert!(classifications.get("germlineClassification").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn vcv_details_from_clinical_assertions() -> Result<(), anyhow::Error> {
+        let line =
+            std::fs::read_to_string("tests/clinvar-minimal/clinvar-seqvars-grch37-details.jsonl")?;
+        let vcv_record: crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord =
+            serde_json::from_str(line.trim())?;
+        let details = vcv_details(&vcv_record);
+
+        assert_eq!(details.accession, vcv_record.accession);
+        assert_eq!(details.submissions.len(), 2);
+        let submission = &details.submissions[1];
+        assert_eq!(
+            submission.accession.as_ref().map(|a| a.accession.as_str()),
+            Some("SCV001479341")
+        );
+        assert_eq!(
+            submission
+                .submitter_identifiers
+                .as_ref()
+                .map(|s| s.submitter_name.as_str()),
+            Some("Example Diagnostic Laboratory")
+        );
+        assert_eq!(
+            submission.classification.as_deref(),
+            Some("Likely pathogenic")
+        );
+        assert_eq!(
+            submission.review_status,
+            crate::pbs::clinvar_data::clinvar_public::SubmitterReviewStatus::CriteriaProvidedSingleSubmitter
+                as i32
+        );
+        assert!(submission.date_last_evaluated.is_some());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(true)]
+    #[case(false)]
+    fn import_and_query_details(#[case] with_details: bool) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_jsonl: String::from(
+                "tests/clinvar-minimal/clinvar-seqvars-grch37-details.jsonl",
+            ),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            db_version: None,
+            cf_name: String::from("clinvar"),
+            cf_name_by_accession: String::from("clinvar_by_accession"),
+            with_details,
+            cf_name_details: String::from("clinvar_details"),
+            path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+        run(&common, &args)?;
+
+        let query_args = crate::clinvar_minimal::cli::query::Args {
+            path_rocksdb: Some(args.path_out_rocksdb.clone()),
+            server_url: None,
+            cf_name: args.cf_name.clone(),
+            cf_name_by_accession: args.cf_name_by_accession.clone(),
+            cf_name_details: args.cf_name_details.clone(),
+            details: true,
+            out_file: format!("{}", tmp_dir.join("out.jsonl").display()),
+            output: Default::default(),
+            query: crate::cons::cli::args::vars::ArgsQuery {
+                variant: Some("GRCh37:13:95243151:T:C".parse()?),
+                ..Default::default()
+            },
+            limit: Default::default(),
+            strict: false,
+        };
+        crate::clinvar_minimal::cli::query::run(&common, &query_args)?;
+
+        let out_data = std::fs::read_to_string(&query_args.out_file)?;
+        let value: serde_json::Value = serde_json::from_str(out_data.trim())?;
+        assert_eq!(value["accession"]["accession"], "VCV000162457");
+        if with_details {
+            let submissions = value["submissions"].as_array().unwrap();
+            assert_eq!(submissions.len(), 2);
+            assert_eq!(submissions[0]["accession"]["accession"], "SCV000196954");
+            assert_eq!(
+                submissions[0]["submitterIdentifiers"]["submitterName"],
+                "OMIM"
+            );
+            assert_eq!(submissions[0]["classification"], "Pathogenic");
+        } else {
+            assert!(value.get("submissions").is_none());
+        }
+
+        Ok(())
     }
 }
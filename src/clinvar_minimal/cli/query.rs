@@ -1,11 +1,9 @@
 //! Query of clinvar-minimal annotation data.
 
-use std::{io::Write, sync::Arc};
-
-use prost::Message;
+use std::sync::Arc;
 
 use crate::{
-    common::{self, cli::extract_chrom, keys, spdi},
+    common::{self, cli::extract_chrom, decode::decode_protobuf, keys, spdi},
     cons::cli::args::vars::ArgsQuery,
 };
 
@@ -14,24 +12,41 @@ use crate::{
 #[command(about = "query clinvar-minimal data stored in RocksDB", long_about = None)]
 pub struct Args {
     /// Path to RocksDB directory with data.
-    #[arg(long)]
-    pub path_rocksdb: String,
+    #[arg(long, required_unless_present = "server_url")]
+    pub path_rocksdb: Option<String>,
+    /// URL of a running annonars server to query instead of a local RocksDB.
+    #[arg(long, conflicts_with = "path_rocksdb")]
+    pub server_url: Option<String>,
     /// Name of the column family to import into.
     #[arg(long, default_value = "clinvar")]
     pub cf_name: String,
     /// Name of the column family for accession lookup.
     #[arg(long, default_value = "clinvar_by_accession")]
     pub cf_name_by_accession: String,
+    /// Name of the column family with the submission-level details.
+    #[arg(long, default_value = "clinvar_details")]
+    pub cf_name_details: String,
+    /// Merge the submission-level details (SCVs) into the output records, if the database
+    /// has them.
+    #[arg(long)]
+    pub details: bool,
     /// Output file (default is stdout == "-").
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// Number of records of position and range queries to skip and write.
+    #[command(flatten)]
+    pub limit: common::cli::ArgsLimit,
+    /// Fail on the first query from `--path-input-jsonl` that cannot be parsed or run rather
+    /// than writing an error record.
+    #[arg(long, requires = "path_input_jsonl")]
+    pub strict: bool,
 }
 
 /// Meta information as read from database.
@@ -39,37 +54,52 @@ pub struct Args {
 pub struct Meta {
     /// Genome release of data in database.
     pub genome_release: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
+///
+/// The details column family `cf_details` is opened only if the database has it.
 pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     path_rocksdb: P,
     cf_data: &str,
     cf_meta: &str,
     cf_by_accession: &str,
+    cf_details: &str,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
     tracing::info!("Opening RocksDB database ...");
     let before_open = std::time::Instant::now();
-    let cf_names = &[cf_meta, cf_data, cf_by_accession];
+    let path_rocksdb = common::readlink_f_existing(&path_rocksdb)?;
+    let mut cf_names = vec![cf_meta, cf_data, cf_by_accession];
+    if rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_rocksdb)
+        .map_err(|e| anyhow::anyhow!("problem listing column families: {}", e))?
+        .iter()
+        .any(|name| name == cf_details)
+    {
+        cf_names.push(cf_details);
+    }
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
-        cf_names,
+        path_rocksdb,
+        &cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
     tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -82,138 +112,223 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 pub fn open_rocksdb_from_args(
     args: &Args,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
+    let path_rocksdb = args
+        .path_rocksdb
+        .as_ref()
+        .ok_or_else(|| crate::ErrorCategory::Usage.error("missing --path-rocksdb"))?;
     open_rocksdb(
-        &args.path_rocksdb,
+        path_rocksdb,
         &args.cf_name,
         "meta",
         &args.cf_name_by_accession,
+        &args.cf_name_details,
     )
 }
 
+/// A VCV record with the submissions merged in, as written with `--details`.
+#[derive(serde::Serialize)]
+struct RecordWithDetails<'a> {
+    /// The VCV record.
+    #[serde(flatten)]
+    record: &'a crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+    /// The submissions of the VCV.
+    submissions: &'a [crate::pbs::clinvar::minimal::SubmissionDetails],
+}
+
+/// Write each record of `value` with the given `key`, merged with its entry in `details`.
 fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
+    key: &str,
     value: &crate::pbs::clinvar::minimal::ExtractedVcvRecordList,
+    details: Option<&crate::pbs::clinvar::minimal::VcvDetailsList>,
 ) -> Result<(), anyhow::Error> {
     for record in &value.records {
-        match output_format {
-            common::cli::OutputFormat::Jsonl => {
-                writeln!(out_writer, "{}", serde_json::to_string(record)?)?;
-            }
+        if let Some(details) = details {
+            let submissions = details
+                .records
+                .iter()
+                .find(|vcv_details| vcv_details.accession == record.accession)
+                .map(|vcv_details| vcv_details.submissions.as_slice())
+                .unwrap_or_default();
+            out_writer.write(
+                key,
+                &RecordWithDetails {
+                    record,
+                    submissions,
+                },
+            )?;
+        } else {
+            out_writer.write(key, record)?;
         }
     }
 
     Ok(())
 }
 
-/// Query for a single variant in the RocksDB database.
-pub fn query_for_variant(
-    variant: &common::spdi::Var,
-    meta: &Meta,
+/// Query for the submission-level details stored under the database `key`.
+///
+/// Returns `None` if the database has no details column family `cf_details_name`.
+pub fn query_details(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
-) -> Result<Option<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>, anyhow::Error> {
+    cf_details_name: &str,
+    key: &[u8],
+) -> Result<Option<crate::pbs::clinvar::minimal::VcvDetailsList>, anyhow::Error> {
+    let Some(cf_details) = db.cf_handle(cf_details_name) else {
+        return Ok(None);
+    };
+    let raw_value = db.get_cf(&cf_details, key).map_err(|e| {
+        crate::ErrorCategory::Database
+            .error(format!("error while querying for details {:?}: {}", key, e))
+    })?;
+    Ok(Some(
+        raw_value
+            .map(|raw_value| decode_protobuf(db, cf_details_name, key, &raw_value))
+            .transpose()?
+            .unwrap_or_default(),
+    ))
+}
+
+/// Return the database key of `variant`.
+fn variant_key(variant: &common::spdi::Var, meta: &Meta) -> Result<Vec<u8>, anyhow::Error> {
     // Split off the genome release (checked) and convert to key as used in database.
     let query = spdi::Var {
         sequence: extract_chrom::from_var(variant, Some(&meta.genome_release))?,
         ..variant.clone()
     };
-    // Execute query.
     tracing::debug!("query = {:?}", &query);
-    let var: keys::Var = query.into();
-    let key: Vec<u8> = var.into();
-    let raw_value = db
-        .get_cf(cf_data, key)
-        .map_err(|e| anyhow::anyhow!("error while querying for variant {}: {}", variant, e))?;
-    raw_value
+    Ok(common::norm::query_key(query, meta.keys_normalized))
+}
+
+/// Return the database key of the variant with the given `accession`.
+fn accession_key(
+    accession: &str,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_by_rsid: &Arc<rocksdb::BoundColumnFamily>,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let accession = accession.to_uppercase(); // VCV*, RCV*
+    db.get_cf(cf_data_by_rsid, accession.clone())
+        .map_err(|e| {
+            crate::ErrorCategory::Database.error(format!(
+                "error while querying for accession {}: {}",
+                &accession, e
+            ))
+        })?
+        .ok_or_else(|| {
+            crate::ErrorCategory::NotFound
+                .error(format!("no record found for accession {}", &accession))
+        })
+}
+
+/// Query for a single variant in the RocksDB database.
+pub fn query_for_variant(
+    variant: &common::spdi::Var,
+    meta: &Meta,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_name: &str,
+) -> Result<Option<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>, anyhow::Error> {
+    // Execute query.
+    let key = variant_key(variant, meta)?;
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let raw_value = db.get_cf(&cf_data, &key).map_err(|e| {
+        crate::ErrorCategory::Database.error(format!(
+            "error while querying for variant {}: {}",
+            variant, e
+        ))
+    })?;
+    Ok(raw_value
         .map(|raw_value| {
-            // Decode via prost.
-            crate::pbs::clinvar::minimal::ExtractedVcvRecordList::decode(&mut std::io::Cursor::new(
+            decode_protobuf::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
+                db,
+                cf_data_name,
+                &key,
                 &raw_value,
-            ))
-            .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
+            )
         })
-        .transpose()
+        .transpose()?)
 }
 
 /// Query for a single variant by accession.
 pub fn query_for_accession(
     accession: &str,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
     cf_data_by_rsid: &Arc<rocksdb::BoundColumnFamily>,
 ) -> Result<Option<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>, anyhow::Error> {
-    let accession = accession.to_uppercase(); // VCV*, RCV*
-
     // First, lookup accession.
-    let var_key = db
-        .get_cf(cf_data_by_rsid, accession.clone())
-        .map_err(|e| anyhow::anyhow!("error while querying for accession {}: {}", &accession, e))?
-        .ok_or_else(|| anyhow::anyhow!("no record found for accession {}", &accession))?;
+    let var_key = accession_key(accession, db, cf_data_by_rsid)?;
 
     // Execute query for key.
-    let raw_value = db
-        .get_cf(cf_data, var_key.clone())
-        .map_err(|e| anyhow::anyhow!("error while querying for variant {:?}: {}", &var_key, e))?;
-    raw_value
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let raw_value = db.get_cf(&cf_data, var_key.clone()).map_err(|e| {
+        crate::ErrorCategory::Database.error(format!(
+            "error while querying for variant {:?}: {}",
+            &var_key, e
+        ))
+    })?;
+    Ok(raw_value
         .map(|raw_value| {
-            // Decode via prost.
-            crate::pbs::clinvar::minimal::ExtractedVcvRecordList::decode(&mut std::io::Cursor::new(
+            decode_protobuf::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
+                db,
+                cf_data_name,
+                &var_key,
                 &raw_value,
-            ))
-            .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
+            )
         })
-        .transpose()
+        .transpose()?)
 }
 
-/// Implementation of `tsv query` sub command.
-pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("Starting 'clinvar-minimal query' command");
-    tracing::info!("common = {:#?}", &common);
-    tracing::info!("args = {:#?}", &args);
-
-    let (db, meta) = open_rocksdb_from_args(args)?;
+/// Run the single `query` and write its records to `out_writer`.
+///
+/// The submission-level details are merged into the records if `with_details` is given.
+fn run_query(
+    query: &ArgsQuery,
+    args: &Args,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+    with_details: bool,
+    out_writer: &mut common::output::RecordWriter,
+) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let cf_by_accession = db.cf_handle(&args.cf_name_by_accession).unwrap();
-
-    // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
+    let details = |key: &[u8]| -> Result<_, anyhow::Error> {
+        if with_details {
+            query_details(db, &args.cf_name_details, key)
+        } else {
+            Ok(None)
         }
     };
 
-    tracing::info!("Running query...");
-    let before_query = std::time::Instant::now();
-    if let Some(accession) = args.query.accession.as_ref() {
-        if let Some(record) = query_for_accession(accession, &db, &cf_data, &cf_by_accession)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+    if let Some(accession) = query.accession.as_ref() {
+        if let Some(record) = query_for_accession(accession, db, &args.cf_name, &cf_by_accession)? {
+            let details = details(&accession_key(accession, db, &cf_by_accession)?)?;
+            print_record(out_writer, accession, &record, details.as_ref())?;
         } else {
             tracing::info!("no record found for accession {}", accession);
         }
-    } else if let Some(variant) = args.query.variant.as_ref() {
-        if let Some(record) = query_for_variant(variant, &meta, &db, &cf_data)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+    } else if let Some(variant) = query.variant.as_ref() {
+        if let Some(record) = query_for_variant(variant, meta, db, &args.cf_name)? {
+            let details = details(&variant_key(variant, meta)?)?;
+            print_record(
+                out_writer,
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &record,
+                details.as_ref(),
+            )?;
         } else {
             tracing::info!("no record found for variant {:?}", &variant);
         }
     } else {
-        let (start, stop) = if let Some(position) = args.query.position.as_ref() {
+        let (start, stop) = if let Some(position) = query.position.as_ref() {
             let position = spdi::Pos {
                 sequence: extract_chrom::from_pos(position, Some(&meta.genome_release))?,
                 ..position.clone()
             };
             (Some(position.clone()), Some(position))
-        } else if let Some(range) = args.query.range.as_ref() {
-            let range = spdi::Range {
-                sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-                ..range.clone()
-            };
+        } else if let Some(range) = query.range.as_ref() {
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
             let (start, stop) = range.into();
             (Some(start), Some(stop))
-        } else if args.query.all {
+        } else if query.all {
             (None, None)
         } else {
             unreachable!()
@@ -239,8 +354,9 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             tracing::debug!("stop = {:?}", &stop);
         }
 
-        // Iterate over all variants until we are behind stop.
-        while iter.valid() {
+        // Iterate over all variants until we are behind stop or reach the limit.
+        let mut counter = args.limit.counter();
+        while iter.valid() && !counter.is_done() {
             if let Some(raw_value) = iter.value() {
                 tracing::trace!("iterator at {:?} => {:?}", &iter.key(), &raw_value);
                 if let Some(stop) = stop.as_ref() {
@@ -252,17 +368,75 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                     }
                 }
 
-                let record = crate::pbs::clinvar::minimal::ExtractedVcvRecordList::decode(
-                    &mut std::io::Cursor::new(&raw_value),
-                )
-                .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
-                print_record(&mut out_writer, args.out_format, &record)?;
+                let iter_key = iter.key().unwrap();
+                if counter.admit() {
+                    let record: crate::pbs::clinvar::minimal::ExtractedVcvRecordList =
+                        decode_protobuf(db, &args.cf_name, iter_key, raw_value)?;
+                    let details = details(iter_key)?;
+                    print_record(
+                        out_writer,
+                        &common::output::db_key(iter_key),
+                        &record,
+                        details.as_ref(),
+                    )?;
+                }
                 iter.next();
             } else {
                 break;
             }
         }
     }
+
+    Ok(())
+}
+
+/// Implementation of `tsv query` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'clinvar-minimal query' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if let Some(server_url) = args.server_url.as_ref() {
+        return common::remote::run_query(
+            server_url,
+            &args.query,
+            "clinvar",
+            &args.out_file,
+            args.output.output_format,
+        );
+    }
+
+    let (db, meta) = open_rocksdb_from_args(args)?;
+    let with_details = args.details && db.cf_handle(&args.cf_name_details).is_some();
+    if args.details && !with_details {
+        tracing::warn!(
+            "database has no column family {}, writing records without details",
+            &args.cf_name_details
+        );
+    }
+
+    // Obtain writer to output.
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "clinvar-minimal",
+        Some(&meta.genome_release),
+    )?;
+
+    tracing::info!("Running query...");
+    let before_query = std::time::Instant::now();
+    if let Some(path_input_jsonl) = args.query.path_input_jsonl.as_ref() {
+        common::batch::run(
+            common::batch::open_input(path_input_jsonl)?,
+            args.strict,
+            &mut out_writer,
+            |query: &ArgsQuery, out_writer| {
+                run_query(query, args, &db, &meta, with_details, out_writer)
+            },
+        )?;
+    } else {
+        run_query(&args.query, args, &db, &meta, with_details, &mut out_writer)?;
+    }
     tracing::info!("... done querying in {:?}", before_query.elapsed());
 
     tracing::info!("All done. Have a nice day!");
@@ -281,14 +455,22 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            path_rocksdb: String::from("tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.db"),
+            path_rocksdb: Some(String::from(
+                "tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.db",
+            )),
+            server_url: None,
             cf_name: String::from("clinvar"),
             cf_name_by_accession: String::from("clinvar_by_accession"),
+            cf_name_details: String::from("clinvar_details"),
+            details: false,
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             query,
+            limit: Default::default(),
+            strict: false,
         };
 
         (common, args, temp)
@@ -398,6 +580,46 @@ mod test {
         Ok(())
     }
 
+    #[rstest::rstest]
+    #[case(Some(5), 0, 5)]
+    #[case(Some(5), 10, 5)]
+    #[case(Some(0), 0, 0)]
+    #[case(None, 10_000, 0)]
+    fn query_all_limit_offset(
+        #[case] limit: Option<usize>,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) -> Result<(), anyhow::Error> {
+        let (common, args_all, _temp) = args(ArgsQuery {
+            all: true,
+            ..Default::default()
+        });
+        run(&common, &args_all)?;
+        let out_all = std::fs::read_to_string(&args_all.out_file)?;
+        let (_, args, _temp) = args(ArgsQuery {
+            all: true,
+            ..Default::default()
+        });
+        let args = Args {
+            limit: common::cli::ArgsLimit { limit, offset },
+            ..args
+        };
+        run(&common, &args)?;
+
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out_data.lines().count(), expected);
+        assert_eq!(
+            out_data.lines().collect::<Vec<_>>(),
+            out_all
+                .lines()
+                .skip(offset)
+                .take(expected)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
     #[rstest::rstest]
     #[test]
     #[case("RCV001679107.1")]
@@ -415,4 +637,114 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:13:95227055:A:G").unwrap()),
+            ..Default::default()
+        },
+        &["13:95227055:A:G"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:13:95227093:95227093").unwrap()),
+            ..Default::default()
+        },
+        &["13:95227093:T:C"],
+    )]
+    #[case::accession(
+        ArgsQuery {
+            accession: Some("VCV001307216".to_string()),
+            ..Default::default()
+        },
+        &["VCV001307216"],
+    )]
+    fn smoke_query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args_v1, _temp_v1) = args(query.clone());
+        run(&common, &args_v1)?;
+        let (common, args_v2, _temp_v2) = args(query);
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            "clinvar-minimal",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_path_input_jsonl() -> Result<(), anyhow::Error> {
+        let queries = [
+            ArgsQuery {
+                variant: Some(spdi::Var::from_str("GRCh37:13:95227055:A:G")?),
+                ..Default::default()
+            },
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("GRCh37:13:95227055:95248751")?),
+                ..Default::default()
+            },
+            ArgsQuery {
+                accession: Some(String::from("VCV001307216.2")),
+                ..Default::default()
+            },
+        ];
+        let mut expected = Vec::new();
+        for (input_index, query) in queries.into_iter().enumerate() {
+            let (common, args, _temp) = args(query);
+            run(&common, &args)?;
+            for line in std::fs::read_to_string(&args.out_file)?.lines() {
+                let mut record: serde_json::Value = serde_json::from_str(line)?;
+                record
+                    .as_object_mut()
+                    .unwrap()
+                    .insert(String::from("input_index"), input_index.into());
+                expected.push(record);
+            }
+        }
+        expected.push(serde_json::json!({
+            "input_index": 4,
+            "error": "no record found for accession VCV000000001.1"
+        }));
+
+        let (common, args, temp) = args(ArgsQuery::default());
+        let path_input = temp.join("input.jsonl").to_string_lossy().to_string();
+        std::fs::write(
+            &path_input,
+            "{\"variant\": \"GRCh37:13:95227055:A:G\"}\n\
+            {\"range\": \"GRCh37:13:95227055:95248751\"}\n\
+            {\"accession\": \"VCV001307216.2\"}\n\
+            \n\
+            {\"accession\": \"VCV000000001.1\"}\n",
+        )?;
+        let args = Args {
+            query: ArgsQuery {
+                path_input_jsonl: Some(path_input),
+                ..Default::default()
+            },
+            ..args
+        };
+        run(&common, &args)?;
+        let lines = std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+        assert_eq!(lines, expected);
+
+        Ok(())
+    }
 }
@@ -1,3 +1,7 @@
 //! Support for "minimal" ClinVar information (for Mehari).
 
 pub mod cli;
+
+/// Default name of the column family with the submission-level details, written by
+/// `clinvar-minimal import --with-details`.
+pub const DETAILS_CF_NAME: &str = "clinvar_details";
@@ -5,7 +5,7 @@ use std::{io::BufRead, sync::Arc};
 use clap::Parser;
 use prost::Message as _;
 
-use crate::common;
+use crate::{clinvar_sv::index, common};
 
 /// Command line arguments for `clinvar-sv import` sub command.
 #[derive(Parser, Debug, Clone)]
@@ -30,9 +30,18 @@ pub struct Args {
     /// Mapping from ClinVar RCV to ClinVar VCV.
     #[arg(long, default_value = "clinvar_sv_by_rcv")]
     pub cf_name_by_rcv: String,
+    /// Name of the column family with the interval index, see `clinvar_sv::index`.
+    #[arg(long, default_value = index::CF_NAME)]
+    pub cf_name_index: String,
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform import of the JSONL file.
@@ -40,20 +49,13 @@ fn jsonl_import(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     args: &Args,
     path_in_jsonl: &str,
+    index: &mut index::IndexBuilder,
 ) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let cf_by_rcv = db.cf_handle(&args.cf_name_by_rcv).unwrap();
 
     // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if path_in_jsonl.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            path_in_jsonl,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(path_in_jsonl)?)
-    };
-
-    let reader = std::io::BufReader::new(reader);
+    let reader = common::open_read_maybe_gz(path_in_jsonl)?;
 
     for line in reader.lines() {
         let line = line?;
@@ -116,6 +118,7 @@ fn jsonl_import(
 
         let buf = vcv_record.encode_to_vec();
         db.put_cf(&cf_data, &key, &buf)?;
+        index.insert(&vcv_record)?;
 
         for rcv_record in &rcv_records {
             let accession = rcv_record
@@ -130,12 +133,38 @@ fn jsonl_import(
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+///
+/// The first record of each input file is parsed in the same way as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    for path_in_jsonl in &args.path_in_jsonl {
+        validation.read_input(
+            path_in_jsonl,
+            common::dry_run::first_jsonl_record::<
+                crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+            >,
+        );
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `clinvar-minimal import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'clinvar-minimal import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -143,7 +172,12 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
-    let cf_names = &["meta", &args.cf_name, &args.cf_name_by_rcv];
+    let cf_names = &[
+        "meta",
+        &args.cf_name,
+        &args.cf_name_by_rcv,
+        &args.cf_name_index,
+    ];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out_rocksdb)?,
@@ -168,15 +202,25 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Importing JSONL file ...");
     let before_import = std::time::Instant::now();
+    let mut index = index::IndexBuilder::default();
     for path in &args.path_in_jsonl {
         tracing::info!("  - {}", &path);
-        jsonl_import(&db, args, path)?;
+        jsonl_import(&db, args, path, &mut index)?;
     }
     tracing::info!(
         "... done importing JSONL file in {:?}",
         before_import.elapsed()
     );
 
+    tracing::info!("Writing interval index ...");
+    let before_index = std::time::Instant::now();
+    let count = index.write(&db, &args.cf_name_index)?;
+    tracing::info!(
+        "... done writing interval index with {} intervals in {:?}",
+        count,
+        before_index.elapsed()
+    );
+
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
     rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
@@ -185,6 +229,17 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "clinvar-sv".to_string(),
+            title: "ClinVar SVs".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("ClinVar".to_string(), args.spec.data_version())],
+            paths_in: args.path_in_jsonl.clone(),
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -201,6 +256,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
@@ -211,10 +267,50 @@ mod test {
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("clinvar_sv"),
             cf_name_by_rcv: String::from("clinvar_sv_by_rcv"),
+            cf_name_index: String::from(index::CF_NAME),
             min_var_size: 50,
             path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
     }
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[test]
+    fn dry_run_import_jsonl() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_jsonl: vec![String::from(
+                "tests/clinvar-sv/clinvar-variants-grch37-strucvars.jsonl",
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("clinvar_sv"),
+            cf_name_by_rcv: String::from("clinvar_sv_by_rcv"),
+            cf_name_index: String::from(index::CF_NAME),
+            min_var_size: 50,
+            path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_jsonl: vec![
+                String::from("tests/clinvar-sv/clinvar-variants-grch37-strucvars.jsonl"),
+                String::from("tests/clinvar-sv/boostrap.sh"),
+            ],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
 }
@@ -1,16 +1,26 @@
 //! Query of ClinVar SV annonars data.
 
-use std::{io::Write, sync::Arc};
+use std::sync::Arc;
 
 use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
-use prost::Message;
 
-use crate::common::{self, cli::extract_chrom, spdi};
+use prost::Message as _;
+
+use crate::{
+    clinvar_sv::index,
+    common::{
+        self,
+        cli::{extract_chrom, GenomeRelease},
+        decode::decode_protobuf,
+        spdi,
+    },
+    genes,
+};
 
 /// Number of cases in ExAC CNV (PMID:27899611).
 pub const EXAC_CNV_CASES: u32 = 60_706;
 
-/// Argument group for specifying accession or range.
+/// Argument group for specifying accession, range, or gene.
 #[derive(clap::Args, Debug, Clone, Default)]
 #[group(required = true, multiple = false)]
 pub struct ArgsQuery {
@@ -23,6 +33,10 @@ pub struct ArgsQuery {
     /// Specify range to query for.
     #[arg(long, group = "query")]
     pub range: Option<spdi::Range>,
+    /// Query for the interval of a gene, given as HGNC ID or symbol, requires
+    /// `--path-genes-rocksdb`.
+    #[arg(long, group = "query", requires = "path_genes_rocksdb")]
+    pub hgnc_id: Option<String>,
 }
 
 /// Command line arguments for `clinvar-sv query` sub command.
@@ -38,14 +52,23 @@ pub struct Args {
     /// Mapping from ClinVar RCV to ClinVar VCV.
     #[arg(long, default_value = "clinvar_sv_by_rcv")]
     pub cf_name_by_rcv: String,
+    /// Name of the column family with the interval index, see `clinvar_sv::index`.
+    #[arg(long, default_value = index::CF_NAME)]
+    pub cf_name_index: String,
     /// Output file (default is stdout == "-").
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
+    /// Path to genes RocksDB directory for resolving `--hgnc-id`.
+    #[arg(long)]
+    pub path_genes_rocksdb: Option<String>,
+    /// Number of base pairs to extend the gene interval of `--hgnc-id` by on each side.
+    #[arg(long, default_value_t = 0)]
+    pub padding_bp: u32,
 
-    /// Variant or position to query for.
+    /// Accession, range, or gene to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
 }
@@ -58,28 +81,39 @@ pub struct Meta {
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
+///
+/// The interval index column family `cf_index` is only opened if present.
 pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     path_rocksdb: P,
     cf_data: &str,
     cf_meta: &str,
     cf_by_rcv: &str,
+    cf_index: &str,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
     tracing::info!("Opening RocksDB database ...");
     let before_open = std::time::Instant::now();
-    let cf_names = &[cf_meta, cf_data, cf_by_rcv];
+    let path_rocksdb = common::readlink_f_existing(&path_rocksdb)?;
+    let mut cf_names = vec![cf_meta, cf_data, cf_by_rcv];
+    if rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_rocksdb)
+        .map_err(|e| anyhow::anyhow!("problem listing column families: {}", e))?
+        .iter()
+        .any(|name| name == cf_index)
+    {
+        cf_names.push(cf_index);
+    }
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
-        cf_names,
+        path_rocksdb,
+        &cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
         }
@@ -103,30 +137,111 @@ pub fn open_rocksdb_from_args(
         &args.cf_name,
         "meta",
         &args.cf_name_by_rcv,
+        &args.cf_name_index,
     )
 }
 
+/// Write a single record to `out_writer` with its VCV accession as the key, with the gene
+/// interval it has been found for if given.
 fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     value: &crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+    gene_interval: Option<&GeneInterval>,
 ) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
+    let key = value
+        .accession
+        .as_ref()
+        .map(|accession| accession.accession.as_str())
+        .unwrap_or_default();
+    match gene_interval {
+        Some(gene_interval) => out_writer.write(
+            key,
+            &WithGeneInterval {
+                record: value,
+                gene_interval,
+            },
+        ),
+        None => out_writer.write(key, value),
     }
+}
 
-    Ok(())
+/// Gene interval used for a `--hgnc-id` query, written with each record.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GeneInterval {
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// Symbol of the gene.
+    pub symbol: String,
+    /// Range of the gene as `CHROM:START:END`, 1-based and inclusive.
+    pub gene_range: String,
+    /// Padding of the gene range on each side.
+    pub padding_bp: u32,
+    /// The padded range that has been queried.
+    pub query_range: String,
+}
+
+/// Record with the `GeneInterval` it has been found for.
+#[derive(Debug, serde::Serialize)]
+struct WithGeneInterval<'a> {
+    /// The record itself.
+    #[serde(flatten)]
+    record: &'a crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord,
+    /// The gene interval.
+    gene_interval: &'a GeneInterval,
+}
+
+/// Resolve `gene` with the genes database `genes_db` to its range on the genome release of
+/// the ClinVar SV database, extended by `padding_bp` on each side.
+///
+/// Returns the range to query and its description for the output.
+pub fn gene_interval(
+    gene: &str,
+    padding_bp: u32,
+    genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+) -> Result<(spdi::Range, GeneInterval), anyhow::Error> {
+    let genome_release: GenomeRelease =
+        meta.genome_release.to_lowercase().parse().map_err(|e| {
+            anyhow::anyhow!("invalid genome release {}: {}", &meta.genome_release, e)
+        })?;
+    let record = genes::cli::query::resolve_gene(gene, genes_db, "genes")?;
+    let gene_range = genes::cli::query::gene_range(&record, genome_release)?;
+    let padding = i32::try_from(padding_bp)?;
+    let query_range = spdi::Range::new(
+        gene_range.sequence.clone(),
+        gene_range.start.saturating_sub(padding).max(1),
+        gene_range.end.saturating_add(padding),
+    );
+    let (hgnc_id, symbol) = record
+        .hgnc
+        .map(|hgnc| (hgnc.hgnc_id, hgnc.symbol))
+        .unwrap_or_default();
+    tracing::info!(
+        "gene {} ({}) resolved to range {}, querying {} with padding of {} bp",
+        &symbol,
+        &hgnc_id,
+        &gene_range,
+        &query_range,
+        padding_bp
+    );
+    let gene_interval = GeneInterval {
+        hgnc_id,
+        symbol,
+        gene_range: gene_range.to_string(),
+        padding_bp,
+        query_range: query_range.to_string(),
+    };
+    Ok((query_range, gene_interval))
 }
 
 /// Query by accession.
 pub fn query_for_accession(
     accession: &str,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
     cf_by_rcv: &Arc<rocksdb::BoundColumnFamily>,
 ) -> Result<Option<crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord>, anyhow::Error> {
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
     // Execute query.
     tracing::debug!("accession = {:?}", &accession);
     let vcv = if accession.starts_with("VCV") {
@@ -139,38 +254,31 @@ pub fn query_for_accession(
     };
     tracing::debug!("vcv = {:?}", &vcv);
 
-    let raw_value = db
-        .get_cf(cf_data, vcv.clone())
-        .map_err(|e| anyhow::anyhow!("error while querying for vcv {:?}: {}", vcv, e))?;
-    raw_value
-        .map(|raw_value| {
-            // Decode via prost.
-            crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord::decode(
-                &mut std::io::Cursor::new(&raw_value),
-            )
-            .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
-        })
-        .transpose()
+    let raw_value = db.get_cf(&cf_data, vcv.clone()).map_err(|e| {
+        crate::ErrorCategory::Database
+            .error(format!("error while querying for vcv {:?}: {}", vcv, e))
+    })?;
+    Ok(raw_value
+        .map(|raw_value| decode_protobuf(db, cf_data_name, &vcv, &raw_value))
+        .transpose()?)
 }
 
 /// Query all variants and print to `out_writer`.
 fn print_all(
-    out_writer: &mut Box<dyn std::io::Write>,
-    out_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("dumping all records...");
 
-    let mut iter = db.raw_iterator_cf(cf_data);
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let mut iter = db.raw_iterator_cf(&cf_data);
     iter.seek(b"");
     while iter.valid() {
-        if let Some(raw_value) = iter.value() {
-            let record = crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord::decode(
-                &mut std::io::Cursor::new(&raw_value),
-            )
-            .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
-            print_record(out_writer, out_format, &record)?;
+        if let (Some(iter_key), Some(raw_value)) = (iter.key(), iter.value()) {
+            let record: crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord =
+                decode_protobuf(db, cf_data_name, iter_key, raw_value)?;
+            print_record(out_writer, &record, None)?;
             iter.next();
         } else {
             break;
@@ -181,11 +289,14 @@ fn print_all(
     Ok(())
 }
 
+/// Per-chromosome interval trees mapping to the VCV.
+type Trees = rustc_hash::FxHashMap<String, ArrayBackedIntervalTree<u64, String>>;
+
 /// Helper data structure that provides per-chromosome interval trees for querying.
 #[derive(Debug)]
 pub struct IntervalTrees {
     /// Per-chromosome interval trees.
-    trees: rustc_hash::FxHashMap<String, ArrayBackedIntervalTree<u64, String>>,
+    trees: Trees,
     /// Backing RocksDB.
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     /// Name of column family with data.
@@ -197,12 +308,14 @@ pub struct IntervalTrees {
 impl IntervalTrees {
     /// Construct new per-contig interval trees.
     ///
-    /// This will read all records from the database and build the interval trees.
+    /// The trees are built from the interval index if the database has the column family
+    /// `cf_index_name` and from all records otherwise.
     ///
     /// # Arguments
     ///
     /// * `db` - Database to read from.
     /// * `cf_data_name` - Name of column family with data.
+    /// * `cf_index_name` - Name of column family with the interval index.
     /// * `meta` - Meta information from database.
     ///
     /// # Returns
@@ -215,94 +328,122 @@ impl IntervalTrees {
     pub fn with_db(
         db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
         cf_data_name: &str,
+        cf_index_name: &str,
         meta: Meta,
     ) -> Result<Self, anyhow::Error> {
-        let cf_data = db.cf_handle(cf_data_name).ok_or_else(|| {
-            anyhow::anyhow!("no column family with name {:?} found", cf_data_name)
-        })?;
+        if db.cf_handle(cf_data_name).is_none() {
+            anyhow::bail!("no column family with name {:?} found", cf_data_name);
+        }
+        let before_build = std::time::Instant::now();
+        let (trees, source) = if db.cf_handle(cf_index_name).is_some() {
+            (Self::load_trees(&db, cf_index_name)?, "interval index")
+        } else {
+            tracing::warn!(
+                "no interval index in column family {:?}, building interval trees from all \
+                records; re-import the database to speed this up",
+                cf_index_name
+            );
+            (Self::build_trees(db.clone(), cf_data_name)?, "all records")
+        };
+        let (intervals, heap_size) = Self::stats(&trees);
+        tracing::info!(
+            "built interval trees with {} intervals (~{} bytes) from {} in {:?}",
+            intervals,
+            heap_size,
+            source,
+            before_build.elapsed()
+        );
         Ok(Self {
-            trees: Self::build_trees(db.clone(), cf_data.clone())?,
+            trees,
             db: db.clone(),
             cf_data_name: cf_data_name.to_string(),
             meta,
         })
     }
 
-    /// Build the interval trees.
+    /// Return the backing RocksDB.
+    pub fn db(&self) -> &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>> {
+        &self.db
+    }
+
+    /// Return the number of intervals in `trees` and their approximate heap size in bytes.
+    fn stats(trees: &Trees) -> (usize, usize) {
+        let entry_size = std::mem::size_of::<(std::ops::Range<u64>, String, u64)>();
+        trees
+            .iter()
+            .fold((0, 0), |(count, heap_size), (contig, tree)| {
+                let entries = tree.find(0..u64::MAX);
+                (
+                    count + entries.len(),
+                    heap_size
+                        + contig.len()
+                        + entries
+                            .iter()
+                            .map(|entry| entry_size + entry.data().len())
+                            .sum::<usize>(),
+                )
+            })
+    }
+
+    /// Build the interval trees from the interval index in `cf_index_name`.
+    fn load_trees(
+        db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+        cf_index_name: &str,
+    ) -> Result<Trees, anyhow::Error> {
+        let cf_index = db.cf_handle(cf_index_name).unwrap();
+        let mut result = Trees::default();
+
+        let mut iter = db.raw_iterator_cf(&cf_index);
+        iter.seek(b"");
+        while let (Some(iter_key), Some(raw_value)) = (iter.key(), iter.value()) {
+            let contig = String::from_utf8(iter_key.to_vec())?;
+            let index = crate::pbs::clinvar::sv::ContigIndex::decode(raw_value).map_err(|e| {
+                crate::ErrorCategory::Database.error(format!(
+                    "problem decoding interval index of contig {:?}: {}",
+                    &contig, e
+                ))
+            })?;
+            let tree = result.entry(contig).or_default();
+            for interval in index.intervals {
+                tree.insert(interval.start..interval.stop, interval.vcv);
+            }
+            iter.next();
+        }
+        iter.status()?;
+
+        result.values_mut().for_each(|tree| tree.index());
+
+        Ok(result)
+    }
+
+    /// Build the interval trees from all records.
     fn build_trees(
         db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
-        cf_data: Arc<rocksdb::BoundColumnFamily>,
-    ) -> Result<rustc_hash::FxHashMap<String, ArrayBackedIntervalTree<u64, String>>, anyhow::Error>
-    {
-        let mut result: rustc_hash::FxHashMap<String, ArrayBackedIntervalTree<u64, String>> =
-            rustc_hash::FxHashMap::default();
+        cf_data_name: &str,
+    ) -> Result<Trees, anyhow::Error> {
+        let cf_data = db.cf_handle(cf_data_name).unwrap();
+        let mut result = Trees::default();
 
         // Obtain iterator and seek to start.
         let mut iter = db.raw_iterator_cf(&cf_data);
         iter.seek(b"");
         while iter.valid() {
-            if let Some(raw_value) = iter.value() {
-                let record = crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord::decode(
-                    &mut std::io::Cursor::new(&raw_value),
-                )
-                .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
+            if let (Some(iter_key), Some(raw_value)) = (iter.key(), iter.value()) {
+                let record: crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord =
+                    decode_protobuf(&db, cf_data_name, iter_key, raw_value)?;
                 tracing::trace!("iterator at {:?} => {:?}", &iter.key(), &record);
 
-                let crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord {
-                    accession,
-                    sequence_location,
-                    ..
-                } = record;
-                let accession = accession.expect("accession is required");
-                let vcv = format!("{}.{}", accession.accession, accession.version);
-                let sequence_location = sequence_location.expect("sequence_location is required");
-                let crate::pbs::clinvar_data::clinvar_public::location::SequenceLocation {
-                    chr,
-                    start,
-                    stop,
-                    inner_start,
-                    inner_stop,
-                    outer_start,
-                    outer_stop,
-                    ..
-                } = sequence_location.clone();
-                let chr_pb = crate::pbs::clinvar_data::clinvar_public::Chromosome::try_from(chr)
-                    .map_err(|e| {
-                        anyhow::anyhow!(
-                            "problem converting chromosome {} to Chromosome: {}",
-                            chr,
-                            e
-                        )
-                    })?;
-
-                let (start, stop) = if let (Some(start), Some(stop)) = (start, stop) {
-                    (start, stop)
-                } else if let (Some(inner_start), Some(inner_stop)) = (inner_start, inner_stop) {
-                    (inner_start, inner_stop)
-                } else if let (Some(outer_start), Some(outer_stop)) = (outer_start, outer_stop) {
-                    (outer_start, outer_stop)
-                } else {
+                let Some((contig, interval, vcv)) = index::record_interval(&record)? else {
                     tracing::warn!(
                         "skipping record without start/stop: {:?}, {:?}",
-                        &vcv,
-                        &sequence_location
+                        &record.accession,
+                        &record.sequence_location
                     );
                     iter.next();
                     continue;
                 };
-
-                let interval = (start as u64 - 1)..(stop as u64);
-                tracing::trace!(
-                    "contig = {} / {:?} / {}",
-                    &chr_pb.as_chr_name(),
-                    &interval,
-                    &vcv
-                );
-                result
-                    .entry(chr_pb.as_chr_name())
-                    .or_default()
-                    .insert(interval, vcv);
-                assert!(result.contains_key(&chr_pb.as_chr_name()));
+                tracing::trace!("contig = {} / {:?} / {}", &contig, &interval, &vcv);
+                result.entry(contig).or_default().insert(interval, vcv);
 
                 iter.next();
             } else {
@@ -321,7 +462,8 @@ impl IntervalTrees {
         range: &spdi::Range,
     ) -> Result<Vec<crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord>, anyhow::Error>
     {
-        let contig = extract_chrom::from_range(range, Some(&self.meta.genome_release))?;
+        let range = extract_chrom::range(range, Some(&self.meta.genome_release))?;
+        let contig = range.sequence.clone();
         let cf_data = self.db.cf_handle(&self.cf_data_name).ok_or_else(|| {
             anyhow::anyhow!("no column family with name {:?} found", &self.cf_data_name)
         })?;
@@ -330,11 +472,13 @@ impl IntervalTrees {
         if let Some(tree) = self.trees.get(&contig) {
             for entry in tree.find(&interval) {
                 if let Some(raw_value) = self.db.get_cf(&cf_data, entry.data().as_bytes())? {
-                    let record =
-                        crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord::decode(
-                            &mut std::io::Cursor::new(&raw_value),
-                        )
-                        .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
+                    let record: crate::pbs::clinvar_data::extracted_vars::ExtractedVcvRecord =
+                        decode_protobuf(
+                            &self.db,
+                            &self.cf_data_name,
+                            entry.data().as_bytes(),
+                            &raw_value,
+                        )?;
                     result.push(record);
                 }
             }
@@ -353,31 +497,46 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("args = {:#?}", &args);
 
     let (db, meta) = open_rocksdb_from_args(args)?;
-    let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let cf_by_rcv = db.cf_handle(&args.cf_name_by_rcv).unwrap();
+    let gene = args
+        .query
+        .hgnc_id
+        .as_ref()
+        .map(|gene| -> Result<_, anyhow::Error> {
+            let path_genes_rocksdb = args.path_genes_rocksdb.as_ref().ok_or_else(|| {
+                crate::ErrorCategory::Usage.error("--hgnc-id requires --path-genes-rocksdb")
+            })?;
+            let genes_db = genes::cli::query::open_rocksdb(path_genes_rocksdb, "genes", "meta")?;
+            gene_interval(gene, args.padding_bp, &genes_db, &meta)
+        })
+        .transpose()?;
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "clinvar-sv",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
     if let Some(accession) = args.query.accession.as_ref() {
         tracing::info!("for accession {}", &accession);
-        if let Some(record) = query_for_accession(accession, &db, &cf_data, &cf_by_rcv)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+        if let Some(record) = query_for_accession(accession, &db, &args.cf_name, &cf_by_rcv)? {
+            print_record(&mut out_writer, &record, None)?;
         } else {
             tracing::info!("no record found for accession {:?}", &accession);
         }
-    } else if let Some(range) = args.query.range.as_ref() {
+    } else if let Some(range) = args
+        .query
+        .range
+        .as_ref()
+        .or(gene.as_ref().map(|(range, _)| range))
+    {
         tracing::info!("for range {:?}", &range);
         tracing::info!("Building interval trees...");
-        let trees = IntervalTrees::with_db(db.clone(), &args.cf_name, meta)
+        let trees = IntervalTrees::with_db(db.clone(), &args.cf_name, &args.cf_name_index, meta)
             .map_err(|e| anyhow::anyhow!("failed to build interval trees: {}", e))?;
         tracing::info!("... done building interval trees");
         tracing::info!("Running query...");
@@ -385,12 +544,16 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             .query(range)
             .map_err(|e| anyhow::anyhow!("failed to query interval trees: {}", e))?;
         for record in &records {
-            print_record(&mut out_writer, args.out_format, record)?;
+            print_record(
+                &mut out_writer,
+                record,
+                gene.as_ref().map(|(_, gene_interval)| gene_interval),
+            )?;
         }
         tracing::info!("... done running query");
     } else if args.query.all {
         tracing::info!("for all");
-        print_all(&mut out_writer, args.out_format, &db, &cf_data)?;
+        print_all(&mut out_writer, &db, &args.cf_name)?;
     } else {
         unreachable!();
     }
@@ -404,6 +567,8 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 mod test {
     use std::str::FromStr as _;
 
+    use clap::Parser as _;
+
     use super::*;
 
     use temp_testdir::TempDir;
@@ -412,13 +577,17 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_rocksdb: String::from("tests/clinvar-sv/clinvar-sv-grch37.db"),
             cf_name: String::from("clinvar_sv"),
             cf_name_by_rcv: String::from("clinvar_sv_by_rcv"),
+            cf_name_index: String::from(index::CF_NAME),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
+            path_genes_rocksdb: None,
+            padding_bp: 0,
             query,
         };
 
@@ -490,6 +659,75 @@ mod test {
         Ok(())
     }
 
+    #[rstest::rstest]
+    #[case::exact("GRCh37:22:34150132:34182300", 1)]
+    #[case::overlap_multiple("GRCh37:X:8590000:8600000", 4)]
+    #[case::contig_x("GRCh37:X:1:155270560", 13)]
+    #[case::contig_y("GRCh37:Y:2650000:2660000", 2)]
+    #[case::overlap_end("GRCh37:22:34182000:34182300", 1)]
+    #[case::no_records("GRCh37:17:1:81195210", 0)]
+    fn query_interval_index_same_as_full_scan(
+        #[case] range: &str,
+        #[case] expected_len: usize,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_rocksdb = format!("{}", tmp_dir.join("clinvar-sv").display());
+        crate::clinvar_sv::cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::clinvar_sv::cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_jsonl: vec![
+                    String::from("tests/clinvar-sv/clinvar-variants-grch37-seqvars.jsonl"),
+                    String::from("tests/clinvar-sv/clinvar-variants-grch37-strucvars.jsonl"),
+                ],
+                path_out_rocksdb: path_rocksdb.clone(),
+                min_var_size: 50,
+                cf_name: String::from("clinvar_sv"),
+                cf_name_by_rcv: String::from("clinvar_sv_by_rcv"),
+                cf_name_index: String::from(index::CF_NAME),
+                path_wal_dir: None,
+                spec: Default::default(),
+                dry_run: false,
+            },
+        )?;
+        let (db, meta) = open_rocksdb(
+            &path_rocksdb,
+            "clinvar_sv",
+            "meta",
+            "clinvar_sv_by_rcv",
+            index::CF_NAME,
+        )?;
+        assert!(db.cf_handle(index::CF_NAME).is_some());
+        let full_scan = IntervalTrees {
+            trees: IntervalTrees::build_trees(db.clone(), "clinvar_sv")?,
+            db: db.clone(),
+            cf_data_name: String::from("clinvar_sv"),
+            meta: Meta {
+                genome_release: meta.genome_release.clone(),
+            },
+        };
+        let from_index = IntervalTrees::with_db(db, "clinvar_sv", index::CF_NAME, meta)?;
+
+        let range = spdi::Range::from_str(range)?;
+        let accessions = |trees: &IntervalTrees| -> Result<Vec<_>, anyhow::Error> {
+            let mut result = trees
+                .query(&range)?
+                .into_iter()
+                .map(|record| record.accession)
+                .collect::<Vec<_>>();
+            result.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+            Ok(result)
+        };
+        let expected = accessions(&full_scan)?;
+        assert_eq!(expected.len(), expected_len);
+        assert_eq!(accessions(&from_index)?, expected);
+
+        Ok(())
+    }
+
     #[test]
     fn smoke_query_var_range_no_overlap() -> Result<(), anyhow::Error> {
         let (common, args, _temp) = args(ArgsQuery {
@@ -502,4 +740,106 @@ mod test {
 
         Ok(())
     }
+
+    /// Write a genes database with `(hgnc_id, symbol, genomic_location_37)`.
+    fn genes_db(temp: &TempDir, genes: &[(&str, &str, &str)]) -> String {
+        let path = temp.join("genes-db").to_string_lossy().to_string();
+        let records = genes
+            .iter()
+            .map(
+                |(hgnc_id, symbol, genomic_location_37)| crate::pbs::genes::base::Record {
+                    clingen: (!genomic_location_37.is_empty()).then(|| {
+                        crate::pbs::genes::base::ClingenDosageRecord {
+                            gene_symbol: symbol.to_string(),
+                            genomic_location_37: genomic_location_37.to_string(),
+                            ..Default::default()
+                        }
+                    }),
+                    ..crate::genes::cli::import::test::record(hgnc_id, symbol)
+                },
+            )
+            .collect::<Vec<_>>();
+        crate::genes::cli::import::test::write_records(&path, &records).unwrap();
+        path
+    }
+
+    /// Return the arguments for querying `gene` with `padding_bp` and the genes database.
+    fn args_gene(gene: &str, padding_bp: u32) -> (common::cli::Args, Args, TempDir) {
+        let (common, mut args, temp) = args(ArgsQuery {
+            hgnc_id: Some(gene.into()),
+            ..Default::default()
+        });
+        // The SV VCV000057627 ends at 22:34182300, 100 bp before the start of GENE1.
+        args.path_genes_rocksdb = Some(genes_db(
+            &temp,
+            &[
+                ("HGNC:1", "GENE1", "chr22:34182401-34190000"),
+                ("HGNC:2", "GENE2", ""),
+            ],
+        ));
+        args.padding_bp = padding_bp;
+        (common, args, temp)
+    }
+
+    #[rstest::rstest]
+    #[case::no_padding("HGNC:1", 0, 0)]
+    #[case::padding_short("HGNC:1", 100, 0)]
+    #[case::padding_overlap("HGNC:1", 101, 1)]
+    #[case::padding_by_symbol("GENE1", 1000, 1)]
+    fn query_gene_padding(
+        #[case] gene: &str,
+        #[case] padding_bp: u32,
+        #[case] expected_len: usize,
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args_gene(gene, padding_bp);
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+
+        let records = out_data
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records.len(), expected_len);
+        for record in &records {
+            assert_eq!(record["accession"]["accession"], "VCV000057627");
+            assert_eq!(
+                record["gene_interval"],
+                serde_json::json!({
+                    "hgnc_id": "HGNC:1",
+                    "symbol": "GENE1",
+                    "gene_range": "22:34182401:34190000",
+                    "padding_bp": padding_bp,
+                    "query_range": format!("22:{}:{}", 34182401 - padding_bp, 34190000 + padding_bp),
+                })
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_gene_without_coordinates() {
+        let (common, args, _temp) = args_gene("HGNC:2", 0);
+
+        let err = run(&common, &args).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "gene GENE2 (HGNC:2) has no coordinates for grch37"
+        );
+    }
+
+    #[test]
+    fn query_gene_requires_path_genes_rocksdb() {
+        let err = Args::try_parse_from([
+            "query",
+            "--path-rocksdb",
+            "tests/clinvar-sv/clinvar-sv-grch37.db",
+            "--hgnc-id",
+            "HGNC:1",
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    }
 }
@@ -0,0 +1,220 @@
+//! Interval index of the ClinVar SVs.
+//!
+//! Building the interval trees requires decoding all records, which takes about a minute per
+//! ClinVar release.  `clinvar-sv import` thus writes the intervals of each contig, sorted by
+//! start, to a dedicated column family keyed by contig name.  The trees are built from this
+//! index without touching the records; databases imported without it fall back to scanning all
+//! records.
+
+use std::ops::Range;
+
+use prost::Message as _;
+
+use crate::pbs::{
+    clinvar::sv::{ContigIndex, IndexInterval},
+    clinvar_data::{clinvar_public, extracted_vars::ExtractedVcvRecord},
+};
+
+/// Default name of the column family with the interval index.
+pub const CF_NAME: &str = "clinvar_sv_index";
+
+/// Return the contig name, the 0-based, half-open interval, and the VCV of `record`.
+///
+/// The interval is taken from the first of the start/stop, inner start/stop, and outer
+/// start/stop pairs that is present; `None` is returned if none is.
+pub fn record_interval(
+    record: &ExtractedVcvRecord,
+) -> Result<Option<(String, Range<u64>, String)>, anyhow::Error> {
+    let accession = record.accession.as_ref().expect("accession is required");
+    let vcv = format!("{}.{}", accession.accession, accession.version);
+    let clinvar_public::location::SequenceLocation {
+        chr,
+        start,
+        stop,
+        inner_start,
+        inner_stop,
+        outer_start,
+        outer_stop,
+        ..
+    } = record
+        .sequence_location
+        .as_ref()
+        .expect("sequence_location is required");
+    let chr_pb = clinvar_public::Chromosome::try_from(*chr).map_err(|e| {
+        anyhow::anyhow!("problem converting chromosome {} to Chromosome: {}", chr, e)
+    })?;
+
+    let (start, stop) = if let (Some(start), Some(stop)) = (start, stop) {
+        (start, stop)
+    } else if let (Some(inner_start), Some(inner_stop)) = (inner_start, inner_stop) {
+        (inner_start, inner_stop)
+    } else if let (Some(outer_start), Some(outer_stop)) = (outer_start, outer_stop) {
+        (outer_start, outer_stop)
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        chr_pb.as_chr_name(),
+        (*start as u64 - 1)..(*stop as u64),
+        vcv,
+    )))
+}
+
+/// Collects the intervals of the imported records for writing the index.
+///
+/// Records written more than once, e.g., when given in several input files, are indexed
+/// with the interval of the last one as that is the one in the database.
+#[derive(Debug, Default)]
+pub struct IndexBuilder {
+    /// Contig and interval by VCV.
+    by_vcv: rustc_hash::FxHashMap<String, (String, Range<u64>)>,
+}
+
+impl IndexBuilder {
+    /// Add the interval of `record`, if any.
+    pub fn insert(&mut self, record: &ExtractedVcvRecord) -> Result<(), anyhow::Error> {
+        if let Some((contig, interval, vcv)) = record_interval(record)? {
+            self.by_vcv.insert(vcv, (contig, interval));
+        }
+        Ok(())
+    }
+
+    /// Return the per-contig indices with sorted intervals.
+    pub fn build(self) -> std::collections::BTreeMap<String, ContigIndex> {
+        let mut result = std::collections::BTreeMap::<String, ContigIndex>::new();
+        for (vcv, (contig, interval)) in self.by_vcv {
+            result
+                .entry(contig)
+                .or_default()
+                .intervals
+                .push(IndexInterval {
+                    start: interval.start,
+                    stop: interval.end,
+                    vcv,
+                });
+        }
+        for index in result.values_mut() {
+            index
+                .intervals
+                .sort_by(|a, b| (a.start, a.stop, &a.vcv).cmp(&(b.start, b.stop, &b.vcv)));
+        }
+        result
+    }
+
+    /// Write the index to the column family `cf_name` and return the number of intervals.
+    pub fn write(
+        self,
+        db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+        cf_name: &str,
+    ) -> Result<usize, anyhow::Error> {
+        let cf_index = db
+            .cf_handle(cf_name)
+            .ok_or_else(|| anyhow::anyhow!("no column family with name {:?} found", cf_name))?;
+        let mut count = 0;
+        for (contig, index) in self.build() {
+            count += index.intervals.len();
+            db.put_cf(&cf_index, contig.as_bytes(), index.encode_to_vec())?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Return a record with the given accession and location.
+    fn record(
+        accession: &str,
+        chr: clinvar_public::Chromosome,
+        start: Option<u32>,
+        stop: Option<u32>,
+        outer: Option<(u32, u32)>,
+    ) -> ExtractedVcvRecord {
+        ExtractedVcvRecord {
+            accession: Some(
+                crate::pbs::clinvar_data::extracted_vars::VersionedAccession {
+                    accession: accession.into(),
+                    version: 1,
+                },
+            ),
+            sequence_location: Some(clinvar_public::location::SequenceLocation {
+                chr: chr as i32,
+                start,
+                stop,
+                outer_start: outer.map(|(start, _)| start),
+                outer_stop: outer.map(|(_, stop)| stop),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn record_interval_fallbacks() -> Result<(), anyhow::Error> {
+        use clinvar_public::Chromosome;
+
+        assert_eq!(
+            record_interval(&record(
+                "VCV000000001",
+                Chromosome::Chromosome22,
+                Some(100),
+                Some(200),
+                Some((50, 250))
+            ))?,
+            Some(("22".into(), 99..200, "VCV000000001.1".into()))
+        );
+        assert_eq!(
+            record_interval(&record(
+                "VCV000000002",
+                Chromosome::ChromosomeX,
+                None,
+                Some(200),
+                Some((50, 250))
+            ))?,
+            Some(("X".into(), 49..250, "VCV000000002.1".into()))
+        );
+        assert_eq!(
+            record_interval(&record(
+                "VCV000000003",
+                Chromosome::Chromosome1,
+                Some(100),
+                None,
+                None
+            ))?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn builder_sorts_and_deduplicates() -> Result<(), anyhow::Error> {
+        use clinvar_public::Chromosome;
+
+        let mut builder = IndexBuilder::default();
+        for record in [
+            record("VCV2", Chromosome::Chromosome1, Some(300), Some(400), None),
+            record("VCV1", Chromosome::Chromosome1, Some(100), Some(500), None),
+            record("VCV3", Chromosome::Chromosome2, Some(100), Some(200), None),
+            record("VCV2", Chromosome::Chromosome1, Some(50), Some(60), None),
+        ] {
+            builder.insert(&record)?;
+        }
+        let index = builder.build();
+
+        assert_eq!(index.keys().collect::<Vec<_>>(), vec!["1", "2"]);
+        assert_eq!(
+            index["1"]
+                .intervals
+                .iter()
+                .map(|interval| (interval.start, interval.stop, interval.vcv.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(49, 60, "VCV2.1"), (99, 500, "VCV1.1")]
+        );
+        assert_eq!(index["2"].intervals.len(), 1);
+
+        Ok(())
+    }
+}
@@ -3,7 +3,9 @@
 //! The implementation is as follows.
 //!
 //! - The structural variants are stored by their VCV identifier.
-//! - On startup, all variants are loaded and interval trees are built mapping
-//!   region to VCV identifier.
+//! - On import, the intervals of all variants are written to an index, see `index`.
+//! - On startup, interval trees mapping region to VCV identifier are built from the index or,
+//!   for databases without one, from all variants.
 
 pub mod cli;
+pub mod index;
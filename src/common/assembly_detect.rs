@@ -0,0 +1,262 @@
+//! Detection of the genome release of importer inputs.
+//!
+//! The importers accept `--genome-release auto` in which case the release is derived from the
+//! input file: from the contig lengths in VCF headers or, for TSV files, from the observed
+//! coordinates.  Coordinates can only rule out a release (position beyond the end of the
+//! chromosome), so detection fails with an ambiguity error if the data does not reach into the
+//! region where the chromosome lengths differ.
+
+use super::cli::{canonicalize, chrom_lengths, is_canonical, GenomeRelease};
+
+/// Genome release for importer command line arguments, including auto-detection.
+#[derive(
+    Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Debug, clap::ValueEnum, strum::Display,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum GenomeReleaseArg {
+    /// GRCh37 genome release.
+    #[default]
+    Grch37,
+    /// GRCh38 genome release.
+    Grch38,
+    /// Detect the genome release from the input file(s).
+    Auto,
+}
+
+impl From<GenomeRelease> for GenomeReleaseArg {
+    fn from(val: GenomeRelease) -> Self {
+        match val {
+            GenomeRelease::Grch37 => GenomeReleaseArg::Grch37,
+            GenomeRelease::Grch38 => GenomeReleaseArg::Grch38,
+        }
+    }
+}
+
+impl GenomeReleaseArg {
+    /// Return the explicitly given release or run `detect` for `auto`.
+    ///
+    /// The resolved release is logged in both cases.
+    pub fn resolve<F>(self, detect: F) -> Result<GenomeRelease, anyhow::Error>
+    where
+        F: FnOnce() -> Result<GenomeRelease, anyhow::Error>,
+    {
+        let result = match self {
+            GenomeReleaseArg::Grch37 => GenomeRelease::Grch37,
+            GenomeReleaseArg::Grch38 => GenomeRelease::Grch38,
+            GenomeReleaseArg::Auto => {
+                let result = detect().map_err(|e| {
+                    anyhow::anyhow!(
+                        "could not detect genome release, please pass --genome-release \
+                        grch37 or grch38: {}",
+                        e
+                    )
+                })?;
+                tracing::info!("  detected genome release: {}", result);
+                return Ok(result);
+            }
+        };
+        tracing::info!("  using genome release: {}", result);
+        Ok(result)
+    }
+}
+
+/// The releases that can be detected.
+const RELEASES: &[GenomeRelease] = &[GenomeRelease::Grch37, GenomeRelease::Grch38];
+
+/// Turn the list of remaining candidates into a result.
+fn pick(candidates: &[GenomeRelease], what: &str) -> Result<GenomeRelease, anyhow::Error> {
+    match candidates {
+        [release] => Ok(*release),
+        [] => anyhow::bail!("{} match neither GRCh37 nor GRCh38", what),
+        _ => anyhow::bail!("{} are compatible with both GRCh37 and GRCh38", what),
+    }
+}
+
+/// Detect the genome release from `(name, length)` pairs of contigs.
+///
+/// Non-canonical contigs are ignored.  A release matches if at least one canonical contig is
+/// present and all of them have the length from the release.
+pub fn from_contig_lengths<'a, I>(contigs: I) -> Result<GenomeRelease, anyhow::Error>
+where
+    I: IntoIterator<Item = (&'a str, usize)>,
+{
+    let contigs = contigs
+        .into_iter()
+        .filter(|(name, _)| is_canonical(name))
+        .map(|(name, length)| (canonicalize(name), length))
+        .collect::<Vec<_>>();
+    if contigs.is_empty() {
+        anyhow::bail!("no canonical contigs with lengths found");
+    }
+
+    let candidates = RELEASES
+        .iter()
+        .copied()
+        .filter(|release| {
+            let lengths = chrom_lengths(*release);
+            contigs
+                .iter()
+                .all(|(name, length)| lengths.get(name) == Some(length))
+        })
+        .collect::<Vec<_>>();
+    pick(&candidates, "contig lengths")
+}
+
+/// Detect the genome release from the `##contig` lines of a VCF header.
+pub fn from_vcf_header(header: &noodles::vcf::Header) -> Result<GenomeRelease, anyhow::Error> {
+    let contigs = header
+        .contigs()
+        .iter()
+        .filter(|(name, _)| is_canonical(name))
+        .map(|(name, contig)| {
+            contig
+                .length()
+                .map(|length| (name.as_str(), length))
+                .ok_or_else(|| anyhow::anyhow!("no length in VCF header for contig {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    from_contig_lengths(contigs)
+}
+
+/// Detect the genome release from the header of the VCF file at `path`.
+pub fn from_vcf_path(path: &str) -> Result<GenomeRelease, anyhow::Error> {
+    let header = noodles::vcf::io::reader::Builder::default()
+        .build_from_path(path)
+        .and_then(|mut reader| reader.read_header())
+        .map_err(|e| anyhow::anyhow!("could not read VCF header of {}: {}", path, e))?;
+    from_vcf_header(&header)
+        .map_err(|e| anyhow::anyhow!("could not detect genome release of {}: {}", path, e))
+}
+
+/// Detect the genome release from the VCF headers of all files in `paths`.
+///
+/// All files must agree on the release.
+pub fn from_vcf_paths<S: AsRef<str>>(paths: &[S]) -> Result<GenomeRelease, anyhow::Error> {
+    let mut result = None;
+    for path in paths {
+        let release = from_vcf_path(path.as_ref())?;
+        match result {
+            Some(previous) if previous != release => anyhow::bail!(
+                "{} looks like {} but previous files look like {}",
+                path.as_ref(),
+                release,
+                previous
+            ),
+            _ => result = Some(release),
+        }
+    }
+    result.ok_or_else(|| anyhow::anyhow!("no input files given"))
+}
+
+/// Detect the genome release from `(chrom, pos)` coordinates.
+///
+/// A release is ruled out as soon as a position lies beyond the end of its chromosome.  The
+/// iterator is only consumed until one candidate release is left.
+pub fn from_positions<I>(positions: I) -> Result<GenomeRelease, anyhow::Error>
+where
+    I: IntoIterator<Item = Result<(String, usize), anyhow::Error>>,
+{
+    let lengths = RELEASES
+        .iter()
+        .map(|release| (*release, chrom_lengths(*release)))
+        .collect::<Vec<_>>();
+    let mut candidates = RELEASES.to_vec();
+
+    for position in positions {
+        let (chrom, pos) = position?;
+        if !is_canonical(&chrom) {
+            continue;
+        }
+        let chrom = canonicalize(&chrom);
+        candidates.retain(|release| {
+            lengths
+                .iter()
+                .find(|(other, _)| other == release)
+                .and_then(|(_, lengths)| lengths.get(&chrom))
+                .map(|length| pos <= *length)
+                .unwrap_or(true)
+        });
+        if candidates.len() <= 1 {
+            break;
+        }
+    }
+
+    pick(&candidates, "positions")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(
+        "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz",
+        GenomeRelease::Grch37
+    )]
+    #[case(
+        "tests/gnomad-nuclear/example-exomes-grch38/v2.1/gnomad-exomes.vcf.bgz",
+        GenomeRelease::Grch38
+    )]
+    #[case(
+        "tests/gnomad-nuclear/example-exomes-grch38/v4.0/gnomad-exomes.vcf.bgz",
+        GenomeRelease::Grch38
+    )]
+    fn detect_from_vcf_fixtures(#[case] path: &str, #[case] expected: GenomeRelease) {
+        assert_eq!(from_vcf_path(path).unwrap(), expected);
+    }
+
+    #[test]
+    fn detect_from_mixed_vcf_fixtures_fails() {
+        let paths = [
+            "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz",
+            "tests/gnomad-nuclear/example-exomes-grch38/v2.1/gnomad-exomes.vcf.bgz",
+        ];
+        assert!(from_vcf_paths(&paths).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case(&[("1", 249_250_621)], Some(GenomeRelease::Grch37))]
+    #[case(&[("chr1", 248_956_422)], Some(GenomeRelease::Grch38))]
+    #[case(&[("chr1", 248_956_422), ("chr2", 243_199_373)], None)]
+    #[case(&[("MT", 16_569)], None)]
+    #[case(&[("GL000192.1", 547_496)], None)]
+    fn detect_from_contig_lengths(
+        #[case] contigs: &[(&str, usize)],
+        #[case] expected: Option<GenomeRelease>,
+    ) {
+        let result = from_contig_lengths(contigs.iter().copied());
+        assert_eq!(result.ok(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(&[("1", 249_000_000)], Some(GenomeRelease::Grch37))]
+    #[case(&[("chr3", 198_100_000)], Some(GenomeRelease::Grch38))]
+    #[case(&[("1", 1_000), ("2", 2_000)], None)]
+    fn detect_from_positions(
+        #[case] positions: &[(&str, usize)],
+        #[case] expected: Option<GenomeRelease>,
+    ) {
+        let result = from_positions(
+            positions
+                .iter()
+                .map(|(chrom, pos)| Ok((chrom.to_string(), *pos))),
+        );
+        assert_eq!(result.ok(), expected);
+    }
+
+    #[test]
+    fn resolve_explicit_does_not_detect() {
+        let result = GenomeReleaseArg::Grch38
+            .resolve(|| panic!("must not be called"))
+            .unwrap();
+        assert_eq!(result, GenomeRelease::Grch38);
+    }
+
+    #[test]
+    fn resolve_auto_reports_ambiguity() {
+        let err = GenomeReleaseArg::Auto
+            .resolve(|| from_contig_lengths([("MT", 16_569)]))
+            .unwrap_err();
+        assert!(err.to_string().contains("--genome-release"));
+    }
+}
@@ -0,0 +1,296 @@
+//! Batch queries from a JSONL file with `--path-input-jsonl`.
+//!
+//! Instead of one `--variant`, `--position`, etc., the query commands accept a file (or `-`
+//! for stdin) with one query per line, given as a JSON object with a single field named like
+//! the command line argument:
+//!
+//! ```text
+//! {"variant": "GRCh37:17:41267746:C:CA"}
+//! {"range": "GRCh37:17:41267740:41267750"}
+//! ```
+//!
+//! All queries run on the database opened once.  The records are written in input order,
+//! each with the 0-based line number of its query as `input_index` (see
+//! `output::RecordWriter::set_input_index`).  Queries that cannot be parsed or fail are
+//! written as `{"input_index": 2, "error": "..."}` in place of their records, unless
+//! `--strict` is given, in which case the first failing query aborts the command.  Empty lines
+//! are skipped.
+
+use std::io::BufRead;
+
+use super::output::RecordWriter;
+use crate::cons::cli::args::vars::ArgsQuery;
+
+/// One line of the input, exactly one of the fields must be given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Line {
+    /// Variant to query for, as for `--variant`.
+    pub variant: Option<String>,
+    /// Position to query for, as for `--position`.
+    pub position: Option<String>,
+    /// Range to query for, as for `--range`.
+    pub range: Option<String>,
+    /// Accession to query for, as for `--accession`.
+    pub accession: Option<String>,
+    /// Gene to query for, as for `--gene`.
+    pub gene: Option<String>,
+}
+
+impl Line {
+    /// Return the name and value of the single query of the line.
+    pub fn single(self) -> Result<(&'static str, String), anyhow::Error> {
+        let mut fields = [
+            ("variant", self.variant),
+            ("position", self.position),
+            ("range", self.range),
+            ("accession", self.accession),
+            ("gene", self.gene),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect::<Vec<_>>();
+        match fields.len() {
+            0 => Err(crate::ErrorCategory::Usage.error(
+                "no query given, expected one of variant, position, range, accession, or gene",
+            )),
+            1 => Ok(fields.pop().expect("checked length")),
+            _ => Err(crate::ErrorCategory::Usage.error(format!(
+                "more than one query given: {}",
+                fields
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
+
+impl TryFrom<Line> for ArgsQuery {
+    type Error = anyhow::Error;
+
+    fn try_from(line: Line) -> Result<Self, Self::Error> {
+        let (name, value) = line.single()?;
+        let parse_error = |e: anyhow::Error| crate::ErrorCategory::Usage.wrap(e);
+        Ok(match name {
+            "variant" => ArgsQuery {
+                variant: Some(value.parse().map_err(parse_error)?),
+                ..Default::default()
+            },
+            "position" => ArgsQuery {
+                position: Some(value.parse().map_err(parse_error)?),
+                ..Default::default()
+            },
+            "range" => ArgsQuery {
+                range: Some(value.parse().map_err(parse_error)?),
+                ..Default::default()
+            },
+            "accession" => ArgsQuery {
+                accession: Some(value),
+                ..Default::default()
+            },
+            _ => {
+                return Err(crate::ErrorCategory::Usage
+                    .error(format!("{} queries are not supported", name)))
+            }
+        })
+    }
+}
+
+/// Fail for `--path-input-jsonl` with the sub commands `command` that do not support it.
+pub fn check_unsupported(query: &ArgsQuery, command: &str) -> Result<(), anyhow::Error> {
+    if query.path_input_jsonl.is_some() {
+        Err(crate::ErrorCategory::Usage.error(format!(
+            "--path-input-jsonl is not supported by `{}`",
+            command
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Open `path_input` for reading, stdin for `"-"`.
+pub fn open_input(path_input: &str) -> Result<Box<dyn BufRead>, anyhow::Error> {
+    Ok(match path_input {
+        "-" => Box::new(std::io::stdin().lock()) as Box<dyn BufRead>,
+        path_input => Box::new(std::io::BufReader::new(
+            std::fs::File::open(path_input).map_err(|e| {
+                anyhow::Error::from(e).context(format!("could not open input file {}", path_input))
+            })?,
+        )) as Box<dyn BufRead>,
+    })
+}
+
+/// Run the queries from the lines of `input` and write their records to `out_writer`.
+///
+/// `run_query` runs a single query and writes its records.  Failing queries are written as
+/// errors, or returned if `strict` is given.
+pub fn run<Q, F>(
+    input: impl BufRead,
+    strict: bool,
+    out_writer: &mut RecordWriter,
+    mut run_query: F,
+) -> Result<(), anyhow::Error>
+where
+    Q: TryFrom<Line, Error = anyhow::Error>,
+    F: FnMut(&Q, &mut RecordWriter) -> Result<(), anyhow::Error>,
+{
+    let mut count = 0;
+    for (input_index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        count += 1;
+
+        out_writer.set_input_index(Some(input_index));
+        let result = serde_json::from_str::<Line>(&line)
+            .map_err(|e| crate::ErrorCategory::InputParse.wrap(e))
+            .and_then(Q::try_from)
+            .and_then(|query| run_query(&query, out_writer));
+        if let Err(e) = result {
+            if strict {
+                return Err(e.context(format!("problem with query in line {}", input_index + 1)));
+            }
+            tracing::debug!("problem with query in line {}: {}", input_index + 1, e);
+            out_writer.write_error(&e.to_string())?;
+        }
+    }
+    out_writer.set_input_index(None);
+    tracing::info!("ran {} queries", count);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use super::*;
+    use crate::common::{cli::OutputFormat, spdi};
+
+    #[rstest::rstest]
+    #[case::variant(
+        "{\"variant\": \"GRCh37:17:41267746:C:CA\"}",
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:17:41267746:C:CA").unwrap()),
+            ..Default::default()
+        }
+    )]
+    #[case::position(
+        "{\"position\": \"17:41267746\"}",
+        ArgsQuery {
+            position: Some(spdi::Pos::from_str("17:41267746").unwrap()),
+            ..Default::default()
+        }
+    )]
+    #[case::range(
+        "{\"range\": \"17:1:100\"}",
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("17:1:100").unwrap()),
+            ..Default::default()
+        }
+    )]
+    #[case::accession(
+        "{\"accession\": \"rs1\"}",
+        ArgsQuery {
+            accession: Some(String::from("rs1")),
+            ..Default::default()
+        }
+    )]
+    fn args_query_from_line(
+        #[case] line: &str,
+        #[case] expected: ArgsQuery,
+    ) -> Result<(), anyhow::Error> {
+        let query = ArgsQuery::try_from(serde_json::from_str::<Line>(line)?)?;
+
+        assert_eq!(format!("{:?}", query), format!("{:?}", expected));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::empty("{}", "no query given")]
+    #[case::two(
+        "{\"variant\": \"1:1:A:C\", \"range\": \"1:1:2\"}",
+        "more than one query"
+    )]
+    #[case::gene("{\"gene\": \"BRCA1\"}", "gene queries are not supported")]
+    #[case::invalid_variant(
+        "{\"variant\": \"1:1\"}",
+        "expected SEQUENCE:POSITION:DELETION:INSERTION"
+    )]
+    fn args_query_from_line_invalid(#[case] line: &str, #[case] expected: &str) {
+        let err = ArgsQuery::try_from(serde_json::from_str::<Line>(line).unwrap()).unwrap_err();
+
+        assert!(err.to_string().contains(expected), "{}", err);
+        assert_eq!(crate::ErrorCategory::of(&err), crate::ErrorCategory::Usage);
+    }
+
+    #[test]
+    fn line_unknown_field() {
+        assert!(serde_json::from_str::<Line>("{\"chrom\": \"1\"}").is_err());
+    }
+
+    /// Run the queries of `input`, writing each variant query's position, and return the output.
+    fn run_input(input: &str, strict: bool) -> Result<String, anyhow::Error> {
+        let temp = temp_testdir::TempDir::default();
+        let out_file = temp.join("out").to_string_lossy().to_string();
+        let mut out_writer = RecordWriter::new(&out_file, OutputFormat::Jsonl, "example", None)?;
+        run(
+            input.as_bytes(),
+            strict,
+            &mut out_writer,
+            |query: &ArgsQuery, out_writer| {
+                let variant = query
+                    .variant
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("not a variant"))?;
+                out_writer.write("", &serde_json::json!({"pos": variant.position}))
+            },
+        )?;
+        drop(out_writer);
+        Ok(std::fs::read_to_string(&out_file)?)
+    }
+
+    #[test]
+    fn run_inline_errors() -> Result<(), anyhow::Error> {
+        let out_data = run_input(
+            "{\"variant\": \"1:100:A:C\"}\n\
+            \n\
+            {\"position\": \"1:100\"}\n\
+            not json\n\
+            {\"variant\": \"1:200:A:C\"}\n",
+            false,
+        )?;
+
+        let lines = out_data
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], serde_json::json!({"input_index": 0, "pos": 100}));
+        assert_eq!(
+            lines[1],
+            serde_json::json!({"input_index": 2, "error": "not a variant"})
+        );
+        assert_eq!(lines[2]["input_index"], 3);
+        assert!(lines[2]["error"].is_string());
+        assert_eq!(lines[3], serde_json::json!({"input_index": 4, "pos": 200}));
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_strict() {
+        let err = run_input(
+            "{\"variant\": \"1:100:A:C\"}\n{\"position\": \"1:100\"}\n",
+            true,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "problem with query in line 2");
+        assert_eq!(err.root_cause().to_string(), "not a variant");
+    }
+}
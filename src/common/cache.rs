@@ -0,0 +1,293 @@
+//! On-disk memoization of expensive results, e.g., of `report gene-panel`.
+//!
+//! The entries are JSON files in the directory given with `--cache-dir`, named by a hash of
+//! the inputs of the computation.  Each entry embeds the inputs and the `meta` column family
+//! of each database the result was computed from.  Both are compared on lookup, so entries
+//! for swapped or re-imported databases are recomputed without having to clear the cache.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Contents of the `meta` column family of each database, by name of the database.
+pub type DbVersions = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Read the entries of the `meta` column family of `db` as strings.
+pub fn db_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<BTreeMap<String, String>, anyhow::Error> {
+    let mut result = BTreeMap::new();
+    let cf_meta = db
+        .cf_handle("meta")
+        .ok_or_else(|| crate::ErrorCategory::Database.error("no 'meta' column family"))?;
+    let mut iter = db.raw_iterator_cf(&cf_meta);
+    iter.seek(b"");
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        result.insert(
+            String::from_utf8_lossy(key).to_string(),
+            String::from_utf8_lossy(value).to_string(),
+        );
+        iter.next();
+    }
+    iter.status()?;
+    Ok(result)
+}
+
+/// One entry of the cache as written to disk.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct Entry<T> {
+    /// The inputs of the computation.
+    inputs: serde_json::Value,
+    /// The `meta` column families of the databases at the time of the computation.
+    db_versions: DbVersions,
+    /// The result of the computation.
+    value: T,
+}
+
+/// Result of `ResultCache::get`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup<T> {
+    /// The entry exists and was computed from the same databases.
+    Hit(T),
+    /// There is no entry for the inputs.
+    Miss,
+    /// The entry was computed from other databases.
+    Stale,
+}
+
+/// Cache of results in a directory.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    /// Path to the cache directory.
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    /// Create a cache in `dir`, which is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Return the path of the entry for `inputs`.
+    fn path(&self, inputs: &serde_json::Value) -> PathBuf {
+        self.dir.join(format!(
+            "{:016x}.json",
+            fnv1a(inputs.to_string().as_bytes())
+        ))
+    }
+
+    /// Look up the result for `inputs` computed from databases with `db_versions`.
+    ///
+    /// Unreadable entries are treated as missing.
+    pub fn get<T>(
+        &self,
+        inputs: &impl serde::Serialize,
+        db_versions: &DbVersions,
+    ) -> Result<Lookup<T>, anyhow::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let inputs = serde_json::to_value(inputs)?;
+        let path = self.path(&inputs);
+        let Ok(contents) = std::fs::read(&path) else {
+            return Ok(Lookup::Miss);
+        };
+        let entry: Entry<T> = match serde_json::from_slice(&contents) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("ignoring invalid cache entry {}: {}", path.display(), e);
+                return Ok(Lookup::Miss);
+            }
+        };
+        Ok(if entry.inputs != inputs {
+            // Hash collision.
+            Lookup::Miss
+        } else if &entry.db_versions != db_versions {
+            Lookup::Stale
+        } else {
+            Lookup::Hit(entry.value)
+        })
+    }
+
+    /// Write `value` as the result for `inputs` computed from databases with `db_versions`.
+    pub fn put<T>(
+        &self,
+        inputs: &impl serde::Serialize,
+        db_versions: &DbVersions,
+        value: &T,
+    ) -> Result<(), anyhow::Error>
+    where
+        T: serde::Serialize,
+    {
+        let inputs = serde_json::to_value(inputs)?;
+        let path = self.path(&inputs);
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            anyhow::anyhow!(
+                "problem creating cache directory {}: {}",
+                self.dir.display(),
+                e
+            )
+        })?;
+        // Write to a temporary file first so concurrent readers never see partial entries.
+        let tmp_path = path.with_extension(format!("json.tmp-{}", std::process::id()));
+        std::fs::write(
+            &tmp_path,
+            serde_json::to_vec(&Entry {
+                inputs,
+                db_versions: db_versions.clone(),
+                value,
+            })?,
+        )
+        .and_then(|()| std::fs::rename(&tmp_path, &path))
+        .map_err(|e| anyhow::anyhow!("problem writing cache entry {}: {}", path.display(), e))
+    }
+
+    /// Return the cached result for `inputs` and `db_versions` or compute and cache it.
+    pub fn get_or_compute<T, F>(
+        &self,
+        inputs: &impl serde::Serialize,
+        db_versions: &DbVersions,
+        compute: F,
+    ) -> Result<T, anyhow::Error>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        F: FnOnce() -> Result<T, anyhow::Error>,
+    {
+        match self.get(inputs, db_versions)? {
+            Lookup::Hit(value) => {
+                tracing::info!("  using cached result from {}", self.dir.display());
+                return Ok(value);
+            }
+            Lookup::Miss => (),
+            Lookup::Stale => {
+                tracing::info!("  cached result is for other database versions, recomputing")
+            }
+        }
+        let value = compute()?;
+        self.put(inputs, db_versions, &value)?;
+        Ok(value)
+    }
+}
+
+/// Return the 64 bit FNV-1a hash of `data`, which is stable across builds and platforms.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    /// Return `DbVersions` for one database `genes` with `db-version` set to `version`.
+    fn db_versions(version: &str) -> DbVersions {
+        DbVersions::from([(
+            String::from("genes"),
+            BTreeMap::from([(String::from("db-version"), String::from(version))]),
+        )])
+    }
+
+    #[test]
+    fn fnv1a_known_values() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn get_or_compute_hit_miss_stale() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let cache = ResultCache::new(tmp_dir.join("cache"));
+        let inputs = serde_json::json!({"command": "test", "genes": ["HGNC:1100"]});
+        let computed = std::cell::Cell::new(0);
+        let compute = || {
+            computed.set(computed.get() + 1);
+            Ok(vec![computed.get()])
+        };
+
+        // Miss: computed and written.
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("1"))?,
+            Lookup::Miss
+        );
+        assert_eq!(
+            cache.get_or_compute(&inputs, &db_versions("1"), compute)?,
+            vec![1]
+        );
+        // Hit: not computed again.
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("1"))?,
+            Lookup::Hit(vec![1])
+        );
+        assert_eq!(
+            cache.get_or_compute(&inputs, &db_versions("1"), compute)?,
+            vec![1]
+        );
+        assert_eq!(computed.get(), 1);
+        // Other inputs: miss.
+        assert_eq!(
+            cache.get::<Vec<i32>>(&serde_json::json!({"command": "other"}), &db_versions("1"))?,
+            Lookup::Miss
+        );
+        // Swapped database: stale, recomputed and overwritten.
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("2"))?,
+            Lookup::Stale
+        );
+        assert_eq!(
+            cache.get_or_compute(&inputs, &db_versions("2"), compute)?,
+            vec![2]
+        );
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("2"))?,
+            Lookup::Hit(vec![2])
+        );
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("1"))?,
+            Lookup::Stale
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_invalid_entry() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let cache = ResultCache::new(tmp_dir.join("cache"));
+        let inputs = serde_json::json!({"command": "test"});
+        cache.put(&inputs, &db_versions("1"), &vec![1])?;
+        std::fs::write(cache.path(&serde_json::to_value(&inputs)?), "{")?;
+
+        assert_eq!(
+            cache.get::<Vec<i32>>(&inputs, &db_versions("1"))?,
+            Lookup::Miss
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn db_meta_entries() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, tmp_dir.join("db"), ["meta", "data"])?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        db.put_cf(&cf_meta, "db-version", "1.0")?;
+        db.put_cf(&cf_meta, "genome-release", "grch37")?;
+        drop(cf_meta);
+
+        assert_eq!(
+            db_meta(&db)?,
+            BTreeMap::from([
+                (String::from("db-version"), String::from("1.0")),
+                (String::from("genome-release"), String::from("grch37")),
+            ])
+        );
+
+        Ok(())
+    }
+}
@@ -1,11 +1,45 @@
 //! Common CLI code.
 
+use std::{
+    io::IsTerminal as _,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
 /// Commonly used command line arguments.
 #[derive(clap::Parser, Debug, Clone)]
 pub struct Args {
-    /// Verbosity of the program
+    /// Verbosity of the program; `--quiet` keeps only warnings and errors and disables
+    /// progress bars
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity<clap_verbosity_flag::InfoLevel>,
+    /// Disable progress bars; they are also disabled if stderr is not a terminal
+    #[arg(long, global = true)]
+    pub no_progress: bool,
+}
+
+impl Args {
+    /// Return whether progress bars are requested, i.e., neither `--no-progress` nor
+    /// `--quiet` was given.
+    pub fn progress(&self) -> bool {
+        !self.no_progress
+            && self
+                .verbose
+                .log_level()
+                .is_some_and(|level| level >= log::Level::Info)
+    }
+}
+
+/// Whether progress bars are enabled, see `init_progress()`.
+static PROGRESS: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable the progress bars of `progress_bar()` according to `args`.
+pub fn init_progress(args: &Args) {
+    PROGRESS.store(args.progress(), Ordering::Relaxed);
+}
+
+/// Return whether progress bars are drawn, i.e., they are enabled and stderr is a terminal.
+pub fn progress_enabled() -> bool {
+    PROGRESS.load(Ordering::Relaxed) && std::io::stderr().is_terminal()
 }
 
 /// Output format to write.
@@ -17,6 +51,74 @@ pub enum OutputFormat {
     /// JSONL format.
     #[default]
     Jsonl,
+    /// JSONL format with each record in a versioned envelope, see `common::output`.
+    #[value(name = "jsonl-v2")]
+    #[strum(serialize = "jsonl-v2")]
+    JsonlV2,
+    /// YAML format with one document per record.
+    Yaml,
+}
+
+/// Command line arguments for writing only part of the records of a query, e.g., to look at
+/// a sample of a large range.
+#[derive(clap::Args, Debug, Clone, Copy, Default)]
+pub struct ArgsLimit {
+    /// Write at most this many records.
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Skip this many records before writing.
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+}
+
+impl ArgsLimit {
+    /// Return a counter for applying the limit and offset to the records of one query.
+    pub fn counter(&self) -> LimitCounter {
+        LimitCounter {
+            limit: self.limit,
+            to_skip: self.offset,
+            written: 0,
+        }
+    }
+}
+
+/// Counter of the records of one query for `ArgsLimit`.
+///
+/// Queries check `is_done()` before reading the next record from the database, so the work
+/// is bounded by offset and limit, and call `admit()` before decoding the record.
+#[derive(Debug, Clone)]
+pub struct LimitCounter {
+    /// Maximal number of records to write.
+    limit: Option<usize>,
+    /// Number of records still to skip.
+    to_skip: usize,
+    /// Number of records written so far.
+    written: usize,
+}
+
+impl LimitCounter {
+    /// Return whether the limit is reached, i.e., no more records are to be read.
+    pub fn is_done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.written >= limit)
+    }
+
+    /// Count the next record and return whether it is to be written rather than skipped.
+    pub fn admit(&mut self) -> bool {
+        if self.is_done() {
+            false
+        } else if self.to_skip > 0 {
+            self.to_skip -= 1;
+            false
+        } else {
+            self.written += 1;
+            true
+        }
+    }
+
+    /// Return the number of records written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
 }
 
 /// Local genome release for command line arguments.
@@ -56,6 +158,16 @@ impl From<GenomeRelease> for biocommons_bioutils::assemblies::Assembly {
     }
 }
 
+impl GenomeRelease {
+    /// Return the display name of the release, e.g., `GRCh37`.
+    pub fn name(self) -> &'static str {
+        match self {
+            GenomeRelease::Grch37 => "GRCh37",
+            GenomeRelease::Grch38 => "GRCh38",
+        }
+    }
+}
+
 /// Construct the `indicatif` style for progress bars.
 pub fn indicatif_style() -> indicatif::ProgressStyle {
     let tpl = "{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] \
@@ -73,11 +185,13 @@ pub fn indicatif_style() -> indicatif::ProgressStyle {
 
 /// Construct an `indicatif` progress bar with the common style.
 ///
-/// Also, we will enable a steady tick every 0.1s and hide in tests.
-pub fn progress_bar(#[allow(unused_variables)] len: usize) -> indicatif::ProgressBar {
-    #[cfg(test)]
-    let pb = indicatif::ProgressBar::hidden();
-    #[cfg(not(test))]
+/// Also, we will enable a steady tick every 0.1s.  The bar is hidden in tests and if
+/// progress bars are disabled, see `progress_enabled()`.  All commands must use this
+/// function rather than constructing their own bars.
+pub fn progress_bar(len: usize) -> indicatif::ProgressBar {
+    if cfg!(test) || !progress_enabled() {
+        return indicatif::ProgressBar::hidden();
+    }
     let pb = indicatif::ProgressBar::new(len as u64).with_style(indicatif_style());
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
     pb
@@ -109,6 +223,94 @@ pub fn is_canonical(chrom: &str) -> bool {
     CANONICAL.contains(&chrom)
 }
 
+/// Return the lengths of the canonical chromosomes of `release`, keyed by canonical name.
+pub fn chrom_lengths(release: GenomeRelease) -> std::collections::HashMap<String, usize> {
+    biocommons_bioutils::assemblies::ASSEMBLY_INFOS
+        [biocommons_bioutils::assemblies::Assembly::from(release)]
+    .sequences
+    .iter()
+    .filter(|seq| is_canonical(&seq.name))
+    .map(|seq| (canonicalize(&seq.name), seq.length))
+    .collect()
+}
+
+/// Return the length of chromosome `chrom` in `release` or `None` if it is not canonical.
+pub fn chrom_length(release: GenomeRelease, chrom: &str) -> Option<usize> {
+    let chrom = canonicalize(chrom);
+    biocommons_bioutils::assemblies::ASSEMBLY_INFOS
+        [biocommons_bioutils::assemblies::Assembly::from(release)]
+    .sequences
+    .iter()
+    .find(|seq| is_canonical(&seq.name) && canonicalize(&seq.name) == chrom)
+    .map(|seq| seq.length)
+}
+
+/// Format `value` with commas as thousands separators, e.g., `248,956,422`.
+fn with_thousands_separators(value: usize) -> String {
+    let digits = value.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Condition of a 1-based position beyond the end of its chromosome.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "position {} exceeds length of chr{} ({}) for {}",
+    with_thousands_separators(*.position),
+    .chrom,
+    with_thousands_separators(*.length),
+    .genome_release.name()
+)]
+pub struct PositionOutOfBounds {
+    /// The offending position.
+    pub position: usize,
+    /// Canonical name of the chromosome, e.g., `1`.
+    pub chrom: String,
+    /// Length of the chromosome.
+    pub length: usize,
+    /// The genome release of the chromosome.
+    pub genome_release: GenomeRelease,
+}
+
+/// Check that the 1-based `position` is within chromosome `chrom` of `release`.
+///
+/// Positions on non-canonical chromosomes are not checked.
+pub fn check_position(
+    release: GenomeRelease,
+    chrom: &str,
+    position: usize,
+) -> Result<(), PositionOutOfBounds> {
+    match chrom_length(release, chrom) {
+        Some(length) if position > length => Err(PositionOutOfBounds {
+            position,
+            chrom: canonicalize(chrom),
+            length,
+            genome_release: release,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Clamp the 1-based `stop` of a range to the length of chromosome `chrom` of `release`.
+///
+/// Returns the clamped stop and, if it was clamped, the condition for a warning.
+pub fn clamp_stop(
+    release: GenomeRelease,
+    chrom: &str,
+    stop: usize,
+) -> (usize, Option<PositionOutOfBounds>) {
+    match check_position(release, chrom, stop) {
+        Ok(()) => (stop, None),
+        Err(out_of_bounds) => (out_of_bounds.length, Some(out_of_bounds)),
+    }
+}
+
 /// Build windows for a given assembly.
 pub fn build_genome_windows(
     assembly: biocommons_bioutils::assemblies::Assembly,
@@ -135,10 +337,71 @@ pub fn build_genome_windows(
     Ok(result)
 }
 
+/// Return whether the tabix `index` has data in the 0-based, half-open window `begin..end`
+/// of the reference sequence with index `ref_id`.
+///
+/// Only the in-memory bins and linear index are consulted.  Windows without chunks are
+/// guaranteed to be empty, windows with chunks may still turn out to have no records.
+pub fn tbi_window_has_data(
+    index: &noodles::tabix::Index,
+    ref_id: usize,
+    begin: usize,
+    end: usize,
+) -> Result<bool, anyhow::Error> {
+    use noodles::csi::BinningIndex as _;
+
+    let start = noodles::core::Position::try_from(begin + 1)?;
+    let stop = noodles::core::Position::try_from(std::cmp::max(begin + 1, end))?;
+    Ok(!index.query(ref_id, start..=stop)?.is_empty())
+}
+
+/// Remove the windows for which `has_data` returns `false` and log the number of skipped
+/// windows.
+pub fn prune_genome_windows<T, F>(windows: Vec<T>, mut has_data: F) -> Result<Vec<T>, anyhow::Error>
+where
+    F: FnMut(&T) -> Result<bool, anyhow::Error>,
+{
+    let total = windows.len();
+    let mut result = Vec::with_capacity(total);
+    for window in windows {
+        if has_data(&window)? {
+            result.push(window);
+        }
+    }
+    tracing::info!(
+        "  skipping {} of {} windows without data in the index",
+        total - result.len(),
+        total
+    );
+    Ok(result)
+}
+
 /// Helpers to extract chromosome name from `<release>:<chrom>` string.
 pub mod extract_chrom {
     use crate::common::spdi;
 
+    /// Parse the expected genome release, `None` if not given or not a known release.
+    fn parse_genome_release(expected_genome_release: Option<&str>) -> Option<super::GenomeRelease> {
+        expected_genome_release
+            .and_then(|genome_release| genome_release.to_lowercase().parse().ok())
+    }
+
+    /// Check the 1-based `position` on `chrom` if `expected_genome_release` is a known release.
+    fn check_position(
+        chrom: &str,
+        position: i32,
+        expected_genome_release: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(genome_release) = parse_genome_release(expected_genome_release) {
+            super::check_position(
+                genome_release,
+                chrom,
+                usize::try_from(position).unwrap_or_default(),
+            )?;
+        }
+        Ok(())
+    }
+
     /// Get chromosome from the SPDI variant.
     ///
     /// If the optional genome release was given then it is compared to the one specified
@@ -147,24 +410,25 @@ pub mod extract_chrom {
         variant: &spdi::Var,
         expected_genome_release: Option<&str>,
     ) -> Result<String, anyhow::Error> {
-        if variant.sequence.contains(':') {
+        let chrom = if variant.sequence.contains(':') {
             let mut iter = variant.sequence.rsplitn(2, ':');
             let chromosome = iter.next().unwrap();
             if let Some(genome_release) = iter.next() {
                 if let Some(expected_genome_release) = expected_genome_release {
                     if genome_release.to_lowercase() != expected_genome_release.to_lowercase() {
-                        return Err(anyhow::anyhow!(
+                        return Err(crate::ErrorCategory::Usage.error(format!(
                             "genome release mismatch (lowercase): expected {}, got {}",
-                            expected_genome_release,
-                            genome_release
-                        ));
+                            expected_genome_release, genome_release
+                        )));
                     }
                 }
             }
-            Ok(super::canonicalize(chromosome))
+            super::canonicalize(chromosome)
         } else {
-            Ok(super::canonicalize(&variant.sequence))
-        }
+            super::canonicalize(&variant.sequence)
+        };
+        check_position(&chrom, variant.position, expected_genome_release)?;
+        Ok(chrom)
     }
 
     /// Get chromosome from the SPDI position.
@@ -174,24 +438,25 @@ pub mod extract_chrom {
         pos: &spdi::Pos,
         expected_genome_release: Option<&str>,
     ) -> Result<String, anyhow::Error> {
-        if pos.sequence.contains(':') {
+        let chrom = if pos.sequence.contains(':') {
             let mut iter = pos.sequence.rsplitn(2, ':');
             let chromosome = iter.next().unwrap();
             if let Some(genome_release) = iter.next() {
                 if let Some(expected_genome_release) = expected_genome_release {
                     if genome_release.to_lowercase() != expected_genome_release.to_lowercase() {
-                        return Err(anyhow::anyhow!(
+                        return Err(crate::ErrorCategory::Usage.error(format!(
                             "genome release mismatch (lowercase): expected {}, got {}",
-                            expected_genome_release,
-                            genome_release
-                        ));
+                            expected_genome_release, genome_release
+                        )));
                     }
                 }
             }
-            Ok(super::canonicalize(chromosome))
+            super::canonicalize(chromosome)
         } else {
-            Ok(super::canonicalize(&pos.sequence))
-        }
+            super::canonicalize(&pos.sequence)
+        };
+        check_position(&chrom, pos.position, expected_genome_release)?;
+        Ok(chrom)
     }
 
     /// Get chromosome from the SPDI range.
@@ -201,23 +466,174 @@ pub mod extract_chrom {
         range: &spdi::Range,
         expected_genome_release: Option<&str>,
     ) -> Result<String, anyhow::Error> {
-        if range.sequence.contains(':') {
+        let chrom = if range.sequence.contains(':') {
             let mut iter = range.sequence.rsplitn(2, ':');
             let chromosome = iter.next().unwrap();
             if let Some(genome_release) = iter.next() {
                 if let Some(expected_genome_release) = expected_genome_release {
                     if genome_release.to_lowercase() != expected_genome_release.to_lowercase() {
-                        return Err(anyhow::anyhow!(
+                        return Err(crate::ErrorCategory::Usage.error(format!(
                             "genome release mismatch (lowercase): expected {}, got {}",
-                            expected_genome_release,
-                            genome_release
-                        ));
+                            expected_genome_release, genome_release
+                        )));
                     }
                 }
             }
-            Ok(super::canonicalize(chromosome))
+            super::canonicalize(chromosome)
         } else {
-            Ok(super::canonicalize(&range.sequence))
+            super::canonicalize(&range.sequence)
+        };
+        check_position(&chrom, range.start, expected_genome_release)?;
+        Ok(chrom)
+    }
+
+    /// Get the SPDI range with the chromosome from `from_range` and the end clamped to the
+    /// length of the chromosome.
+    ///
+    /// Clamping is logged as a warning.
+    pub fn range(
+        range: &spdi::Range,
+        expected_genome_release: Option<&str>,
+    ) -> Result<spdi::Range, anyhow::Error> {
+        let chrom = from_range(range, expected_genome_release)?;
+        let mut end = range.end;
+        if let Some(genome_release) = parse_genome_release(expected_genome_release) {
+            if let (stop, Some(out_of_bounds)) = super::clamp_stop(
+                genome_release,
+                &chrom,
+                usize::try_from(range.end).unwrap_or_default(),
+            ) {
+                tracing::warn!("{}, clamping the range end to {}", out_of_bounds, stop);
+                end = stop as i32;
+            }
+        }
+        Ok(spdi::Range {
+            sequence: chrom,
+            end,
+            ..range.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser as _;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(None, 0, 5)]
+    #[case(Some(2), 0, 2)]
+    #[case(None, 3, 2)]
+    #[case(Some(1), 3, 1)]
+    #[case(Some(3), 4, 1)]
+    #[case(Some(0), 0, 0)]
+    #[case(None, 9, 0)]
+    fn limit_counter(#[case] limit: Option<usize>, #[case] offset: usize, #[case] expected: usize) {
+        let mut counter = ArgsLimit { limit, offset }.counter();
+        let mut admitted = 0;
+        for _ in 0..5 {
+            if counter.is_done() {
+                break;
+            }
+            if counter.admit() {
+                admitted += 1;
+            }
         }
+
+        assert_eq!(admitted, expected);
+        assert_eq!(counter.written(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(&[], true)]
+    #[case(&["--no-progress"], false)]
+    #[case(&["--quiet"], false)]
+    #[case(&["-v"], true)]
+    fn args_progress(#[case] flags: &[&str], #[case] expected: bool) {
+        let args =
+            Args::try_parse_from(std::iter::once("annonars").chain(flags.iter().copied())).unwrap();
+
+        assert_eq!(args.progress(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(GenomeRelease::Grch38, "chr1", 248_956_422, None)]
+    #[case(
+        GenomeRelease::Grch38,
+        "chr1",
+        2_491_553_345,
+        Some("position 2,491,553,345 exceeds length of chr1 (248,956,422) for GRCh38")
+    )]
+    #[case(
+        GenomeRelease::Grch37,
+        "MT",
+        16_570,
+        Some("position 16,570 exceeds length of chrMT (16,569) for GRCh37")
+    )]
+    #[case(GenomeRelease::Grch37, "GL000220.1", 1_000_000_000, None)]
+    fn check_position_bounds(
+        #[case] release: GenomeRelease,
+        #[case] chrom: &str,
+        #[case] position: usize,
+        #[case] message: Option<&str>,
+    ) {
+        assert_eq!(
+            check_position(release, chrom, position)
+                .err()
+                .map(|e| e.to_string())
+                .as_deref(),
+            message
+        );
+    }
+
+    #[test]
+    fn clamp_stop_to_length() {
+        assert_eq!(clamp_stop(GenomeRelease::Grch38, "1", 1_000), (1_000, None));
+        let (stop, out_of_bounds) = clamp_stop(GenomeRelease::Grch38, "1", 300_000_000);
+        assert_eq!(stop, 248_956_422);
+        assert_eq!(out_of_bounds.map(|e| e.position), Some(300_000_000));
+    }
+
+    #[test]
+    fn extract_chrom_bounds() -> Result<(), anyhow::Error> {
+        use crate::common::spdi;
+
+        let err = extract_chrom::from_pos(&"1:249250622".parse::<spdi::Pos>()?, Some("grch37"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "position 249,250,622 exceeds length of chr1 (249,250,621) for GRCh37"
+        );
+        // The same position is valid for GRCh38 and without a known release.
+        assert_eq!(
+            extract_chrom::from_pos(&"1:249250622".parse::<spdi::Pos>()?, Some("grch38"))?,
+            "1"
+        );
+        assert_eq!(
+            extract_chrom::from_pos(&"1:249250622".parse::<spdi::Pos>()?, None)?,
+            "1"
+        );
+        assert!(extract_chrom::from_var(
+            &"chr1:249250622:G:A".parse::<spdi::Var>()?,
+            Some("grch37")
+        )
+        .is_err());
+
+        // Range starts beyond the end are rejected, range ends are clamped.
+        assert!(extract_chrom::range(
+            &"1:249250622:249250700".parse::<spdi::Range>()?,
+            Some("grch37")
+        )
+        .is_err());
+        assert_eq!(
+            extract_chrom::range(
+                &"chr1:249250000:2491553345".parse::<spdi::Range>()?,
+                Some("grch37")
+            )?,
+            spdi::Range::new(String::from("1"), 249_250_000, 249_250_621)
+        );
+
+        Ok(())
     }
 }
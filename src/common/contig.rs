@@ -0,0 +1,172 @@
+//! Lookup of the contigs of a database.
+//!
+//! Databases only have records on the contigs of their input files, e.g., a database built
+//! from the autosomes only has no records on chrY.  Queries for such contigs are reported as
+//! `NotInDatabase` such that the server can answer with an empty section and a warning and
+//! the CLIs can print a warning rather than failing in key construction or silently writing
+//! nothing.  As the databases do not record their contigs, the check seeks to the first key
+//! of the contig, which is cheap compared to the query itself.
+
+use super::{
+    cli::{canonicalize, extract_chrom},
+    keys,
+};
+use crate::cons::cli::args::vars::ArgsQuery;
+
+/// Condition of a queried contig not being present in a database.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("contig {contig} not present in {db_name}")]
+pub struct NotInDatabase {
+    /// Canonical name of the contig, e.g., `Y`.
+    pub contig: String,
+    /// Name of the database, e.g., `gnomad_exomes`.
+    pub db_name: String,
+}
+
+/// Condition of a queried contig not being in the contig allowlist of the server.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("contig {contig} not in the contig allowlist of the server: {}", .allowlist.join(", "))]
+pub struct NotInAllowlist {
+    /// Canonical name of the contig, e.g., `Y`.
+    pub contig: String,
+    /// Canonical names of the allowed contigs.
+    pub allowlist: Vec<String>,
+}
+
+/// Check that `contig` is in `allowlist` of canonical contig names, if not empty.
+pub fn check_allowlist(allowlist: &[String], contig: &str) -> Result<(), NotInAllowlist> {
+    let contig = canonicalize(contig);
+    if allowlist.is_empty() || allowlist.contains(&contig) {
+        Ok(())
+    } else {
+        Err(NotInAllowlist {
+            contig,
+            allowlist: allowlist.to_vec(),
+        })
+    }
+}
+
+/// Return the key prefix of `contig` or `None` if it cannot be part of a key.
+///
+/// Keys only have two bytes for the chromosome, see `keys::chrom_name_to_key`.
+pub fn key_prefix(contig: &str) -> Option<Vec<u8>> {
+    let chrom = canonicalize(contig);
+    if chrom.is_empty() || chrom.len() > 2 || !chrom.bytes().all(|c| c.is_ascii_alphanumeric()) {
+        None
+    } else {
+        Some(keys::chrom_name_to_key(&chrom).into_bytes())
+    }
+}
+
+/// Check that the column family `cf_name` of the database `db_name` has records on `contig`.
+///
+/// The column family must be keyed by `keys::Pos` or `keys::Var`.
+pub fn lookup(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    contig: &str,
+    db_name: &str,
+) -> Result<(), NotInDatabase> {
+    let not_in_database = || NotInDatabase {
+        contig: canonicalize(contig),
+        db_name: db_name.to_string(),
+    };
+    let prefix = key_prefix(contig).ok_or_else(not_in_database)?;
+    let cf_data = db.cf_handle(cf_name).ok_or_else(not_in_database)?;
+
+    let mut read_opts = rocksdb::ReadOptions::default();
+    read_opts.set_total_order_seek(true);
+    let mut iter = db.raw_iterator_cf_opt(&cf_data, read_opts);
+    iter.seek(&prefix);
+    match iter.key() {
+        Some(key) if key.starts_with(&prefix) => Ok(()),
+        _ => Err(not_in_database()),
+    }
+}
+
+/// Return the canonical contig of the variant, position, or range of a CLI `query`.
+///
+/// Returns `None` for queries by accession or for all records.
+pub fn of_query(query: &ArgsQuery, genome_release: &str) -> Result<Option<String>, anyhow::Error> {
+    if let Some(variant) = query.variant.as_ref() {
+        Ok(Some(extract_chrom::from_var(
+            variant,
+            Some(genome_release),
+        )?))
+    } else if let Some(position) = query.position.as_ref() {
+        Ok(Some(extract_chrom::from_pos(
+            position,
+            Some(genome_release),
+        )?))
+    } else if let Some(range) = query.range.as_ref() {
+        Ok(Some(extract_chrom::from_range(
+            range,
+            Some(genome_release),
+        )?))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("1", Some("01"))]
+    #[case("chrX", Some(" X"))]
+    #[case("chrM", Some("MT"))]
+    #[case("22", Some("22"))]
+    #[case("chrUn_gl000220", None)]
+    #[case("", None)]
+    #[case("1-2", None)]
+    fn key_prefix_contigs(#[case] contig: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            key_prefix(contig),
+            expected.map(|expected| expected.as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn check_allowlist_contigs() {
+        let allowlist = vec!["1".to_string(), "X".to_string()];
+        for contig in ["1", "chr1", "chrX"] {
+            assert_eq!(check_allowlist(&allowlist, contig), Ok(()), "{}", contig);
+        }
+        assert_eq!(
+            check_allowlist(&allowlist, "chr2").unwrap_err().to_string(),
+            "contig 2 not in the contig allowlist of the server: 1, X"
+        );
+        assert_eq!(check_allowlist(&[], "chr2"), Ok(()));
+    }
+
+    #[test]
+    fn lookup_contigs() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, tmp_dir.as_ref(), ["data"])?;
+        let cf_data = db.cf_handle("data").unwrap();
+        for (chrom, pos) in [("1", 100), ("X", 5), ("MT", 10)] {
+            let key: Vec<u8> = keys::Var::from(chrom, pos, "A", "C").into();
+            db.put_cf(&cf_data, key, "")?;
+        }
+        drop(cf_data);
+
+        for contig in ["1", "chr1", "X", "chrM", "MT"] {
+            assert_eq!(lookup(&db, "data", contig, "example"), Ok(()), "{}", contig);
+        }
+        for contig in ["2", "chrY", "10", "chrUn_gl000220"] {
+            let err = lookup(&db, "data", contig, "example").unwrap_err();
+            assert_eq!(err.db_name, "example");
+            assert_eq!(
+                err.to_string(),
+                format!("contig {} not present in example", canonicalize(contig))
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Decoding of protobuf values read from RocksDB.
+//!
+//! A database built by a newer `annonars` may contain messages that an older binary cannot
+//! decode.  The errors created here carry the database, column family, and key of the
+//! offending value together with a hint when the `annonars-version` of the database differs
+//! from the running binary.
+
+/// Error when decoding a protobuf value read from a database.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DecodeError {
+    /// Name of the database, i.e., the last component of its path.
+    pub db_name: String,
+    /// Name of the column family.
+    pub cf_name: String,
+    /// The key of the value, hex-encoded.
+    pub key: String,
+    /// Value of `meta:annonars-version` of the database, if any.
+    pub db_annonars_version: Option<String>,
+    /// Version of the running `annonars`.
+    pub annonars_version: String,
+    /// The error message from decoding.
+    pub message: String,
+}
+
+impl DecodeError {
+    /// Return a hint on a version mismatch between database and binary, if any.
+    pub fn hint(&self) -> Option<String> {
+        self.db_annonars_version
+            .as_ref()
+            .filter(|db_annonars_version| **db_annonars_version != self.annonars_version)
+            .map(|db_annonars_version| {
+                format!(
+                    "the database was built with annonars {} but this is annonars {}, \
+                    try running a matching version",
+                    db_annonars_version, self.annonars_version
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "problem decoding protobuf from database {} (cf_name={}, key={}): {}",
+            self.db_name, self.cf_name, self.key, self.message
+        )?;
+        if let Some(hint) = self.hint() {
+            write!(f, "; {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Read `meta:annonars-version` from `db`, ignoring any errors.
+fn db_annonars_version(db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>) -> Option<String> {
+    let cf_meta = db.cf_handle("meta")?;
+    db.get_cf(&cf_meta, "annonars-version")
+        .ok()
+        .flatten()
+        .and_then(|value| String::from_utf8(value).ok())
+}
+
+/// Decode the protobuf message `T` from `raw_value` stored under `key` in `cf_name`.
+pub fn decode_protobuf<T>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    key: &[u8],
+    raw_value: &[u8],
+) -> Result<T, DecodeError>
+where
+    T: prost::Message + Default,
+{
+    T::decode(raw_value).map_err(|e| DecodeError {
+        db_name: db
+            .path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| db.path().display().to_string()),
+        cf_name: cf_name.to_string(),
+        key: super::keys::to_hex(key),
+        db_annonars_version: db_annonars_version(db),
+        annonars_version: crate::VERSION.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Decode the first record of `cf_name` as `T` to detect incompatible databases early.
+///
+/// Succeeds if the column family is empty.
+pub fn check_first_record<T>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+) -> Result<(), DecodeError>
+where
+    T: prost::Message + Default,
+{
+    let Some(cf_data) = db.cf_handle(cf_name) else {
+        return Ok(());
+    };
+    let mut iter = db.raw_iterator_cf(&cf_data);
+    iter.seek_to_first();
+    if let (Some(key), Some(raw_value)) = (iter.key(), iter.value()) {
+        decode_protobuf::<T>(db, cf_name, key, raw_value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    /// Create a database with a `meta` and a `data` column family holding garbage.
+    fn garbage_db(
+        tmp_dir: &TempDir,
+        annonars_version: &str,
+    ) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = rocksdb::DBWithThreadMode::<rocksdb::MultiThreaded>::open_cf(
+            &options,
+            tmp_dir.join("garbage-db"),
+            ["meta", "data"],
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        db.put_cf(&cf_meta, "annonars-version", annonars_version)?;
+        let cf_data = db.cf_handle("data").unwrap();
+        db.put_cf(&cf_data, b"\x01\x02", b"\xff\xff\xff\xff")?;
+        drop(cf_meta);
+        drop(cf_data);
+        Ok(db)
+    }
+
+    #[test]
+    fn decode_protobuf_garbage() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = garbage_db(&tmp_dir, "999.0.0")?;
+
+        let err = check_first_record::<crate::pbs::helixmtdb::Record>(&db, "data").unwrap_err();
+
+        assert_eq!(err.db_name, "garbage-db");
+        assert_eq!(err.cf_name, "data");
+        assert_eq!(err.key, "0102");
+        assert_eq!(err.db_annonars_version.as_deref(), Some("999.0.0"));
+        assert_eq!(err.annonars_version, crate::VERSION);
+        assert!(err
+            .to_string()
+            .contains("built with annonars 999.0.0 but this is annonars"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_protobuf_garbage_same_version() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = garbage_db(&tmp_dir, crate::VERSION)?;
+
+        let err = check_first_record::<crate::pbs::helixmtdb::Record>(&db, "data").unwrap_err();
+
+        assert_eq!(err.hint(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_first_record_missing_cf() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = garbage_db(&tmp_dir, crate::VERSION)?;
+
+        assert!(check_first_record::<crate::pbs::helixmtdb::Record>(&db, "other").is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Validation of importer inputs for `--dry-run` mode.
+//!
+//! The importers use `Validation` to run their setup checks (input files, headers, tabix
+//! indices, output path) without creating the output RocksDB.  All problems are collected
+//! so that they can be reported at once rather than failing on the first one.
+
+use std::path::Path;
+
+use crate::common::assembly_detect::GenomeReleaseArg;
+use crate::common::cli::GenomeRelease;
+
+/// Collects the outcome of the checks performed in a dry run.
+#[derive(Debug, Default)]
+pub struct Validation {
+    /// Descriptions of the checks that passed.
+    pub passed: Vec<String>,
+    /// Descriptions of the problems that were found.
+    pub problems: Vec<String>,
+}
+
+impl Validation {
+    /// Record the outcome of a check described by `what`, returning the value on success.
+    pub fn check<T, S>(&mut self, what: S, result: Result<T, anyhow::Error>) -> Option<T>
+    where
+        S: Into<String>,
+    {
+        let what = what.into();
+        match result {
+            Ok(value) => {
+                self.passed.push(what);
+                Some(value)
+            }
+            Err(e) => {
+                self.problems.push(format!("{}: {}", what, e));
+                None
+            }
+        }
+    }
+
+    /// Resolve `genome_release`, running `detect` for `auto`.
+    ///
+    /// Only the detection is recorded as a check, explicit releases are returned as is.
+    pub fn genome_release<F>(
+        &mut self,
+        genome_release: GenomeReleaseArg,
+        detect: F,
+    ) -> Option<GenomeRelease>
+    where
+        F: FnOnce() -> Result<GenomeRelease, anyhow::Error>,
+    {
+        match genome_release {
+            GenomeReleaseArg::Auto => self.check(
+                "genome release can be detected from inputs",
+                genome_release.resolve(detect),
+            ),
+            _ => genome_release.resolve(detect).ok(),
+        }
+    }
+
+    /// Check that the input file at `path` exists and can be opened for reading.
+    pub fn input_file(&mut self, path: &str) -> bool {
+        self.check(
+            format!("input file {} can be opened", path),
+            std::fs::File::open(path)
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{}", e)),
+        )
+        .is_some()
+    }
+
+    /// Check that the input file at `path` can be read with `read`, returning its result.
+    ///
+    /// The importers pass the functions that they read their inputs with, such that the dry
+    /// run performs the same header and format checks as the import.
+    pub fn read_input<T, F>(&mut self, path: &str, read: F) -> Option<T>
+    where
+        F: FnOnce(&str) -> Result<T, anyhow::Error>,
+    {
+        if !self.input_file(path) {
+            return None;
+        }
+        self.check(format!("input file {} can be read", path), read(path))
+    }
+
+    /// Check that the tabix index `{path}.tbi` exists and can be read.
+    pub fn tabix_index(&mut self, path: &str) -> bool {
+        let path_tbi = format!("{}.tbi", path);
+        self.check(
+            format!("tabix index {} can be read", &path_tbi),
+            noodles::tabix::read(&path_tbi)
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{}", e)),
+        )
+        .is_some()
+    }
+
+    /// Check that the VCF header in `path` parses and is compatible with `genome_release`.
+    ///
+    /// The assembly check is only performed if all contigs in the header have a length.
+    pub fn vcf_header(
+        &mut self,
+        path: &str,
+        genome_release: GenomeRelease,
+    ) -> Option<noodles::vcf::Header> {
+        let header = self.check(
+            format!("VCF header of {} can be parsed", path),
+            noodles::vcf::io::reader::Builder::default()
+                .build_from_path(path)
+                .and_then(|mut reader| reader.read_header())
+                .map_err(|e| anyhow::anyhow!("{}", e)),
+        )?;
+
+        let have_lengths = !header.contigs().is_empty()
+            && header
+                .contigs()
+                .values()
+                .all(|contig| contig.length().is_some());
+        if have_lengths {
+            self.check(
+                format!(
+                    "VCF contigs of {} match genome release {}",
+                    path, genome_release
+                ),
+                crate::freqs::cli::import::reading::guess_assembly(
+                    &header,
+                    true,
+                    Some(genome_release.into()),
+                )
+                .map(|_| ()),
+            );
+        } else {
+            tracing::warn!(
+                "Cannot check assembly of {} because contig lengths are missing",
+                path
+            );
+        }
+
+        Some(header)
+    }
+
+    /// Check that the output RocksDB directory at `path` can be created or written to.
+    pub fn output_dir(&mut self, path: &str) -> bool {
+        let path = Path::new(path);
+        let result = if path.exists() {
+            if path.is_dir() {
+                tempfile::tempfile_in(path)
+                    .map(|_| ())
+                    .map_err(|e| anyhow::anyhow!("directory is not writable: {}", e))
+            } else {
+                Err(anyhow::anyhow!("exists but is not a directory"))
+            }
+        } else {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            if parent.is_dir() {
+                tempfile::tempfile_in(parent)
+                    .map(|_| ())
+                    .map_err(|e| anyhow::anyhow!("parent directory is not writable: {}", e))
+            } else {
+                Err(anyhow::anyhow!("parent directory does not exist"))
+            }
+        };
+        self.check(
+            format!("output path {} is writable", path.display()),
+            result,
+        )
+        .is_some()
+    }
+
+    /// Log the validation summary and fail with all problems if there were any.
+    pub fn finish(self) -> Result<(), anyhow::Error> {
+        tracing::info!(
+            "Dry run performed {} check(s):",
+            self.passed.len() + self.problems.len()
+        );
+        for passed in &self.passed {
+            tracing::info!("  OK   {}", passed);
+        }
+        for problem in &self.problems {
+            tracing::error!("  FAIL {}", problem);
+        }
+
+        if self.problems.is_empty() {
+            tracing::info!("Dry run found no problems.");
+            Ok(())
+        } else {
+            anyhow::bail!(
+                "dry run found {} problem(s):\n{}",
+                self.problems.len(),
+                self.problems
+                    .iter()
+                    .map(|problem| format!("  - {}", problem))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        }
+    }
+}
+
+/// Return the first record of `records`, failing if there is none.
+///
+/// Used with the record iterators of the importers for checking the input format.
+pub fn first_record<T, E>(
+    mut records: impl Iterator<Item = Result<T, E>>,
+) -> Result<T, anyhow::Error>
+where
+    E: Into<anyhow::Error>,
+{
+    records
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no records"))?
+        .map_err(Into::into)
+}
+
+/// Parse the first line of the JSONL file at `path`, possibly gzip compressed, as a `T`.
+pub fn first_jsonl_record<T>(path: &str) -> Result<T, anyhow::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    use std::io::BufRead as _;
+
+    let line = first_record(super::open_read_maybe_gz(path)?.lines())?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    #[test]
+    fn validation_ok() {
+        let tmp_dir = TempDir::default();
+        let mut validation = Validation::default();
+
+        assert!(validation.input_file("tests/helixmtdb/example/helixmtdb.vcf.bgz"));
+        assert!(validation.tabix_index("tests/helixmtdb/example/helixmtdb.vcf.bgz"));
+        assert!(validation.output_dir(&format!("{}", tmp_dir.join("out-rocksdb").display())));
+
+        assert_eq!(validation.passed.len(), 3);
+        assert!(validation.finish().is_ok());
+    }
+
+    #[test]
+    fn validation_collects_all_problems() {
+        let mut validation = Validation::default();
+
+        assert!(!validation.input_file("tests/does-not-exist.tsv"));
+        assert!(!validation.tabix_index("tests/does-not-exist.tsv"));
+        assert!(!validation.output_dir("tests/does-not-exist/out-rocksdb"));
+
+        assert_eq!(validation.problems.len(), 3);
+        let err = validation.finish().unwrap_err().to_string();
+        assert!(err.starts_with("dry run found 3 problem(s)"));
+    }
+
+    #[test]
+    fn read_input() {
+        let mut validation = Validation::default();
+
+        let record = validation.read_input(
+            "tests/clinvar-genes/gene-variant-report.jsonl",
+            first_jsonl_record::<serde_json::Value>,
+        );
+        assert_eq!(record.unwrap()["hgncId"], "HGNC:5");
+        // The file is opened but the first line is not JSON.
+        assert!(validation
+            .read_input(
+                "tests/clinvar-genes/bootstrap.sh",
+                first_jsonl_record::<serde_json::Value>,
+            )
+            .is_none());
+        // The reading is skipped for missing files.
+        assert!(validation
+            .read_input("tests/does-not-exist.jsonl", |_| -> Result<(), _> {
+                panic!("must not be called")
+            })
+            .is_none());
+
+        assert_eq!(validation.passed.len(), 3);
+        assert_eq!(validation.problems.len(), 2);
+        assert!(validation.problems[0]
+            .starts_with("input file tests/clinvar-genes/bootstrap.sh can be read"));
+        assert!(validation.problems[1]
+            .starts_with("input file tests/does-not-exist.jsonl can be opened"));
+    }
+
+    #[test]
+    fn first_record_of_empty_input() {
+        let err = first_record(std::iter::empty::<Result<(), std::io::Error>>()).unwrap_err();
+        assert_eq!(err.to_string(), "no records");
+    }
+}
@@ -107,6 +107,35 @@ impl Var {
             alternative: value.alternate_bases().as_ref()[allele_no].to_string(),
         }
     }
+
+    /// Return copy with alleles normalized with `normalize_allele` and trimmed with
+    /// `trimmed`.
+    pub fn normalized(&self, allow_iupac: bool) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            chrom: self.chrom.clone(),
+            pos: self.pos,
+            reference: normalize_allele(&self.reference, allow_iupac)?,
+            alternative: normalize_allele(&self.alternative, allow_iupac)?,
+        }
+        .trimmed())
+    }
+
+    /// Return copy with common suffix and prefix of the alleles removed.
+    ///
+    /// See `common::norm::trim_alleles` for details.
+    pub fn trimmed(&self) -> Self {
+        let (pos, reference, alternative) = super::norm::trim_alleles(
+            self.pos,
+            &self.reference.to_ascii_uppercase(),
+            &self.alternative.to_ascii_uppercase(),
+        );
+        Self {
+            chrom: self.chrom.clone(),
+            pos,
+            reference,
+            alternative,
+        }
+    }
 }
 
 impl From<Var> for Vec<u8> {
@@ -115,9 +144,9 @@ impl From<Var> for Vec<u8> {
 
         result.extend_from_slice(chrom_name_to_key(&val.chrom).as_bytes());
         result.extend_from_slice(&val.pos.to_be_bytes());
-        result.extend_from_slice(val.reference.as_bytes());
+        result.extend_from_slice(val.reference.to_ascii_uppercase().as_bytes());
         result.push(b'>');
-        result.extend_from_slice(val.alternative.as_bytes());
+        result.extend_from_slice(val.alternative.to_ascii_uppercase().as_bytes());
 
         result
     }
@@ -134,6 +163,77 @@ impl From<super::spdi::Var> for Var {
     }
 }
 
+/// Nucleotide codes that are always allowed in alleles.
+const ALLELE_BASES: &[u8] = b"ACGTN";
+
+/// IUPAC ambiguity codes that are allowed in alleles with `allow_iupac`.
+const IUPAC_AMBIGUITY_CODES: &[u8] = b"RYSWKMBDHV";
+
+/// The VCF allele for a spanning deletion, which is kept as is.
+const SPANNING_DELETION: &str = "*";
+
+/// Normalize allele to uppercase and check that it only contains valid nucleotide codes.
+///
+/// Only `ACGTN` are allowed unless `allow_iupac` is given, in which case the remaining
+/// IUPAC ambiguity codes are allowed as well.  Empty alleles and the spanning deletion
+/// allele `*` are allowed.
+pub fn normalize_allele(allele: &str, allow_iupac: bool) -> Result<String, anyhow::Error> {
+    let allele = allele.to_ascii_uppercase();
+    if allele == SPANNING_DELETION {
+        return Ok(allele);
+    }
+    if let Some(c) = allele
+        .bytes()
+        .find(|c| !ALLELE_BASES.contains(c) && !(allow_iupac && IUPAC_AMBIGUITY_CODES.contains(c)))
+    {
+        anyhow::bail!(
+            "invalid character {:?} in allele {:?}",
+            char::from(c),
+            allele
+        );
+    }
+    Ok(allele)
+}
+
+/// Counts the variants skipped by the importers because `Var::normalized` failed.
+///
+/// One summary is logged per import window or file rather than one warning per allele.
+#[derive(Debug, Default)]
+pub struct InvalidAlleles {
+    /// Number of skipped variants.
+    pub count: usize,
+    /// The first skipped variant and the reason.
+    pub first: Option<String>,
+}
+
+impl InvalidAlleles {
+    /// Return `var` normalized with `Var::normalized`, or `None` if it is to be skipped.
+    pub fn normalize(&mut self, var: &Var, allow_iupac: bool) -> Option<Var> {
+        match var.normalized(allow_iupac) {
+            Ok(var) => Some(var),
+            Err(e) => {
+                tracing::debug!("skipping variant at {}:{}: {}", &var.chrom, var.pos, e);
+                self.count += 1;
+                self.first
+                    .get_or_insert_with(|| format!("{}:{}: {}", &var.chrom, var.pos, e));
+                None
+            }
+        }
+    }
+
+    /// Log the number of skipped variants in `context`, e.g., the window, if any.
+    pub fn log(&self, context: &str) {
+        if let Some(first) = self.first.as_ref() {
+            tracing::warn!(
+                "skipped {} variant(s) with invalid alleles in {}, first at {}",
+                self.count,
+                context,
+                first
+            );
+        }
+    }
+}
+
 /// Convert chromosome to key in RocksDB.
 pub fn chrom_name_to_key(name: &str) -> String {
     let chrom = if let Some(stripped) = name.strip_prefix("chr") {
@@ -171,11 +271,45 @@ pub fn chrom_key_to_name(key: &[u8]) -> String {
     }
 }
 
+/// Encode `key` as lowercase hex string, e.g., for resuming scans.
+pub fn to_hex(key: &[u8]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a key from the hex string `value` as written by `to_hex`.
+pub fn from_hex(value: &str) -> Result<Vec<u8>, anyhow::Error> {
+    if value.len() % 2 != 0 || !value.is_ascii() {
+        anyhow::bail!(
+            "invalid hex key {:?}, expected an even number of digits",
+            value
+        );
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&value[i..i + 2], 16)
+                .map_err(|e| anyhow::anyhow!("invalid hex key {:?}: {}", value, e))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn hex_roundtrip() -> Result<(), anyhow::Error> {
+        let key: Vec<u8> = Var::from("chrX", 12345, "A", "AT").into();
+        assert_eq!(to_hex(&key), "205800003039413e4154");
+        assert_eq!(from_hex(&to_hex(&key))?, key);
+        assert_eq!(from_hex("205800003039413E4154")?, key);
+        assert!(from_hex("2058a").is_err());
+        assert!(from_hex("zz").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_pos() {
         let pos = Pos::from("chr1", 123);
@@ -198,6 +332,66 @@ mod test {
         insta::assert_debug_snapshot!(buf);
     }
 
+    #[test]
+    fn test_var_lowercase_key() {
+        let upper: Vec<u8> = Var::from("chr1", 123, "AC", "T").into();
+        let lower: Vec<u8> = Var::from("chr1", 123, "ac", "t").into();
+
+        assert_eq!(upper, lower);
+    }
+
+    #[test]
+    fn test_normalize_allele() {
+        assert_eq!(normalize_allele("acgtn", false).unwrap(), "ACGTN");
+        assert_eq!(normalize_allele("", false).unwrap(), "");
+        assert!(normalize_allele("ACR", false).is_err());
+        assert_eq!(normalize_allele("acr", true).unwrap(), "ACR");
+        assert_eq!(normalize_allele("*", false).unwrap(), "*");
+        assert!(normalize_allele("A*", true).is_err());
+        assert!(normalize_allele("<DEL>", true).is_err());
+    }
+
+    #[test]
+    fn test_invalid_alleles() {
+        let mut invalid_alleles = InvalidAlleles::default();
+
+        assert_eq!(
+            invalid_alleles.normalize(&Var::from("chr1", 123, "a", "*"), false),
+            Some(Var::from("chr1", 123, "A", "*"))
+        );
+        assert_eq!(
+            invalid_alleles.normalize(&Var::from("chr1", 124, "A", "<DEL>"), false),
+            None
+        );
+        assert_eq!(
+            invalid_alleles.normalize(&Var::from("chr1", 125, "A", "R"), false),
+            None
+        );
+
+        assert_eq!(invalid_alleles.count, 2);
+        assert_eq!(
+            invalid_alleles.first.as_deref(),
+            Some("chr1:124: invalid character '<' in allele \"<DEL>\"")
+        );
+    }
+
+    #[test]
+    fn test_var_normalized() {
+        let var = Var::from("chr1", 123, "a", "tn");
+
+        assert_eq!(
+            var.normalized(false).unwrap(),
+            Var::from("chr1", 123, "A", "TN")
+        );
+        assert!(Var::from("chr1", 123, "A", "Y").normalized(false).is_err());
+        assert_eq!(
+            Var::from("chr1", 123, "cat", "ct")
+                .normalized(false)
+                .unwrap(),
+            Var::from("chr1", 123, "CA", "C")
+        );
+    }
+
     #[test]
     fn test_chrom_name_to_key() {
         assert_eq!(chrom_name_to_key("chr1"), "01");
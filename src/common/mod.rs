@@ -2,10 +2,26 @@
 
 use std::path::{Path, PathBuf};
 
+pub mod assembly_detect;
+pub mod batch;
+pub mod cache;
 pub mod cli;
+pub mod compaction;
+pub mod contig;
+pub mod decode;
+pub mod dry_run;
 pub mod keys;
 pub mod noodles;
+pub mod norm;
+pub mod output;
+pub mod par;
+pub mod pos_index;
+pub mod prefix_bloom;
+pub mod record_stats;
+pub mod remote;
+pub mod sample;
 pub mod spdi;
+pub mod spec;
 
 /// The version of `annonars` package.
 #[cfg(not(test))]
@@ -40,6 +56,36 @@ where
     _readlink_f(path.as_ref(), 20)
 }
 
+/// Recursively resolve the `path` of an existing file or directory, e.g., a database.
+///
+/// Fails with `ErrorCategory::NotFound` if there is nothing at `path`.
+pub fn readlink_f_existing<P>(path: P) -> Result<PathBuf, anyhow::Error>
+where
+    P: AsRef<Path>,
+{
+    let resolved = readlink_f(&path)?;
+    if resolved.exists() {
+        Ok(resolved)
+    } else {
+        Err(crate::ErrorCategory::NotFound
+            .error(format!("{} does not exist", path.as_ref().display())))
+    }
+}
+
+/// Open the file at `path` for reading, decompressing it if the name ends with `.gz`.
+///
+/// Multi-member gzip files, e.g., from `bgzip`, are read completely.
+pub fn open_read_maybe_gz(path: &str) -> Result<Box<dyn std::io::BufRead>, anyhow::Error> {
+    let file = std::fs::File::open(path)?;
+    Ok(if path.ends_with(".gz") {
+        Box::new(std::io::BufReader::new(flate2::read::MultiGzDecoder::new(
+            file,
+        )))
+    } else {
+        Box::new(std::io::BufReader::new(file))
+    })
+}
+
 /// Allows to set the rstest snapshot suffix.
 #[macro_export]
 macro_rules! set_snapshot_suffix {
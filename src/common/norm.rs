@@ -0,0 +1,192 @@
+//! Normalization of variant alleles for RocksDB keys.
+//!
+//! The same indel can be written in several ways in VCF, e.g., `CAT>CT` and `CA>C` at the
+//! same position describe the same deletion.  To make lookups independent of the caller's
+//! representation, the alleles are trimmed before constructing keys:
+//!
+//! 1. The common suffix is removed as long as both alleles keep at least one base.
+//! 2. The common prefix is removed as long as both alleles keep at least one base; the
+//!    position is incremented for each removed base.
+//!
+//! Trimming the suffix first keeps the VCF-style anchor base of indels.  The result is
+//! idempotent, and alleles that only differ by shared flanking sequence are mapped to the
+//! same key.  Left-alignment of indels in repeats requires the reference sequence and is
+//! not performed.
+//!
+//! Importers that trim their keys write `keys-normalized = true` into the `meta` column
+//! family.  For databases without this flag, queries fall back to the untrimmed variant
+//! and a warning is logged.
+
+/// Name of the meta value that flags databases with trimmed keys.
+pub const META_KEYS_NORMALIZED: &str = "keys-normalized";
+
+/// Trim the common suffix and then the common prefix of `reference` and `alternative`.
+///
+/// At least one base is kept on each allele.  Returns the adjusted position and alleles.
+pub fn trim_alleles(pos: i32, reference: &str, alternative: &str) -> (i32, String, String) {
+    let mut reference = reference.as_bytes();
+    let mut alternative = alternative.as_bytes();
+
+    while reference.len() > 1
+        && alternative.len() > 1
+        && reference[reference.len() - 1] == alternative[alternative.len() - 1]
+    {
+        reference = &reference[..reference.len() - 1];
+        alternative = &alternative[..alternative.len() - 1];
+    }
+
+    let mut pos = pos;
+    while reference.len() > 1 && alternative.len() > 1 && reference[0] == alternative[0] {
+        reference = &reference[1..];
+        alternative = &alternative[1..];
+        pos += 1;
+    }
+
+    (
+        pos,
+        String::from_utf8_lossy(reference).to_string(),
+        String::from_utf8_lossy(alternative).to_string(),
+    )
+}
+
+/// Write the `keys-normalized` flag to the meta column family.
+pub fn write_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_meta: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+) -> Result<(), anyhow::Error> {
+    db.put_cf(cf_meta, META_KEYS_NORMALIZED, "true")
+        .map_err(|e| anyhow::anyhow!("problem writing meta:{}: {}", META_KEYS_NORMALIZED, e))
+}
+
+/// Read the `keys-normalized` flag from the meta column family.
+///
+/// Logs a compatibility warning if this is not the case.
+pub fn read_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_meta: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+) -> Result<bool, anyhow::Error> {
+    let keys_normalized = db
+        .get_cf(cf_meta, META_KEYS_NORMALIZED)
+        .map_err(|e| anyhow::anyhow!("problem reading meta:{}: {}", META_KEYS_NORMALIZED, e))?
+        .map(|value| value == b"true")
+        .unwrap_or(false);
+    if !keys_normalized {
+        tracing::warn!(
+            "database {} was imported without allele trimming; indels are only found in the \
+            representation used in the input files",
+            db.path().display()
+        );
+    }
+    Ok(keys_normalized)
+}
+
+/// Convert the query `variant` to a key, trimming the alleles if `keys_normalized`.
+pub fn query_key(variant: super::spdi::Var, keys_normalized: bool) -> Vec<u8> {
+    let var: super::keys::Var = variant.into();
+    if keys_normalized {
+        var.trimmed().into()
+    } else {
+        var.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// All alleles over `ACGT` of length 1 to `max_len`.
+    fn alleles(max_len: usize) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut current = vec![String::new()];
+        for _ in 0..max_len {
+            current = current
+                .iter()
+                .flat_map(|prefix| "ACGT".chars().map(move |c| format!("{}{}", prefix, c)))
+                .collect();
+            result.extend(current.iter().cloned());
+        }
+        result
+    }
+
+    #[rstest::rstest]
+    #[case("CAT", "CT", 100, "CA", "C", 100)]
+    #[case("CA", "C", 100, "CA", "C", 100)]
+    #[case("C", "CAT", 100, "C", "CAT", 100)]
+    #[case("GCAT", "GCT", 100, "CA", "C", 101)]
+    #[case("ACG", "ATG", 100, "C", "T", 101)]
+    #[case("A", "G", 100, "A", "G", 100)]
+    #[case("AT", "AT", 100, "A", "A", 100)]
+    fn trim_alleles_examples(
+        #[case] reference: &str,
+        #[case] alternative: &str,
+        #[case] pos: i32,
+        #[case] expected_reference: &str,
+        #[case] expected_alternative: &str,
+        #[case] expected_pos: i32,
+    ) {
+        assert_eq!(
+            trim_alleles(pos, reference, alternative),
+            (
+                expected_pos,
+                expected_reference.to_string(),
+                expected_alternative.to_string()
+            )
+        );
+    }
+
+    /// Check the properties of `trim_alleles` for all allele pairs up to length 3.
+    #[test]
+    fn trim_alleles_properties() {
+        let alleles = alleles(3);
+        for reference in &alleles {
+            for alternative in &alleles {
+                let (pos, trimmed_ref, trimmed_alt) = trim_alleles(100, reference, alternative);
+
+                // At least one base is kept on each allele.
+                assert!(!trimmed_ref.is_empty() && !trimmed_alt.is_empty());
+                // The result is a substring at the adjusted position.
+                let offset = (pos - 100) as usize;
+                assert_eq!(&reference[offset..offset + trimmed_ref.len()], trimmed_ref);
+                assert_eq!(
+                    &alternative[offset..offset + trimmed_alt.len()],
+                    trimmed_alt
+                );
+                // Trimming is idempotent.
+                assert_eq!(
+                    trim_alleles(pos, &trimmed_ref, &trimmed_alt),
+                    (pos, trimmed_ref.clone(), trimmed_alt.clone())
+                );
+                // Adding shared flanking sequence does not change the result.
+                for flank in ["A", "GT"] {
+                    assert_eq!(
+                        trim_alleles(
+                            100,
+                            &format!("{}{}", reference, flank),
+                            &format!("{}{}", alternative, flank)
+                        ),
+                        (pos, trimmed_ref.clone(), trimmed_alt.clone()),
+                        "suffix {} for {}>{}",
+                        flank,
+                        reference,
+                        alternative
+                    );
+                }
+            }
+        }
+    }
+
+    /// Regression test: an untrimmed deletion gives the same key as the trimmed one.
+    #[test]
+    fn differently_expressed_indel_same_key() {
+        let untrimmed: Vec<u8> = crate::common::keys::Var::from("1", 1000, "CAT", "CT")
+            .normalized(false)
+            .unwrap()
+            .into();
+        let trimmed: Vec<u8> = crate::common::keys::Var::from("1", 1000, "CA", "C")
+            .normalized(false)
+            .unwrap()
+            .into();
+
+        assert_eq!(untrimmed, trimmed);
+    }
+}
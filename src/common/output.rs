@@ -0,0 +1,404 @@
+//! Output of query results with `--output-format`.
+//!
+//! All query commands write their records through `RecordWriter`, which supports the
+//! following formats:
+//!
+//! - `jsonl` (default) writes one JSON record per line, as before.
+//! - `jsonl-v2` wraps each record in a versioned envelope, one per line:
+//!
+//!   ```text
+//!   {"schema_version":2,"database":"dbsnp","genome_release":"grch37","key":"1:55516888:G:GA","payload":{...}}
+//!   ```
+//!
+//!   `database` is the name of the database (the sub command or, for `tsv`, the `db-name`
+//!   meta value), `genome_release` is the release from the database meta information
+//!   (`null` for release-independent data), and `key` identifies the record, e.g., the
+//!   variant queried for or `CHROM:POS[:REF:ALT]` when scanning a position or range.
+//!   `payload` is the record exactly as written with `jsonl`.  `SCHEMA_VERSION` is
+//!   incremented on every incompatible change of the envelope.
+//! - `yaml` writes each record as a separate YAML document.
+//!
+//! For queries from `--path-input-jsonl` (see `common::batch`), each record has the
+//! `input_index` of its query, added as a field of the record for `jsonl` and `yaml` and of
+//! the envelope for `jsonl-v2`.  Failing queries are written as
+//! `{"input_index":2,"error":"..."}`, with the envelope fields other than `key` and
+//! `payload` for `jsonl-v2`.
+
+use std::io::Write;
+
+use super::{
+    cli::{extract_chrom, OutputFormat},
+    keys, spdi,
+};
+
+/// Version of the `jsonl-v2` envelope.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Command line arguments for selecting the output format.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ArgsOutput {
+    /// Output format.
+    #[arg(
+        long = "output-format",
+        visible_alias = "out-format",
+        value_enum,
+        default_value_t = OutputFormat::Jsonl
+    )]
+    pub output_format: OutputFormat,
+}
+
+/// Envelope of a record for `jsonl-v2`.
+#[derive(Debug, serde::Serialize)]
+pub struct Envelope<'a, T: serde::Serialize + ?Sized> {
+    /// Version of the envelope, see `SCHEMA_VERSION`.
+    pub schema_version: u32,
+    /// Name of the database.
+    pub database: &'a str,
+    /// Genome release of the database, if any.
+    pub genome_release: Option<&'a str>,
+    /// Key of the record.
+    pub key: &'a str,
+    /// Index of the query in the `--path-input-jsonl` input, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_index: Option<usize>,
+    /// The record itself.
+    pub payload: &'a T,
+}
+
+/// Record of a query from `--path-input-jsonl` for `jsonl` and `yaml`.
+#[derive(Debug, serde::Serialize)]
+pub struct Indexed<'a, T: serde::Serialize + ?Sized> {
+    /// Index of the query in the input.
+    pub input_index: usize,
+    /// The record itself.
+    #[serde(flatten)]
+    pub record: &'a T,
+}
+
+/// Error of a query from `--path-input-jsonl`, written in place of its records.
+#[derive(Debug, serde::Serialize)]
+pub struct QueryError<'a> {
+    /// Version of the envelope for `jsonl-v2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    /// Name of the database for `jsonl-v2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<&'a str>,
+    /// Genome release of the database for `jsonl-v2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genome_release: Option<&'a str>,
+    /// Index of the query in the input.
+    pub input_index: usize,
+    /// Description of the error.
+    pub error: &'a str,
+}
+
+/// Open `out_file` for writing, stdout for `"-"`.
+pub fn open_out_file(out_file: &str) -> Result<Box<dyn Write>, anyhow::Error> {
+    Ok(match out_file {
+        "-" => Box::new(std::io::stdout()) as Box<dyn Write>,
+        out_file => Box::new(
+            std::fs::File::create(out_file)
+                .map_err(|e| anyhow::anyhow!("could not create output file {}: {}", out_file, e))?,
+        ) as Box<dyn Write>,
+    })
+}
+
+/// Writer of query results in the selected output format.
+pub struct RecordWriter {
+    /// Writer to the output file.
+    out_writer: Box<dyn Write>,
+    /// Output format to write.
+    output_format: OutputFormat,
+    /// Name of the database for the envelope.
+    database: String,
+    /// Genome release for the envelope.
+    genome_release: Option<String>,
+    /// Index of the current query from `--path-input-jsonl`, if any.
+    input_index: Option<usize>,
+}
+
+impl RecordWriter {
+    /// Create a new writer to `out_file` (stdout for `"-"`) for records from `database`.
+    pub fn new(
+        out_file: &str,
+        output_format: OutputFormat,
+        database: &str,
+        genome_release: Option<&str>,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            out_writer: open_out_file(out_file)?,
+            output_format,
+            database: database.to_string(),
+            genome_release: genome_release.map(|genome_release| genome_release.to_lowercase()),
+            input_index: None,
+        })
+    }
+
+    /// Set the index of the query from `--path-input-jsonl` of the following records.
+    pub fn set_input_index(&mut self, input_index: Option<usize>) {
+        self.input_index = input_index;
+    }
+
+    /// Write the record `payload` with the given `key`.
+    pub fn write<T: serde::Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        payload: &T,
+    ) -> Result<(), anyhow::Error> {
+        match (self.output_format, self.input_index) {
+            (OutputFormat::Jsonl, None) => {
+                writeln!(self.out_writer, "{}", serde_json::to_string(payload)?)?;
+            }
+            (OutputFormat::Jsonl, Some(input_index)) => {
+                let record = Indexed {
+                    input_index,
+                    record: payload,
+                };
+                writeln!(self.out_writer, "{}", serde_json::to_string(&record)?)?;
+            }
+            (OutputFormat::JsonlV2, input_index) => {
+                let envelope = Envelope {
+                    schema_version: SCHEMA_VERSION,
+                    database: &self.database,
+                    genome_release: self.genome_release.as_deref(),
+                    key,
+                    input_index,
+                    payload,
+                };
+                writeln!(self.out_writer, "{}", serde_json::to_string(&envelope)?)?;
+            }
+            (OutputFormat::Yaml, None) => {
+                write!(self.out_writer, "---\n{}", serde_yaml::to_string(payload)?)?;
+            }
+            (OutputFormat::Yaml, Some(input_index)) => {
+                let record = Indexed {
+                    input_index,
+                    record: payload,
+                };
+                write!(self.out_writer, "---\n{}", serde_yaml::to_string(&record)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the `error` of the current query from `--path-input-jsonl`.
+    pub fn write_error(&mut self, error: &str) -> Result<(), anyhow::Error> {
+        let input_index = self
+            .input_index
+            .ok_or_else(|| anyhow::anyhow!("no query from --path-input-jsonl to write error of"))?;
+        let envelope = self.output_format == OutputFormat::JsonlV2;
+        let record = QueryError {
+            schema_version: envelope.then_some(SCHEMA_VERSION),
+            database: envelope.then_some(self.database.as_str()),
+            genome_release: self.genome_release.as_deref().filter(|_| envelope),
+            input_index,
+            error,
+        };
+        match self.output_format {
+            OutputFormat::Jsonl | OutputFormat::JsonlV2 => {
+                writeln!(self.out_writer, "{}", serde_json::to_string(&record)?)?;
+            }
+            OutputFormat::Yaml => {
+                write!(self.out_writer, "---\n{}", serde_yaml::to_string(&record)?)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Return the key `CHROM:POS:REF:ALT` for the queried `variant`.
+///
+/// The chromosome is canonicalized and the genome release checked as for the query itself.
+pub fn var_key(variant: &spdi::Var, genome_release: Option<&str>) -> Result<String, anyhow::Error> {
+    Ok(format!(
+        "{}:{}:{}:{}",
+        extract_chrom::from_var(variant, genome_release)?,
+        variant.position,
+        variant.deletion.to_ascii_uppercase(),
+        variant.insertion.to_ascii_uppercase()
+    ))
+}
+
+/// Return the key `CHROM:POS` or `CHROM:POS:REF:ALT` for a `keys::Pos` or `keys::Var` key.
+pub fn db_key(key: &[u8]) -> String {
+    let pos: keys::Pos = key.into();
+    match String::from_utf8_lossy(&key[6..]).split_once('>') {
+        Some((reference, alternative)) => {
+            format!("{}:{}:{}:{}", pos.chrom, pos.pos, reference, alternative)
+        }
+        None => format!("{}:{}", pos.chrom, pos.pos),
+    }
+}
+
+/// Assert that `out_data_v2` holds the records of the `jsonl` output `out_data` in envelopes
+/// with the given `keys`.
+#[cfg(test)]
+pub(crate) fn assert_envelopes(
+    out_data: &str,
+    out_data_v2: &str,
+    database: &str,
+    genome_release: Option<&str>,
+    keys: &[&str],
+) {
+    let genome_release = genome_release
+        .map(|genome_release| format!("\"{}\"", genome_release))
+        .unwrap_or_else(|| String::from("null"));
+    let expected = out_data
+        .lines()
+        .zip(keys)
+        .map(|(line, key)| {
+            format!(
+                "{{\"schema_version\":{},\"database\":\"{}\",\"genome_release\":{},\
+                \"key\":\"{}\",\"payload\":{}}}\n",
+                SCHEMA_VERSION, database, genome_release, key, line
+            )
+        })
+        .collect::<String>();
+
+    assert_eq!(out_data.lines().count(), keys.len());
+    assert_eq!(out_data_v2, expected);
+}
+
+#[cfg(test)]
+mod test {
+    use clap::ValueEnum;
+
+    use super::*;
+
+    /// Write `records` with `output_format` and return the output.
+    fn write_records(
+        output_format: OutputFormat,
+        genome_release: Option<&str>,
+        records: &[(&str, serde_json::Value)],
+    ) -> Result<String, anyhow::Error> {
+        let temp = temp_testdir::TempDir::default();
+        let out_file = temp.join("out").to_string_lossy().to_string();
+        let mut writer = RecordWriter::new(&out_file, output_format, "example", genome_release)?;
+        for (key, record) in records {
+            writer.write(key, record)?;
+        }
+        drop(writer);
+        Ok(std::fs::read_to_string(&out_file)?)
+    }
+
+    #[test]
+    fn output_format_names() -> Result<(), anyhow::Error> {
+        for (name, output_format) in [
+            ("jsonl", OutputFormat::Jsonl),
+            ("jsonl-v2", OutputFormat::JsonlV2),
+            ("yaml", OutputFormat::Yaml),
+        ] {
+            assert_eq!(output_format.to_string(), name);
+            assert_eq!(OutputFormat::from_str(name, false).unwrap(), output_format);
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(OutputFormat::Jsonl, "{\"a\":1}\n{\"a\":2}\n")]
+    #[case(
+        OutputFormat::JsonlV2,
+        "{\"schema_version\":2,\"database\":\"example\",\"genome_release\":\"grch37\",\
+        \"key\":\"1:100:A:C\",\"payload\":{\"a\":1}}\n\
+        {\"schema_version\":2,\"database\":\"example\",\"genome_release\":\"grch37\",\
+        \"key\":\"1:200\",\"payload\":{\"a\":2}}\n"
+    )]
+    #[case(OutputFormat::Yaml, "---\na: 1\n---\na: 2\n")]
+    fn write_formats(
+        #[case] output_format: OutputFormat,
+        #[case] expected: &str,
+    ) -> Result<(), anyhow::Error> {
+        let out_data = write_records(
+            output_format,
+            Some("GRCh37"),
+            &[
+                ("1:100:A:C", serde_json::json!({"a": 1})),
+                ("1:200", serde_json::json!({"a": 2})),
+            ],
+        )?;
+
+        assert_eq!(out_data, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_without_genome_release() -> Result<(), anyhow::Error> {
+        let out_data = write_records(
+            OutputFormat::JsonlV2,
+            None,
+            &[("HGNC:1", serde_json::json!({"a": 1}))],
+        )?;
+
+        assert_eq!(
+            out_data,
+            "{\"schema_version\":2,\"database\":\"example\",\"genome_release\":null,\
+            \"key\":\"HGNC:1\",\"payload\":{\"a\":1}}\n"
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(
+        OutputFormat::Jsonl,
+        "{\"input_index\":3,\"a\":1}\n{\"input_index\":4,\"error\":\"invalid\"}\n"
+    )]
+    #[case(
+        OutputFormat::JsonlV2,
+        "{\"schema_version\":2,\"database\":\"example\",\"genome_release\":\"grch37\",\
+        \"key\":\"1:100:A:C\",\"input_index\":3,\"payload\":{\"a\":1}}\n\
+        {\"schema_version\":2,\"database\":\"example\",\"genome_release\":\"grch37\",\
+        \"input_index\":4,\"error\":\"invalid\"}\n"
+    )]
+    #[case(
+        OutputFormat::Yaml,
+        "---\ninput_index: 3\na: 1\n---\ninput_index: 4\nerror: invalid\n"
+    )]
+    fn write_with_input_index(
+        #[case] output_format: OutputFormat,
+        #[case] expected: &str,
+    ) -> Result<(), anyhow::Error> {
+        let temp = temp_testdir::TempDir::default();
+        let out_file = temp.join("out").to_string_lossy().to_string();
+        let mut writer = RecordWriter::new(&out_file, output_format, "example", Some("GRCh37"))?;
+        assert!(writer.write_error("invalid").is_err());
+        writer.set_input_index(Some(3));
+        writer.write("1:100:A:C", &serde_json::json!({"a": 1}))?;
+        writer.set_input_index(Some(4));
+        writer.write_error("invalid")?;
+        drop(writer);
+
+        assert_eq!(std::fs::read_to_string(&out_file)?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn var_key_canonical() -> Result<(), anyhow::Error> {
+        let variant = spdi::Var::new(
+            String::from("GRCh37:chr1"),
+            100,
+            String::from("a"),
+            String::from("c"),
+        );
+
+        assert_eq!(var_key(&variant, Some("grch37"))?, "1:100:A:C");
+        assert!(var_key(&variant, Some("grch38")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn db_key_pos_and_var() {
+        let pos: Vec<u8> = keys::Pos::from("chrX", 100).into();
+        let var: Vec<u8> = keys::Var::from("MT", 200, "A", "").into();
+
+        assert_eq!(db_key(&pos), "X:100");
+        assert_eq!(db_key(&var), "MT:200:A:");
+    }
+}
@@ -0,0 +1,70 @@
+//! Pseudoautosomal regions (PAR) of chrX and chrY.
+//!
+//! Variants in the PARs are diploid in XY individuals, variants on chrX and chrY outside of
+//! them are hemizygous.  The boundaries are the ones of the Genome Reference Consortium as 1-based
+//! closed intervals, the same for the UCSC and Ensembl flavours of each release.
+
+use super::cli::{canonicalize, GenomeRelease};
+
+/// PAR1 and PAR2 of chrX in GRCh37.
+const GRCH37_X: &[(usize, usize)] = &[(60_001, 2_699_520), (154_931_044, 155_260_560)];
+/// PAR1 and PAR2 of chrY in GRCh37.
+const GRCH37_Y: &[(usize, usize)] = &[(10_001, 2_649_520), (59_034_050, 59_363_566)];
+/// PAR1 and PAR2 of chrX in GRCh38.
+const GRCH38_X: &[(usize, usize)] = &[(10_001, 2_781_479), (155_701_383, 156_030_895)];
+/// PAR1 and PAR2 of chrY in GRCh38.
+const GRCH38_Y: &[(usize, usize)] = &[(10_001, 2_781_479), (56_887_903, 57_217_415)];
+
+/// Return the PARs of chromosome `chrom` in `release` as 1-based closed intervals, empty for
+/// chromosomes other than chrX and chrY.
+pub fn regions(release: GenomeRelease, chrom: &str) -> &'static [(usize, usize)] {
+    match (release, canonicalize(chrom).as_str()) {
+        (GenomeRelease::Grch37, "X") => GRCH37_X,
+        (GenomeRelease::Grch37, "Y") => GRCH37_Y,
+        (GenomeRelease::Grch38, "X") => GRCH38_X,
+        (GenomeRelease::Grch38, "Y") => GRCH38_Y,
+        _ => &[],
+    }
+}
+
+/// Return whether the 1-based position `pos` on `chrom` of `release` is in a PAR.
+pub fn is_par(release: GenomeRelease, chrom: &str, pos: usize) -> bool {
+    regions(release, chrom)
+        .iter()
+        .any(|(start, stop)| (*start..=*stop).contains(&pos))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::grch37_x_before_par1(GenomeRelease::Grch37, "X", 60_000, false)]
+    #[case::grch37_x_par1_start(GenomeRelease::Grch37, "X", 60_001, true)]
+    #[case::grch37_x_par1_stop(GenomeRelease::Grch37, "X", 2_699_520, true)]
+    #[case::grch37_x_after_par1(GenomeRelease::Grch37, "X", 2_699_521, false)]
+    #[case::grch37_x_par2_start(GenomeRelease::Grch37, "X", 154_931_044, true)]
+    #[case::grch37_x_par2_stop(GenomeRelease::Grch37, "X", 155_260_560, true)]
+    #[case::grch37_y_par1_stop(GenomeRelease::Grch37, "Y", 2_649_520, true)]
+    #[case::grch37_y_after_par1(GenomeRelease::Grch37, "Y", 2_649_521, false)]
+    #[case::grch37_y_par2_start(GenomeRelease::Grch37, "Y", 59_034_050, true)]
+    #[case::grch38_x_before_par1(GenomeRelease::Grch38, "chrX", 10_000, false)]
+    #[case::grch38_x_par1_start(GenomeRelease::Grch38, "chrX", 10_001, true)]
+    #[case::grch38_x_par1_stop(GenomeRelease::Grch38, "chrX", 2_781_479, true)]
+    #[case::grch38_x_after_par1(GenomeRelease::Grch38, "chrX", 2_781_480, false)]
+    #[case::grch38_x_before_par2(GenomeRelease::Grch38, "chrX", 155_701_382, false)]
+    #[case::grch38_x_par2_stop(GenomeRelease::Grch38, "chrX", 156_030_895, true)]
+    #[case::grch38_y_par1_stop(GenomeRelease::Grch38, "chrY", 2_781_479, true)]
+    #[case::grch38_y_after_par1(GenomeRelease::Grch38, "chrY", 2_781_480, false)]
+    #[case::grch38_y_par2_start(GenomeRelease::Grch38, "chrY", 56_887_903, true)]
+    #[case::grch38_y_after_par2(GenomeRelease::Grch38, "chrY", 57_217_416, false)]
+    #[case::autosome(GenomeRelease::Grch38, "1", 10_001, false)]
+    fn is_par_cases(
+        #[case] release: GenomeRelease,
+        #[case] chrom: &str,
+        #[case] pos: usize,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_par(release, chrom, pos), expected);
+    }
+}
@@ -0,0 +1,292 @@
+//! Positional index of features for finding the nearest features of a position.
+//!
+//! Databases keyed by accession or gene ID, e.g., the functional elements and genes
+//! databases, have no positional keys to search for features next to a position.  Their
+//! importers thus also write each feature twice into an index column family: keyed by its
+//! start and keyed by its stop position, each as `keys::Pos` followed by the feature ID.
+//! The nearest upstream feature is found by seeking to the stop key of the position and
+//! stepping backward, the nearest downstream one by seeking to the start key after the
+//! position, without scanning the search window.
+//!
+//! "Upstream" and "downstream" refer to lower and higher positions on the contig regardless
+//! of the feature strand.
+
+use super::keys;
+
+/// Prefix of the keys with the start position.
+const KIND_START: u8 = b'S';
+/// Prefix of the keys with the stop position.
+const KIND_STOP: u8 = b'E';
+
+/// Default maximal distance to search for the nearest features.
+pub const DEFAULT_MAX_WINDOW: u32 = 1_000_000;
+
+/// Feature next to a position.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct NearestFeature {
+    /// Identifier of the feature, e.g., the accession or HGNC ID.
+    pub id: String,
+    /// 1-based start position of the feature.
+    pub start: i32,
+    /// 1-based, inclusive stop position of the feature.
+    pub stop: i32,
+    /// Signed distance from the position to the closest end of the feature, negative for
+    /// upstream features.
+    pub distance: i32,
+}
+
+/// Nearest features up- and downstream of a position.
+///
+/// Features overlapping the position are neither up- nor downstream.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct NearestFeatures {
+    /// Feature with the highest stop position before the position, if within the window.
+    pub upstream: Option<NearestFeature>,
+    /// Feature with the lowest start position after the position, if within the window.
+    pub downstream: Option<NearestFeature>,
+}
+
+/// Return the index key of `kind` for `chrom:pos`, followed by `id`.
+fn key(kind: u8, chrom: &str, pos: i32, id: &str) -> Vec<u8> {
+    let mut result = vec![kind];
+    result.extend(Vec::<u8>::from(keys::Pos::new(chrom.to_string(), pos)));
+    result.extend_from_slice(id.as_bytes());
+    result
+}
+
+/// Decode an index key into its kind, encoded chromosome, position, and ID.
+fn decode_key(key: &[u8]) -> Result<(u8, &[u8], i32, String), anyhow::Error> {
+    if key.len() < 7 {
+        anyhow::bail!("invalid position index key: {:?}", key);
+    }
+    let pos = i32::from_be_bytes(key[3..7].try_into()?);
+    let id = String::from_utf8(key[7..].to_vec())?;
+    Ok((key[0], &key[1..3], pos, id))
+}
+
+/// Decode an index value, the other position of the feature.
+fn decode_value(value: &[u8]) -> Result<i32, anyhow::Error> {
+    Ok(i32::from_be_bytes(value.try_into().map_err(|_| {
+        anyhow::anyhow!("invalid position index value: {:?}", value)
+    })?))
+}
+
+/// Write the feature `id` at `chrom:start-stop` (1-based, inclusive) to the index `cf`.
+pub fn put(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+    chrom: &str,
+    start: i32,
+    stop: i32,
+    id: &str,
+) -> Result<(), anyhow::Error> {
+    db.put_cf(cf, key(KIND_START, chrom, start, id), stop.to_be_bytes())?;
+    db.put_cf(cf, key(KIND_STOP, chrom, stop, id), start.to_be_bytes())?;
+    Ok(())
+}
+
+/// Return the nearest features up- and downstream of `chrom:pos` from the index `cf` that are
+/// at most `max_window` bases away.
+pub fn nearest(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+    chrom: &str,
+    pos: i32,
+    max_window: u32,
+) -> Result<NearestFeatures, anyhow::Error> {
+    let chrom_key = keys::chrom_name_to_key(chrom);
+    let mut iter = db.raw_iterator_cf(cf);
+
+    // The first stop key at or after `pos`; the previous one is the highest stop before it.
+    iter.seek(key(KIND_STOP, chrom, pos, ""));
+    if iter.valid() {
+        iter.prev();
+    } else {
+        iter.seek_to_last();
+    }
+    iter.status()?;
+    let upstream = match (iter.key(), iter.value()) {
+        (Some(key), Some(value)) => {
+            let (kind, key_chrom, stop, id) = decode_key(key)?;
+            if kind == KIND_STOP
+                && key_chrom == chrom_key.as_bytes()
+                && (pos - stop) as u32 <= max_window
+            {
+                Some(NearestFeature {
+                    id,
+                    start: decode_value(value)?,
+                    stop,
+                    distance: stop - pos,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    // The first start key after `pos`.
+    iter.seek(key(KIND_START, chrom, pos + 1, ""));
+    iter.status()?;
+    let downstream = match (iter.key(), iter.value()) {
+        (Some(key), Some(value)) => {
+            let (kind, key_chrom, start, id) = decode_key(key)?;
+            if kind == KIND_START
+                && key_chrom == chrom_key.as_bytes()
+                && (start - pos) as u32 <= max_window
+            {
+                Some(NearestFeature {
+                    id,
+                    start,
+                    stop: decode_value(value)?,
+                    distance: start - pos,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    Ok(NearestFeatures {
+        upstream,
+        downstream,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    /// Write the features to an index in a new database in `tmp_dir`.
+    fn index_db(
+        tmp_dir: &TempDir,
+        features: &[(&str, i32, i32, &str)],
+    ) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = rocksdb::DB::open_cf(&options, tmp_dir.join("db"), ["index"])?;
+        let cf = db.cf_handle("index").unwrap();
+        for (chrom, start, stop, id) in features {
+            put(&db, &cf, chrom, *start, *stop, id)?;
+        }
+        drop(cf);
+        Ok(db)
+    }
+
+    /// Features on chromosomes 1, 2, and X.
+    const FEATURES: &[(&str, i32, i32, &str)] = &[
+        ("1", 1000, 2000, "a"),
+        ("1", 5000, 6000, "b"),
+        ("1", 5500, 9000, "c"),
+        ("2", 100, 200, "d"),
+        ("X", 3000, 4000, "e"),
+    ];
+
+    /// Return the IDs and distances of the nearest features.
+    fn ids_and_distances(nearest: &NearestFeatures) -> (Option<(&str, i32)>, Option<(&str, i32)>) {
+        fn id_and_distance(feature: &Option<NearestFeature>) -> Option<(&str, i32)> {
+            feature
+                .as_ref()
+                .map(|feature| (feature.id.as_str(), feature.distance))
+        }
+        (
+            id_and_distance(&nearest.upstream),
+            id_and_distance(&nearest.downstream),
+        )
+    }
+
+    #[rstest::rstest]
+    #[case::between("1", 3000, Some(("a", -1000)), Some(("b", 2000)))]
+    #[case::overlapping("1", 5800, Some(("a", -3800)), None)]
+    #[case::adjacent("1", 2001, Some(("a", -1)), Some(("b", 2999)))]
+    #[case::contig_start("1", 1, None, Some(("a", 999)))]
+    #[case::contig_end("1", 20000, Some(("c", -11000)), None)]
+    #[case::first_contig_after_previous("2", 50, None, Some(("d", 50)))]
+    #[case::past_last_key("2", 1000, Some(("d", -800)), None)]
+    #[case::contig_x_end("X", 5000, Some(("e", -1000)), None)]
+    #[case::contig_x_start("X", 1, None, Some(("e", 2999)))]
+    #[case::no_features("Y", 1000, None, None)]
+    fn nearest_features(
+        #[case] chrom: &str,
+        #[case] pos: i32,
+        #[case] upstream: Option<(&str, i32)>,
+        #[case] downstream: Option<(&str, i32)>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = index_db(&tmp_dir, FEATURES)?;
+        let cf = db.cf_handle("index").unwrap();
+
+        let nearest = nearest(&db, &cf, chrom, pos, DEFAULT_MAX_WINDOW)?;
+
+        assert_eq!(ids_and_distances(&nearest), (upstream, downstream));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nearest_feature_coordinates() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = index_db(&tmp_dir, FEATURES)?;
+        let cf = db.cf_handle("index").unwrap();
+
+        assert_eq!(
+            nearest(&db, &cf, "1", 3000, DEFAULT_MAX_WINDOW)?,
+            NearestFeatures {
+                upstream: Some(NearestFeature {
+                    id: "a".into(),
+                    start: 1000,
+                    stop: 2000,
+                    distance: -1000,
+                }),
+                downstream: Some(NearestFeature {
+                    id: "b".into(),
+                    start: 5000,
+                    stop: 6000,
+                    distance: 2000,
+                }),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::both_outside(999, None, None)]
+    #[case::at_window(1000, Some(("a", -1000)), None)]
+    #[case::both_inside(2000, Some(("a", -1000)), Some(("b", 2000)))]
+    fn nearest_features_max_window(
+        #[case] max_window: u32,
+        #[case] upstream: Option<(&str, i32)>,
+        #[case] downstream: Option<(&str, i32)>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = index_db(&tmp_dir, FEATURES)?;
+        let cf = db.cf_handle("index").unwrap();
+
+        let nearest = nearest(&db, &cf, "1", 3000, max_window)?;
+
+        assert_eq!(ids_and_distances(&nearest), (upstream, downstream));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nearest_features_empty_index() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = index_db(&tmp_dir, &[])?;
+        let cf = db.cf_handle("index").unwrap();
+
+        assert_eq!(
+            nearest(&db, &cf, "1", 1000, DEFAULT_MAX_WINDOW)?,
+            NearestFeatures::default()
+        );
+
+        Ok(())
+    }
+}
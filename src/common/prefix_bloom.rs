@@ -0,0 +1,234 @@
+//! Prefix extractor and bloom filters for faster point lookups.
+//!
+//! Point lookups in large variant databases spend a considerable part of their time in the
+//! index blocks of SST files that do not contain the key.  With `--prefix-bloom`, the
+//! importers configure a fixed-length prefix extractor covering the chromosome and position
+//! part of the `keys::Pos` and `keys::Var` encodings together with whole-key and prefix
+//! bloom filters, such that most of these SST files are skipped.
+//!
+//! RocksDB only uses the filters if the same extractor and filter policy are configured when
+//! opening the database.  The importers thus write `prefix-bloom = true` into the `meta`
+//! column family and the server opens such databases with matching options.  Databases
+//! built without the flag are opened and read as before.
+
+use super::keys;
+
+/// Name of the meta value that flags databases built with prefix bloom filters.
+pub const META_PREFIX_BLOOM: &str = "prefix-bloom";
+
+/// Length of the prefix, the encoded chromosome (2 bytes) and position (4 bytes).
+pub const PREFIX_LEN: usize = 6;
+
+/// Number of bits per key of the bloom filters.
+const BLOOM_BITS_PER_KEY: f64 = 10.0;
+
+/// Fraction of the write buffer size to use for the memtable prefix bloom filter.
+const MEMTABLE_PREFIX_BLOOM_RATIO: f64 = 0.1;
+
+/// Configure `options` and `block_opts` with the prefix extractor and bloom filters.
+///
+/// `block_opts` must then be set as the block-based table factory of `options`.
+pub fn configure(options: &mut rocksdb::Options, block_opts: &mut rocksdb::BlockBasedOptions) {
+    options.set_prefix_extractor(rocksdb::SliceTransform::create_fixed_prefix(PREFIX_LEN));
+    options.set_memtable_prefix_bloom_ratio(MEMTABLE_PREFIX_BLOOM_RATIO);
+    block_opts.set_bloom_filter(BLOOM_BITS_PER_KEY, false);
+    block_opts.set_whole_key_filtering(true);
+}
+
+/// Return `options` for writing a database with prefix bloom filters.
+///
+/// To be applied after `rocksdb_utils_lookup::tune_options()`.
+pub fn tune_options(mut options: rocksdb::Options) -> rocksdb::Options {
+    let mut block_opts = rocksdb::BlockBasedOptions::default();
+    configure(&mut options, &mut block_opts);
+    options.set_block_based_table_factory(&block_opts);
+    options
+}
+
+/// Write the `prefix-bloom` flag to the meta column family.
+pub fn write_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_meta: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+) -> Result<(), anyhow::Error> {
+    db.put_cf(cf_meta, META_PREFIX_BLOOM, "true")
+        .map_err(|e| anyhow::anyhow!("problem writing meta:{}: {}", META_PREFIX_BLOOM, e))
+}
+
+/// Read the `prefix-bloom` flag from the meta column family.
+pub fn read_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_meta: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+) -> Result<bool, anyhow::Error> {
+    Ok(db
+        .get_cf(cf_meta, META_PREFIX_BLOOM)
+        .map_err(|e| anyhow::anyhow!("problem reading meta:{}: {}", META_PREFIX_BLOOM, e))?
+        .map(|value| value == b"true")
+        .unwrap_or(false))
+}
+
+/// Return the read options for scanning the keys from `start` to `stop`.
+///
+/// Scans within one position only visit the keys with the prefix of `start` and can use
+/// the prefix bloom filters, all other scans use the total order of the keys.  Without a
+/// prefix extractor, both are equivalent to the default read options.  In both cases, the
+/// iterator is bounded by the keys of `start` and the position after `stop` such that
+/// RocksDB does not read blocks beyond the range, e.g., of the next contig.
+pub fn scan_read_options(start: &keys::Pos, stop: &keys::Pos) -> rocksdb::ReadOptions {
+    let mut read_opts = rocksdb::ReadOptions::default();
+    if start.with_key_as_chrom() == stop.with_key_as_chrom() {
+        read_opts.set_prefix_same_as_start(true);
+    } else {
+        read_opts.set_total_order_seek(true);
+    }
+    let lower: Vec<u8> = start.clone().into();
+    read_opts.set_iterate_lower_bound(lower);
+    if let Some(pos) = (stop.pos as u32).checked_add(1) {
+        let mut upper = keys::chrom_name_to_key(&stop.chrom).into_bytes();
+        upper.extend_from_slice(&pos.to_be_bytes());
+        read_opts.set_iterate_upper_bound(upper);
+    }
+    read_opts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Db = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
+
+    /// Write a database with `n_positions` positions on chr1, with two variants each.
+    fn write_db(path: &std::path::Path, prefix_bloom: bool, n_positions: i32) -> Db {
+        let options = rocksdb_utils_lookup::tune_options(rocksdb::Options::default(), None);
+        let options = if prefix_bloom {
+            tune_options(options)
+        } else {
+            options
+        };
+        let cf_names = &["meta", "data"];
+        let db: Db = rocksdb::DB::open_cf_with_opts(
+            &options,
+            path,
+            cf_names
+                .iter()
+                .map(|name| (name.to_string(), options.clone()))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let cf_meta = db.cf_handle("meta").unwrap();
+        if prefix_bloom {
+            write_meta(&db, &cf_meta).unwrap();
+        }
+        let cf_data = db.cf_handle("data").unwrap();
+        for pos in 1..=n_positions {
+            for alt in ["C", "G"] {
+                let key: Vec<u8> = keys::Var::from("1", pos * 10, "A", alt).into();
+                db.put_cf(&cf_data, key, alt).unwrap();
+            }
+        }
+        rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, None, true).unwrap();
+        db
+    }
+
+    /// Open the database at `path` read-only, with the prefix bloom options if `prefix_bloom`.
+    fn open_db(path: &std::path::Path, prefix_bloom: bool) -> Db {
+        let mut options = rocksdb::Options::default();
+        let mut block_opts = rocksdb::BlockBasedOptions::default();
+        if prefix_bloom {
+            configure(&mut options, &mut block_opts);
+        }
+        options.set_block_based_table_factory(&block_opts);
+        rocksdb::DB::open_cf_for_read_only(&options, path, ["meta", "data"], true).unwrap()
+    }
+
+    /// Return the values from scanning the data from `start` to `stop`.
+    fn scan(db: &Db, start: keys::Pos, stop: keys::Pos) -> Vec<String> {
+        let cf_data = db.cf_handle("data").unwrap();
+        let mut iter = db.raw_iterator_cf_opt(&cf_data, scan_read_options(&start, &stop));
+        let start_key: Vec<u8> = start.into();
+        iter.seek(&start_key);
+        let mut result = Vec::new();
+        while iter.valid() {
+            let iter_pos: keys::Pos = iter.key().unwrap().into();
+            if iter_pos.pos > stop.pos {
+                break;
+            }
+            result.push(format!(
+                "{}:{}",
+                iter_pos.pos,
+                String::from_utf8_lossy(iter.value().unwrap())
+            ));
+            iter.next();
+        }
+        result
+    }
+
+    #[rstest::rstest]
+    #[case(false)]
+    #[case(true)]
+    fn meta_roundtrip(#[case] prefix_bloom: bool) {
+        let tmp_dir = temp_testdir::TempDir::default();
+        drop(write_db(tmp_dir.as_ref(), prefix_bloom, 10));
+
+        let db = open_db(tmp_dir.as_ref(), prefix_bloom);
+        let cf_meta = db.cf_handle("meta").unwrap();
+
+        assert_eq!(read_meta(&db, &cf_meta).unwrap(), prefix_bloom);
+    }
+
+    #[rstest::rstest]
+    #[case::without(false, false)]
+    #[case::with(true, true)]
+    #[case::with_opened_without(true, false)]
+    fn point_lookups_and_scans(#[case] write_prefix_bloom: bool, #[case] open_prefix_bloom: bool) {
+        let tmp_dir = temp_testdir::TempDir::default();
+        drop(write_db(tmp_dir.as_ref(), write_prefix_bloom, 100));
+
+        let db = open_db(tmp_dir.as_ref(), open_prefix_bloom);
+        let cf_data = db.cf_handle("data").unwrap();
+        let get = |pos: i32, alt: &str| {
+            let key: Vec<u8> = keys::Var::from("1", pos, "A", alt).into();
+            db.get_cf(&cf_data, key)
+                .unwrap()
+                .map(|value| String::from_utf8(value).unwrap())
+        };
+
+        assert_eq!(get(500, "G"), Some("G".to_string()));
+        assert_eq!(get(500, "T"), None);
+        assert_eq!(get(505, "G"), None);
+        assert_eq!(
+            scan(&db, keys::Pos::from("1", 500), keys::Pos::from("1", 500)),
+            vec!["500:C", "500:G"]
+        );
+        assert_eq!(
+            scan(&db, keys::Pos::from("1", 505), keys::Pos::from("1", 505)),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            scan(&db, keys::Pos::from("1", 495), keys::Pos::from("chr1", 520)),
+            vec!["500:C", "500:G", "510:C", "510:G", "520:C", "520:G"]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(false)]
+    #[case(true)]
+    fn scans_bounded(#[case] prefix_bloom: bool) {
+        let tmp_dir = temp_testdir::TempDir::default();
+        drop(write_db(tmp_dir.as_ref(), prefix_bloom, 100));
+
+        let db = open_db(tmp_dir.as_ref(), prefix_bloom);
+        let cf_data = db.cf_handle("data").unwrap();
+        let start = keys::Pos::from("1", 495);
+        let stop = keys::Pos::from("1", 510);
+        let mut iter = db.raw_iterator_cf_opt(&cf_data, scan_read_options(&start, &stop));
+
+        // The iterator stops at the bounds without checking the positions.
+        iter.seek_to_first();
+        let mut positions = Vec::new();
+        while let Some(key) = iter.key() {
+            positions.push(keys::Pos::from(key).pos);
+            iter.next();
+        }
+        assert_eq!(positions, vec![500, 500, 510, 510]);
+    }
+}
@@ -0,0 +1,179 @@
+//! Record counts and key ranges of annotation databases.
+//!
+//! Counting the records of a database at request time needs a full scan, which takes far too
+//! long for genome-wide databases.  Instead, the importers write the number of records and
+//! the first and last key of their data column families into the `meta` column family at the
+//! end of the import.  The count is RocksDB's `rocksdb.estimate-num-keys` property, which is
+//! exact after the final compaction as no overwritten or deleted entries remain.  The keys
+//! are written as `CHROM:POS[:REF:ALT]` and compared in key order, so `X` and `Y` come
+//! before the autosomes.
+//!
+//! Databases written by older versions do not have these meta values.
+
+use super::keys;
+
+/// Name of the meta value with the number of records.
+pub const META_RECORD_COUNT: &str = "record-count";
+/// Name of the meta value with the first key.
+pub const META_KEY_RANGE_FIRST: &str = "key-range-first";
+/// Name of the meta value with the last key.
+pub const META_KEY_RANGE_LAST: &str = "key-range-last";
+
+/// Record count and key range of a database, `None` if not known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordStats {
+    /// Number of records in the data column families.
+    pub record_count: Option<u64>,
+    /// First key in the data column families.
+    pub key_range_first: Option<String>,
+    /// Last key in the data column families.
+    pub key_range_last: Option<String>,
+}
+
+/// Return the `keys::Pos` or `keys::Var` encoded `key` as `CHROM:POS[:REF:ALT]`.
+///
+/// Keys in other encodings are returned as UTF-8 or, failing that, in hex.
+pub fn key_to_string(key: &[u8]) -> String {
+    let is_pos_key = key.len() >= 6
+        && key[..2]
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || *c == b' ');
+    match std::str::from_utf8(key.get(6..).unwrap_or_default()) {
+        Ok(rest) if is_pos_key => {
+            let pos = keys::Pos::from(key);
+            match rest.split_once('>') {
+                Some((reference, alternative)) => {
+                    format!("{}:{}:{}:{}", pos.chrom, pos.pos, reference, alternative)
+                }
+                None if rest.is_empty() => format!("{}:{}", pos.chrom, pos.pos),
+                None => format!("{}:{}:{}", pos.chrom, pos.pos, rest),
+            }
+        }
+        _ => match std::str::from_utf8(key) {
+            Ok(key) => key.to_string(),
+            Err(_) => key.iter().map(|b| format!("{:02x}", b)).collect(),
+        },
+    }
+}
+
+/// Compute the record count and key range of the column families `cf_data_names` and write
+/// them to the `meta` column family.
+///
+/// Must be called after the final compaction for the count to be exact.
+pub fn write_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_names: &[&str],
+) -> Result<RecordStats, anyhow::Error> {
+    let mut record_count = 0;
+    let mut first: Option<Vec<u8>> = None;
+    let mut last: Option<Vec<u8>> = None;
+    for cf_name in cf_data_names {
+        let cf = db
+            .cf_handle(cf_name)
+            .ok_or_else(|| anyhow::anyhow!("column family {} not found", cf_name))?;
+        record_count += db
+            .property_int_value_cf(&cf, "rocksdb.estimate-num-keys")
+            .map_err(|e| anyhow::anyhow!("problem counting records of {}: {}", cf_name, e))?
+            .unwrap_or_default();
+
+        let mut iter = db.raw_iterator_cf(&cf);
+        iter.seek_to_first();
+        if let Some(key) = iter.key() {
+            if first.as_deref().map_or(true, |first| key < first) {
+                first = Some(key.to_vec());
+            }
+        }
+        iter.seek_to_last();
+        if let Some(key) = iter.key() {
+            if last.as_deref().map_or(true, |last| key > last) {
+                last = Some(key.to_vec());
+            }
+        }
+        iter.status()?;
+    }
+
+    let stats = RecordStats {
+        record_count: Some(record_count),
+        key_range_first: first.as_deref().map(key_to_string),
+        key_range_last: last.as_deref().map(key_to_string),
+    };
+    tracing::info!(
+        "  {} records from {:?} to {:?}",
+        record_count,
+        stats.key_range_first.as_deref().unwrap_or_default(),
+        stats.key_range_last.as_deref().unwrap_or_default()
+    );
+
+    let cf_meta = db.cf_handle("meta").unwrap();
+    db.put_cf(&cf_meta, META_RECORD_COUNT, record_count.to_string())?;
+    for (name, value) in [
+        (META_KEY_RANGE_FIRST, &stats.key_range_first),
+        (META_KEY_RANGE_LAST, &stats.key_range_last),
+    ] {
+        if let Some(value) = value {
+            db.put_cf(&cf_meta, name, value)?;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Read the record count and key range from the `meta` column family.
+pub fn read_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<RecordStats, anyhow::Error> {
+    Ok(RecordStats {
+        record_count: rocksdb_utils_lookup::fetch_meta(db, META_RECORD_COUNT)?
+            .map(|value| value.parse())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("invalid meta:{}: {}", META_RECORD_COUNT, e))?,
+        key_range_first: rocksdb_utils_lookup::fetch_meta(db, META_KEY_RANGE_FIRST)?,
+        key_range_last: rocksdb_utils_lookup::fetch_meta(db, META_KEY_RANGE_LAST)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn key_to_string_keys() {
+        let var: Vec<u8> = keys::Var::from("chrX", 12345, "A", "AT").into();
+        assert_eq!(key_to_string(&var), "X:12345:A:AT");
+        let pos: Vec<u8> = keys::Pos::from("17", 41267746).into();
+        assert_eq!(key_to_string(&pos), "17:41267746");
+        assert_eq!(key_to_string(b"HGNC:1097"), "HGNC:1097");
+        assert_eq!(key_to_string(&[0xff, 0x01]), "ff01");
+    }
+
+    #[test]
+    fn write_and_read_meta() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, tmp_dir.join("db"), ["meta", "first", "second"])?;
+
+        // Legacy databases have no record count and key range.
+        assert_eq!(read_meta(&db)?, RecordStats::default());
+
+        for (cf_name, chrom, pos) in [("first", "2", 100), ("first", "1", 200), ("second", "3", 1)]
+        {
+            let cf = db.cf_handle(cf_name).unwrap();
+            let key: Vec<u8> = keys::Var::from(chrom, pos, "G", "A").into();
+            db.put_cf(&cf, key, b"value")?;
+        }
+        rocksdb_utils_lookup::force_compaction_cf(&db, ["first", "second"], None, true)?;
+
+        let expected = RecordStats {
+            record_count: Some(3),
+            key_range_first: Some("1:200:G:A".to_string()),
+            key_range_last: Some("3:1:G:A".to_string()),
+        };
+        assert_eq!(write_meta(&db, &["first", "second"])?, expected);
+        assert_eq!(read_meta(&db)?, expected);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+//! Querying a running annonars server instead of a local RocksDB.
+//!
+//! The variant query sub commands accept `--server-url` as an alternative to `--path-rocksdb`.
+//! The variant is then fetched from the server's `/annos/variant` endpoint and the section of
+//! the respective database is written out.  The HTTP client is only available with the
+//! `http-client` feature.
+
+use crate::{
+    common::{self, spdi},
+    cons::cli::args::vars::ArgsQuery,
+};
+
+/// Build the `/annos/variant` query parameters for `variant`.
+///
+/// The genome release is taken from the variant, e.g., `GRCh37:1:123:A:G`.
+pub fn variant_query_params(
+    variant: &spdi::Var,
+) -> Result<Vec<(&'static str, String)>, anyhow::Error> {
+    let (genome_release, chromosome) = variant.sequence.rsplit_once(':').ok_or_else(|| {
+        anyhow::anyhow!(
+            "the variant must include the genome release with --server-url, e.g., GRCh37:1:123:A:G"
+        )
+    })?;
+    Ok(vec![
+        ("genome_release", genome_release.to_lowercase()),
+        ("chromosome", common::cli::canonicalize(chromosome)),
+        ("pos", variant.position.to_string()),
+        ("reference", variant.deletion.clone()),
+        ("alternative", variant.insertion.clone()),
+    ])
+}
+
+/// Extract the `section` of the result from an `/annos/variant` response.
+///
+/// Returns `None` if the server has no record for the variant in the database (or does not
+/// serve the database).
+pub fn extract_section(
+    mut response: serde_json::Value,
+    section: &str,
+) -> Result<Option<serde_json::Value>, anyhow::Error> {
+    let result = response
+        .get_mut("result")
+        .ok_or_else(|| anyhow::anyhow!("invalid response from annonars server: missing result"))?;
+    match result.get_mut(section).map(serde_json::Value::take) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => Ok(Some(value)),
+    }
+}
+
+/// Fetch the `/annos/variant` response for `variant` from the server at `server_url`.
+#[cfg(feature = "http-client")]
+pub fn fetch_variant(
+    server_url: &str,
+    variant: &spdi::Var,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let url = format!("{}/annos/variant", server_url.trim_end_matches('/'));
+    let params = variant_query_params(variant)?;
+    tracing::debug!("querying {} with {:?}", &url, &params);
+
+    let response = reqwest::blocking::Client::new()
+        .get(&url)
+        .query(&params)
+        .send()
+        .map_err(|e| {
+            if e.is_connect() {
+                anyhow::anyhow!(
+                    "could not connect to annonars server at {}: {}",
+                    server_url,
+                    e
+                )
+            } else if e.is_timeout() {
+                anyhow::anyhow!(
+                    "request to annonars server at {} timed out: {}",
+                    server_url,
+                    e
+                )
+            } else {
+                anyhow::anyhow!("problem querying annonars server at {}: {}", server_url, e)
+            }
+        })?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        anyhow::bail!(
+            "annonars server at {} returned HTTP {}: {}",
+            server_url,
+            status,
+            body
+        );
+    }
+
+    response.json().map_err(|e| {
+        anyhow::anyhow!(
+            "invalid response from annonars server at {}: {}",
+            server_url,
+            e
+        )
+    })
+}
+
+/// Fetch the `/annos/variant` response for `variant` from the server at `server_url`.
+#[cfg(not(feature = "http-client"))]
+pub fn fetch_variant(
+    server_url: &str,
+    _variant: &spdi::Var,
+) -> Result<serde_json::Value, anyhow::Error> {
+    anyhow::bail!(
+        "cannot query annonars server at {}: annonars was built without the `http-client` feature",
+        server_url
+    )
+}
+
+/// Query the server at `server_url` and write the record from `section` to `out_file`.
+///
+/// Only variant queries are supported as the server has no endpoint for position or range
+/// queries of the single databases.
+pub fn run_query(
+    server_url: &str,
+    query: &ArgsQuery,
+    section: &str,
+    out_file: &str,
+    out_format: common::cli::OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let variant = query
+        .variant
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("only --variant queries are supported with --server-url"))?;
+
+    // Obtain writer to output.
+    let genome_release = variant
+        .sequence
+        .rsplit_once(':')
+        .map(|(genome_release, _)| genome_release);
+    let mut out_writer =
+        common::output::RecordWriter::new(out_file, out_format, section, genome_release)?;
+
+    tracing::info!("Running query against {} ...", server_url);
+    let before_query = std::time::Instant::now();
+    if let Some(value) = extract_section(fetch_variant(server_url, variant)?, section)? {
+        out_writer.write(&common::output::var_key(variant, None)?, &value)?;
+    } else {
+        tracing::info!("no record found for variant {:?}", &variant);
+    }
+    tracing::info!("... done querying in {:?}", before_query.elapsed());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn variant_query_params_with_release() -> Result<(), anyhow::Error> {
+        let params = variant_query_params(&spdi::Var::from_str("GRCh37:chr1:69094:G:T")?)?;
+
+        assert_eq!(
+            params,
+            vec![
+                ("genome_release", "grch37".to_string()),
+                ("chromosome", "1".to_string()),
+                ("pos", "69094".to_string()),
+                ("reference", "G".to_string()),
+                ("alternative", "T".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn variant_query_params_without_release() -> Result<(), anyhow::Error> {
+        assert!(variant_query_params(&spdi::Var::from_str("1:69094:G:T")?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn extract_section_found_and_missing() -> Result<(), anyhow::Error> {
+        let response = serde_json::json!({
+            "server_version": "0.0.0",
+            "result": {"dbsnp": {"rs_id": 1}, "helixmtdb": null}
+        });
+
+        assert_eq!(
+            extract_section(response.clone(), "dbsnp")?,
+            Some(serde_json::json!({"rs_id": 1}))
+        );
+        assert_eq!(extract_section(response.clone(), "helixmtdb")?, None);
+        assert_eq!(extract_section(response, "clinvar")?, None);
+        assert!(extract_section(serde_json::json!({}), "dbsnp").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_query_requires_variant() {
+        let err = run_query(
+            "http://127.0.0.1:1",
+            &ArgsQuery {
+                all: true,
+                ..Default::default()
+            },
+            "dbsnp",
+            "-",
+            common::cli::OutputFormat::Jsonl,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "only --variant queries are supported with --server-url"
+        );
+    }
+
+    #[cfg(feature = "http-client")]
+    #[test]
+    fn fetch_variant_connection_refused() -> Result<(), anyhow::Error> {
+        let err = fetch_variant(
+            "http://127.0.0.1:1",
+            &spdi::Var::from_str("GRCh37:1:69094:G:T")?,
+        )
+        .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .starts_with("could not connect to annonars server at http://127.0.0.1:1"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+//! Deterministic sampling of variant records at import time.
+//!
+//! With `--sample-fraction` or `--sample-every-nth`, the variant importers only write a
+//! subset of the records, e.g., for building small databases for testing.  Whether a record
+//! is kept only depends on a stable hash of its encoded `keys::Var` key, such that databases
+//! built from different sources with the same parameters contain the same variants and can be
+//! joined.  The parameters are written to the `meta` column family as `sample-fraction` and
+//! `sample-every-nth`; queries against sampled databases work as for full ones.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Name of the meta value with the sampled fraction.
+pub const META_SAMPLE_FRACTION: &str = "sample-fraction";
+
+/// Name of the meta value with the sampling period.
+pub const META_SAMPLE_EVERY_NTH: &str = "sample-every-nth";
+
+/// Command line arguments for sampling records.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ArgsSample {
+    /// Only import the given fraction (between 0 and 1) of the variants, selected by a hash
+    /// of the variant such that the same variants are kept in all databases.
+    #[arg(long, conflicts_with = "sample_every_nth")]
+    pub sample_fraction: Option<f64>,
+    /// Only import about every N-th variant, selected by a hash of the variant such that the
+    /// same variants are kept in all databases.
+    #[arg(long)]
+    pub sample_every_nth: Option<u64>,
+}
+
+/// Selection of records to keep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Keep all records.
+    All,
+    /// Keep the records whose hash falls into the given fraction of the hash space.
+    Fraction(f64),
+    /// Keep the records whose hash is divisible by the given number.
+    EveryNth(u64),
+}
+
+/// Decides which records to keep and counts the kept and total records.
+///
+/// The counters are atomic as the importers process windows in parallel.  Records of windows
+/// that are retried are counted again.
+#[derive(Debug)]
+pub struct Sampler {
+    /// Selection of records to keep.
+    mode: Mode,
+    /// Number of records kept.
+    kept: AtomicU64,
+    /// Number of records seen.
+    total: AtomicU64,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            mode: Mode::All,
+            kept: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Sampler {
+    /// Construct from the command line arguments.
+    pub fn new(args: &ArgsSample) -> Result<Self, anyhow::Error> {
+        let mode = match (args.sample_fraction, args.sample_every_nth) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("--sample-fraction and --sample-every-nth cannot be used together")
+            }
+            (Some(fraction), None) => {
+                if !(fraction > 0.0 && fraction <= 1.0) {
+                    anyhow::bail!("--sample-fraction must be in (0, 1], was {}", fraction);
+                }
+                Mode::Fraction(fraction)
+            }
+            (None, Some(n)) => {
+                if n == 0 {
+                    anyhow::bail!("--sample-every-nth must be at least 1");
+                }
+                Mode::EveryNth(n)
+            }
+            (None, None) => Mode::All,
+        };
+        Ok(Self {
+            mode,
+            ..Default::default()
+        })
+    }
+
+    /// Return whether the record with the encoded `keys::Var` `key` is to be written.
+    pub fn keep(&self, key: &[u8]) -> bool {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        let keep = match self.mode {
+            Mode::All => true,
+            // Use the upper 53 bits such that the conversion to `f64` is exact.
+            Mode::Fraction(fraction) => ((hash(key) >> 11) as f64) < fraction * (1u64 << 53) as f64,
+            Mode::EveryNth(n) => hash(key) % n == 0,
+        };
+        if keep {
+            self.kept.fetch_add(1, Ordering::Relaxed);
+        }
+        keep
+    }
+
+    /// Return the number of kept records.
+    pub fn kept(&self) -> u64 {
+        self.kept.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of records seen.
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Log the number of kept and total records if sampling.
+    pub fn log(&self) {
+        if self.mode != Mode::All {
+            tracing::info!(
+                "Sampling kept {} of {} records ({:?})",
+                self.kept(),
+                self.total(),
+                self.mode
+            );
+        }
+    }
+
+    /// Write the sampling parameters to the meta column family if sampling.
+    pub fn write_meta(
+        &self,
+        db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+        cf_meta: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+    ) -> Result<(), anyhow::Error> {
+        let (name, value) = match self.mode {
+            Mode::All => return Ok(()),
+            Mode::Fraction(fraction) => (META_SAMPLE_FRACTION, fraction.to_string()),
+            Mode::EveryNth(n) => (META_SAMPLE_EVERY_NTH, n.to_string()),
+        };
+        db.put_cf(cf_meta, name, value)
+            .map_err(|e| anyhow::anyhow!("problem writing meta:{}: {}", name, e))
+    }
+}
+
+/// Stable 64 bit hash of `key`.
+///
+/// This is FNV-1a followed by the finalizer of MurmurHash3 for mixing the lower bits.  The
+/// hashes must not change between versions or platforms, so `std::hash` is not used.
+fn hash(key: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key {
+        h ^= u64::from(*byte);
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::common::keys;
+
+    /// Encoded keys of 10,000 synthetic variants.
+    fn keys() -> Vec<Vec<u8>> {
+        (1..=10_000)
+            .map(|pos| keys::Var::from("1", pos, "A", "G").into())
+            .collect()
+    }
+
+    fn kept_keys(sampler: &Sampler) -> Vec<Vec<u8>> {
+        keys().into_iter().filter(|key| sampler.keep(key)).collect()
+    }
+
+    #[test]
+    fn hash_stable() {
+        let key: Vec<u8> = keys::Var::from("1", 100, "A", "G").into();
+        assert_eq!(hash(&key), 0xa194_dd6f_7922_be68);
+    }
+
+    #[test]
+    fn keep_all() -> Result<(), anyhow::Error> {
+        let sampler = Sampler::new(&ArgsSample::default())?;
+
+        assert_eq!(kept_keys(&sampler).len(), 10_000);
+        assert_eq!((sampler.kept(), sampler.total()), (10_000, 10_000));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::fraction(ArgsSample { sample_fraction: Some(0.1), sample_every_nth: None })]
+    #[case::every_nth(ArgsSample { sample_fraction: None, sample_every_nth: Some(10) })]
+    fn keep_deterministic(#[case] args: ArgsSample) -> Result<(), anyhow::Error> {
+        let first = kept_keys(&Sampler::new(&args)?);
+        let second = kept_keys(&Sampler::new(&args)?);
+
+        assert_eq!(first, second);
+        assert!((800..1200).contains(&first.len()), "{}", first.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn keep_fraction_nested() -> Result<(), anyhow::Error> {
+        let small = kept_keys(&Sampler::new(&ArgsSample {
+            sample_fraction: Some(0.01),
+            ..Default::default()
+        })?);
+        let large = kept_keys(&Sampler::new(&ArgsSample {
+            sample_fraction: Some(0.1),
+            ..Default::default()
+        })?);
+
+        assert!(small.iter().all(|key| large.contains(key)));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::fraction_zero(ArgsSample { sample_fraction: Some(0.0), sample_every_nth: None })]
+    #[case::fraction_above_one(ArgsSample { sample_fraction: Some(1.5), sample_every_nth: None })]
+    #[case::every_zeroth(ArgsSample { sample_fraction: None, sample_every_nth: Some(0) })]
+    #[case::both(ArgsSample { sample_fraction: Some(0.1), sample_every_nth: Some(10) })]
+    fn new_invalid(#[case] args: ArgsSample) {
+        assert!(Sampler::new(&args).is_err());
+    }
+}
@@ -8,6 +8,42 @@
 
 use std::{fmt::Display, str::FromStr};
 
+use super::cli::{check_position, GenomeRelease};
+
+/// Parse the 1-based position `value` on `sequence`, `what` is used in error messages.
+///
+/// With a `<release>:` prefix on the sequence, e.g., `grch38:1`, the position is checked
+/// against the length of the chromosome in the release.
+fn parse_position(sequence: &str, value: &str, what: &str) -> Result<i32, anyhow::Error> {
+    let position = value
+        .parse::<i64>()
+        .map_err(|e| anyhow::anyhow!("Could not parse {}: {}", what, e))?;
+    if let Some((genome_release, chrom)) = sequence.rsplit_once(':') {
+        if let Ok(genome_release) = genome_release.to_lowercase().parse::<GenomeRelease>() {
+            check_position(
+                genome_release,
+                chrom,
+                usize::try_from(position).unwrap_or_default(),
+            )?;
+        }
+    }
+    value
+        .parse::<i32>()
+        .map_err(|e| anyhow::anyhow!("Could not parse {}: {}", what, e))
+}
+
+/// Split `spdi` from the right into the `N` colon-separated parts of `format`.
+fn split_parts<'a, const N: usize>(
+    spdi: &'a str,
+    format: &str,
+) -> Result<[&'a str; N], anyhow::Error> {
+    let mut parts = spdi.rsplitn(N, ':').collect::<Vec<_>>();
+    parts.reverse();
+    parts
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid {:?}, expected {}", spdi, format))
+}
+
 /// A variant in in SPDI format.
 ///
 /// The SPDI format is described in [Holmes et al.
@@ -52,20 +88,13 @@ impl FromStr for Var {
     type Err = anyhow::Error;
 
     fn from_str(spdi: &str) -> Result<Self, Self::Err> {
-        let mut parts = spdi.rsplitn(4, ':');
-        let insertion = parts.next().unwrap().to_string();
-        let deletion = parts.next().unwrap().to_string();
-        let position = parts
-            .next()
-            .unwrap()
-            .parse::<i32>()
-            .map_err(|e| anyhow::anyhow!("Could not parse position: {}", e))?;
-        let sequence = parts.next().unwrap().to_string();
+        let [sequence, position, deletion, insertion] =
+            split_parts(spdi, "SEQUENCE:POSITION:DELETION:INSERTION")?;
         Ok(Self {
-            sequence,
-            position,
-            deletion,
-            insertion,
+            position: parse_position(sequence, position, "position")?,
+            sequence: sequence.to_string(),
+            deletion: deletion.to_ascii_uppercase(),
+            insertion: insertion.to_ascii_uppercase(),
         })
     }
 }
@@ -110,14 +139,11 @@ impl FromStr for Pos {
     type Err = anyhow::Error;
 
     fn from_str(spdi: &str) -> Result<Self, Self::Err> {
-        let mut parts = spdi.rsplitn(2, ':');
-        let position = parts
-            .next()
-            .unwrap()
-            .parse::<i32>()
-            .map_err(|e| anyhow::anyhow!("Could not parse position: {}", e))?;
-        let sequence = parts.next().unwrap().to_string();
-        Ok(Self { sequence, position })
+        let [sequence, position] = split_parts(spdi, "SEQUENCE:POSITION")?;
+        Ok(Self {
+            position: parse_position(sequence, position, "position")?,
+            sequence: sequence.to_string(),
+        })
     }
 }
 
@@ -165,21 +191,15 @@ impl FromStr for Range {
     type Err = anyhow::Error;
 
     fn from_str(spdi: &str) -> Result<Self, Self::Err> {
-        let mut parts = spdi.rsplitn(3, ':');
-        let end = parts
-            .next()
-            .unwrap()
-            .parse::<i32>()
+        let [sequence, start, end] = split_parts(spdi, "SEQUENCE:START:END")?;
+        // Ends beyond the chromosome end are clamped, see `cli::extract_chrom::range()`.
+        let end = end
+            .parse::<i64>()
             .map_err(|e| anyhow::anyhow!("Could not parse end position: {}", e))?;
-        let start = parts
-            .next()
-            .unwrap()
-            .parse::<i32>()
-            .map_err(|e| anyhow::anyhow!("Could not parse start position: {}", e))?;
-        let sequence = parts.next().unwrap().to_string();
+        let end = i32::try_from(end).unwrap_or(if end < 0 { i32::MIN } else { i32::MAX });
         Ok(Self {
-            sequence,
-            start,
+            start: parse_position(sequence, start, "start position")?,
+            sequence: sequence.to_string(),
             end,
         })
     }
@@ -229,6 +249,13 @@ mod test {
         assert_eq!(var.insertion, "T");
     }
 
+    #[test]
+    fn var_from_str_lowercase() {
+        let var = Var::from_str("NC_000001.11:123:ac:t").unwrap();
+        assert_eq!(var.deletion, "AC");
+        assert_eq!(var.insertion, "T");
+    }
+
     #[test]
     fn var_display() {
         let var = Var::new(
@@ -276,6 +303,65 @@ mod test {
         assert_eq!(range.end, 456);
     }
 
+    #[rstest::rstest]
+    #[case("grch38:1:248956422:G:A", None)]
+    #[case(
+        "GRCh38:chr1:2491553345:G:A",
+        Some("position 2,491,553,345 exceeds length of chr1 (248,956,422) for GRCh38")
+    )]
+    #[case(
+        "grch37:X:155270561:G:A",
+        Some("position 155,270,561 exceeds length of chrX (155,270,560) for GRCh37")
+    )]
+    #[case(
+        "NC_000001.11:2491553345:G:A",
+        Some("Could not parse position: number too large to fit in target type")
+    )]
+    fn var_from_str_bounds(#[case] spdi: &str, #[case] message: Option<&str>) {
+        assert_eq!(
+            Var::from_str(spdi).err().map(|e| e.to_string()).as_deref(),
+            message
+        );
+    }
+
+    #[test]
+    fn pos_from_str_bounds() {
+        assert_eq!(
+            Pos::from_str("grch37:1:249250622").unwrap_err().to_string(),
+            "position 249,250,622 exceeds length of chr1 (249,250,621) for GRCh37"
+        );
+        assert!(Pos::from_str("grch37:1:249250621").is_ok());
+    }
+
+    #[test]
+    fn range_from_str_bounds() {
+        assert_eq!(
+            Range::from_str("grch38:1:248956423:248956500")
+                .unwrap_err()
+                .to_string(),
+            "position 248,956,423 exceeds length of chr1 (248,956,422) for GRCh38"
+        );
+        // Ends beyond the chromosome end are clamped later on.
+        let range = Range::from_str("grch38:1:248956000:2491553345").unwrap();
+        assert_eq!(range.end, i32::MAX);
+    }
+
+    #[test]
+    fn from_str_too_few_parts() {
+        assert_eq!(
+            Var::from_str("1:123:A").unwrap_err().to_string(),
+            "invalid \"1:123:A\", expected SEQUENCE:POSITION:DELETION:INSERTION"
+        );
+        assert_eq!(
+            Pos::from_str("123").unwrap_err().to_string(),
+            "invalid \"123\", expected SEQUENCE:POSITION"
+        );
+        assert_eq!(
+            Range::from_str("1:123").unwrap_err().to_string(),
+            "invalid \"1:123\", expected SEQUENCE:START:END"
+        );
+    }
+
     #[test]
     fn range_display() {
         let range = Range::new(String::from("NC_000001.11"), 123, 456);
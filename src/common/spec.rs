@@ -0,0 +1,245 @@
+//! Writing of `spec.yaml` version specification files at import time.
+//!
+//! The server reads the `spec.yaml` file in the parent directory of each RocksDB to report
+//! version information.  The importers write it after a successful import, populated from
+//! their command line arguments.  As the parent directory may be shared with other
+//! databases, an existing `spec.yaml` is only overwritten with `--force-spec-yaml`.
+
+use std::path::{Path, PathBuf};
+
+use crate::common::cli::GenomeRelease;
+use crate::server::run::versions::schema::{CreatedFrom, VersionSpec};
+
+/// Command line arguments for writing `spec.yaml`.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ArgsSpec {
+    /// Write `spec.yaml` next to the output RocksDB after a successful import (the default).
+    #[arg(long, overrides_with = "no_write_spec_yaml")]
+    pub write_spec_yaml: bool,
+    /// Do not write `spec.yaml` next to the output RocksDB.
+    #[arg(long, overrides_with = "write_spec_yaml")]
+    pub no_write_spec_yaml: bool,
+    /// Overwrite an existing `spec.yaml` next to the output RocksDB.
+    #[arg(long)]
+    pub force_spec_yaml: bool,
+    /// Version of the imported data for `spec.yaml`, if not given by other arguments.
+    #[arg(long)]
+    pub spec_data_version: Option<String>,
+}
+
+/// Description of one import for building its `spec.yaml`.
+#[derive(Debug, Clone)]
+pub struct ImportSpec {
+    /// Short name of the database, e.g., `helixmtdb`.
+    pub db_name: String,
+    /// Title of the data source, e.g., `HelixMtDb`.
+    pub title: String,
+    /// Genome release, if the database is release-specific.
+    pub genome_release: Option<GenomeRelease>,
+    /// Names and versions of the imported data sources.
+    pub created_from: Vec<(String, String)>,
+    /// Paths of the input files.
+    pub paths_in: Vec<String>,
+}
+
+impl ArgsSpec {
+    /// Return the data version given with `--spec-data-version` or `unknown`.
+    pub fn data_version(&self) -> String {
+        self.spec_data_version
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Check that `spec.yaml` for the RocksDB at `path_out_rocksdb` can be written.
+    ///
+    /// Importers call this before importing so that an existing `spec.yaml` is reported
+    /// before the import rather than after it.
+    pub fn check(&self, path_out_rocksdb: &str) -> Result<(), anyhow::Error> {
+        let path = spec_yaml_path(path_out_rocksdb);
+        if !self.no_write_spec_yaml && !self.force_spec_yaml && path.exists() {
+            anyhow::bail!(
+                "{} already exists, use --force-spec-yaml to overwrite or \
+                 --no-write-spec-yaml to keep it",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Write `spec.yaml` for the RocksDB at `path_out_rocksdb` unless disabled.
+    pub fn write(
+        &self,
+        path_out_rocksdb: &str,
+        import_spec: &ImportSpec,
+    ) -> Result<(), anyhow::Error> {
+        if self.no_write_spec_yaml {
+            tracing::info!("Not writing spec.yaml as requested");
+            return Ok(());
+        }
+        self.check(path_out_rocksdb)?;
+
+        let path = spec_yaml_path(path_out_rocksdb);
+        tracing::info!("Writing {}", path.display());
+        let yaml_str = serde_yaml::to_string(&build_version_spec(import_spec))
+            .map_err(|e| anyhow::anyhow!("problem serializing spec.yaml: {}", e))?;
+        std::fs::write(&path, yaml_str)
+            .map_err(|e| anyhow::anyhow!("problem writing {}: {}", path.display(), e))
+    }
+}
+
+/// Return the path of `spec.yaml` in the parent directory of `path_out_rocksdb`.
+pub fn spec_yaml_path(path_out_rocksdb: &str) -> PathBuf {
+    match Path::new(path_out_rocksdb).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join("spec.yaml"),
+        _ => PathBuf::from("spec.yaml"),
+    }
+}
+
+/// Build the `VersionSpec` for an import.
+///
+/// The version is built from the data source versions and the annonars version, joined
+/// with `+`, as in the hand-written files.
+pub fn build_version_spec(import_spec: &ImportSpec) -> VersionSpec {
+    let version = import_spec
+        .created_from
+        .iter()
+        .map(|(_, version)| version.as_str())
+        .chain(std::iter::once(crate::common::version()))
+        .collect::<Vec<_>>()
+        .join("+");
+    let genome_release = import_spec
+        .genome_release
+        .map(|genome_release| genome_release.to_string());
+    let identifier = match genome_release.as_ref() {
+        Some(genome_release) => format!(
+            "annonars/{}:{}-{}",
+            import_spec.db_name, version, genome_release
+        ),
+        None => format!("annonars/{}:{}", import_spec.db_name, version),
+    };
+    let description = format!(
+        "RocksDB with the information from {} imported using the annonars package v{}.\n\
+         Input files:\n{}",
+        import_spec.title,
+        crate::common::version(),
+        import_spec
+            .paths_in
+            .iter()
+            .map(|path| format!("  - {}\n", path))
+            .collect::<String>()
+    );
+
+    VersionSpec {
+        identifier,
+        title: format!("annonars {} RocksDB Database", import_spec.title),
+        creator: "annonars".to_string(),
+        contributor: None,
+        format: "application/x-rocksdb".to_string(),
+        date: chrono::Local::now().format("%Y%m%d").to_string(),
+        version,
+        genome_release,
+        description,
+        source: import_spec.paths_in.clone(),
+        created_from: import_spec
+            .created_from
+            .iter()
+            .map(|(name, version)| CreatedFrom {
+                name: name.clone(),
+                version: version.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    fn import_spec() -> ImportSpec {
+        ImportSpec {
+            db_name: "helixmtdb".to_string(),
+            title: "HelixMtDb".to_string(),
+            genome_release: Some(GenomeRelease::Grch37),
+            created_from: vec![("HelixMtDb".to_string(), "20200327".to_string())],
+            paths_in: vec!["tests/helixmtdb/example/helixmtdb.vcf.bgz".to_string()],
+        }
+    }
+
+    #[test]
+    fn spec_yaml_path_parent() {
+        assert_eq!(
+            spec_yaml_path("/data/helixmtdb/rocksdb"),
+            PathBuf::from("/data/helixmtdb/spec.yaml")
+        );
+        assert_eq!(spec_yaml_path("rocksdb"), PathBuf::from("spec.yaml"));
+    }
+
+    #[test]
+    fn write_round_trip() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_out_rocksdb = format!("{}", tmp_dir.join("rocksdb").display());
+
+        ArgsSpec::default().write(&path_out_rocksdb, &import_spec())?;
+        let spec = VersionSpec::from_path(tmp_dir.join("spec.yaml"))?;
+
+        assert_eq!(spec.identifier, "annonars/helixmtdb:20200327+0.0.0-grch37");
+        assert_eq!(spec.title, "annonars HelixMtDb RocksDB Database");
+        assert_eq!(spec.version, "20200327+0.0.0");
+        assert_eq!(spec.genome_release.as_deref(), Some("grch37"));
+        assert_eq!(
+            spec.source,
+            vec!["tests/helixmtdb/example/helixmtdb.vcf.bgz".to_string()]
+        );
+        assert_eq!(spec.created_from.len(), 1);
+        assert_eq!(spec.created_from[0].name, "HelixMtDb");
+        assert_eq!(spec.created_from[0].version, "20200327");
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_refuses_overwrite() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_out_rocksdb = format!("{}", tmp_dir.join("rocksdb").display());
+        std::fs::write(tmp_dir.join("spec.yaml"), "other: database\n")?;
+
+        let err = ArgsSpec::default()
+            .write(&path_out_rocksdb, &import_spec())
+            .unwrap_err();
+        assert!(err.to_string().contains("--force-spec-yaml"));
+        assert_eq!(
+            std::fs::read_to_string(tmp_dir.join("spec.yaml"))?,
+            "other: database\n"
+        );
+
+        let args = ArgsSpec {
+            force_spec_yaml: true,
+            ..Default::default()
+        };
+        args.write(&path_out_rocksdb, &import_spec())?;
+        let spec = VersionSpec::from_path(tmp_dir.join("spec.yaml"))?;
+        assert_eq!(spec.version, "20200327+0.0.0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_disabled() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let args = ArgsSpec {
+            no_write_spec_yaml: true,
+            ..Default::default()
+        };
+
+        args.write(
+            &format!("{}", tmp_dir.join("rocksdb").display()),
+            &import_spec(),
+        )?;
+
+        assert!(!tmp_dir.join("spec.yaml").exists());
+
+        Ok(())
+    }
+}
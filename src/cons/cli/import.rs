@@ -5,7 +5,10 @@ use std::sync::Arc;
 use clap::Parser;
 use prost::Message;
 
-use crate::common::{self, keys};
+use crate::{
+    common::{self, keys},
+    cons::per_base,
+};
 
 /// Helper data structures for reading CSV files.
 pub mod reading {
@@ -47,6 +50,18 @@ pub mod reading {
     }
 }
 
+/// Format of the input TSV file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum InputFormat {
+    /// UCSC alignments per transcript and codon, see `reading::Record`.
+    #[default]
+    UcscTranscript,
+    /// Per-base scores with the columns chromosome, position, and score (no header, lines
+    /// starting with `#` are ignored), see `cons::per_base`.
+    PerBase,
+}
+
 /// Command line arguments for `tsv import` sub command.
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import conservation data into RocksDB", long_about = None)]
@@ -60,13 +75,23 @@ pub struct Args {
     /// Path to output RocksDB directory.
     #[arg(long)]
     pub path_out_rocksdb: String,
+    /// Format of the input file.  Per-base scores are always imported into the column
+    /// family `ucsc_conservation_by_pos`.
+    #[arg(long, value_enum, default_value_t = InputFormat::UcscTranscript)]
+    pub input_format: InputFormat,
 
-    /// Name of the column family to import into.
+    /// Name of the column family to import UCSC transcript alignments into.
     #[arg(long, default_value = "ucsc_conservation")]
     pub cf_name: String,
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Utility to make a `Vec<crate::pbs::cons::Record>` unique.
@@ -89,6 +114,20 @@ fn dedup_records(records: &mut Vec<crate::pbs::cons::Record>) {
     })
 }
 
+/// Open the input TSV file for reading with the CSV settings of `args.input_format`.
+///
+/// The file is decompressed if its name ends with `.gz`.
+fn open_csv_reader(args: &Args) -> Result<csv::Reader<Box<dyn std::io::BufRead>>, anyhow::Error> {
+    let reader = common::open_read_maybe_gz(&args.path_in_tsv)?;
+    let mut builder = csv::ReaderBuilder::new();
+    builder.delimiter(b'\t');
+    match args.input_format {
+        InputFormat::UcscTranscript => builder.has_headers(true),
+        InputFormat::PerBase => builder.has_headers(false).comment(Some(b'#')),
+    };
+    Ok(builder.from_reader(reader))
+}
+
 /// Perform import of the TSV file.
 fn tsv_import(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
@@ -96,20 +135,7 @@ fn tsv_import(
 ) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
-    // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if args.path_in_tsv.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            &args.path_in_tsv,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(&args.path_in_tsv)?)
-    };
-
-    // Construct CSV reader.
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .has_headers(true)
-        .from_reader(reader);
+    let mut csv_reader = open_csv_reader(args)?;
 
     // Read through all records.  Collect all at the same position into a `RecordList` and
     // insert these into the database.
@@ -149,12 +175,80 @@ fn tsv_import(
     Ok(())
 }
 
+/// Number of per-base scores to write at once.
+const PER_BASE_BATCH_SIZE: usize = 100_000;
+
+/// Perform import of the per-base scores from the TSV file.
+fn per_base_import(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    let cf_data = db.cf_handle(per_base::CF_NAME).unwrap();
+
+    let mut csv_reader = open_csv_reader(args)?;
+
+    // Write the scores in batches as the files have one line per base.
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut skipped = 0usize;
+    for result in csv_reader.deserialize() {
+        let (chrom, pos, score): (String, i32, f32) = result?;
+        if !common::cli::is_canonical(&chrom) {
+            skipped += 1;
+            continue;
+        }
+
+        let key: Vec<u8> = keys::Pos::from(&chrom, pos).into();
+        batch.put_cf(&cf_data, key, per_base::encode_score(score));
+        if batch.len() >= PER_BASE_BATCH_SIZE {
+            db.write(std::mem::take(&mut batch))?;
+        }
+    }
+    db.write(batch)?;
+
+    if skipped > 0 {
+        tracing::warn!("skipped {} scores on non-canonical contigs", skipped);
+    }
+
+    Ok(())
+}
+
+/// Validate the inputs and output path without writing the database.
+///
+/// The first record of the input file is parsed in the same way as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    validation.read_input(&args.path_in_tsv, |_| {
+        let mut csv_reader = open_csv_reader(args)?;
+        match args.input_format {
+            InputFormat::UcscTranscript => {
+                common::dry_run::first_record(csv_reader.deserialize::<reading::Record>())
+                    .map(|_| ())
+            }
+            InputFormat::PerBase => {
+                common::dry_run::first_record(csv_reader.deserialize::<(String, i32, f32)>())
+                    .map(|_| ())
+            }
+        }
+    });
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `cons import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'cons import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -162,7 +256,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
-    let cf_names = &["meta", &args.cf_name];
+    let cf_data = match args.input_format {
+        InputFormat::UcscTranscript => args.cf_name.as_str(),
+        InputFormat::PerBase => per_base::CF_NAME,
+    };
+    let cf_names = &["meta", cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out_rocksdb)?,
@@ -187,7 +285,10 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Importing TSV files ...");
     let before_import = std::time::Instant::now();
-    tsv_import(&db, args)?;
+    match args.input_format {
+        InputFormat::UcscTranscript => tsv_import(&db, args)?,
+        InputFormat::PerBase => per_base_import(&db, args)?,
+    }
     tracing::info!(
         "... done importing TSV files in {:?}",
         before_import.elapsed()
@@ -201,6 +302,19 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    common::record_stats::write_meta(&db, &[cf_data])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "cons".to_string(),
+            title: "UCSC conservation".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("UCSC conservation".to_string(), args.spec.data_version())],
+            paths_in: vec![args.path_in_tsv.clone()],
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -217,15 +331,85 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
             path_in_tsv: String::from("tests/cons/example/tgds.tsv"),
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            input_format: InputFormat::UcscTranscript,
+            cf_name: String::from("ucsc_conservation"),
+            path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+    }
+
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[rstest::rstest]
+    #[case::ucsc_transcript(InputFormat::UcscTranscript, "tests/cons/example/tgds.tsv")]
+    #[case::per_base(InputFormat::PerBase, "tests/cons/example/per-base.tsv")]
+    fn dry_run_import(#[case] input_format: InputFormat, #[case] path_in_tsv: &str) {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_tsv: String::from(path_in_tsv),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            input_format,
             cf_name: String::from("ucsc_conservation"),
             path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        // The input file does not match the other input format.
+        let args = Args {
+            input_format: match input_format {
+                InputFormat::UcscTranscript => InputFormat::PerBase,
+                InputFormat::PerBase => InputFormat::UcscTranscript,
+            },
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
+
+    #[test]
+    fn smoke_test_import_per_base() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_rocksdb = per_base::test::import_per_base(&tmp_dir);
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &path_rocksdb,
+            ["meta", per_base::CF_NAME],
+            false,
+        )?;
+        let cf_data = db.cf_handle(per_base::CF_NAME).unwrap();
+        // The score on `chrUn_gl000220` is skipped.
+        assert_eq!(
+            db.iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+                .count(),
+            11
+        );
+        let key: Vec<u8> = keys::Pos::from("14", 20000001).into();
+        assert_eq!(
+            db.get_cf(&cf_data, key)?
+                .map(|value| per_base::decode_score(&value))
+                .transpose()?,
+            Some(-2.5)
+        );
+
+        Ok(())
     }
 }
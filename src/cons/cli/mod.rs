@@ -28,6 +28,9 @@ pub mod args {
             /// Query for all variants.
             #[arg(long, group = "query")]
             pub all: bool,
+            /// Read queries from a JSONL file with one query per line, "-" for stdin.
+            #[arg(long, group = "query")]
+            pub path_input_jsonl: Option<String>,
         }
     }
 }
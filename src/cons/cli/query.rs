@@ -1,10 +1,14 @@
 //! Query of UCSC 100 vertebrate conservation data.
 
-use std::sync::Arc;
+use std::{collections::HashMap, ops::ControlFlow, sync::Arc};
 
 use prost::Message;
 
-use crate::common::{self, cli::extract_chrom, keys, spdi};
+use crate::{
+    common::{self, cli::extract_chrom, keys, spdi},
+    cons::per_base,
+    genes::mane::{ManeAnnotation, WithMane},
+};
 
 /// Command line arguments for `cons query` sub command.
 #[derive(clap::Parser, Debug, Clone)]
@@ -20,8 +24,8 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Range to query for (or all).
     #[command(flatten)]
@@ -29,6 +33,16 @@ pub struct Args {
     /// Optional HGNC gene identifier to limit query to.
     #[arg(long)]
     pub hgnc_id: Option<String>,
+    /// Path to genes RocksDB directory to annotate each record with whether its transcript
+    /// is the MANE Select transcript of its gene.
+    #[arg(long)]
+    pub path_genes_rocksdb: Option<String>,
+    /// Only write the records of MANE Select transcripts.
+    #[arg(long, requires = "path_genes_rocksdb")]
+    pub mane_only: bool,
+    /// Number of records to skip and write.
+    #[command(flatten)]
+    pub limit: common::cli::ArgsLimit,
 }
 
 /// Argument group for specifying one of range or all.
@@ -48,9 +62,14 @@ pub struct ArgsQuery {
 pub struct Meta {
     /// Genome release of data in database.
     pub genome_release: String,
+    /// Whether the database has per-base scores rather than UCSC transcript alignments.
+    pub per_base: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
+///
+/// Databases with per-base scores only are opened with their column family instead of
+/// `cf_data`, see `Meta::per_base`.
 pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     path_rocksdb: P,
     cf_data: &str,
@@ -58,22 +77,24 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
     tracing::info!("Opening RocksDB database ...");
     let before_open = std::time::Instant::now();
-    let cf_names = &[cf_meta, cf_data];
+    let cf_data = per_base::data_cf_name(&path_rocksdb, cf_data)?;
+    let cf_names = &[cf_meta, cf_data.as_str()];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
+            per_base: cf_data == per_base::CF_NAME,
         }
     };
 
@@ -93,19 +114,45 @@ pub fn open_rocksdb_from_args(
     open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
 }
 
-/// Print values to `out_writer`.
-fn print_values(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    record: &crate::pbs::cons::Record,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(&record)?)?;
-        }
+/// MANE Select accessions of the genes, read from the genes database on first use.
+struct ManeLookup {
+    /// The genes database.
+    db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    /// MANE Select accessions by HGNC ID.
+    mane_select: HashMap<String, Vec<String>>,
+}
+
+impl ManeLookup {
+    /// Open the genes database at `path`.
+    fn open(path: &str) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            db: crate::genes::cli::query::open_rocksdb(path, "genes", "meta")?,
+            mane_select: HashMap::new(),
+        })
     }
 
-    Ok(())
+    /// Return the MANE annotation of the transcript of `record`.
+    fn annotate(
+        &mut self,
+        record: &crate::pbs::cons::Record,
+    ) -> Result<ManeAnnotation, anyhow::Error> {
+        if !self.mane_select.contains_key(&record.hgnc_id) {
+            let mane_select = match record.hgnc_id.parse() {
+                Ok(hgnc_id) => {
+                    crate::genes::cli::query::query_for_gene(&hgnc_id, &self.db, "genes")?
+                        .and_then(|gene| gene.hgnc)
+                        .map(|hgnc| hgnc.mane_select)
+                        .unwrap_or_default()
+                }
+                Err(_) => Vec::new(),
+            };
+            self.mane_select.insert(record.hgnc_id.clone(), mane_select);
+        }
+        Ok(ManeAnnotation::new(
+            &record.enst_id,
+            &self.mane_select[&record.hgnc_id],
+        ))
+    }
 }
 
 /// Implementation of `cons query` sub command.
@@ -116,24 +163,19 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     // Open the RocksDB database.
     let (db, meta) = open_rocksdb_from_args(args)?;
-    let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "cons",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
     let (start, stop) = if let Some(range) = args.query.range.as_ref() {
-        let range = spdi::Range {
-            sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-            ..range.clone()
-        };
+        let range = extract_chrom::range(range, Some(&meta.genome_release))?;
 
         let (start, stop) = range.into();
         (Some(start), Some(stop))
@@ -142,6 +184,41 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     };
     tracing::debug!("  start = {:?}, stop = {:?}", &start, &stop);
 
+    let mut counter = args.limit.counter();
+    if meta.per_base {
+        if args.hgnc_id.is_some() {
+            anyhow::bail!("--hgnc-id is not supported for per-base conservation scores");
+        }
+        if args.path_genes_rocksdb.is_some() {
+            anyhow::bail!("--path-genes-rocksdb is not supported for per-base conservation scores");
+        }
+        let range = start
+            .zip(stop)
+            .map(|(start, stop)| (start.into(), stop.into()));
+        per_base::scan(&db, per_base::CF_NAME, range, |record| {
+            if counter.admit() {
+                out_writer.write(&format!("{}:{}", record.chrom, record.pos), &record)?;
+            }
+            Ok(if counter.is_done() {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            })
+        })?;
+        tracing::info!("... done querying in {:?}", before_query.elapsed());
+
+        tracing::info!("All done. Have a nice day!");
+        return Ok(());
+    }
+
+    let mut mane_lookup = args
+        .path_genes_rocksdb
+        .as_deref()
+        .map(ManeLookup::open)
+        .transpose()?;
+
+    let cf_data = db.cf_handle(&args.cf_name).unwrap();
+
     // Obtain iterator and seek to start (actually 2 bp before as each alignment column spans
     // one codon).
     let mut iter = db.raw_iterator_cf(&cf_data);
@@ -165,8 +242,8 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         tracing::debug!("  stop = {:?}", &stop);
     }
 
-    // Iterate over all variants until we are behind stop.
-    while iter.valid() {
+    // Iterate over all variants until we are behind stop or reach the limit.
+    while iter.valid() && !counter.is_done() {
         if let Some(value) = iter.value() {
             tracing::trace!("  iterator at {:?} => {:?}", &iter.key(), &value);
 
@@ -202,8 +279,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                 }
 
                 // If we reach here then we have a record that matches the query range and HGNC gene
-                // ID (if given).
-                print_values(&mut out_writer, args.out_format, record)?;
+                // ID (if given).  With the genes database, annotate the record with MANE Select
+                // and skip non-MANE records if requested.
+                let key = format!("{}:{}", record.chrom, record.start);
+                if let Some(mane_lookup) = mane_lookup.as_mut() {
+                    let mane = mane_lookup.annotate(record)?;
+                    if args.mane_only && !mane.mane_select {
+                        continue;
+                    }
+                    if counter.admit() {
+                        out_writer.write(&key, &WithMane { record, mane })?;
+                    }
+                } else if counter.admit() {
+                    out_writer.write(&key, record)?;
+                }
             }
 
             // Proceed to the next database row.
@@ -230,14 +319,33 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_rocksdb: String::from("tests/cons/example/tgds.tsv.db"),
             cf_name: String::from("ucsc_conservation"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             hgnc_id,
+            path_genes_rocksdb: None,
+            mane_only: false,
             query,
+            limit: Default::default(),
+        };
+
+        (common, args, temp)
+    }
+
+    /// Return the arguments for querying the per-base example data imported to `tmp_dir`.
+    fn args_per_base(
+        query: ArgsQuery,
+        hgnc_id: Option<String>,
+        tmp_dir: &TempDir,
+    ) -> (common::cli::Args, Args, TempDir) {
+        let (common, args, temp) = args(query, hgnc_id);
+        let args = Args {
+            path_rocksdb: per_base::test::import_per_base(tmp_dir),
+            ..args
         };
 
         (common, args, temp)
@@ -322,4 +430,217 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn smoke_query_jsonl_v2() -> Result<(), anyhow::Error> {
+        let query = ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:13:95248336:95248351")?),
+            all: false,
+        };
+        let (common, args_v1, _temp_v1) = args(query.clone(), None);
+        run(&common, &args_v1)?;
+        let (common, args_v2, _temp_v2) = args(query, None);
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            "cons",
+            Some("grch37"),
+            &["13:95248340", "13:95248343", "13:95248346", "13:95248349"],
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_per_base_range() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let (common, args, _temp) = args_per_base(
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("GRCh37:13:95248336:95248340")?),
+                all: false,
+            },
+            None,
+            &tmp_dir,
+        );
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn smoke_query_per_base_all() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let (common, args, _temp) = args_per_base(
+            ArgsQuery {
+                range: None,
+                all: true,
+            },
+            None,
+            &tmp_dir,
+        );
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::alignment(false, None, 0, &["13:95248340", "13:95248343", "13:95248346", "13:95248349"])]
+    #[case::alignment_limit(false, Some(2), 0, &["13:95248340", "13:95248343"])]
+    #[case::alignment_offset(false, None, 3, &["13:95248349"])]
+    #[case::alignment_limit_offset(false, Some(2), 1, &["13:95248343", "13:95248346"])]
+    #[case::alignment_zero(false, Some(0), 0, &[])]
+    #[case::per_base_limit(true, Some(2), 0, &["13:95248336", "13:95248337"])]
+    #[case::per_base_limit_offset(true, Some(2), 2, &["13:95248338", "13:95248339"])]
+    #[case::per_base_past_end(true, Some(2), 4, &["13:95248340"])]
+    #[case::per_base_zero(true, Some(0), 0, &[])]
+    fn query_range_limit_offset(
+        #[case] per_base: bool,
+        #[case] limit: Option<usize>,
+        #[case] offset: usize,
+        #[case] expected: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let query = ArgsQuery {
+            range: Some(spdi::Range::from_str(if per_base {
+                "GRCh37:13:95248336:95248340"
+            } else {
+                "GRCh37:13:95248336:95248351"
+            })?),
+            all: false,
+        };
+        let (common, args, _temp) = if per_base {
+            args_per_base(query, None, &tmp_dir)
+        } else {
+            args(query, None)
+        };
+        let args = Args {
+            limit: common::cli::ArgsLimit { limit, offset },
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args
+        };
+        run(&common, &args)?;
+
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        let keys = out_data
+            .lines()
+            .map(|line| -> Result<String, anyhow::Error> {
+                let value: serde_json::Value = serde_json::from_str(line)?;
+                Ok(value["key"].as_str().unwrap_or_default().to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(keys, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_per_base_with_hgnc_id() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let (common, args, _temp) = args_per_base(
+            ArgsQuery {
+                range: None,
+                all: true,
+            },
+            Some(String::from("HGNC:20324")),
+            &tmp_dir,
+        );
+
+        assert!(run(&common, &args).is_err());
+
+        Ok(())
+    }
+
+    /// Build a genes database in `temp` with the MANE Select accessions of TGDS.
+    fn genes_db(temp: &TempDir, mane_select: &[&str]) -> String {
+        let path = temp.join("genes-db").to_string_lossy().to_string();
+        let mut record = crate::genes::cli::import::test::record("HGNC:20324", "TGDS");
+        if let Some(hgnc) = record.hgnc.as_mut() {
+            hgnc.mane_select = mane_select.iter().map(ToString::to_string).collect();
+        }
+        crate::genes::cli::import::test::write_records(&path, &[record]).unwrap();
+        path
+    }
+
+    // The records of the example all have the transcript ENST00000261296 of TGDS.
+    #[rstest::rstest]
+    #[case::mane_select(&["ENST00000261296.7", "NM_014305.4"], false, 4, true)]
+    #[case::mane_select_only(&["ENST00000261296.7", "NM_014305.4"], true, 4, true)]
+    #[case::other_transcript(&["ENST00000999999.1", "NM_014305.4"], false, 4, false)]
+    #[case::other_transcript_only(&["ENST00000999999.1", "NM_014305.4"], true, 0, false)]
+    #[case::without_mane_select(&[], false, 4, false)]
+    fn query_range_mane(
+        #[case] mane_select: &[&str],
+        #[case] mane_only: bool,
+        #[case] expected_count: usize,
+        #[case] expected_mane_select: bool,
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, temp) = args(
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("GRCh37:13:95248336:95248351")?),
+                all: false,
+            },
+            None,
+        );
+        let args = Args {
+            path_genes_rocksdb: Some(genes_db(&temp, mane_select)),
+            mane_only,
+            ..args
+        };
+        run(&common, &args)?;
+
+        let records = std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(records.len(), expected_count);
+        for record in &records {
+            assert_eq!(record["enstId"], "ENST00000261296");
+            assert_eq!(record["maneSelect"], expected_mane_select);
+            assert_eq!(
+                record
+                    .get("maneSelectAccession")
+                    .and_then(|value| value.as_str()),
+                mane_select.first().copied()
+            );
+            // The transcripts of the example have no version, so there is nothing to note.
+            assert_eq!(record.get("maneNote"), None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_per_base_with_path_genes_rocksdb() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let (common, args, temp) = args_per_base(
+            ArgsQuery {
+                range: None,
+                all: true,
+            },
+            None,
+            &tmp_dir,
+        );
+        let args = Args {
+            path_genes_rocksdb: Some(genes_db(&temp, &["ENST00000261296.7"])),
+            ..args
+        };
+
+        assert!(run(&common, &args).is_err());
+
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
 //! Handling of UCSC 100 vertebrate conservation data.
 
 pub mod cli;
+pub mod per_base;
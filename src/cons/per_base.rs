@@ -0,0 +1,222 @@
+//! Per-base conservation scores, e.g., phyloP or phastCons converted from bigWig files.
+//!
+//! In contrast to the transcript-keyed UCSC alignments, each score is stored as a
+//! little-endian `f32` under the `keys::Pos` key of its position in the column family
+//! `CF_NAME`.  Range queries are thus a straight scan of the keys from start to stop.
+
+use crate::common::{self, keys};
+
+/// Name of the column family with the per-base scores.
+pub const CF_NAME: &str = "ucsc_conservation_by_pos";
+
+/// A per-base conservation score.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Record {
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based position.
+    pub pos: i32,
+    /// Conservation score.
+    pub score: f32,
+}
+
+/// Encode the `score` for the database.
+pub fn encode_score(score: f32) -> [u8; 4] {
+    score.to_le_bytes()
+}
+
+/// Decode a score from the database.
+pub fn decode_score(value: &[u8]) -> Result<f32, anyhow::Error> {
+    Ok(f32::from_le_bytes(value.try_into().map_err(|_| {
+        anyhow::anyhow!("invalid per-base score of {} bytes", value.len())
+    })?))
+}
+
+/// Return whether `db` holds per-base scores.
+pub fn is_per_base(db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>) -> bool {
+    db.cf_handle(CF_NAME).is_some()
+}
+
+/// Return the column family with the conservation data of the database at `path`.
+///
+/// This is `CF_NAME` for databases with per-base scores only and `cf_name` otherwise.
+pub fn data_cf_name<P: AsRef<std::path::Path>>(
+    path: P,
+    cf_name: &str,
+) -> Result<String, anyhow::Error> {
+    let cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), common::readlink_f(path)?)
+        .map_err(|e| anyhow::anyhow!("problem listing column families: {}", e))?;
+    let has_cf = |name: &str| cf_names.iter().any(|existing| existing == name);
+    if !has_cf(cf_name) && has_cf(CF_NAME) {
+        Ok(CF_NAME.to_string())
+    } else {
+        Ok(cf_name.to_string())
+    }
+}
+
+/// Call `f` for each score from `start` to `stop` (inclusive) or for all scores if `None`.
+///
+/// The scan ends early when `f` returns `ControlFlow::Break`.
+pub fn scan<F>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    range: Option<(keys::Pos, keys::Pos)>,
+    mut f: F,
+) -> Result<(), anyhow::Error>
+where
+    F: FnMut(Record) -> Result<std::ops::ControlFlow<()>, anyhow::Error>,
+{
+    let cf_data = db
+        .cf_handle(cf_name)
+        .ok_or_else(|| anyhow::anyhow!("no column family {}", cf_name))?;
+    let (mut iter, stop) = if let Some((start, stop)) = range {
+        let mut iter = db.raw_iterator_cf_opt(
+            &cf_data,
+            common::prefix_bloom::scan_read_options(&start, &stop),
+        );
+        let start: Vec<u8> = start.into();
+        iter.seek(&start);
+        (iter, Some(stop))
+    } else {
+        let mut iter = db.raw_iterator_cf(&cf_data);
+        iter.seek(b"");
+        (iter, None)
+    };
+
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        let pos: keys::Pos = key.into();
+        if let Some(stop) = stop.as_ref() {
+            if pos.chrom != stop.chrom || pos.pos > stop.pos {
+                break;
+            }
+        }
+        if f(Record {
+            chrom: pos.chrom,
+            pos: pos.pos,
+            score: decode_score(value)?,
+        })?
+        .is_break()
+        {
+            break;
+        }
+        iter.next();
+    }
+    iter.status()?;
+
+    Ok(())
+}
+
+/// Fetch the scores from `start` to `stop` (inclusive).
+pub fn fetch_range(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    start: keys::Pos,
+    stop: keys::Pos,
+) -> Result<Vec<Record>, anyhow::Error> {
+    let mut result = Vec::new();
+    scan(db, cf_name, Some((start, stop)), |record| {
+        result.push(record);
+        Ok(std::ops::ControlFlow::Continue(()))
+    })?;
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Import the per-base example data and return the path to the database.
+    pub(crate) fn import_per_base(tmp_dir: &temp_testdir::TempDir) -> String {
+        let path_rocksdb = format!("{}", tmp_dir.join("per-base").display());
+        crate::cons::cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::cons::cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_tsv: String::from("tests/cons/example/per-base.tsv"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                input_format: crate::cons::cli::import::InputFormat::PerBase,
+                cf_name: String::from("ucsc_conservation"),
+                path_wal_dir: None,
+                dry_run: false,
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        path_rocksdb
+    }
+
+    #[rstest::rstest]
+    #[case(1.532)]
+    #[case(-0.418)]
+    #[case(0.0)]
+    fn score_roundtrip(#[case] score: f32) {
+        assert_eq!(decode_score(&encode_score(score)).unwrap(), score);
+    }
+
+    #[test]
+    fn decode_score_invalid() {
+        assert!(decode_score(b"abc").is_err());
+    }
+
+    #[test]
+    fn fetch_range_per_base() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path_rocksdb = import_per_base(&tmp_dir);
+        assert_eq!(data_cf_name(&path_rocksdb, "ucsc_conservation")?, CF_NAME);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                &path_rocksdb,
+                ["meta", CF_NAME],
+                false,
+            )?;
+        assert!(is_per_base(&db));
+
+        let records = fetch_range(
+            &db,
+            CF_NAME,
+            keys::Pos::from("13", 95248339),
+            keys::Pos::from("13", 95248341),
+        )?;
+
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    chrom: String::from("13"),
+                    pos: 95248339,
+                    score: 3.875
+                },
+                Record {
+                    chrom: String::from("13"),
+                    pos: 95248340,
+                    score: 5.62
+                },
+                Record {
+                    chrom: String::from("13"),
+                    pos: 95248341,
+                    score: -1.25
+                },
+            ]
+        );
+        // The scan stops at the end of the chromosome.
+        assert_eq!(
+            fetch_range(
+                &db,
+                CF_NAME,
+                keys::Pos::from("13", 95248342),
+                keys::Pos::from("13", 200000000),
+            )?
+            .len(),
+            1
+        );
+
+        Ok(())
+    }
+}
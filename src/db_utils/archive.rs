@@ -0,0 +1,290 @@
+//! Portable single-file archive of a RocksDB database.
+//!
+//! The archive starts with the magic bytes `MAGIC` and the format version as a little-endian
+//! `u32`, both uncompressed.  The rest of the file is one zstd stream with
+//!
+//! - the `ArchiveHeader` as JSON, prefixed by its length,
+//! - for each column family, a `TAG_CF` frame with the column family name followed by one
+//!   `TAG_RECORD` frame per key/value pair in key order,
+//! - a `TAG_END` frame with the total number of records.
+//!
+//! All lengths are little-endian `u32`, the record count is a little-endian `u64`.  Both
+//! writing and reading stream the records, so memory use does not depend on the database
+//! size.
+
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+/// Magic bytes at the start of each archive.
+pub const MAGIC: &[u8; 8] = b"ANNARCH\0";
+
+/// Version of the archive format.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Frame tag for the end of the archive.
+const TAG_END: u8 = 0;
+/// Frame tag for the start of a column family.
+const TAG_CF: u8 = 1;
+/// Frame tag for a key/value record.
+const TAG_RECORD: u8 = 2;
+
+/// Header of an archive.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ArchiveHeader {
+    /// Version of the archive format.
+    pub format_version: u32,
+    /// Version of annonars that wrote the archive.
+    pub annonars_version: String,
+    /// Names of the column families in the archive, in archive order.
+    pub column_families: Vec<String>,
+    /// Entries of the `meta` column family, if any, for inspection without reading the records.
+    pub meta: indexmap::IndexMap<String, String>,
+}
+
+/// One frame of the archive after the header.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// Start of the column family with the given name.
+    ColumnFamily(String),
+    /// Key/value record in the current column family.
+    Record(Vec<u8>, Vec<u8>),
+    /// End of the archive with the total number of records.
+    End(u64),
+}
+
+/// Write a length-prefixed byte string.
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| anyhow::anyhow!("value of {} bytes is too large", bytes.len()))?;
+    writer.write_u32::<LittleEndian>(len)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// Read a length-prefixed byte string.
+///
+/// The length comes from the archive, so the buffer only grows with the bytes actually read
+/// instead of being allocated up front.
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, anyhow::Error> {
+    let len = reader.read_u32::<LittleEndian>()? as usize;
+    let mut buf = Vec::new();
+    reader.by_ref().take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        anyhow::bail!(
+            "corrupt archive: expected {} bytes but only {} are left",
+            len,
+            buf.len()
+        );
+    }
+    Ok(buf)
+}
+
+/// Writer for archives.
+pub struct ArchiveWriter<W: Write> {
+    /// The zstd stream after the magic bytes and version.
+    encoder: zstd::Encoder<'static, W>,
+    /// Number of records written so far.
+    count: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Start an archive on `writer` with the given `header` and zstd compression `level`.
+    pub fn new(mut writer: W, header: &ArchiveHeader, level: i32) -> Result<Self, anyhow::Error> {
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(header.format_version)?;
+        let mut encoder = zstd::Encoder::new(writer, level)?;
+        write_bytes(&mut encoder, &serde_json::to_vec(header)?)?;
+        Ok(Self { encoder, count: 0 })
+    }
+
+    /// Start the column family `name`.
+    pub fn column_family(&mut self, name: &str) -> Result<(), anyhow::Error> {
+        self.encoder.write_u8(TAG_CF)?;
+        write_bytes(&mut self.encoder, name.as_bytes())
+    }
+
+    /// Write one record of the current column family.
+    pub fn record(&mut self, key: &[u8], value: &[u8]) -> Result<(), anyhow::Error> {
+        self.encoder.write_u8(TAG_RECORD)?;
+        write_bytes(&mut self.encoder, key)?;
+        write_bytes(&mut self.encoder, value)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish the archive and return the underlying writer and the number of records.
+    pub fn finish(mut self) -> Result<(W, u64), anyhow::Error> {
+        self.encoder.write_u8(TAG_END)?;
+        self.encoder.write_u64::<LittleEndian>(self.count)?;
+        let mut writer = self.encoder.finish()?;
+        writer.flush()?;
+        Ok((writer, self.count))
+    }
+}
+
+/// Reader for archives.
+pub struct ArchiveReader<R: Read> {
+    /// The zstd stream after the magic bytes and version.
+    decoder: zstd::Decoder<'static, std::io::BufReader<R>>,
+    /// The archive header.
+    header: ArchiveHeader,
+    /// Whether a column family has been started, records are only valid afterwards.
+    in_cf: bool,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Open an archive on `reader` and read its header.
+    pub fn new(mut reader: R) -> Result<Self, anyhow::Error> {
+        let mut magic = [0; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| anyhow::anyhow!("problem reading archive magic bytes: {}", e))?;
+        if &magic != MAGIC {
+            anyhow::bail!("not an annonars archive (invalid magic bytes)");
+        }
+        let format_version = reader.read_u32::<LittleEndian>()?;
+        if format_version != FORMAT_VERSION {
+            anyhow::bail!(
+                "unsupported archive format version {}, expected {}",
+                format_version,
+                FORMAT_VERSION
+            );
+        }
+        let mut decoder = zstd::Decoder::new(reader)?;
+        let header: ArchiveHeader = serde_json::from_slice(&read_bytes(&mut decoder)?)
+            .map_err(|e| anyhow::anyhow!("problem parsing archive header: {}", e))?;
+        Ok(Self {
+            decoder,
+            header,
+            in_cf: false,
+        })
+    }
+
+    /// Return the archive header.
+    pub fn header(&self) -> &ArchiveHeader {
+        &self.header
+    }
+
+    /// Read the next frame.
+    pub fn next_frame(&mut self) -> Result<Frame, anyhow::Error> {
+        match self.decoder.read_u8()? {
+            TAG_END => Ok(Frame::End(self.decoder.read_u64::<LittleEndian>()?)),
+            TAG_CF => {
+                self.in_cf = true;
+                Ok(Frame::ColumnFamily(String::from_utf8(read_bytes(
+                    &mut self.decoder,
+                )?)?))
+            }
+            TAG_RECORD if self.in_cf => {
+                let key = read_bytes(&mut self.decoder)?;
+                let value = read_bytes(&mut self.decoder)?;
+                Ok(Frame::Record(key, value))
+            }
+            TAG_RECORD => anyhow::bail!("record before first column family in archive"),
+            tag => anyhow::bail!("invalid frame tag {} in archive", tag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header() -> ArchiveHeader {
+        ArchiveHeader {
+            format_version: FORMAT_VERSION,
+            annonars_version: String::from("0.0.0"),
+            column_families: vec![String::from("meta"), String::from("data")],
+            meta: [(String::from("genome-release"), String::from("grch37"))]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn write_archive() -> Result<Vec<u8>, anyhow::Error> {
+        let mut writer = ArchiveWriter::new(Vec::new(), &header(), 3)?;
+        writer.column_family("meta")?;
+        writer.record(b"genome-release", b"grch37")?;
+        writer.column_family("data")?;
+        writer.record(b"\x00\x01", b"")?;
+        writer.record(b"\x00\x02", &[0xff; 1000])?;
+        let (buf, count) = writer.finish()?;
+        assert_eq!(count, 3);
+        Ok(buf)
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), anyhow::Error> {
+        let buf = write_archive()?;
+        assert_eq!(&buf[..8], MAGIC);
+
+        let mut reader = ArchiveReader::new(buf.as_slice())?;
+        assert_eq!(reader.header(), &header());
+        let mut frames = Vec::new();
+        loop {
+            let frame = reader.next_frame()?;
+            let is_end = matches!(frame, Frame::End(_));
+            frames.push(frame);
+            if is_end {
+                break;
+            }
+        }
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame::ColumnFamily(String::from("meta")),
+                Frame::Record(b"genome-release".to_vec(), b"grch37".to_vec()),
+                Frame::ColumnFamily(String::from("data")),
+                Frame::Record(b"\x00\x01".to_vec(), Vec::new()),
+                Frame::Record(b"\x00\x02".to_vec(), vec![0xff; 1000]),
+                Frame::End(3),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_magic_and_version() -> Result<(), anyhow::Error> {
+        let mut buf = write_archive()?;
+        buf[8] = 99;
+        let err = ArchiveReader::new(buf.as_slice()).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("unsupported archive format version 99"));
+
+        buf[0] = b'X';
+        assert!(ArchiveReader::new(buf.as_slice()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated() -> Result<(), anyhow::Error> {
+        let buf = write_archive()?;
+        let result = ArchiveReader::new(&buf[..buf.len() - 8])
+            .and_then(|mut reader| (0..10).try_for_each(|_| reader.next_frame().map(|_| ())));
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_length() -> Result<(), anyhow::Error> {
+        let mut buf = MAGIC.to_vec();
+        buf.write_u32::<LittleEndian>(FORMAT_VERSION)?;
+        let mut encoder = zstd::Encoder::new(buf, 3)?;
+        encoder.write_u32::<LittleEndian>(u32::MAX)?;
+        encoder.write_all(b"{}")?;
+        let buf = encoder.finish()?;
+
+        let err = ArchiveReader::new(buf.as_slice()).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("corrupt archive: expected 4294967295 bytes but only 2 are left"));
+
+        Ok(())
+    }
+}
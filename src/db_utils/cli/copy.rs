@@ -1,6 +1,25 @@
 //! Implementation of `db-utils copy` sub command.
-
-use std::{fs::File, io::BufReader, path::PathBuf};
+//!
+//! With `--deterministic`, the copy of the same input yields the same database: the records
+//! of each column family are written in key order to SST files of `DETERMINISTIC_SST_SIZE`
+//! bytes that are then ingested, auto compactions are disabled, and flushes run on a single
+//! thread.  The names, levels, key ranges, and contents of the SST files are thus reproducible,
+//! see `layout_digest()`.  The following sources of nondeterminism remain as RocksDB does not
+//! allow to configure them:
+//!
+//! - The SST files embed a random session ID, the file creation time, and the host name in
+//!   their table properties, so they are not byte-identical.
+//! - `LOG*`, `IDENTITY`, `MANIFEST-*`, and `OPTIONS-*` contain timestamps and random IDs.
+//!
+//! The importers do not support deterministic output; run `db-utils copy --deterministic`
+//! on their output instead.
+
+use std::{
+    fs::File,
+    hash::{Hash, Hasher},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
 use clap::Parser;
 
@@ -23,15 +42,30 @@ pub struct Args {
     /// Range to query for (or all).
     #[command(flatten)]
     pub query: ArgsQuery,
-    /// Names of column families to skip contents for.
+    /// Names of column families to skip contents for.  The column families are still
+    /// created in the output, but left empty.
     #[arg(long)]
     pub skip_cfs: Vec<String>,
+    /// Names of column families to drop from the output.  In contrast to `--skip-cfs`, the
+    /// column families are not created in the output at all.
+    #[arg(long)]
+    pub drop_cf: Vec<String>,
+    /// Rename column family while copying, given as `old=new`.
+    #[arg(long, value_parser = parse_cf_rename)]
+    pub rename_cf: Vec<(String, String)>,
 
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Write the output reproducibly, see the module documentation.  Not supported with
+    /// `--path-beds`.
+    #[arg(long)]
+    pub deterministic: bool,
 }
 
+/// Size of the SST files written with `--deterministic`.
+pub const DETERMINISTIC_SST_SIZE: u64 = 64 * 1024 * 1024;
+
 /// Argument group for specifying one of variant, position, or range.
 #[derive(clap::Args, Debug, Clone, Default)]
 #[group(required = true, multiple = false)]
@@ -50,11 +84,81 @@ pub struct ArgsQuery {
     pub all: bool,
 }
 
+/// Parse a column family rename `old=new`.
+fn parse_cf_rename(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((old, new)) if !old.is_empty() && !new.is_empty() => {
+            Ok((old.to_string(), new.to_string()))
+        }
+        _ => Err(format!(
+            "invalid column family rename {:?}, expected old=new",
+            s
+        )),
+    }
+}
+
+/// Compute the output column family name for each input column family.
+///
+/// Dropped column families are omitted from the result.  Renames of unknown or dropped
+/// column families, of the `meta` column family, and renames to a column family name that
+/// already exists are rejected.
+fn cf_layout(
+    cf_names: &[String],
+    drop_cf: &[String],
+    rename_cf: &[(String, String)],
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    for name in drop_cf {
+        if !cf_names.contains(name) {
+            anyhow::bail!("cannot drop unknown column family {:?}", name);
+        }
+    }
+    for (i, (old, new)) in rename_cf.iter().enumerate() {
+        if !cf_names.contains(old) {
+            anyhow::bail!("cannot rename unknown column family {:?}", old);
+        }
+        if drop_cf.contains(old) {
+            anyhow::bail!("cannot rename dropped column family {:?}", old);
+        }
+        if old == "meta" {
+            anyhow::bail!("cannot rename column family \"meta\"");
+        }
+        if cf_names.contains(new) {
+            anyhow::bail!(
+                "cannot rename column family {:?} to existing column family {:?}",
+                old,
+                new
+            );
+        }
+        if rename_cf[..i]
+            .iter()
+            .any(|(prev_old, prev_new)| prev_old == old || prev_new == new)
+        {
+            anyhow::bail!("conflicting renames for column family {:?}", old);
+        }
+    }
+
+    Ok(cf_names
+        .iter()
+        .filter(|name| !drop_cf.contains(name))
+        .map(|name| {
+            let name_out = rename_cf
+                .iter()
+                .find(|(old, _)| old == name)
+                .map(|(_, new)| new.clone())
+                .unwrap_or_else(|| name.clone());
+            (name.clone(), name_out)
+        })
+        .collect())
+}
+
 /// Copy data from `db_read` to `db_write` for column family `cf_name` in the intervals in `path_bed`.
+///
+/// The data is written to column family `cf_name_out`.
 fn copy_cf_bed(
     db_read: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     db_write: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_name: &str,
+    cf_name_out: &str,
     path_bed: &PathBuf,
 ) -> Result<(), anyhow::Error> {
     let mut reader = File::open(path_bed)
@@ -88,23 +192,33 @@ fn copy_cf_bed(
                 position: stop as i32,
             };
 
-            copy_cf(db_read, db_write, cf_name, Some(start), Some(stop))
+            copy_cf(
+                db_read,
+                db_write,
+                cf_name,
+                cf_name_out,
+                Some(start),
+                Some(stop),
+            )
         })
         .collect::<Result<Vec<_>, _>>()?;
     Ok(())
 }
 
 /// Copy data from `db_read` to `db_write` for column family `cf_name` between `start` and `stop`.
+///
+/// The data is written to column family `cf_name_out`.
 fn copy_cf(
     db_read: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     db_write: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_name: &str,
+    cf_name_out: &str,
     start: Option<spdi::Pos>,
     stop: Option<spdi::Pos>,
 ) -> Result<(), anyhow::Error> {
     // Obtain
     let cf_read = db_read.cf_handle(cf_name).unwrap();
-    let cf_write = db_write.cf_handle(cf_name).unwrap();
+    let cf_write = db_write.cf_handle(cf_name_out).unwrap();
 
     tracing::debug!("start = {:?}, stop = {:?}", &start, &stop);
 
@@ -153,6 +267,121 @@ fn copy_cf(
     Ok(())
 }
 
+/// Adjust `options` for reproducible output with `--deterministic`.
+fn deterministic_options(mut options: rocksdb::Options) -> rocksdb::Options {
+    options.set_disable_auto_compactions(true);
+    options.set_max_background_jobs(1);
+    options.set_max_subcompactions(1);
+    options.set_target_file_size_base(DETERMINISTIC_SST_SIZE);
+    options.set_periodic_compaction_seconds(0);
+    options
+}
+
+/// Copy data from `db_read` to `db_write` like `copy_cf()` but through SST files.
+///
+/// The records are written in key order to SST files of about `DETERMINISTIC_SST_SIZE`
+/// bytes in `tmp_dir` that are then moved into the column family `cf_name_out`.
+#[allow(clippy::too_many_arguments)]
+fn copy_cf_sst(
+    db_read: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    db_write: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    options: &rocksdb::Options,
+    tmp_dir: &Path,
+    cf_name: &str,
+    cf_name_out: &str,
+    start: Option<spdi::Pos>,
+    stop: Option<spdi::Pos>,
+) -> Result<(), anyhow::Error> {
+    let cf_read = db_read.cf_handle(cf_name).unwrap();
+    let cf_write = db_write.cf_handle(cf_name_out).unwrap();
+
+    let mut iter = db_read.raw_iterator_cf(&cf_read);
+    if let Some(start) = start {
+        let pos: keys::Pos = start.into();
+        let key: Vec<u8> = pos.into();
+        iter.seek(&key);
+    } else {
+        iter.seek(b"")
+    }
+    let stop = stop.map(|stop| {
+        let stop: keys::Pos = stop.into();
+        stop.with_key_as_chrom()
+    });
+
+    let past_stop = |key: &[u8]| {
+        stop.as_ref().is_some_and(|stop| {
+            let iter_pos: keys::Pos = key.into();
+            let iter_pos = iter_pos.with_key_as_chrom();
+            iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos
+        })
+    };
+
+    let mut paths = Vec::new();
+    while iter.key().is_some_and(|key| !past_stop(key)) {
+        let path = tmp_dir.join(format!("{}-{:06}.sst", cf_name_out, paths.len()));
+        let mut writer = rocksdb::SstFileWriter::create(options);
+        writer.open(&path)?;
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            if past_stop(key) || writer.file_size() >= DETERMINISTIC_SST_SIZE {
+                break;
+            }
+            writer.put(key, value)?;
+            iter.next();
+        }
+        writer.finish()?;
+        paths.push(path);
+    }
+    iter.status()?;
+
+    if !paths.is_empty() {
+        let mut ingest_options = rocksdb::IngestExternalFileOptions::default();
+        ingest_options.set_move_files(true);
+        db_write.ingest_external_file_cf_opts(&cf_write, &ingest_options, paths)?;
+    }
+    Ok(())
+}
+
+/// Compute a digest of the SST file layout and the contents of the database at `path`.
+///
+/// The digest covers the name, level, key range, and number of entries of each SST file
+/// as well as all key/value pairs, but not the SST file bytes themselves (see the module
+/// documentation).  Two `--deterministic` copies of the same input have the same digest.
+pub fn layout_digest<P: AsRef<Path>>(path: P) -> Result<String, anyhow::Error> {
+    let mut cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), path.as_ref())?;
+    cf_names.sort();
+    let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> = rocksdb::DB::open_cf_for_read_only(
+        &rocksdb::Options::default(),
+        path.as_ref(),
+        &cf_names,
+        false,
+    )?;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut live_files = db.live_files()?;
+    live_files.sort_by(|a, b| a.name.cmp(&b.name));
+    for live_file in &live_files {
+        live_file.column_family_name.hash(&mut hasher);
+        live_file.name.hash(&mut hasher);
+        live_file.level.hash(&mut hasher);
+        live_file.start_key.hash(&mut hasher);
+        live_file.end_key.hash(&mut hasher);
+        live_file.num_entries.hash(&mut hasher);
+    }
+    for cf_name in &cf_names {
+        cf_name.hash(&mut hasher);
+        let cf = db.cf_handle(cf_name).unwrap();
+        let mut iter = db.raw_iterator_cf(&cf);
+        iter.seek(b"");
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            key.hash(&mut hasher);
+            value.hash(&mut hasher);
+            iter.next();
+        }
+        iter.status()?;
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
 /// Main entry point for `db copy` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'db-utils copy' command");
@@ -182,23 +411,70 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         None
     };
 
+    // Compute output column family layout (validates dropping and renaming).
+    let layout = cf_layout(&cf_names, &args.drop_cf, &args.rename_cf)?;
+    let cf_names_out = layout
+        .iter()
+        .map(|(_, name_out)| name_out.clone())
+        .collect::<Vec<_>>();
+
+    if args.deterministic && !args.query.path_beds.is_empty() {
+        anyhow::bail!("--deterministic is not supported with --path-beds");
+    }
+
     tracing::info!("Opening output database");
     let options = rocksdb_utils_lookup::tune_options(
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
+    let options = if args.deterministic {
+        deterministic_options(options)
+    } else {
+        options
+    };
     let db_write = rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out)?,
-        cf_names
+        cf_names_out
             .iter()
             .map(|name| (name.to_string(), options.clone()))
             .collect::<Vec<_>>(),
     )?;
 
+    // Temporary directory for the SST files with `--deterministic`, next to rather than in
+    // the output database so the files can be moved on ingestion.
+    let tmp_dir = if args.deterministic {
+        let path_out = common::readlink_f(&args.path_out)?;
+        Some(match path_out.parent() {
+            Some(parent) => tempfile::TempDir::new_in(parent)?,
+            None => tempfile::TempDir::new()?,
+        })
+    } else {
+        None
+    };
+    let copy = |cf_name: &str, cf_name_out: &str, start, stop| {
+        if let Some(tmp_dir) = tmp_dir.as_ref() {
+            copy_cf_sst(
+                &db_read,
+                &db_write,
+                &options,
+                tmp_dir.path(),
+                cf_name,
+                cf_name_out,
+                start,
+                stop,
+            )
+        } else {
+            copy_cf(&db_read, &db_write, cf_name, cf_name_out, start, stop)
+        }
+    };
+
     // Perform the main work of copying over data.
     tracing::info!("Copying data");
-    for cf_name in &cf_names {
+    for (cf_name, cf_name_out) in &layout {
+        if cf_name != cf_name_out {
+            tracing::info!("  renaming column family {} to {}", cf_name, cf_name_out);
+        }
         if args.skip_cfs.contains(cf_name) {
             tracing::info!("  skipping column family contents {}", cf_name);
             continue;
@@ -208,11 +484,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         if cf_name == "meta" || cf_name.contains("_by_") {
             tracing::info!("  ignoring query for column family {}", &cf_name);
 
-            copy_cf(&db_read, &db_write, cf_name, None, None)?;
+            copy(cf_name, cf_name_out, None, None)?;
         } else if !args.query.path_beds.is_empty() {
             // If BED files were given then use each to query for ranges.
             for path_bed in &args.query.path_beds {
-                copy_cf_bed(&db_read, &db_write, cf_name, path_bed)?;
+                copy_cf_bed(&db_read, &db_write, cf_name, cf_name_out, path_bed)?;
             }
         } else {
             // Otherwise, get single range from command line arguments.
@@ -223,28 +499,56 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                 };
                 (Some(position.clone()), Some(position))
             } else if let Some(range) = args.query.range.as_ref() {
-                let range = spdi::Range {
-                    sequence: extract_chrom::from_range(range, genome_release.as_deref())?,
-                    ..range.clone()
-                };
+                let range = extract_chrom::range(range, genome_release.as_deref())?;
                 let (start, stop) = range.into();
                 (Some(start), Some(stop))
             } else {
                 (None, None)
             };
 
-            copy_cf(&db_read, &db_write, cf_name, start, stop)?;
+            copy(cf_name, cf_name_out, start, stop)?;
+        }
+    }
+    drop(tmp_dir);
+
+    // Record the renames in the meta column family for provenance.
+    if !args.rename_cf.is_empty() {
+        if let Some(cf_meta) = db_write.cf_handle("meta") {
+            let renamed_from = args
+                .rename_cf
+                .iter()
+                .map(|(old, new)| format!("{}={}", new, old))
+                .collect::<Vec<_>>()
+                .join(",");
+            db_write.put_cf(&cf_meta, "cf-renamed-from", renamed_from)?;
+            if args.deterministic {
+                db_write.flush_cf(&cf_meta)?;
+            }
+        } else {
+            tracing::warn!("no meta column family, cannot record column family renames");
         }
     }
 
-    // Finally, compact manually.
-    tracing::info!("Running RocksDB compaction ...");
-    let before_compaction = std::time::Instant::now();
-    rocksdb_utils_lookup::force_compaction_cf(&db_write, cf_names, Some("  "), true)?;
-    tracing::info!(
-        "... done compacting RocksDB in {:?}",
-        before_compaction.elapsed()
-    );
+    // Finally, compact manually.  The ingested SST files of `--deterministic` are already
+    // sorted and do not overlap, so compaction is skipped to keep the layout reproducible.
+    if !args.deterministic {
+        tracing::info!("Running RocksDB compaction ...");
+        let before_compaction = std::time::Instant::now();
+        rocksdb_utils_lookup::force_compaction_cf(&db_write, cf_names_out, Some("  "), true)?;
+        tracing::info!(
+            "... done compacting RocksDB in {:?}",
+            before_compaction.elapsed()
+        );
+    }
+
+    tracing::info!("Column families in output database:");
+    for (cf_name, cf_name_out) in &layout {
+        if cf_name == cf_name_out {
+            tracing::info!("  - {}", cf_name_out);
+        } else {
+            tracing::info!("  - {} (renamed from {})", cf_name_out, cf_name);
+        }
+    }
 
     Ok(())
 }
@@ -263,6 +567,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
@@ -275,6 +580,9 @@ mod test {
             },
             path_wal_dir: None,
             skip_cfs: Vec::new(),
+            drop_cf: Vec::new(),
+            rename_cf: Vec::new(),
+            deterministic: false,
         };
 
         run(&common, &args)
@@ -285,6 +593,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
@@ -297,6 +606,9 @@ mod test {
             },
             path_wal_dir: None,
             skip_cfs: Vec::new(),
+            drop_cf: Vec::new(),
+            rename_cf: Vec::new(),
+            deterministic: false,
         };
 
         run(&common, &args)
@@ -307,6 +619,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
@@ -319,8 +632,227 @@ mod test {
             },
             path_wal_dir: None,
             skip_cfs: Vec::new(),
+            drop_cf: Vec::new(),
+            rename_cf: Vec::new(),
+            deterministic: false,
         };
 
         run(&common, &args)
     }
+
+    #[test]
+    fn smoke_test_copy_rename_cf() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in: String::from("tests/tsv/example/data.tsv.gz.db"),
+            path_out: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            query: ArgsQuery {
+                position: None,
+                range: None,
+                path_beds: Vec::new(),
+                all: true,
+            },
+            path_wal_dir: None,
+            skip_cfs: Vec::new(),
+            drop_cf: Vec::new(),
+            rename_cf: vec![(String::from("tsv_data"), String::from("data"))],
+            deterministic: false,
+        };
+        run(&common, &args)?;
+
+        // The renamed column family can be queried with `tsv query`.
+        let query_args =
+            |path_rocksdb: &str, cf_name: &str, out_file: &str| crate::tsv::cli::query::Args {
+                path_rocksdb: path_rocksdb.to_string(),
+                cf_name: cf_name.to_string(),
+                out_file: out_file.to_string(),
+                output: Default::default(),
+                output_tsv: false,
+                query: crate::cons::cli::args::vars::ArgsQuery {
+                    all: true,
+                    ..Default::default()
+                },
+                start_after_key: None,
+                limit: Default::default(),
+            };
+        let out_orig = format!("{}", tmp_dir.join("out-orig.jsonl").display());
+        let out_copy = format!("{}", tmp_dir.join("out-copy.jsonl").display());
+        crate::tsv::cli::query::run(
+            &common,
+            &query_args("tests/tsv/example/data.tsv.gz.db", "tsv_data", &out_orig),
+        )?;
+        crate::tsv::cli::query::run(&common, &query_args(&args.path_out, "data", &out_copy))?;
+        let out_orig = std::fs::read_to_string(&out_orig)?;
+        assert!(!out_orig.is_empty());
+        assert_eq!(out_orig, std::fs::read_to_string(&out_copy)?);
+
+        // The layout and provenance information are as expected.
+        let cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), &args.path_out)?;
+        assert!(cf_names.contains(&String::from("data")));
+        assert!(!cf_names.contains(&String::from("tsv_data")));
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &args.path_out,
+            ["meta"],
+            false,
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        assert_eq!(
+            db.get_cf(&cf_meta, "cf-renamed-from")?,
+            Some(b"data=tsv_data".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("tests/tsv/example/data.tsv.gz.db")]
+    #[case("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db")]
+    fn copy_deterministic(#[case] path_in: &str) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let copy_to = |name: &str, deterministic: bool| -> Result<String, anyhow::Error> {
+            let path_out = format!("{}", tmp_dir.join(name).display());
+            run(
+                &common,
+                &Args {
+                    path_in: String::from(path_in),
+                    path_out: path_out.clone(),
+                    query: ArgsQuery {
+                        all: true,
+                        ..Default::default()
+                    },
+                    path_wal_dir: None,
+                    skip_cfs: Vec::new(),
+                    drop_cf: Vec::new(),
+                    rename_cf: Vec::new(),
+                    deterministic,
+                },
+            )?;
+            Ok(path_out)
+        };
+
+        let first = copy_to("first", true)?;
+        let second = copy_to("second", true)?;
+        let regular = copy_to("regular", false)?;
+
+        assert_eq!(layout_digest(&first)?, layout_digest(&second)?);
+        // The temporary SST files have been moved into the database and their directory next
+        // to it has been removed.
+        assert!(std::fs::read_dir(&first)?
+            .filter_map(Result::ok)
+            .all(|entry| !entry.path().is_dir()));
+        let mut names = std::fs::read_dir(&*tmp_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        names.sort();
+        assert_eq!(names, vec!["first", "regular", "second"]);
+        // The contents are the same as for a regular copy.
+        let contents =
+            |path: &str| -> Result<Vec<(String, Vec<(Box<[u8]>, Box<[u8]>)>)>, anyhow::Error> {
+                let mut cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), path)?;
+                cf_names.sort();
+                let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+                    rocksdb::DB::open_cf_for_read_only(
+                        &rocksdb::Options::default(),
+                        path,
+                        &cf_names,
+                        false,
+                    )?;
+                cf_names
+                    .into_iter()
+                    .map(|cf_name| {
+                        let cf = db.cf_handle(&cf_name).unwrap();
+                        let records = db
+                            .iterator_cf(&cf, rocksdb::IteratorMode::Start)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok((cf_name, records))
+                    })
+                    .collect()
+            };
+        assert_eq!(contents(&first)?, contents(&regular)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn copy_deterministic_rejects_beds() {
+        let tmp_dir = TempDir::default();
+        let args = Args {
+            path_in: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
+            path_out: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            query: ArgsQuery {
+                path_beds: vec![PathBuf::from("tests/dbsnp/example/regions.bed")],
+                ..Default::default()
+            },
+            path_wal_dir: None,
+            skip_cfs: Vec::new(),
+            drop_cf: Vec::new(),
+            rename_cf: Vec::new(),
+            deterministic: true,
+        };
+
+        assert!(run(
+            &common::cli::Args {
+                verbose: Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &args
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn cf_layout_drop_and_rename() -> Result<(), anyhow::Error> {
+        let cf_names = vec![
+            String::from("meta"),
+            String::from("data"),
+            String::from("data_by_rsid"),
+        ];
+
+        let layout = cf_layout(
+            &cf_names,
+            &[String::from("data_by_rsid")],
+            &[(String::from("data"), String::from("tsv_data"))],
+        )?;
+
+        assert_eq!(
+            layout,
+            vec![
+                (String::from("meta"), String::from("meta")),
+                (String::from("data"), String::from("tsv_data")),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cf_layout_rejects_invalid() {
+        let cf_names = vec![String::from("meta"), String::from("data")];
+        let rename = |old: &str, new: &str| vec![(String::from(old), String::from(new))];
+
+        assert!(cf_layout(&cf_names, &[], &rename("data", "meta")).is_err());
+        assert!(cf_layout(&cf_names, &[], &rename("meta", "meta2")).is_err());
+        assert!(cf_layout(&cf_names, &[], &rename("unknown", "other")).is_err());
+        assert!(cf_layout(&cf_names, &[String::from("data")], &rename("data", "x")).is_err());
+        assert!(cf_layout(&cf_names, &[String::from("unknown")], &[]).is_err());
+    }
+
+    #[test]
+    fn parse_cf_rename_values() {
+        assert_eq!(
+            parse_cf_rename("data=tsv_data"),
+            Ok((String::from("data"), String::from("tsv_data")))
+        );
+        assert!(parse_cf_rename("data").is_err());
+        assert!(parse_cf_rename("=tsv_data").is_err());
+    }
 }
@@ -68,6 +68,7 @@ mod test {
     fn smoke_test_dump() -> Result<(), anyhow::Error> {
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
@@ -0,0 +1,99 @@
+//! Implementation of `db-utils export` sub command.
+
+use std::io::BufWriter;
+
+use clap::Parser;
+
+use crate::{
+    common,
+    db_utils::archive::{ArchiveHeader, ArchiveWriter, FORMAT_VERSION},
+};
+
+/// Command line arguments for `db-utils export` sub command.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Export rocksdb database to a portable archive", long_about = None)]
+pub struct Args {
+    /// Path to input RocksDB directory.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Path to output archive file, e.g., `archive.ann`.
+    #[arg(long)]
+    pub path_out: String,
+    /// zstd compression level.
+    #[arg(long, default_value_t = 3)]
+    pub compression_level: i32,
+}
+
+/// Read the entries of the `meta` column family as strings, if any.
+fn read_meta(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<indexmap::IndexMap<String, String>, anyhow::Error> {
+    let mut result = indexmap::IndexMap::new();
+    if let Some(cf_meta) = db.cf_handle("meta") {
+        let mut iter = db.raw_iterator_cf(&cf_meta);
+        iter.seek(b"");
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            result.insert(
+                String::from_utf8_lossy(key).to_string(),
+                String::from_utf8_lossy(value).to_string(),
+            );
+            iter.next();
+        }
+        iter.status()?;
+    }
+    Ok(result)
+}
+
+/// Main entry point for `db-utils export` sub command.
+///
+/// The records are streamed from the database to the archive, so memory use is bounded
+/// independent of the database size.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'db-utils export' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    tracing::info!("Opening input database");
+    let path_rocksdb = common::readlink_f(&args.path_rocksdb)?;
+    let cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_rocksdb)?;
+    let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> = rocksdb::DB::open_cf_for_read_only(
+        &rocksdb::Options::default(),
+        &path_rocksdb,
+        &cf_names,
+        false,
+    )?;
+
+    let header = ArchiveHeader {
+        format_version: FORMAT_VERSION,
+        annonars_version: crate::VERSION.to_string(),
+        column_families: cf_names.clone(),
+        meta: read_meta(&db)?,
+    };
+    let file = std::fs::File::create(&args.path_out)
+        .map_err(|e| anyhow::anyhow!("problem creating {}: {}", &args.path_out, e))?;
+    let mut writer = ArchiveWriter::new(BufWriter::new(file), &header, args.compression_level)?;
+
+    tracing::info!("Exporting data");
+    let before_export = std::time::Instant::now();
+    for cf_name in &cf_names {
+        tracing::info!("  exporting column family {}", cf_name);
+        writer.column_family(cf_name)?;
+        let cf = db.cf_handle(cf_name).unwrap();
+        let mut iter = db.raw_iterator_cf(&cf);
+        iter.seek(b"");
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            writer.record(key, value)?;
+            iter.next();
+        }
+        iter.status()?;
+    }
+    let (_, count) = writer.finish()?;
+    tracing::info!(
+        "... done exporting {} records in {:?}",
+        count,
+        before_export.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
@@ -0,0 +1,185 @@
+//! Implementation of `db-utils import-archive` sub command.
+
+use std::io::BufReader;
+
+use clap::Parser;
+
+use crate::{
+    common,
+    db_utils::archive::{ArchiveReader, Frame},
+};
+
+/// Command line arguments for `db-utils import-archive` sub command.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Import rocksdb database from a portable archive", long_about = None)]
+pub struct Args {
+    /// Path to input archive file as written by `db-utils export`.
+    #[arg(long)]
+    pub path_in: String,
+    /// Path to output RocksDB directory.
+    #[arg(long)]
+    pub path_out_rocksdb: String,
+    /// Size of the write batches in bytes.
+    #[arg(long, default_value_t = 64 * 1024 * 1024)]
+    pub batch_size: usize,
+
+    /// Optional path to RocksDB WAL directory.
+    #[arg(long)]
+    pub path_wal_dir: Option<String>,
+}
+
+/// Main entry point for `db-utils import-archive` sub command.
+///
+/// The records are written in batches of about `--batch-size` bytes, followed by a final
+/// compaction.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'db-utils import-archive' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let file = std::fs::File::open(&args.path_in)
+        .map_err(|e| anyhow::anyhow!("problem opening {}: {}", &args.path_in, e))?;
+    let mut reader = ArchiveReader::new(BufReader::new(file))?;
+    let cf_names = reader.header().column_families.clone();
+    tracing::info!(
+        "  archive written by annonars {}",
+        &reader.header().annonars_version
+    );
+    for (key, value) in &reader.header().meta {
+        tracing::info!("  meta:{} = {}", key, value);
+    }
+
+    tracing::info!("Opening output database");
+    let options = rocksdb_utils_lookup::tune_options(
+        rocksdb::Options::default(),
+        args.path_wal_dir.as_ref().map(|s| s.as_ref()),
+    );
+    let db = rocksdb::DB::open_cf_with_opts(
+        &options,
+        common::readlink_f(&args.path_out_rocksdb)?,
+        cf_names
+            .iter()
+            .map(|name| (name.to_string(), options.clone()))
+            .collect::<Vec<_>>(),
+    )?;
+
+    tracing::info!("Importing data");
+    let before_import = std::time::Instant::now();
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut cf = None;
+    let mut count = 0;
+    loop {
+        match reader.next_frame()? {
+            Frame::ColumnFamily(cf_name) => {
+                tracing::info!("  importing column family {}", &cf_name);
+                cf = Some(db.cf_handle(&cf_name).ok_or_else(|| {
+                    anyhow::anyhow!("column family {} not in archive header", &cf_name)
+                })?);
+            }
+            Frame::Record(key, value) => {
+                batch.put_cf(cf.as_ref().expect("checked by reader"), key, value);
+                count += 1;
+                if batch.size_in_bytes() >= args.batch_size {
+                    db.write(std::mem::take(&mut batch))?;
+                }
+            }
+            Frame::End(expected) => {
+                if expected != count {
+                    anyhow::bail!(
+                        "archive has {} records but its end marker says {}",
+                        count,
+                        expected
+                    );
+                }
+                break;
+            }
+        }
+    }
+    db.write(batch)?;
+    tracing::info!(
+        "... done importing {} records in {:?}",
+        count,
+        before_import.elapsed()
+    );
+
+    tracing::info!("Running RocksDB compaction ...");
+    let before_compaction = std::time::Instant::now();
+    rocksdb_utils_lookup::force_compaction_cf(&db, &cf_names, Some("  "), true)?;
+    tracing::info!(
+        "... done compacting RocksDB in {:?}",
+        before_compaction.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    /// Return all column families with their records in key order.
+    fn read_all(path: &str) -> Result<Vec<(String, Vec<(Vec<u8>, Vec<u8>)>)>, anyhow::Error> {
+        let mut cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), path)?;
+        cf_names.sort();
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                path,
+                &cf_names,
+                false,
+            )?;
+        cf_names
+            .into_iter()
+            .map(|cf_name| {
+                let cf = db.cf_handle(&cf_name).unwrap();
+                let records = db
+                    .iterator_cf(&cf, rocksdb::IteratorMode::Start)
+                    .map(|item| item.map(|(key, value)| (key.to_vec(), value.to_vec())))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((cf_name, records))
+            })
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case("tests/tsv/example/data.tsv.gz.db")]
+    #[case("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db")]
+    fn roundtrip(#[case] path_rocksdb: &str) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_archive = format!("{}", tmp_dir.join("archive.ann").display());
+        let path_out_rocksdb = format!("{}", tmp_dir.join("out-rocksdb").display());
+
+        crate::db_utils::cli::export::run(
+            &common,
+            &crate::db_utils::cli::export::Args {
+                path_rocksdb: path_rocksdb.to_string(),
+                path_out: path_archive.clone(),
+                compression_level: 3,
+            },
+        )?;
+        run(
+            &common,
+            &Args {
+                path_in: path_archive,
+                path_out_rocksdb: path_out_rocksdb.clone(),
+                // Small batches to cover writing more than one batch.
+                batch_size: 1024,
+                path_wal_dir: None,
+            },
+        )?;
+
+        let expected = read_all(path_rocksdb)?;
+        assert!(expected.iter().any(|(_, records)| !records.is_empty()));
+        assert_eq!(read_all(&path_out_rocksdb)?, expected);
+
+        Ok(())
+    }
+}
@@ -2,3 +2,6 @@
 
 pub mod copy;
 pub mod dump_meta;
+pub mod export;
+pub mod import_archive;
+pub mod upgrade;
@@ -0,0 +1,719 @@
+//! Implementation of `db-utils upgrade` sub command.
+//!
+//! Databases built with older versions of annonars do not follow all conventions that the
+//! server expects, e.g., they lack `meta:annonars-version` or have their data in a column
+//! family named `data`.  Each convention is an entry of `CONVENTIONS` with a check that
+//! returns the `Fix` for an outdated database, such that new conventions only need a new
+//! entry.  Without `--apply`, the conventions are reported only.  With `--apply`, meta
+//! values and `spec.yaml` are fixed in place or in a copy written to `--path-out`; renaming
+//! column families always needs the copy, see `db-utils copy --rename-cf`.
+
+use std::collections::BTreeMap;
+
+use clap::Parser;
+use strum::IntoEnumIterator as _;
+
+use crate::{
+    common::{self, cli::GenomeRelease, spec},
+    server::run::{versions::schema::VersionSpec, AnnoDb},
+};
+
+/// Command line arguments for `db-utils upgrade` sub command.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Upgrade databases of older annonars versions", long_about = None)]
+pub struct Args {
+    /// Path to input directory.
+    #[arg(long)]
+    pub path_in: String,
+    /// Path to write the upgraded copy to; the database is upgraded in place if not given,
+    /// which is not possible if column families need to be renamed.
+    #[arg(long)]
+    pub path_out: Option<String>,
+    /// Kind of the database, e.g., `gnomad_exomes`; inferred from `meta:db-name` or the
+    /// column families if not given.
+    #[arg(long)]
+    pub anno_db: Option<AnnoDb>,
+    /// Genome release to write if `meta:genome-release` is missing; read from `spec.yaml`
+    /// if not given.
+    #[arg(long)]
+    pub genome_release: Option<GenomeRelease>,
+    /// Data version to write if the meta value with the version is missing, e.g.,
+    /// `meta:db-version`.
+    #[arg(long)]
+    pub db_version: Option<String>,
+    /// Version of annonars that built the database to write if `meta:annonars-version` is
+    /// missing.
+    #[arg(long)]
+    pub builder_version: Option<String>,
+    /// Perform the upgrade rather than only reporting the outdated conventions.
+    #[arg(long)]
+    pub apply: bool,
+}
+
+/// Name of the data column family of older databases.
+pub const LEGACY_DATA_CF: &str = "data";
+
+/// Column families, meta values, and `spec.yaml` of a database.
+#[derive(Debug, Clone, Default)]
+pub struct DbState {
+    /// Names of the column families.
+    pub cf_names: Vec<String>,
+    /// Values of the `meta` column family, empty if there is none.
+    pub meta: BTreeMap<String, String>,
+    /// The `spec.yaml` file next to the database, if any.
+    pub spec: Option<VersionSpec>,
+}
+
+impl DbState {
+    /// Read the state of the database at `path`.
+    pub fn read(path: &str) -> Result<Self, anyhow::Error> {
+        let path_db = common::readlink_f(path)?;
+        let cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_db)
+            .map_err(|e| anyhow::anyhow!("problem listing column families of {}: {}", path, e))?;
+
+        let mut meta = BTreeMap::new();
+        if cf_names.iter().any(|name| name == "meta") {
+            let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+                rocksdb::DB::open_cf_for_read_only(
+                    &rocksdb::Options::default(),
+                    &path_db,
+                    ["meta"],
+                    false,
+                )?;
+            let cf_meta = db.cf_handle("meta").unwrap();
+            let mut iter = db.raw_iterator_cf(&cf_meta);
+            iter.seek(b"");
+            while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                meta.insert(
+                    String::from_utf8_lossy(key).to_string(),
+                    String::from_utf8_lossy(value).to_string(),
+                );
+                iter.next();
+            }
+            iter.status()?;
+        }
+
+        let path_spec = spec::spec_yaml_path(path);
+        let spec = path_spec
+            .exists()
+            .then(|| VersionSpec::from_path(&path_spec))
+            .transpose()?;
+
+        Ok(Self {
+            cf_names,
+            meta,
+            spec,
+        })
+    }
+
+    /// Return whether the database has the column family `name`.
+    fn has_cf(&self, name: &str) -> bool {
+        self.cf_names.iter().any(|cf_name| cf_name == name)
+    }
+
+    /// Infer the kind of the database from `meta:db-name` or the column families.
+    ///
+    /// Returns `None` if the column families are ambiguous, e.g., `tsv_data` for CADD and
+    /// dbNSFP.
+    pub fn infer_anno_db(&self) -> Option<AnnoDb> {
+        self.meta
+            .get("db-name")
+            .and_then(|db_name| db_name.parse::<AnnoDb>().ok())
+            .filter(|anno_db| *anno_db != AnnoDb::Other)
+            .or_else(|| {
+                let candidates = AnnoDb::iter()
+                    .filter(|anno_db| *anno_db != AnnoDb::Other && self.has_cf(anno_db.cf_name()))
+                    .collect::<Vec<_>>();
+                match candidates.as_slice() {
+                    [anno_db] => Some(*anno_db),
+                    _ => None,
+                }
+            })
+    }
+}
+
+/// Values for the fixes from the command line and the database.
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// Kind of the database.
+    pub anno_db: AnnoDb,
+    /// Genome release, if known.
+    pub genome_release: Option<GenomeRelease>,
+    /// Data version, if known.
+    pub db_version: Option<String>,
+    /// Version of annonars that built the database, if known.
+    pub builder_version: Option<String>,
+}
+
+impl Context {
+    /// Build the context from `args`, falling back to the values in `state`.
+    pub fn new(args: &Args, state: &DbState) -> Result<Self, anyhow::Error> {
+        let anno_db = args
+            .anno_db
+            .or_else(|| state.infer_anno_db())
+            .filter(|anno_db| *anno_db != AnnoDb::Other)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "cannot infer the kind of database {} from its meta values or column \
+                    families, give it with --anno-db",
+                    &args.path_in
+                )
+            })?;
+        let genome_release = args.genome_release.or_else(|| {
+            state
+                .meta
+                .get("genome-release")
+                .cloned()
+                .or_else(|| state.spec.as_ref()?.genome_release.clone())
+                .and_then(|genome_release| genome_release.parse().ok())
+        });
+        let db_version = args.db_version.clone().or_else(|| {
+            anno_db
+                .db_version_meta()
+                .and_then(|key| state.meta.get(key).cloned())
+        });
+        Ok(Self {
+            anno_db,
+            genome_release,
+            db_version,
+            builder_version: args.builder_version.clone(),
+        })
+    }
+}
+
+/// Fix of an outdated convention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Write `value` to the meta value `key`, to be given with `flag` if `None`.
+    PutMeta {
+        /// Key of the meta value.
+        key: String,
+        /// Value to write, if known.
+        value: Option<String>,
+        /// Command line flag to give the value with.
+        flag: &'static str,
+    },
+    /// Rename the column family `old` to `new` while copying.
+    RenameCf {
+        /// Current name of the column family.
+        old: String,
+        /// Expected name of the column family.
+        new: String,
+    },
+    /// Write a `spec.yaml` skeleton next to the database.
+    WriteSpecYaml,
+}
+
+impl Fix {
+    /// Return the command line flag to give the missing value of the fix with, if any.
+    pub fn missing_flag(&self) -> Option<&'static str> {
+        match self {
+            Fix::PutMeta {
+                value: None, flag, ..
+            } => Some(flag),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Fix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fix::PutMeta {
+                key,
+                value: Some(value),
+                ..
+            } => write!(f, "write meta:{}={}", key, value),
+            Fix::PutMeta {
+                key,
+                value: None,
+                flag,
+            } => write!(f, "write meta:{}, needs {}", key, flag),
+            Fix::RenameCf { old, new } => write!(f, "rename column family {} to {}", old, new),
+            Fix::WriteSpecYaml => write!(f, "write spec.yaml skeleton"),
+        }
+    }
+}
+
+/// A convention of databases built by the current version of annonars.
+pub struct Convention {
+    /// Short description for the report.
+    pub description: &'static str,
+    /// Return the fix if the database does not follow the convention.
+    pub check: fn(&DbState, &Context) -> Option<Fix>,
+}
+
+/// The conventions checked by `db-utils upgrade`, in the order of their fixes.
+pub const CONVENTIONS: &[Convention] = &[
+    Convention {
+        description: "data column family name",
+        check: check_data_cf,
+    },
+    Convention {
+        description: "meta:annonars-version",
+        check: |state, context| {
+            check_meta(
+                state,
+                "annonars-version",
+                context.builder_version.as_deref(),
+                "--builder-version",
+            )
+        },
+    },
+    Convention {
+        description: "meta:genome-release",
+        check: |state, context| {
+            check_meta(
+                state,
+                "genome-release",
+                context
+                    .genome_release
+                    .map(|genome_release| genome_release.to_string())
+                    .as_deref(),
+                "--genome-release",
+            )
+        },
+    },
+    Convention {
+        description: "meta value with the data version",
+        check: |state, context| {
+            context.anno_db.db_version_meta().and_then(|key| {
+                check_meta(state, key, context.db_version.as_deref(), "--db-version")
+            })
+        },
+    },
+    Convention {
+        description: "spec.yaml",
+        check: |state, _| state.spec.is_none().then_some(Fix::WriteSpecYaml),
+    },
+];
+
+/// Check that the data are not in `LEGACY_DATA_CF` but in the column family of the kind.
+fn check_data_cf(state: &DbState, context: &Context) -> Option<Fix> {
+    state.has_cf(LEGACY_DATA_CF).then(|| Fix::RenameCf {
+        old: LEGACY_DATA_CF.to_string(),
+        new: context.anno_db.cf_name().to_string(),
+    })
+}
+
+/// Check that the meta value `key` is present, with `value` given by `flag` otherwise.
+fn check_meta(state: &DbState, key: &str, value: Option<&str>, flag: &'static str) -> Option<Fix> {
+    (!state.meta.contains_key(key)).then(|| Fix::PutMeta {
+        key: key.to_string(),
+        value: value.map(ToString::to_string),
+        flag,
+    })
+}
+
+/// Check all `CONVENTIONS` and return the description and the fix of each, `None` if the
+/// database follows the convention.
+pub fn inspect(state: &DbState, context: &Context) -> Vec<(&'static str, Option<Fix>)> {
+    CONVENTIONS
+        .iter()
+        .map(|convention| (convention.description, (convention.check)(state, context)))
+        .collect()
+}
+
+/// Apply `fixes` to the database of `args`, see the module documentation.
+fn apply(
+    common: &common::cli::Args,
+    args: &Args,
+    context: &Context,
+    fixes: &[Fix],
+) -> Result<(), anyhow::Error> {
+    let missing_flags = fixes
+        .iter()
+        .filter_map(Fix::missing_flag)
+        .collect::<Vec<_>>();
+    if !missing_flags.is_empty() {
+        anyhow::bail!(
+            "cannot infer all values for the upgrade, give them with {}",
+            missing_flags.join(", ")
+        );
+    }
+
+    let rename_cf = fixes
+        .iter()
+        .filter_map(|fix| match fix {
+            Fix::RenameCf { old, new } => Some((old.clone(), new.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let path = match args.path_out.as_ref() {
+        Some(path_out) => {
+            tracing::info!("Copying database to {}", path_out);
+            super::copy::run(
+                common,
+                &super::copy::Args {
+                    path_in: args.path_in.clone(),
+                    path_out: path_out.clone(),
+                    query: super::copy::ArgsQuery {
+                        all: true,
+                        ..Default::default()
+                    },
+                    skip_cfs: Vec::new(),
+                    drop_cf: Vec::new(),
+                    rename_cf,
+                    path_wal_dir: None,
+                    deterministic: false,
+                },
+            )?;
+            path_out.clone()
+        }
+        None if !rename_cf.is_empty() => {
+            anyhow::bail!("renaming column families needs a copy, give it with --path-out")
+        }
+        None => args.path_in.clone(),
+    };
+
+    let put_meta = fixes
+        .iter()
+        .filter_map(|fix| match fix {
+            Fix::PutMeta {
+                key,
+                value: Some(value),
+                ..
+            } => Some((key, value)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !put_meta.is_empty() {
+        tracing::info!("Writing meta values to {}", &path);
+        let path_db = common::readlink_f(&path)?;
+        let mut cf_names = rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_db)?;
+        if !cf_names.iter().any(|name| name == "meta") {
+            cf_names.push("meta".to_string());
+        }
+        let mut options = rocksdb::Options::default();
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, &path_db, &cf_names)?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        for (key, value) in put_meta {
+            tracing::info!("  meta:{}={}", key, value);
+            db.put_cf(&cf_meta, key, value)?;
+        }
+        db.flush_cf(&cf_meta)?;
+    }
+
+    if fixes.contains(&Fix::WriteSpecYaml) {
+        let db_version = context
+            .db_version
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        spec::ArgsSpec::default().write(
+            &path,
+            &spec::ImportSpec {
+                db_name: context.anno_db.to_string(),
+                title: context.anno_db.to_string(),
+                genome_release: context.genome_release,
+                created_from: vec![(context.anno_db.to_string(), db_version)],
+                paths_in: vec![args.path_in.clone()],
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Main entry point for `db-utils upgrade` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'db-utils upgrade' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    tracing::info!("Inspecting database");
+    let state = DbState::read(&args.path_in)?;
+    let context = Context::new(args, &state)?;
+    tracing::info!("  kind of database: {}", context.anno_db);
+
+    println!("#convention\tstatus\tfix");
+    let mut fixes = Vec::new();
+    for (description, fix) in inspect(&state, &context) {
+        match fix {
+            Some(fix) => {
+                println!("{}\toutdated\t{}", description, fix);
+                fixes.push(fix);
+            }
+            None => println!("{}\tok\t", description),
+        }
+    }
+
+    if fixes.is_empty() && args.path_out.is_none() {
+        tracing::info!("Database follows all conventions, nothing to do");
+    } else if args.apply {
+        tracing::info!("Upgrading database");
+        apply(common, args, &context, &fixes)?;
+    } else {
+        tracing::info!("Not upgrading the database without --apply");
+    }
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    fn common_args() -> common::cli::Args {
+        common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        }
+    }
+
+    fn args(path_in: &str) -> Args {
+        Args {
+            path_in: path_in.to_string(),
+            path_out: None,
+            anno_db: None,
+            genome_release: None,
+            db_version: None,
+            builder_version: None,
+            apply: false,
+        }
+    }
+
+    /// Import the dbSNP example data without `spec.yaml` and return the path.
+    fn import_dbsnp(tmp_dir: &TempDir) -> Result<String, anyhow::Error> {
+        let path_rocksdb = format!("{}", tmp_dir.join("current").join("rocksdb").display());
+        std::fs::create_dir_all(tmp_dir.join("current"))?;
+        crate::dbsnp::cli::import::run(
+            &common_args(),
+            &crate::dbsnp::cli::import::Args {
+                genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+                path_in_vcf: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("dbsnp_data"),
+                cf_name_by_rsid: String::from("dbsnp_by_rsid"),
+                path_wal_dir: None,
+                allow_iupac: false,
+                dry_run: false,
+                sample: Default::default(),
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                tbi_window_size: 1_000_000,
+            },
+        )?;
+        Ok(path_rocksdb)
+    }
+
+    /// Write an "aged" copy of the dbSNP example data with the data in `data`, without
+    /// `meta:annonars-version`, `meta:db-name`, and `meta:db-version`, and without
+    /// `spec.yaml`.
+    fn aged_dbsnp(tmp_dir: &TempDir) -> Result<String, anyhow::Error> {
+        let path_aged = format!("{}", tmp_dir.join("aged").join("rocksdb").display());
+        std::fs::create_dir_all(tmp_dir.join("aged"))?;
+        super::super::copy::run(
+            &common_args(),
+            &super::super::copy::Args {
+                path_in: import_dbsnp(tmp_dir)?,
+                path_out: path_aged.clone(),
+                query: super::super::copy::ArgsQuery {
+                    all: true,
+                    ..Default::default()
+                },
+                skip_cfs: Vec::new(),
+                drop_cf: Vec::new(),
+                rename_cf: vec![(String::from("dbsnp_data"), String::from("data"))],
+                path_wal_dir: None,
+                deterministic: false,
+            },
+        )?;
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> = rocksdb::DB::open_cf(
+            &rocksdb::Options::default(),
+            &path_aged,
+            ["meta", LEGACY_DATA_CF],
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        for key in [
+            "annonars-version",
+            "db-name",
+            "db-version",
+            "cf-renamed-from",
+        ] {
+            db.delete_cf(&cf_meta, key)?;
+        }
+        Ok(path_aged)
+    }
+
+    #[test]
+    fn inspect_aged() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_aged = aged_dbsnp(&tmp_dir)?;
+
+        let state = DbState::read(&path_aged)?;
+        assert!(state.has_cf(LEGACY_DATA_CF));
+        assert_eq!(state.infer_anno_db(), None);
+        assert!(Context::new(&args(&path_aged), &state).is_err());
+
+        let context = Context::new(
+            &Args {
+                anno_db: Some(AnnoDb::Dbsnp),
+                builder_version: Some(String::from("0.24.0")),
+                ..args(&path_aged)
+            },
+            &state,
+        )?;
+        let fixes = inspect(&state, &context)
+            .into_iter()
+            .map(|(description, fix)| (description, fix.map(|fix| fix.to_string())))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fixes,
+            vec![
+                (
+                    "data column family name",
+                    Some(String::from("rename column family data to dbsnp_data"))
+                ),
+                (
+                    "meta:annonars-version",
+                    Some(String::from("write meta:annonars-version=0.24.0"))
+                ),
+                ("meta:genome-release", None),
+                (
+                    "meta value with the data version",
+                    Some(String::from("write meta:db-version, needs --db-version"))
+                ),
+                ("spec.yaml", Some(String::from("write spec.yaml skeleton"))),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_current() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_in = import_dbsnp(&tmp_dir)?;
+        let state = DbState::read(&path_in)?;
+        assert_eq!(state.infer_anno_db(), Some(AnnoDb::Dbsnp));
+
+        let context = Context::new(&args(&path_in), &state)?;
+        let outdated = inspect(&state, &context)
+            .into_iter()
+            .filter_map(|(description, fix)| fix.map(|_| description))
+            .collect::<Vec<_>>();
+
+        assert_eq!(outdated, vec!["spec.yaml"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_aged() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_aged = aged_dbsnp(&tmp_dir)?;
+        let path_out = format!("{}", tmp_dir.join("upgraded").join("rocksdb").display());
+        std::fs::create_dir_all(tmp_dir.join("upgraded"))?;
+        let args = Args {
+            path_out: Some(path_out.clone()),
+            anno_db: Some(AnnoDb::Dbsnp),
+            builder_version: Some(String::from("0.24.0")),
+            apply: true,
+            ..args(&path_aged)
+        };
+
+        // Values that cannot be inferred must be given.
+        let err = run(&common_args(), &args).unwrap_err();
+        assert!(err.to_string().contains("--db-version"), "{}", err);
+        // Renaming column families needs a copy.
+        let err = run(
+            &common_args(),
+            &Args {
+                path_out: None,
+                db_version: Some(String::from("b151")),
+                ..args.clone()
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--path-out"), "{}", err);
+
+        run(
+            &common_args(),
+            &Args {
+                db_version: Some(String::from("b151")),
+                ..args.clone()
+            },
+        )?;
+
+        let state = DbState::read(&path_out)?;
+        assert!(state.has_cf("dbsnp_data"));
+        assert!(!state.has_cf(LEGACY_DATA_CF));
+        assert_eq!(
+            state.meta.get("annonars-version").map(String::as_str),
+            Some("0.24.0")
+        );
+        assert_eq!(
+            state.meta.get("db-version").map(String::as_str),
+            Some("b151")
+        );
+        let spec = state.spec.as_ref().expect("spec.yaml written");
+        assert_eq!(spec.genome_release.as_deref(), Some("grch37"));
+        assert_eq!(spec.created_from[0].version, "b151");
+
+        let context = Context::new(
+            &Args {
+                anno_db: None,
+                ..args
+            },
+            &state,
+        )?;
+        assert!(inspect(&state, &context)
+            .iter()
+            .all(|(_, fix)| fix.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_in_place() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_aged = aged_dbsnp(&tmp_dir)?;
+        // Simulate a database that only lacks meta values.
+        let path_db = format!("{}", tmp_dir.join("aged").join("renamed").display());
+        super::super::copy::run(
+            &common_args(),
+            &super::super::copy::Args {
+                path_in: path_aged,
+                path_out: path_db.clone(),
+                query: super::super::copy::ArgsQuery {
+                    all: true,
+                    ..Default::default()
+                },
+                skip_cfs: Vec::new(),
+                drop_cf: Vec::new(),
+                rename_cf: vec![(String::from("data"), String::from("dbsnp_data"))],
+                path_wal_dir: None,
+                deterministic: false,
+            },
+        )?;
+
+        run(
+            &common_args(),
+            &Args {
+                anno_db: Some(AnnoDb::Dbsnp),
+                builder_version: Some(String::from("0.24.0")),
+                db_version: Some(String::from("b151")),
+                apply: true,
+                ..args(&path_db)
+            },
+        )?;
+
+        let state = DbState::read(&path_db)?;
+        assert_eq!(
+            state.meta.get("annonars-version").map(String::as_str),
+            Some("0.24.0")
+        );
+        assert!(state.spec.is_some());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 //! Database utilities.
 
+pub mod archive;
 pub mod cli;
@@ -2,7 +2,6 @@
 
 use std::{str::FromStr, sync::Arc};
 
-use biocommons_bioutils::assemblies::Assembly;
 use byteorder::ByteOrder as _;
 use clap::Parser;
 use indicatif::ParallelProgressIterator;
@@ -18,9 +17,9 @@ use crate::{common, dbsnp};
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import dbsNP data into RocksDB", long_about = None)]
 pub struct Args {
-    /// Genome build to use in the build.
+    /// Genome build to use in the build, `auto` detects it from the `##reference` header.
     #[arg(long, value_enum)]
-    pub genome_release: common::cli::GenomeRelease,
+    pub genome_release: common::assembly_detect::GenomeReleaseArg,
     /// Path to input VCF file(s).
     #[arg(long, required = true)]
     pub path_in_vcf: String,
@@ -41,12 +40,26 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform TBI-parallel import of the data.
 fn tsv_import(
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     args: &Args,
+    genome_release: common::cli::GenomeRelease,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Load tabix header and create BGZF reader with tabix index.
     let tabix_src = format!("{}.tbi", args.path_in_vcf);
@@ -70,7 +83,7 @@ fn tsv_import(
 
     // Generate list of regions on canonical chromosomes, limited to those present in header.
     let windows =
-        common::cli::build_genome_windows(args.genome_release.into(), Some(args.tbi_window_size))?
+        common::cli::build_genome_windows(genome_release.into(), Some(args.tbi_window_size))?
             .into_iter()
             .filter_map(|(window_chrom, begin, end)| {
                 let canon_chrom = common::cli::canonicalize(&window_chrom);
@@ -85,7 +98,7 @@ fn tsv_import(
     windows
         .par_iter()
         .progress_with(common::cli::progress_bar(windows.len()))
-        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args))
+        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args, sampler))
         .collect::<Result<Vec<_>, _>>()?;
     tracing::info!(
         "... done loading dbSNP VCF file into RocksDB in {:?}",
@@ -102,6 +115,7 @@ fn process_window(
     begin: usize,
     end: usize,
     args: &Args,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     let cf_dbsnp = db.cf_handle(&args.cf_name).unwrap();
     let cf_dbsnp_by_rsid = db.cf_handle(&args.cf_name_by_rsid).unwrap();
@@ -129,14 +143,21 @@ fn process_window(
 
     // Process the result (skip if determined above that the sequence does not
     // exist).
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     if let Some(query) = query {
         for result in query {
             let vcf_record = RecordBuf::try_from_variant_record(&header, &result?)?;
 
             // Process each alternate allele into one record.
             for allele_no in 0..vcf_record.alternate_bases().as_ref().len() {
-                let key_buf: Vec<u8> =
-                    common::keys::Var::from_vcf_allele(&vcf_record, allele_no).into();
+                let var = common::keys::Var::from_vcf_allele(&vcf_record, allele_no);
+                let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+                    continue;
+                };
+                let key_buf: Vec<u8> = var.into();
+                if !sampler.keep(&key_buf) {
+                    continue;
+                }
                 let record = dbsnp::pbs::Record::from_vcf_allele(&vcf_record, allele_no)?;
                 let record_buf = record.encode_to_vec();
                 db.put_cf(&cf_dbsnp, &key_buf, &record_buf)?;
@@ -147,59 +168,130 @@ fn process_window(
         }
     }
 
+    invalid_alleles.log(&raw_region);
+
     Ok(())
 }
 
-/// Implementation of `dbsnp import` sub command.
-pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("Starting 'dbsnp import' command");
-    tracing::info!("common = {:#?}", &common);
-    tracing::info!("args = {:#?}", &args);
-
-    tracing::info!("Opening dbSNP VCF file...");
-    let before_loading = std::time::Instant::now();
-    let mut reader_vcf =
-        noodles::vcf::io::indexed_reader::Builder::default().build_from_path(&args.path_in_vcf)?;
-    let header = reader_vcf.read_header()?;
+/// Return the genome release from the `##reference` header of the dbSNP VCF.
+fn header_genome_release(
+    header: &noodles::vcf::Header,
+) -> Result<common::cli::GenomeRelease, anyhow::Error> {
     let dbsnp_reference = if let record::value::Collection::Unstructured(values) = header
         .other_records()
         .get(&record::key::Other::from_str("reference")?)
-        .expect("no ##reference header")
+        .ok_or_else(|| anyhow::anyhow!("no ##reference header"))?
     {
-        values.first().expect("no reference value").to_owned()
+        values
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("no ##reference value"))?
+            .to_owned()
     } else {
         anyhow::bail!("invalid type of ##reference header");
     };
+
+    if dbsnp_reference.starts_with("GRCh37") {
+        Ok(common::cli::GenomeRelease::Grch37)
+    } else if dbsnp_reference.starts_with("GRCh38") {
+        Ok(common::cli::GenomeRelease::Grch38)
+    } else {
+        anyhow::bail!("unknown assembly in dbSNP reference: {}", dbsnp_reference);
+    }
+}
+
+/// Detect the genome release from the `##reference` header of the dbSNP VCF at `path`.
+fn detect_genome_release(path: &str) -> Result<common::cli::GenomeRelease, anyhow::Error> {
+    let header = noodles::vcf::io::reader::Builder::default()
+        .build_from_path(path)?
+        .read_header()?;
+    header_genome_release(&header)
+}
+
+/// Check the dbSNP VCF header against `genome_release` and return the dbSNP build ID.
+fn check_header(
+    header: &noodles::vcf::Header,
+    genome_release: common::cli::GenomeRelease,
+) -> Result<String, anyhow::Error> {
     let dbsnp_build_id = if let record::value::Collection::Unstructured(values) = header
         .other_records()
         .get(&record::key::Other::from_str("dbSNP_BUILD_ID")?)
-        .expect("no ##dbSNP_BUILD_ID header")
+        .ok_or_else(|| anyhow::anyhow!("no ##dbSNP_BUILD_ID header"))?
     {
-        values.first().expect("no reference value").to_owned()
+        values
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("no ##dbSNP_BUILD_ID value"))?
+            .to_owned()
     } else {
         anyhow::bail!("invalid type of ##dbSNP_BUILD_ID header");
     };
-    tracing::info!(
-        "...done opening dbSNP VCF file in {:?}",
-        before_loading.elapsed()
-    );
 
     // Check that the dbSNP reference from the matches the genome release.
-    let assembly = if dbsnp_reference.starts_with("GRCh37") {
-        Assembly::Grch37p10
-    } else if dbsnp_reference.starts_with("GRCh38") {
-        Assembly::Grch38
-    } else {
-        anyhow::bail!("unknown assembly in dbSNP reference: {}", dbsnp_reference);
-    };
-    if assembly != args.genome_release.into() {
+    let header_release = header_genome_release(header)?;
+    if header_release != genome_release {
         anyhow::bail!(
             "dbSNP reference assembly ({}) does not match genome release from args ({})",
-            dbsnp_reference,
-            args.genome_release
+            header_release,
+            genome_release
         );
     }
 
+    Ok(dbsnp_build_id)
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if validation.input_file(&args.path_in_vcf) {
+        validation.tabix_index(&args.path_in_vcf);
+        let genome_release = validation.genome_release(args.genome_release, || {
+            detect_genome_release(&args.path_in_vcf)
+        });
+        if let Some(genome_release) = genome_release {
+            if let Some(header) = validation.vcf_header(&args.path_in_vcf, genome_release) {
+                validation.check(
+                    format!(
+                        "dbSNP header of {} matches genome release",
+                        &args.path_in_vcf
+                    ),
+                    check_header(&header, genome_release).map(|_| ()),
+                );
+            }
+        }
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
+/// Implementation of `dbsnp import` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'dbsnp import' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+    let sampler = common::sample::Sampler::new(&args.sample)?;
+
+    tracing::info!("Opening dbSNP VCF file...");
+    let before_loading = std::time::Instant::now();
+    let mut reader_vcf =
+        noodles::vcf::io::indexed_reader::Builder::default().build_from_path(&args.path_in_vcf)?;
+    let header = reader_vcf.read_header()?;
+    let genome_release = args
+        .genome_release
+        .resolve(|| header_genome_release(&header))?;
+    let dbsnp_build_id = check_header(&header, genome_release)?;
+    tracing::info!(
+        "...done opening dbSNP VCF file in {:?}",
+        before_loading.elapsed()
+    );
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -219,13 +311,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
-    db.put_cf(
-        &cf_meta,
-        "genome-release",
-        format!("{}", args.genome_release),
-    )?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
+    db.put_cf(&cf_meta, "genome-release", format!("{}", genome_release))?;
     db.put_cf(&cf_meta, "db-version", dbsnp_build_id)?;
     db.put_cf(&cf_meta, "db-name", "dbsnp")?;
+    sampler.write_meta(&db, &cf_meta)?;
     tracing::info!(
         "... done opening RocksDB for writing in {:?}",
         before_opening_rocksdb.elapsed()
@@ -233,11 +323,12 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Importing dbSNP file ...");
     let before_import = std::time::Instant::now();
-    tsv_import(db.clone(), args)?;
+    tsv_import(db.clone(), args, genome_release, &sampler)?;
     tracing::info!(
         "... done importing dbSNP file in {:?}",
         before_import.elapsed()
     );
+    sampler.log();
 
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
@@ -247,6 +338,19 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "dbsnp".to_string(),
+            title: "dbSNP".to_string(),
+            genome_release: Some(genome_release),
+            created_from: vec![("dbSNP".to_string(), args.spec.data_version())],
+            paths_in: vec![args.path_in_vcf.clone()],
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -263,17 +367,116 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch37,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
             path_in_vcf: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz"),
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("dbsnp_data"),
             cf_name_by_rsid: String::from("dbsnp_by_rsid"),
             path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
         };
 
         run(&common, &args).unwrap();
     }
+
+    /// Dry run must not create the database and must find the missing tabix index.
+    #[test]
+    fn dry_run_import_dbsnp() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            path_in_vcf: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz"),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("dbsnp_data"),
+            cf_name_by_rsid: String::from("dbsnp_by_rsid"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: true,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_vcf: String::from("tests/dbsnp/example/dbsnp.brca1.vcf"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(validation.problems[0].starts_with("tabix index"));
+        assert!(run(&common, &args).is_err());
+    }
+
+    /// Import with `--sample-fraction` into `path_out_rocksdb` and return the keys.
+    fn import_sampled(path_out_rocksdb: &std::path::Path) -> Result<Vec<Vec<u8>>, anyhow::Error> {
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            path_in_vcf: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz"),
+            path_out_rocksdb: format!("{}", path_out_rocksdb.display()),
+            cf_name: String::from("dbsnp_data"),
+            cf_name_by_rsid: String::from("dbsnp_by_rsid"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: false,
+            sample: common::sample::ArgsSample {
+                sample_fraction: Some(0.5),
+                sample_every_nth: None,
+            },
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+            tbi_window_size: 1_000_000,
+        };
+        run(&common, &args)?;
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            path_out_rocksdb,
+            ["meta", "dbsnp_data", "dbsnp_by_rsid"],
+            false,
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        assert_eq!(
+            db.get_cf(&cf_meta, common::sample::META_SAMPLE_FRACTION)?,
+            Some(b"0.5".to_vec())
+        );
+        let cf_data = db.cf_handle("dbsnp_data").unwrap();
+        Ok(db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .map(|result| result.map(|(key, _)| key.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[test]
+    fn import_dbsnp_sampled() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+
+        let first = import_sampled(&tmp_dir.join("first"))?;
+        let second = import_sampled(&tmp_dir.join("second"))?;
+
+        assert_eq!(first, second);
+        // The fixture has 50 alleles.
+        assert!((15..=35).contains(&first.len()), "{}", first.len());
+
+        Ok(())
+    }
 }
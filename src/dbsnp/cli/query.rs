@@ -1,12 +1,11 @@
 //! Query of dbSNP annotation data.
 
-use std::{io::Write, sync::Arc};
+use std::sync::Arc;
 
 use byteorder::ByteOrder as _;
-use prost::Message;
 
 use crate::{
-    common::{self, cli::extract_chrom, keys, spdi},
+    common::{self, cli::extract_chrom, decode::decode_protobuf, keys, spdi},
     cons::cli::args::vars::ArgsQuery,
     dbsnp,
 };
@@ -16,8 +15,11 @@ use crate::{
 #[command(about = "query dbSNP data stored in RocksDB", long_about = None)]
 pub struct Args {
     /// Path to RocksDB directory with data.
-    #[arg(long)]
-    pub path_rocksdb: String,
+    #[arg(long, required_unless_present = "server_url")]
+    pub path_rocksdb: Option<String>,
+    /// URL of a running annonars server to query instead of a local RocksDB.
+    #[arg(long, conflicts_with = "path_rocksdb")]
+    pub server_url: Option<String>,
     /// Name of the column family to import into.
     #[arg(long, default_value = "dbsnp_data")]
     pub cf_name: String,
@@ -28,12 +30,16 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// Fail on the first query from `--path-input-jsonl` that cannot be parsed or run rather
+    /// than writing an error record.
+    #[arg(long, requires = "path_input_jsonl")]
+    pub strict: bool,
 }
 
 /// Meta information as read from database.
@@ -45,6 +51,8 @@ pub struct Meta {
     pub db_name: String,
     /// Version of the database.
     pub db_version: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -59,35 +67,37 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data, cf_by_rs_id];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_db_name = String::from_utf8(
-            db.get_cf(&cf_meta, "db-name")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-schema"))?,
-        )?;
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
-        let meta_db_version = String::from_utf8(
-            db.get_cf(&cf_meta, "db-version")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-schema"))?,
-        )?;
+        let meta_db_name =
+            String::from_utf8(db.get_cf(&cf_meta, "db-name")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-schema")
+            })?)?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
+        let meta_db_version =
+            String::from_utf8(db.get_cf(&cf_meta, "db-version")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-schema")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
             db_name: meta_db_name,
             db_version: meta_db_version,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
     tracing::info!("  meta:db-name = {}", &meta.db_name);
     tracing::info!("  meta:genome-release = {}", &meta.genome_release);
     tracing::info!("  meta:db-version = {}", &meta.db_version);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -100,26 +110,11 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 pub fn open_rocksdb_from_args(
     args: &Args,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
-    open_rocksdb(
-        &args.path_rocksdb,
-        &args.cf_name,
-        "meta",
-        &args.cf_name_by_rsid,
-    )
-}
-
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &dbsnp::pbs::Record,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
-    }
-
-    Ok(())
+    let path_rocksdb = args
+        .path_rocksdb
+        .as_ref()
+        .ok_or_else(|| crate::ErrorCategory::Usage.error("missing --path-rocksdb"))?;
+    open_rocksdb(path_rocksdb, &args.cf_name, "meta", &args.cf_name_by_rsid)
 }
 
 /// Query for a single variant in the RocksDB database.
@@ -127,7 +122,7 @@ pub fn query_for_variant(
     variant: &common::spdi::Var,
     meta: &Meta,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<Option<dbsnp::pbs::Record>, anyhow::Error> {
     // Split off the genome release (checked) and convert to key as used in database.
     let query = spdi::Var {
@@ -136,33 +131,35 @@ pub fn query_for_variant(
     };
     // Execute query.
     tracing::debug!("query = {:?}", &query);
-    let var: keys::Var = query.into();
-    let key: Vec<u8> = var.into();
-    let raw_value = db
-        .get_cf(cf_data, key)
-        .map_err(|e| anyhow::anyhow!("error while querying for variant {}: {}", variant, e))?;
-    raw_value
-        .map(|raw_value| {
-            // Decode via prost.
-            dbsnp::pbs::Record::decode(&mut std::io::Cursor::new(&raw_value))
-                .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
-        })
-        .transpose()
+    let key = common::norm::query_key(query, meta.keys_normalized);
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let raw_value = db.get_cf(&cf_data, &key).map_err(|e| {
+        crate::ErrorCategory::Database.error(format!(
+            "error while querying for variant {}: {}",
+            variant, e
+        ))
+    })?;
+    Ok(raw_value
+        .map(|raw_value| decode_protobuf::<dbsnp::pbs::Record>(db, cf_data_name, &key, &raw_value))
+        .transpose()?)
 }
 
 /// Query for a single variant by accession.
 pub fn query_for_accession(
     accession: &str,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
     cf_data_by_rsid: &Arc<rocksdb::BoundColumnFamily>,
 ) -> Result<Option<dbsnp::pbs::Record>, anyhow::Error> {
     // Convert accession into i32 number.
     let accession = accession.to_lowercase();
-    let accession = accession
-        .strip_prefix("rs")
-        .ok_or_else(|| anyhow::anyhow!("accession {} does not start with 'rs'", accession))?;
-    let ac_i32: i32 = accession.parse()?;
+    let accession = accession.strip_prefix("rs").ok_or_else(|| {
+        crate::ErrorCategory::Usage
+            .error(format!("accession {} does not start with 'rs'", accession))
+    })?;
+    let ac_i32: i32 = accession
+        .parse()
+        .map_err(|e| crate::ErrorCategory::Usage.wrap(e))?;
     let ac_key: Vec<u8> = {
         let mut buf = [0; 4];
         byteorder::LittleEndian::write_i32(&mut buf[0..4], ac_i32);
@@ -172,70 +169,78 @@ pub fn query_for_accession(
     // First, lookup accession.
     let var_key = db
         .get_cf(cf_data_by_rsid, ac_key)
-        .map_err(|e| anyhow::anyhow!("error while querying for accession {}: {}", ac_i32, e))?
-        .ok_or_else(|| anyhow::anyhow!("no record found for accession {}", ac_i32))?;
+        .map_err(|e| {
+            crate::ErrorCategory::Database.error(format!(
+                "error while querying for accession {}: {}",
+                ac_i32, e
+            ))
+        })?
+        .ok_or_else(|| {
+            crate::ErrorCategory::NotFound
+                .error(format!("no record found for accession {}", ac_i32))
+        })?;
 
     // Execute query for key.
-    let raw_value = db
-        .get_cf(cf_data, var_key.clone())
-        .map_err(|e| anyhow::anyhow!("error while querying for variant {:?}: {}", &var_key, e))?;
-    raw_value
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let raw_value = db.get_cf(&cf_data, var_key.clone()).map_err(|e| {
+        crate::ErrorCategory::Database.error(format!(
+            "error while querying for variant {:?}: {}",
+            &var_key, e
+        ))
+    })?;
+    Ok(raw_value
         .map(|raw_value| {
-            // Decode via prost.
-            dbsnp::pbs::Record::decode(&mut std::io::Cursor::new(&raw_value))
-                .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
+            decode_protobuf::<dbsnp::pbs::Record>(db, cf_data_name, &var_key, &raw_value)
         })
-        .transpose()
+        .transpose()?)
 }
 
-/// Implementation of `tsv query` sub command.
-pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("Starting 'dbsnp query' command");
-    tracing::info!("common = {:#?}", &common);
-    tracing::info!("args = {:#?}", &args);
-
-    let (db, meta) = open_rocksdb_from_args(args)?;
+/// Run the single `query` and write its records to `out_writer`.
+fn run_query(
+    query: &ArgsQuery,
+    args: &Args,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+    out_writer: &mut common::output::RecordWriter,
+) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let cf_by_rsid = db.cf_handle(&args.cf_name_by_rsid).unwrap();
 
-    // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
+    // Contigs without records are not an error, e.g., chrY in an autosomes-only database.
+    if let Some(contig) = common::contig::of_query(query, &meta.genome_release)? {
+        if let Err(e) = common::contig::lookup(db, &args.cf_name, &contig, "dbsnp") {
+            tracing::warn!("{}", e);
+            return Ok(());
         }
-    };
+    }
 
-    tracing::info!("Running query...");
-    let before_query = std::time::Instant::now();
-    if let Some(accession) = args.query.accession.as_ref() {
-        if let Some(record) = query_for_accession(accession, &db, &cf_data, &cf_by_rsid)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+    if let Some(accession) = query.accession.as_ref() {
+        if let Some(record) = query_for_accession(accession, db, &args.cf_name, &cf_by_rsid)? {
+            out_writer.write(accession, &record)?;
         } else {
             tracing::info!("no record found for accession {}", accession);
         }
-    } else if let Some(variant) = args.query.variant.as_ref() {
-        if let Some(record) = query_for_variant(variant, &meta, &db, &cf_data)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+    } else if let Some(variant) = query.variant.as_ref() {
+        if let Some(record) = query_for_variant(variant, meta, db, &args.cf_name)? {
+            out_writer.write(
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &record,
+            )?;
         } else {
             tracing::info!("no record found for variant {}", variant);
         }
     } else {
-        let (start, stop) = if let Some(position) = args.query.position.as_ref() {
+        let (start, stop) = if let Some(position) = query.position.as_ref() {
             let position = spdi::Pos {
                 sequence: extract_chrom::from_pos(position, Some(&meta.genome_release))?,
                 ..position.clone()
             };
             (Some(position.clone()), Some(position))
-        } else if let Some(range) = args.query.range.as_ref() {
-            let range = spdi::Range {
-                sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-                ..range.clone()
-            };
+        } else if let Some(range) = query.range.as_ref() {
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
             let (start, stop) = range.into();
             (Some(start), Some(stop))
-        } else if args.query.all {
+        } else if query.all {
             (None, None)
         } else {
             unreachable!()
@@ -274,15 +279,58 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                     }
                 }
 
-                let record = dbsnp::pbs::Record::decode(&mut std::io::Cursor::new(&raw_value))
-                    .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
-                print_record(&mut out_writer, args.out_format, &record)?;
+                let iter_key = iter.key().unwrap();
+                let record: dbsnp::pbs::Record =
+                    decode_protobuf(db, &args.cf_name, iter_key, raw_value)?;
+                out_writer.write(&common::output::db_key(iter_key), &record)?;
                 iter.next();
             } else {
                 break;
             }
         }
     }
+
+    Ok(())
+}
+
+/// Implementation of `tsv query` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'dbsnp query' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if let Some(server_url) = args.server_url.as_ref() {
+        return common::remote::run_query(
+            server_url,
+            &args.query,
+            "dbsnp",
+            &args.out_file,
+            args.output.output_format,
+        );
+    }
+
+    let (db, meta) = open_rocksdb_from_args(args)?;
+
+    // Obtain writer to output.
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "dbsnp",
+        Some(&meta.genome_release),
+    )?;
+
+    tracing::info!("Running query...");
+    let before_query = std::time::Instant::now();
+    if let Some(path_input_jsonl) = args.query.path_input_jsonl.as_ref() {
+        common::batch::run(
+            common::batch::open_input(path_input_jsonl)?,
+            args.strict,
+            &mut out_writer,
+            |query: &ArgsQuery, out_writer| run_query(query, args, &db, &meta, out_writer),
+        )?;
+    } else {
+        run_query(&args.query, args, &db, &meta, &mut out_writer)?;
+    }
     tracing::info!("... done querying in {:?}", before_query.elapsed());
 
     tracing::info!("All done. Have a nice day!");
@@ -301,14 +349,17 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            path_rocksdb: String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
+            path_rocksdb: Some(String::from("tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db")),
+            server_url: None,
             cf_name: String::from("dbsnp_data"),
             cf_name_by_rsid: String::from("dbsnp_by_rsid"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             query,
+            strict: false,
         };
 
         (common, args, temp)
@@ -340,6 +391,32 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn smoke_query_var_single_lowercase() -> Result<(), anyhow::Error> {
+        let (common, args_upper, _temp_upper) = args(ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:17:41267746:C:CA")?),
+            ..Default::default()
+        });
+        run(&common, &args_upper)?;
+        let (common, args_lower, _temp_lower) = args(ArgsQuery {
+            variant: Some(spdi::Var::new(
+                String::from("GRCh37:17"),
+                41267746,
+                String::from("c"),
+                String::from("ca"),
+            )),
+            ..Default::default()
+        });
+        run(&common, &args_lower)?;
+
+        let out_upper = std::fs::read_to_string(&args_upper.out_file)?;
+        let out_lower = std::fs::read_to_string(&args_lower.out_file)?;
+        assert!(!out_upper.is_empty());
+        assert_eq!(out_upper, out_lower);
+
+        Ok(())
+    }
+
     #[test]
     fn smoke_query_pos_single() -> Result<(), anyhow::Error> {
         let (common, args, _temp) = args(ArgsQuery {
@@ -431,6 +508,45 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn query_pos_out_of_bounds() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            position: Some(spdi::Pos::from_str("17:812675200")?),
+            ..Default::default()
+        });
+        let err = run(&common, &args).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "position 812,675,200 exceeds length of chr17 (81,195,210) for GRCh37"
+        );
+
+        Ok(())
+    }
+
+    #[tracing_test::traced_test]
+    #[test]
+    fn query_range_stop_clamped() -> Result<(), anyhow::Error> {
+        let (common, clamped_args, _clamped_temp) = args(ArgsQuery {
+            range: Some(spdi::Range::from_str("17:40000000:500000000")?),
+            ..Default::default()
+        });
+        run(&common, &clamped_args)?;
+        let out_data = std::fs::read_to_string(&clamped_args.out_file)?;
+        assert!(logs_contain(
+            "position 500,000,000 exceeds length of chr17 (81,195,210) for GRCh37, \
+            clamping the range end to 81195210"
+        ));
+
+        let (common, args, _temp) = args(ArgsQuery {
+            range: Some(spdi::Range::from_str("17:40000000:81195210")?),
+            ..Default::default()
+        });
+        run(&common, &args)?;
+        assert_eq!(out_data, std::fs::read_to_string(&args.out_file)?);
+
+        Ok(())
+    }
+
     #[tracing_test::traced_test]
     #[test]
     fn smoke_query_range_by_accession() -> Result<(), anyhow::Error> {
@@ -444,4 +560,214 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:17:41267746:C:CA").unwrap()),
+            ..Default::default()
+        },
+        &["17:41267746:C:CA"],
+    )]
+    #[case::position(
+        ArgsQuery {
+            position: Some(spdi::Pos::from_str("GRCh37:17:41267747").unwrap()),
+            ..Default::default()
+        },
+        &["17:41267747:A:C", "17:41267747:A:G", "17:41267747:A:T"],
+    )]
+    #[case::accession(
+        ArgsQuery {
+            accession: Some("rs431825385".to_string()),
+            ..Default::default()
+        },
+        &["rs431825385"],
+    )]
+    fn smoke_query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args_v1, _temp_v1) = args(query.clone());
+        run(&common, &args_v1)?;
+        let (common, args_v2, _temp_v2) = args(query);
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            "dbsnp",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("variant", "GRCh37:Y:2655180:G:A")]
+    #[case("position", "GRCh37:1:55516888")]
+    #[case("range", "GRCh37:chrUn_gl000220:1:1000")]
+    fn query_contig_not_in_database(
+        #[case] kind: &str,
+        #[case] query: &str,
+    ) -> Result<(), anyhow::Error> {
+        let query = match kind {
+            "variant" => ArgsQuery {
+                variant: Some(spdi::Var::from_str(query)?),
+                ..Default::default()
+            },
+            "position" => ArgsQuery {
+                position: Some(spdi::Pos::from_str(query)?),
+                ..Default::default()
+            },
+            _ => ArgsQuery {
+                range: Some(spdi::Range::from_str(query)?),
+                ..Default::default()
+            },
+        };
+        let (common, args, _temp) = args(query);
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out_data, "");
+
+        Ok(())
+    }
+
+    /// Return the arguments for the queries from `input` and the output lines.
+    fn run_input_jsonl(
+        input: &str,
+        strict: bool,
+    ) -> Result<(Args, Vec<serde_json::Value>), anyhow::Error> {
+        let (common, args, temp) = args(ArgsQuery::default());
+        let path_input = temp.join("input.jsonl").to_string_lossy().to_string();
+        std::fs::write(&path_input, input)?;
+        let args = Args {
+            query: ArgsQuery {
+                path_input_jsonl: Some(path_input),
+                ..Default::default()
+            },
+            strict,
+            ..args
+        };
+        run(&common, &args)?;
+        let lines = std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<serde_json::Value>, _>>()?;
+        Ok((args, lines))
+    }
+
+    /// Return the output lines of the single `query`.
+    fn single_query_lines(query: ArgsQuery) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        let (common, args, _temp) = args(query);
+        run(&common, &args)?;
+        Ok(std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[test]
+    fn query_path_input_jsonl() -> Result<(), anyhow::Error> {
+        // Variants with a record, variants without, accessions, and positions with three
+        // records each.
+        let input = (0..1000)
+            .map(|i| match i % 4 {
+                0 => String::from("{\"variant\": \"GRCh37:17:41267746:C:CA\"}\n"),
+                1 => format!("{{\"variant\": \"GRCh37:17:{}:A:T\"}}\n", 41_000_000 + i),
+                2 => String::from("{\"accession\": \"rs431825385\"}\n"),
+                _ => String::from("{\"position\": \"GRCh37:17:41267747\"}\n"),
+            })
+            .collect::<String>();
+
+        let (_args, lines) = run_input_jsonl(&input, false)?;
+
+        let by_offset = [
+            (
+                0,
+                single_query_lines(ArgsQuery {
+                    variant: Some(spdi::Var::from_str("GRCh37:17:41267746:C:CA")?),
+                    ..Default::default()
+                })?,
+            ),
+            (
+                2,
+                single_query_lines(ArgsQuery {
+                    accession: Some(String::from("rs431825385")),
+                    ..Default::default()
+                })?,
+            ),
+            (
+                3,
+                single_query_lines(ArgsQuery {
+                    position: Some(spdi::Pos::from_str("GRCh37:17:41267747")?),
+                    ..Default::default()
+                })?,
+            ),
+        ];
+        let mut expected = Vec::new();
+        for group in 0..250 {
+            for (offset, records) in &by_offset {
+                for record in records {
+                    let mut record = record.clone();
+                    record
+                        .as_object_mut()
+                        .unwrap()
+                        .insert(String::from("input_index"), (4 * group + offset).into());
+                    expected.push(record);
+                }
+            }
+        }
+        assert_eq!(lines.len(), 1250);
+        assert_eq!(lines, expected);
+
+        Ok(())
+    }
+
+    const INPUT_WITH_ERRORS: &str = "{\"variant\": \"GRCh37:17:41267746:C:CA\"}\n\
+        {\"variant\": \"GRCh37:17:41267746\"}\n\
+        {\"gene\": \"BRCA1\"}\n\
+        {\"variant\": \"GRCh37:Y:2655180:G:A\"}\n\
+        {\"accession\": \"rs431825385\"}\n";
+
+    #[test]
+    fn query_path_input_jsonl_errors() -> Result<(), anyhow::Error> {
+        let (_args, lines) = run_input_jsonl(INPUT_WITH_ERRORS, false)?;
+
+        let input_indices = lines
+            .iter()
+            .map(|line| line["input_index"].as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(input_indices, vec![Some(0), Some(1), Some(2), Some(4)]);
+        assert_eq!(
+            lines[1],
+            serde_json::json!({
+                "input_index": 1,
+                "error": "invalid \"GRCh37:17:41267746\", expected \
+                    SEQUENCE:POSITION:DELETION:INSERTION"
+            })
+        );
+        assert_eq!(
+            lines[2],
+            serde_json::json!({"input_index": 2, "error": "gene queries are not supported"})
+        );
+        assert!(lines[0].get("error").is_none());
+        assert!(lines[3].get("error").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_path_input_jsonl_strict() {
+        let err = run_input_jsonl(INPUT_WITH_ERRORS, true).unwrap_err();
+
+        assert_eq!(err.to_string(), "problem with query in line 2");
+        assert_eq!(crate::ErrorCategory::of(&err), crate::ErrorCategory::Usage);
+    }
 }
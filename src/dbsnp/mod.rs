@@ -2,3 +2,4 @@
 
 pub mod cli;
 pub mod pbs;
+pub mod rs_id;
@@ -0,0 +1,177 @@
+//! Annotation of query results with dbSNP rsIDs.
+//!
+//! The `freqs query` and `gnomad-nuclear query` commands accept `--path-dbsnp-rocksdb` to
+//! add an `rs_id` field to each record, `null` for variants not in dbSNP.  The rsID is
+//! resolved with one point lookup per record into the dbSNP data column family, using the
+//! `CHROM:POS:REF:ALT` key of the record.
+
+use std::sync::Arc;
+
+use crate::{
+    common::{decode::decode_protobuf, keys, output::RecordWriter},
+    dbsnp,
+};
+
+/// Name of the dbSNP data column family.
+const CF_DATA: &str = "dbsnp_data";
+
+/// Lookup of rsIDs in a dbSNP database.
+pub struct RsIdLookup {
+    /// The dbSNP database.
+    db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    /// Meta information of the dbSNP database.
+    meta: dbsnp::cli::query::Meta,
+}
+
+impl RsIdLookup {
+    /// Open the dbSNP database at `path_rocksdb`, which must have `genome_release`.
+    pub fn open(path_rocksdb: &str, genome_release: &str) -> Result<Self, anyhow::Error> {
+        let (db, meta) =
+            dbsnp::cli::query::open_rocksdb(path_rocksdb, CF_DATA, "meta", "dbsnp_by_rsid")?;
+        if !meta.genome_release.eq_ignore_ascii_case(genome_release) {
+            anyhow::bail!(
+                "genome release of dbSNP database ({}) does not match the queried database ({})",
+                &meta.genome_release,
+                genome_release
+            );
+        }
+        Ok(Self { db, meta })
+    }
+
+    /// Return the rsID of the variant with the key `CHROM:POS:REF:ALT`, if any.
+    pub fn rs_id(&self, key: &str) -> Result<Option<i32>, anyhow::Error> {
+        let var = match key.splitn(4, ':').collect::<Vec<_>>()[..] {
+            [chrom, pos, reference, alternative] => {
+                keys::Var::from(chrom, pos.parse()?, reference, alternative)
+            }
+            _ => anyhow::bail!("invalid variant key {:?}", key),
+        };
+        let key: Vec<u8> = if self.meta.keys_normalized {
+            var.trimmed().into()
+        } else {
+            var.into()
+        };
+
+        let cf_data = self.db.cf_handle(CF_DATA).unwrap();
+        let raw_value = self
+            .db
+            .get_cf(&cf_data, &key)
+            .map_err(|e| anyhow::anyhow!("problem querying dbSNP database: {}", e))?;
+        Ok(raw_value
+            .map(|raw_value| {
+                decode_protobuf::<dbsnp::pbs::Record>(&self.db, CF_DATA, &key, &raw_value)
+            })
+            .transpose()?
+            .map(|record| record.rs_id))
+    }
+}
+
+/// A record together with its rsID.
+#[derive(Debug, serde::Serialize)]
+pub struct WithRsId<'a, T: serde::Serialize + ?Sized> {
+    /// The record itself.
+    #[serde(flatten)]
+    pub record: &'a T,
+    /// The rsID, `None` if not in dbSNP.
+    pub rs_id: Option<i32>,
+}
+
+/// Write `record` with the variant key `key` to `out_writer`, with its rsID if `rs_ids` is
+/// given.
+pub fn write_record<T: serde::Serialize + ?Sized>(
+    out_writer: &mut RecordWriter,
+    rs_ids: Option<&RsIdLookup>,
+    key: &str,
+    record: &T,
+) -> Result<(), anyhow::Error> {
+    match rs_ids {
+        Some(rs_ids) => out_writer.write(
+            key,
+            &WithRsId {
+                record,
+                rs_id: rs_ids.rs_id(key)?,
+            },
+        ),
+        None => out_writer.write(key, record),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use prost::Message as _;
+
+    use super::*;
+
+    /// Write a GRCh37 dbSNP database with normalized keys and the given
+    /// `(CHROM:POS:REF:ALT, rs_id)` records and return its path.
+    pub(crate) fn write_dbsnp_db(
+        tmp_dir: &temp_testdir::TempDir,
+        records: &[(&str, i32)],
+    ) -> String {
+        let path = format!("{}", tmp_dir.join("dbsnp").display());
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, &path, ["meta", CF_DATA, "dbsnp_by_rsid"]).unwrap();
+        let cf_meta = db.cf_handle("meta").unwrap();
+        db.put_cf(&cf_meta, "genome-release", "grch37").unwrap();
+        db.put_cf(&cf_meta, "db-name", "dbsnp").unwrap();
+        db.put_cf(&cf_meta, "db-version", "b151").unwrap();
+        crate::common::norm::write_meta(&db, &cf_meta).unwrap();
+        let cf_data = db.cf_handle(CF_DATA).unwrap();
+        for (key, rs_id) in records {
+            let [chrom, pos, reference, alternative] = key.split(':').collect::<Vec<_>>()[..]
+            else {
+                panic!("invalid key {:?}", key);
+            };
+            let var = keys::Var::from(chrom, pos.parse().unwrap(), reference, alternative);
+            let record = dbsnp::pbs::Record {
+                chrom: chrom.to_string(),
+                pos: var.pos,
+                ref_allele: reference.to_string(),
+                alt_allele: alternative.to_string(),
+                rs_id: *rs_id,
+            };
+            let key: Vec<u8> = var.trimmed().into();
+            db.put_cf(&cf_data, key, record.encode_to_vec()).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn rs_id() -> Result<(), anyhow::Error> {
+        let path_rocksdb = "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db";
+        let rs_ids = RsIdLookup::open(path_rocksdb, "GRCh37")?;
+
+        assert_eq!(rs_ids.rs_id("17:41267746:C:A")?, Some(80357446));
+        assert_eq!(rs_ids.rs_id("17:41267747:A:G")?, Some(80357327));
+        assert_eq!(rs_ids.rs_id("17:41267747:A:AT")?, None);
+        assert!(rs_ids.rs_id("17:41267746").is_err());
+        assert!(RsIdLookup::open(path_rocksdb, "grch38").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn with_rs_id_json() -> Result<(), anyhow::Error> {
+        let record = serde_json::json!({"chrom": "1", "pos": 55516888});
+
+        assert_eq!(
+            serde_json::to_value(WithRsId {
+                record: &record,
+                rs_id: Some(1234),
+            })?,
+            serde_json::json!({"chrom": "1", "pos": 55516888, "rs_id": 1234})
+        );
+        assert_eq!(
+            serde_json::to_value(WithRsId {
+                record: &record,
+                rs_id: None,
+            })?,
+            serde_json::json!({"chrom": "1", "pos": 55516888, "rs_id": null})
+        );
+
+        Ok(())
+    }
+}
@@ -53,3 +53,243 @@ pub enum Error {
     #[error("other error")]
     OtherError,
 }
+
+/// Description of the exit codes of `annonars`, shown with `--help`.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0   success, also if the output is closed early, e.g., when piped into `head`
+  1   changes found, e.g., watched genes with changed counts in `clinvar-genes watchlist`
+  2   usage error, e.g., invalid command line arguments or query
+  3   not found, e.g., missing input file, database, or record
+  4   input parse error, e.g., malformed input file
+  5   database error, e.g., database that cannot be opened or decoded
+  10  internal error";
+
+/// Category of an error of a command, determining the exit code of `annonars`.
+///
+/// Errors are categorized with `ErrorCategory::wrap` where they are created.  Errors that
+/// have not been categorized explicitly are categorized by their type in `ErrorCategory::of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ErrorCategory {
+    /// Changes found by a command comparing databases; the output has been written.
+    Changed,
+    /// Invalid command line arguments or query.
+    Usage,
+    /// Input file, database, or record not found.
+    NotFound,
+    /// Input file that cannot be parsed.
+    InputParse,
+    /// Database that cannot be opened or read.
+    Database,
+    /// Any other error.
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Return the exit code of the category.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorCategory::Changed => 1,
+            ErrorCategory::Usage => 2,
+            ErrorCategory::NotFound => 3,
+            ErrorCategory::InputParse => 4,
+            ErrorCategory::Database => 5,
+            ErrorCategory::Internal => 10,
+        }
+    }
+
+    /// Return `error` annotated with the category.
+    pub fn wrap<E: Into<anyhow::Error>>(self, error: E) -> anyhow::Error {
+        CategorizedError {
+            category: self,
+            error: error.into(),
+        }
+        .into()
+    }
+
+    /// Return an error with the given `message` annotated with the category.
+    pub fn error<M>(self, message: M) -> anyhow::Error
+    where
+        M: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    {
+        self.wrap(anyhow::Error::msg(message))
+    }
+
+    /// Return the category of `error`.
+    ///
+    /// The chain of `error` is searched from the outermost error on, so explicit annotations
+    /// take precedence over the categories inferred from the types of the underlying errors.
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(Self::of_source)
+            .unwrap_or(ErrorCategory::Internal)
+    }
+
+    /// Return the category of one error of a chain, if known.
+    fn of_source(error: &(dyn std::error::Error + 'static)) -> Option<Self> {
+        if let Some(error) = error.downcast_ref::<CategorizedError>() {
+            Some(error.category)
+        } else if let Some(error) = error.downcast_ref::<std::io::Error>() {
+            Self::of_io(error)
+        } else if let Some(error) = error.downcast_ref::<Error>() {
+            match error {
+                Error::RocksDBOpen(..) | Error::RocksDBProperty(..) => {
+                    Some(ErrorCategory::Database)
+                }
+                Error::Io(error) => Self::of_io(error),
+                Error::OtherError => None,
+                _ => Some(ErrorCategory::InputParse),
+            }
+        } else if error.is::<rocksdb::Error>()
+            || error.is::<prost::DecodeError>()
+            || error.is::<crate::common::decode::DecodeError>()
+        {
+            Some(ErrorCategory::Database)
+        } else if error.is::<serde_json::Error>()
+            || error.is::<serde_yaml::Error>()
+            || error.is::<csv::Error>()
+            || error.is::<std::num::ParseIntError>()
+            || error.is::<std::num::ParseFloatError>()
+        {
+            Some(ErrorCategory::InputParse)
+        } else if error.is::<crate::common::cli::PositionOutOfBounds>() || error.is::<clap::Error>()
+        {
+            Some(ErrorCategory::Usage)
+        } else {
+            None
+        }
+    }
+
+    /// Return the category of an I/O error, if known.
+    fn of_io(error: &std::io::Error) -> Option<Self> {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Some(ErrorCategory::NotFound),
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => {
+                Some(ErrorCategory::InputParse)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Return whether `error` is caused by writing to a closed pipe, e.g., when the output is
+/// piped into `head`.
+pub fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain().any(|error| {
+        error
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|error| error.kind() == std::io::ErrorKind::BrokenPipe)
+    })
+}
+
+/// An error annotated with its `ErrorCategory`, see `ErrorCategory::wrap`.
+///
+/// Displays as and has the sources of the wrapped error.
+#[derive(Debug)]
+pub struct CategorizedError {
+    /// The category of the error.
+    pub category: ErrorCategory,
+    /// The wrapped error.
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for CategorizedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for CategorizedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(ErrorCategory::Changed.exit_code(), 1);
+        assert_eq!(ErrorCategory::Usage.exit_code(), 2);
+        assert_eq!(ErrorCategory::NotFound.exit_code(), 3);
+        assert_eq!(ErrorCategory::InputParse.exit_code(), 4);
+        assert_eq!(ErrorCategory::Database.exit_code(), 5);
+        assert_eq!(ErrorCategory::Internal.exit_code(), 10);
+    }
+
+    #[test]
+    fn of_explicit() {
+        let error = ErrorCategory::NotFound.error("no such record");
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::NotFound);
+        assert_eq!(error.to_string(), "no such record");
+
+        // Context added later keeps the category.
+        let error = error.context("problem querying");
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::NotFound);
+
+        // Explicit annotations take precedence over the inferred category.
+        let error = ErrorCategory::Usage.wrap(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::Usage);
+    }
+
+    #[test]
+    fn of_inferred() {
+        let of = |error: anyhow::Error| ErrorCategory::of(&error);
+
+        assert_eq!(
+            of(std::io::Error::from(std::io::ErrorKind::NotFound).into()),
+            ErrorCategory::NotFound
+        );
+        assert_eq!(
+            of(std::io::Error::from(std::io::ErrorKind::InvalidData).into()),
+            ErrorCategory::InputParse
+        );
+        assert_eq!(
+            of(serde_json::from_str::<serde_json::Value>("{")
+                .unwrap_err()
+                .into()),
+            ErrorCategory::InputParse
+        );
+        assert_eq!(
+            of(anyhow::Error::from("x".parse::<i32>().unwrap_err()).context("invalid count")),
+            ErrorCategory::InputParse
+        );
+        assert_eq!(
+            of(Error::ColumnCount(1, 2).into()),
+            ErrorCategory::InputParse
+        );
+        assert_eq!(
+            of(crate::common::cli::check_position(
+                crate::common::cli::GenomeRelease::Grch37,
+                "1",
+                usize::MAX
+            )
+            .unwrap_err()
+            .into()),
+            ErrorCategory::Usage
+        );
+        assert_eq!(of(anyhow::anyhow!("something")), ErrorCategory::Internal);
+        assert_eq!(
+            of(std::io::Error::from(std::io::ErrorKind::Other).into()),
+            ErrorCategory::Internal
+        );
+    }
+
+    #[test]
+    fn broken_pipe() {
+        let error = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+        assert!(is_broken_pipe(&error));
+        assert!(is_broken_pipe(&error.context("problem writing record")));
+        assert!(is_broken_pipe(
+            &Error::Io(std::io::Error::from(std::io::ErrorKind::BrokenPipe)).into()
+        ));
+        assert!(!is_broken_pipe(
+            &std::io::Error::from(std::io::ErrorKind::NotFound).into()
+        ));
+        assert!(!is_broken_pipe(&anyhow::anyhow!("broken pipe")));
+    }
+}
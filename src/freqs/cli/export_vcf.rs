@@ -0,0 +1,467 @@
+//! Export of frequency database slices as VCF.
+
+use std::{fs::File, io::BufReader, io::Write, path::PathBuf};
+
+use crate::{
+    common::{
+        self,
+        cli::{extract_chrom, GenomeRelease},
+        keys, spdi,
+    },
+    freqs,
+};
+
+/// Command line arguments for `freqs export-vcf` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "export frequency counts from RocksDB as VCF", long_about = None)]
+pub struct Args {
+    /// Path to RocksDB directory with data.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Path to output VCF file, written with BGZF compression if it ends with `.gz`.
+    #[arg(long)]
+    pub path_out_vcf: String,
+
+    /// Range(s) to export, all canonical chromosomes if neither this nor
+    /// `--path-ranges-bed` is given.
+    #[arg(long)]
+    pub range: Vec<spdi::Range>,
+    /// Path(s) to BED files with ranges to export.
+    #[arg(long)]
+    pub path_ranges_bed: Vec<PathBuf>,
+}
+
+/// Read the ranges from the given BED file as 1-based, inclusive ranges.
+fn read_bed_ranges(path_bed: &PathBuf) -> Result<Vec<spdi::Range>, anyhow::Error> {
+    let mut reader = File::open(path_bed)
+        .map(BufReader::new)
+        .map(noodles::bed::Reader::new)
+        .map_err(|e| anyhow::anyhow!("could not open BED file {:?}: {}", path_bed, e))?;
+
+    let mut result = Vec::new();
+    for record in reader.records::<3>() {
+        let record = record?;
+        let start: usize = record.start_position().into();
+        let end: usize = record.end_position().into();
+        result.push(spdi::Range::new(
+            record.reference_sequence_name().to_string(),
+            start as i32 + 1,
+            end as i32,
+        ));
+    }
+    Ok(result)
+}
+
+/// Return the rank of the canonical chromosome `chrom` for sorting.
+fn chrom_rank(chrom: &str) -> Result<usize, anyhow::Error> {
+    common::cli::CANONICAL
+        .iter()
+        .position(|canonical| *canonical == chrom)
+        .ok_or_else(|| anyhow::anyhow!("non-canonical chromosome {:?}", chrom))
+}
+
+/// Sort the ranges by chromosome and start position and merge overlapping or adjacent ones.
+///
+/// The chromosome names must already be canonicalized.
+pub fn merge_ranges(ranges: Vec<spdi::Range>) -> Result<Vec<spdi::Range>, anyhow::Error> {
+    let mut ranges = ranges
+        .into_iter()
+        .map(|range| Ok((chrom_rank(&range.sequence)?, range)))
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    ranges.sort_by_key(|(rank, range)| (*rank, range.start, range.end));
+
+    let mut result: Vec<spdi::Range> = Vec::new();
+    for (_, range) in ranges {
+        match result.last_mut() {
+            Some(last) if last.sequence == range.sequence && range.start <= last.end + 1 => {
+                last.end = std::cmp::max(last.end, range.end);
+            }
+            _ => result.push(range),
+        }
+    }
+    Ok(result)
+}
+
+/// Collect the ranges to export from the command line, defaulting to all canonical
+/// chromosomes of `assembly`.
+fn collect_ranges(
+    args: &Args,
+    genome_release: &str,
+    assembly: biocommons_bioutils::assemblies::Assembly,
+) -> Result<Vec<spdi::Range>, anyhow::Error> {
+    let mut ranges = Vec::new();
+    for range in &args.range {
+        ranges.push(extract_chrom::range(range, Some(genome_release))?);
+    }
+    for path_bed in &args.path_ranges_bed {
+        for range in read_bed_ranges(path_bed)? {
+            ranges.push(spdi::Range {
+                sequence: common::cli::canonicalize(&range.sequence),
+                ..range
+            });
+        }
+    }
+
+    if args.range.is_empty() && args.path_ranges_bed.is_empty() {
+        for (chrom, start, end) in common::cli::build_genome_windows(assembly, None)? {
+            ranges.push(spdi::Range::new(
+                common::cli::canonicalize(&chrom),
+                start as i32 + 1,
+                end as i32,
+            ));
+        }
+    }
+
+    merge_ranges(ranges)
+}
+
+/// Write the VCF header with the `INFO` fields and the canonical contigs of `assembly`.
+fn write_header<W: Write>(
+    writer: &mut W,
+    assembly: biocommons_bioutils::assemblies::Assembly,
+) -> Result<(), anyhow::Error> {
+    let info = &biocommons_bioutils::assemblies::ASSEMBLY_INFOS[assembly];
+
+    writeln!(writer, "##fileformat=VCFv4.2")?;
+    for source in [
+        "gnomad_exomes",
+        "gnomad_genomes",
+        "gnomad_mtdna",
+        "helixmtdb",
+    ] {
+        writeln!(
+            writer,
+            "##INFO=<ID={source}_AC,Number=A,Type=Integer,Description=\"Alternate allele count in {source}\">"
+        )?;
+        writeln!(
+            writer,
+            "##INFO=<ID={source}_AN,Number=1,Type=Integer,Description=\"Total number of alleles in {source}\">"
+        )?;
+        writeln!(
+            writer,
+            "##INFO=<ID={source}_AF,Number=A,Type=Float,Description=\"Alternate allele frequency in {source}\">"
+        )?;
+        writeln!(
+            writer,
+            "##INFO=<ID={source}_nhomalt,Number=A,Type=Integer,Description=\"Number of homozygous (homoplasmic for chrMT) alternate alleles in {source}\">"
+        )?;
+        if source.starts_with("gnomad_") && source != "gnomad_mtdna" {
+            writeln!(
+                writer,
+                "##INFO=<ID={source}_nhemialt,Number=A,Type=Integer,Description=\"Number of hemizygous alternate alleles in {source} (chrX/chrY only)\">"
+            )?;
+        }
+    }
+    for seq in &info.sequences {
+        if common::cli::is_canonical(&seq.name) {
+            writeln!(
+                writer,
+                "##contig=<ID={},length={},assembly={}>",
+                common::cli::canonicalize(&seq.name),
+                seq.length,
+                &info.name
+            )?;
+        }
+    }
+    writeln!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+
+    Ok(())
+}
+
+/// Decode the variant from a RocksDB key `CHROM-POS-REF>ALT`.
+pub(crate) fn decode_key(key: &[u8]) -> Result<keys::Var, anyhow::Error> {
+    let pos: keys::Pos = key.into();
+    let alleles = std::str::from_utf8(&key[6..])?;
+    let (reference, alternative) = alleles
+        .split_once('>')
+        .ok_or_else(|| anyhow::anyhow!("invalid alleles in key: {:?}", alleles))?;
+    Ok(keys::Var::new(
+        pos.chrom,
+        pos.pos,
+        reference.to_string(),
+        alternative.to_string(),
+    ))
+}
+
+/// Append the `INFO` entries for one source, skipping sources without data.
+fn push_info(
+    info: &mut Vec<String>,
+    source: &str,
+    an: u32,
+    ac: u32,
+    nhomalt: u32,
+    nhemialt: Option<u32>,
+) {
+    if an == 0 {
+        return;
+    }
+    info.push(format!("{source}_AC={ac}"));
+    info.push(format!("{source}_AN={an}"));
+    info.push(format!("{source}_AF={:.5e}", ac as f64 / an as f64));
+    info.push(format!("{source}_nhomalt={nhomalt}"));
+    if let Some(nhemialt) = nhemialt {
+        info.push(format!("{source}_nhemialt={nhemialt}"));
+    }
+}
+
+/// Build the `INFO` column from the raw value of column family `cf_name`.
+fn info_column(cf_name: &str, raw_value: &[u8]) -> String {
+    let mut info = Vec::new();
+    match cf_name {
+        "mitochondrial" => {
+            let record = freqs::serialized::mt::Record::from_buf(raw_value);
+            for (source, counts) in [
+                ("gnomad_mtdna", &record.gnomad_mtdna),
+                ("helixmtdb", &record.helixmtdb),
+            ] {
+                let ac = counts.ac_hom + counts.ac_het;
+                push_info(&mut info, source, counts.an, ac, counts.ac_hom, None);
+            }
+        }
+        "gonosomal" => {
+            let record = freqs::serialized::xy::Record::from_buf(raw_value);
+            for (source, counts) in [
+                ("gnomad_exomes", &record.gnomad_exomes),
+                ("gnomad_genomes", &record.gnomad_genomes),
+            ] {
+                let ac = 2 * counts.ac_hom + counts.ac_het + counts.ac_hemi;
+                push_info(
+                    &mut info,
+                    source,
+                    counts.an,
+                    ac,
+                    counts.ac_hom,
+                    Some(counts.ac_hemi),
+                );
+            }
+        }
+        _ => {
+            let record = freqs::serialized::auto::Record::from_buf(raw_value);
+            for (source, counts) in [
+                ("gnomad_exomes", &record.gnomad_exomes),
+                ("gnomad_genomes", &record.gnomad_genomes),
+            ] {
+                let ac = 2 * counts.ac_hom + counts.ac_het;
+                push_info(&mut info, source, counts.an, ac, counts.ac_hom, None);
+            }
+        }
+    }
+
+    if info.is_empty() {
+        ".".to_string()
+    } else {
+        info.join(";")
+    }
+}
+
+/// Write the records in `range` to `writer`, returning the number of records written.
+fn export_range<W: Write>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    range: &spdi::Range,
+    writer: &mut W,
+) -> Result<usize, anyhow::Error> {
+    let cf_name = match range.sequence.as_str() {
+        "MT" => "mitochondrial",
+        "X" | "Y" => "gonosomal",
+        _ => "autosomal",
+    };
+    let cf_data = db.cf_handle(cf_name).unwrap();
+
+    let (start, stop): (spdi::Pos, spdi::Pos) = range.clone().into();
+    let start: keys::Pos = start.into();
+    let start: Vec<u8> = start.into();
+    let stop: keys::Pos = stop.into();
+
+    let mut count = 0;
+    let mut iter = db.raw_iterator_cf(&cf_data);
+    iter.seek(&start);
+    while iter.valid() {
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let var = decode_key(iter_key)?;
+            if var.chrom != stop.chrom || var.pos > stop.pos {
+                break;
+            }
+
+            writeln!(
+                writer,
+                "{}\t{}\t.\t{}\t{}\t.\t.\t{}",
+                &var.chrom,
+                var.pos,
+                &var.reference,
+                &var.alternative,
+                info_column(cf_name, iter_value)
+            )?;
+            count += 1;
+
+            iter.next();
+        } else {
+            break;
+        }
+    }
+    iter.status()?;
+
+    Ok(count)
+}
+
+/// Implementation of `freqs export-vcf` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'freqs export-vcf' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let (db, meta) = freqs::cli::query::open_rocksdb(
+        &args.path_rocksdb,
+        "autosomal",
+        "gonosomal",
+        "mitochondrial",
+        "meta",
+    )?;
+    let genome_release: GenomeRelease =
+        meta.genome_release.to_lowercase().parse().map_err(|e| {
+            anyhow::anyhow!("invalid genome release {:?}: {}", &meta.genome_release, e)
+        })?;
+    let assembly: biocommons_bioutils::assemblies::Assembly = genome_release.into();
+
+    let ranges = collect_ranges(args, &meta.genome_release, assembly)?;
+    tracing::info!("Exporting {} range(s)...", ranges.len());
+    let before_export = std::time::Instant::now();
+
+    let file = File::create(&args.path_out_vcf)
+        .map_err(|e| anyhow::anyhow!("could not create {}: {}", &args.path_out_vcf, e))?;
+    let mut writer = if args.path_out_vcf.ends_with(".gz") {
+        Box::new(noodles::bgzf::Writer::new(file)) as Box<dyn Write>
+    } else {
+        Box::new(std::io::BufWriter::new(file)) as Box<dyn Write>
+    };
+
+    write_header(&mut writer, assembly)?;
+    let mut count = 0;
+    for range in &ranges {
+        count += export_range(&db, range, &mut writer)?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    tracing::info!(
+        "... done exporting {} record(s) in {:?}",
+        count,
+        before_export.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    #[test]
+    fn merge_ranges_sorts_and_merges() -> Result<(), anyhow::Error> {
+        let ranges = vec![
+            spdi::Range::new("MT".into(), 1, 100),
+            spdi::Range::new("X".into(), 50, 60),
+            spdi::Range::new("10".into(), 1, 10),
+            spdi::Range::new("2".into(), 20, 30),
+            spdi::Range::new("2".into(), 1, 10),
+            spdi::Range::new("2".into(), 11, 15),
+            spdi::Range::new("2".into(), 25, 40),
+        ];
+
+        assert_eq!(
+            merge_ranges(ranges)?,
+            vec![
+                spdi::Range::new("2".into(), 1, 15),
+                spdi::Range::new("2".into(), 20, 40),
+                spdi::Range::new("10".into(), 1, 10),
+                spdi::Range::new("X".into(), 50, 60),
+                spdi::Range::new("MT".into(), 1, 100),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_vcf_ranges() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let common_args = common::cli::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_rocksdb: "tests/freqs/grch37/v2.1/example/freqs.db".into(),
+            path_out_vcf: temp.join("out.vcf").to_string_lossy().to_string(),
+            range: vec![
+                spdi::Range::from_str("M:11:11")?,
+                spdi::Range::from_str("GRCh37:X:69902557:69902557")?,
+                spdi::Range::from_str("1:55516885:55516885")?,
+            ],
+            path_ranges_bed: Vec::new(),
+        };
+
+        run(&common_args, &args)?;
+        let out_data = std::fs::read_to_string(&args.path_out_vcf)?;
+
+        assert!(out_data.starts_with("##fileformat=VCFv4.2\n"));
+        assert!(out_data.contains("##contig=<ID=1,length=249250621,"));
+        assert!(out_data.contains("##contig=<ID=MT,length=16569,"));
+
+        // Records are sorted by chromosome, independent of the order of the ranges.
+        let records = out_data
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        let chroms = records
+            .iter()
+            .map(|line| line.split('\t').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(chroms.first(), Some(&"1"));
+        assert_eq!(chroms.last(), Some(&"MT"));
+        assert!(records.iter().any(|line| line.starts_with(
+            "1\t55516885\t.\tG\tA\t.\t.\tgnomad_exomes_AC=1;gnomad_exomes_AN=128256;"
+        )));
+        assert!(records.iter().any(|line| line.starts_with(
+            "X\t69902557\t.\tG\tT\t.\t.\tgnomad_exomes_AC=1;gnomad_exomes_AN=179321;"
+        )));
+        assert!(records
+            .iter()
+            .any(|line| line
+                .starts_with("MT\t11\t.\tC\tT\t.\t.\thelixmtdb_AC=1;helixmtdb_AN=196554;")));
+
+        // All records round-trip to the database through `freqs query`.
+        let (db, meta) = freqs::cli::query::open_rocksdb(
+            &args.path_rocksdb,
+            "autosomal",
+            "gonosomal",
+            "mitochondrial",
+            "meta",
+        )?;
+        for line in records {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let variant = spdi::Var::new(
+                fields[0].to_string(),
+                fields[1].parse()?,
+                fields[3].to_string(),
+                fields[4].to_string(),
+            );
+            assert!(
+                freqs::cli::query::query_for_variant(
+                    &variant,
+                    &meta,
+                    &db,
+                    common::cli::OutputFormat::Jsonl
+                )?
+                .is_some(),
+                "{}",
+                line
+            );
+        }
+
+        Ok(())
+    }
+}
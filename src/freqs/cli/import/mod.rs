@@ -3,6 +3,7 @@
 pub mod auto;
 pub mod mt;
 pub mod reading;
+pub mod retry;
 pub mod xy;
 
 use std::{collections::HashMap, sync::Arc};
@@ -20,9 +21,9 @@ use reading::ContigMap;
 #[derive(Parser, Debug, Clone)]
 #[command(about = "Construct sequence variant frequencies database", long_about = None)]
 pub struct Args {
-    /// Genome build to use in the build.
+    /// Genome build to use in the build, `auto` detects it from the gnomAD VCF headers.
     #[arg(long, value_enum)]
-    pub genome_release: common::cli::GenomeRelease,
+    pub genome_release: common::assembly_detect::GenomeReleaseArg,
     /// Path to the output database to build.
     #[arg(long)]
     pub path_out_rocksdb: String,
@@ -49,9 +50,25 @@ pub struct Args {
     /// Optional path to WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Compact each chromosome after its import instead of running one full compaction at
+    /// the end (lowers peak disk usage, see `common::compaction`).
+    #[arg(long)]
+    pub incremental_compaction: bool,
+    /// Write prefix bloom filters for faster point lookups (see `common::prefix_bloom`).
+    #[arg(long)]
+    pub prefix_bloom: bool,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
     /// Windows size for TBI-based parallel import.
     #[arg(long, default_value = "100000")]
     pub tbi_window_size: usize,
+    /// Maximal number of retries for windows failing with transient I/O errors.
+    #[arg(long, default_value_t = 3)]
+    pub max_retries: usize,
+    /// Backoff in seconds before the first retry of a window, doubled after each retry.
+    #[arg(long, default_value_t = 10)]
+    pub retry_backoff_secs: u64,
 
     /// Version of gnomAD genomes.
     #[arg(long)]
@@ -65,6 +82,15 @@ pub struct Args {
     /// Version of HelixMtDb.
     #[arg(long)]
     pub helixmtdb_version: String,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Return mapping from chromosome to path.
@@ -101,21 +127,50 @@ fn assign_to_chrom(
     Ok(res)
 }
 
+/// Tabix indices of the input files, each read once per run.
+///
+/// The windows are built per contig from the paths of the contig and the same file may be
+/// given for several contigs, so the parsed indices are kept by path.
+#[derive(Debug, Default)]
+pub struct TabixIndices {
+    /// The parsed indices by path of the indexed file.
+    indices: HashMap<String, noodles::tabix::Index>,
+}
+
+impl TabixIndices {
+    /// Return the index of the file at `path`, reading `{path}.tbi` on first access.
+    pub fn get(&mut self, path: &str) -> Result<&noodles::tabix::Index, anyhow::Error> {
+        if !self.indices.contains_key(path) {
+            let tabix_src = format!("{}.tbi", path);
+            tracing::debug!("reading tabix index {}", &tabix_src);
+            let index = noodles::tabix::read(&tabix_src).map_err(|e| {
+                anyhow::anyhow!("problem reading tabix index {}: {}", &tabix_src, e)
+            })?;
+            self.indices.insert(path.to_string(), index);
+        }
+        Ok(&self.indices[path])
+    }
+}
+
 /// Get windows for the up to two given paths.
+///
+/// Windows without data in the tabix index of any of the paths are skipped.  The indices are
+/// taken from `indices`.
 pub fn build_windows(
     genome_release: biocommons_bioutils::assemblies::Assembly,
     tbi_window_size: usize,
     paths: &[String],
+    indices: &mut TabixIndices,
 ) -> Result<Vec<(String, usize, usize)>, anyhow::Error> {
     let mut result = Vec::new();
+    let genome_windows = common::cli::build_genome_windows(genome_release, Some(tbi_window_size))?;
 
     for path in paths.iter() {
-        // Load tabix header and create BGZF reader with tabix index.
-        let tabix_src = format!("{}.tbi", path);
-        let index = noodles::tabix::read(tabix_src)?;
-        let header = index.header().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing tabix header")
-        })?;
+        // Load tabix header from the tabix index.
+        let index = indices.get(path)?;
+        let header = index
+            .header()
+            .ok_or_else(|| anyhow::anyhow!("missing tabix header in {}.tbi", path))?;
 
         // Build list of canonical chromosome names from header.
         let canonical_header_chroms = header
@@ -131,18 +186,27 @@ pub fn build_windows(
             })
             .collect::<std::collections::HashMap<String, String>>();
 
-        // Generate list of regions on canonical chromosomes, limited to those present in header.
-        result.append(
-            &mut common::cli::build_genome_windows(genome_release, Some(tbi_window_size))?
-                .into_iter()
-                .filter_map(|(window_chrom, begin, end)| {
-                    let canon_chrom = common::cli::canonicalize(&window_chrom);
-                    canonical_header_chroms
-                        .get(&canon_chrom)
-                        .map(|header_chrom| (header_chrom.clone(), begin, end))
-                })
-                .collect::<Vec<_>>(),
-        );
+        // Generate list of regions on canonical chromosomes, limited to those present in header
+        // and having data according to the index.
+        let windows = genome_windows
+            .iter()
+            .filter_map(|(window_chrom, begin, end)| {
+                let canon_chrom = common::cli::canonicalize(window_chrom);
+                canonical_header_chroms
+                    .get(&canon_chrom)
+                    .map(|header_chrom| (header_chrom.clone(), *begin, *end))
+            })
+            .collect::<Vec<_>>();
+        result.append(&mut common::cli::prune_genome_windows(
+            windows,
+            |(chrom, begin, end)| {
+                let ref_id = header
+                    .reference_sequence_names()
+                    .get_index_of(chrom.as_str())
+                    .expect("chromosome must be in header");
+                common::cli::tbi_window_has_data(index, ref_id, *begin, *end)
+            },
+        )?);
     }
 
     result.sort();
@@ -151,14 +215,98 @@ pub fn build_windows(
     Ok(result)
 }
 
+/// Import `windows` in parallel with `import_region`, retrying windows on transient errors.
+///
+/// Returns the description and number of retries of each window that had to be retried.
+fn import_windows<F>(
+    windows: &[(String, usize, usize)],
+    policy: &retry::RetryPolicy,
+    import_region: F,
+) -> Result<Vec<(String, usize)>, anyhow::Error>
+where
+    F: Fn(&noodles::core::region::Region) -> Result<(), anyhow::Error> + Sync,
+{
+    let results = windows
+        .par_iter()
+        .progress_with(common::cli::progress_bar(windows.len()))
+        .map(
+            |(chrom, begin, end)| -> Result<(String, usize), anyhow::Error> {
+                let start = noodles::core::position::Position::try_from(begin + 1)?;
+                let stop = noodles::core::position::Position::try_from(*end)?;
+                let region = noodles::core::region::Region::new(chrom.as_bytes(), start..=stop);
+                let window = format!("{}:{}-{}", chrom, begin + 1, end);
+                let ((), retries) =
+                    retry::with_retries(policy, &window, || import_region(&region))?;
+                Ok((window, retries))
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(results
+        .into_iter()
+        .filter(|(_, retries)| *retries > 0)
+        .collect())
+}
+
+/// Detect the genome release from the headers of the nuclear gnomAD VCF files.
+///
+/// The mtDNA and HelixMtDb files are not considered as chrMT is the same in both releases.
+fn detect_genome_release(args: &Args) -> Result<common::cli::GenomeRelease, anyhow::Error> {
+    let paths = args
+        .path_gnomad_exomes_auto
+        .iter()
+        .chain(args.path_gnomad_genomes_auto.iter())
+        .chain(args.path_gnomad_exomes_xy.iter())
+        .chain(args.path_gnomad_genomes_xy.iter())
+        .collect::<Vec<_>>();
+    common::assembly_detect::from_vcf_paths(&paths)
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    let genome_release =
+        validation.genome_release(args.genome_release, || detect_genome_release(args));
+    let paths = args
+        .path_gnomad_exomes_auto
+        .iter()
+        .chain(args.path_gnomad_genomes_auto.iter())
+        .chain(args.path_gnomad_exomes_xy.iter())
+        .chain(args.path_gnomad_genomes_xy.iter())
+        .chain(args.path_gnomad_mtdna.iter())
+        .chain(args.path_helixmtdb.iter());
+    for path in paths {
+        if validation.input_file(path) {
+            validation.tabix_index(path);
+            if let Some(genome_release) = genome_release {
+                validation.vcf_header(path, genome_release);
+            }
+        }
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `gnomad_nuclear import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting mehari frequency import ...");
     tracing::info!("  common = {:#?}", &common);
     tracing::info!("  args =   {:#?}", &args);
 
-    // Guess genome release from paths.
-    let genome_release = match args.genome_release {
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+    let sampler = common::sample::Sampler::new(&args.sample)?;
+
+    // Use the given genome release or detect it from the VCF headers.
+    let release = args
+        .genome_release
+        .resolve(|| detect_genome_release(args))?;
+    let genome_release = match release {
         common::cli::GenomeRelease::Grch37 => biocommons_bioutils::assemblies::Assembly::Grch37p10, // has chrMT!
         common::cli::GenomeRelease::Grch38 => biocommons_bioutils::assemblies::Assembly::Grch38,
     };
@@ -170,6 +318,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
+    let options = if args.prefix_bloom {
+        common::prefix_bloom::tune_options(options)
+    } else {
+        options
+    };
     let cf_names = ["meta", "autosomal", "gonosomal", "mitochondrial"];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
@@ -182,6 +335,10 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
+    if args.prefix_bloom {
+        crate::common::prefix_bloom::write_meta(&db, &cf_meta)?;
+    }
     db.put_cf(
         &cf_meta,
         "gnomad-exomes-version",
@@ -194,11 +351,8 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     )?;
     db.put_cf(&cf_meta, "gnomad-mtdna-version", &args.gnomad_mtdna_version)?;
     db.put_cf(&cf_meta, "helixmtdb-version", &args.helixmtdb_version)?;
-    db.put_cf(
-        &cf_meta,
-        "genome-release",
-        format!("{}", args.genome_release),
-    )?;
+    db.put_cf(&cf_meta, "genome-release", format!("{}", release))?;
+    sampler.write_meta(&db, &cf_meta)?;
     tracing::info!(
         "... done opening RocksDB for writing in {:?}",
         before_opening_rocksdb.elapsed()
@@ -233,6 +387,13 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_chroms.elapsed()
     );
 
+    let policy = retry::RetryPolicy {
+        max_retries: args.max_retries,
+        backoff: std::time::Duration::from_secs(args.retry_backoff_secs),
+    };
+    let mut retried_windows = Vec::new();
+
+    let mut tabix_indices = TabixIndices::default();
     tracing::info!("Importing autosomal variants...");
     let before_auto = std::time::Instant::now();
     for k in &auto_keys {
@@ -252,17 +413,25 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             }
             paths
         };
-        let windows = build_windows(genome_release, args.tbi_window_size, &paths)?;
-        windows
-            .par_iter()
-            .progress_with(common::cli::progress_bar(windows.len()))
-            .map(|(chrom, begin, end)| {
-                let start = noodles::core::position::Position::try_from(begin + 1)?;
-                let stop = noodles::core::position::Position::try_from(*end)?;
-                let region = noodles::core::region::Region::new(chrom.as_bytes(), start..=stop);
-                auto::import_region(&db, path_genome, path_exome, &region)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let windows = build_windows(
+            genome_release,
+            args.tbi_window_size,
+            &paths,
+            &mut tabix_indices,
+        )?;
+        retried_windows.append(&mut import_windows(&windows, &policy, |region| {
+            auto::import_region(
+                &db,
+                path_genome,
+                path_exome,
+                region,
+                args.allow_iupac,
+                &sampler,
+            )
+        })?);
+        if args.incremental_compaction {
+            common::compaction::compact_chrom(&db, "autosomal", common::cli::CANONICAL[*k])?;
+        }
     }
     tracing::info!(
         "... done importing autosomal variants in {:?}",
@@ -287,17 +456,26 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             }
             paths
         };
-        let windows = build_windows(genome_release, args.tbi_window_size, &paths)?;
-        windows
-            .par_iter()
-            .progress_with(common::cli::progress_bar(windows.len()))
-            .map(|(chrom, begin, end)| {
-                let start = noodles::core::position::Position::try_from(begin + 1)?;
-                let stop = noodles::core::position::Position::try_from(*end)?;
-                let region = noodles::core::region::Region::new(chrom.as_bytes(), start..=stop);
-                xy::import_region(&db, path_genome, path_exome, &region)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let windows = build_windows(
+            genome_release,
+            args.tbi_window_size,
+            &paths,
+            &mut tabix_indices,
+        )?;
+        retried_windows.append(&mut import_windows(&windows, &policy, |region| {
+            xy::import_region(
+                &db,
+                path_genome,
+                path_exome,
+                region,
+                release,
+                args.allow_iupac,
+                &sampler,
+            )
+        })?);
+        if args.incremental_compaction {
+            common::compaction::compact_chrom(&db, "gonosomal", common::cli::CANONICAL[*k])?;
+        }
     }
     tracing::info!(
         "... done importing gonosomal variants in {:?}",
@@ -322,31 +500,260 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         }
         paths
     };
-    let windows = build_windows(genome_release, args.tbi_window_size, &paths)?;
-    windows
-        .par_iter()
-        .progress_with(common::cli::progress_bar(windows.len()))
-        .map(|(chrom, begin, end)| {
-            let start = noodles::core::position::Position::try_from(begin + 1)?;
-            let stop = noodles::core::position::Position::try_from(*end)?;
-            let region = noodles::core::region::Region::new(chrom.as_bytes(), start..=stop);
-            mt::import_region(&db, path_gnomad, path_helix, &region)
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let windows = build_windows(
+        genome_release,
+        args.tbi_window_size,
+        &paths,
+        &mut tabix_indices,
+    )?;
+    retried_windows.append(&mut import_windows(&windows, &policy, |region| {
+        mt::import_region(
+            &db,
+            path_gnomad,
+            path_helix,
+            region,
+            args.allow_iupac,
+            &sampler,
+        )
+    })?);
+    if args.incremental_compaction {
+        common::compaction::compact_chrom(&db, "mitochondrial", "MT")?;
+    }
 
     tracing::info!(
         "... done importing mitochondrial variants in {:?}",
         before_mito.elapsed()
     );
+    sampler.log();
 
-    tracing::info!("Running RocksDB compaction ...");
-    let before_compaction = std::time::Instant::now();
-    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
-    tracing::info!(
-        "... done compacting RocksDB in {:?}",
-        before_compaction.elapsed()
-    );
+    if retried_windows.is_empty() {
+        tracing::info!("No windows had to be retried.");
+    } else {
+        tracing::warn!("{} window(s) had to be retried:", retried_windows.len());
+        for (window, retries) in &retried_windows {
+            tracing::warn!("  - {} ({} retries)", window, retries);
+        }
+    }
+
+    if args.incremental_compaction {
+        tracing::info!("Skipping final RocksDB compaction, chromosomes have been compacted");
+    } else {
+        tracing::info!("Running RocksDB compaction ...");
+        let before_compaction = std::time::Instant::now();
+        rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+        tracing::info!(
+            "... done compacting RocksDB in {:?}",
+            before_compaction.elapsed()
+        );
+    }
+
+    common::record_stats::write_meta(&db, &["autosomal", "gonosomal", "mitochondrial"])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "freqs".to_string(),
+            title: "gnomAD and HelixMtDb frequencies".to_string(),
+            genome_release: Some(release),
+            created_from: vec![
+                (
+                    "gnomAD exomes".to_string(),
+                    args.gnomad_exomes_version.clone(),
+                ),
+                (
+                    "gnomAD genomes".to_string(),
+                    args.gnomad_genomes_version.clone(),
+                ),
+                (
+                    "gnomAD mtDNA".to_string(),
+                    args.gnomad_mtdna_version.clone(),
+                ),
+                ("HelixMtDb".to_string(), args.helixmtdb_version.clone()),
+            ],
+            paths_in: args
+                .path_gnomad_exomes_auto
+                .iter()
+                .chain(args.path_gnomad_genomes_auto.iter())
+                .chain(args.path_gnomad_exomes_xy.iter())
+                .chain(args.path_gnomad_genomes_xy.iter())
+                .chain(args.path_gnomad_mtdna.iter())
+                .chain(args.path_helixmtdb.iter())
+                .cloned()
+                .collect(),
+        },
+    )?;
 
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Windows without data in the index are skipped.
+    #[test]
+    fn build_windows_skips_empty_windows() -> Result<(), anyhow::Error> {
+        let assembly = biocommons_bioutils::assemblies::Assembly::Grch37p10;
+        let paths = vec![String::from(
+            "tests/freqs/grch37/v2.1/gnomad-exomes.1.vcf.bgz",
+        )];
+
+        let windows = build_windows(assembly, 100_000, &paths, &mut Default::default())?;
+        let chrom_windows = common::cli::build_genome_windows(assembly, Some(100_000))?
+            .into_iter()
+            .filter(|(chrom, _, _)| chrom == "1")
+            .count();
+
+        // All records are within 1:55,505,599-55,516,888.
+        assert!(windows.contains(&(String::from("1"), 55_500_000, 55_600_000)));
+        assert!(windows.len() < chrom_windows);
+
+        Ok(())
+    }
+
+    /// Window building before caching the tabix indices, reading the index on each call.
+    fn build_windows_uncached(
+        genome_release: biocommons_bioutils::assemblies::Assembly,
+        tbi_window_size: usize,
+        paths: &[String],
+    ) -> Result<Vec<(String, usize, usize)>, anyhow::Error> {
+        let mut result = Vec::new();
+
+        for path in paths.iter() {
+            let index = noodles::tabix::read(format!("{}.tbi", path))?;
+            let header = index.header().unwrap();
+            let canonical_header_chroms = header
+                .reference_sequence_names()
+                .iter()
+                .filter_map(|chrom| {
+                    let canon_chrom = chrom.strip_prefix("chr").unwrap_or(chrom);
+                    common::cli::is_canonical(canon_chrom)
+                        .then(|| (common::cli::canonicalize(canon_chrom), chrom.clone()))
+                })
+                .collect::<std::collections::HashMap<String, String>>();
+            let windows = common::cli::build_genome_windows(genome_release, Some(tbi_window_size))?
+                .into_iter()
+                .filter_map(|(window_chrom, begin, end)| {
+                    canonical_header_chroms
+                        .get(&common::cli::canonicalize(&window_chrom))
+                        .map(|header_chrom| (header_chrom.clone(), begin, end))
+                })
+                .collect::<Vec<_>>();
+            result.append(&mut common::cli::prune_genome_windows(
+                windows,
+                |(chrom, begin, end)| {
+                    let ref_id = header
+                        .reference_sequence_names()
+                        .get_index_of(chrom.as_str())
+                        .unwrap();
+                    common::cli::tbi_window_has_data(&index, ref_id, *begin, *end)
+                },
+            )?);
+        }
+
+        result.sort();
+        result.dedup();
+
+        Ok(result)
+    }
+
+    /// Reusing the indices across calls yields the same windows as reading them each time.
+    #[test]
+    fn build_windows_cached_same_as_uncached() -> Result<(), anyhow::Error> {
+        let assembly = biocommons_bioutils::assemblies::Assembly::Grch37p10;
+        let path = |name: &str| format!("tests/freqs/grch37/v2.1/gnomad-{}.vcf.bgz", name);
+        let path_lists = [
+            vec![path("genomes.1"), path("exomes.1")],
+            vec![path("genomes.X"), path("exomes.X")],
+            vec![path("exomes.Y")],
+            vec![path("genomes.1")],
+            vec![path("exomes.1"), path("exomes.X")],
+        ];
+
+        let mut indices = TabixIndices::default();
+        for paths in &path_lists {
+            assert_eq!(
+                build_windows(assembly, 100_000, paths, &mut indices)?,
+                build_windows_uncached(assembly, 100_000, paths)?,
+                "{:?}",
+                paths
+            );
+        }
+        assert_eq!(indices.indices.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_windows_missing_index() {
+        let paths = vec![String::from("tests/freqs/grch37/v2.1/gnomad-exomes.1.vcf")];
+
+        let err = build_windows(
+            biocommons_bioutils::assemblies::Assembly::Grch37p10,
+            100_000,
+            &paths,
+            &mut Default::default(),
+        )
+        .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("tests/freqs/grch37/v2.1/gnomad-exomes.1.vcf.tbi"),
+            "{}",
+            err
+        );
+    }
+
+    /// Dry run must not create the database and must find the missing tabix index.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let common = common::cli::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_gnomad_exomes_auto: vec![String::from(
+                "tests/freqs/grch37/v2.1/gnomad-exomes.1.vcf.bgz",
+            )],
+            path_gnomad_genomes_auto: vec![String::from(
+                "tests/freqs/grch37/v2.1/gnomad-genomes.1.vcf.bgz",
+            )],
+            path_gnomad_exomes_xy: Vec::new(),
+            path_gnomad_genomes_xy: Vec::new(),
+            path_gnomad_mtdna: None,
+            path_helixmtdb: None,
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            tbi_window_size: 100_000,
+            max_retries: 3,
+            retry_backoff_secs: 10,
+            gnomad_genomes_version: String::from("2.1"),
+            gnomad_exomes_version: String::from("2.1"),
+            gnomad_mtdna_version: String::from("3.1"),
+            helixmtdb_version: String::from("20200327"),
+            sample: Default::default(),
+            spec: Default::default(),
+            dry_run: true,
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_gnomad_genomes_auto: vec![String::from(
+                "tests/freqs/grch37/v2.1/gnomad-genomes.1.vcf",
+            )],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(validation.problems[0].starts_with("tabix index"));
+        assert!(run(&common, &args).is_err());
+    }
+}
@@ -1,42 +1,37 @@
 //! Import of mitochondrial variant frequencies.
 
+use std::collections::BTreeMap;
+
 use crate::{common, freqs};
 
+/// Counts of both sources for one normalized variant, pending write out.
+#[derive(Debug, Default)]
+struct PendingRecord {
+    /// Counts from gnomAD mtDNA, if any.
+    gnomad: Option<freqs::serialized::mt::Counts>,
+    /// Counts from HelixMtDb, if any.
+    helix: Option<freqs::serialized::mt::Counts>,
+}
+
 /// Write out the given record to the database.
 fn write_record(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf: &std::sync::Arc<rocksdb::BoundColumnFamily>,
-    record_key: &common::keys::Var,
-    record_gnomad: &mut Option<noodles::vcf::variant::RecordBuf>,
-    record_helix: &mut Option<noodles::vcf::variant::RecordBuf>,
+    record_key: common::keys::Var,
+    record: PendingRecord,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
-    if record_gnomad.is_none() && record_helix.is_none() {
-        // Early exit, nothing to write out.
+    let key: Vec<u8> = record_key.into();
+    if !sampler.keep(&key) {
         return Ok(());
     }
-
-    let count_gnomad = if let Some(record_gnomad) = record_gnomad {
-        freqs::serialized::mt::Counts::from_vcf_allele(record_gnomad, 0)
-    } else {
-        freqs::serialized::mt::Counts::default()
-    };
-    let count_helix = if let Some(record_helix) = record_helix {
-        freqs::serialized::mt::Counts::from_vcf_allele(record_helix, 0)
-    } else {
-        freqs::serialized::mt::Counts::default()
-    };
-
     let mito_record = freqs::serialized::mt::Record {
-        gnomad_mtdna: count_gnomad,
-        helixmtdb: count_helix,
+        gnomad_mtdna: record.gnomad.unwrap_or_default(),
+        helixmtdb: record.helix.unwrap_or_default(),
     };
-    // dbg!(&record_key, &mito_record);
 
     let mut buf = vec![0u8; freqs::serialized::mt::Record::buf_len()];
     mito_record.to_buf(&mut buf);
-    let key: Vec<u8> = record_key.clone().into();
-
-    // tracing::info!("  key = {:?}, record = {:?}", &record_key, &mito_record);
 
     db.put_cf(cf, key, &buf)?;
 
@@ -49,6 +44,8 @@ pub fn import_region(
     path_gnomad: Option<&String>,
     path_helix: Option<&String>,
     region: &noodles::core::region::Region,
+    allow_iupac: bool,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Get handle to "mitochondrial" column family.
     let cf_mito = db.cf_handle("mitochondrial").unwrap();
@@ -100,53 +97,123 @@ pub fn import_region(
 
     // Now iterate over the `MultiQuery` and write to the database.
     //
-    // The key of the records stored in `record_{genome,exome}`.
-    let mut record_key = None;
-    // Record from gnomAD genomes (same position as record_exome, if either).
-    let mut record_gnomad = None;
-    // Record from gnomAD exomes (same position as record_genome, if either).
-    let mut record_helix = None;
+    // The sources may disagree on the representation of a variant, e.g., gnomAD-mtDNA may
+    // write `AT>AC` at the position before HelixMtDb's `T>C`.  Records are thus collected by
+    // their trimmed key and merged into one record per key.  Trimming never moves a variant
+    // to the left, so keys left of the current (untrimmed) position are complete and can
+    // be written out.
+    let mut pending: BTreeMap<common::keys::Var, PendingRecord> = BTreeMap::new();
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     for result in multi_query {
         let (idx, record) = result?;
-        // Obtain the key of the next record.
-        let curr_key = common::keys::Var::from_vcf_allele(&record, 0);
-
-        // Write out current records to database if we advance.
-        if record_key.as_ref() != Some(&curr_key) {
-            if let Some(record_key) = record_key.as_ref() {
-                write_record(
-                    db,
-                    &cf_mito,
-                    record_key,
-                    &mut record_gnomad,
-                    &mut record_helix,
-                )?;
+        let raw_key = common::keys::Var::from_vcf_allele(&record, 0);
+        let Some(curr_key) = invalid_alleles.normalize(&raw_key, allow_iupac) else {
+            continue;
+        };
+
+        // Write out the records that no later record can be merged into.
+        while let Some(entry) = pending.first_entry() {
+            if entry.key().pos >= raw_key.pos {
+                break;
             }
-            record_gnomad = None;
-            record_helix = None;
+            let (record_key, pending_record) = entry.remove_entry();
+            write_record(db, &cf_mito, record_key, pending_record, sampler)?;
         }
 
-        // Shortcut to whether this from gnomAD genomes.
-        if is_gnomad[idx] {
-            record_gnomad = Some(record);
+        let counts = freqs::serialized::mt::Counts::from_vcf_allele(&record, 0);
+        let pending_record = pending.entry(curr_key).or_default();
+        let (source, slot) = if is_gnomad[idx] {
+            ("gnomAD-mtDNA", &mut pending_record.gnomad)
+        } else {
+            ("HelixMtDb", &mut pending_record.helix)
+        };
+        if slot.is_some() {
+            tracing::warn!(
+                "duplicate {} record for {:?} in {}, keeping the first",
+                source,
+                &raw_key,
+                paths[idx]
+            );
         } else {
-            record_helix = Some(record);
+            *slot = Some(counts);
         }
-
-        // Updating the current key cannot cause harm.
-        record_key = Some(curr_key);
     }
 
     // Write final records to database.
-    if let Some(record_key) = record_key.as_ref() {
-        write_record(
-            db,
-            &cf_mito,
-            record_key,
-            &mut record_gnomad,
-            &mut record_helix,
-        )?;
+    for (record_key, pending_record) in pending {
+        write_record(db, &cf_mito, record_key, pending_record, sampler)?;
     }
+    invalid_alleles.log(&region.to_string());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::freqs::serialized::mt::{Counts, Record};
+
+    #[test]
+    fn import_region_merges_sources() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let options = rocksdb_utils_lookup::tune_options(rocksdb::Options::default(), None);
+        let db = rocksdb::DB::open_cf_with_opts(
+            &options,
+            temp.join("freqs-db"),
+            ["meta", "mitochondrial"]
+                .iter()
+                .map(|name| (name.to_string(), options.clone()))
+                .collect::<Vec<_>>(),
+        )?;
+
+        // gnomAD-mtDNA writes `chrM:10:T:C` untrimmed as `chrM:8:GAT:GAC`, HelixMtDb has
+        // `chrM:9:A:G` in between.
+        let path_gnomad = String::from("tests/freqs/grch37/v2.1/mt-dedup/gnomad.chrM.vcf.bgz");
+        let path_helix = String::from("tests/freqs/grch37/v2.1/mt-dedup/helix.chrM.vcf.bgz");
+        import_region(
+            &db,
+            Some(&path_gnomad),
+            Some(&path_helix),
+            &"chrM".parse()?,
+            false,
+            &Default::default(),
+        )?;
+
+        let cf_mito = db.cf_handle("mitochondrial").unwrap();
+        let records = db
+            .iterator_cf(&cf_mito, rocksdb::IteratorMode::Start)
+            .map(|result| result.map(|(key, value)| (key.to_vec(), Record::from_buf(&value))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let counts = |an, ac_hom, ac_het| Counts { an, ac_hom, ac_het };
+        assert_eq!(
+            records,
+            vec![
+                (
+                    common::keys::Var::from("chrM", 9, "A", "G").into(),
+                    Record {
+                        gnomad_mtdna: Counts::default(),
+                        helixmtdb: counts(196554, 1, 0),
+                    }
+                ),
+                (
+                    common::keys::Var::from("chrM", 10, "T", "C").into(),
+                    Record {
+                        gnomad_mtdna: counts(56434, 19, 1),
+                        helixmtdb: counts(196554, 7, 1),
+                    }
+                ),
+                (
+                    common::keys::Var::from("chrM", 11, "C", "T").into(),
+                    Record {
+                        gnomad_mtdna: counts(56433, 2, 3),
+                        helixmtdb: Counts::default(),
+                    }
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+}
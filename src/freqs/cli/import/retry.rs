@@ -0,0 +1,165 @@
+//! Retrying the import of single windows on transient I/O errors.
+//!
+//! Reading the input VCF files from network storage may fail for transient reasons (timeouts,
+//! interrupted or reset connections).  Such errors are retried with exponential backoff while
+//! permanent errors (e.g., parse errors) fail the window immediately.
+
+use std::time::Duration;
+
+/// Retry policy for importing one window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximal number of retries after the first attempt.
+    pub max_retries: usize,
+    /// Backoff before the first retry, doubled after each retry.
+    pub backoff: Duration,
+}
+
+/// Return whether `err` is a transient I/O error that is worth retrying.
+///
+/// Errors without an underlying `std::io::Error` and I/O errors indicating invalid data
+/// (which is how parse errors surface in noodles) are considered permanent.
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .map(|e| {
+                !matches!(
+                    e.kind(),
+                    std::io::ErrorKind::InvalidData
+                        | std::io::ErrorKind::InvalidInput
+                        | std::io::ErrorKind::NotFound
+                        | std::io::ErrorKind::PermissionDenied
+                        | std::io::ErrorKind::Unsupported
+                )
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Call `f` for the window described by `window`, retrying on retryable errors.
+///
+/// Returns the result of `f` together with the number of retries that were needed.  Fails
+/// with the window description in the error on a permanent error or once all retries have
+/// been used up.
+pub fn with_retries<T, F>(
+    policy: &RetryPolicy,
+    window: &str,
+    mut f: F,
+) -> Result<(T, usize), anyhow::Error>
+where
+    F: FnMut() -> Result<T, anyhow::Error>,
+{
+    let mut retries = 0;
+    let mut backoff = policy.backoff;
+    loop {
+        match f() {
+            Ok(value) => return Ok((value, retries)),
+            Err(e) if !is_retryable(&e) => {
+                return Err(e.context(format!("problem importing window {}", window)));
+            }
+            Err(e) if retries >= policy.max_retries => {
+                return Err(e.context(format!(
+                    "problem importing window {} after {} retries",
+                    window, retries
+                )));
+            }
+            Err(e) => {
+                retries += 1;
+                tracing::warn!(
+                    "transient error importing window {}, retry {}/{} in {:?}: {}",
+                    window,
+                    retries,
+                    policy.max_retries,
+                    backoff,
+                    e
+                );
+                std::thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy(max_retries: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    /// Return a function that fails with an error of `kind` for the first `failures` calls.
+    fn flaky(
+        failures: usize,
+        kind: std::io::ErrorKind,
+    ) -> impl FnMut() -> Result<usize, anyhow::Error> {
+        let mut calls = 0;
+        move || {
+            calls += 1;
+            if calls <= failures {
+                Err(anyhow::Error::new(std::io::Error::new(kind, "flaky")))
+            } else {
+                Ok(calls)
+            }
+        }
+    }
+
+    #[test]
+    fn is_retryable_kinds() {
+        let io_err = |kind| anyhow::Error::new(std::io::Error::new(kind, "x"));
+
+        assert!(is_retryable(&io_err(std::io::ErrorKind::TimedOut)));
+        assert!(is_retryable(&io_err(std::io::ErrorKind::ConnectionReset)));
+        assert!(is_retryable(
+            &io_err(std::io::ErrorKind::Interrupted).context("reading record")
+        ));
+        assert!(!is_retryable(&io_err(std::io::ErrorKind::InvalidData)));
+        assert!(!is_retryable(&anyhow::anyhow!("invalid record")));
+    }
+
+    #[test]
+    fn with_retries_succeeds_after_transient_errors() {
+        let (calls, retries) = with_retries(
+            &policy(3),
+            "1:1-100",
+            flaky(2, std::io::ErrorKind::TimedOut),
+        )
+        .unwrap();
+
+        assert_eq!(calls, 3);
+        assert_eq!(retries, 2);
+    }
+
+    #[test]
+    fn with_retries_fails_when_exhausted() {
+        let err = with_retries(
+            &policy(2),
+            "1:1-100",
+            flaky(5, std::io::ErrorKind::TimedOut),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "problem importing window 1:1-100 after 2 retries"
+        );
+    }
+
+    #[test]
+    fn with_retries_fails_fast_on_permanent_error() {
+        let mut f = flaky(1, std::io::ErrorKind::InvalidData);
+        let mut calls = 0;
+        let err = with_retries(&policy(3), "1:1-100", || {
+            calls += 1;
+            f()
+        })
+        .unwrap_err();
+
+        assert_eq!(calls, 1);
+        assert_eq!(err.to_string(), "problem importing window 1:1-100");
+    }
+}
@@ -9,19 +9,25 @@ fn write_record(
     record_key: &common::keys::Var,
     record_genome: &mut Option<noodles::vcf::variant::RecordBuf>,
     record_exome: &mut Option<noodles::vcf::variant::RecordBuf>,
+    genome_release: common::cli::GenomeRelease,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     if record_genome.is_none() && record_exome.is_none() {
         // Early exit, nothing to write out.
         return Ok(());
     }
+    let key: Vec<u8> = record_key.clone().into();
+    if !sampler.keep(&key) {
+        return Ok(());
+    }
 
     let count_genomes = if let Some(record_genome) = record_genome {
-        freqs::serialized::xy::Counts::from_vcf_allele(record_genome, 0)
+        freqs::serialized::xy::Counts::from_vcf_allele(record_genome, 0, genome_release)?
     } else {
         freqs::serialized::xy::Counts::default()
     };
     let counts_exomes = if let Some(record_exome) = record_exome {
-        freqs::serialized::xy::Counts::from_vcf_allele(record_exome, 0)
+        freqs::serialized::xy::Counts::from_vcf_allele(record_exome, 0, genome_release)?
     } else {
         freqs::serialized::xy::Counts::default()
     };
@@ -33,7 +39,6 @@ fn write_record(
 
     let mut buf = vec![0u8; freqs::serialized::xy::Record::buf_len()];
     gono_record.to_buf(&mut buf);
-    let key: Vec<u8> = record_key.clone().into();
 
     // tracing::info!("  key = {:?}, record = {:?}", &record_key, &gono_record);
 
@@ -43,11 +48,16 @@ fn write_record(
 }
 
 /// Import of gonosomal variant frequencies.
+///
+/// The PARs of `genome_release` are treated as diploid, see `freqs::serialized::xy`.
 pub fn import_region(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     path_genome: Option<&String>,
     path_exome: Option<&String>,
     region: &noodles::core::region::Region,
+    genome_release: common::cli::GenomeRelease,
+    allow_iupac: bool,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Get handle to "gonosomal" column family.
     let cf_gono = db.cf_handle("gonosomal").unwrap();
@@ -89,10 +99,15 @@ pub fn import_region(
     let mut record_genome = None;
     // Record from gnomAD exomes (same position as record_genome, if either).
     let mut record_exome = None;
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     for result in multi_query {
         let (idx, record) = result?;
-        // Obtain the key of the next record.
-        let curr_key = common::keys::Var::from_vcf_allele(&record, 0);
+        // Obtain the key of the next record, skipping records with invalid alleles.
+        let Some(curr_key) =
+            invalid_alleles.normalize(&common::keys::Var::from_vcf_allele(&record, 0), allow_iupac)
+        else {
+            continue;
+        };
 
         // Write out current records to database if we advance.
         if record_key.as_ref() != Some(&curr_key) {
@@ -103,6 +118,8 @@ pub fn import_region(
                     record_key,
                     &mut record_genome,
                     &mut record_exome,
+                    genome_release,
+                    sampler,
                 )?;
             }
             record_genome = None;
@@ -128,8 +145,12 @@ pub fn import_region(
             record_key,
             &mut record_genome,
             &mut record_exome,
+            genome_release,
+            sampler,
         )?;
     }
 
+    invalid_alleles.log(&region.to_string());
+
     Ok(())
 }
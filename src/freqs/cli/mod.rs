@@ -1,4 +1,5 @@
 //! Command line interface for importing variant frequencies.
 
+pub mod export_vcf;
 pub mod import;
 pub mod query;
@@ -3,8 +3,14 @@
 use std::sync::Arc;
 
 use crate::{
-    common::{self, keys, spdi},
-    freqs,
+    common::{
+        self,
+        cli::{extract_chrom, GenomeRelease},
+        keys, spdi,
+    },
+    dbsnp::rs_id::{self, RsIdLookup},
+    freqs::{self, computed},
+    genes,
 };
 
 /// Command line arguments for `freq query` sub command.
@@ -19,12 +25,74 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub path_output: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
-    /// Variant to query for.
+    /// Variant, range, or gene to query for.
+    #[command(flatten)]
+    pub query: ArgsQuery,
+    /// Path to genes RocksDB directory for resolving `--gene`.
+    #[arg(long)]
+    pub path_genes_rocksdb: Option<String>,
+    /// Path to dbSNP RocksDB directory for adding the `rs_id` of each record.
+    #[arg(long)]
+    pub path_dbsnp_rocksdb: Option<String>,
+    /// Number of records of range and gene queries to skip and write.
+    #[command(flatten)]
+    pub limit: common::cli::ArgsLimit,
+    /// Add the combined population frequency of each record as `computed_frequencies`, see
+    /// `freqs::computed`.
     #[arg(long)]
-    pub variant: spdi::Var,
+    pub computed: bool,
+    /// Fail on the first query from `--path-input-jsonl` that cannot be parsed or run rather
+    /// than writing an error record.
+    #[arg(long, requires = "path_input_jsonl")]
+    pub strict: bool,
+}
+
+/// Argument group for specifying one of variant, range, or gene.
+#[derive(clap::Args, Debug, Clone, Default)]
+#[group(required = true, multiple = false)]
+pub struct ArgsQuery {
+    /// Variant to query for.
+    #[arg(long, group = "query")]
+    pub variant: Option<spdi::Var>,
+    /// Range to query for.
+    #[arg(long, group = "query")]
+    pub range: Option<spdi::Range>,
+    /// Gene to query for, given as HGNC ID or symbol, requires `--path-genes-rocksdb`.
+    #[arg(long, group = "query", requires = "path_genes_rocksdb")]
+    pub gene: Option<String>,
+    /// Read queries from a JSONL file with one query per line, "-" for stdin.
+    #[arg(long, group = "query")]
+    pub path_input_jsonl: Option<String>,
+}
+
+impl TryFrom<common::batch::Line> for ArgsQuery {
+    type Error = anyhow::Error;
+
+    fn try_from(line: common::batch::Line) -> Result<Self, Self::Error> {
+        let (name, value) = line.single()?;
+        let parse_error = |e: anyhow::Error| crate::ErrorCategory::Usage.wrap(e);
+        Ok(match name {
+            "variant" => ArgsQuery {
+                variant: Some(value.parse().map_err(parse_error)?),
+                ..Default::default()
+            },
+            "range" => ArgsQuery {
+                range: Some(value.parse().map_err(parse_error)?),
+                ..Default::default()
+            },
+            "gene" => ArgsQuery {
+                gene: Some(value),
+                ..Default::default()
+            },
+            _ => {
+                return Err(crate::ErrorCategory::Usage
+                    .error(format!("{} queries are not supported", name)))
+            }
+        })
+    }
 }
 
 /// Meta information as read from database.
@@ -32,6 +100,8 @@ pub struct Args {
 pub struct Meta {
     /// Genome release of data in database.
     pub genome_release: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -47,23 +117,25 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_auto, cf_gono, cf_mtdna];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
     tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -100,12 +172,12 @@ pub enum Record {
 /// Query for a single variant in the RocksDB database.
 pub fn query_for_variant(
     variant: &spdi::Var,
+    meta: &Meta,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     _out_format: common::cli::OutputFormat,
 ) -> Result<Option<Record>, anyhow::Error> {
     let seq = variant.sequence.to_lowercase();
-    let var: keys::Var = variant.clone().into();
-    let key: Vec<u8> = var.into();
+    let key = common::norm::query_key(variant.clone(), meta.keys_normalized);
     if seq.contains('m') {
         let cf_mtdna: Arc<rocksdb::BoundColumnFamily> = db.cf_handle("mitochondrial").unwrap();
         let raw_value = db
@@ -141,45 +213,226 @@ pub fn query_for_variant(
     Ok(None)
 }
 
+impl Record {
+    /// Return the combined population frequency of the record.
+    pub fn computed_frequencies(&self) -> computed::ComputedFrequencies {
+        computed::compute(&match self {
+            Record::Autosomal(record) => record.into(),
+            Record::Gonosomal(record) => record.into(),
+            Record::Mitochondrial(record) => record.into(),
+        })
+    }
+}
+
+/// Record of a range query together with its variant.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RangeRecord {
+    /// The variant as `CHROM:POS:REF:ALT`.
+    pub variant: String,
+    /// The frequency record.
+    #[serde(flatten)]
+    pub record: Record,
+}
+
+/// Query for all variants in `range` in the RocksDB database.
+///
+/// Only the records admitted by `counter` are decoded and returned.
+pub fn query_for_range(
+    range: &spdi::Range,
+    meta: &Meta,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    counter: &mut common::cli::LimitCounter,
+) -> Result<Vec<RangeRecord>, anyhow::Error> {
+    let range = extract_chrom::range(range, Some(&meta.genome_release))?;
+    let cf_name = match range.sequence.as_str() {
+        "MT" => "mitochondrial",
+        "X" | "Y" => "gonosomal",
+        _ => "autosomal",
+    };
+    let cf_data = db.cf_handle(cf_name).unwrap();
+
+    let (start, stop): (spdi::Pos, spdi::Pos) = range.into();
+    let start: keys::Pos = start.into();
+    let start: Vec<u8> = start.into();
+    let stop: keys::Pos = stop.into();
+
+    let mut result = Vec::new();
+    let mut iter = db.raw_iterator_cf(&cf_data);
+    iter.seek(&start);
+    while iter.valid() && !counter.is_done() {
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let var = super::export_vcf::decode_key(iter_key)?;
+            if var.chrom != stop.chrom || var.pos > stop.pos {
+                break;
+            }
+            if !counter.admit() {
+                iter.next();
+                continue;
+            }
+
+            let record = match cf_name {
+                "mitochondrial" => {
+                    Record::Mitochondrial(freqs::serialized::mt::Record::from_buf(iter_value))
+                }
+                "gonosomal" => {
+                    Record::Gonosomal(freqs::serialized::xy::Record::from_buf(iter_value))
+                }
+                _ => Record::Autosomal(freqs::serialized::auto::Record::from_buf(iter_value)),
+            };
+            result.push(RangeRecord {
+                variant: format!(
+                    "{}:{}:{}:{}",
+                    var.chrom, var.pos, var.reference, var.alternative
+                ),
+                record,
+            });
+
+            iter.next();
+        } else {
+            break;
+        }
+    }
+    iter.status()?;
+
+    Ok(result)
+}
+
+/// Resolve `gene` with the genes database `genes_db` to its range on the genome release of
+/// the frequency database.
+pub fn gene_range(
+    gene: &str,
+    genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+) -> Result<spdi::Range, anyhow::Error> {
+    let genome_release: GenomeRelease = meta
+        .genome_release
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid genome release {}: {}", &meta.genome_release, e))?;
+    let record = genes::cli::query::resolve_gene(gene, genes_db, "genes")?;
+    let range = genes::cli::query::gene_range(&record, genome_release)?;
+    tracing::info!("gene {} resolved to range {}", gene, &range);
+    Ok(range)
+}
+
+/// Write `record` with the variant key `key` to `out_writer`, with its computed frequencies
+/// from `frequencies` if given.
+fn write_record<T: serde::Serialize + ?Sized>(
+    out_writer: &mut common::output::RecordWriter,
+    rs_ids: Option<&RsIdLookup>,
+    key: &str,
+    record: &T,
+    frequencies: Option<computed::ComputedFrequencies>,
+) -> Result<(), anyhow::Error> {
+    match frequencies {
+        Some(computed_frequencies) => rs_id::write_record(
+            out_writer,
+            rs_ids,
+            key,
+            &computed::WithComputed {
+                record,
+                computed_frequencies,
+            },
+        ),
+        None => rs_id::write_record(out_writer, rs_ids, key, record),
+    }
+}
+
+/// Run the single `query` and write its records to `out_writer`.
+fn run_query(
+    query: &ArgsQuery,
+    args: &Args,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+    genes_db: Option<&rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    rs_ids: Option<&RsIdLookup>,
+    out_writer: &mut common::output::RecordWriter,
+) -> Result<(), anyhow::Error> {
+    let range = if let Some(gene) = query.gene.as_ref() {
+        let genes_db = genes_db.ok_or_else(|| {
+            crate::ErrorCategory::Usage.error("--gene requires --path-genes-rocksdb")
+        })?;
+        Some(gene_range(gene, genes_db, meta)?)
+    } else {
+        query.range.clone()
+    };
+
+    if let Some(range) = range.as_ref() {
+        for record in query_for_range(range, meta, db, &mut args.limit.counter())? {
+            let frequencies = args.computed.then(|| record.record.computed_frequencies());
+            write_record(out_writer, rs_ids, &record.variant, &record, frequencies)?;
+        }
+    } else if let Some(variant) = query.variant.as_ref() {
+        if let Some(record) = query_for_variant(variant, meta, db, args.output.output_format)? {
+            let key = common::output::var_key(variant, Some(&meta.genome_release))?;
+            let frequencies = args.computed.then(|| record.computed_frequencies());
+            match record {
+                Record::Autosomal(record) => {
+                    write_record(out_writer, rs_ids, &key, &record, frequencies)?
+                }
+                Record::Gonosomal(record) => {
+                    write_record(out_writer, rs_ids, &key, &record, frequencies)?
+                }
+                Record::Mitochondrial(record) => {
+                    write_record(out_writer, rs_ids, &key, &record, frequencies)?
+                }
+            }
+        } else {
+            tracing::info!("no record found for variant {:?}", variant);
+        }
+    }
+
+    Ok(())
+}
+
 /// Implementation of `tsv query` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'freqs query' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    let (db, meta) = open_rocksdb_from_args(args)?;
+
     // Obtain writer to output.
-    let mut out_writer = match args.path_output.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.path_output,
+        args.output.output_format,
+        "freqs",
+        Some(&meta.genome_release),
+    )?;
+    let rs_ids = args
+        .path_dbsnp_rocksdb
+        .as_ref()
+        .map(|path| RsIdLookup::open(path, &meta.genome_release))
+        .transpose()?;
 
-    let (db, _meta) = open_rocksdb_from_args(args)?;
+    let genes_db = args
+        .path_genes_rocksdb
+        .as_ref()
+        .map(|path| genes::cli::query::open_rocksdb(path, "genes", "meta"))
+        .transpose()?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
-    if let Some(variant) = query_for_variant(&args.variant, &db, args.out_format)? {
-        match variant {
-            Record::Autosomal(record) => {
-                let json_value = serde_json::to_value(record)?;
-                let json = serde_json::to_string(&json_value)?;
-                writeln!(out_writer, "{}", &json)?;
-            }
-            Record::Gonosomal(record) => {
-                let json_value = serde_json::to_value(record)?;
-                let json = serde_json::to_string(&json_value)?;
-                writeln!(out_writer, "{}", &json)?;
-            }
-            Record::Mitochondrial(record) => {
-                let json_value = serde_json::to_value(record)?;
-                let json = serde_json::to_string(&json_value)?;
-                writeln!(out_writer, "{}", &json)?;
-            }
-        }
+    let query_one = |query: &ArgsQuery, out_writer: &mut common::output::RecordWriter| {
+        run_query(
+            query,
+            args,
+            &db,
+            &meta,
+            genes_db.as_deref(),
+            rs_ids.as_ref(),
+            out_writer,
+        )
+    };
+    if let Some(path_input_jsonl) = args.query.path_input_jsonl.as_ref() {
+        common::batch::run(
+            common::batch::open_input(path_input_jsonl)?,
+            args.strict,
+            &mut out_writer,
+            query_one,
+        )?;
     } else {
-        tracing::info!("no record found for variant {:?}", &args.variant);
+        query_one(&args.query, &mut out_writer)?;
     }
     tracing::info!("... done querying in {:?}", before_query.elapsed());
 
@@ -219,12 +472,21 @@ mod test {
         let temp = TempDir::default();
         let common_args = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_rocksdb: format!("tests/freqs/{genome}/v{version}/example/freqs.db"),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             path_output: temp.join("out").to_string_lossy().to_string(),
-            variant: spdi::Var::from_str(variant_str).expect("invalid SPDI"),
+            query: ArgsQuery {
+                variant: Some(spdi::Var::from_str(variant_str).expect("invalid SPDI")),
+                ..Default::default()
+            },
+            path_genes_rocksdb: None,
+            path_dbsnp_rocksdb: None,
+            limit: Default::default(),
+            computed: false,
+            strict: false,
         };
 
         ArgsFreqs {
@@ -256,7 +518,13 @@ mod test {
             "{}-{}-{}",
             &genome,
             &version,
-            &args.variant.to_string().replace(':', "_")
+            &args
+                .query
+                .variant
+                .as_ref()
+                .unwrap()
+                .to_string()
+                .replace(':', "_")
         );
         run(&common_args, &args)?;
         let out_data = std::fs::read_to_string(&args.path_output)?;
@@ -285,7 +553,13 @@ mod test {
             "{}-{}-{}",
             &genome,
             &version,
-            &args.variant.to_string().replace(':', "_")
+            &args
+                .query
+                .variant
+                .as_ref()
+                .unwrap()
+                .to_string()
+                .replace(':', "_")
         );
         run(&common_args, &args)?;
         let out_data = std::fs::read_to_string(&args.path_output)?;
@@ -314,7 +588,13 @@ mod test {
             "{}-{}-{}",
             &genome,
             &version,
-            &args.variant.to_string().replace(':', "_")
+            &args
+                .query
+                .variant
+                .as_ref()
+                .unwrap()
+                .to_string()
+                .replace(':', "_")
         );
         run(&common_args, &args)?;
         let out_data = std::fs::read_to_string(&args.path_output)?;
@@ -343,7 +623,13 @@ mod test {
             "{}-{}-{}",
             &genome,
             &version,
-            &args.variant.to_string().replace(':', "_")
+            &args
+                .query
+                .variant
+                .as_ref()
+                .unwrap()
+                .to_string()
+                .replace(':', "_")
         );
         run(&common_args, &args)?;
         let out_data = std::fs::read_to_string(&args.path_output)?;
@@ -372,7 +658,13 @@ mod test {
             "{}-{}-{}",
             &genome,
             &version,
-            &args.variant.to_string().replace(':', "_")
+            &args
+                .query
+                .variant
+                .as_ref()
+                .unwrap()
+                .to_string()
+                .replace(':', "_")
         );
         run(&common_args, &args)?;
         let out_data = std::fs::read_to_string(&args.path_output)?;
@@ -380,4 +672,422 @@ mod test {
 
         Ok(())
     }
+
+    fn common_args() -> common::cli::Args {
+        common::cli::Args {
+            verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
+        }
+    }
+
+    /// Write a genes database with `(hgnc_id, symbol, alias_symbols, genomic_location_37)`.
+    fn genes_db(temp: &TempDir, genes: &[(&str, &str, &[&str], &str)]) -> String {
+        let path = temp.join("genes-db").to_string_lossy().to_string();
+        let records = genes
+            .iter()
+            .map(|(hgnc_id, symbol, alias_symbols, genomic_location_37)| {
+                let mut record = crate::genes::cli::import::test::record(hgnc_id, symbol);
+                if let Some(hgnc) = record.hgnc.as_mut() {
+                    hgnc.alias_symbol = alias_symbols.iter().map(|s| s.to_string()).collect();
+                }
+                record.clingen = (!genomic_location_37.is_empty()).then(|| {
+                    crate::pbs::genes::base::ClingenDosageRecord {
+                        gene_symbol: symbol.to_string(),
+                        genomic_location_37: genomic_location_37.to_string(),
+                        ..Default::default()
+                    }
+                });
+                record
+            })
+            .collect::<Vec<_>>();
+        crate::genes::cli::import::test::write_records(&path, &records).unwrap();
+        path
+    }
+
+    /// Build arguments for a range or gene query against the GRCh37 example database.
+    fn args_query(temp: &TempDir, query: ArgsQuery, path_genes_rocksdb: Option<String>) -> Args {
+        Args {
+            path_rocksdb: String::from("tests/freqs/grch37/v2.1/example/freqs.db"),
+            output: Default::default(),
+            path_output: temp.join("out").to_string_lossy().to_string(),
+            query,
+            path_genes_rocksdb,
+            path_dbsnp_rocksdb: None,
+            limit: Default::default(),
+            computed: false,
+            strict: false,
+        }
+    }
+
+    #[test]
+    fn query_range() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let args = args_query(
+            &temp,
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505602")?),
+                ..Default::default()
+            },
+            None,
+        );
+
+        run(&common_args(), &args)?;
+        let out_data = std::fs::read_to_string(&args.path_output)?;
+        let variants = out_data
+            .lines()
+            .map(|line| -> Result<_, anyhow::Error> {
+                Ok(serde_json::from_str::<RangeRecord>(line)?.variant)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(variants, vec!["1:55505599:C:G", "1:55505601:C:G"]);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(Some(1), 0, &["1:55505599:C:G"])]
+    #[case(None, 1, &["1:55505601:C:G"])]
+    #[case(Some(1), 1, &["1:55505601:C:G"])]
+    #[case(Some(5), 0, &["1:55505599:C:G", "1:55505601:C:G"])]
+    #[case(Some(0), 0, &[])]
+    #[case(None, 2, &[])]
+    fn query_range_limit_offset(
+        #[case] limit: Option<usize>,
+        #[case] offset: usize,
+        #[case] expected: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let args = Args {
+            limit: common::cli::ArgsLimit { limit, offset },
+            ..args_query(
+                &temp,
+                ArgsQuery {
+                    range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505602")?),
+                    ..Default::default()
+                },
+                None,
+            )
+        };
+
+        run(&common_args(), &args)?;
+        let out_data = std::fs::read_to_string(&args.path_output)?;
+        let variants = out_data
+            .lines()
+            .map(|line| -> Result<_, anyhow::Error> {
+                Ok(serde_json::from_str::<RangeRecord>(line)?.variant)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(variants, expected);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::autosomal(
+        "1:55516885:G:A",
+        serde_json::json!({
+            "af": 1.0 / 128256.0,
+            "ac": 1,
+            "an": 128256,
+            "sources": ["gnomad_exomes"],
+            "tier": "single_source",
+        }),
+    )]
+    #[case::mitochondrial(
+        "M:11:C:T",
+        serde_json::json!({
+            "af": 0.0,
+            "ac": 0,
+            "an": 196554,
+            "af_heteroplasmic": 1.0 / 196554.0,
+            "sources": ["helixmtdb"],
+            "tier": "single_source",
+        }),
+    )]
+    fn query_variant_computed(
+        #[case] variant: &str,
+        #[case] expected: serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let args = Args {
+            computed: true,
+            ..args_query(
+                &temp,
+                ArgsQuery {
+                    variant: Some(spdi::Var::from_str(variant)?),
+                    ..Default::default()
+                },
+                None,
+            )
+        };
+
+        run(&common_args(), &args)?;
+        let out_data = std::fs::read_to_string(&args.path_output)?;
+        let value: serde_json::Value = serde_json::from_str(out_data.trim())?;
+
+        assert_eq!(value["computed_frequencies"], expected);
+        assert!(value.get("an").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_range_computed() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let args = Args {
+            computed: true,
+            ..args_query(
+                &temp,
+                ArgsQuery {
+                    range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505602")?),
+                    ..Default::default()
+                },
+                None,
+            )
+        };
+
+        run(&common_args(), &args)?;
+        let out_data = std::fs::read_to_string(&args.path_output)?;
+        for line in out_data.lines() {
+            let record: RangeRecord = serde_json::from_str(line)?;
+            let value: serde_json::Value = serde_json::from_str(line)?;
+            assert_eq!(
+                serde_json::from_value::<computed::ComputedFrequencies>(
+                    value["computed_frequencies"].clone()
+                )?,
+                record.record.computed_frequencies()
+            );
+        }
+        assert!(!out_data.is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("1:55505599:C:G").unwrap()),
+            ..Default::default()
+        },
+        &[Some(11591147)],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505602").unwrap()),
+            ..Default::default()
+        },
+        &[Some(11591147), None],
+    )]
+    fn query_rs_id(
+        #[case] query: ArgsQuery,
+        #[case] expected: &[Option<i64>],
+    ) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let path_dbsnp_rocksdb = crate::dbsnp::rs_id::test::write_dbsnp_db(
+            &temp,
+            &[("1:55505599:C:G", 11591147), ("17:41267746:C:A", 80357446)],
+        );
+        let args = Args {
+            path_dbsnp_rocksdb: Some(path_dbsnp_rocksdb),
+            ..args_query(&temp, query.clone(), None)
+        };
+        let temp_plain = TempDir::default();
+        let args_plain = args_query(&temp_plain, query, None);
+
+        run(&common_args(), &args)?;
+        run(&common_args(), &args_plain)?;
+        let out_data = std::fs::read_to_string(&args.path_output)?;
+        let out_plain = std::fs::read_to_string(&args_plain.path_output)?;
+
+        assert_eq!(out_data.lines().count(), expected.len());
+        for ((line, line_plain), expected) in out_data.lines().zip(out_plain.lines()).zip(expected)
+        {
+            let mut record: serde_json::Value = serde_json::from_str(line)?;
+            let rs_id = record
+                .as_object_mut()
+                .and_then(|record| record.remove("rs_id"))
+                .unwrap();
+            assert_eq!(rs_id.as_i64(), *expected);
+            assert_eq!(
+                record,
+                serde_json::from_str::<serde_json::Value>(line_plain)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("1:55505599:C:G").unwrap()),
+            ..Default::default()
+        },
+        &["1:55505599:C:G"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505602").unwrap()),
+            ..Default::default()
+        },
+        &["1:55505599:C:G", "1:55505601:C:G"],
+    )]
+    fn query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let args_v1 = args_query(&temp, query.clone(), None);
+        run(&common_args(), &args_v1)?;
+        let temp_v2 = TempDir::default();
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_query(&temp_v2, query, None)
+        };
+        run(&common_args(), &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.path_output)?,
+            &std::fs::read_to_string(&args_v2.path_output)?,
+            "freqs",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::symbol("PCSK9")]
+    #[case::symbol_lowercase("pcsk9")]
+    #[case::alias("NARC1")]
+    #[case::hgnc_id("HGNC:20001")]
+    fn query_gene(#[case] gene: &str) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let path_genes_rocksdb = genes_db(
+            &temp,
+            &[
+                ("HGNC:20001", "PCSK9", &["NARC1"], "chr1:55505221-55530525"),
+                ("HGNC:1100", "BRCA1", &[], "chr17:41196312-41277500"),
+            ],
+        );
+        let args_gene = args_query(
+            &temp,
+            ArgsQuery {
+                gene: Some(gene.to_string()),
+                ..Default::default()
+            },
+            Some(path_genes_rocksdb),
+        );
+        let temp_range = TempDir::default();
+        let args_range = args_query(
+            &temp_range,
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("1:55505221:55530525")?),
+                ..Default::default()
+            },
+            None,
+        );
+
+        run(&common_args(), &args_gene)?;
+        run(&common_args(), &args_range)?;
+        let out_gene = std::fs::read_to_string(&args_gene.path_output)?;
+        let out_range = std::fs::read_to_string(&args_range.path_output)?;
+
+        assert!(out_gene.lines().count() > 100);
+        assert_eq!(out_gene, out_range);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::ambiguous(
+        "PCSK",
+        "gene symbol PCSK is ambiguous, candidates: HGNC:20001 (PCSK9), HGNC:8743 (PCSK1)"
+    )]
+    #[case::no_coordinates("LDLR", "gene LDLR (HGNC:6547) has no coordinates for grch37")]
+    #[case::unknown_symbol("APOB", "no gene found for symbol APOB")]
+    #[case::unknown_hgnc_id("HGNC:603", "no gene found for HGNC ID HGNC:603")]
+    fn query_gene_fails(#[case] gene: &str, #[case] message: &str) {
+        let temp = TempDir::default();
+        let path_genes_rocksdb = genes_db(
+            &temp,
+            &[
+                ("HGNC:20001", "PCSK9", &["PCSK"], "chr1:55505221-55530525"),
+                ("HGNC:8743", "PCSK1", &["PCSK"], "chr5:95726040-95768985"),
+                ("HGNC:6547", "LDLR", &[], ""),
+            ],
+        );
+        let args = args_query(
+            &temp,
+            ArgsQuery {
+                gene: Some(gene.to_string()),
+                ..Default::default()
+            },
+            Some(path_genes_rocksdb),
+        );
+
+        let err = run(&common_args(), &args).unwrap_err();
+
+        assert_eq!(err.to_string(), message);
+    }
+
+    #[test]
+    fn query_path_input_jsonl() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let path_genes_rocksdb = genes_db(
+            &temp,
+            &[("HGNC:20001", "PCSK9", &["NARC1"], "chr1:55505599-55505602")],
+        );
+        let path_input = temp.join("input.jsonl").to_string_lossy().to_string();
+        std::fs::write(
+            &path_input,
+            "{\"variant\": \"1:55505599:C:G\"}\n\
+            {\"position\": \"1:55505599\"}\n\
+            {\"gene\": \"PCSK9\"}\n\
+            {\"range\": \"GRCh37:1:55505600:55505602\"}\n",
+        )?;
+        let args = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_query(
+                &temp,
+                ArgsQuery {
+                    path_input_jsonl: Some(path_input),
+                    ..Default::default()
+                },
+                Some(path_genes_rocksdb),
+            )
+        };
+
+        run(&common_args(), &args)?;
+        let lines = std::fs::read_to_string(&args.path_output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| (
+                    line["input_index"].as_u64().unwrap(),
+                    line["key"].as_str().or(line["error"].as_str()).unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "1:55505599:C:G"),
+                (1, "position queries are not supported"),
+                (2, "1:55505599:C:G"),
+                (2, "1:55505601:C:G"),
+                (3, "1:55505601:C:G"),
+            ]
+        );
+
+        Ok(())
+    }
 }
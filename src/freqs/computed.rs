@@ -0,0 +1,608 @@
+//! Combined population frequency of a variant from the configured frequency databases.
+//!
+//! ACMG criteria such as BA1, BS1, and PM2 are assessed on one population frequency per
+//! variant.  `compute` derives it from the allele counts of whichever sources are available,
+//! following the gnomAD guidance to prefer the joint exomes and genomes frequencies:
+//!
+//! 1. If there are gnomAD joint counts (gnomAD v4.0), they are used as is (`Joint`).
+//! 2. Otherwise, if there are counts from both gnomAD exomes and genomes, the counts of the
+//!    source with the higher frequency are used (`BothSources`).  Sources with the same
+//!    frequency are weighted by AN, i.e., the one with more alleles is used.
+//! 3. Otherwise, the counts of the only gnomAD source are used (`SingleSource`).
+//! 4. For the mitochondrial genome, which is not in the gnomAD exomes and genomes, the counts
+//!    of gnomAD-mtDNA and HelixMTdb are pooled by summing AC and AN (`BothSources`), or the
+//!    counts of the only source are used (`SingleSource`).  The frequency is the one of
+//!    homoplasmic carriers (heteroplasmy level >= 0.95 in gnomAD-mtDNA) among all individuals
+//!    as assessed by the ClinGen mitochondrial disease variant curation expert panel; the
+//!    frequency of heteroplasmic carriers is given separately.
+//! 5. Without any of the above, the variant is `Absent` and has no frequency.
+//!
+//! Counts with AN=0, i.e., without any called genotypes, and inconsistent counts with more
+//! alternate alleles (or carriers) than alleles are treated as absent.  Note that a variant
+//! that is absent from one source is not counted with AC=0 for this source as its AN is not
+//! known, so the frequency of a variant only seen in one source is not diluted by the other.
+
+/// Allele counts of a gnomAD exomes, genomes, or joint cohort.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AlleleCounts {
+    /// Number of alternate alleles.
+    pub ac: u64,
+    /// Total number of alleles.
+    pub an: u64,
+}
+
+/// Carrier counts of a mitochondrial source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MtCounts {
+    /// Total number of individuals.
+    pub an: u64,
+    /// Number of homoplasmic carriers.
+    pub ac_hom: u64,
+    /// Number of heteroplasmic carriers.
+    pub ac_het: u64,
+}
+
+/// Counts of all sources of one variant, `None` for sources that are not configured or that
+/// have no record of the variant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Input {
+    /// gnomAD joint exomes and genomes counts.
+    pub gnomad_joint: Option<AlleleCounts>,
+    /// gnomAD exomes counts.
+    pub gnomad_exomes: Option<AlleleCounts>,
+    /// gnomAD genomes counts.
+    pub gnomad_genomes: Option<AlleleCounts>,
+    /// gnomAD-mtDNA counts.
+    pub gnomad_mtdna: Option<MtCounts>,
+    /// HelixMTdb counts.
+    pub helixmtdb: Option<MtCounts>,
+}
+
+/// Source of a `ComputedFrequencies`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FrequencySource {
+    /// gnomAD joint exomes and genomes counts.
+    GnomadJoint,
+    /// gnomAD exomes.
+    GnomadExomes,
+    /// gnomAD genomes.
+    GnomadGenomes,
+    /// gnomAD-mtDNA.
+    GnomadMtdna,
+    /// HelixMTdb.
+    Helixmtdb,
+}
+
+/// Quality tier of a `ComputedFrequencies`, ordered from worst to best.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityTier {
+    /// The variant is in none of the sources.
+    #[default]
+    Absent,
+    /// Only one source has the variant.
+    SingleSource,
+    /// Both sources have the variant, see `compute` for how they are combined.
+    BothSources,
+    /// gnomAD joint exomes and genomes counts.
+    Joint,
+}
+
+/// The best available population frequency of a variant, see `compute` for the rules.
+#[serde_with::skip_serializing_none]
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct ComputedFrequencies {
+    /// Combined alternate allele frequency, of homoplasmic carriers for the mitochondrial
+    /// genome; `None` if absent.
+    pub af: Option<f64>,
+    /// Number of alternate alleles, of homoplasmic carriers for the mitochondrial genome.
+    pub ac: u64,
+    /// Total number of alleles, of individuals for the mitochondrial genome.
+    pub an: u64,
+    /// Frequency of heteroplasmic carriers, for the mitochondrial genome only.
+    pub af_heteroplasmic: Option<f64>,
+    /// The sources the frequency has been computed from.
+    pub sources: Vec<FrequencySource>,
+    /// The quality tier.
+    pub tier: QualityTier,
+}
+
+/// Counts of one source as used for pooling.
+#[derive(Debug, Clone, Copy)]
+struct SourceCounts {
+    /// The source.
+    source: FrequencySource,
+    /// Alternate alleles or homoplasmic carriers.
+    ac: u64,
+    /// Heteroplasmic carriers, `0` for the nuclear sources.
+    ac_het: u64,
+    /// Alleles or individuals.
+    an: u64,
+}
+
+impl SourceCounts {
+    /// Whether the counts have alleles and are consistent.
+    fn is_usable(&self) -> bool {
+        self.an > 0 && self.ac.saturating_add(self.ac_het) <= self.an
+    }
+}
+
+impl From<(FrequencySource, AlleleCounts)> for SourceCounts {
+    fn from((source, counts): (FrequencySource, AlleleCounts)) -> Self {
+        Self {
+            source,
+            ac: counts.ac,
+            ac_het: 0,
+            an: counts.an,
+        }
+    }
+}
+
+impl From<(FrequencySource, MtCounts)> for SourceCounts {
+    fn from((source, counts): (FrequencySource, MtCounts)) -> Self {
+        Self {
+            source,
+            ac: counts.ac_hom,
+            ac_het: counts.ac_het,
+            an: counts.an,
+        }
+    }
+}
+
+/// Return the usable counts of `sources`.
+fn usable<T>(sources: impl IntoIterator<Item = (FrequencySource, Option<T>)>) -> Vec<SourceCounts>
+where
+    SourceCounts: From<(FrequencySource, T)>,
+{
+    sources
+        .into_iter()
+        .filter_map(|(source, counts)| counts.map(|counts| SourceCounts::from((source, counts))))
+        .filter(SourceCounts::is_usable)
+        .collect()
+}
+
+/// Pool the usable `counts` into the result for `tier`.
+fn pooled(tier: QualityTier, counts: &[SourceCounts], mitochondrial: bool) -> ComputedFrequencies {
+    let ac: u64 = counts.iter().map(|counts| counts.ac).sum();
+    let ac_het: u64 = counts.iter().map(|counts| counts.ac_het).sum();
+    let an: u64 = counts.iter().map(|counts| counts.an).sum();
+    ComputedFrequencies {
+        af: Some(ac as f64 / an as f64),
+        ac,
+        an,
+        af_heteroplasmic: mitochondrial.then(|| ac_het as f64 / an as f64),
+        sources: counts.iter().map(|counts| counts.source).collect(),
+        tier,
+    }
+}
+
+/// Return the counts of the source with the highest frequency of `counts`, which must not be
+/// empty, and of the one with the highest AN among those with the same frequency.
+fn highest(counts: &[SourceCounts]) -> SourceCounts {
+    *counts
+        .iter()
+        .max_by(|lhs, rhs| {
+            // Compare `lhs.ac / lhs.an` and `rhs.ac / rhs.an` without rounding.
+            (lhs.ac as u128 * rhs.an as u128)
+                .cmp(&(rhs.ac as u128 * lhs.an as u128))
+                .then(lhs.an.cmp(&rhs.an))
+        })
+        .expect("counts must not be empty")
+}
+
+/// Return the tier of `counts`, which must not be empty.
+fn tier_of(counts: &[SourceCounts]) -> QualityTier {
+    if counts.len() > 1 {
+        QualityTier::BothSources
+    } else {
+        QualityTier::SingleSource
+    }
+}
+
+/// Compute the best available population frequency from `input`.
+///
+/// See the module documentation for the rules.
+pub fn compute(input: &Input) -> ComputedFrequencies {
+    let joint = usable([(FrequencySource::GnomadJoint, input.gnomad_joint)]);
+    if !joint.is_empty() {
+        return pooled(QualityTier::Joint, &joint, false);
+    }
+    let nuclear = usable([
+        (FrequencySource::GnomadExomes, input.gnomad_exomes),
+        (FrequencySource::GnomadGenomes, input.gnomad_genomes),
+    ]);
+    if !nuclear.is_empty() {
+        return pooled(tier_of(&nuclear), &[highest(&nuclear)], false);
+    }
+    let mitochondrial = usable([
+        (FrequencySource::GnomadMtdna, input.gnomad_mtdna),
+        (FrequencySource::Helixmtdb, input.helixmtdb),
+    ]);
+    if !mitochondrial.is_empty() {
+        return pooled(tier_of(&mitochondrial), &mitochondrial, true);
+    }
+    ComputedFrequencies::default()
+}
+
+/// Record with its `ComputedFrequencies` for the query output.
+#[derive(Debug, serde::Serialize)]
+pub struct WithComputed<'a, T: serde::Serialize + ?Sized> {
+    /// The record itself.
+    #[serde(flatten)]
+    pub record: &'a T,
+    /// The computed frequencies.
+    pub computed_frequencies: ComputedFrequencies,
+}
+
+impl From<&super::serialized::auto::Counts> for AlleleCounts {
+    fn from(value: &super::serialized::auto::Counts) -> Self {
+        Self {
+            ac: value.ac_het as u64 + 2 * value.ac_hom as u64,
+            an: value.an as u64,
+        }
+    }
+}
+
+impl From<&super::serialized::xy::Counts> for AlleleCounts {
+    fn from(value: &super::serialized::xy::Counts) -> Self {
+        Self {
+            ac: value.ac_het as u64 + 2 * value.ac_hom as u64 + value.ac_hemi as u64,
+            an: value.an as u64,
+        }
+    }
+}
+
+impl From<&super::serialized::mt::Counts> for MtCounts {
+    fn from(value: &super::serialized::mt::Counts) -> Self {
+        Self {
+            an: value.an as u64,
+            ac_hom: value.ac_hom as u64,
+            ac_het: value.ac_het as u64,
+        }
+    }
+}
+
+impl From<&super::serialized::auto::Record> for Input {
+    fn from(value: &super::serialized::auto::Record) -> Self {
+        Self {
+            gnomad_exomes: Some((&value.gnomad_exomes).into()),
+            gnomad_genomes: Some((&value.gnomad_genomes).into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&super::serialized::xy::Record> for Input {
+    fn from(value: &super::serialized::xy::Record) -> Self {
+        Self {
+            gnomad_exomes: Some((&value.gnomad_exomes).into()),
+            gnomad_genomes: Some((&value.gnomad_genomes).into()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&super::serialized::mt::Record> for Input {
+    fn from(value: &super::serialized::mt::Record) -> Self {
+        Self {
+            gnomad_mtdna: Some((&value.gnomad_mtdna).into()),
+            helixmtdb: Some((&value.helixmtdb).into()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use FrequencySource::*;
+
+    fn counts(ac: u64, an: u64) -> Option<AlleleCounts> {
+        Some(AlleleCounts { ac, an })
+    }
+
+    fn mt_counts(ac_hom: u64, ac_het: u64, an: u64) -> Option<MtCounts> {
+        Some(MtCounts { an, ac_hom, ac_het })
+    }
+
+    fn expected(
+        ac: u64,
+        an: u64,
+        sources: &[FrequencySource],
+        tier: QualityTier,
+    ) -> ComputedFrequencies {
+        ComputedFrequencies {
+            af: Some(ac as f64 / an as f64),
+            ac,
+            an,
+            af_heteroplasmic: None,
+            sources: sources.to_vec(),
+            tier,
+        }
+    }
+
+    fn expected_mt(
+        ac_hom: u64,
+        ac_het: u64,
+        an: u64,
+        sources: &[FrequencySource],
+        tier: QualityTier,
+    ) -> ComputedFrequencies {
+        ComputedFrequencies {
+            af_heteroplasmic: Some(ac_het as f64 / an as f64),
+            ..expected(ac_hom, an, sources, tier)
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::joint_preferred(
+        Input {
+            gnomad_joint: counts(12, 1200),
+            gnomad_exomes: counts(10, 1000),
+            gnomad_genomes: counts(1, 100),
+            ..Default::default()
+        },
+        expected(12, 1200, &[GnomadJoint], QualityTier::Joint),
+    )]
+    #[case::joint_only(
+        Input { gnomad_joint: counts(0, 1200), ..Default::default() },
+        expected(0, 1200, &[GnomadJoint], QualityTier::Joint),
+    )]
+    #[case::joint_an_zero_falls_back(
+        Input {
+            gnomad_joint: counts(0, 0),
+            gnomad_exomes: counts(10, 1000),
+            gnomad_genomes: counts(1, 100),
+            ..Default::default()
+        },
+        expected(10, 1000, &[GnomadExomes], QualityTier::BothSources),
+    )]
+    #[case::both_genomes_higher(
+        Input {
+            gnomad_exomes: counts(2, 100_000),
+            gnomad_genomes: counts(2, 20),
+            ..Default::default()
+        },
+        expected(2, 20, &[GnomadGenomes], QualityTier::BothSources),
+    )]
+    #[case::both_exomes_higher(
+        Input {
+            gnomad_exomes: counts(500, 10_000),
+            gnomad_genomes: counts(1, 100_000),
+            ..Default::default()
+        },
+        expected(500, 10_000, &[GnomadExomes], QualityTier::BothSources),
+    )]
+    #[case::both_same_af_larger_an(
+        Input {
+            gnomad_exomes: counts(1, 100),
+            gnomad_genomes: counts(10, 1000),
+            ..Default::default()
+        },
+        expected(10, 1000, &[GnomadGenomes], QualityTier::BothSources),
+    )]
+    #[case::both_not_observed(
+        Input {
+            gnomad_exomes: counts(0, 1000),
+            gnomad_genomes: counts(0, 100),
+            ..Default::default()
+        },
+        expected(0, 1000, &[GnomadExomes], QualityTier::BothSources),
+    )]
+    #[case::exomes_only(
+        Input { gnomad_exomes: counts(10, 1000), ..Default::default() },
+        expected(10, 1000, &[GnomadExomes], QualityTier::SingleSource),
+    )]
+    #[case::genomes_only(
+        Input { gnomad_genomes: counts(1, 100), ..Default::default() },
+        expected(1, 100, &[GnomadGenomes], QualityTier::SingleSource),
+    )]
+    #[case::exomes_an_zero(
+        Input {
+            gnomad_exomes: counts(0, 0),
+            gnomad_genomes: counts(1, 100),
+            ..Default::default()
+        },
+        expected(1, 100, &[GnomadGenomes], QualityTier::SingleSource),
+    )]
+    #[case::genomes_inconsistent(
+        Input {
+            gnomad_exomes: counts(10, 1000),
+            gnomad_genomes: counts(101, 100),
+            ..Default::default()
+        },
+        expected(10, 1000, &[GnomadExomes], QualityTier::SingleSource),
+    )]
+    #[case::homozygous_all(
+        Input { gnomad_exomes: counts(1000, 1000), ..Default::default() },
+        expected(1000, 1000, &[GnomadExomes], QualityTier::SingleSource),
+    )]
+    #[case::nuclear_preferred_over_mtdna(
+        Input {
+            gnomad_genomes: counts(1, 100),
+            gnomad_mtdna: mt_counts(5, 3, 1000),
+            ..Default::default()
+        },
+        expected(1, 100, &[GnomadGenomes], QualityTier::SingleSource),
+    )]
+    #[case::mtdna_only(
+        Input { gnomad_mtdna: mt_counts(5, 3, 1000), ..Default::default() },
+        expected_mt(5, 3, 1000, &[GnomadMtdna], QualityTier::SingleSource),
+    )]
+    #[case::helixmtdb_only(
+        Input {
+            gnomad_mtdna: mt_counts(0, 0, 0),
+            helixmtdb: mt_counts(2, 0, 200_000),
+            ..Default::default()
+        },
+        expected_mt(2, 0, 200_000, &[Helixmtdb], QualityTier::SingleSource),
+    )]
+    #[case::mtdna_both_pooled(
+        Input {
+            gnomad_mtdna: mt_counts(5, 3, 1000),
+            helixmtdb: mt_counts(15, 7, 9000),
+            ..Default::default()
+        },
+        expected_mt(20, 10, 10_000, &[GnomadMtdna, Helixmtdb], QualityTier::BothSources),
+    )]
+    #[case::mtdna_heteroplasmy_only(
+        Input { gnomad_mtdna: mt_counts(0, 7, 1000), ..Default::default() },
+        expected_mt(0, 7, 1000, &[GnomadMtdna], QualityTier::SingleSource),
+    )]
+    #[case::mtdna_inconsistent(
+        Input {
+            gnomad_mtdna: mt_counts(600, 500, 1000),
+            helixmtdb: mt_counts(1, 0, 100),
+            ..Default::default()
+        },
+        expected_mt(1, 0, 100, &[Helixmtdb], QualityTier::SingleSource),
+    )]
+    fn compute_rules(#[case] input: Input, #[case] expected: ComputedFrequencies) {
+        assert_eq!(compute(&input), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::nothing(Input::default())]
+    #[case::all_an_zero(Input {
+        gnomad_joint: counts(0, 0),
+        gnomad_exomes: counts(0, 0),
+        gnomad_genomes: counts(0, 0),
+        gnomad_mtdna: mt_counts(0, 0, 0),
+        helixmtdb: mt_counts(0, 0, 0),
+    })]
+    #[case::all_inconsistent(Input {
+        gnomad_exomes: counts(3, 2),
+        helixmtdb: mt_counts(2, 1, 2),
+        ..Default::default()
+    })]
+    fn compute_absent(#[case] input: Input) {
+        let result = compute(&input);
+
+        assert_eq!(result, ComputedFrequencies::default());
+        assert_eq!(result.tier, QualityTier::Absent);
+        assert_eq!(result.af, None);
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({"ac": 0, "an": 0, "sources": [], "tier": "absent"})
+        );
+    }
+
+    #[test]
+    fn compute_overflow() {
+        let result = compute(&Input {
+            gnomad_exomes: counts(u64::MAX, u64::MAX),
+            gnomad_genomes: counts(u64::MAX - 1, u64::MAX),
+            ..Default::default()
+        });
+
+        assert_eq!(result.af, Some(1.0));
+        assert_eq!(result.an, u64::MAX);
+        assert_eq!(result.sources, vec![GnomadExomes]);
+
+        let result = compute(&Input {
+            gnomad_mtdna: mt_counts(u32::MAX as u64 * 2, 0, u32::MAX as u64 * 2),
+            helixmtdb: mt_counts(u32::MAX as u64 * 2, 0, u32::MAX as u64 * 2),
+            ..Default::default()
+        });
+
+        assert_eq!(result.af, Some(1.0));
+        assert_eq!(result.an, u32::MAX as u64 * 4);
+    }
+
+    #[test]
+    fn tier_order() {
+        assert!(QualityTier::Joint > QualityTier::BothSources);
+        assert!(QualityTier::BothSources > QualityTier::SingleSource);
+        assert!(QualityTier::SingleSource > QualityTier::Absent);
+    }
+
+    #[test]
+    fn serialize_mtdna() {
+        let result = compute(&Input {
+            gnomad_mtdna: mt_counts(5, 3, 1000),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({
+                "af": 0.005,
+                "ac": 5,
+                "an": 1000,
+                "af_heteroplasmic": 0.003,
+                "sources": ["gnomad_mtdna"],
+                "tier": "single_source",
+            })
+        );
+    }
+
+    #[test]
+    fn input_from_freqs_records() {
+        use crate::freqs::serialized::{auto, mt, xy};
+
+        let input = Input::from(&auto::Record {
+            gnomad_exomes: auto::Counts {
+                an: 1000,
+                ac_hom: 2,
+                ac_het: 3,
+            },
+            gnomad_genomes: Default::default(),
+        });
+        assert_eq!(input.gnomad_exomes, counts(7, 1000));
+        assert_eq!(
+            compute(&input),
+            expected(7, 1000, &[GnomadExomes], QualityTier::SingleSource)
+        );
+
+        let input = Input::from(&xy::Record {
+            gnomad_exomes: Default::default(),
+            gnomad_genomes: xy::Counts {
+                an: 1000,
+                ac_hom: 1,
+                ac_het: 2,
+                ac_hemi: 3,
+                ..Default::default()
+            },
+        });
+        assert_eq!(input.gnomad_genomes, counts(7, 1000));
+
+        let input = Input::from(&mt::Record {
+            gnomad_mtdna: mt::Counts {
+                an: 1000,
+                ac_hom: 5,
+                ac_het: 3,
+            },
+            helixmtdb: Default::default(),
+        });
+        assert_eq!(
+            compute(&input),
+            expected_mt(5, 3, 1000, &[GnomadMtdna], QualityTier::SingleSource)
+        );
+    }
+}
@@ -12,4 +12,5 @@
 //! - HelixMtDb
 
 pub mod cli;
+pub mod computed;
 pub mod serialized;
@@ -1,9 +1,75 @@
 //! gonosomal counts.
+//!
+//! gnomAD reports the counts of XX and XY individuals separately for chrX and chrY.  Outside
+//! of the pseudoautosomal regions (PAR, see `common::par`), XY individuals carry one allele,
+//! so `AN_XY` counts XY individuals and each of the `AC_XY` alternate alleles is from a
+//! hemizygous carrier.  The combined counts follow the gnomAD methodology:
+//!
+//! - `AN = AN_XX + AN_XY` and `AC = AC_XX + AC_XY` everywhere,
+//! - in the PARs, all individuals are diploid, so homozygous carriers are
+//!   `nhomalt_XX + nhomalt_XY` and there are no hemizygous carriers,
+//! - outside of the PARs, homozygous carriers are `nhomalt_XX` and hemizygous carriers are
+//!   `AC_XY`.
+//!
+//! Whether a variant is in a PAR is decided by its position and the PAR boundaries of the
+//! genome release rather than the `nonpar` / `non_par` INFO flags.
+//!
+//! Records written before the XX and XY counts were stored have the combined counts only and
+//! are still read, with `xx` and `xy` of `None`.
 
 use byteorder::{ByteOrder, LittleEndian};
 use noodles::vcf::variant::record::AlternateBases;
 
-use crate::common;
+use crate::common::{self, cli::GenomeRelease};
+
+/// Length of the buffer of the combined counts of `Counts`, the complete buffer of records
+/// written before the XX and XY counts were stored.
+const COMBINED_BUF_LEN: usize = 16;
+
+/// AN, AC, and nhomalt of the XX or XY individuals.
+#[derive(Default, Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SexCounts {
+    /// Total number of alleles, the number of individuals for XY outside of the PARs.
+    pub an: u32,
+    /// Number of alternate alleles.
+    pub ac: u32,
+    /// Number of homozygous alternate individuals as reported by gnomAD.
+    pub nhomalt: u32,
+}
+
+impl SexCounts {
+    /// Length of the buffer.
+    const BUF_LEN: usize = 12;
+
+    /// Read from buffer.
+    fn from_buf(buf: &[u8]) -> Self {
+        Self {
+            an: LittleEndian::read_u32(&buf[0..4]),
+            ac: LittleEndian::read_u32(&buf[4..8]),
+            nhomalt: LittleEndian::read_u32(&buf[8..12]),
+        }
+    }
+
+    /// Write to buffer.
+    fn to_buf(&self, buf: &mut [u8]) {
+        LittleEndian::write_u32(&mut buf[0..4], self.an);
+        LittleEndian::write_u32(&mut buf[4..8], self.ac);
+        LittleEndian::write_u32(&mut buf[8..12], self.nhomalt);
+    }
+}
+
+/// Return the count `names[0]` or the first of its aliases in `names` present in `value`.
+///
+/// Missing values and the value `2147483647` that gnomAD v4 uses for `nhomalt_XX` on chrY
+/// are skipped in favour of the next alias, yielding `None` if no alias remains.
+fn get_count(value: &noodles::vcf::variant::RecordBuf, names: &[&str]) -> Option<u32> {
+    names.iter().find_map(|name| {
+        common::noodles::get_i32(value, name)
+            .ok()
+            .filter(|count| *count != i32::MAX)
+            .and_then(|count| u32::try_from(count).ok())
+    })
+}
 
 /// Record type for storing AN, AC_hom, AC_het, AC_hemi counts for chrX/chrY.
 #[derive(Default, Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
@@ -16,11 +82,27 @@ pub struct Counts {
     pub ac_het: u32,
     /// Number of hemi. alt. alleles.
     pub ac_hemi: u32,
+    /// Counts of the XX individuals, `None` for records without them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xx: Option<SexCounts>,
+    /// Counts of the XY individuals, `None` for records without them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xy: Option<SexCounts>,
 }
 
 impl Counts {
-    /// Create from the given VCF record.
-    pub fn from_vcf_allele(value: &noodles::vcf::variant::RecordBuf, _allele_no: usize) -> Self {
+    /// Length of the buffer.
+    pub const BUF_LEN: usize = COMBINED_BUF_LEN + 2 * SexCounts::BUF_LEN;
+
+    /// Create from the given VCF record of `genome_release`.
+    ///
+    /// See the module documentation for how the combined counts are computed.  Missing XX
+    /// and XY counts are taken as zero.
+    pub fn from_vcf_allele(
+        value: &noodles::vcf::variant::RecordBuf,
+        _allele_no: usize,
+        genome_release: GenomeRelease,
+    ) -> Result<Self, anyhow::Error> {
         assert_eq!(
             value.alternate_bases().len(),
             1,
@@ -28,64 +110,103 @@ impl Counts {
         );
         tracing::trace!("@ {:?}", &value);
 
-        let an = common::noodles::get_i32(value, "AN").unwrap() as u32;
-
-        let ac_hom_xx = common::noodles::get_i32(value, "nhomalt_female")
-            .or_else(|_| common::noodles::get_i32(value, "nhomalt_XX"))
-            .unwrap_or_default() as u32;
-        let ac_xx = common::noodles::get_i32(value, "AC_female")
-            .or_else(|_| common::noodles::get_i32(value, "AC_XX"))
-            .unwrap_or_default() as u32;
-
-        let ac_hom_xy = common::noodles::get_i32(value, "nhomalt_male")
-            .or_else(|_| common::noodles::get_i32(value, "nhomalt_XY"))
-            .expect("neither found: nhomalt_male, nhomalt_XY") as u32;
-        let ac_xy = common::noodles::get_i32(value, "AC_male")
-            .or_else(|_| common::noodles::get_i32(value, "AC_XY"))
-            .expect("neither found: AC_male, AC_XY") as u32;
-
-        let nonpar = common::noodles::get_flag(value, "nonpar").unwrap_or(false)
-            || common::noodles::get_flag(value, "non_par").unwrap_or(false);
-
-        if nonpar {
-            // not in PAR
-            Counts {
-                ac_hom: ac_hom_xx,
-                ac_het: ac_xx.saturating_sub(2 * ac_hom_xx),
-                ac_hemi: ac_xy,
-                an,
-            }
+        let an_xx = get_count(value, &["AN_XX", "AN_female"]);
+        let an_xy = get_count(value, &["AN_XY", "AN_male"]);
+        let xx = SexCounts {
+            an: an_xx.unwrap_or_default(),
+            ac: get_count(value, &["AC_XX", "AC_female"]).unwrap_or_default(),
+            nhomalt: get_count(value, &["nhomalt_XX", "nhomalt_female"]).unwrap_or_default(),
+        };
+        let xy = SexCounts {
+            an: an_xy.unwrap_or_default(),
+            ac: get_count(value, &["AC_XY", "AC_male"]).unwrap_or_default(),
+            nhomalt: get_count(value, &["nhomalt_XY", "nhomalt_male"]).unwrap_or_default(),
+        };
+
+        let an = if an_xx.is_some() || an_xy.is_some() {
+            xx.an + xy.an
         } else {
-            // is in PAR
-            Counts {
-                ac_hom: ac_hom_xx + ac_hom_xy,
-                ac_het: ac_xx.saturating_sub(2 * ac_hom_xx + 2 * ac_hom_xy),
+            get_count(value, &["AN"])
+                .ok_or_else(|| anyhow::anyhow!("neither found: AN_XX, AN_XY, AN"))?
+        };
+        let pos = value
+            .variant_start()
+            .expect("Telomeric breakends not supported")
+            .get();
+        let is_par = common::par::is_par(
+            genome_release,
+            &value.reference_sequence_name().to_string(),
+            pos,
+        );
+
+        if is_par {
+            // diploid in XX and XY
+            let ac_hom = xx.nhomalt + xy.nhomalt;
+            Ok(Counts {
+                an,
+                ac_hom,
+                ac_het: (xx.ac + xy.ac).saturating_sub(2 * ac_hom),
                 ac_hemi: 0,
+                xx: Some(xx),
+                xy: Some(xy),
+            })
+        } else {
+            // hemizygous in XY
+            Ok(Counts {
                 an,
-            }
+                ac_hom: xx.nhomalt,
+                ac_het: xx.ac.saturating_sub(2 * xx.nhomalt),
+                ac_hemi: xy.ac,
+                xx: Some(xx),
+                xy: Some(xy),
+            })
         }
     }
 
-    /// Read from buffer.
+    /// Read from buffer of length `BUF_LEN` or, for older records, of the combined counts
+    /// only.
+    ///
+    /// The XX and XY counts are `None` if all of them are zero, i.e., for absent sources.
     pub fn from_buf(buf: &[u8]) -> Self {
+        let (xx, xy) = if buf.len() >= Self::BUF_LEN {
+            let xx = SexCounts::from_buf(&buf[COMBINED_BUF_LEN..]);
+            let xy = SexCounts::from_buf(&buf[(COMBINED_BUF_LEN + SexCounts::BUF_LEN)..]);
+            if xx == SexCounts::default() && xy == SexCounts::default() {
+                (None, None)
+            } else {
+                (Some(xx), Some(xy))
+            }
+        } else {
+            (None, None)
+        };
         Self {
             an: LittleEndian::read_u32(&buf[0..4]),
             ac_hom: LittleEndian::read_u32(&buf[4..8]),
             ac_het: LittleEndian::read_u32(&buf[8..12]),
             ac_hemi: LittleEndian::read_u32(&buf[12..16]),
+            xx,
+            xy,
         }
     }
 
-    /// Write to buffer.
+    /// Write to buffer of length `BUF_LEN`.
     pub fn to_buf(&self, buf: &mut [u8]) {
         LittleEndian::write_u32(&mut buf[0..4], self.an);
         LittleEndian::write_u32(&mut buf[4..8], self.ac_hom);
         LittleEndian::write_u32(&mut buf[8..12], self.ac_het);
         LittleEndian::write_u32(&mut buf[12..16], self.ac_hemi);
+        self.xx
+            .clone()
+            .unwrap_or_default()
+            .to_buf(&mut buf[COMBINED_BUF_LEN..]);
+        self.xy
+            .clone()
+            .unwrap_or_default()
+            .to_buf(&mut buf[(COMBINED_BUF_LEN + SexCounts::BUF_LEN)..]);
     }
 }
 
-/// Record type for the "mitochondrial" column family.
+/// Record type for the "gonosomal" column family.
 #[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Record {
     /// Counts from gnomAD exomes.
@@ -95,22 +216,226 @@ pub struct Record {
 }
 
 impl Record {
-    /// Read from buffer.
+    /// Read from buffer, also of the records written before the XX and XY counts were stored.
     pub fn from_buf(buf: &[u8]) -> Self {
+        let counts_len = if buf.len() >= Self::buf_len() {
+            Counts::BUF_LEN
+        } else {
+            COMBINED_BUF_LEN
+        };
         Self {
-            gnomad_exomes: Counts::from_buf(&buf[0..16]),
-            gnomad_genomes: Counts::from_buf(&buf[16..32]),
+            gnomad_exomes: Counts::from_buf(&buf[0..counts_len]),
+            gnomad_genomes: Counts::from_buf(&buf[counts_len..(2 * counts_len)]),
         }
     }
 
     /// Write to buffer.
     pub fn to_buf(&self, buf: &mut [u8]) {
-        self.gnomad_exomes.to_buf(&mut buf[0..16]);
-        self.gnomad_genomes.to_buf(&mut buf[16..32]);
+        self.gnomad_exomes.to_buf(&mut buf[0..Counts::BUF_LEN]);
+        self.gnomad_genomes
+            .to_buf(&mut buf[Counts::BUF_LEN..(2 * Counts::BUF_LEN)]);
     }
 
     /// Length of the buffer.
     pub fn buf_len() -> usize {
-        32
+        2 * Counts::BUF_LEN
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Return the counts of each record in the VCF file at `path`.
+    fn counts_from_vcf(
+        path: &str,
+        genome_release: GenomeRelease,
+    ) -> Result<Vec<(String, Counts)>, anyhow::Error> {
+        let mut reader = noodles::vcf::io::reader::Builder::default().build_from_path(path)?;
+        let header = reader.read_header()?;
+
+        let mut result = Vec::new();
+        for record in reader.record_bufs(&header) {
+            let record = record?;
+            let var = common::keys::Var::from_vcf_allele(&record, 0);
+            result.push((
+                format!("{}:{}", var.chrom, var.pos),
+                Counts::from_vcf_allele(&record, 0, genome_release)?,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    fn counts(
+        an: u32,
+        ac_hom: u32,
+        ac_het: u32,
+        ac_hemi: u32,
+        xx: [u32; 3],
+        xy: [u32; 3],
+    ) -> Counts {
+        let sex_counts = |[an, ac, nhomalt]: [u32; 3]| SexCounts { an, ac, nhomalt };
+        Counts {
+            an,
+            ac_hom,
+            ac_het,
+            ac_hemi,
+            xx: Some(sex_counts(xx)),
+            xy: Some(sex_counts(xy)),
+        }
+    }
+
+    #[test]
+    fn from_vcf_allele_grch38_v4() -> Result<(), anyhow::Error> {
+        let result = counts_from_vcf(
+            "tests/freqs/xy/gnomad-grch38-v4.0.vcf",
+            GenomeRelease::Grch38,
+        )?;
+
+        assert_eq!(
+            result,
+            vec![
+                // PAR1: 257 = 132 + 125 alleles, of which 2 * (12 + 14) in homozygous carriers
+                (
+                    String::from("chrX:10072"),
+                    counts(8904, 26, 205, 0, [4792, 132, 12], [4112, 125, 14])
+                ),
+                // last base of PAR1, without `non_par`
+                (
+                    String::from("chrX:2781479"),
+                    counts(1800, 5, 10, 0, [1000, 12, 2], [800, 8, 3])
+                ),
+                // first base after PAR1, with `non_par`
+                (
+                    String::from("chrX:2781480"),
+                    counts(1400, 2, 8, 5, [1000, 12, 2], [400, 5, 5])
+                ),
+                (
+                    String::from("chrY:2781554"),
+                    counts(10663, 0, 0, 1, [0, 0, 0], [10663, 1, 0])
+                ),
+                // `nhomalt_XX=2147483647` on chrY is ignored
+                (
+                    String::from("chrY:2786738"),
+                    counts(100190, 0, 0, 1, [0, 0, 0], [100190, 1, 0])
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_vcf_allele_grch37_v2_1() -> Result<(), anyhow::Error> {
+        let result = counts_from_vcf(
+            "tests/freqs/xy/gnomad-grch37-v2.1.vcf",
+            GenomeRelease::Grch37,
+        )?;
+
+        assert_eq!(
+            result,
+            vec![
+                // last base of PAR1
+                (
+                    String::from("X:2699520"),
+                    counts(1000, 2, 5, 0, [600, 5, 1], [400, 4, 1])
+                ),
+                (
+                    String::from("X:69902557"),
+                    counts(179321, 0, 1, 0, [115294, 1, 0], [64027, 0, 0])
+                ),
+                (
+                    String::from("Y:4967199"),
+                    counts(67766, 0, 0, 1, [0, 0, 0], [67766, 1, 0])
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_vcf_allele_fallbacks() -> Result<(), anyhow::Error> {
+        let result = counts_from_vcf(
+            "tests/freqs/xy/gnomad-grch37-fallbacks.vcf",
+            GenomeRelease::Grch37,
+        )?;
+
+        assert_eq!(
+            result,
+            vec![
+                // `AC_XY` and `nhomalt_XY` of 2147483647 fall back to `AC_male` and `nhomalt_male`
+                (
+                    String::from("X:69902557"),
+                    counts(1000, 1, 3, 7, [600, 5, 1], [400, 7, 2])
+                ),
+                // missing `nhomalt_XY` and `nhomalt_male` are taken as zero
+                (
+                    String::from("X:69902600"),
+                    counts(1000, 1, 3, 4, [600, 5, 1], [400, 4, 0])
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn record_buf_roundtrip() {
+        let record = Record {
+            gnomad_exomes: counts(1400, 2, 8, 5, [1000, 12, 2], [400, 5, 5]),
+            gnomad_genomes: Counts::default(),
+        };
+
+        let mut buf = vec![0u8; Record::buf_len()];
+        record.to_buf(&mut buf);
+
+        assert_eq!(Record::from_buf(&buf), record);
+        assert_eq!(
+            serde_json::to_value(&record.gnomad_exomes).unwrap(),
+            serde_json::json!({
+                "an": 1400,
+                "ac_hom": 2,
+                "ac_het": 8,
+                "ac_hemi": 5,
+                "xx": {"an": 1000, "ac": 12, "nhomalt": 2},
+                "xy": {"an": 400, "ac": 5, "nhomalt": 5},
+            })
+        );
+    }
+
+    #[test]
+    fn record_from_buf_combined_only() {
+        let mut buf = vec![0u8; 2 * COMBINED_BUF_LEN];
+        for (i, value) in [1000u32, 1, 2, 3, 2000, 4, 5, 6].iter().enumerate() {
+            LittleEndian::write_u32(&mut buf[(4 * i)..(4 * i + 4)], *value);
+        }
+
+        let record = Record::from_buf(&buf);
+
+        assert_eq!(
+            record,
+            Record {
+                gnomad_exomes: Counts {
+                    an: 1000,
+                    ac_hom: 1,
+                    ac_het: 2,
+                    ac_hemi: 3,
+                    ..Default::default()
+                },
+                gnomad_genomes: Counts {
+                    an: 2000,
+                    ac_hom: 4,
+                    ac_het: 5,
+                    ac_hemi: 6,
+                    ..Default::default()
+                },
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&record.gnomad_exomes).unwrap(),
+            serde_json::json!({"an": 1000, "ac_hom": 1, "ac_het": 2, "ac_hemi": 3})
+        );
     }
 }
@@ -62,6 +62,9 @@ impl FromStr for RefseqRegulatoryClass {
     }
 }
 
+/// Default name of the column family with the positional index, see `common::pos_index`.
+pub const CF_NAME_POS_INDEX: &str = "functional_by_pos";
+
 /// Command line arguments for `functional-elements import` sub command.
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import functional elements data into RocksDB", long_about = None)]
@@ -79,9 +82,18 @@ pub struct Args {
     /// Name of the column family to import into.
     #[arg(long, default_value = "functional")]
     pub cf_name: String,
+    /// Name of the column family with the positional index.
+    #[arg(long, default_value = CF_NAME_POS_INDEX)]
+    pub cf_name_pos_index: String,
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform import of the GFF files.
@@ -91,21 +103,15 @@ fn gff_import(
     path_in_gff: &str,
 ) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
+    let cf_pos_index = db.cf_handle(&args.cf_name_pos_index).unwrap();
 
     // Open reader, possibly decompressing gziped files.
-    let reader: Box<dyn std::io::Read> = if path_in_gff.ends_with(".gz") {
-        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
-            path_in_gff,
-        )?))
-    } else {
-        Box::new(std::fs::File::open(path_in_gff)?)
-    };
+    let mut reader = gff::Reader::new(common::open_read_maybe_gz(path_in_gff)?);
 
     let mut skipped_seq = indexmap::IndexSet::new();
 
     // Import of RefSeq GFF data.
     let contig_map = ContigMap::new(args.genome_release.into());
-    let mut reader = gff::Reader::new(std::io::BufReader::new(reader));
     for result in reader.records() {
         let record = result?;
 
@@ -181,17 +187,49 @@ fn gff_import(
 
         let buf = record.encode_to_vec();
         db.put_cf(&cf_data, record.id.as_bytes(), buf)?;
+        common::pos_index::put(
+            db,
+            &cf_pos_index,
+            &record.chromosome,
+            record.start,
+            record.stop,
+            &record.id,
+        )?;
     }
 
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+///
+/// The first record of each input file is parsed in the same way as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    for path_in_gff in &args.path_in_gff {
+        validation.read_input(path_in_gff, |path| {
+            let mut reader = gff::Reader::new(common::open_read_maybe_gz(path)?);
+            common::dry_run::first_record(reader.records()).map(|_| ())
+        });
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `clinvar-minimal import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'functional import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -199,7 +237,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
-    let cf_names = &["meta", &args.cf_name];
+    let cf_names = &["meta", &args.cf_name, &args.cf_name_pos_index];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out_rocksdb)?,
@@ -241,6 +279,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "functional".to_string(),
+            title: "RefSeq functional elements".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![(
+                "RefSeq functional elements".to_string(),
+                args.spec.data_version(),
+            )],
+            paths_in: args.path_in_gff.clone(),
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -257,6 +309,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
@@ -265,7 +318,10 @@ mod test {
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("functional"),
+            cf_name_pos_index: String::from(CF_NAME_POS_INDEX),
             path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
@@ -276,6 +332,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch38,
@@ -284,9 +341,44 @@ mod test {
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("functional"),
+            cf_name_pos_index: String::from(CF_NAME_POS_INDEX),
             path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
     }
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[test]
+    fn dry_run_import_gff() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_gff: vec![String::from(
+                "tests/functional/GCF_000001405.25_GRCh37.p13_genomic.functional.gff",
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("functional"),
+            cf_name_pos_index: String::from(CF_NAME_POS_INDEX),
+            path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_gff: vec![String::from("tests/cons/example/tgds.tsv")],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
 }
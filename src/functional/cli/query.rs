@@ -1,6 +1,6 @@
 //! Functional element queries.
 
-use std::{io::Write, sync::Arc};
+use std::sync::Arc;
 
 use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
 use prost::Message;
@@ -36,12 +36,15 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// Number of records of range queries to skip and write.
+    #[command(flatten)]
+    pub limit: common::cli::ArgsLimit,
 }
 
 /// Meta information as read from database.
@@ -62,17 +65,17 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
         }
@@ -94,20 +97,6 @@ pub fn open_rocksdb_from_args(
     open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
 }
 
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &crate::pbs::functional::refseq::Record,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
-    }
-
-    Ok(())
-}
-
 /// Query by accession.
 pub fn query_for_accession(
     accession: &str,
@@ -115,7 +104,10 @@ pub fn query_for_accession(
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
 ) -> Result<Option<crate::pbs::functional::refseq::Record>, anyhow::Error> {
     let raw_value = db.get_cf(cf_data, accession.as_bytes()).map_err(|e| {
-        anyhow::anyhow!("error while querying for accession {:?}: {}", accession, e)
+        crate::ErrorCategory::Database.error(format!(
+            "error while querying for accession {:?}: {}",
+            accession, e
+        ))
     })?;
     raw_value
         .map(|raw_value| {
@@ -126,24 +118,28 @@ pub fn query_for_accession(
         .transpose()
 }
 
-/// Query all variants and print to `out_writer`.
+/// Query all variants admitted by `counter` and print to `out_writer`.
 fn print_all(
-    out_writer: &mut Box<dyn std::io::Write>,
-    out_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    counter: &mut common::cli::LimitCounter,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("dumping all records...");
 
     let mut iter = db.raw_iterator_cf(cf_data);
     iter.seek(b"");
-    while iter.valid() {
+    while iter.valid() && !counter.is_done() {
         if let Some(raw_value) = iter.value() {
+            if !counter.admit() {
+                iter.next();
+                continue;
+            }
             let record = crate::pbs::functional::refseq::Record::decode(&mut std::io::Cursor::new(
                 &raw_value,
             ))
             .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
-            print_record(out_writer, out_format, &record)?;
+            out_writer.write(&record.id, &record)?;
             iter.next();
         } else {
             break;
@@ -247,12 +243,14 @@ impl IntervalTrees {
         Ok(result)
     }
 
-    /// Query for a range.
+    /// Query for a range, returning only the records admitted by `counter`.
     pub fn query(
         &self,
         range: &spdi::Range,
+        counter: &mut common::cli::LimitCounter,
     ) -> Result<Vec<crate::pbs::functional::refseq::Record>, anyhow::Error> {
-        let contig = extract_chrom::from_range(range, Some(&self.meta.genome_release))?;
+        let range = extract_chrom::range(range, Some(&self.meta.genome_release))?;
+        let contig = range.sequence.clone();
         let cf_data = self.db.cf_handle(&self.cf_data_name).ok_or_else(|| {
             anyhow::anyhow!("no column family with name {:?} found", &self.cf_data_name)
         })?;
@@ -260,6 +258,12 @@ impl IntervalTrees {
         let mut result = Vec::new();
         if let Some(tree) = self.trees.get(&contig) {
             for entry in tree.find(&interval) {
+                if counter.is_done() {
+                    break;
+                }
+                if !counter.admit() {
+                    continue;
+                }
                 if let Some(raw_value) = self.db.get_cf(&cf_data, entry.data().as_bytes())? {
                     let record = crate::pbs::functional::refseq::Record::decode(
                         &mut std::io::Cursor::new(&raw_value),
@@ -286,20 +290,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "functional",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
+    let mut counter = args.limit.counter();
     if let Some(accession) = args.query.accession.as_ref() {
         tracing::info!("for accession {}", &accession);
         if let Some(record) = query_for_accession(accession, &db, &cf_data)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+            out_writer.write(&record.id, &record)?;
         } else {
             tracing::info!("no record found for accession {:?}", &accession);
         }
@@ -311,15 +315,15 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         tracing::info!("... done building interval trees");
         tracing::info!("Running query...");
         let records = trees
-            .query(range)
+            .query(range, &mut counter)
             .map_err(|e| anyhow::anyhow!("failed to query interval trees: {}", e))?;
         for record in &records {
-            print_record(&mut out_writer, args.out_format, record)?;
+            out_writer.write(&record.id, record)?;
         }
         tracing::info!("... done running query");
     } else if args.query.all {
         tracing::info!("for all");
-        print_all(&mut out_writer, args.out_format, &db, &cf_data)?;
+        print_all(&mut out_writer, &db, &cf_data, &mut counter)?;
     } else {
         unreachable!();
     }
@@ -343,12 +347,13 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             // path_rocksdb: String::from("tests/functional/GCF_000001405.25_GRCh37.p13_genomic.db"),
             cf_name: String::from("functional"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             ..Default::default()
         };
 
@@ -452,4 +457,103 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case::accession(
+        ArgsQuery {
+            accession: Some("id-GeneID:106783496-2".into()),
+            ..Default::default()
+        },
+        &["id-GeneID:106783496-2"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:3157509:3157803").unwrap()),
+            ..Default::default()
+        },
+        &["id-GeneID:112577578", "id-GeneID:112577578-2"],
+    )]
+    fn smoke_query_jsonl_v2_37(
+        args_37: (common::cli::Args, Args, TempDir),
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, temp) = args_37;
+        let args_v1 = Args {
+            query: query.clone(),
+            ..args.clone()
+        };
+        run(&common, &args_v1)?;
+        let args_v2 = Args {
+            out_file: temp.join("out-v2").to_string_lossy().to_string(),
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            query,
+            ..args
+        };
+        run(&common, &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            "functional",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::range_limit(false, Some(1), 0, 1)]
+    #[case::range_offset(false, None, 1, 1)]
+    #[case::range_past_end(false, Some(5), 2, 0)]
+    #[case::range_zero(false, Some(0), 0, 0)]
+    #[case::all_limit(true, Some(3), 0, 3)]
+    #[case::all_limit_offset(true, Some(3), 2, 3)]
+    fn query_limit_offset_37(
+        args_37: (common::cli::Args, Args, TempDir),
+        #[case] all: bool,
+        #[case] limit: Option<usize>,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, temp) = args_37;
+        let args_unlimited = Args {
+            query: if all {
+                ArgsQuery {
+                    all: true,
+                    ..Default::default()
+                }
+            } else {
+                ArgsQuery {
+                    range: Some(spdi::Range::from_str("GRCh37:1:3157509:3157803")?),
+                    ..Default::default()
+                }
+            },
+            ..args
+        };
+        run(&common, &args_unlimited)?;
+        let args = Args {
+            out_file: temp.join("out-limit").to_string_lossy().to_string(),
+            limit: common::cli::ArgsLimit { limit, offset },
+            ..args_unlimited.clone()
+        };
+        run(&common, &args)?;
+
+        let out_unlimited = std::fs::read_to_string(&args_unlimited.out_file)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out_data.lines().count(), expected);
+        assert_eq!(
+            out_data.lines().collect::<Vec<_>>(),
+            out_unlimited
+                .lines()
+                .skip(offset)
+                .take(expected)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
 }
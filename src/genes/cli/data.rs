@@ -18,6 +18,8 @@ pub struct Record {
     pub dbnsfp: Option<dbnsfp_gene::Record>,
     /// Information from the gnomAD constraints database.
     pub gnomad_constraints: Option<gnomad_constraints::Record>,
+    /// Transcript-level information from the gnomAD constraints database.
+    pub gnomad_constraints_transcripts: Vec<gnomad_constraints::Record>,
     /// Information from the HGNC database.
     pub hgnc: hgnc::Record,
     /// Information from the NCBI gene database (aka "Entrez").
@@ -251,6 +253,8 @@ pub mod decipher_hi {
 
 /// Code for deserializing data from dbNSFP gene.
 pub mod dbnsfp_gene {
+    use std::collections::HashMap;
+
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     /// Deserialize `Option::None` as `"."` - String version.
@@ -380,15 +384,196 @@ pub mod dbnsfp_gene {
         }
     }
 
+    /// Column names of the record fields in the dbNSFP gene files, the first present one is used.
+    ///
+    /// The names of dbNSFP 4.4 come first, followed by the ones of later releases.
+    pub static DEFAULT_COLUMN_NAMES: &[(&str, &[&str])] = &[
+        ("gene_name", &["Gene_name"]),
+        ("ensembl_gene", &["Ensembl_gene"]),
+        ("chr", &["chr"]),
+        ("gene_old_names", &["Gene_old_names"]),
+        ("gene_other_names", &["Gene_other_names"]),
+        ("uniprot_acc", &["Uniprot_acc(HGNC/Uniprot)", "Uniprot_acc"]),
+        (
+            "uniprot_id",
+            &["Uniprot_id(HGNC/Uniprot)", "Uniprot_entry", "Uniprot_id"],
+        ),
+        ("entrez_gene_id", &["Entrez_gene_id"]),
+        ("ccds_id", &["CCDS_id"]),
+        ("refseq_id", &["Refseq_id"]),
+        ("ucsc_id", &["ucsc_id"]),
+        ("mim_id", &["MIM_id"]),
+        ("omim_id", &["OMIM_id"]),
+        ("gene_full_name", &["Gene_full_name"]),
+        ("pathway_uniprot", &["Pathway(Uniprot)"]),
+        ("pathway_biocarta_short", &["Pathway(BioCarta)_short"]),
+        ("pathway_biocarta_full", &["Pathway(BioCarta)_full"]),
+        ("pathway_consensus_path_db", &["Pathway(ConsensusPathDB)"]),
+        ("pathway_kegg_id", &["Pathway(KEGG)_id"]),
+        ("pathway_kegg_full", &["Pathway(KEGG)_full"]),
+        ("function_description", &["Function_description"]),
+        ("disease_description", &["Disease_description"]),
+        ("mim_phenotype_id", &["MIM_phenotype_id"]),
+        ("mim_disease", &["MIM_disease"]),
+        ("orphanet_disorder_id", &["Orphanet_disorder_id"]),
+        ("orphanet_disorder", &["Orphanet_disorder"]),
+        ("orphanet_association_type", &["Orphanet_association_type"]),
+        ("trait_association_gwas", &["Trait_association(GWAS)"]),
+        ("hpo_id", &["HPO_id"]),
+        ("hpo_name", &["HPO_name"]),
+        ("go_biological_process", &["GO_biological_process"]),
+        ("go_cellular_component", &["GO_cellular_component"]),
+        ("go_molecular_function", &["GO_molecular_function"]),
+        (
+            "tissue_specificity_uniprot",
+            &["Tissue_specificity(Uniprot)"],
+        ),
+        ("expression_egenetics", &["Expression(egenetics)"]),
+        ("expression_gnf_atlas", &["Expression(GNF/Atlas)"]),
+        ("interactions_intact", &["Interactions(IntAct)"]),
+        ("interactions_biogrid", &["Interactions(BioGRID)"]),
+        (
+            "interactions_consensus_path_db",
+            &["Interactions(ConsensusPathDB)"],
+        ),
+        ("haploinsufficiency", &["P(HI)"]),
+        ("hipred_score", &["HIPred_score"]),
+        ("hipred", &["HIPred"]),
+        ("ghis", &["GHIS"]),
+        ("prec", &["P(rec)"]),
+        ("known_rec_info", &["Known_rec_info"]),
+        ("rvis_evs", &["RVIS_EVS"]),
+        ("rvis_percentile_evs", &["RVIS_percentile_EVS"]),
+        ("lof_fdr_exac", &["LoF-FDR_ExAC"]),
+        ("rvis_exac", &["RVIS_ExAC"]),
+        ("rvis_percentile_exac", &["RVIS_percentile_ExAC"]),
+        ("exac_pli", &["ExAC_pLI"]),
+        ("exac_prec", &["ExAC_pRec"]),
+        ("exac_pnull", &["ExAC_pNull"]),
+        ("exac_nontcga_pli", &["ExAC_nonTCGA_pLI"]),
+        ("exac_nontcga_prec", &["ExAC_nonTCGA_pRec"]),
+        ("exac_nontcga_pnull", &["ExAC_nonTCGA_pNull"]),
+        ("exac_nonpsych_pli", &["ExAC_nonpsych_pLI"]),
+        ("exac_nonpsych_prec", &["ExAC_nonpsych_pRec"]),
+        ("exac_nonpsych_pnull", &["ExAC_nonpsych_pNull"]),
+        ("gnomad_pli", &["gnomAD_pLI"]),
+        ("gnomad_prec", &["gnomAD_pRec"]),
+        ("gnomad_pnull", &["gnomAD_pNull"]),
+        ("exac_del_score", &["ExAC_del.score"]),
+        ("exac_dup_score", &["ExAC_dup.score"]),
+        ("exac_cnv_score", &["ExAC_cnv.score"]),
+        ("exac_cnv_flag", &["ExAC_cnv_flag"]),
+        ("gdi", &["GDI"]),
+        ("gdi_phred", &["GDI-Phred"]),
+        (
+            "gdp_all_disease_causing",
+            &["Gene damage prediction (all disease-causing genes)"],
+        ),
+        (
+            "gdp_all_mendelian",
+            &["Gene damage prediction (all Mendelian disease-causing genes)"],
+        ),
+        (
+            "gdp_all_mendelian_ad",
+            &["Gene damage prediction (Mendelian AD disease-causing genes)"],
+        ),
+        (
+            "gdp_mendelian_ar",
+            &["Gene damage prediction (Mendelian AR disease-causing genes)"],
+        ),
+        (
+            "gdp_pid",
+            &["Gene damage prediction (all PID disease-causing genes)"],
+        ),
+        (
+            "gdp_pid_ad",
+            &["Gene damage prediction (PID AD disease-causing genes)"],
+        ),
+        (
+            "gdp_pid_ar",
+            &["Gene damage prediction (PID AR disease-causing genes)"],
+        ),
+        (
+            "gdp_cancer",
+            &["Gene damage prediction (all cancer disease-causing genes)"],
+        ),
+        (
+            "gdb_cancer_rec",
+            &["Gene damage prediction (cancer recessive disease-causing genes)"],
+        ),
+        (
+            "gdp_cancer_dom",
+            &["Gene damage prediction (cancer dominant disease-causing genes)"],
+        ),
+        ("loftool_score", &["LoFtool_score"]),
+        (
+            "sorva_lof_maf_5_het_or_hom",
+            &["SORVA_LOF_MAF0.005_HetOrHom"],
+        ),
+        (
+            "sorva_lof_maf_5_hom_or_comphet",
+            &["SORVA_LOF_MAF0.005_HomOrCompoundHet"],
+        ),
+        (
+            "sorva_lof_maf_1_het_or_hom",
+            &["SORVA_LOF_MAF0.001_HetOrHom"],
+        ),
+        (
+            "sorva_lof_maf_1_hom_or_comphet",
+            &["SORVA_LOF_MAF0.001_HomOrCompoundHet"],
+        ),
+        (
+            "sorva_lof_or_mis_maf_5_het_or_hom",
+            &["SORVA_LOForMissense_MAF0.005_HetOrHom"],
+        ),
+        (
+            "sorva_lof_or_mis_maf_5_hom_or_comphet",
+            &["SORVA_LOForMissense_MAF0.005_HomOrCompoundHet"],
+        ),
+        (
+            "sorva_lof_or_mis_maf_1_het_or_hom",
+            &["SORVA_LOForMissense_MAF0.001_HetOrHom"],
+        ),
+        (
+            "sorva_lof_or_mis_maf_1_hom_or_comphet",
+            &["SORVA_LOForMissense_MAF0.001_HomOrCompoundHet"],
+        ),
+        ("essential_gene", &["Essential_gene"]),
+        ("essential_gene_crispr", &["Essential_gene_CRISPR"]),
+        ("essential_gene_crispr2", &["Essential_gene_CRISPR2"]),
+        ("essential_gene_gene_trap", &["Essential_gene_gene-trap"]),
+        (
+            "gene_indispensability_score",
+            &["Gene_indispensability_score"],
+        ),
+        (
+            "gene_indispensability_pred",
+            &["Gene_indispensability_pred"],
+        ),
+        ("mgi_mouse_gene", &["MGI_mouse_gene"]),
+        ("mgi_mouse_phenotype", &["MGI_mouse_phenotype"]),
+        ("zfin_zebrafish_gene", &["ZFIN_zebrafish_gene"]),
+        ("zfin_zebrafish_structure", &["ZFIN_zebrafish_structure"]),
+        (
+            "zfin_zebrafish_phenotype_quality",
+            &["ZFIN_zebrafish_phenotype_quality"],
+        ),
+        (
+            "zfin_zebrafish_phenotype_tag",
+            &["ZFIN_zebrafish_phenotype_tag"],
+        ),
+    ];
+
     /// A record from the dbNSFP gene database.
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    ///
+    /// The fields are named after the columns by `ColumnMapping`, missing columns are left empty.
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct Record {
         /// Gene symbol from HGNC.
-        #[serde(alias = "Gene_name")]
         pub gene_name: String,
         /// Ensembl gene id (from HGNC).
         #[serde(
-            alias = "Ensembl_gene",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -400,78 +585,49 @@ pub mod dbnsfp_gene {
         )]
         pub chr: Option<String>,
         /// Old gene symbol (from HGNC).
-        #[serde(
-            alias = "Gene_old_names",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub gene_old_names: Vec<String>,
         /// Other gene names (from HGNC).
-        #[serde(
-            alias = "Gene_other_names",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub gene_other_names: Vec<String>,
         /// Uniprot acc (from HGNC).
         #[serde(
-            alias = "Uniprot_acc(HGNC/Uniprot)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub uniprot_acc: Option<String>,
         /// Uniprot id (from HGNC).
         #[serde(
-            alias = "Uniprot_id(HGNC/Uniprot)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub uniprot_id: Option<String>,
         /// Uniprot id (from HGNC).
         #[serde(
-            alias = "Entrez_gene_id",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub entrez_gene_id: Option<String>,
         /// CCDS id (from HGNC).
-        #[serde(
-            alias = "CCDS_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub ccds_id: Vec<String>,
         /// Refseq gene id (from HGNC).
-        #[serde(
-            alias = "Refseq_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub refseq_id: Vec<String>,
         /// UCSC gene id (from HGNC).
         #[serde(
-            alias = "ucsc_id",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub ucsc_id: Option<String>,
         /// MIM gene id (from OMIM).
-        #[serde(
-            alias = "MIM_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub mim_id: Vec<String>,
         /// MIM gene id from OMIM.
-        #[serde(
-            alias = "OMIM_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub omim_id: Vec<String>,
         /// Gene full name (from HGNC).
         #[serde(
-            alias = "Gene_full_name",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -479,185 +635,87 @@ pub mod dbnsfp_gene {
 
         /// Pathway description from Uniprot.
         #[serde(
-            alias = "Pathway(Uniprot)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub pathway_uniprot: Option<String>,
         /// Short name of the Pathway(s) the gene belongs to (from BioCarta).
-        #[serde(
-            alias = "Pathway(BioCarta)_short",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub pathway_biocarta_short: Vec<String>,
         /// Full name(s) of the Pathway(s) the gene belongs to (from BioCarta).
-        #[serde(
-            alias = "Pathway(BioCarta)_full",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub pathway_biocarta_full: Vec<String>,
         /// Pathway(s) the gene belongs to (from ConsensusPathDB).
-        #[serde(
-            alias = "Pathway(ConsensusPathDB)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub pathway_consensus_path_db: Vec<String>,
         /// ID(s) of the Pathway(s) the gene belongs to (from KEGG).
-        #[serde(
-            alias = "Pathway(KEGG)_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub pathway_kegg_id: Vec<String>,
         /// Full name(s) of the Pathway(s) the gene belongs to (from KEGG).
-        #[serde(
-            alias = "Pathway(KEGG)_full",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub pathway_kegg_full: Vec<String>,
 
         /// Function description of the gene (from Uniprot).
-        #[serde(
-            alias = "Function_description",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub function_description: Vec<String>,
         /// Disease(s) the gene caused or associated with (from Uniprot).
-        #[serde(
-            alias = "Disease_description",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub disease_description: Vec<String>,
         /// MIM id(s) of the phenotype the gene caused or associated with (from Uniprot).
-        #[serde(
-            alias = "MIM_phenotype_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub mim_phenotype_id: Vec<String>,
         /// MIM disease name(s) with MIM id(s) in [] (from Uniprot).
-        #[serde(
-            alias = "MIM_disease",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub mim_disease: Vec<String>,
         /// Orphanet Number of the disorder the gene caused or associated with.
-        #[serde(
-            alias = "Orphanet_disorder_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub orphanet_disorder_id: Vec<String>,
         /// Disorder name from Orphanet.
-        #[serde(
-            alias = "Orphanet_disorder",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub orphanet_disorder: Vec<String>,
         /// The type of association beteen the gene and the disorder in Orphanet.
-        #[serde(
-            alias = "Orphanet_association_type",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub orphanet_association_type: Vec<String>,
         /// Trait(s) the gene associated with (from GWAS catalog).
-        #[serde(
-            alias = "Trait_association(GWAS)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub trait_association_gwas: Vec<String>,
         /// ID of the mapped Human Phenotype Ontology.
-        #[serde(
-            alias = "HPO_id",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub hpo_id: Vec<String>,
         /// Name of the mapped Human Phenotype Ontology.
-        #[serde(
-            alias = "HPO_name",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub hpo_name: Vec<String>,
         /// GO terms for biological process.
-        #[serde(
-            alias = "GO_biological_process",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub go_biological_process: Vec<String>,
         /// GO terms for cellular component.
-        #[serde(
-            alias = "GO_cellular_component",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub go_cellular_component: Vec<String>,
         /// GO terms for molecular function.
-        #[serde(
-            alias = "GO_molecular_function",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub go_molecular_function: Vec<String>,
         /// Tissue specificity description from Uniprot.
-        #[serde(
-            alias = "Tissue_specificity(Uniprot)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub tissue_specificity_uniprot: Vec<String>,
         /// Tissues/organs the gene expressed in (egenetics data from BioMart).
-        #[serde(
-            alias = "Expression(egenetics)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub expression_egenetics: Vec<String>,
         /// Tissues/organs the gene expressed in (GNF/Atlas data from BioMart).
-        #[serde(
-            alias = "Expression(GNF/Atlas)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub expression_gnf_atlas: Vec<String>,
         /// The interacting genes from IntAct.
-        #[serde(
-            alias = "Interactions(IntAct)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub interactions_intact: Vec<String>,
         /// The interacting genes from BioGRID.
-        #[serde(
-            alias = "Interactions(BioGRID)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub interactions_biogrid: Vec<String>,
         /// The interacting genes from ConsensusPathDB.
-        #[serde(
-            alias = "Interactions(ConsensusPathDB)",
-            serialize_with = "serialize_vec",
-            deserialize_with = "deserialize_vec"
-        )]
+        #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
         pub interactions_consensus_path_db: Vec<String>,
 
         /// Estimated probability of haploinsufficiency of the gene (from
         /// doi:10.1371/journal.pgen.1001154).
         #[serde(
-            alias = "P(HI)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -665,7 +723,6 @@ pub mod dbnsfp_gene {
         /// Estimated probability of haploinsufficiency of the gene (from
         /// doi:10.1093/bioinformatics/btx028).
         #[serde(
-            alias = "HIPred_score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -673,7 +730,6 @@ pub mod dbnsfp_gene {
         /// HIPred prediction of haploinsufficiency of the gene. Y(es) or N(o). (from
         /// doi:10.1093/bioinformatics/btx028).
         #[serde(
-            alias = "HIPred",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -681,7 +737,6 @@ pub mod dbnsfp_gene {
         /// A score predicting the gene haploinsufficiency. The higher the score the more likely the
         /// gene is haploinsufficient (from doi: 10.1093/nar/gkv474).
         #[serde(
-            alias = "GHIS",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -689,7 +744,6 @@ pub mod dbnsfp_gene {
         /// Estimated probability that gene is a recessive disease gene (from
         /// DOI:10.1126/science.1215040).
         #[serde(
-            alias = "P(rec)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -698,7 +752,6 @@ pub mod dbnsfp_gene {
         /// seen in homozygous state in at least one 1000G individual" "recessive = known OMIM
         /// recessive disease" (original annotations from DOI:10.1126/science.1215040).
         #[serde(
-            alias = "Known_rec_info",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -707,7 +760,6 @@ pub mod dbnsfp_gene {
         /// higher the score the more tolerant to mutational burden the gene is. Based on EVS
         /// (ESP6500) data.  from doi:10.1371/journal.pgen.1003709.
         #[serde(
-            alias = "RVIS_EVS",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -715,7 +767,6 @@ pub mod dbnsfp_gene {
         /// The percentile rank of the gene based on RVIS, the higher the percentile the more
         /// tolerant to mutational burden the gene is. Based on EVS (ESP6500) data.
         #[serde(
-            alias = "RVIS_percentile_EVS",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -724,7 +775,6 @@ pub mod dbnsfp_gene {
         /// population.  Lower FDR corresponds with genes that are increasingly depleted of LoF
         /// variants." cited from RVIS document.
         #[serde(
-            alias = "LoF-FDR_ExAC",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -732,7 +782,6 @@ pub mod dbnsfp_gene {
         /// "ExAC-based RVIS; setting 'common' MAF filter at 0.05% in at least one of the six
         /// individual ethnic strata from ExAC." cited from RVIS document.
         #[serde(
-            alias = "RVIS_ExAC",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -740,7 +789,6 @@ pub mod dbnsfp_gene {
         /// "Genome-Wide percentile for the new ExAC-based RVIS; setting 'common' MAF filter at 0.05%
         /// in at least one of the six individual ethnic strata from ExAC." cited from RVIS document.
         #[serde(
-            alias = "RVIS_percentile_ExAC",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -748,7 +796,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being loss-of-function intolerant (intolerant of both heterozygous
         /// and homozygous lof variants)" based on ExAC r0.3 data.
         #[serde(
-            alias = "ExAC_pLI",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -756,7 +803,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being intolerant of homozygous, but not heterozygous lof variants"
         /// based on ExAC r0.3 data.
         #[serde(
-            alias = "ExAC_pRec",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -764,7 +810,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being tolerant of both heterozygous and homozygous lof variants"
         /// based on ExAC r0.3 data.
         #[serde(
-            alias = "ExAC_pNull",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -772,7 +817,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being loss-of-function intolerant (intolerant of both heterozygous
         /// and homozygous lof variants)" based on ExAC r0.3 nonTCGA subset.
         #[serde(
-            alias = "ExAC_nonTCGA_pLI",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -780,7 +824,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being intolerant of homozygous, but not heterozygous lof variants"
         /// based on ExAC r0.3 nonTCGA subset.
         #[serde(
-            alias = "ExAC_nonTCGA_pRec",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -788,7 +831,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being tolerant of both heterozygous and homozygous lof variants"
         /// based on ExAC r0.3 nonTCGA subset.
         #[serde(
-            alias = "ExAC_nonTCGA_pNull",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -796,7 +838,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being loss-of-function intolerant (intolerant of both heterozygous
         /// and homozygous lof variants)" based on ExAC r0.3 nonpsych subset.
         #[serde(
-            alias = "ExAC_nonpsych_pLI",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -804,7 +845,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being intolerant of homozygous, but not heterozygous lof variants"
         /// based on ExAC r0.3 nonpsych subset.
         #[serde(
-            alias = "ExAC_nonpsych_pRec",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -812,7 +852,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being tolerant of both heterozygous and homozygous lof variants"
         /// based on ExAC r0.3 nonpsych subset/
         #[serde(
-            alias = "ExAC_nonpsych_pNull",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -820,7 +859,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being loss-of-function intolerant (intolerant of both heterozygous
         /// and homozygous lof variants)" based on gnomAD 2.1 data.
         #[serde(
-            alias = "gnomAD_pLI",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -828,7 +866,6 @@ pub mod dbnsfp_gene {
         /// "the probability of being intolerant of homozygous, but not heterozygous lof variants"
         /// based on gnomAD 2.1 data.
         #[serde(
-            alias = "gnomAD_pRec",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -836,28 +873,24 @@ pub mod dbnsfp_gene {
         /// "the probability of being tolerant of both heterozygous and homozygous lof variants"
         /// based on gnomAD 2.1 data.
         #[serde(
-            alias = "gnomAD_pNull",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub gnomad_pnull: Option<f64>,
         /// "Winsorised deletion intolerance z-score" based on ExAC r0.3.1 CNV data.
         #[serde(
-            alias = "ExAC_del.score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub exac_del_score: Option<f64>,
         /// "Winsorised duplication intolerance z-score" based on ExAC r0.3.1 CNV data.
         #[serde(
-            alias = "ExAC_dup.score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub exac_dup_score: Option<f64>,
         /// "Winsorised cnv intolerance z-score" based on ExAC r0.3.1 CNV data.
         #[serde(
-            alias = "ExAC_cnv.score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -866,7 +899,6 @@ pub mod dbnsfp_gene {
         /// and intolerance scores are more likely to be biased or noisy." from ExAC r0.3.1 CNV
         /// release.
         #[serde(
-            alias = "ExAC_cnv_flag",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -875,28 +907,24 @@ pub mod dbnsfp_gene {
         /// has accumulated in the general population" from doi: 10.1073/pnas.1518646112. The higher
         /// the score the less likely the gene is to be responsible for monogenic diseases.
         #[serde(
-            alias = "GDI",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub gdi: Option<f64>,
         /// Phred-scaled GDI scores.
         #[serde(
-            alias = "GDI-Phred",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub gdi_phred: Option<f64>,
         /// gene damage prediction (low/medium/high) by GDI for all diseases.,
         #[serde(
-            alias = "Gene damage prediction (all disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub gdp_all_disease_causing: Option<String>,
         /// gene damage prediction (low/medium/high) by GDI for all Mendelian diseases.
         #[serde(
-            alias = "Gene damage prediction (all Mendelian disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -904,7 +932,6 @@ pub mod dbnsfp_gene {
         /// gene damage prediction (low/medium/high) by GDI for Mendelian autosomal dominant
         /// diseases.
         #[serde(
-            alias = "Gene damage prediction (Mendelian AD disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -912,7 +939,6 @@ pub mod dbnsfp_gene {
         /// gene damage prediction (low/medium/high) by GDI for Mendelian autosomal recessive
         /// diseases.
         #[serde(
-            alias = "Gene damage prediction (Mendelian AR disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -920,7 +946,6 @@ pub mod dbnsfp_gene {
         /// gene damage prediction (low/medium/high) by GDI for all primary immunodeficiency
         /// diseases.
         #[serde(
-            alias = "Gene damage prediction (all PID disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -928,7 +953,6 @@ pub mod dbnsfp_gene {
         /// gene damage prediction (low/medium/high) by GDI for primary immunodeficiency autosomal
         /// dominant diseases.
         #[serde(
-            alias = "Gene damage prediction (PID AD disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -936,28 +960,24 @@ pub mod dbnsfp_gene {
         /// gene damage prediction (low/medium/high) by GDI for primary immunodeficiency autosomal
         /// recessive diseases.
         #[serde(
-            alias = "Gene damage prediction (PID AR disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub gdp_pid_ar: Option<String>,
         /// gene damage prediction (low/medium/high) by GDI for all cancer disease.
         #[serde(
-            alias = "Gene damage prediction (all cancer disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub gdp_cancer: Option<String>,
         /// gene damage prediction (low/medium/high) by GDI for cancer recessive disease.
         #[serde(
-            alias = "Gene damage prediction (cancer recessive disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub gdb_cancer_rec: Option<String>,
         /// gene damage prediction (low/medium/high) by GDI for cancer dominant disease.
         #[serde(
-            alias = "Gene damage prediction (cancer dominant disease-causing genes)",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -966,7 +986,6 @@ pub mod dbnsfp_gene {
         /// higher gene intolerance to functional change. For details see doi:
         /// 10.1093/bioinformatics/btv602.
         #[serde(
-            alias = "LoFtool_score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -976,7 +995,6 @@ pub mod dbnsfp_gene {
         /// for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants). Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOF_MAF0.005_HetOrHom",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -986,7 +1004,6 @@ pub mod dbnsfp_gene {
         /// method for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants). Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOF_MAF0.005_HomOrCompoundHet",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -996,7 +1013,6 @@ pub mod dbnsfp_gene {
         /// for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants). Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOF_MAF0.001_HetOrHom",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1006,7 +1022,6 @@ pub mod dbnsfp_gene {
         /// method for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants). Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOF_MAF0.001_HomOrCompoundHet",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1016,7 +1031,6 @@ pub mod dbnsfp_gene {
         /// a method for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants).  Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOForMissense_MAF0.005_HetOrHom",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1026,7 +1040,6 @@ pub mod dbnsfp_gene {
         /// fraction is from a method for ranking genes based on mutational burden called SORVA
         /// (Significance Of Rare VAriants).  Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOForMissense_MAF0.005_HomOrCompoundHet",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1036,7 +1049,6 @@ pub mod dbnsfp_gene {
         /// a method for ranking genes based on mutational burden called SORVA (Significance Of Rare
         /// VAriants).  Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOForMissense_MAF0.001_HetOrHom",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1046,7 +1058,6 @@ pub mod dbnsfp_gene {
         /// fraction is from a method for ranking genes based on mutational burden called SORVA
         /// (Significance Of Rare VAriants).  Please see doi: 10.1101/103218 for details.
         #[serde(
-            alias = "SORVA_LOForMissense_MAF0.001_HomOrCompoundHet",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
@@ -1054,7 +1065,6 @@ pub mod dbnsfp_gene {
         /// Essential ("E") or Non-essential phenotype-changing ("N") based on Mouse Genome
         /// Informatics database. from doi:10.1371/journal.pgen.1003484.
         #[serde(
-            alias = "Essential_gene",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -1062,7 +1072,6 @@ pub mod dbnsfp_gene {
         /// Essential ("E") or Non-essential phenotype-changing ("N") based on large scale CRISPR
         /// experiments. from doi: 10.1126/science.aac7041.
         #[serde(
-            alias = "Essential_gene_CRISPR",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -1071,7 +1080,6 @@ pub mod dbnsfp_gene {
         /// ("N") based on large scale CRISPR experiments. from
         /// http://dx.doi.org/10.1016/j.cell.2015.11.015.
         #[serde(
-            alias = "Essential_gene_CRISPR2",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -1080,7 +1088,6 @@ pub mod dbnsfp_gene {
         /// Non-essential phenotype-changing ("N"), based on large scale mutagenesis experiments.
         /// from doi: 10.1126/science.aac7557.
         #[serde(
-            alias = "Essential_gene_gene-trap",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
@@ -1088,60 +1095,158 @@ pub mod dbnsfp_gene {
         /// A probability prediction of the gene being essential. From
         /// doi:10.1371/journal.pcbi.1002886.
         #[serde(
-            alias = "Gene_indispensability_score",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot"
         )]
         pub gene_indispensability_score: Option<f64>,
         /// Essential ("E") or loss-of-function tolerant ("N") based on Gene_indispensability_score.
         #[serde(
-            alias = "Gene_indispensability_pred",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub gene_indispensability_pred: Option<String>,
         /// Homolog mouse gene name from MGI.
         #[serde(
-            alias = "MGI_mouse_gene",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub mgi_mouse_gene: Option<String>,
         /// Phenotype description for the homolog mouse gene from MGI.
         #[serde(
-            alias = "MGI_mouse_phenotype",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub mgi_mouse_phenotype: Option<String>,
         /// Homolog zebrafish gene name from ZFIN.
         #[serde(
-            alias = "ZFIN_zebrafish_gene",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub zfin_zebrafish_gene: Option<String>,
         /// Affected structure of the homolog zebrafish gene from ZFIN.
         #[serde(
-            alias = "ZFIN_zebrafish_structure",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub zfin_zebrafish_structure: Option<String>,
         /// Phenotype description for the homolog zebrafish gene from ZFIN.
         #[serde(
-            alias = "ZFIN_zebrafish_phenotype_quality",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub zfin_zebrafish_phenotype_quality: Option<String>,
         /// Phenotype tag for the homolog zebrafish gene from ZFIN"
         #[serde(
-            alias = "ZFIN_zebrafish_phenotype_tag",
             serialize_with = "serialize_option_dot",
             deserialize_with = "deserialize_option_dot_str"
         )]
         pub zfin_zebrafish_phenotype_tag: Option<String>,
+        /// Values of the columns not mapped to any of the fields above, by column name.
+        #[serde(skip_deserializing, skip_serializing_if = "HashMap::is_empty")]
+        pub extra: HashMap<String, String>,
+    }
+
+    /// Additional column names by record field, e.g., from `--path-dbnsfp-column-map`.
+    ///
+    /// These names are tried before the ones from `DEFAULT_COLUMN_NAMES`.
+    pub type ColumnNames = std::collections::BTreeMap<String, Vec<String>>;
+
+    /// Mapping of the columns of a dbNSFP gene file to the fields of `Record`.
+    #[derive(Debug, Clone)]
+    pub struct ColumnMapping {
+        /// The header with the mapped columns renamed to their fields, the others empty.
+        header: csv::StringRecord,
+        /// Index and name of the columns not mapped to any field.
+        unmapped: Vec<(usize, String)>,
+    }
+
+    impl ColumnMapping {
+        /// Map the columns of `header` to the fields, trying the names from `column_names`
+        /// before the default ones.
+        ///
+        /// Each column is mapped to at most one field.  Fails if there is no gene name column.
+        pub fn new(
+            header: &csv::StringRecord,
+            column_names: &ColumnNames,
+        ) -> Result<Self, anyhow::Error> {
+            let mut fields = vec![None; header.len()];
+            let mut missing = Vec::new();
+            for (field, default_names) in DEFAULT_COLUMN_NAMES {
+                let names = column_names
+                    .get(*field)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .chain(default_names.iter().copied());
+                let index = names
+                    .filter_map(|name| header.iter().position(|column| column == name))
+                    .find(|index| fields[*index].is_none());
+                match index {
+                    Some(index) => fields[index] = Some(*field),
+                    None => missing.push(*field),
+                }
+            }
+            if missing.contains(&"gene_name") {
+                anyhow::bail!(
+                    "no gene name column found in dbNSFP gene header, tried {}",
+                    column_names
+                        .get("gene_name")
+                        .into_iter()
+                        .flatten()
+                        .map(String::as_str)
+                        .chain(DEFAULT_COLUMN_NAMES[0].1.iter().copied())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            if !missing.is_empty() {
+                tracing::info!(
+                    "  dbNSFP gene fields without column: {}",
+                    missing.join(", ")
+                );
+            }
+
+            let unmapped = header
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| fields[*index].is_none())
+                .map(|(index, column)| (index, column.to_string()))
+                .collect::<Vec<_>>();
+            if !unmapped.is_empty() {
+                tracing::warn!(
+                    "  dbNSFP gene columns not mapped to any field, kept as extra: {}",
+                    unmapped
+                        .iter()
+                        .map(|(_, column)| column.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+
+            Ok(Self {
+                header: fields
+                    .into_iter()
+                    .map(|field| field.unwrap_or_default())
+                    .collect(),
+                unmapped,
+            })
+        }
+
+        /// Convert the data row `row` into a record, with the non-empty values of the unmapped
+        /// columns in `Record::extra`.
+        pub fn record(&self, row: &csv::StringRecord) -> Result<Record, csv::Error> {
+            let mut record: Record = row.deserialize(Some(&self.header))?;
+            record.extra = self
+                .unmapped
+                .iter()
+                .filter_map(|(index, column)| {
+                    row.get(*index)
+                        .filter(|value| !value.is_empty() && *value != ".")
+                        .map(|value| (column.clone(), value.to_string()))
+                })
+                .collect();
+            Ok(record)
+        }
     }
 }
 
@@ -1343,6 +1448,15 @@ pub mod gnomad_constraints {
             deserialize_with = "deserialize_option_na"
         )]
         pub exac_oe_lof: Option<f64>,
+        /// The Ensembl transcript ID, only for transcript-level rows.
+        #[serde(default)]
+        pub transcript_id: Option<String>,
+        /// Whether the transcript is the canonical one, only for transcript-level rows.
+        #[serde(default)]
+        pub canonical: Option<bool>,
+        /// Whether the transcript is the MANE Select one, only for transcript-level rows.
+        #[serde(default)]
+        pub mane_select: Option<bool>,
     }
 }
 
@@ -2721,21 +2835,102 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn deserialize_dbnsfp_record() -> Result<(), anyhow::Error> {
-        let path_tsv = "tests/genes/dbnsfp/genes.tsv";
+    /// Read the dbNSFP gene records from `path_tsv` with the column mapping.
+    fn read_dbnsfp_records(
+        path_tsv: &str,
+        column_names: &dbnsfp_gene::ColumnNames,
+    ) -> Result<Vec<dbnsfp_gene::Record>, anyhow::Error> {
         let str_tsv = std::fs::read_to_string(path_tsv)?;
         let mut rdr = csv::ReaderBuilder::new()
             .delimiter(b'\t')
             .has_headers(true)
             .from_reader(str_tsv.as_bytes());
-        let records = rdr
-            .deserialize()
-            .collect::<Result<Vec<dbnsfp_gene::Record>, csv::Error>>()?;
+        let mapping = dbnsfp_gene::ColumnMapping::new(rdr.headers()?, column_names)?;
+        Ok(rdr
+            .records()
+            .map(|row| mapping.record(&row?))
+            .collect::<Result<Vec<_>, csv::Error>>()?)
+    }
+
+    #[test]
+    fn deserialize_dbnsfp_record() -> Result<(), anyhow::Error> {
+        let records = read_dbnsfp_records("tests/genes/dbnsfp/genes.tsv", &Default::default())?;
         insta::assert_yaml_snapshot!(records);
         Ok(())
     }
 
+    #[test]
+    fn deserialize_dbnsfp_record_4_7() -> Result<(), anyhow::Error> {
+        let records_4_4 = read_dbnsfp_records("tests/genes/dbnsfp/genes.tsv", &Default::default())?;
+        let records_4_7 = read_dbnsfp_records(
+            "tests/genes/dbnsfp/genes-4.7-excerpt.tsv",
+            &Default::default(),
+        )?;
+
+        assert_eq!(records_4_7.len(), 2);
+        for (record_4_4, record_4_7) in records_4_4.iter().zip(records_4_7.iter()) {
+            assert_eq!(record_4_7.gene_name, record_4_4.gene_name);
+            assert_eq!(record_4_7.uniprot_acc, record_4_4.uniprot_acc);
+            assert_eq!(record_4_7.uniprot_id, record_4_4.uniprot_id);
+            assert_eq!(record_4_7.gnomad_pli, record_4_4.gnomad_pli);
+            assert_eq!(
+                record_4_7.zfin_zebrafish_phenotype_tag,
+                record_4_4.zfin_zebrafish_phenotype_tag
+            );
+            assert!(record_4_4.extra.is_empty());
+        }
+        assert!(records_4_4[0].pathway_biocarta_short.is_some());
+        assert_eq!(records_4_7[0].pathway_biocarta_short, None);
+        assert_eq!(
+            records_4_7[0].extra,
+            [
+                (String::from("gnomAD_v4_pLI"), String::from("0.99")),
+                (String::from("gnomAD_v4_LOEUF"), String::from("0.24")),
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(
+            records_4_7[1].extra,
+            [(String::from("gnomAD_v4_pLI"), String::from("0.96"))]
+                .into_iter()
+                .collect()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_dbnsfp_record_column_names() -> Result<(), anyhow::Error> {
+        let column_names = [(
+            String::from("gnomad_pli"),
+            vec![String::from("gnomAD_v4_pLI")],
+        )]
+        .into_iter()
+        .collect();
+        let records =
+            read_dbnsfp_records("tests/genes/dbnsfp/genes-4.7-excerpt.tsv", &column_names)?;
+
+        assert_eq!(records[0].gnomad_pli, Some(0.99));
+        assert_eq!(
+            records[0].extra.get("gnomAD_pLI").map(String::as_str),
+            Some("9.9300e-01")
+        );
+        assert!(!records[0].extra.contains_key("gnomAD_v4_pLI"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dbnsfp_column_mapping_without_gene_name() {
+        let header = csv::StringRecord::from(vec!["Symbol", "Ensembl_gene"]);
+        let err = dbnsfp_gene::ColumnMapping::new(&header, &Default::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no gene name column found in dbNSFP gene header, tried Gene_name"
+        );
+    }
+
     #[test]
     fn deserialize_hgnc_record() -> Result<(), anyhow::Error> {
         let path_json = "tests/genes/hgnc/hgnc_info.jsonl";
@@ -2753,6 +2948,7 @@ mod tests {
     #[rstest::rstest]
     #[case::gnomad_v2("2.1")]
     #[case::gnomad_v4("4.0")]
+    #[case::gnomad_v4_1("4.1")]
     fn deserialize_gnomad_constraints(
         #[case] gnomad_constraints_version: &str,
     ) -> Result<(), anyhow::Error> {
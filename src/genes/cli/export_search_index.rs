@@ -0,0 +1,182 @@
+//! Export of the `/genes/search` index to a static JSON file.
+//!
+//! The file contains the same gene names that the server keeps in memory for `/genes/search`
+//! and is built with the same code such that client-side search is consistent with the
+//! server.  It is a JSON object with a `header` and the `genes` array, gzip-compressed if
+//! the output path ends in `.gz`.
+
+use std::io::Write;
+
+use crate::{
+    common,
+    server::run::{extract_gene_names, versions::schema::VersionSpec, GeneNames},
+};
+
+/// Command line arguments for `gene export-search-index` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "export gene search index to static JSON", long_about = None)]
+pub struct Args {
+    /// Path to RocksDB directory with data.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Path to the output JSON file, gzip-compressed if ending in `.gz`.
+    #[arg(long)]
+    pub path_out: String,
+}
+
+/// Header of the search index.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SearchIndexHeader {
+    /// Version of annonars that wrote the index.
+    pub annonars_version: String,
+    /// Version of the genes database from its `spec.yaml`, if any.
+    pub db_version: Option<String>,
+    /// Version of annonars that built the genes database, if known.
+    pub builder_version: Option<String>,
+    /// Number of genes in the index.
+    pub count: usize,
+}
+
+/// The search index as written to the output file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchIndex {
+    /// The header.
+    pub header: SearchIndexHeader,
+    /// The gene names as used by `/genes/search`.
+    pub genes: Vec<GeneNames>,
+}
+
+/// Build the search index of the genes database `db` at `path_rocksdb`.
+pub fn build(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    path_rocksdb: &str,
+) -> Result<SearchIndex, anyhow::Error> {
+    let genes = extract_gene_names(db)?;
+    let path_spec = common::spec::spec_yaml_path(path_rocksdb);
+    let db_version = path_spec
+        .exists()
+        .then(|| VersionSpec::from_path(&path_spec))
+        .transpose()?
+        .map(|spec| spec.version);
+    let cf_meta = db.cf_handle("meta").unwrap();
+    let builder_version = db
+        .get_cf(&cf_meta, "builder-version")?
+        .map(|value| String::from_utf8_lossy(&value).to_string());
+    Ok(SearchIndex {
+        header: SearchIndexHeader {
+            annonars_version: crate::VERSION.to_string(),
+            db_version,
+            builder_version,
+            count: genes.len(),
+        },
+        genes,
+    })
+}
+
+/// Main entry point for `gene export-search-index` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'gene export-search-index' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let db = super::query::open_rocksdb(&args.path_rocksdb, "genes", "meta")?;
+
+    tracing::info!("Exporting to {} ...", &args.path_out);
+    let before_export = std::time::Instant::now();
+    let index = build(&db, &args.path_rocksdb)?;
+    let file = std::fs::File::create(&args.path_out)
+        .map_err(|e| anyhow::anyhow!("problem creating {}: {}", &args.path_out, e))?;
+    let writer = std::io::BufWriter::new(file);
+    if args.path_out.ends_with(".gz") {
+        let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+        serde_json::to_writer(&mut encoder, &index)?;
+        encoder.finish()?.flush()?;
+    } else {
+        let mut writer = writer;
+        serde_json::to_writer(&mut writer, &index)?;
+        writer.flush()?;
+    }
+    tracing::info!(
+        "... done exporting {} genes in {:?}",
+        index.header.count,
+        before_export.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use std::io::Read as _;
+    use temp_testdir::TempDir;
+
+    #[rstest::rstest]
+    #[case("index.json")]
+    #[case("index.json.gz")]
+    fn export(#[case] file_name: &str) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = format!("{}", tmp_dir.join("genes").join("rocksdb").display());
+        std::fs::create_dir_all(tmp_dir.join("genes"))?;
+        crate::genes::cli::import::run(
+            &common,
+            &crate::genes::cli::import::Args {
+                path_in_clingen_regions_37: None,
+                spec: common::spec::ArgsSpec {
+                    spec_data_version: Some(String::from("20240105")),
+                    ..Default::default()
+                },
+                ..crate::genes::cli::import::test::example_args(&path_rocksdb)
+            },
+        )?;
+
+        let path_out = format!("{}", tmp_dir.join(file_name).display());
+        run(
+            &common,
+            &Args {
+                path_rocksdb,
+                path_out: path_out.clone(),
+            },
+        )?;
+
+        let mut json = String::new();
+        let file = std::fs::File::open(&path_out)?;
+        if file_name.ends_with(".gz") {
+            flate2::read::GzDecoder::new(file).read_to_string(&mut json)?;
+        } else {
+            std::io::BufReader::new(file).read_to_string(&mut json)?;
+        }
+        let index: SearchIndex = serde_json::from_str(&json)?;
+
+        assert_eq!(index.header.count, 3);
+        assert_eq!(index.genes.len(), 3);
+        assert_eq!(
+            index.header.db_version,
+            Some(format!("20240105+{}", common::version()))
+        );
+        assert_eq!(
+            index.header.builder_version.as_deref(),
+            Some(common::version())
+        );
+        let braf = index
+            .genes
+            .iter()
+            .find(|gene| gene.hgnc_id == "HGNC:1097")
+            .expect("BRAF in index");
+        assert_eq!(braf.symbol, "BRAF");
+        assert_eq!(
+            braf.ncbi_gene_id.as_ref().map(|id| id.as_str()),
+            Some("673")
+        );
+
+        Ok(())
+    }
+}
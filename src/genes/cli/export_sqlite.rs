@@ -0,0 +1,370 @@
+//! Export of gene annotation data to SQLite.
+//!
+//! Writes the main per-gene scalar fields into one table per data source and the repeated
+//! data into child tables, all keyed by `hgnc_id`.  Column names follow the protobuf field
+//! names.
+
+use prost::Message;
+use rusqlite::params;
+
+use crate::{common, pbs::genes};
+
+/// Command line arguments for `gene export-sqlite` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "export gene information data RocksDB to SQLite", long_about = None)]
+pub struct Args {
+    /// Path to RocksDB directory with data.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Name of the column family to export from.
+    #[arg(long, default_value = "genes")]
+    pub cf_name: String,
+    /// Path to the output SQLite file, must not exist yet.
+    #[arg(long)]
+    pub path_out: String,
+}
+
+/// SQL for creating the tables.
+const CREATE_TABLES: &str = r#"
+CREATE TABLE hgnc (
+    hgnc_id TEXT PRIMARY KEY NOT NULL,
+    symbol TEXT NOT NULL,
+    name TEXT NOT NULL,
+    locus_group TEXT,
+    locus_type TEXT,
+    location TEXT,
+    entrez_id TEXT,
+    ensembl_gene_id TEXT,
+    ucsc_id TEXT,
+    cosmic TEXT,
+    agr TEXT
+);
+CREATE TABLE gnomad_constraints (
+    hgnc_id TEXT PRIMARY KEY NOT NULL REFERENCES hgnc (hgnc_id),
+    ensembl_gene_id TEXT NOT NULL,
+    entrez_id TEXT NOT NULL,
+    gene_symbol TEXT NOT NULL,
+    exp_lof REAL,
+    exp_mis REAL,
+    exp_syn REAL,
+    mis_z REAL,
+    obs_lof INTEGER,
+    obs_mis INTEGER,
+    obs_syn INTEGER,
+    oe_lof REAL,
+    oe_lof_lower REAL,
+    oe_lof_upper REAL,
+    oe_mis REAL,
+    oe_mis_lower REAL,
+    oe_mis_upper REAL,
+    oe_syn REAL,
+    oe_syn_lower REAL,
+    oe_syn_upper REAL,
+    pli REAL,
+    syn_z REAL,
+    exac_pli REAL,
+    exac_obs_lof REAL,
+    exac_exp_lof REAL,
+    exac_oe_lof REAL
+);
+CREATE TABLE rcnv (
+    hgnc_id TEXT PRIMARY KEY NOT NULL REFERENCES hgnc (hgnc_id),
+    p_haplo REAL NOT NULL,
+    p_triplo REAL NOT NULL
+);
+CREATE TABLE shet (
+    hgnc_id TEXT PRIMARY KEY NOT NULL REFERENCES hgnc (hgnc_id),
+    s_het REAL NOT NULL
+);
+CREATE TABLE domino (
+    hgnc_id TEXT PRIMARY KEY NOT NULL REFERENCES hgnc (hgnc_id),
+    gene_symbol TEXT NOT NULL,
+    score REAL NOT NULL
+);
+CREATE TABLE decipher_hi (
+    hgnc_id TEXT PRIMARY KEY NOT NULL REFERENCES hgnc (hgnc_id),
+    hgnc_symbol TEXT NOT NULL,
+    p_hi REAL NOT NULL,
+    hi_index REAL NOT NULL
+);
+CREATE TABLE omim_diseases (
+    hgnc_id TEXT NOT NULL REFERENCES hgnc (hgnc_id),
+    omim_id TEXT NOT NULL,
+    label TEXT NOT NULL
+);
+CREATE INDEX omim_diseases_hgnc_id ON omim_diseases (hgnc_id);
+CREATE TABLE orpha_diseases (
+    hgnc_id TEXT NOT NULL REFERENCES hgnc (hgnc_id),
+    orpha_id TEXT NOT NULL,
+    label TEXT NOT NULL
+);
+CREATE INDEX orpha_diseases_hgnc_id ON orpha_diseases (hgnc_id);
+CREATE TABLE panelapp (
+    hgnc_id TEXT NOT NULL REFERENCES hgnc (hgnc_id),
+    entity_type TEXT NOT NULL,
+    entity_name TEXT NOT NULL,
+    confidence_level TEXT NOT NULL,
+    penetrance TEXT NOT NULL,
+    mode_of_inheritance TEXT NOT NULL,
+    panel_id INTEGER,
+    panel_name TEXT,
+    panel_version TEXT
+);
+CREATE INDEX panelapp_hgnc_id ON panelapp (hgnc_id);
+"#;
+
+/// Prepared insert statements for all tables.
+struct Inserts<'conn> {
+    hgnc: rusqlite::Statement<'conn>,
+    gnomad_constraints: rusqlite::Statement<'conn>,
+    rcnv: rusqlite::Statement<'conn>,
+    shet: rusqlite::Statement<'conn>,
+    domino: rusqlite::Statement<'conn>,
+    decipher_hi: rusqlite::Statement<'conn>,
+    omim_diseases: rusqlite::Statement<'conn>,
+    orpha_diseases: rusqlite::Statement<'conn>,
+    panelapp: rusqlite::Statement<'conn>,
+}
+
+impl<'conn> Inserts<'conn> {
+    /// Prepare the statements in the transaction `tx`.
+    fn prepare(tx: &'conn rusqlite::Transaction) -> Result<Self, rusqlite::Error> {
+        Ok(Self {
+            hgnc: tx.prepare(
+                "INSERT INTO hgnc VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?,
+            gnomad_constraints: tx.prepare(
+                "INSERT INTO gnomad_constraints VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, \
+                 ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, \
+                 ?25, ?26)",
+            )?,
+            rcnv: tx.prepare("INSERT INTO rcnv VALUES (?1, ?2, ?3)")?,
+            shet: tx.prepare("INSERT INTO shet VALUES (?1, ?2)")?,
+            domino: tx.prepare("INSERT INTO domino VALUES (?1, ?2, ?3)")?,
+            decipher_hi: tx.prepare("INSERT INTO decipher_hi VALUES (?1, ?2, ?3, ?4)")?,
+            omim_diseases: tx.prepare("INSERT INTO omim_diseases VALUES (?1, ?2, ?3)")?,
+            orpha_diseases: tx.prepare("INSERT INTO orpha_diseases VALUES (?1, ?2, ?3)")?,
+            panelapp: tx
+                .prepare("INSERT INTO panelapp VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?,
+        })
+    }
+
+    /// Insert the rows for one gene record.
+    fn insert(&mut self, record: &genes::base::Record) -> Result<(), rusqlite::Error> {
+        let Some(hgnc) = record.hgnc.as_ref() else {
+            return Ok(());
+        };
+        let hgnc_id = &hgnc.hgnc_id;
+
+        self.hgnc.execute(params![
+            hgnc_id,
+            hgnc.symbol,
+            hgnc.name,
+            hgnc.locus_group,
+            hgnc.locus_type,
+            hgnc.location,
+            hgnc.entrez_id,
+            hgnc.ensembl_gene_id,
+            hgnc.ucsc_id,
+            hgnc.cosmic,
+            hgnc.agr,
+        ])?;
+        if let Some(c) = record.gnomad_constraints.as_ref() {
+            self.gnomad_constraints.execute(params![
+                hgnc_id,
+                c.ensembl_gene_id,
+                c.entrez_id,
+                c.gene_symbol,
+                c.exp_lof,
+                c.exp_mis,
+                c.exp_syn,
+                c.mis_z,
+                c.obs_lof,
+                c.obs_mis,
+                c.obs_syn,
+                c.oe_lof,
+                c.oe_lof_lower,
+                c.oe_lof_upper,
+                c.oe_mis,
+                c.oe_mis_lower,
+                c.oe_mis_upper,
+                c.oe_syn,
+                c.oe_syn_lower,
+                c.oe_syn_upper,
+                c.pli,
+                c.syn_z,
+                c.exac_pli,
+                c.exac_obs_lof,
+                c.exac_exp_lof,
+                c.exac_oe_lof,
+            ])?;
+        }
+        if let Some(rcnv) = record.rcnv.as_ref() {
+            self.rcnv
+                .execute(params![hgnc_id, rcnv.p_haplo, rcnv.p_triplo])?;
+        }
+        if let Some(shet) = record.shet.as_ref() {
+            self.shet.execute(params![hgnc_id, shet.s_het])?;
+        }
+        if let Some(domino) = record.domino.as_ref() {
+            self.domino
+                .execute(params![hgnc_id, domino.gene_symbol, domino.score])?;
+        }
+        if let Some(decipher_hi) = record.decipher_hi.as_ref() {
+            self.decipher_hi.execute(params![
+                hgnc_id,
+                decipher_hi.hgnc_symbol,
+                decipher_hi.p_hi,
+                decipher_hi.hi_index,
+            ])?;
+        }
+        for term in record
+            .omim
+            .iter()
+            .flat_map(|omim| omim.omim_diseases.iter())
+        {
+            self.omim_diseases
+                .execute(params![hgnc_id, term.omim_id, term.label])?;
+        }
+        for term in record
+            .orpha
+            .iter()
+            .flat_map(|orpha| orpha.orpha_diseases.iter())
+        {
+            self.orpha_diseases
+                .execute(params![hgnc_id, term.orpha_id, term.label])?;
+        }
+        for panelapp in &record.panelapp {
+            let panel = panelapp.panel.as_ref();
+            self.panelapp.execute(params![
+                hgnc_id,
+                panelapp.entity_type().as_str_name(),
+                panelapp.entity_name,
+                panelapp.confidence_level().as_str_name(),
+                panelapp.penetrance().as_str_name(),
+                panelapp.mode_of_inheritance,
+                panel.map(|panel| panel.id),
+                panel.map(|panel| panel.name.clone()),
+                panel.map(|panel| panel.version.clone()),
+            ])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Export all gene records from `db` into a new SQLite database at `path_out`.
+pub fn export(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    path_out: &str,
+) -> Result<usize, anyhow::Error> {
+    if std::path::Path::new(path_out).exists() {
+        anyhow::bail!("output file {} already exists", path_out);
+    }
+    let mut conn = rusqlite::Connection::open(path_out)
+        .map_err(|e| anyhow::anyhow!("problem creating SQLite file {}: {}", path_out, e))?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(CREATE_TABLES)
+        .map_err(|e| anyhow::anyhow!("problem creating tables: {}", e))?;
+
+    let cf_data = db
+        .cf_handle(cf_name)
+        .ok_or_else(|| anyhow::anyhow!("column family {} not found", cf_name))?;
+    let tx = conn.transaction()?;
+    let mut count = 0;
+    {
+        let mut inserts = Inserts::prepare(&tx)?;
+        let mut iter = db.raw_iterator_cf(&cf_data);
+        iter.seek(b"");
+        while iter.valid() {
+            if let Some(iter_value) = iter.value() {
+                let record = genes::base::Record::decode(std::io::Cursor::new(iter_value))?;
+                if record.hgnc.is_none() {
+                    tracing::warn!(
+                        "skipping record without HGNC information for key {:?}",
+                        iter.key().map(String::from_utf8_lossy)
+                    );
+                } else {
+                    inserts.insert(&record).map_err(|e| {
+                        anyhow::anyhow!("problem inserting record {:?}: {}", &record.hgnc, e)
+                    })?;
+                    count += 1;
+                }
+            }
+            iter.next();
+        }
+    }
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// Implementation of `gene export-sqlite` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'gene export-sqlite' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let db = super::query::open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")?;
+
+    tracing::info!("Exporting to {} ...", &args.path_out);
+    let before_export = std::time::Instant::now();
+    let count = export(&db, &args.cf_name, &args.path_out)?;
+    tracing::info!(
+        "... done exporting {} genes in {:?}",
+        count,
+        before_export.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    #[test]
+    fn smoke_test_export() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = format!("{}", tmp_dir.join("rocksdb").display());
+        let import_args = crate::genes::cli::import::Args {
+            path_in_clingen_regions_37: None,
+            ..crate::genes::cli::import::test::example_args(&path_rocksdb)
+        };
+        crate::genes::cli::import::run(&common, &import_args)?;
+
+        let path_out = format!("{}", tmp_dir.join("genes.sqlite").display());
+        let args = Args {
+            path_rocksdb,
+            cf_name: String::from("genes"),
+            path_out: path_out.clone(),
+        };
+        run(&common, &args)?;
+
+        let conn = rusqlite::Connection::open(&path_out)?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM hgnc", [], |row| row.get(0))?;
+        assert_eq!(count, 3);
+        let symbol: String = conn.query_row(
+            "SELECT symbol FROM hgnc WHERE hgnc_id = 'HGNC:1097'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(symbol, "BRAF");
+        let violations: i64 =
+            conn.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| {
+                row.get(0)
+            })?;
+        assert_eq!(violations, 0);
+
+        Ok(())
+    }
+}
@@ -6,6 +6,10 @@ use std::{
     time::Instant,
 };
 
+use bio::{
+    bio_types::genome::AbstractInterval as _,
+    data_structures::interval_tree::ArrayBackedIntervalTree,
+};
 use clap::Parser;
 use indicatif::ProgressIterator;
 use prost::Message;
@@ -13,8 +17,9 @@ use tracing::info;
 
 use crate::{
     common::{self, version},
-    genes::cli::data::conditions,
+    genes::{cli::data::conditions, diseases},
     pbs::{self, genes::base::ConditionsRecord, genes::base::PanelAppRecord},
+    regions::cli::import::clingen::{self as clingen_region, genomic_location_to_interval},
 };
 
 use super::data::{
@@ -74,10 +79,186 @@ pub struct Args {
     /// Path to the conditions HGNC file.
     #[arg(long, required = true)]
     pub path_in_conditions: String,
+    /// Optional path to the TSV file with ClinGen region curations for GRCh37.
+    #[arg(long)]
+    pub path_in_clingen_regions_37: Option<String>,
+    /// Optional path to the TSV file with ClinGen region curations for GRCh38.
+    #[arg(long)]
+    pub path_in_clingen_regions_38: Option<String>,
+    /// Optional path to a YAML file mapping source names (see `SOURCE_NAMES`) to the versions
+    /// of the input files, e.g., `panelapp: "2024-01-05"`.
+    #[arg(long)]
+    pub path_source_versions: Option<String>,
+    /// Optional path to a YAML file mapping dbNSFP gene record fields to lists of column
+    /// names to try before the default ones, e.g., `uniprot_acc: ["Uniprot_acc"]`.
+    #[arg(long)]
+    pub path_dbnsfp_column_map: Option<String>,
 
     /// Path to output RocksDB.
     #[arg(long, required = true)]
     pub path_out_rocksdb: String,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Names of the data sources for `--path-source-versions`.
+pub const SOURCE_NAMES: &[&str] = &[
+    "acmg",
+    "clingen",
+    "clingen_regions",
+    "conditions",
+    "dbnsfp",
+    "decipher_hi",
+    "domino",
+    "gnomad_constraints",
+    "gtex",
+    "hgnc",
+    "ncbi",
+    "omim",
+    "orpha",
+    "panelapp",
+    "rcnv",
+    "shet",
+];
+
+/// Prefix of the meta values with the version of each data source.
+pub const META_SOURCE_VERSION_PREFIX: &str = "source-version:";
+
+/// Return the name of the column family with the positional index of the genes on
+/// `genome_release`, see `common::pos_index`.
+///
+/// Only genes with a ClinGen gene curation have coordinates and are thus indexed.
+pub fn cf_name_pos_index(genome_release: common::cli::GenomeRelease) -> &'static str {
+    match genome_release {
+        common::cli::GenomeRelease::Grch37 => "genes_by_pos_grch37",
+        common::cli::GenomeRelease::Grch38 => "genes_by_pos_grch38",
+    }
+}
+
+/// Write the location of `record` on each genome release to the positional indices.
+fn put_pos_index(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    record: &pbs::genes::base::Record,
+) -> Result<(), anyhow::Error> {
+    let (Some(hgnc), Some(clingen)) = (record.hgnc.as_ref(), record.clingen.as_ref()) else {
+        return Ok(());
+    };
+    for genome_release in [
+        common::cli::GenomeRelease::Grch37,
+        common::cli::GenomeRelease::Grch38,
+    ] {
+        match clingen.get_interval(genome_release.into()) {
+            Ok(interval) => {
+                let cf_pos_index = db.cf_handle(cf_name_pos_index(genome_release)).unwrap();
+                common::pos_index::put(
+                    db,
+                    &cf_pos_index,
+                    &common::cli::canonicalize(interval.contig()),
+                    interval.range().start as i32 + 1,
+                    interval.range().end as i32,
+                    &hgnc.hgnc_id,
+                )?;
+            }
+            Err(e) => tracing::debug!(
+                "no {} location for gene {}: {}",
+                genome_release,
+                &hgnc.hgnc_id,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Load the data source versions from the YAML file at `path`.
+pub fn load_source_versions(
+    path: &str,
+) -> Result<std::collections::BTreeMap<String, String>, anyhow::Error> {
+    info!("  loading source versions from {}", path);
+    let yaml_str = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+    let source_versions: std::collections::BTreeMap<String, String> =
+        serde_yaml::from_str(&yaml_str)
+            .map_err(|e| anyhow::anyhow!("problem parsing {}: {}", path, e))?;
+    for name in source_versions.keys() {
+        if !SOURCE_NAMES.contains(&name.as_str()) {
+            anyhow::bail!(
+                "unknown source {} in {}, expected one of {}",
+                name,
+                path,
+                SOURCE_NAMES.join(", ")
+            );
+        }
+    }
+    Ok(source_versions)
+}
+
+/// Load the additional dbNSFP gene column names from the YAML file at `path`.
+pub fn load_dbnsfp_column_names(path: &str) -> Result<dbnsfp_gene::ColumnNames, anyhow::Error> {
+    info!("  loading dbNSFP gene column names from {}", path);
+    let yaml_str = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+    let column_names: dbnsfp_gene::ColumnNames = serde_yaml::from_str(&yaml_str)
+        .map_err(|e| anyhow::anyhow!("problem parsing {}: {}", path, e))?;
+    for field in column_names.keys() {
+        if !dbnsfp_gene::DEFAULT_COLUMN_NAMES
+            .iter()
+            .any(|(name, _)| name == field)
+        {
+            anyhow::bail!("unknown dbNSFP gene field {} in {}", field, path);
+        }
+    }
+    Ok(column_names)
+}
+
+/// Read the data source versions from the meta column family of the genes database.
+///
+/// Databases built by older versions do not have the source versions.
+pub fn read_source_versions(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<std::collections::BTreeMap<String, String>, anyhow::Error> {
+    let cf_meta = db
+        .cf_handle("meta")
+        .ok_or_else(|| anyhow::anyhow!("no column family meta"))?;
+    let mut result = std::collections::BTreeMap::new();
+    let mut iter = db.raw_iterator_cf(&cf_meta);
+    iter.seek(META_SOURCE_VERSION_PREFIX.as_bytes());
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        let Some(name) = key.strip_prefix(META_SOURCE_VERSION_PREFIX.as_bytes()) else {
+            break;
+        };
+        result.insert(
+            String::from_utf8(name.to_vec())?,
+            String::from_utf8(value.to_vec())?,
+        );
+        iter.next();
+    }
+    iter.status()?;
+    Ok(result)
+}
+
+/// Return the `(disease ID, HGNC ID)` pairs of the OMIM and Orphanet disease associations.
+fn disease_associations<'a>(
+    omim_by_hgnc_id: &'a HashMap<String, omim::Record>,
+    orpha_by_hgnc_id: &'a HashMap<String, orpha::Record>,
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    let omim = omim_by_hgnc_id.values().flat_map(|record| {
+        record
+            .diseases
+            .iter()
+            .map(|term| (term.omim_id.as_str(), record.hgnc_id.as_str()))
+    });
+    let orpha = orpha_by_hgnc_id.values().flat_map(|record| {
+        record
+            .diseases
+            .iter()
+            .map(|term| (term.orpha_id.as_str(), record.hgnc_id.as_str()))
+    });
+    omim.chain(orpha)
 }
 
 /// Load ACMG SF list.
@@ -136,14 +317,17 @@ fn load_clingen(path: &str) -> Result<HashMap<String, clingen_gene::Gene>, anyho
 
 /// Load gnomAD constraints.
 ///
+/// Gene-level files have one row per gene, transcript-level files (gnomAD v4) have one row
+/// per transcript.
+///
 /// # Result
 ///
-/// A map from ENSEMBL gene ID to gnomAD constraints record.
+/// A map from ENSEMBL gene ID to the gnomAD constraints records of the gene.
 fn load_gnomad_constraints(
     path: &str,
-) -> Result<HashMap<String, gnomad_constraints::Record>, anyhow::Error> {
+) -> Result<HashMap<String, Vec<gnomad_constraints::Record>>, anyhow::Error> {
     info!("  loading gnomAD constraints from {}", path);
-    let mut result = HashMap::new();
+    let mut result: HashMap<String, Vec<gnomad_constraints::Record>> = HashMap::new();
 
     let mut reader = csv::ReaderBuilder::new()
         .delimiter(b'\t')
@@ -151,12 +335,33 @@ fn load_gnomad_constraints(
         .from_path(path)?;
     for record in reader.deserialize::<gnomad_constraints::Record>() {
         let record = record?;
-        result.insert(record.ensembl_gene_id.clone(), record);
+        result
+            .entry(record.ensembl_gene_id.clone())
+            .or_default()
+            .push(record);
     }
 
     Ok(result)
 }
 
+/// Select the gnomAD constraints record with the gene-level values.
+///
+/// For transcript-level input, this is the canonical transcript, falling back to the MANE
+/// Select transcript and then to the first row.
+fn gene_level_gnomad_constraints(
+    records: &[gnomad_constraints::Record],
+) -> Option<&gnomad_constraints::Record> {
+    records
+        .iter()
+        .find(|record| record.canonical == Some(true))
+        .or_else(|| {
+            records
+                .iter()
+                .find(|record| record.mane_select == Some(true))
+        })
+        .or_else(|| records.first())
+}
+
 /// Load DECIPHER HI predictions.
 ///
 /// # Result
@@ -190,7 +395,10 @@ fn load_decipher_hi(path: &str) -> Result<HashMap<String, decipher_hi::Record>,
 /// # Result
 ///
 /// A map from HGNC gene symbol to dbNSFP gene information.
-fn load_dbnsfp(path: &str) -> Result<HashMap<String, dbnsfp_gene::Record>, anyhow::Error> {
+fn load_dbnsfp(
+    path: &str,
+    column_names: &dbnsfp_gene::ColumnNames,
+) -> Result<HashMap<String, dbnsfp_gene::Record>, anyhow::Error> {
     info!("  loading dbNSFP gene information from {}", path);
     let mut result = HashMap::new();
 
@@ -205,8 +413,9 @@ fn load_dbnsfp(path: &str) -> Result<HashMap<String, dbnsfp_gene::Record>, anyho
     let mut reader = csv::ReaderBuilder::new()
         .delimiter(b'\t')
         .from_reader(reader);
-    for record in reader.deserialize::<dbnsfp_gene::Record>() {
-        let record = record?;
+    let mapping = dbnsfp_gene::ColumnMapping::new(reader.headers()?, column_names)?;
+    for row in reader.records() {
+        let record = mapping.record(&row?)?;
         result.insert(record.gene_name.clone(), record);
     }
 
@@ -469,6 +678,83 @@ fn load_conditions(path: &str) -> Result<HashMap<String, conditions::Record>, an
     Ok(result)
 }
 
+/// Per-chromosome interval trees of ClinGen dosage sensitivity regions.
+///
+/// Used for annotating the genes with the overlapping regions; the chromosome names are
+/// canonicalized such that `chr1` and `1` are treated the same.
+#[derive(Debug, Default)]
+struct ClingenRegionIndex {
+    /// Per-chromosome interval trees with indices into `regions`.
+    trees: HashMap<String, ArrayBackedIntervalTree<u64, usize>>,
+    /// The regions.
+    regions: Vec<pbs::genes::base::ClingenRegionOverlap>,
+}
+
+impl ClingenRegionIndex {
+    /// Load the ClinGen region curations from `path`, if any.
+    fn load(path: Option<&str>) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        let Some(path) = path else {
+            return Ok(result);
+        };
+
+        for (_, region) in clingen_region::load_clingen(path)? {
+            let interval = genomic_location_to_interval(&region.genomic_location)?;
+            let haploinsufficiency_score =
+                clingen_gene::Score::try_from(region.haploinsufficiency_score).map_err(|e| {
+                    anyhow::anyhow!("problem parsing haplosensitivity score: {}", e)
+                })?;
+            let triplosensitivity_score =
+                clingen_gene::Score::try_from(region.triplosensitivity_score).map_err(|e| {
+                    anyhow::anyhow!("problem parsing triplosensitivity score: {}", e)
+                })?;
+            result
+                .trees
+                .entry(common::cli::canonicalize(interval.contig()))
+                .or_default()
+                .insert(interval.range(), result.regions.len());
+            result.regions.push(pbs::genes::base::ClingenRegionOverlap {
+                isca_id: region.isca_id,
+                isca_region_name: region.isca_region_name,
+                genomic_location: region.genomic_location,
+                haploinsufficiency_score: Into::<pbs::genes::base::ClingenDosageScore>::into(
+                    haploinsufficiency_score,
+                ) as i32,
+                triplosensitivity_score: Into::<pbs::genes::base::ClingenDosageScore>::into(
+                    triplosensitivity_score,
+                ) as i32,
+            });
+        }
+        for tree in result.trees.values_mut() {
+            tree.index();
+        }
+
+        Ok(result)
+    }
+
+    /// Return the regions overlapping `genomic_location`, sorted by ISCA ID.
+    ///
+    /// Genes with an invalid genomic location (e.g., "tbd") have no overlaps.
+    fn overlaps(&self, genomic_location: &str) -> Vec<pbs::genes::base::ClingenRegionOverlap> {
+        let Ok(interval) = genomic_location_to_interval(genomic_location) else {
+            return Vec::default();
+        };
+        let Some(tree) = self
+            .trees
+            .get(&common::cli::canonicalize(interval.contig()))
+        else {
+            return Vec::default();
+        };
+        let mut result = tree
+            .find(interval.range())
+            .iter()
+            .map(|entry| self.regions[*entry.data()].clone())
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| a.isca_id.cmp(&b.isca_id));
+        result
+    }
+}
+
 /// Convert from `data::*` records to protobuf records.
 fn convert_record(record: data::Record) -> pbs::genes::base::Record {
     let data::Record {
@@ -477,6 +763,7 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
         clingen_38,
         dbnsfp,
         gnomad_constraints,
+        gnomad_constraints_transcripts,
         hgnc,
         ncbi,
         omim,
@@ -670,7 +957,13 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
             zfin_zebrafish_structure,
             zfin_zebrafish_phenotype_quality,
             zfin_zebrafish_phenotype_tag,
+            extra,
         } = dbnsfp;
+        let mut extra = extra
+            .into_iter()
+            .map(|(key, value)| pbs::genes::base::DbnsfpExtraColumn { key, value })
+            .collect::<Vec<_>>();
+        extra.sort_by(|a, b| a.key.cmp(&b.key));
 
         pbs::genes::base::DbnsfpRecord {
             gene_name,
@@ -772,6 +1065,7 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
             zfin_zebrafish_structure,
             zfin_zebrafish_phenotype_quality,
             zfin_zebrafish_phenotype_tag,
+            extra,
         }
     });
 
@@ -802,8 +1096,26 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
             exac_obs_lof,
             exac_exp_lof,
             exac_oe_lof,
+            transcript_id: _,
+            canonical: _,
+            mane_select: _,
         } = gnomad_constraints;
 
+        let transcripts = gnomad_constraints_transcripts
+            .into_iter()
+            .map(
+                |record| pbs::genes::base::GnomadConstraintsTranscriptRecord {
+                    transcript_id: record.transcript_id.unwrap_or_default(),
+                    canonical: record.canonical.unwrap_or_default(),
+                    mane_select: record.mane_select.unwrap_or_default(),
+                    pli: record.pli,
+                    oe_lof_upper: record.oe_lof_upper,
+                    mis_z: record.mis_z,
+                    syn_z: record.syn_z,
+                },
+            )
+            .collect();
+
         pbs::genes::base::GnomadConstraintsRecord {
             ensembl_gene_id,
             entrez_id,
@@ -830,6 +1142,7 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
             exac_obs_lof,
             exac_exp_lof,
             exac_oe_lof,
+            transcripts,
         }
     });
 
@@ -1078,6 +1391,8 @@ fn convert_record(record: data::Record) -> pbs::genes::base::Record {
         panelapp,
         decipher_hi,
         conditions,
+        clingen_regions_37: Vec::default(),
+        clingen_regions_38: Vec::default(),
     }
 }
 
@@ -1088,7 +1403,7 @@ fn write_rocksdb(
     clingen_by_symbol_37: HashMap<String, clingen_gene::Gene>,
     clingen_by_symbol_38: HashMap<String, clingen_gene::Gene>,
     dbnsfp_by_symbol: HashMap<String, dbnsfp_gene::Record>,
-    constraints_by_ensembl_id: HashMap<String, gnomad_constraints::Record>,
+    constraints_by_ensembl_id: HashMap<String, Vec<gnomad_constraints::Record>>,
     hgnc: HashMap<String, hgnc::Record>,
     ncbi_by_ncbi_id: HashMap<String, ncbi::Record>,
     omim_by_hgnc_id: HashMap<String, omim::Record>,
@@ -1100,14 +1415,24 @@ fn write_rocksdb(
     domino_by_symbol: HashMap<String, domino::Record>,
     decipher_hi_by_hgnc_id: HashMap<String, decipher_hi::Record>,
     conditions_by_hgnc_id: HashMap<String, conditions::Record>,
+    clingen_regions_37: ClingenRegionIndex,
+    clingen_regions_38: ClingenRegionIndex,
+    source_versions: &std::collections::BTreeMap<String, String>,
     args: &&Args,
 ) -> Result<(), anyhow::Error> {
     // Construct RocksDB options and open file for writing.
     let options = rocksdb_utils_lookup::tune_options(rocksdb::Options::default(), None);
+    let cf_names = [
+        "meta",
+        "genes",
+        cf_name_pos_index(common::cli::GenomeRelease::Grch37),
+        cf_name_pos_index(common::cli::GenomeRelease::Grch38),
+        diseases::CF_NAME_BY_DISEASE,
+    ];
     let db = rocksdb::DB::open_cf_with_opts(
         &options,
         common::readlink_f(&args.path_out_rocksdb)?,
-        ["meta", "genes"]
+        cf_names
             .iter()
             .map(|name| (name.to_string(), options.clone()))
             .collect::<Vec<_>>(),
@@ -1118,7 +1443,13 @@ fn write_rocksdb(
 
     tracing::info!("  writing meta data to database");
     db.put_cf(&cf_meta, "builder-version", version())?;
-    // TODO: read meta information about input data and write out
+    for (name, version) in source_versions {
+        db.put_cf(
+            &cf_meta,
+            format!("{}{}", META_SOURCE_VERSION_PREFIX, name),
+            version,
+        )?;
+    }
 
     tracing::info!("  compose genes data into database");
     for hgnc_record in hgnc
@@ -1126,15 +1457,29 @@ fn write_rocksdb(
         .progress_with(common::cli::progress_bar(hgnc.len()))
     {
         let hgnc_id = hgnc_record.hgnc_id.clone();
-        let record = convert_record(data::Record {
+        let gnomad_constraints = hgnc_record
+            .ensembl_gene_id
+            .as_ref()
+            .and_then(|ensembl_gene_id| constraints_by_ensembl_id.get(ensembl_gene_id));
+        let clingen_37 = clingen_by_symbol_37.get(&hgnc_record.symbol);
+        let clingen_38 = clingen_by_symbol_38.get(&hgnc_record.symbol);
+        let mut record = convert_record(data::Record {
             acmg_sf: acmg_by_hgnc_id.get(&hgnc_id).cloned(),
-            clingen_37: clingen_by_symbol_37.get(&hgnc_record.symbol).cloned(),
-            clingen_38: clingen_by_symbol_38.get(&hgnc_record.symbol).cloned(),
+            clingen_37: clingen_37.cloned(),
+            clingen_38: clingen_38.cloned(),
             dbnsfp: dbnsfp_by_symbol.get(&hgnc_record.symbol).cloned(),
-            gnomad_constraints: hgnc_record
-                .ensembl_gene_id
-                .as_ref()
-                .map(|ensembl_gene_id| constraints_by_ensembl_id.get(ensembl_gene_id).cloned())
+            gnomad_constraints: gnomad_constraints
+                .map(Vec::as_slice)
+                .and_then(gene_level_gnomad_constraints)
+                .cloned(),
+            gnomad_constraints_transcripts: gnomad_constraints
+                .map(|records| {
+                    records
+                        .iter()
+                        .filter(|record| record.transcript_id.is_some())
+                        .cloned()
+                        .collect()
+                })
                 .unwrap_or_default(),
             hgnc: hgnc_record.clone(),
             omim: omim_by_hgnc_id.get(&hgnc_id).cloned(),
@@ -1155,30 +1500,128 @@ fn write_rocksdb(
             decipher_hi: decipher_hi_by_hgnc_id.get(&hgnc_id).cloned(),
             conditions: conditions_by_hgnc_id.get(&hgnc_id).cloned(),
         });
+        // Only genes with a ClinGen gene curation have coordinates to overlap with.
+        if let Some(clingen_37) = clingen_37 {
+            record.clingen_regions_37 = clingen_regions_37.overlaps(&clingen_37.genomic_location);
+        }
+        if let Some(clingen_38) = clingen_38 {
+            record.clingen_regions_38 = clingen_regions_38.overlaps(&clingen_38.genomic_location);
+        }
         tracing::debug!("writing {:?} -> {:?}", &hgnc, &record);
+        put_pos_index(&db, &record)?;
         db.put_cf(&cf_genes, hgnc_id, record.encode_to_vec())?;
     }
 
+    tracing::info!("  writing disease index");
+    let cf_by_disease = db.cf_handle(diseases::CF_NAME_BY_DISEASE).unwrap();
+    let disease_index = diseases::build_index(
+        disease_associations(&omim_by_hgnc_id, &orpha_by_hgnc_id)
+            .filter(|(_, hgnc_id)| hgnc.contains_key(*hgnc_id)),
+    );
+    for (key, hgnc_ids) in &disease_index {
+        db.put_cf(&cf_by_disease, key, hgnc_ids.join(","))?;
+    }
+
     // Finally, compact manually.
     tracing::info!("  enforce manual compaction");
-    rocksdb_utils_lookup::force_compaction_cf(&db, ["meta", "genes"], Some("  "), true)?;
+    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
 
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+///
+/// The input files are read with the same functions as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    validation.read_input(&args.path_in_acmg, load_acmg);
+    validation.read_input(&args.path_in_clingen_37, load_clingen);
+    validation.read_input(&args.path_in_clingen_38, load_clingen);
+    validation.read_input(&args.path_in_gnomad_constraints, load_gnomad_constraints);
+    let dbnsfp_column_names = match &args.path_dbnsfp_column_map {
+        Some(path) => validation.read_input(path, load_dbnsfp_column_names),
+        None => Some(Default::default()),
+    };
+    if let Some(dbnsfp_column_names) = dbnsfp_column_names {
+        validation.read_input(&args.path_in_dbnsfp, |path| {
+            load_dbnsfp(path, &dbnsfp_column_names)
+        });
+    }
+    let hgnc = validation.read_input(&args.path_in_hgnc, load_hgnc);
+    validation.read_input(&args.path_in_ncbi, load_ncbi);
+    validation.read_input(&args.path_in_omim, load_omim);
+    validation.read_input(&args.path_in_orpha, load_orpha);
+    if let Some(hgnc) = hgnc {
+        validation.read_input(&args.path_in_panelapp, |path| load_panelapp(path, &hgnc));
+    }
+    validation.read_input(&args.path_in_rcnv, load_rcnv);
+    validation.read_input(&args.path_in_shet, load_shet);
+    validation.read_input(&args.path_in_gtex, load_gtex);
+    validation.read_input(&args.path_in_domino, load_domino);
+    validation.read_input(&args.path_in_decipher_hi, load_decipher_hi);
+    validation.read_input(&args.path_in_conditions, load_conditions);
+    for path in [
+        &args.path_in_clingen_regions_37,
+        &args.path_in_clingen_regions_38,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        validation.read_input(path, |path| ClingenRegionIndex::load(Some(path)));
+    }
+    if let Some(path) = &args.path_source_versions {
+        validation.read_input(path, load_source_versions);
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
+/// Return the version of the input files at `paths` for `spec.yaml`.
+///
+/// The inputs carry no version information themselves, so this is the modification date of
+/// the most recently modified input as `YYYYMMDD`.
+fn inputs_version(paths: &[String]) -> Result<String, anyhow::Error> {
+    let mut latest = None;
+    for path in paths {
+        let modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| anyhow::anyhow!("problem getting modification time of {}: {}", path, e))?;
+        latest = latest.max(Some(modified));
+    }
+    let latest = latest.ok_or_else(|| anyhow::anyhow!("no input files"))?;
+    Ok(chrono::DateTime::<chrono::Local>::from(latest)
+        .format("%Y%m%d")
+        .to_string())
+}
+
 /// Main entry point for the `db gene build` command.
 pub fn run(common_args: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     info!("Starting `db gene build`");
     info!("  common_args = {:?}", &common_args);
     info!("  args = {:?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     let before_loading = Instant::now();
     info!("Loading genes data files...");
     let acmg_by_hgnc_id = load_acmg(&args.path_in_acmg)?;
     let clingen_by_symbol_37 = load_clingen(&args.path_in_clingen_37)?;
     let clingen_by_symbol_38 = load_clingen(&args.path_in_clingen_38)?;
     let constraints_by_ensembl_id = load_gnomad_constraints(&args.path_in_gnomad_constraints)?;
-    let dbnsfp_by_symbol = load_dbnsfp(&args.path_in_dbnsfp)?;
+    let dbnsfp_column_names = args
+        .path_dbnsfp_column_map
+        .as_deref()
+        .map(load_dbnsfp_column_names)
+        .transpose()?
+        .unwrap_or_default();
+    let dbnsfp_by_symbol = load_dbnsfp(&args.path_in_dbnsfp, &dbnsfp_column_names)?;
     let hgnc = load_hgnc(&args.path_in_hgnc)?;
     let ncbi_by_ncbi_id = load_ncbi(&args.path_in_ncbi)?;
     let omim_by_hgnc_id = load_omim(&args.path_in_omim)?;
@@ -1190,6 +1633,14 @@ pub fn run(common_args: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
     let domino_by_symbol = load_domino(&args.path_in_domino)?;
     let decipher_hi_by_hgnc_id = load_decipher_hi(&args.path_in_decipher_hi)?;
     let conditions_by_hgnc_id = load_conditions(&args.path_in_conditions)?;
+    let clingen_regions_37 = ClingenRegionIndex::load(args.path_in_clingen_regions_37.as_deref())?;
+    let clingen_regions_38 = ClingenRegionIndex::load(args.path_in_clingen_regions_38.as_deref())?;
+    let source_versions = args
+        .path_source_versions
+        .as_deref()
+        .map(load_source_versions)
+        .transpose()?
+        .unwrap_or_default();
     info!(
         "... done loadin genes data files in {:?}",
         before_loading.elapsed()
@@ -1214,6 +1665,9 @@ pub fn run(common_args: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
         domino_by_symbol,
         decipher_hi_by_hgnc_id,
         conditions_by_hgnc_id,
+        clingen_regions_37,
+        clingen_regions_38,
+        &source_versions,
         &args,
     )?;
     info!(
@@ -1221,6 +1675,50 @@ pub fn run(common_args: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
         before_writing.elapsed()
     );
 
+    let paths_in = vec![
+        args.path_in_acmg.clone(),
+        args.path_in_clingen_37.clone(),
+        args.path_in_clingen_38.clone(),
+        args.path_in_gnomad_constraints.clone(),
+        args.path_in_dbnsfp.clone(),
+        args.path_in_hgnc.clone(),
+        args.path_in_ncbi.clone(),
+        args.path_in_omim.clone(),
+        args.path_in_orpha.clone(),
+        args.path_in_panelapp.clone(),
+        args.path_in_rcnv.clone(),
+        args.path_in_shet.clone(),
+        args.path_in_gtex.clone(),
+        args.path_in_domino.clone(),
+        args.path_in_decipher_hi.clone(),
+        args.path_in_conditions.clone(),
+    ]
+    .into_iter()
+    .chain(args.path_in_clingen_regions_37.clone())
+    .chain(args.path_in_clingen_regions_38.clone())
+    .chain(args.path_source_versions.clone())
+    .chain(args.path_dbnsfp_column_map.clone())
+    .collect::<Vec<_>>();
+
+    // The version of each data source, after the `--spec-data-version` if given.  Without
+    // either, the version is derived from the input files.
+    let mut created_from = source_versions.into_iter().collect::<Vec<_>>();
+    if let Some(spec_data_version) = &args.spec.spec_data_version {
+        created_from.insert(0, ("genes".to_string(), spec_data_version.clone()));
+    } else if created_from.is_empty() && !args.spec.no_write_spec_yaml {
+        created_from.push(("genes".to_string(), inputs_version(&paths_in)?));
+    }
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "genes".to_string(),
+            title: "genes".to_string(),
+            genome_release: None,
+            created_from,
+            paths_in,
+        },
+    )?;
+
     Ok(())
 }
 
@@ -1232,15 +1730,11 @@ pub mod test {
     use clap_verbosity_flag::Verbosity;
     use temp_testdir::TempDir;
 
-    #[rstest::rstest]
-    #[case::gnomad_v2("2.1")]
-    #[case::gnomad_v4("4.0")]
-    fn smoke_test(#[case] gnomad_constraints_version: &str) -> Result<(), anyhow::Error> {
-        let tmp_dir = TempDir::default();
-        let common_args = common::cli::Args {
-            verbose: Verbosity::new(1, 0),
-        };
-        let args = Args {
+    /// Return the arguments for importing the example genes data to `path_out_rocksdb`.
+    ///
+    /// No `spec.yaml` is written.
+    pub fn example_args(path_out_rocksdb: &str) -> Args {
+        Args {
             path_in_acmg: String::from("tests/genes/acmg/acmg.tsv"),
             path_in_clingen_37: String::from(
                 "tests/genes/clingen/ClinGen_gene_curation_list_GRCh37.tsv",
@@ -1248,8 +1742,8 @@ pub mod test {
             path_in_clingen_38: String::from(
                 "tests/genes/clingen/ClinGen_gene_curation_list_GRCh38.tsv",
             ),
-            path_in_gnomad_constraints: format!(
-                "tests/genes/gnomad_constraints/v{gnomad_constraints_version}/gnomad_constraints.tsv",
+            path_in_gnomad_constraints: String::from(
+                "tests/genes/gnomad_constraints/v4.0/gnomad_constraints.tsv",
             ),
             path_in_dbnsfp: String::from("tests/genes/dbnsfp/genes.tsv"),
             path_in_hgnc: String::from("tests/genes/hgnc/hgnc_info.jsonl"),
@@ -1263,15 +1757,288 @@ pub mod test {
             path_in_domino: String::from("tests/genes/domino/domino.tsv"),
             path_in_decipher_hi: String::from("tests/genes/decipher/decipher_hi_prediction.tsv"),
             path_in_conditions: String::from("tests/genes/conditions/conditions.jsonl"),
-            path_out_rocksdb: tmp_dir
-                .to_path_buf()
-                .into_os_string()
-                .into_string()
-                .unwrap(),
+            path_in_clingen_regions_37: Some(String::from(
+                "tests/regions/clingen/ClinGen_region_curation_list_GRCh37.tsv",
+            )),
+            path_in_clingen_regions_38: None,
+            path_source_versions: None,
+            path_dbnsfp_column_map: None,
+            path_out_rocksdb: path_out_rocksdb.to_string(),
+            dry_run: false,
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Return a genes record of `hgnc_id` and `symbol` with all other fields unset.
+    pub fn record(hgnc_id: &str, symbol: &str) -> pbs::genes::base::Record {
+        pbs::genes::base::Record {
+            hgnc: Some(pbs::genes::base::HgncRecord {
+                hgnc_id: hgnc_id.to_string(),
+                symbol: symbol.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Write a genes database with `records`, keyed by their HGNC ID, to `path_out_rocksdb`.
+    pub fn write_records(
+        path_out_rocksdb: &str,
+        records: &[pbs::genes::base::Record],
+    ) -> Result<(), anyhow::Error> {
+        let options = rocksdb_utils_lookup::tune_options(rocksdb::Options::default(), None);
+        let cf_names = &["meta", "genes"];
+        let db = rocksdb::DB::open_cf_with_opts(
+            &options,
+            path_out_rocksdb,
+            cf_names
+                .iter()
+                .map(|name| (name.to_string(), options.clone()))
+                .collect::<Vec<_>>(),
+        )?;
+        let cf_genes = db.cf_handle("genes").unwrap();
+        for record in records {
+            let hgnc_id = &record.hgnc.as_ref().expect("missing HGNC record").hgnc_id;
+            db.put_cf(&cf_genes, hgnc_id, record.encode_to_vec())?;
+        }
+        rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, None, true)?;
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::gnomad_v2("2.1")]
+    #[case::gnomad_v4("4.0")]
+    #[case::gnomad_v4_1("4.1")]
+    fn smoke_test(#[case] gnomad_constraints_version: &str) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common_args = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_gnomad_constraints: format!(
+                "tests/genes/gnomad_constraints/v{gnomad_constraints_version}/gnomad_constraints.tsv",
+            ),
+            ..example_args(&format!("{}", tmp_dir.join("genes.db").display()))
         };
 
         run(&common_args, &args)?;
 
         Ok(())
     }
+
+    /// Dry run must read the inputs like the import, but not create the database.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let path_out_rocksdb = format!("{}", tmp_dir.join("genes.db").display());
+        let args = Args {
+            dry_run: true,
+            ..example_args(&path_out_rocksdb)
+        };
+
+        let validation = validate(&args);
+        assert!(validation.problems.is_empty(), "{:?}", validation.problems);
+        run(
+            &common::cli::Args {
+                verbose: Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &args,
+        )
+        .unwrap();
+        assert!(!tmp_dir.join("genes.db").exists());
+
+        // A file in the wrong format is found by parsing it, not only by opening it.
+        let args = Args {
+            path_in_hgnc: String::from("tests/genes/acmg/acmg.tsv"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(
+            validation.problems[0].starts_with("input file tests/genes/acmg/acmg.tsv can be read")
+        );
+    }
+
+    /// Without source versions, the version in `spec.yaml` is derived from the inputs.
+    #[test]
+    fn import_spec_yaml() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let args = Args {
+            spec: Default::default(),
+            ..example_args(&format!("{}", tmp_dir.join("genes.db").display()))
+        };
+
+        run(
+            &common::cli::Args {
+                verbose: Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &args,
+        )?;
+
+        let spec = crate::server::run::versions::schema::VersionSpec::from_path(
+            tmp_dir.join("spec.yaml"),
+        )?;
+        let version = inputs_version(&[args.path_in_hgnc.clone()])?;
+        assert_eq!(spec.created_from.len(), 1);
+        assert_eq!(spec.created_from[0].name, "genes");
+        assert_ne!(spec.created_from[0].version, "unknown");
+        assert_eq!(spec.created_from[0].version.len(), 8);
+        assert!(spec.created_from[0].version >= version);
+        assert_eq!(
+            spec.version,
+            format!("{}+0.0.0", spec.created_from[0].version)
+        );
+        assert!(spec.source.contains(&args.path_in_hgnc));
+
+        // A second import must not overwrite the `spec.yaml` without `--force-spec-yaml`.
+        let err = run(
+            &common::cli::Args {
+                verbose: Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &args,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--force-spec-yaml"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn source_versions() -> Result<(), anyhow::Error> {
+        let source_versions = load_source_versions("tests/genes/source_versions.yaml")?;
+        assert_eq!(
+            source_versions.into_iter().collect::<Vec<_>>(),
+            vec![
+                (String::from("hgnc"), String::from("2024-01-02")),
+                (String::from("omim"), String::from("2023-12-18")),
+                (String::from("panelapp"), String::from("2024-01-05")),
+            ]
+        );
+
+        let tmp_dir = TempDir::default();
+        let path = format!("{}", tmp_dir.join("source_versions.yaml").display());
+        std::fs::write(&path, "hgnc: \"2024-01-02\"\npanel_app: \"2024-01-05\"\n")?;
+        let err = load_source_versions(&path).unwrap_err();
+        assert!(err.to_string().starts_with(&format!(
+            "unknown source panel_app in {}, expected one of acmg, clingen",
+            path
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dbnsfp_column_map() -> Result<(), anyhow::Error> {
+        let column_names = load_dbnsfp_column_names("tests/genes/dbnsfp/column_map.yaml")?;
+        let records = load_dbnsfp("tests/genes/dbnsfp/genes-4.7-excerpt.tsv", &column_names)?;
+        assert_eq!(records["BRAF"].gnomad_pli, Some(0.99));
+        assert_eq!(records["KRAS"].gnomad_pli, Some(0.96));
+
+        let tmp_dir = TempDir::default();
+        let path = format!("{}", tmp_dir.join("column_map.yaml").display());
+        std::fs::write(&path, "gnomad_pLI:\n  - gnomAD_v4_pLI\n")?;
+        let err = load_dbnsfp_column_names(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("unknown dbNSFP gene field gnomad_pLI in {}", path)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn gnomad_constraints_transcripts() -> Result<(), anyhow::Error> {
+        let constraints =
+            load_gnomad_constraints("tests/genes/gnomad_constraints/v4.1/gnomad_constraints.tsv")?;
+        let records = &constraints["ENSG00000157764"];
+        assert_eq!(records.len(), 2);
+
+        let gene_level = gene_level_gnomad_constraints(records).unwrap();
+        assert_eq!(gene_level.transcript_id.as_deref(), Some("ENST00000646891"));
+        assert_eq!(gene_level.pli, Some(0.99));
+
+        Ok(())
+    }
+
+    #[test]
+    fn clingen_region_overlaps() -> Result<(), anyhow::Error> {
+        let index = ClingenRegionIndex::load(Some(
+            "tests/regions/clingen/ClinGen_region_curation_list_GRCh37.tsv",
+        ))?;
+
+        let overlaps = index.overlaps("chr22:24330000-24370000");
+        assert_eq!(
+            overlaps
+                .iter()
+                .map(|region| region.isca_id.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "ISCA-46292",
+                "ISCA-46294",
+                "ISCA-46536",
+                "ISCA-46537",
+                "ISCA-46725",
+                "ISCA-46726",
+                "ISCA-46747",
+            ]
+        );
+        let region = overlaps.last().unwrap();
+        assert_eq!(region.genomic_location, "chr22:23119415-24994433");
+        assert_eq!(
+            region.haploinsufficiency_score,
+            pbs::genes::base::ClingenDosageScore::SufficientEvidenceAvailable as i32
+        );
+        assert_eq!(
+            region.triplosensitivity_score,
+            pbs::genes::base::ClingenDosageScore::LittleEvidence as i32
+        );
+
+        // Chromosome names are canonicalized, invalid locations have no overlaps.
+        assert_eq!(index.overlaps("22:24330000-24370000"), overlaps);
+        assert!(index.overlaps("tbd").is_empty());
+        assert!(ClingenRegionIndex::load(None)?
+            .overlaps("chr22:24330000-24370000")
+            .is_empty());
+
+        Ok(())
+    }
+    #[test]
+    fn disease_index() -> Result<(), anyhow::Error> {
+        let omim_by_hgnc_id = load_omim("tests/genes/omim/omim_diseases.tsv")?;
+        let orpha_by_hgnc_id = load_orpha("tests/genes/orphanet/orphanet_diseases.tsv")?;
+
+        let index =
+            diseases::build_index(disease_associations(&omim_by_hgnc_id, &orpha_by_hgnc_id));
+
+        assert_eq!(
+            index.get("omim:613287"),
+            Some(&vec![String::from("HGNC:20")])
+        );
+        assert_eq!(
+            index.get("omim:205400"),
+            Some(&vec![String::from("HGNC:29")])
+        );
+        assert_eq!(
+            index.get("orpha:71275"),
+            Some(&vec![
+                String::from("HGNC:10006"),
+                String::from("HGNC:10008")
+            ])
+        );
+        assert_eq!(index.get("orpha:613287"), None);
+        // Each association of the fixtures is in the index once.
+        assert_eq!(
+            index.values().map(Vec::len).sum::<usize>(),
+            disease_associations(&omim_by_hgnc_id, &orpha_by_hgnc_id).count()
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,236 @@
+//! Offline lookup of genes by identifier, symbol, or transcript accession.
+//!
+//! This resolves the queries the same way as the `/api/v1/genes/lookup` endpoint of the
+//! server, see `server::run::genes_lookup`.
+
+use crate::{
+    common,
+    server::run::{
+        build_name_to_hgnc_idx, build_other_symbol_to_hgnc_idxs, extract_gene_names, genes_lookup,
+    },
+};
+
+/// Command line arguments for `gene lookup` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "look up genes by identifier, symbol, or transcript", long_about = None)]
+pub struct Args {
+    /// Path to RocksDB directory with data.
+    #[arg(long)]
+    pub path_rocksdb: String,
+    /// Name of the column family with the gene data.
+    #[arg(long, default_value = "genes")]
+    pub cf_name: String,
+    /// Output file (default is stdout == "-").
+    #[arg(long, default_value = "-")]
+    pub out_file: String,
+    /// Output format.
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
+
+    /// HGNC/NCBI/ENSEMBL gene identifiers, symbols, previous or alias symbols, or
+    /// Ensembl/RefSeq transcript accessions to look up, comma-separated.
+    #[arg(long, required = true, value_delimiter = ',')]
+    pub q: Vec<String>,
+}
+
+/// Implementation of `gene lookup` sub command.
+///
+/// Writes one `GenesLookupResultEntry` per match and query, keyed by the query.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'gene lookup' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    // Open the RocksDB database.
+    let db = super::query::open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")?;
+
+    tracing::info!("Building gene names...");
+    let gene_names = extract_gene_names(&db)?;
+    let name_to_hgnc_idx = build_name_to_hgnc_idx(&gene_names);
+    let other_symbol_to_hgnc_idxs = build_other_symbol_to_hgnc_idxs(&gene_names);
+    let transcript_to_hgnc_idxs = genes_lookup::build_transcript_index(&gene_names);
+
+    // Obtain writer to output.
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "genes",
+        None,
+    )?;
+
+    tracing::info!("Running lookup...");
+    let response = genes_lookup::build_response(
+        &gene_names,
+        &name_to_hgnc_idx,
+        &other_symbol_to_hgnc_idxs,
+        &transcript_to_hgnc_idxs,
+        &args.q,
+    );
+    for entry in &response.genes {
+        if entry.gene_names.is_none() {
+            tracing::info!("no gene found for {:?}", &entry.query);
+        }
+        if let Some(symbol_notice) = entry.symbol_notice.as_ref() {
+            tracing::warn!("{}", symbol_notice);
+        }
+        out_writer.write(&entry.query, entry)?;
+    }
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    /// Import the genes example data into `tmp_dir` and return the database path.
+    fn import_genes(
+        tmp_dir: &TempDir,
+        common: &common::cli::Args,
+    ) -> Result<String, anyhow::Error> {
+        let path_rocksdb = format!("{}", tmp_dir.join("rocksdb").display());
+        let import_args = crate::genes::cli::import::Args {
+            path_in_clingen_regions_37: None,
+            ..crate::genes::cli::import::test::example_args(&path_rocksdb)
+        };
+        crate::genes::cli::import::run(common, &import_args)?;
+        Ok(path_rocksdb)
+    }
+
+    /// Run the lookup of `q` on the database at `path_rocksdb` and return the entries.
+    fn run_lookup(
+        tmp_dir: &TempDir,
+        common: &common::cli::Args,
+        path_rocksdb: String,
+        q: &[&str],
+    ) -> Result<Vec<genes_lookup::GenesLookupResultEntry>, anyhow::Error> {
+        let out_file = format!("{}", tmp_dir.join("out.jsonl").display());
+        let args = Args {
+            path_rocksdb,
+            cf_name: String::from("genes"),
+            out_file: out_file.clone(),
+            output: Default::default(),
+            q: q.iter().map(|q| q.to_string()).collect(),
+        };
+        run(common, &args)?;
+
+        Ok(std::fs::read_to_string(&out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    #[test]
+    fn smoke_lookup_transcripts() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = import_genes(&tmp_dir, &common)?;
+
+        let entries = run_lookup(
+            &tmp_dir,
+            &common,
+            path_rocksdb,
+            &[
+                "NM_004333.6",
+                "ENST00000646891",
+                "NM_004333.99",
+                "NM_123456.1",
+            ],
+        )?;
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (
+                    entry.query.as_str(),
+                    entry.gene_names.as_ref().map(|g| g.hgnc_id.as_str()),
+                    entry.matched_by,
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "NM_004333.6",
+                    Some("HGNC:1097"),
+                    Some(genes_lookup::GenesLookupMatchedBy::Transcript)
+                ),
+                (
+                    "ENST00000646891",
+                    Some("HGNC:1097"),
+                    Some(genes_lookup::GenesLookupMatchedBy::Transcript)
+                ),
+                (
+                    "NM_004333.99",
+                    Some("HGNC:1097"),
+                    Some(genes_lookup::GenesLookupMatchedBy::Transcript)
+                ),
+                ("NM_123456.1", None, None),
+            ]
+        );
+
+        Ok(())
+    }
+    #[test]
+    fn smoke_lookup_symbols() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = import_genes(&tmp_dir, &common)?;
+
+        let entries = run_lookup(
+            &tmp_dir,
+            &common,
+            path_rocksdb,
+            &["TTN", "BRAF1", "A2MP", "CMD1G"],
+        )?;
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (
+                    entry.query.as_str(),
+                    entry.gene_names.as_ref().map(|g| g.symbol.as_str()),
+                    entry.matched_by,
+                    entry.symbol_notice.as_deref(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "TTN",
+                    Some("TTN"),
+                    Some(genes_lookup::GenesLookupMatchedBy::Symbol),
+                    None
+                ),
+                (
+                    "BRAF1",
+                    Some("BRAF"),
+                    Some(genes_lookup::GenesLookupMatchedBy::AliasSymbol),
+                    Some("queried symbol BRAF1 is an alias of BRAF; returning current record")
+                ),
+                (
+                    "A2MP",
+                    Some("A2MP1"),
+                    Some(genes_lookup::GenesLookupMatchedBy::PrevSymbol),
+                    Some(
+                        "queried symbol A2MP was replaced by A2MP1 on 2010-02-24; \
+                        returning current record"
+                    )
+                ),
+                (
+                    "CMD1G",
+                    Some("TTN"),
+                    Some(genes_lookup::GenesLookupMatchedBy::PrevSymbol),
+                    Some("queried symbol CMD1G was replaced by TTN; returning current record")
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 //! Command line interface for gene annotation data.
 
 pub mod data;
+pub mod export_search_index;
+pub mod export_sqlite;
 pub mod import;
+pub mod lookup;
+pub mod panel_report;
 pub mod query;
@@ -0,0 +1,548 @@
+//! Gene panel report with ClinVar and gnomAD summaries.
+//!
+//! For each gene of a panel, the ClinVar variants with a (likely) pathogenic germline
+//! classification and the gnomAD-genomes variants with an overall allele frequency above
+//! `--min-af-common` are counted.  The gene intervals are resolved from the ClinGen genomic
+//! locations in the genes database as for `/genes/clinvar-variants`, so they span the whole
+//! gene and not only the coding sequence.  Genes without a known location are reported
+//! without counts.  Variants in overlapping genes are counted once per gene, also in the
+//! totals.
+
+use std::io::Write;
+
+use crate::{
+    common::{self, cli::GenomeRelease, keys},
+    genes::ids::HgncId,
+    server::run::{
+        annos_presence::decode_gnomad_af, fetch, genes_clinvar_sv_summary, genes_clinvar_variants,
+        AnnoDb,
+    },
+};
+
+/// Format of the report.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ReportFormat {
+    /// One line per gene and a final line with the totals.
+    #[default]
+    Tsv,
+    /// One JSON object with the genes and the totals.
+    Json,
+}
+
+/// Command line arguments for `report gene-panel` sub command.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(about = "report ClinVar and gnomAD counts for gene panel", long_about = None)]
+pub struct Args {
+    /// Path to genes RocksDB directory.
+    #[arg(long)]
+    pub path_genes: String,
+    /// Path to ClinVar minimal RocksDB directory.
+    #[arg(long)]
+    pub path_clinvar: String,
+    /// Path to gnomAD genomes RocksDB directory.
+    #[arg(long)]
+    pub path_gnomad_genomes: String,
+    /// HGNC IDs of the panel genes, may be given multiple times or comma-separated.
+    #[arg(long, value_delimiter = ',')]
+    pub hgnc_id: Vec<HgncId>,
+    /// Path to file with one HGNC ID per line, lines starting with `#` are ignored.
+    #[arg(long)]
+    pub path_hgnc_ids: Option<String>,
+    /// Minimal overall allele frequency (exclusive) for gnomAD variants to count as common.
+    #[arg(long, default_value_t = 0.01)]
+    pub min_af_common: f32,
+    /// Output file (default is stdout == "-").
+    #[arg(long, default_value = "-")]
+    pub out_file: String,
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = ReportFormat::default())]
+    pub out_format: ReportFormat,
+    /// Directory for caching reports, see `common::cache`.
+    #[arg(long)]
+    pub cache_dir: Option<String>,
+}
+
+/// Counts for one gene of the panel.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GeneCounts {
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// HGNC symbol of the gene, if in the genes database.
+    pub symbol: Option<String>,
+    /// The 1-based, inclusive gene interval as `CHROM:START-STOP`.
+    pub interval: Option<String>,
+    /// Number of ClinVar variants with a (likely) pathogenic germline classification.
+    pub clinvar_pathogenic: Option<usize>,
+    /// Number of gnomAD-genomes variants above the allele frequency threshold.
+    pub gnomad_common: Option<usize>,
+    /// Why there are no counts, e.g., for genes without a known location.
+    pub note: Option<String>,
+}
+
+/// The report for the whole panel.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PanelReport {
+    /// Genome release of the databases.
+    pub genome_release: GenomeRelease,
+    /// Allele frequency threshold for common gnomAD variants.
+    pub min_af_common: f32,
+    /// Counts for each gene, in the order of the panel.
+    pub genes: Vec<GeneCounts>,
+    /// Sum of the ClinVar counts.
+    pub total_clinvar_pathogenic: usize,
+    /// Sum of the gnomAD counts.
+    pub total_gnomad_common: usize,
+}
+
+/// Return the number of germline classifications in `classifications` that are (likely)
+/// pathogenic.
+pub fn count_pathogenic<'a>(classifications: impl IntoIterator<Item = Option<&'a str>>) -> usize {
+    classifications
+        .into_iter()
+        .flatten()
+        .filter(|classification| {
+            genes_clinvar_sv_summary::PATHOGENIC_CLASSIFICATIONS
+                .contains(&classification.to_lowercase().as_str())
+        })
+        .count()
+}
+
+/// Return the number of allele frequencies in `afs` above `min_af`.
+pub fn count_common(afs: impl IntoIterator<Item = Option<f32>>, min_af: f32) -> usize {
+    afs.into_iter().flatten().filter(|af| *af > min_af).count()
+}
+
+/// Count the (likely) pathogenic ClinVar variants from `start` to `stop`.
+fn count_clinvar(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    start: keys::Pos,
+    stop: keys::Pos,
+) -> Result<usize, anyhow::Error> {
+    let records = genes_clinvar_variants::fetch_variants(db, start, stop)
+        .map_err(|e| anyhow::anyhow!("problem reading ClinVar variants: {}", e.message()))?;
+    Ok(count_pathogenic(
+        records
+            .iter()
+            .map(|record| record.germline_significance.as_deref()),
+    ))
+}
+
+/// Count the gnomAD variants above `min_af` from `start` to `stop`.
+fn count_gnomad(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    start: keys::Pos,
+    stop: keys::Pos,
+    min_af: f32,
+) -> Result<usize, anyhow::Error> {
+    let cf_name = AnnoDb::GnomadGenomes.cf_name();
+    let raw = fetch::fetch_pos_raw(db, cf_name, start, stop)
+        .map_err(|e| anyhow::anyhow!("problem reading gnomAD variants: {}", e.message()))?;
+    let afs = raw
+        .iter()
+        .map(|(key, value)| decode_gnomad_af(db, cf_name, key, value))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("problem decoding gnomAD variant: {}", e.message()))?;
+    Ok(count_common(afs, min_af))
+}
+
+/// Count the variants in the gene with `hgnc_id`.
+fn count_gene(
+    genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    clinvar_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    gnomad_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    genome_release: GenomeRelease,
+    hgnc_id: &HgncId,
+    min_af: f32,
+) -> Result<GeneCounts, anyhow::Error> {
+    let mut result = GeneCounts {
+        hgnc_id: hgnc_id.to_string(),
+        symbol: None,
+        interval: None,
+        clinvar_pathogenic: None,
+        gnomad_common: None,
+        note: None,
+    };
+    let Some(record) = super::query::query_for_gene(hgnc_id, genes_db, "genes")? else {
+        result.note = Some("gene not found".to_string());
+        return Ok(result);
+    };
+    result.symbol = record.hgnc.as_ref().map(|hgnc| hgnc.symbol.clone());
+    let range = match super::query::gene_range(&record, genome_release) {
+        Ok(range) => range,
+        Err(e) => {
+            result.note = Some(e.to_string());
+            return Ok(result);
+        }
+    };
+    let start = keys::Pos::new(range.sequence.clone(), range.start);
+    let stop = keys::Pos::new(range.sequence, range.end);
+    result.interval = Some(format!("{}:{}-{}", start.chrom, start.pos, stop.pos));
+    result.clinvar_pathogenic = Some(count_clinvar(clinvar_db, start.clone(), stop.clone())?);
+    result.gnomad_common = Some(count_gnomad(gnomad_db, start, stop, min_af)?);
+    Ok(result)
+}
+
+/// Build the report for the genes `hgnc_ids`.
+pub fn build_report(
+    genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    clinvar_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    gnomad_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    genome_release: GenomeRelease,
+    hgnc_ids: &[HgncId],
+    min_af: f32,
+) -> Result<PanelReport, anyhow::Error> {
+    let genes = hgnc_ids
+        .iter()
+        .map(|hgnc_id| {
+            count_gene(
+                genes_db,
+                clinvar_db,
+                gnomad_db,
+                genome_release,
+                hgnc_id,
+                min_af,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(PanelReport {
+        genome_release,
+        min_af_common: min_af,
+        total_clinvar_pathogenic: genes.iter().filter_map(|g| g.clinvar_pathogenic).sum(),
+        total_gnomad_common: genes.iter().filter_map(|g| g.gnomad_common).sum(),
+        genes,
+    })
+}
+
+/// Write `report` as TSV to `writer`.
+pub fn write_tsv<W: Write>(report: &PanelReport, writer: &mut W) -> Result<(), anyhow::Error> {
+    let opt = |value: Option<usize>| value.map(|value| value.to_string()).unwrap_or_default();
+    writeln!(
+        writer,
+        "hgnc_id\tsymbol\tinterval\tclinvar_pathogenic\tgnomad_common\tnote"
+    )?;
+    for gene in &report.genes {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            gene.hgnc_id,
+            gene.symbol.as_deref().unwrap_or_default(),
+            gene.interval.as_deref().unwrap_or_default(),
+            opt(gene.clinvar_pathogenic),
+            opt(gene.gnomad_common),
+            gene.note.as_deref().unwrap_or_default(),
+        )?;
+    }
+    writeln!(
+        writer,
+        "#total\t\t\t{}\t{}\t",
+        report.total_clinvar_pathogenic, report.total_gnomad_common
+    )?;
+    Ok(())
+}
+
+/// Return the HGNC IDs from `--hgnc-id` and `--path-hgnc-ids` without duplicates.
+fn hgnc_ids(args: &Args) -> Result<Vec<HgncId>, anyhow::Error> {
+    let mut result = args.hgnc_id.clone();
+    if let Some(path) = &args.path_hgnc_ids {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("problem reading {}: {}", path, e))?;
+        for line in contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            result.push(
+                line.parse()
+                    .map_err(|e| anyhow::anyhow!("invalid gene in {}: {}", path, e))?,
+            );
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    result.retain(|hgnc_id| seen.insert(hgnc_id.clone()));
+    if result.is_empty() {
+        anyhow::bail!("no genes given, use --hgnc-id or --path-hgnc-ids");
+    }
+    Ok(result)
+}
+
+/// Return the genome release of the database at `path` from its meta information.
+fn genome_release(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    path: &str,
+) -> Result<GenomeRelease, anyhow::Error> {
+    rocksdb_utils_lookup::fetch_meta(db, "genome-release")?
+        .ok_or_else(|| anyhow::anyhow!("meta:genome-release not found in {}", path))?
+        .parse()
+        .map_err(|e| anyhow::anyhow!("invalid genome release in {}: {}", path, e))
+}
+
+/// Main entry point for `report gene-panel` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'report gene-panel' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    let hgnc_ids = hgnc_ids(args)?;
+    let genes_db = super::query::open_rocksdb(&args.path_genes, "genes", "meta")?;
+    let clinvar_db =
+        super::query::open_rocksdb(&args.path_clinvar, AnnoDb::Clinvar.cf_name(), "meta")?;
+    let gnomad_db = super::query::open_rocksdb(
+        &args.path_gnomad_genomes,
+        AnnoDb::GnomadGenomes.cf_name(),
+        "meta",
+    )?;
+    let release = genome_release(&clinvar_db, &args.path_clinvar)?;
+    let gnomad_release = genome_release(&gnomad_db, &args.path_gnomad_genomes)?;
+    if release != gnomad_release {
+        anyhow::bail!(
+            "genome release of ClinVar ({}) and gnomAD genomes ({}) databases differ",
+            release,
+            gnomad_release
+        );
+    }
+
+    tracing::info!("Counting variants in {} genes ...", hgnc_ids.len());
+    let before_report = std::time::Instant::now();
+    let compute = || {
+        build_report(
+            &genes_db,
+            &clinvar_db,
+            &gnomad_db,
+            release,
+            &hgnc_ids,
+            args.min_af_common,
+        )
+    };
+    let report = if let Some(cache_dir) = &args.cache_dir {
+        let inputs = serde_json::json!({
+            "command": "report gene-panel",
+            "genome_release": release,
+            "hgnc_ids": &hgnc_ids,
+            "min_af_common": args.min_af_common,
+        });
+        let db_versions = common::cache::DbVersions::from([
+            (String::from("genes"), common::cache::db_meta(&genes_db)?),
+            (
+                String::from("clinvar"),
+                common::cache::db_meta(&clinvar_db)?,
+            ),
+            (
+                String::from("gnomad_genomes"),
+                common::cache::db_meta(&gnomad_db)?,
+            ),
+        ]);
+        common::cache::ResultCache::new(cache_dir).get_or_compute(&inputs, &db_versions, compute)?
+    } else {
+        compute()?
+    };
+    tracing::info!(
+        "... done counting variants in {:?}",
+        before_report.elapsed()
+    );
+
+    let mut out_writer: Box<dyn Write> = match args.out_file.as_ref() {
+        "-" => Box::new(std::io::stdout()),
+        out_file => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(out_file)
+                .map_err(|e| anyhow::anyhow!("problem creating {}: {}", out_file, e))?,
+        )),
+    };
+    match args.out_format {
+        ReportFormat::Tsv => write_tsv(&report, &mut out_writer)?,
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out_writer, &report)?;
+            writeln!(out_writer)?;
+        }
+    }
+    out_writer.flush()?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    #[test]
+    fn count_pathogenic_classifications() {
+        assert_eq!(
+            count_pathogenic([
+                Some("Pathogenic"),
+                Some("Likely pathogenic"),
+                Some("Pathogenic/Likely pathogenic"),
+                Some("Conflicting classifications of pathogenicity"),
+                Some("Uncertain significance"),
+                Some("Benign"),
+                None,
+            ]),
+            3
+        );
+    }
+
+    #[test]
+    fn count_common_afs() {
+        assert_eq!(
+            count_common(
+                [Some(0.5), Some(0.011), Some(0.01), Some(0.001), None],
+                0.01
+            ),
+            2
+        );
+        assert_eq!(count_common([], 0.01), 0);
+    }
+
+    #[test]
+    fn count_clinvar_tgds() -> Result<(), anyhow::Error> {
+        let db = super::super::query::open_rocksdb(
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.db",
+            AnnoDb::Clinvar.cf_name(),
+            "meta",
+        )?;
+
+        // 3 pathogenic, 4 likely pathogenic, 2 pathogenic/likely pathogenic of 90 variants.
+        assert_eq!(
+            count_clinvar(
+                &db,
+                keys::Pos::from("13", 95_226_000),
+                keys::Pos::from("13", 95_249_000)
+            )?,
+            9
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn count_gnomad_genomes() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path_rocksdb = format!("{}", tmp_dir.join("gnomad-genomes").display());
+        crate::gnomad_nuclear::cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::gnomad_nuclear::cli::import::Args {
+                genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+                path_in_vcf: vec![String::from(
+                    "tests/gnomad-nuclear/example-genomes-grch37/v2.1/gnomad-genomes.vcf.bgz",
+                )],
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: AnnoDb::GnomadGenomes.cf_name().to_string(),
+                path_wal_dir: None,
+                incremental_compaction: false,
+                prefix_bloom: false,
+                allow_iupac: false,
+                dry_run: false,
+                sample: Default::default(),
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                tbi_window_size: 1_000_000,
+                import_fields_json: None,
+                gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
+                gnomad_version: String::from("2.1"),
+            },
+        )?;
+        let db = super::super::query::open_rocksdb(
+            &path_rocksdb,
+            AnnoDb::GnomadGenomes.cf_name(),
+            "meta",
+        )?;
+
+        // The variants at 55505599 (AF=3.2e-5) and 55516888 (AF=5.0e-3) are in the interval.
+        let (start, stop) = (
+            keys::Pos::from("1", 55_505_000),
+            keys::Pos::from("1", 55_517_000),
+        );
+        assert_eq!(count_gnomad(&db, start.clone(), stop.clone(), 0.001)?, 1);
+        assert_eq!(count_gnomad(&db, start.clone(), stop.clone(), 0.0)?, 2);
+        assert_eq!(count_gnomad(&db, start, stop, 0.01)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_tsv_report() -> Result<(), anyhow::Error> {
+        let report = PanelReport {
+            genome_release: GenomeRelease::Grch37,
+            min_af_common: 0.01,
+            genes: vec![
+                GeneCounts {
+                    hgnc_id: "HGNC:20324".into(),
+                    symbol: Some("TGDS".into()),
+                    interval: Some("13:95226000-95249000".into()),
+                    clinvar_pathogenic: Some(9),
+                    gnomad_common: Some(2),
+                    note: None,
+                },
+                GeneCounts {
+                    hgnc_id: "HGNC:1097".into(),
+                    symbol: Some("BRAF".into()),
+                    interval: None,
+                    clinvar_pathogenic: None,
+                    gnomad_common: None,
+                    note: Some("gene BRAF (HGNC:1097) has no coordinates for grch37".into()),
+                },
+            ],
+            total_clinvar_pathogenic: 9,
+            total_gnomad_common: 2,
+        };
+
+        let mut buf = Vec::new();
+        write_tsv(&report, &mut buf)?;
+
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "hgnc_id\tsymbol\tinterval\tclinvar_pathogenic\tgnomad_common\tnote\n\
+            HGNC:20324\tTGDS\t13:95226000-95249000\t9\t2\t\n\
+            HGNC:1097\tBRAF\t\t\t\tgene BRAF (HGNC:1097) has no coordinates for grch37\n\
+            #total\t\t\t9\t2\t\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn hgnc_ids_from_args_and_file() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path = tmp_dir.join("panel.txt");
+        std::fs::write(&path, "# panel\nHGNC:1097\n\nHGNC:20324\n")?;
+        let mut args = Args {
+            path_genes: String::new(),
+            path_clinvar: String::new(),
+            path_gnomad_genomes: String::new(),
+            hgnc_id: vec!["HGNC:1097".parse()?, "HGNC:12403".parse()?],
+            path_hgnc_ids: Some(format!("{}", path.display())),
+            min_af_common: 0.01,
+            out_file: String::from("-"),
+            out_format: ReportFormat::Tsv,
+            cache_dir: None,
+        };
+
+        assert_eq!(
+            hgnc_ids(&args)?,
+            vec!["HGNC:1097", "HGNC:12403", "HGNC:20324"]
+        );
+
+        std::fs::write(&path, "HGNC:1097\nENSG00000157764\n")?;
+        assert_eq!(
+            hgnc_ids(&args).unwrap_err().to_string(),
+            format!(
+                "invalid gene in {}: expected HGNC:<number>, got ENSG00000157764",
+                path.display()
+            )
+        );
+
+        args.hgnc_id.clear();
+        args.path_hgnc_ids = None;
+        assert!(hgnc_ids(&args).is_err());
+
+        Ok(())
+    }
+}
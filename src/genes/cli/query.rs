@@ -2,9 +2,30 @@
 
 use std::sync::Arc;
 
-use prost::Message;
+use bio::bio_types::genome::AbstractInterval as _;
 
-use crate::{common, pbs::genes};
+use crate::{
+    common::{self, cli::GenomeRelease, decode::decode_protobuf, spdi},
+    genes::{
+        diseases::{self, DiseaseGenes, DiseaseId},
+        ids::HgncId,
+        symbols,
+    },
+    pbs::genes,
+};
+
+/// Argument group for specifying the gene or the diseases to query for.
+#[derive(clap::Args, Debug, Clone, Default)]
+#[group(required = true, multiple = false)]
+pub struct ArgsQuery {
+    /// HGNC gene identifier to query for.
+    #[arg(long, group = "query")]
+    pub hgnc_id: Option<HgncId>,
+    /// OMIM or Orphanet disease IDs to query the associated genes for, e.g.,
+    /// `OMIM:143890,ORPHA:406`.
+    #[arg(long, group = "query", value_delimiter = ',')]
+    pub disease_id: Vec<DiseaseId>,
+}
 
 /// Command line arguments for `gene query` sub command.
 #[derive(clap::Parser, Debug, Clone)]
@@ -20,12 +41,12 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
-    /// HGNC gene identifier to query for.
-    #[arg(long)]
-    pub hgnc_id: String,
+    /// Gene or diseases to query for.
+    #[command(flatten)]
+    pub query: ArgsQuery,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -39,7 +60,7 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names: &[&str; 2] = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
@@ -53,47 +74,145 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 }
 
 /// Open RocksDB database from command line arguments.
+///
+/// For `--disease-id`, the disease index is opened as well if the database has it.
 pub fn open_rocksdb_from_args(
     args: &Args,
 ) -> Result<Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, anyhow::Error> {
-    open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
-}
-
-/// Print values to `out_writer`.
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &genes::base::Record,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
+    if args.query.disease_id.is_empty() {
+        return open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta");
     }
 
-    Ok(())
+    let path_rocksdb = common::readlink_f_existing(&args.path_rocksdb)?;
+    let mut cf_names = vec!["meta", args.cf_name.as_str()];
+    if rocksdb::DB::list_cf(&rocksdb::Options::default(), &path_rocksdb)?
+        .iter()
+        .any(|cf_name| cf_name == diseases::CF_NAME_BY_DISEASE)
+    {
+        cf_names.push(diseases::CF_NAME_BY_DISEASE);
+    }
+    Ok(Arc::new(rocksdb::DB::open_cf_for_read_only(
+        &rocksdb::Options::default(),
+        path_rocksdb,
+        cf_names,
+        true,
+    )?))
 }
 
 /// Query for one gene annotation record.
 pub fn query_for_gene(
-    hgnc_id: &str,
+    hgnc_id: &HgncId,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<Option<genes::base::Record>, anyhow::Error> {
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
     let raw_value = db
-        .get_cf(cf_data, hgnc_id.as_bytes())
-        .map_err(|e| anyhow::anyhow!("error while querying for HGNC ID {}: {}", hgnc_id, e))?;
-    raw_value
+        .get_cf(&cf_data, hgnc_id.as_str().as_bytes())
+        .map_err(|e| {
+            crate::ErrorCategory::Database.error(format!(
+                "error while querying for HGNC ID {}: {}",
+                hgnc_id, e
+            ))
+        })?;
+    Ok(raw_value
         .map(|raw_value| {
-            genes::base::Record::decode(&mut std::io::Cursor::new(&raw_value)).map_err(|e| {
-                anyhow::anyhow!(
-                    "error while decoding gene record for HGNC ID {}: {}",
-                    hgnc_id,
-                    e
-                )
-            })
+            decode_protobuf::<genes::base::Record>(
+                db,
+                cf_data_name,
+                hgnc_id.as_str().as_bytes(),
+                &raw_value,
+            )
         })
-        .transpose()
+        .transpose()?)
+}
+
+/// Resolve `gene`, given as HGNC ID or gene symbol, to its gene annotation record.
+///
+/// Symbols are matched case-insensitively against the approved symbols first and then
+/// against the alias and previous symbols.  More than one matching gene is an error that
+/// lists the candidates.
+pub fn resolve_gene(
+    gene: &str,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_data_name: &str,
+) -> Result<genes::base::Record, anyhow::Error> {
+    if gene.starts_with("HGNC:") {
+        return query_for_gene(&gene.parse()?, db, cf_data_name)?
+            .ok_or_else(|| anyhow::anyhow!("no gene found for HGNC ID {}", gene));
+    }
+
+    let mut approved = Vec::new();
+    let mut other = Vec::new();
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
+    let mut iter = db.raw_iterator_cf(&cf_data);
+    iter.seek(b"");
+    while iter.valid() {
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let record: genes::base::Record =
+                decode_protobuf(db, cf_data_name, iter_key, iter_value)?;
+            let symbol_match = record.hgnc.as_ref().and_then(|hgnc| {
+                symbols::match_symbol(gene, &hgnc.symbol, &hgnc.prev_symbol, &hgnc.alias_symbol)
+            });
+            match symbol_match {
+                Some(symbols::SymbolMatch::Approved) => approved.push(record),
+                Some(_) => other.push(record),
+                None => (),
+            }
+        }
+        iter.next();
+    }
+    iter.status()?;
+
+    let mut candidates = if approved.is_empty() { other } else { approved };
+    match candidates.len() {
+        0 => anyhow::bail!("no gene found for symbol {}", gene),
+        1 => Ok(candidates.pop().unwrap()),
+        _ => anyhow::bail!(
+            "gene symbol {} is ambiguous, candidates: {}",
+            gene,
+            candidates
+                .iter()
+                .filter_map(|record| record.hgnc.as_ref())
+                .map(|hgnc| format!("{} ({})", hgnc.hgnc_id, hgnc.symbol))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Return the 1-based, inclusive range of the gene `record` on `genome_release`.
+///
+/// The range is taken from the ClinGen genomic location of the gene.
+pub fn gene_range(
+    record: &genes::base::Record,
+    genome_release: GenomeRelease,
+) -> Result<spdi::Range, anyhow::Error> {
+    let (hgnc_id, symbol) = record
+        .hgnc
+        .as_ref()
+        .map(|hgnc| (hgnc.hgnc_id.as_str(), hgnc.symbol.as_str()))
+        .unwrap_or_default();
+    let clingen = record
+        .clingen
+        .as_ref()
+        .filter(|clingen| match genome_release {
+            GenomeRelease::Grch37 => !clingen.genomic_location_37.is_empty(),
+            GenomeRelease::Grch38 => !clingen.genomic_location_38.is_empty(),
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "gene {} ({}) has no coordinates for {}",
+                symbol,
+                hgnc_id,
+                genome_release
+            )
+        })?;
+    let interval = clingen.get_interval(genome_release.into())?;
+    Ok(spdi::Range::new(
+        common::cli::canonicalize(interval.contig()),
+        i32::try_from(interval.range().start + 1)?,
+        i32::try_from(interval.range().end)?,
+    ))
 }
 
 /// Implementation of `gene query` sub command.
@@ -104,23 +223,128 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     // Open the RocksDB database.
     let db = open_rocksdb_from_args(args)?;
-    let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "genes",
+        None,
+    )?;
 
     tracing::info!("Running query...");
-    if let Some(record) = query_for_gene(&args.hgnc_id, &db, &cf_data)? {
-        print_record(&mut out_writer, args.out_format, &record)?;
-    } else {
-        tracing::info!("no record found for HGNC ID {:?}", args.hgnc_id);
+    if let Some(hgnc_id) = args.query.hgnc_id.as_ref() {
+        if let Some(record) = query_for_gene(hgnc_id, &db, &args.cf_name)? {
+            out_writer.write(hgnc_id.as_str(), &record)?;
+        } else {
+            tracing::info!("no record found for HGNC ID {}", hgnc_id);
+        }
+    }
+    for disease_id in &args.query.disease_id {
+        let genes = diseases::query_genes(&db, &args.cf_name, disease_id)?;
+        if genes.is_empty() {
+            tracing::info!("no genes found for disease {}", disease_id);
+        }
+        out_writer.write(
+            &disease_id.to_string(),
+            &DiseaseGenes {
+                disease_id: disease_id.to_string(),
+                genes,
+            },
+        )?;
     }
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    /// Import the genes example data with the disease associations of `tests/genes/by_disease`
+    /// into `tmp_dir` and return the database path.
+    fn import_genes(
+        tmp_dir: &TempDir,
+        common: &common::cli::Args,
+    ) -> Result<String, anyhow::Error> {
+        let path_rocksdb = format!("{}", tmp_dir.join("rocksdb").display());
+        let import_args = crate::genes::cli::import::Args {
+            path_in_omim: String::from("tests/genes/by_disease/omim_diseases.tsv"),
+            path_in_orpha: String::from("tests/genes/by_disease/orphanet_diseases.tsv"),
+            path_in_clingen_regions_37: None,
+            ..crate::genes::cli::import::test::example_args(&path_rocksdb)
+        };
+        crate::genes::cli::import::run(common, &import_args)?;
+        Ok(path_rocksdb)
+    }
+
+    #[test]
+    fn query_disease_ids() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_rocksdb = import_genes(&tmp_dir, &common)?;
+        let out_file = format!("{}", tmp_dir.join("out.jsonl").display());
+        let args = Args {
+            path_rocksdb,
+            cf_name: String::from("genes"),
+            out_file: out_file.clone(),
+            output: Default::default(),
+            query: ArgsQuery {
+                hgnc_id: None,
+                disease_id: vec![
+                    "OMIM:613706".parse()?,
+                    "orphanet:217569".parse()?,
+                    "OMIM:613287".parse()?,
+                ],
+            },
+        };
+
+        run(&common, &args)?;
+
+        let results = std::fs::read_to_string(&out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<DiseaseGenes>, _>>()?;
+        assert_eq!(
+            results,
+            vec![
+                DiseaseGenes {
+                    disease_id: String::from("OMIM:613706"),
+                    genes: vec![diseases::DiseaseGene {
+                        hgnc_id: String::from("HGNC:1097"),
+                        symbol: String::from("BRAF"),
+                        disease_labels: vec![String::from("Noonan syndrome 7")],
+                    }],
+                },
+                DiseaseGenes {
+                    disease_id: String::from("ORPHA:217569"),
+                    genes: vec![
+                        diseases::DiseaseGene {
+                            hgnc_id: String::from("HGNC:1097"),
+                            symbol: String::from("BRAF"),
+                            disease_labels: vec![String::from("Hypertrophic cardiomyopathy")],
+                        },
+                        diseases::DiseaseGene {
+                            hgnc_id: String::from("HGNC:12403"),
+                            symbol: String::from("TTN"),
+                            disease_labels: vec![String::from("Hypertrophic cardiomyopathy")],
+                        },
+                    ],
+                },
+                // HGNC:20 is not in the HGNC example data and thus not imported.
+                DiseaseGenes {
+                    disease_id: String::from("OMIM:613287"),
+                    genes: Vec::new(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
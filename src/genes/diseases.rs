@@ -0,0 +1,251 @@
+//! Reverse index from OMIM and Orphanet disease IDs to the associated genes.
+//!
+//! The gene records carry their OMIM and Orphanet diseases.  To answer "which genes are
+//! associated with OMIM:143890?" without scanning all genes, `genes import` also writes the
+//! column family `genes_by_disease` keyed by `omim:<number>` or `orpha:<number>` with the
+//! comma-separated HGNC IDs of the associated genes as the value.
+
+use std::collections::BTreeMap;
+
+use crate::{common::decode::decode_protobuf, pbs::genes};
+
+/// Name of the column family with the disease index.
+pub const CF_NAME_BY_DISEASE: &str = "genes_by_disease";
+
+/// Source of a disease identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiseaseSource {
+    /// OMIM, e.g., `OMIM:143890`.
+    Omim,
+    /// Orphanet, e.g., `ORPHA:406`.
+    Orpha,
+}
+
+/// OMIM or Orphanet disease identifier.
+///
+/// Parsed from `OMIM:<number>` or `ORPHA:<number>`, ignoring case of the prefix and also
+/// accepting `MIM:` and `Orphanet:`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DiseaseId {
+    /// The source of the identifier.
+    pub source: DiseaseSource,
+    /// The number of the disease in the source.
+    pub number: String,
+}
+
+impl DiseaseId {
+    /// Return the key of the disease in the index.
+    pub fn key(&self) -> String {
+        match self.source {
+            DiseaseSource::Omim => format!("omim:{}", self.number),
+            DiseaseSource::Orpha => format!("orpha:{}", self.number),
+        }
+    }
+}
+
+impl std::str::FromStr for DiseaseId {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (prefix, number) = value.trim().split_once(':').unwrap_or(("", value));
+        let source = match prefix.to_ascii_uppercase().as_str() {
+            "OMIM" | "MIM" => DiseaseSource::Omim,
+            "ORPHA" | "ORPHANET" => DiseaseSource::Orpha,
+            _ => anyhow::bail!("expected OMIM:<number> or ORPHA:<number>, got {}", value),
+        };
+        if number.is_empty() || !number.bytes().all(|c| c.is_ascii_digit()) {
+            anyhow::bail!("expected OMIM:<number> or ORPHA:<number>, got {}", value);
+        }
+        Ok(Self {
+            source,
+            number: number.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for DiseaseId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            DiseaseSource::Omim => write!(f, "OMIM:{}", self.number),
+            DiseaseSource::Orpha => write!(f, "ORPHA:{}", self.number),
+        }
+    }
+}
+
+/// Build the disease index from the `(disease ID, HGNC ID)` associations.
+///
+/// Returns the sorted, unique HGNC IDs by index key.  Associations with disease IDs that
+/// cannot be parsed are skipped.
+pub fn build_index<'a>(
+    associations: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut result: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (disease_id, hgnc_id) in associations {
+        match disease_id.parse::<DiseaseId>() {
+            Ok(disease_id) => result
+                .entry(disease_id.key())
+                .or_default()
+                .push(hgnc_id.to_string()),
+            Err(e) => tracing::debug!("skipping disease of {}: {}", hgnc_id, e),
+        }
+    }
+    for hgnc_ids in result.values_mut() {
+        hgnc_ids.sort();
+        hgnc_ids.dedup();
+    }
+    result
+}
+
+/// Gene associated with a disease.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DiseaseGene {
+    /// HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// Approved HGNC symbol of the gene.
+    pub symbol: String,
+    /// Labels of the disease in the OMIM and Orphanet associations of the gene.
+    pub disease_labels: Vec<String>,
+}
+
+/// Genes associated with one disease, as written by `genes query --disease-id`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DiseaseGenes {
+    /// The disease ID queried for.
+    pub disease_id: String,
+    /// The associated genes, sorted by HGNC ID.
+    pub genes: Vec<DiseaseGene>,
+}
+
+/// Return the labels of `disease_id` in the disease associations of `record`.
+fn disease_labels(record: &genes::base::Record, disease_id: &DiseaseId) -> Vec<String> {
+    let matches = |id: &str| id.parse::<DiseaseId>().is_ok_and(|id| id == *disease_id);
+    let mut result = Vec::new();
+    if let Some(omim) = record.omim.as_ref() {
+        result.extend(
+            omim.omim_diseases
+                .iter()
+                .filter(|term| matches(&term.omim_id))
+                .map(|term| term.label.clone()),
+        );
+    }
+    if let Some(orpha) = record.orpha.as_ref() {
+        result.extend(
+            orpha
+                .orpha_diseases
+                .iter()
+                .filter(|term| matches(&term.orpha_id))
+                .map(|term| term.label.clone()),
+        );
+    }
+    result.dedup();
+    result
+}
+
+/// Return the genes associated with `disease_id`, read from the column family `cf_genes`.
+///
+/// Fails if the database has no disease index, i.e., was built by an older `genes import`.
+pub fn query_genes(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_genes: &str,
+    disease_id: &DiseaseId,
+) -> Result<Vec<DiseaseGene>, anyhow::Error> {
+    let cf_by_disease = db.cf_handle(CF_NAME_BY_DISEASE).ok_or_else(|| {
+        crate::ErrorCategory::Database.error(format!(
+            "genes database has no column family {}, re-run `genes import` to query by disease",
+            CF_NAME_BY_DISEASE
+        ))
+    })?;
+    let Some(raw_hgnc_ids) = db.get_cf(&cf_by_disease, disease_id.key())? else {
+        return Ok(Vec::new());
+    };
+    let hgnc_ids = String::from_utf8(raw_hgnc_ids)?;
+    let hgnc_ids = hgnc_ids.split(',').collect::<Vec<_>>();
+
+    let cf_genes_handle = db.cf_handle(cf_genes).expect("no genes column family");
+    let mut result = Vec::new();
+    for (hgnc_id, raw_value) in hgnc_ids.iter().zip(
+        db.multi_get_cf(
+            hgnc_ids
+                .iter()
+                .map(|hgnc_id| (&cf_genes_handle, hgnc_id.as_bytes())),
+        ),
+    ) {
+        let Some(raw_value) = raw_value? else {
+            tracing::warn!("gene {} of {} not in database", hgnc_id, disease_id);
+            continue;
+        };
+        let record: genes::base::Record =
+            decode_protobuf(db, cf_genes, hgnc_id.as_bytes(), &raw_value)?;
+        result.push(DiseaseGene {
+            hgnc_id: hgnc_id.to_string(),
+            symbol: record
+                .hgnc
+                .as_ref()
+                .map(|hgnc| hgnc.symbol.clone())
+                .unwrap_or_default(),
+            disease_labels: disease_labels(&record, disease_id),
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("OMIM:143890", Some("omim:143890"))]
+    #[case("omim:143890", Some("omim:143890"))]
+    #[case("MIM:143890", Some("omim:143890"))]
+    #[case(" ORPHA:406", Some("orpha:406"))]
+    #[case("Orphanet:406", Some("orpha:406"))]
+    #[case("143890", None)]
+    #[case("OMIM:", None)]
+    #[case("OMIM:14389a", None)]
+    #[case("HP:0001250", None)]
+    fn parse_disease_id(#[case] value: &str, #[case] expected_key: Option<&str>) {
+        assert_eq!(
+            value
+                .parse::<DiseaseId>()
+                .ok()
+                .map(|id| id.key())
+                .as_deref(),
+            expected_key
+        );
+    }
+
+    #[test]
+    fn disease_id_display() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            "mim:143890".parse::<DiseaseId>()?.to_string(),
+            "OMIM:143890"
+        );
+        assert_eq!(
+            "orphanet:406".parse::<DiseaseId>()?.to_string(),
+            "ORPHA:406"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_index_sorted_unique() {
+        let index = build_index([
+            ("OMIM:100", "HGNC:2"),
+            ("OMIM:100", "HGNC:1"),
+            ("ORPHA:100", "HGNC:1"),
+            ("OMIM:100", "HGNC:2"),
+            ("invalid", "HGNC:3"),
+        ]);
+
+        assert_eq!(
+            index,
+            BTreeMap::from([
+                (
+                    String::from("omim:100"),
+                    vec![String::from("HGNC:1"), String::from("HGNC:2")]
+                ),
+                (String::from("orpha:100"), vec![String::from("HGNC:1")]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Typed gene identifiers.
+//!
+//! The gene databases are keyed by HGNC ID while the gene records and the lookup index also
+//! carry Ensembl and NCBI gene IDs.  Passing one kind of identifier where another is expected
+//! silently yields no data, so the APIs take these newtypes that check the format when parsed
+//! and (de)serialize as plain strings.
+
+/// Condition of a string not being a gene identifier of the expected kind.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("expected {expected}, got {value}")]
+pub struct InvalidGeneId {
+    /// Description of the expected format, e.g., `HGNC:<number>`.
+    pub expected: &'static str,
+    /// The value that failed to parse.
+    pub value: String,
+}
+
+/// Return whether `value` is a non-empty string of ASCII digits.
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit())
+}
+
+/// Define a gene identifier newtype with the given format check.
+macro_rules! gene_id {
+    ($(#[$meta:meta])* $name:ident, $expected:literal, $is_valid:expr) => {
+        $(#[$meta])*
+        #[derive(
+            Debug,
+            Clone,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            serde::Serialize,
+            serde::Deserialize,
+        )]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            /// Description of the format for error messages.
+            pub const EXPECTED: &'static str = $expected;
+
+            /// Return the identifier as a string.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = InvalidGeneId;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                let is_valid: fn(&str) -> bool = $is_valid;
+                if is_valid(value) {
+                    Ok(Self(value.to_string()))
+                } else {
+                    Err(InvalidGeneId {
+                        expected: Self::EXPECTED,
+                        value: value.to_string(),
+                    })
+                }
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = InvalidGeneId;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+gene_id!(
+    /// HGNC gene identifier, e.g., `HGNC:1100`, the key of the gene databases.
+    HgncId,
+    "HGNC:<number>",
+    |value| value.strip_prefix("HGNC:").is_some_and(is_number)
+);
+
+gene_id!(
+    /// Ensembl gene identifier, e.g., `ENSG00000012048`, optionally with version.
+    EnsemblGeneId,
+    "ENSG<number>",
+    |value| {
+        value.strip_prefix("ENSG").is_some_and(|rest| {
+            let (number, version) = rest.split_once('.').unwrap_or((rest, "0"));
+            is_number(number) && is_number(version)
+        })
+    }
+);
+
+gene_id!(
+    /// NCBI (Entrez) gene identifier, e.g., `672`.
+    NcbiGeneId,
+    "<number>",
+    |value| is_number(value)
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("HGNC:1100", true)]
+    #[case("HGNC:", false)]
+    #[case("hgnc:1100", false)]
+    #[case("HGNC:11a0", false)]
+    #[case("1100", false)]
+    #[case("ENSG00000012048", false)]
+    fn parse_hgnc_id(#[case] value: &str, #[case] is_valid: bool) {
+        assert_eq!(value.parse::<HgncId>().is_ok(), is_valid);
+    }
+
+    #[rstest::rstest]
+    #[case("ENSG00000012048", true)]
+    #[case("ENSG00000012048.23", true)]
+    #[case("ENSG00000012048.", false)]
+    #[case("ENST00000357654", false)]
+    #[case("ENSG", false)]
+    #[case("HGNC:1100", false)]
+    fn parse_ensembl_gene_id(#[case] value: &str, #[case] is_valid: bool) {
+        assert_eq!(value.parse::<EnsemblGeneId>().is_ok(), is_valid);
+    }
+
+    #[rstest::rstest]
+    #[case("672", true)]
+    #[case("", false)]
+    #[case("-672", false)]
+    #[case("HGNC:1100", false)]
+    fn parse_ncbi_gene_id(#[case] value: &str, #[case] is_valid: bool) {
+        assert_eq!(value.parse::<NcbiGeneId>().is_ok(), is_valid);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            "ENSG00000141510".parse::<HgncId>().unwrap_err().to_string(),
+            "expected HGNC:<number>, got ENSG00000141510"
+        );
+        assert_eq!(
+            "HGNC:11998"
+                .parse::<EnsemblGeneId>()
+                .unwrap_err()
+                .to_string(),
+            "expected ENSG<number>, got HGNC:11998"
+        );
+        assert_eq!(
+            "TP53".parse::<NcbiGeneId>().unwrap_err().to_string(),
+            "expected <number>, got TP53"
+        );
+    }
+
+    #[test]
+    fn serde_as_string() -> Result<(), anyhow::Error> {
+        let hgnc_id: HgncId = "HGNC:1100".parse()?;
+        assert_eq!(serde_json::to_string(&hgnc_id)?, "\"HGNC:1100\"");
+        assert_eq!(serde_json::from_str::<HgncId>("\"HGNC:1100\"")?, hgnc_id);
+        let err = serde_json::from_str::<HgncId>("\"ENSG00000012048\"").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("expected HGNC:<number>, got ENSG00000012048"),
+            "{}",
+            err
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! Annotation of transcripts with the MANE Select transcript of their gene.
+//!
+//! The genes database has the MANE Select accessions from HGNC with version, e.g.,
+//! `ENST00000380152.8` and `NM_000059.4`.  Other data, e.g., the UCSC conservation
+//! alignments, may refer to another version of the same transcript or carry no version at
+//! all.  Accessions are thus compared without version and the annotation has a note if the
+//! versions differ.  HGNC does not list the MANE Plus Clinical transcripts, so these are not
+//! annotated.
+
+/// Return `accession` without the version suffix, e.g., `NM_004333` for `NM_004333.6`.
+pub fn accession_without_version(accession: &str) -> &str {
+    match accession.rsplit_once('.') {
+        Some((prefix, version))
+            if !version.is_empty() && version.bytes().all(|c| c.is_ascii_digit()) =>
+        {
+            prefix
+        }
+        _ => accession,
+    }
+}
+
+/// Return whether `accession` is an Ensembl transcript accession.
+fn is_ensembl(accession: &str) -> bool {
+    accession.starts_with("ENST")
+}
+
+/// MANE annotation of a transcript.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManeAnnotation {
+    /// Whether the transcript is the MANE Select transcript of its gene.
+    pub mane_select: bool,
+    /// The MANE Select accession of the gene from the same source (Ensembl or RefSeq) as the
+    /// transcript, with version; `None` if the gene has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mane_select_accession: Option<String>,
+    /// Note if the transcript is the MANE Select transcript with another version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mane_note: Option<String>,
+}
+
+impl ManeAnnotation {
+    /// Annotate the transcript `accession` given the MANE Select accessions of its gene.
+    pub fn new(accession: &str, mane_select: &[String]) -> Self {
+        let Some(mane_accession) = mane_select
+            .iter()
+            .find(|mane_accession| is_ensembl(mane_accession) == is_ensembl(accession))
+        else {
+            return Default::default();
+        };
+
+        let is_mane_select =
+            accession_without_version(accession) == accession_without_version(mane_accession);
+        let versions_differ = accession_without_version(accession) != accession
+            && accession_without_version(mane_accession) != mane_accession.as_str()
+            && accession != mane_accession.as_str();
+        Self {
+            mane_select: is_mane_select,
+            mane_select_accession: Some(mane_accession.clone()),
+            mane_note: (is_mane_select && versions_differ).then(|| {
+                format!(
+                    "transcript {} is MANE Select in version {}",
+                    accession, mane_accession
+                )
+            }),
+        }
+    }
+}
+
+/// A record together with the MANE annotation of its transcript.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct WithMane<T> {
+    /// The annotated record.
+    #[serde(flatten)]
+    pub record: T,
+    /// MANE annotation of the record's transcript.
+    #[serde(flatten)]
+    pub mane: ManeAnnotation,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("NM_004333.6", "NM_004333")]
+    #[case("ENST00000646891.2", "ENST00000646891")]
+    #[case("NM_004333", "NM_004333")]
+    #[case("NM_004333.", "NM_004333.")]
+    #[case("NM_004333.x", "NM_004333.x")]
+    #[case("HGNC:1097", "HGNC:1097")]
+    fn accession_without_version_cases(#[case] accession: &str, #[case] expected: &str) {
+        assert_eq!(accession_without_version(accession), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::same_version("NM_000059.4", true, None)]
+    #[case::other_version(
+        "NM_000059.3",
+        true,
+        Some("transcript NM_000059.3 is MANE Select in version NM_000059.4")
+    )]
+    #[case::without_version("NM_000059", true, None)]
+    #[case::other_transcript("NM_001136571.1", false, None)]
+    #[case::ensembl_other_version(
+        "ENST00000380152.7",
+        true,
+        Some("transcript ENST00000380152.7 is MANE Select in version ENST00000380152.8")
+    )]
+    #[case::ensembl_without_version("ENST00000380152", true, None)]
+    #[case::ensembl_other_transcript("ENST00000544455", false, None)]
+    fn mane_annotation(
+        #[case] accession: &str,
+        #[case] mane_select: bool,
+        #[case] mane_note: Option<&str>,
+    ) {
+        let annotation = ManeAnnotation::new(
+            accession,
+            &[
+                String::from("ENST00000380152.8"),
+                String::from("NM_000059.4"),
+            ],
+        );
+
+        assert_eq!(annotation.mane_select, mane_select);
+        assert_eq!(annotation.mane_note.as_deref(), mane_note);
+        assert_eq!(
+            annotation.mane_select_accession.as_deref(),
+            Some(if accession.starts_with("ENST") {
+                "ENST00000380152.8"
+            } else {
+                "NM_000059.4"
+            })
+        );
+    }
+
+    #[test]
+    fn mane_annotation_without_mane_select() {
+        assert_eq!(
+            ManeAnnotation::new("ENST00000380152.8", &[]),
+            ManeAnnotation::default()
+        );
+    }
+}
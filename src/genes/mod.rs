@@ -1,3 +1,7 @@
 //! Code for storing and querying gene-related information.
 
 pub mod cli;
+pub mod diseases;
+pub mod ids;
+pub mod mane;
+pub mod symbols;
@@ -0,0 +1,122 @@
+//! Matching of queried gene symbols against the approved, previous, and alias HGNC symbols.
+//!
+//! Symbols renamed by HGNC are still found by their previous symbols.  The responses then
+//! carry a notice that the queried symbol is outdated, built with `symbol_notice` for the
+//! `/genes/info` and `/genes/lookup` endpoints and the `genes lookup` command alike.
+
+/// How a queried symbol matched a gene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolMatch {
+    /// The approved HGNC symbol.
+    Approved,
+    /// A previous HGNC symbol.
+    Previous,
+    /// An alias HGNC symbol.
+    Alias,
+}
+
+/// Return how `query` matches the gene with the given symbols, ignoring case.
+///
+/// Previous symbols take precedence over alias symbols.
+pub fn match_symbol(
+    query: &str,
+    symbol: &str,
+    prev_symbol: &[String],
+    alias_symbol: &[String],
+) -> Option<SymbolMatch> {
+    if symbol.eq_ignore_ascii_case(query) {
+        Some(SymbolMatch::Approved)
+    } else if prev_symbol.iter().any(|s| s.eq_ignore_ascii_case(query)) {
+        Some(SymbolMatch::Previous)
+    } else if alias_symbol.iter().any(|s| s.eq_ignore_ascii_case(query)) {
+        Some(SymbolMatch::Alias)
+    } else {
+        None
+    }
+}
+
+/// Return the notice for `query` matching the gene with the approved `symbol`, `None` for
+/// approved symbols.
+///
+/// `date_symbol_changed` is the HGNC date of the last symbol change, if any.
+pub fn symbol_notice(
+    query: &str,
+    symbol_match: SymbolMatch,
+    symbol: &str,
+    date_symbol_changed: Option<&str>,
+) -> Option<String> {
+    match symbol_match {
+        SymbolMatch::Approved => None,
+        SymbolMatch::Previous => Some(match date_symbol_changed {
+            Some(date) => format!(
+                "queried symbol {} was replaced by {} on {}; returning current record",
+                query, symbol, date
+            ),
+            None => format!(
+                "queried symbol {} was replaced by {}; returning current record",
+                query, symbol
+            ),
+        }),
+        SymbolMatch::Alias => Some(format!(
+            "queried symbol {} is an alias of {}; returning current record",
+            query, symbol
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::approved("PCLAF", Some(SymbolMatch::Approved))]
+    #[case::approved_case("pclaf", Some(SymbolMatch::Approved))]
+    #[case::previous("KIAA0101", Some(SymbolMatch::Previous))]
+    #[case::alias("PAF15", Some(SymbolMatch::Alias))]
+    #[case::none("BRAF", None)]
+    fn match_symbol_paths(#[case] query: &str, #[case] expected: Option<SymbolMatch>) {
+        assert_eq!(
+            match_symbol(
+                query,
+                "PCLAF",
+                &[String::from("KIAA0101")],
+                &[String::from("PAF15"), String::from("OEATC-1")],
+            ),
+            expected
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::approved("PCLAF", SymbolMatch::Approved, Some("2015-06-12"), None)]
+    #[case::previous(
+        "KIAA0101",
+        SymbolMatch::Previous,
+        Some("2015-06-12"),
+        Some(
+            "queried symbol KIAA0101 was replaced by PCLAF on 2015-06-12; returning current record"
+        )
+    )]
+    #[case::previous_without_date(
+        "KIAA0101",
+        SymbolMatch::Previous,
+        None,
+        Some("queried symbol KIAA0101 was replaced by PCLAF; returning current record")
+    )]
+    #[case::alias(
+        "PAF15",
+        SymbolMatch::Alias,
+        Some("2015-06-12"),
+        Some("queried symbol PAF15 is an alias of PCLAF; returning current record")
+    )]
+    fn symbol_notice_paths(
+        #[case] query: &str,
+        #[case] symbol_match: SymbolMatch,
+        #[case] date_symbol_changed: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            symbol_notice(query, symbol_match, "PCLAF", date_symbol_changed).as_deref(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Import of gnomAD coverage summary TSV files.
+
+use std::{
+    io::{BufRead, BufReader},
+    sync::Arc,
+};
+
+use clap::Parser;
+
+use crate::{common, gnomad_coverage};
+
+/// Command line arguments for `gnomad-coverage import` sub command.
+#[derive(Parser, Debug, Clone)]
+#[command(about = "import gnomAD coverage data into RocksDB", long_about = None)]
+pub struct Args {
+    /// Genome build to use in the build.
+    #[arg(long, value_enum)]
+    pub genome_release: common::cli::GenomeRelease,
+    /// Path to input coverage summary TSV file, e.g.,
+    /// `gnomad.genomes.r2.1.coverage.summary.tsv.bgz`, possibly (b)gzip-ed.
+    #[arg(long, required = true)]
+    pub path_in_tsv: String,
+    /// Path to output RocksDB directory.
+    #[arg(long)]
+    pub path_out_rocksdb: String,
+    /// Version of the gnomAD data, e.g., `2.1` or `4.0`.
+    #[arg(long)]
+    pub gnomad_version: String,
+
+    /// Name of the column family to import into.
+    #[arg(long, default_value = gnomad_coverage::CF_NAME)]
+    pub cf_name: String,
+    /// Optional path to RocksDB WAL directory.
+    #[arg(long)]
+    pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Columns with the position of a coverage file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocusColumns {
+    /// Separate `chrom` and `pos` columns as in gnomAD v2.
+    ChromPos(usize, usize),
+    /// One `locus` column with `CHROM:POS` as in gnomAD v3 and later.
+    Locus(usize),
+}
+
+/// Indices of the used columns of a coverage file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    /// The column(s) with the position.
+    pub locus: LocusColumns,
+    /// The `mean` depth column.
+    pub mean: usize,
+    /// The `over_20` column with the fraction of samples over 20x.
+    pub over_20: usize,
+}
+
+impl Columns {
+    /// Find the columns in the `header` line, with or without a leading `#`.
+    pub fn from_header(header: &str) -> Result<Self, anyhow::Error> {
+        let names = header
+            .strip_prefix('#')
+            .unwrap_or(header)
+            .split('\t')
+            .collect::<Vec<_>>();
+        let find = |name: &str| names.iter().position(|column| *column == name);
+        let require = |name: &str| {
+            find(name).ok_or_else(|| anyhow::anyhow!("missing column {:?} in header", name))
+        };
+        let locus = match (find("locus"), find("chrom"), find("pos")) {
+            (Some(locus), _, _) => LocusColumns::Locus(locus),
+            (None, Some(chrom), Some(pos)) => LocusColumns::ChromPos(chrom, pos),
+            _ => anyhow::bail!("missing column \"locus\" or \"chrom\" and \"pos\" in header"),
+        };
+        Ok(Self {
+            locus,
+            mean: require("mean")?,
+            over_20: require("over_20")?,
+        })
+    }
+
+    /// Parse the chromosome, position, mean depth, and fraction over 20x of `line`.
+    pub fn parse<'a>(&self, line: &'a str) -> Result<(&'a str, i32, f32, f32), anyhow::Error> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let field = |idx: usize| {
+            fields
+                .get(idx)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("missing column {} in line {:?}", idx + 1, line))
+        };
+        let (chrom, pos) = match self.locus {
+            LocusColumns::ChromPos(chrom, pos) => (field(chrom)?, field(pos)?),
+            LocusColumns::Locus(locus) => field(locus)?
+                .rsplit_once(':')
+                .ok_or_else(|| anyhow::anyhow!("invalid locus in line {:?}", line))?,
+        };
+        let parse_f32 = |idx: usize| {
+            field(idx)?
+                .parse::<f32>()
+                .map_err(|e| anyhow::anyhow!("invalid number in line {:?}: {}", line, e))
+        };
+        Ok((
+            chrom,
+            pos.parse()
+                .map_err(|e| anyhow::anyhow!("invalid position in line {:?}: {}", line, e))?,
+            parse_f32(self.mean)?,
+            parse_f32(self.over_20)?,
+        ))
+    }
+}
+
+/// Open the coverage file at `path`, decompressing files ending in `.gz` or `.bgz`.
+fn open_reader(path: &str) -> Result<Box<dyn BufRead>, anyhow::Error> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("problem opening {}: {}", path, e))?;
+    Ok(if path.ends_with(".gz") || path.ends_with(".bgz") {
+        Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+/// Read the columns from the header of the coverage file at `path`.
+pub fn read_columns(path: &str) -> Result<Columns, anyhow::Error> {
+    let mut header = String::new();
+    open_reader(path)?.read_line(&mut header)?;
+    Columns::from_header(header.trim_end())
+        .map_err(|e| anyhow::anyhow!("invalid header in {}: {}", path, e))
+}
+
+/// Number of positions to write at once.
+const BATCH_SIZE: usize = 100_000;
+
+/// Perform import of the coverage file.
+fn tsv_import(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    let cf_data = db.cf_handle(&args.cf_name).unwrap();
+    let columns = read_columns(&args.path_in_tsv)?;
+
+    // Write the positions in batches as the files have one line per position.
+    let mut batch = rocksdb::WriteBatch::default();
+    let mut skipped = 0usize;
+    for line in open_reader(&args.path_in_tsv)?.lines().skip(1) {
+        let line = line?;
+        let (chrom, pos, mean, fraction_over_20x) = columns.parse(&line)?;
+        if !common::cli::is_canonical(chrom) {
+            skipped += 1;
+            continue;
+        }
+
+        let key: Vec<u8> = common::keys::Pos::from(chrom, pos).into();
+        batch.put_cf(
+            &cf_data,
+            key,
+            gnomad_coverage::encode(mean, fraction_over_20x),
+        );
+        if batch.len() >= BATCH_SIZE {
+            db.write(std::mem::take(&mut batch))?;
+        }
+    }
+    db.write(batch)?;
+
+    if skipped > 0 {
+        tracing::warn!("skipped {} positions on non-canonical contigs", skipped);
+    }
+
+    Ok(())
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if validation.input_file(&args.path_in_tsv) {
+        validation.check(
+            format!("coverage header of {} is valid", &args.path_in_tsv),
+            read_columns(&args.path_in_tsv),
+        );
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
+/// Implementation of `gnomad-coverage import` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'gnomad-coverage import' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
+    // Open the RocksDB for writing.
+    tracing::info!("Opening RocksDB for writing ...");
+    let before_opening_rocksdb = std::time::Instant::now();
+    let options = rocksdb_utils_lookup::tune_options(
+        rocksdb::Options::default(),
+        args.path_wal_dir.as_ref().map(|s| s.as_ref()),
+    );
+    let cf_names = &["meta", &args.cf_name];
+    let db = Arc::new(rocksdb::DB::open_cf_with_opts(
+        &options,
+        common::readlink_f(&args.path_out_rocksdb)?,
+        cf_names
+            .iter()
+            .map(|name| (name.to_string(), options.clone()))
+            .collect::<Vec<_>>(),
+    )?);
+    tracing::info!("  writing meta information");
+    let cf_meta = db.cf_handle("meta").unwrap();
+    db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    db.put_cf(
+        &cf_meta,
+        "genome-release",
+        format!("{}", args.genome_release),
+    )?;
+    db.put_cf(&cf_meta, "db-name", "gnomad-coverage")?;
+    db.put_cf(&cf_meta, "gnomad-version", &args.gnomad_version)?;
+    tracing::info!(
+        "... done opening RocksDB for writing in {:?}",
+        before_opening_rocksdb.elapsed()
+    );
+
+    tracing::info!("Importing TSV file ...");
+    let before_import = std::time::Instant::now();
+    tsv_import(&db, args)?;
+    tracing::info!(
+        "... done importing TSV file in {:?}",
+        before_import.elapsed()
+    );
+
+    tracing::info!("Running RocksDB compaction ...");
+    let before_compaction = std::time::Instant::now();
+    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+    tracing::info!(
+        "... done compacting RocksDB in {:?}",
+        before_compaction.elapsed()
+    );
+
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "gnomad-coverage".to_string(),
+            title: "gnomAD coverage".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("gnomAD".to_string(), args.gnomad_version.clone())],
+            paths_in: vec![args.path_in_tsv.clone()],
+        },
+    )?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use clap_verbosity_flag::Verbosity;
+    use temp_testdir::TempDir;
+
+    #[rstest::rstest]
+    #[case::v2(
+        "chrom\tpos\tmean\tmedian\tover_1\tover_5\tover_10\tover_15\tover_20\tover_25\tover_30\tover_50\tover_100",
+        "1\t55505599\t31.5\t31\t1.0\t1.0\t0.99\t0.98\t0.9375\t0.8\t0.5\t0.01\t0.0",
+        "1"
+    )]
+    #[case::v3(
+        "locus\tmean\tmedian_approx\ttotal_DP\tover_0\tover_1\tover_5\tover_10\tover_15\tover_20\tover_25\tover_30\tover_50\tover_100",
+        "chr1:55505599\t31.5\t31\t2386102\t1.0\t1.0\t1.0\t0.99\t0.98\t0.9375\t0.8\t0.5\t0.01\t0.0",
+        "chr1"
+    )]
+    fn columns_parse(#[case] header: &str, #[case] line: &str, #[case] chrom: &str) {
+        let columns = Columns::from_header(header).unwrap();
+
+        assert_eq!(
+            columns.parse(line).unwrap(),
+            (chrom, 55505599, 31.5, 0.9375)
+        );
+    }
+
+    #[test]
+    fn columns_from_header_invalid() {
+        assert_eq!(
+            Columns::from_header("chrom\tpos\tmedian\tover_20")
+                .unwrap_err()
+                .to_string(),
+            "missing column \"mean\" in header"
+        );
+        assert!(Columns::from_header("chrom\tmean\tover_20").is_err());
+    }
+
+    #[test]
+    fn smoke_test_import_v3() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch38,
+            path_in_tsv: String::from(
+                "tests/gnomad-coverage/example/gnomad.genomes.r3.0.1.coverage.tsv",
+            ),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            gnomad_version: String::from("3.0.1"),
+            cf_name: String::from(gnomad_coverage::CF_NAME),
+            path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
+        };
+
+        run(&common, &args)?;
+
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                &args.path_out_rocksdb,
+                ["meta", gnomad_coverage::CF_NAME],
+                false,
+            )?;
+        let records = gnomad_coverage::fetch_range(
+            &db,
+            gnomad_coverage::CF_NAME,
+            common::keys::Pos::from("1", 1),
+            common::keys::Pos::from("1", 100_000_000),
+        )?;
+        // The position on `chrUn_KI270302v1` is skipped.
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0].pos, 55039836);
+        assert_eq!(
+            (records[0].mean, records[0].fraction_over_20x),
+            (28.75, 0.8125)
+        );
+
+        Ok(())
+    }
+
+    /// Dry run must not create the database and must find the invalid header.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch38,
+            path_in_tsv: String::from(
+                "tests/gnomad-coverage/example/gnomad.genomes.r3.0.1.coverage.tsv",
+            ),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            gnomad_version: String::from("3.0.1"),
+            cf_name: String::from(gnomad_coverage::CF_NAME),
+            path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_tsv: String::from("tests/gnomad-coverage/example/bootstrap.sh"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+//! Command line interface for gnomAD coverage data.
+
+pub mod import;
@@ -0,0 +1,260 @@
+//! Per-position gnomAD coverage, i.e., mean depth and fraction of samples over 20x.
+//!
+//! Each position is stored under its `keys::Pos` key with the mean depth and the fraction
+//! over 20x as little-endian `f32` values.  Positions that are not in the coverage file are
+//! not stored.
+
+pub mod cli;
+
+use crate::common::{self, keys};
+
+/// Default name of the column family with the coverage.
+pub const CF_NAME: &str = "gnomad_coverage_data";
+
+/// The coverage at one position.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Record {
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based position.
+    pub pos: i32,
+    /// Mean depth of coverage.
+    pub mean: f32,
+    /// Fraction of samples with a depth of coverage over 20x.
+    pub fraction_over_20x: f32,
+}
+
+/// Summary of the coverage in a bin of positions, see `summarize`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Bin {
+    /// Chromosome name.
+    pub chrom: String,
+    /// 1-based, inclusive start position.
+    pub start: i32,
+    /// 1-based, inclusive stop position.
+    pub stop: i32,
+    /// Number of positions in the bin with coverage data.
+    pub positions: usize,
+    /// Average of the mean depths of the positions.
+    pub mean: f32,
+    /// Smallest mean depth of the positions.
+    pub min_mean: f32,
+    /// Average of the fractions over 20x of the positions.
+    pub fraction_over_20x: f32,
+}
+
+/// Encode the `mean` depth and `fraction_over_20x` for the database.
+pub fn encode(mean: f32, fraction_over_20x: f32) -> [u8; 8] {
+    let mut result = [0u8; 8];
+    result[..4].copy_from_slice(&mean.to_le_bytes());
+    result[4..].copy_from_slice(&fraction_over_20x.to_le_bytes());
+    result
+}
+
+/// Decode the mean depth and fraction over 20x from the database.
+pub fn decode(value: &[u8]) -> Result<(f32, f32), anyhow::Error> {
+    let value: [u8; 8] = value
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("invalid coverage value of {} bytes", value.len()))?;
+    Ok((
+        f32::from_le_bytes(value[..4].try_into().expect("4 bytes")),
+        f32::from_le_bytes(value[4..].try_into().expect("4 bytes")),
+    ))
+}
+
+/// Fetch the coverage from `start` to `stop` (inclusive).
+pub fn fetch_range(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    start: keys::Pos,
+    stop: keys::Pos,
+) -> Result<Vec<Record>, anyhow::Error> {
+    let cf_data = db
+        .cf_handle(cf_name)
+        .ok_or_else(|| anyhow::anyhow!("no column family {}", cf_name))?;
+    let mut iter = db.raw_iterator_cf_opt(
+        &cf_data,
+        common::prefix_bloom::scan_read_options(&start, &stop),
+    );
+    let start: Vec<u8> = start.into();
+    iter.seek(&start);
+
+    let mut result = Vec::new();
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        let pos: keys::Pos = key.into();
+        if pos.chrom != stop.chrom || pos.pos > stop.pos {
+            break;
+        }
+        let (mean, fraction_over_20x) = decode(value)?;
+        result.push(Record {
+            chrom: pos.chrom,
+            pos: pos.pos,
+            mean,
+            fraction_over_20x,
+        });
+        iter.next();
+    }
+    iter.status()?;
+
+    Ok(result)
+}
+
+/// Summarize sorted `records` from `start` to `stop` into bins of `resolution` positions.
+///
+/// The last bin is clipped at `stop`.  Bins without coverage data are omitted, as are
+/// positions without coverage data in the per-position records.
+pub fn summarize(records: &[Record], start: i32, stop: i32, resolution: u32) -> Vec<Bin> {
+    let resolution = resolution.max(1) as i32;
+    let mut result: Vec<Bin> = Vec::new();
+    for record in records {
+        let bin_start = start + (record.pos - start).div_euclid(resolution) * resolution;
+        match result.last_mut() {
+            Some(bin) if bin.start == bin_start => {
+                // Sums until all records are seen, see below.
+                bin.positions += 1;
+                bin.mean += record.mean;
+                bin.min_mean = bin.min_mean.min(record.mean);
+                bin.fraction_over_20x += record.fraction_over_20x;
+            }
+            _ => result.push(Bin {
+                chrom: record.chrom.clone(),
+                start: bin_start,
+                stop: (bin_start + resolution - 1).min(stop),
+                positions: 1,
+                mean: record.mean,
+                min_mean: record.mean,
+                fraction_over_20x: record.fraction_over_20x,
+            }),
+        }
+    }
+    for bin in result.iter_mut() {
+        bin.mean /= bin.positions as f32;
+        bin.fraction_over_20x /= bin.positions as f32;
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Import the GRCh37 example coverage and return the path to the database.
+    pub(crate) fn import_coverage(tmp_dir: &temp_testdir::TempDir) -> String {
+        let path_rocksdb = format!("{}", tmp_dir.join("gnomad-coverage").display());
+        cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_tsv: String::from(
+                    "tests/gnomad-coverage/example/gnomad.genomes.r2.1.coverage.tsv",
+                ),
+                path_out_rocksdb: path_rocksdb.clone(),
+                gnomad_version: String::from("2.1"),
+                cf_name: String::from(CF_NAME),
+                path_wal_dir: None,
+                dry_run: false,
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        path_rocksdb
+    }
+
+    /// Return a record at `pos` on chromosome 1.
+    fn record(pos: i32, mean: f32, fraction_over_20x: f32) -> Record {
+        Record {
+            chrom: String::from("1"),
+            pos,
+            mean,
+            fraction_over_20x,
+        }
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() -> Result<(), anyhow::Error> {
+        assert_eq!(decode(&encode(31.25, 0.9375))?, (31.25, 0.9375));
+        assert!(decode(b"abcd").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_range_example() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                import_coverage(&tmp_dir),
+                ["meta", CF_NAME],
+                false,
+            )?;
+
+        let records = fetch_range(
+            &db,
+            CF_NAME,
+            keys::Pos::from("1", 55505598),
+            keys::Pos::from("1", 55505600),
+        )?;
+
+        assert_eq!(
+            records,
+            vec![
+                record(55505598, 31.25, 0.9375),
+                record(55505599, 31.5, 0.9375),
+                record(55505600, 30.75, 0.875),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn summarize_bins() {
+        let records = vec![
+            record(10, 30.0, 1.0),
+            record(11, 20.0, 0.5),
+            record(13, 10.0, 0.25),
+            record(19, 40.0, 1.0),
+        ];
+
+        assert_eq!(
+            summarize(&records, 10, 20, 3),
+            vec![
+                Bin {
+                    chrom: String::from("1"),
+                    start: 10,
+                    stop: 12,
+                    positions: 2,
+                    mean: 25.0,
+                    min_mean: 20.0,
+                    fraction_over_20x: 0.75,
+                },
+                Bin {
+                    chrom: String::from("1"),
+                    start: 13,
+                    stop: 15,
+                    positions: 1,
+                    mean: 10.0,
+                    min_mean: 10.0,
+                    fraction_over_20x: 0.25,
+                },
+                Bin {
+                    chrom: String::from("1"),
+                    start: 19,
+                    stop: 20,
+                    positions: 1,
+                    mean: 40.0,
+                    min_mean: 40.0,
+                    fraction_over_20x: 1.0,
+                },
+            ]
+        );
+        assert_eq!(summarize(&records, 10, 20, 1).len(), 4);
+    }
+}
@@ -47,12 +47,25 @@ pub struct Args {
     /// specified, the default fields are configured.
     #[arg(long)]
     pub import_fields_json: Option<String>,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform TBI-parallel import of the data.
 fn tsv_import(
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     args: &Args,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Load tabix header and create BGZF reader with tabix index.
     let tabix_src = format!("{}.tbi", args.path_in_vcf);
@@ -91,7 +104,7 @@ fn tsv_import(
     windows
         .par_iter()
         .progress_with(common::cli::progress_bar(windows.len()))
-        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args))
+        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args, sampler))
         .collect::<Result<Vec<_>, _>>()?;
     tracing::info!(
         "... done loading gnomad_mtdna VCF file into RocksDB in {:?}",
@@ -108,11 +121,14 @@ fn process_window(
     begin: usize,
     end: usize,
     args: &Args,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     let cf_gnomad = db.cf_handle(&args.cf_name).unwrap();
     let mut reader =
         noodles::vcf::io::indexed_reader::Builder::default().build_from_path(&args.path_in_vcf)?;
     let header = reader.read_header()?;
+    // The haplogroup counts are skipped if the header does not define the haplogroup order.
+    let haplogroups = pbs::gnomad::mtdna::haplogroups_from_header(&header).unwrap_or_default();
 
     let raw_region = format!("{}:{}-{}", chrom, begin + 1, end);
     tracing::debug!("  processing region: {}", raw_region);
@@ -135,25 +151,36 @@ fn process_window(
 
     // Process the result (skip if determined above that the sequence does not
     // exist).
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     if let Some(query) = query {
         for result in query {
             let vcf_record = result?;
             let vcf_record = RecordBuf::try_from_variant_record(&header, &vcf_record)?;
 
             // Process each alternate allele into one record.
-            let details_options = serde_json::from_str(
+            let details_options: pbs::gnomad::mtdna::DetailsOptions = serde_json::from_str(
                 args.import_fields_json
                     .as_ref()
                     .expect("has been set earlier"),
             )?;
             for allele_no in 0..vcf_record.alternate_bases().len() {
-                let key_buf: Vec<u8> =
-                    common::keys::Var::from_vcf_allele(&vcf_record, allele_no).into();
-                let record = pbs::gnomad::mtdna::Record::from_vcf_allele(
+                let var = common::keys::Var::from_vcf_allele(&vcf_record, allele_no);
+                let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+                    continue;
+                };
+                let key_buf: Vec<u8> = var.into();
+                if !sampler.keep(&key_buf) {
+                    continue;
+                }
+                let mut record = pbs::gnomad::mtdna::Record::from_vcf_allele(
                     &vcf_record,
                     allele_no,
                     &details_options,
                 )?;
+                if details_options.haplogroup_counts && !haplogroups.is_empty() {
+                    record.haplogroup_counts =
+                        pbs::gnomad::mtdna::HaplogroupCounts::from_vcf(&vcf_record, &haplogroups)?;
+                }
                 tracing::trace!("  record: {:?}", &record);
                 let record_buf = record.encode_to_vec();
                 db.put_cf(&cf_gnomad, &key_buf, &record_buf)?;
@@ -161,9 +188,26 @@ fn process_window(
         }
     }
 
+    invalid_alleles.log(&raw_region);
+
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if validation.input_file(&args.path_in_vcf) {
+        validation.tabix_index(&args.path_in_vcf);
+        validation.vcf_header(&args.path_in_vcf, args.genome_release);
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `gnomad_mtdna import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     // Put defaults for fields to serialize into args.
@@ -189,6 +233,12 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+    let sampler = common::sample::Sampler::new(&args.sample)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -209,17 +259,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "gnomad-version", &args.gnomad_version)?;
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
     db.put_cf(
         &cf_meta,
         "genome-release",
         format!("{}", args.genome_release),
     )?;
+    sampler.write_meta(&db, &cf_meta)?;
     tracing::info!(
         "... done opening RocksDB for writing in {:?}",
         before_opening_rocksdb.elapsed()
     );
 
-    tsv_import(db.clone(), &args)?;
+    tsv_import(db.clone(), &args, &sampler)?;
+    sampler.log();
 
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
@@ -229,6 +282,19 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "gnomad-mtdna".to_string(),
+            title: "gnomAD mtDNA".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("gnomAD mtDNA".to_string(), args.gnomad_version.clone())],
+            paths_in: vec![args.path_in_vcf.clone()],
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -247,6 +313,7 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
@@ -255,10 +322,50 @@ mod test {
             cf_name: String::from("gnomad_mtdna_data"),
             gnomad_version: String::from("3.1.1"),
             path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(&DetailsOptions::with_all_enabled())?),
         };
 
         run(&common, &args)
     }
+
+    /// Dry run must not create the database and must find the missing tabix index.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_vcf: String::from("tests/gnomad-mtdna/example/gnomad-mtdna.vcf.bgz"),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("gnomad_mtdna_data"),
+            gnomad_version: String::from("3.1.1"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: true,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+            import_fields_json: None,
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_vcf: String::from("tests/gnomad-mtdna/example/gnomad-mtdna.vcf"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(validation.problems[0].starts_with("tabix index"));
+        assert!(run(&common, &args).is_err());
+    }
 }
@@ -1,11 +1,9 @@
 //! Query of gnomAD-mtDNA annotation data.
 
-use std::{io::Write, sync::Arc};
-
-use prost::Message;
+use std::sync::Arc;
 
 use crate::{
-    common::{self, cli::extract_chrom, keys, spdi},
+    common::{self, cli::extract_chrom, decode::decode_protobuf, keys, spdi},
     cons::cli::args::vars::ArgsQuery,
     pbs,
 };
@@ -15,8 +13,11 @@ use crate::{
 #[command(about = "query gnomAD-mtDNA data stored in RocksDB", long_about = None)]
 pub struct Args {
     /// Path to RocksDB directory with data.
-    #[arg(long)]
-    pub path_rocksdb: String,
+    #[arg(long, required_unless_present = "server_url")]
+    pub path_rocksdb: Option<String>,
+    /// URL of a running annonars server to query instead of a local RocksDB.
+    #[arg(long, conflicts_with = "path_rocksdb")]
+    pub server_url: Option<String>,
     /// Name of the column family to import into.
     #[arg(long, default_value = "gnomad_mtdna_data")]
     pub cf_name: String,
@@ -24,8 +25,8 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
@@ -37,6 +38,8 @@ pub struct Args {
 pub struct Meta {
     /// Genome release of data in database.
     pub genome_release: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -50,23 +53,25 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names: &[&str; 2] = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
     tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -79,21 +84,11 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 pub fn open_rocksdb_from_args(
     args: &Args,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
-    open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
-}
-
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &pbs::gnomad::mtdna::Record,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
-    }
-
-    Ok(())
+    let path_rocksdb = args
+        .path_rocksdb
+        .as_ref()
+        .ok_or_else(|| crate::ErrorCategory::Usage.error("missing --path-rocksdb"))?;
+    open_rocksdb(path_rocksdb, &args.cf_name, "meta")
 }
 
 /// Query for a single variant in the RocksDB database.
@@ -101,7 +96,7 @@ pub fn query_for_variant(
     variant: &common::spdi::Var,
     meta: &Meta,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<Option<pbs::gnomad::mtdna::Record>, anyhow::Error> {
     // Split off the genome release (checked) and convert to key as used in database.
     let query = spdi::Var {
@@ -110,18 +105,16 @@ pub fn query_for_variant(
     };
     // Execute query.
     tracing::debug!("query = {:?}", &query);
-    let var: keys::Var = query.into();
-    let key: Vec<u8> = var.into();
+    let key = common::norm::query_key(query, meta.keys_normalized);
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
     let raw_value = db
-        .get_cf(cf_data, key)
+        .get_cf(&cf_data, &key)
         .map_err(|e| anyhow::anyhow!("error reading from RocksDB: {}", e))?;
-    raw_value
+    Ok(raw_value
         .map(|raw_value| {
-            // Decode via prost.
-            pbs::gnomad::mtdna::Record::decode(&mut std::io::Cursor::new(&raw_value))
-                .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))
+            decode_protobuf::<pbs::gnomad::mtdna::Record>(db, cf_data_name, &key, &raw_value)
         })
-        .transpose()
+        .transpose()?)
 }
 
 /// Implementation of `tsv query` sub command.
@@ -129,24 +122,37 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("Starting 'gnomad-mtdna query' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
+    common::batch::check_unsupported(&args.query, "gnomad-mtdna query")?;
+
+    if let Some(server_url) = args.server_url.as_ref() {
+        return common::remote::run_query(
+            server_url,
+            &args.query,
+            "gnomad_mtdna",
+            &args.out_file,
+            args.output.output_format,
+        );
+    }
 
     let (db, meta) = open_rocksdb_from_args(args)?;
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "gnomad-mtdna",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
     if let Some(variant) = args.query.variant.as_ref() {
-        if let Some(record) = query_for_variant(variant, &meta, &db, &cf_data)? {
-            print_record(&mut out_writer, args.out_format, &record)?;
+        if let Some(record) = query_for_variant(variant, &meta, &db, &args.cf_name)? {
+            out_writer.write(
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &record,
+            )?;
         } else {
             tracing::info!("no record found for variant {:?}", &variant);
         }
@@ -158,10 +164,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             };
             (Some(position.clone()), Some(position))
         } else if let Some(range) = args.query.range.as_ref() {
-            let range = spdi::Range {
-                sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-                ..range.clone()
-            };
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
             let (start, stop) = range.into();
             (Some(start), Some(stop))
         } else if args.query.all {
@@ -203,10 +206,10 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                     }
                 }
 
-                let record =
-                    pbs::gnomad::mtdna::Record::decode(&mut std::io::Cursor::new(&raw_value))
-                        .map_err(|e| anyhow::anyhow!("failed to decode record: {}", e))?;
-                print_record(&mut out_writer, args.out_format, &record)?;
+                let iter_key = iter.key().unwrap();
+                let record: pbs::gnomad::mtdna::Record =
+                    decode_protobuf(&db, &args.cf_name, iter_key, raw_value)?;
+                out_writer.write(&common::output::db_key(iter_key), &record)?;
                 iter.next();
             } else {
                 break;
@@ -231,12 +234,16 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            path_rocksdb: String::from("tests/gnomad-mtdna/example/gnomad-mtdna.vcf.bgz.db"),
+            path_rocksdb: Some(String::from(
+                "tests/gnomad-mtdna/example/gnomad-mtdna.vcf.bgz.db",
+            )),
+            server_url: None,
             cf_name: String::from("gnomad_mtdna_data"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             query,
         };
 
@@ -346,4 +353,82 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn smoke_query_var_haplogroup_counts() -> Result<(), anyhow::Error> {
+        let (common, args, temp) = args(ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:M:12544:A:G")?),
+            ..Default::default()
+        });
+        let path_rocksdb = temp.join("rocksdb").to_string_lossy().to_string();
+        crate::gnomad_mtdna::cli::import::run(
+            &common,
+            &crate::gnomad_mtdna::cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_vcf: String::from("tests/gnomad-mtdna/example/gnomad-mtdna.vcf.bgz"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("gnomad_mtdna_data"),
+                gnomad_version: String::from("3.1.1"),
+                path_wal_dir: None,
+                allow_iupac: false,
+                dry_run: false,
+                sample: Default::default(),
+                spec: Default::default(),
+                tbi_window_size: 1_000_000,
+                import_fields_json: Some(serde_json::to_string(
+                    &pbs::gnomad::mtdna::DetailsOptions::with_all_enabled(),
+                )?),
+            },
+        )?;
+        let args = Args {
+            path_rocksdb: Some(path_rocksdb),
+            ..args
+        };
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        insta::assert_snapshot!(&out_data);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:M:12544:A:G").unwrap()),
+            ..Default::default()
+        },
+        &["MT:12544:A:G"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:M:1:7").unwrap()),
+            ..Default::default()
+        },
+        &["MT:3:T:C", "MT:6:C:CCTCAA", "MT:7:A:G"],
+    )]
+    fn smoke_query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args_v1, _temp_v1) = args(query.clone());
+        run(&common, &args_v1)?;
+        let (common, args_v2, _temp_v2) = args(query);
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            "gnomad-mtdna",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
 }
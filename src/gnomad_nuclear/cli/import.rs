@@ -13,6 +13,7 @@ use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::{
     common::{self},
+    gnomad_nuclear::fields::{self, FieldMapping},
     pbs::gnomad::{gnomad2, gnomad3},
 };
 
@@ -86,9 +87,9 @@ pub struct Args {
     /// The data version to write out.
     #[arg(long)]
     pub gnomad_version: String,
-    /// Genome build to use in the build.
+    /// Genome build to use in the build, `auto` detects it from the VCF headers.
     #[arg(long, value_enum)]
-    pub genome_release: common::cli::GenomeRelease,
+    pub genome_release: common::assembly_detect::GenomeReleaseArg,
 
     /// Windows size for TBI-based parallel import.
     #[arg(long, default_value = "100000")]
@@ -100,10 +101,29 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Compact each chromosome after its import instead of running one full compaction at
+    /// the end (lowers peak disk usage, see `common::compaction`).
+    #[arg(long)]
+    pub incremental_compaction: bool,
+    /// Write prefix bloom filters for faster point lookups (see `common::prefix_bloom`).
+    #[arg(long)]
+    pub prefix_bloom: bool,
     /// JSON formatted configuration of which fields to import from gnomAD-mtDNA.  If not
     /// specified, the default fields are configured.
     #[arg(long)]
     pub import_fields_json: Option<String>,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform TBI-parallel import of one file.
@@ -112,6 +132,9 @@ fn vcf_import(
     args: &Args,
     path_in_vcf: &str,
     gnomad_version: GnomadVersion,
+    field_mapping: &FieldMapping,
+    genome_release: common::cli::GenomeRelease,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Load tabix header and create BGZF reader with tabix index.
     let tabix_src = format!("{}.tbi", path_in_vcf);
@@ -135,7 +158,7 @@ fn vcf_import(
 
     // Generate list of regions on canonical chromosomes, limited to those present in header.
     let windows =
-        common::cli::build_genome_windows(args.genome_release.into(), Some(args.tbi_window_size))?
+        common::cli::build_genome_windows(genome_release.into(), Some(args.tbi_window_size))?
             .into_iter()
             .filter_map(|(window_chrom, begin, end)| {
                 let canon_chrom = common::cli::canonicalize(&window_chrom);
@@ -145,26 +168,40 @@ fn vcf_import(
             })
             .collect::<Vec<_>>();
 
-    windows
-        .par_iter()
-        .progress_with(common::cli::progress_bar(windows.len()))
-        .map(|(chrom, begin, end)| {
-            process_window(
-                db.clone(),
-                chrom,
-                *begin,
-                *end,
-                args,
-                path_in_vcf,
-                gnomad_version,
-            )
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let import_windows = |windows: &[(String, usize, usize)]| {
+        windows
+            .par_iter()
+            .progress_with(common::cli::progress_bar(windows.len()))
+            .map(|(chrom, begin, end)| {
+                process_window(
+                    db.clone(),
+                    chrom,
+                    *begin,
+                    *end,
+                    args,
+                    path_in_vcf,
+                    gnomad_version,
+                    field_mapping,
+                    sampler,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    if args.incremental_compaction {
+        // Import one chromosome after the other and compact it right away.
+        for chrom_windows in windows.chunk_by(|(lhs, _, _), (rhs, _, _)| lhs == rhs) {
+            import_windows(chrom_windows)?;
+            common::compaction::compact_chrom(&db, &args.cf_name, &chrom_windows[0].0)?;
+        }
+    } else {
+        import_windows(&windows)?;
+    }
 
     Ok(())
 }
 
 /// Process one window.
+#[allow(clippy::too_many_arguments)]
 fn process_window(
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     chrom: &str,
@@ -173,6 +210,8 @@ fn process_window(
     args: &Args,
     path_in_vcf: &str,
     gnomad_version: GnomadVersion,
+    field_mapping: &FieldMapping,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     let cf_gnomad = db.cf_handle(&args.cf_name).unwrap();
     let mut reader =
@@ -200,15 +239,23 @@ fn process_window(
 
     // Process the result (skip if determined above that the sequence does not
     // exist).
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     if let Some(query) = query {
         for result in query {
             let vcf_record = result?;
-            let vcf_record = RecordBuf::try_from_variant_record(&header, &vcf_record)?;
+            let mut vcf_record = RecordBuf::try_from_variant_record(&header, &vcf_record)?;
+            field_mapping.apply(&mut vcf_record);
 
             // Process each alternate allele into one record.
             for allele_no in 0..vcf_record.alternate_bases().len() {
-                let key_buf: Vec<u8> =
-                    common::keys::Var::from_vcf_allele(&vcf_record, allele_no).into();
+                let var = common::keys::Var::from_vcf_allele(&vcf_record, allele_no);
+                let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+                    continue;
+                };
+                let key_buf: Vec<u8> = var.into();
+                if !sampler.keep(&key_buf) {
+                    continue;
+                }
                 let record_buf = match gnomad_version {
                     GnomadVersion::Two => {
                         let details_options = serde_json::from_str(
@@ -257,6 +304,8 @@ fn process_window(
         }
     }
 
+    invalid_alleles.log(&raw_region);
+
     Ok(())
 }
 
@@ -276,8 +325,78 @@ static MORE_HEADER_FIELDS_V4: &[&str] = &[
     "vrs_schema_version",
 ];
 
+/// Read the header of `path_in_vcf` and resolve the INFO fields to import.
+///
+/// Reports the fields found and missing and fails if required fields are missing.
+fn read_field_mapping(
+    path_in_vcf: &str,
+    gnomad_version: GnomadVersion,
+    gnomad_kind: GnomadKind,
+) -> Result<FieldMapping, anyhow::Error> {
+    let header = noodles::vcf::io::reader::Builder::default()
+        .build_from_path(path_in_vcf)?
+        .read_header()?;
+    let field_mapping =
+        FieldMapping::new(&header, fields::field_specs(gnomad_version, gnomad_kind)?)
+            .map_err(|e| e.context(format!("problem with INFO fields of {}", path_in_vcf)))?;
+    tracing::info!("INFO fields of {}:", path_in_vcf);
+    field_mapping.report();
+    Ok(field_mapping)
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    validation.check(
+        format!("gnomAD version {} is supported", &args.gnomad_version),
+        args.gnomad_version.parse::<GnomadVersion>().and_then(
+            |gnomad_version| match gnomad_version {
+                GnomadVersion::Two | GnomadVersion::Three | GnomadVersion::Four => Ok(()),
+                _ => Err(anyhow::anyhow!("gnomAD version must be either 2, 3, or 4")),
+            },
+        ),
+    );
+    if let Some(import_fields_json) = args.import_fields_json.as_ref() {
+        validation.check(
+            "import fields JSON can be parsed",
+            serde_json::from_str::<serde_json::Value>(import_fields_json)
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{}", e)),
+        );
+    }
+    let genome_release = validation.genome_release(args.genome_release, || {
+        common::assembly_detect::from_vcf_paths(&args.path_in_vcf)
+    });
+    for path_in_vcf in &args.path_in_vcf {
+        if validation.input_file(path_in_vcf) {
+            validation.tabix_index(path_in_vcf);
+            if let Some(genome_release) = genome_release {
+                validation.vcf_header(path_in_vcf, genome_release);
+            }
+            if let Ok(gnomad_version) = args.gnomad_version.parse::<GnomadVersion>() {
+                validation.check(
+                    format!("VCF file {} has the required INFO fields", path_in_vcf),
+                    read_field_mapping(path_in_vcf, gnomad_version, args.gnomad_kind).map(|_| ()),
+                );
+            }
+        }
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `gnomad_nuclear import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    if args.dry_run {
+        tracing::info!("Validating inputs for 'gnomad-nuclear import' command");
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     let gnomad_version: GnomadVersion = args.gnomad_version.parse()?;
     if !matches!(
         gnomad_version,
@@ -285,6 +404,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     ) {
         anyhow::bail!("gnomAD version must be either 2, 3, or 4");
     }
+    let sampler = common::sample::Sampler::new(&args.sample)?;
 
     // Put defaults for fields to serialize into args.
     let args = match gnomad_version {
@@ -328,6 +448,10 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    let genome_release = args
+        .genome_release
+        .resolve(|| common::assembly_detect::from_vcf_paths(&args.path_in_vcf))?;
+
     tracing::info!("Opening gnomAD-nuclear VCF file...");
     let before_loading = std::time::Instant::now();
     let mut reader_vcf =
@@ -375,6 +499,13 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_loading.elapsed()
     );
 
+    // Resolve the INFO fields of all files before writing anything.
+    let field_mappings = args
+        .path_in_vcf
+        .iter()
+        .map(|path_in_vcf| read_field_mapping(path_in_vcf, gnomad_version, args.gnomad_kind))
+        .collect::<Result<Vec<_>, _>>()?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -382,6 +513,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
+    let options = if args.prefix_bloom {
+        common::prefix_bloom::tune_options(options)
+    } else {
+        options
+    };
     let cf_names = &["meta", &args.cf_name];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
@@ -394,17 +530,18 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
-    db.put_cf(
-        &cf_meta,
-        "genome-release",
-        format!("{}", args.genome_release),
-    )?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
+    if args.prefix_bloom {
+        crate::common::prefix_bloom::write_meta(&db, &cf_meta)?;
+    }
+    db.put_cf(&cf_meta, "genome-release", format!("{}", genome_release))?;
     db.put_cf(
         &cf_meta,
         "gnomad-kind",
         args.gnomad_kind.to_string().to_lowercase(),
     )?;
     db.put_cf(&cf_meta, "gnomad-version", &args.gnomad_version)?;
+    sampler.write_meta(&db, &cf_meta)?;
     if let Some(vep_version) = vep_version {
         db.put_cf(&cf_meta, "gnomad-vep-version", vep_version)?;
     }
@@ -429,22 +566,51 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Loading gnomad_nuclear VCF file into RocksDB...");
     let before_loading = std::time::Instant::now();
-    for path_in_tsv in &args.path_in_vcf {
+    for (path_in_tsv, field_mapping) in args.path_in_vcf.iter().zip(&field_mappings) {
         tracing::info!("  importing file {} ...", &path_in_tsv);
-        vcf_import(db.clone(), &args, path_in_tsv, gnomad_version)?;
+        vcf_import(
+            db.clone(),
+            &args,
+            path_in_tsv,
+            gnomad_version,
+            field_mapping,
+            genome_release,
+            &sampler,
+        )?;
     }
     tracing::info!(
         "... done loading gnomad_nuclear VCF file into RocksDB in {:?}",
         before_loading.elapsed()
     );
+    sampler.log();
 
-    tracing::info!("Running RocksDB compaction ...");
-    let before_compaction = std::time::Instant::now();
-    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
-    tracing::info!(
-        "... done compacting RocksDB in {:?}",
-        before_compaction.elapsed()
-    );
+    if args.incremental_compaction {
+        tracing::info!("Skipping final RocksDB compaction, chromosomes have been compacted");
+    } else {
+        tracing::info!("Running RocksDB compaction ...");
+        let before_compaction = std::time::Instant::now();
+        rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+        tracing::info!(
+            "... done compacting RocksDB in {:?}",
+            before_compaction.elapsed()
+        );
+    }
+
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: format!("gnomad-{}", args.gnomad_kind),
+            title: format!("gnomAD {}", args.gnomad_kind),
+            genome_release: Some(genome_release),
+            created_from: vec![(
+                format!("gnomAD {}", args.gnomad_kind),
+                args.gnomad_version.clone(),
+            )],
+            paths_in: args.path_in_vcf.clone(),
+        },
+    )?;
 
     tracing::info!("All done. Have a nice day!");
     Ok(())
@@ -462,15 +628,22 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch37,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad2::DetailsOptions::with_all_enabled(),
@@ -482,20 +655,75 @@ mod test {
         run(&common, &args)
     }
 
+    /// `--genome-release auto` must write the detected release to the metadata.
+    #[rstest::rstest]
+    #[case("example-exomes-grch37/v2.1", "grch37")]
+    #[case("example-exomes-grch38/v2.1", "grch38")]
+    fn import_gnomad_exomes_auto_genome_release(
+        #[case] path: &str,
+        #[case] expected: &str,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Auto,
+            path_in_vcf: vec![format!(
+                "tests/gnomad-nuclear/{}/gnomad-exomes.vcf.bgz",
+                path
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("gnomad_nuclear_data"),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+            import_fields_json: None,
+            gnomad_kind: GnomadKind::Exomes,
+            gnomad_version: String::from("2.1"),
+        };
+        run(&common, &args)?;
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &args.path_out_rocksdb,
+            ["meta", "gnomad_nuclear_data"],
+            false,
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        let genome_release = db.get_cf(&cf_meta, "genome-release")?.unwrap();
+        assert_eq!(std::str::from_utf8(&genome_release)?, expected);
+
+        Ok(())
+    }
+
     #[test]
     fn smoke_test_import_gnomad_genomes_grch37() -> Result<(), anyhow::Error> {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch37,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-genomes-grch37/v2.1/gnomad-genomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad2::DetailsOptions::with_all_enabled(),
@@ -512,15 +740,22 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch38,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch38,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-exomes-grch38/v2.1/gnomad-exomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad2::DetailsOptions::with_all_enabled(),
@@ -532,20 +767,77 @@ mod test {
         run(&common, &args)
     }
 
+    /// The liftover fields are imported by default and returned by the query.
+    #[test]
+    fn import_gnomad_exomes_grch38_v2_1_liftover_by_default() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch38,
+            path_in_vcf: vec![String::from(
+                "tests/gnomad-nuclear/example-exomes-grch38/v2.1/gnomad-exomes.vcf.bgz",
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("gnomad_nuclear_data"),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+            import_fields_json: None,
+            gnomad_kind: GnomadKind::Exomes,
+            gnomad_version: String::from("2.1"),
+        };
+        run(&common, &args)?;
+
+        let (db, meta) = crate::gnomad_nuclear::cli::query::open_rocksdb(
+            &args.path_out_rocksdb,
+            &args.cf_name,
+            "meta",
+        )?;
+        let record = crate::gnomad_nuclear::cli::query::query_for_variant::<gnomad2::Record>(
+            &common::spdi::Var::from_str("GRCh38:1:138654:C:T")?,
+            &meta,
+            &db,
+            &args.cf_name,
+        )?
+        .expect("variant not found");
+        let record = serde_json::to_value(&record)?;
+        assert_eq!(
+            record["liftoverInfo"],
+            serde_json::json!({"originalContig": "1", "originalStart": "138654"})
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn smoke_test_import_gnomad_genomes_grch38_v3_1() -> Result<(), anyhow::Error> {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch38,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch38,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-genomes-grch38/v3.1/gnomad-genomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad3::DetailsOptions::with_all_enabled(),
@@ -562,15 +854,22 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch38,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch38,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-exomes-grch38/v4.0/gnomad-exomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad3::DetailsOptions::with_all_enabled(),
@@ -587,15 +886,22 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch38,
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch38,
             path_in_vcf: vec![String::from(
                 "tests/gnomad-nuclear/example-genomes-grch38/v4.0/gnomad-genomes.vcf.bgz",
             )],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("gnomad_nuclear_data"),
             path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
             tbi_window_size: 1_000_000,
             import_fields_json: Some(serde_json::to_string(
                 &gnomad3::DetailsOptions::with_all_enabled(),
@@ -606,4 +912,47 @@ mod test {
 
         run(&common, &args)
     }
+
+    /// Dry run must not create the database and must find the missing tabix index.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            path_in_vcf: vec![String::from(
+                "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz",
+            )],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("gnomad_nuclear_data"),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            dry_run: true,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+            import_fields_json: None,
+            gnomad_kind: GnomadKind::Exomes,
+            gnomad_version: String::from("2.1"),
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_vcf: vec![String::from(
+                "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf",
+            )],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(validation.problems[0].starts_with("tabix index"));
+        assert!(run(&common, &args).is_err());
+    }
 }
@@ -1,21 +1,39 @@
 //! Query of gnomAD-exomes and genomes annotation data.
 
 use erased_serde::serialize_trait_object;
-use std::{io::Write, sync::Arc};
-
-use prost::Message as _;
+use std::sync::Arc;
 
 /// Helper trait for type erased serialization.
-pub trait SerializeRecordTrait: erased_serde::Serialize {}
-impl SerializeRecordTrait for pbs::gnomad::gnomad2::Record {}
-impl SerializeRecordTrait for pbs::gnomad::gnomad3::Record {}
-impl SerializeRecordTrait for pbs::gnomad::gnomad4::Record {}
+pub trait SerializeRecordTrait: erased_serde::Serialize {
+    /// Return the normalized popmax / grpmax of the record.
+    fn grpmax(&self) -> Option<Grpmax>;
+}
+impl SerializeRecordTrait for pbs::gnomad::gnomad2::Record {
+    fn grpmax(&self) -> Option<Grpmax> {
+        grpmax::from_gnomad2(self)
+    }
+}
+impl SerializeRecordTrait for pbs::gnomad::gnomad3::Record {
+    fn grpmax(&self) -> Option<Grpmax> {
+        grpmax::from_gnomad3(self)
+    }
+}
+impl SerializeRecordTrait for pbs::gnomad::gnomad4::Record {
+    fn grpmax(&self) -> Option<Grpmax> {
+        grpmax::from_gnomad4(self)
+    }
+}
 
 serialize_trait_object!(SerializeRecordTrait);
 
 use crate::{
-    common::{self, cli::extract_chrom, keys, spdi},
+    common::{self, cli::extract_chrom, decode::decode_protobuf, keys, spdi},
     cons::cli::args::vars::ArgsQuery,
+    dbsnp::rs_id::{self, RsIdLookup},
+    gnomad_nuclear::{
+        cli::import::GnomadKind,
+        grpmax::{self, Grpmax, WithGrpmax},
+    },
     pbs,
 };
 
@@ -24,8 +42,11 @@ use crate::{
 #[command(about = "query gnomAD-nuclear data stored in RocksDB", long_about = None)]
 pub struct Args {
     /// Path to RocksDB directory with data.
-    #[arg(long)]
-    pub path_rocksdb: String,
+    #[arg(long, required_unless_present = "server_url")]
+    pub path_rocksdb: Option<String>,
+    /// URL of a running annonars server to query instead of a local RocksDB.
+    #[arg(long, conflicts_with = "path_rocksdb")]
+    pub server_url: Option<String>,
     /// Name of the column family to import into.
     #[arg(long, default_value = "gnomad_nuclear_data")]
     pub cf_name: String,
@@ -33,12 +54,22 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
+    /// gnomAD data set to query with `--server-url`.
+    #[arg(long, value_enum, default_value_t = GnomadKind::Exomes)]
+    pub server_gnomad_kind: GnomadKind,
+    /// Path to dbSNP RocksDB directory for adding the `rs_id` of each record.
+    #[arg(long, conflicts_with = "server_url")]
+    pub path_dbsnp_rocksdb: Option<String>,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// Fail on the first query from `--path-input-jsonl` that cannot be parsed or run rather
+    /// than writing an error record.
+    #[arg(long, requires = "path_input_jsonl")]
+    pub strict: bool,
 }
 
 /// Meta information as read from database.
@@ -48,6 +79,8 @@ pub struct Meta {
     pub genome_release: String,
     /// gnomAD version.
     pub gnomad_version: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -61,28 +94,30 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names: &[&str; 2] = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
-        let meta_gnomad_version = String::from_utf8(
-            db.get_cf(&cf_meta, "gnomad-version")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:gnomad-version"))?,
-        )?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
+        let meta_gnomad_version =
+            String::from_utf8(db.get_cf(&cf_meta, "gnomad-version")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:gnomad-version")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
             gnomad_version: meta_gnomad_version,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
     tracing::info!("  meta:genome-release = {}", &meta.genome_release);
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -95,21 +130,11 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
 pub fn open_rocksdb_from_args(
     args: &Args,
 ) -> Result<(Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>, Meta), anyhow::Error> {
-    open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
-}
-
-fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
-    value: &dyn SerializeRecordTrait,
-) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            writeln!(out_writer, "{}", serde_json::to_string(value)?)?;
-        }
-    }
-
-    Ok(())
+    let path_rocksdb = args
+        .path_rocksdb
+        .as_ref()
+        .ok_or_else(|| crate::ErrorCategory::Usage.error("missing --path-rocksdb"))?;
+    open_rocksdb(path_rocksdb, &args.cf_name, "meta")
 }
 
 /// Query for a single variant in the RocksDB database.
@@ -117,7 +142,7 @@ pub fn query_for_variant<T>(
     variant: &common::spdi::Var,
     meta: &Meta,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
-    cf_data: &Arc<rocksdb::BoundColumnFamily>,
+    cf_data_name: &str,
 ) -> Result<Option<Box<dyn SerializeRecordTrait>>, anyhow::Error>
 where
     T: SerializeRecordTrait + prost::Message + std::default::Default + 'static,
@@ -129,78 +154,81 @@ where
     };
     // Execute query.
     tracing::debug!("query = {:?}", &query);
-    let var: keys::Var = query.into();
-    let key: Vec<u8> = var.into();
+    let key = common::norm::query_key(query, meta.keys_normalized);
+    let cf_data = db.cf_handle(cf_data_name).unwrap();
     let raw_value = db
-        .get_cf(cf_data, key)
+        .get_cf(&cf_data, &key)
         .map_err(|e| anyhow::anyhow!("problem querying RocksDB: {}", e))?;
-    raw_value
+    Ok(raw_value
         .map(|raw_value| {
-            // Decode via prost, box object, and map errors properly.
-            match T::decode(&mut std::io::Cursor::new(&raw_value)) {
-                Ok(record) => Ok(Box::new(record) as Box<dyn SerializeRecordTrait>),
-                Err(e) => Err(anyhow::anyhow!("failed to decode record: {}", e)),
-            }
+            // Decode via prost and box object.
+            decode_protobuf::<T>(db, cf_data_name, &key, &raw_value)
+                .map(|record| Box::new(record) as Box<dyn SerializeRecordTrait>)
         })
-        .transpose()
+        .transpose()?)
 }
 
-/// Implementation of `tsv query` sub command.
-pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("Starting 'gnomad-nuclear query' command");
-    tracing::info!("common = {:#?}", &common);
-    tracing::info!("args = {:#?}", &args);
-
-    let (db, meta) = open_rocksdb_from_args(args)?;
+/// Run the single `query` and write its records to `out_writer`.
+fn run_query(
+    query: &ArgsQuery,
+    args: &Args,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    meta: &Meta,
+    rs_ids: Option<&RsIdLookup>,
+    out_writer: &mut common::output::RecordWriter,
+) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
-    // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
+    // Contigs without records are not an error, e.g., chrY in an autosomes-only database.
+    if let Some(contig) = common::contig::of_query(query, &meta.genome_release)? {
+        if let Err(e) = common::contig::lookup(db, &args.cf_name, &contig, "gnomad-nuclear") {
+            tracing::warn!("{}", e);
+            return Ok(());
         }
-    };
+    }
 
-    tracing::info!("Running query...");
-    let before_query = std::time::Instant::now();
-    if let Some(variant) = args.query.variant.as_ref() {
+    if let Some(variant) = query.variant.as_ref() {
         let query_result = match meta.gnomad_version[0..1].parse::<char>()? {
             '2' => {
-                query_for_variant::<pbs::gnomad::gnomad2::Record>(variant, &meta, &db, &cf_data)?
+                query_for_variant::<pbs::gnomad::gnomad2::Record>(variant, meta, db, &args.cf_name)?
             }
             '3' => {
-                query_for_variant::<pbs::gnomad::gnomad3::Record>(variant, &meta, &db, &cf_data)?
+                query_for_variant::<pbs::gnomad::gnomad3::Record>(variant, meta, db, &args.cf_name)?
             }
             '4' => {
-                query_for_variant::<pbs::gnomad::gnomad4::Record>(variant, &meta, &db, &cf_data)?
+                query_for_variant::<pbs::gnomad::gnomad4::Record>(variant, meta, db, &args.cf_name)?
             }
             _ => unreachable!("unhandled gnomAD version: {}", &meta.gnomad_version),
         };
         if let Some(record) = query_result {
-            print_record(&mut out_writer, args.out_format, record.as_ref())?
+            rs_id::write_record(
+                out_writer,
+                rs_ids,
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &WithGrpmax {
+                    record: record.as_ref(),
+                    grpmax: record.grpmax(),
+                },
+            )?
         } else {
             tracing::info!("no record found for variant {:?}", &variant);
         }
     } else {
-        let (start, stop) = if let Some(position) = args.query.position.as_ref() {
+        let (start, stop) = if let Some(position) = query.position.as_ref() {
             let position = spdi::Pos {
                 sequence: extract_chrom::from_pos(position, Some(&meta.genome_release))?,
                 ..position.clone()
             };
             (Some(position.clone()), Some(position))
-        } else if let Some(range) = args.query.range.as_ref() {
-            let range = spdi::Range {
-                sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-                ..range.clone()
-            };
+        } else if let Some(range) = query.range.as_ref() {
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
             let (start, stop) = range.into();
             (Some(start), Some(stop))
-        } else if args.query.all {
+        } else if query.all {
             (None, None)
         } else {
-            unreachable!()
+            return Err(crate::ErrorCategory::Usage
+                .error("accession queries are not supported by `gnomad-nuclear query`"));
         };
 
         tracing::debug!("start = {:?}, stop = {:?}", &start, &stop);
@@ -236,21 +264,100 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                     }
                 }
 
-                let mut cursor = std::io::Cursor::new(&raw_value);
+                let iter_key = iter.key().unwrap();
                 let record: Box<dyn SerializeRecordTrait> =
                     match meta.gnomad_version[0..1].parse::<char>()? {
-                        '2' => Box::new(pbs::gnomad::gnomad2::Record::decode(&mut cursor)?),
-                        '3' => Box::new(pbs::gnomad::gnomad3::Record::decode(&mut cursor)?),
-                        '4' => Box::new(pbs::gnomad::gnomad4::Record::decode(&mut cursor)?),
+                        '2' => Box::new(decode_protobuf::<pbs::gnomad::gnomad2::Record>(
+                            db,
+                            &args.cf_name,
+                            iter_key,
+                            raw_value,
+                        )?),
+                        '3' => Box::new(decode_protobuf::<pbs::gnomad::gnomad3::Record>(
+                            db,
+                            &args.cf_name,
+                            iter_key,
+                            raw_value,
+                        )?),
+                        '4' => Box::new(decode_protobuf::<pbs::gnomad::gnomad4::Record>(
+                            db,
+                            &args.cf_name,
+                            iter_key,
+                            raw_value,
+                        )?),
                         _ => unreachable!("unhandled gnomAD version: {}", &meta.gnomad_version),
                     };
-                print_record(&mut out_writer, args.out_format, record.as_ref())?;
+                rs_id::write_record(
+                    out_writer,
+                    rs_ids,
+                    &common::output::db_key(iter_key),
+                    &WithGrpmax {
+                        record: record.as_ref(),
+                        grpmax: record.grpmax(),
+                    },
+                )?;
                 iter.next();
             } else {
                 break;
             }
         }
     }
+
+    Ok(())
+}
+
+/// Implementation of `tsv query` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'gnomad-nuclear query' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if let Some(server_url) = args.server_url.as_ref() {
+        return common::remote::run_query(
+            server_url,
+            &args.query,
+            &format!("gnomad_{}", args.server_gnomad_kind),
+            &args.out_file,
+            args.output.output_format,
+        );
+    }
+
+    let (db, meta) = open_rocksdb_from_args(args)?;
+
+    // Obtain writer to output.
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "gnomad-nuclear",
+        Some(&meta.genome_release),
+    )?;
+    let rs_ids = args
+        .path_dbsnp_rocksdb
+        .as_ref()
+        .map(|path| RsIdLookup::open(path, &meta.genome_release))
+        .transpose()?;
+
+    tracing::info!("Running query...");
+    let before_query = std::time::Instant::now();
+    if let Some(path_input_jsonl) = args.query.path_input_jsonl.as_ref() {
+        common::batch::run(
+            common::batch::open_input(path_input_jsonl)?,
+            args.strict,
+            &mut out_writer,
+            |query: &ArgsQuery, out_writer| {
+                run_query(query, args, &db, &meta, rs_ids.as_ref(), out_writer)
+            },
+        )?;
+    } else {
+        run_query(
+            &args.query,
+            args,
+            &db,
+            &meta,
+            rs_ids.as_ref(),
+            &mut out_writer,
+        )?;
+    }
     tracing::info!("... done querying in {:?}", before_query.elapsed());
 
     tracing::info!("All done. Have a nice day!");
@@ -274,16 +381,21 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            path_rocksdb: format!(
+            path_rocksdb: Some(format!(
                 "tests/gnomad-nuclear/example-{}-{}/v{}/gnomad-{}.vcf.bgz.db",
                 kind, genome_release, version, kind
-            ),
+            )),
+            server_url: None,
             cf_name: String::from("gnomad_nuclear_data"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
+            server_gnomad_kind: Default::default(),
+            path_dbsnp_rocksdb: None,
             query,
+            strict: false,
         };
 
         (common, args, temp)
@@ -348,6 +460,137 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn smoke_query_var_single_lowercase() -> Result<(), anyhow::Error> {
+        let (common, args_upper, _temp_upper) = build_args(
+            ArgsQuery {
+                variant: Some(spdi::Var::from_str("GRCh37:1:55516888:G:GA")?),
+                ..Default::default()
+            },
+            "exomes",
+            "grch37",
+            "2.1",
+        );
+        run(&common, &args_upper)?;
+        let (common, args_lower, _temp_lower) = build_args(
+            ArgsQuery {
+                variant: Some(spdi::Var::new(
+                    String::from("GRCh37:1"),
+                    55516888,
+                    String::from("g"),
+                    String::from("ga"),
+                )),
+                ..Default::default()
+            },
+            "exomes",
+            "grch37",
+            "2.1",
+        );
+        run(&common, &args_lower)?;
+
+        let out_upper = std::fs::read_to_string(&args_upper.out_file)?;
+        let out_lower = std::fs::read_to_string(&args_lower.out_file)?;
+        assert!(!out_upper.is_empty());
+        assert_eq!(out_upper, out_lower);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:55516888:G:GA").unwrap()),
+            ..Default::default()
+        },
+        &["1:55516888:G:GA"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505615").unwrap()),
+            ..Default::default()
+        },
+        &["1:55505599:C:G", "1:55505615:C:T"],
+    )]
+    fn smoke_query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = build_args(query.clone(), "exomes", "grch37", "2.1");
+        run(&common, &args)?;
+        let (common, args_v2, _temp_v2) = build_args(query, "exomes", "grch37", "2.1");
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        let out_data_v2 = std::fs::read_to_string(&args_v2.out_file)?;
+        common::output::assert_envelopes(
+            &out_data,
+            &out_data_v2,
+            "gnomad-nuclear",
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:55505599:C:G").unwrap()),
+            ..Default::default()
+        },
+        &[Some(11591147)],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:55505599:55505615").unwrap()),
+            ..Default::default()
+        },
+        &[Some(11591147), None],
+    )]
+    fn smoke_query_rs_id(
+        #[case] query: ArgsQuery,
+        #[case] expected: &[Option<i64>],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, temp) = build_args(query.clone(), "exomes", "grch37", "2.1");
+        let args = Args {
+            path_dbsnp_rocksdb: Some(crate::dbsnp::rs_id::test::write_dbsnp_db(
+                &temp,
+                &[("1:55505599:C:G", 11591147), ("17:41267746:C:A", 80357446)],
+            )),
+            ..args
+        };
+        let (_, args_plain, _temp_plain) = build_args(query, "exomes", "grch37", "2.1");
+
+        run(&common, &args)?;
+        run(&common, &args_plain)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        let out_plain = std::fs::read_to_string(&args_plain.out_file)?;
+
+        assert_eq!(out_data.lines().count(), expected.len());
+        for ((line, line_plain), expected) in out_data.lines().zip(out_plain.lines()).zip(expected)
+        {
+            let mut record: serde_json::Value = serde_json::from_str(line)?;
+            let rs_id = record
+                .as_object_mut()
+                .and_then(|record| record.remove("rs_id"))
+                .unwrap();
+            assert_eq!(rs_id.as_i64(), *expected);
+            assert_eq!(
+                record,
+                serde_json::from_str::<serde_json::Value>(line_plain)?
+            );
+        }
+
+        Ok(())
+    }
+
     #[rstest::rstest]
     #[case("exomes", "grch37", "2.1")]
     #[case("exomes", "grch38", "4.0")]
@@ -545,4 +788,96 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case("exomes", "grch37", "2.1", "GRCh37:2:47641560:A:T")]
+    #[case("exomes", "grch38", "4.0", "GRCh38:chrY:2787139:G:A")]
+    #[case("genomes", "grch38", "4.0", "GRCh38:chrUn_gl000220:100:G:A")]
+    fn query_var_contig_not_in_database(
+        #[case] kind: &str,
+        #[case] genome_release: &str,
+        #[case] version: &str,
+        #[case] variant: &str,
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = build_args(
+            ArgsQuery {
+                variant: Some(spdi::Var::from_str(variant)?),
+                ..Default::default()
+            },
+            kind,
+            genome_release,
+            version,
+        );
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out_data, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_range_contig_not_in_database() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = build_args(
+            ArgsQuery {
+                range: Some(spdi::Range::from_str("GRCh37:22:1:51304566")?),
+                ..Default::default()
+            },
+            "exomes",
+            "grch37",
+            "2.1",
+        );
+        run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out_data, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_path_input_jsonl() -> Result<(), anyhow::Error> {
+        let query = ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:55516888:G:GA")?),
+            ..Default::default()
+        };
+        let (common, args_single, _temp_single) = build_args(query, "exomes", "grch37", "2.1");
+        run(&common, &args_single)?;
+        let mut expected: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&args_single.out_file)?.trim_end())?;
+
+        let (common, args, temp) = build_args(ArgsQuery::default(), "exomes", "grch37", "2.1");
+        let path_input = temp.join("input.jsonl").to_string_lossy().to_string();
+        std::fs::write(
+            &path_input,
+            "{\"accession\": \"rs1\"}\n{\"variant\": \"GRCh37:1:55516888:G:GA\"}\n",
+        )?;
+        let args = Args {
+            query: ArgsQuery {
+                path_input_jsonl: Some(path_input),
+                ..Default::default()
+            },
+            ..args
+        };
+        run(&common, &args)?;
+        let lines = std::fs::read_to_string(&args.out_file)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+        expected
+            .as_object_mut()
+            .unwrap()
+            .insert(String::from("input_index"), 1.into());
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({
+                    "input_index": 0,
+                    "error": "accession queries are not supported by `gnomad-nuclear query`"
+                }),
+                expected
+            ]
+        );
+
+        Ok(())
+    }
 }
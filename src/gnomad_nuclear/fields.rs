@@ -0,0 +1,374 @@
+//! Mapping of the INFO fields read by `gnomad-nuclear import` to the VCF header.
+//!
+//! gnomAD releases rename INFO fields, e.g., `AF_popmax` became `AF_grpmax` with v4.0, and
+//! patch releases add or drop cohorts, e.g., the `*_joint` fields are not in the v4.1 VCFs.
+//! The record extraction in `pbs::gnomad` reads each field by one name.  The tables below
+//! list, per version and kind, these names together with the names to try in the header in
+//! order and whether the field is required.
+//!
+//! Before the import, `FieldMapping::new` resolves the table against the header of each input
+//! file, reports the fields found and missing, and fails if required fields are missing.  The
+//! INFO fields of each record are then renamed to the names read by the extraction with
+//! `FieldMapping::apply`.  Supporting a patch release that renames fields thus only needs
+//! another name in the table.
+
+use noodles::vcf::variant::RecordBuf;
+
+use super::cli::import::{GnomadKind, GnomadVersion};
+
+/// An INFO field read by the import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// Name that the record extraction reads the field by.
+    pub name: &'static str,
+    /// Names to try in the VCF header, in order.
+    pub candidates: &'static [&'static str],
+    /// Whether the import fails if none of the names is in the header.
+    pub required: bool,
+}
+
+/// Shortcut for a required table entry.
+const fn required(name: &'static str, candidates: &'static [&'static str]) -> FieldSpec {
+    FieldSpec {
+        name,
+        candidates,
+        required: true,
+    }
+}
+
+/// Shortcut for an optional table entry.
+const fn optional(name: &'static str, candidates: &'static [&'static str]) -> FieldSpec {
+    FieldSpec {
+        name,
+        candidates,
+        required: false,
+    }
+}
+
+/// Fields of gnomAD v2 and v3, exomes and genomes.
+static FIELDS_V2_V3: &[FieldSpec] = &[
+    required("AC", &["AC"]),
+    required("AN", &["AN"]),
+    required("AF", &["AF"]),
+    required("nhomalt", &["nhomalt"]),
+    optional("popmax", &["popmax", "grpmax"]),
+    optional("AC_popmax", &["AC_popmax", "AC_grpmax"]),
+    optional("AN_popmax", &["AN_popmax", "AN_grpmax"]),
+    optional("AF_popmax", &["AF_popmax", "AF_grpmax"]),
+    optional("nhomalt_popmax", &["nhomalt_popmax", "nhomalt_grpmax"]),
+    optional("vep", &["vep"]),
+];
+
+/// Fields of gnomAD-exomes v4.
+static FIELDS_V4_EXOMES: &[FieldSpec] = &[
+    required("AC", &["AC"]),
+    required("AN", &["AN"]),
+    required("AF", &["AF"]),
+    required("nhomalt", &["nhomalt"]),
+    optional("grpmax", &["grpmax", "popmax"]),
+    optional("AC_grpmax", &["AC_grpmax", "AC_popmax"]),
+    optional("AN_grpmax", &["AN_grpmax", "AN_popmax"]),
+    optional("AF_grpmax", &["AF_grpmax", "AF_popmax"]),
+    optional("nhomalt_grpmax", &["nhomalt_grpmax", "nhomalt_popmax"]),
+    optional("grpmax_non_ukb", &["grpmax_non_ukb", "popmax_non_ukb"]),
+    optional(
+        "AC_grpmax_non_ukb",
+        &["AC_grpmax_non_ukb", "AC_popmax_non_ukb"],
+    ),
+    optional(
+        "AN_grpmax_non_ukb",
+        &["AN_grpmax_non_ukb", "AN_popmax_non_ukb"],
+    ),
+    optional(
+        "AF_grpmax_non_ukb",
+        &["AF_grpmax_non_ukb", "AF_popmax_non_ukb"],
+    ),
+    optional(
+        "nhomalt_grpmax_non_ukb",
+        &["nhomalt_grpmax_non_ukb", "nhomalt_popmax_non_ukb"],
+    ),
+    optional("grpmax_joint", &["grpmax_joint"]),
+    optional("AC_grpmax_joint", &["AC_grpmax_joint"]),
+    optional("AN_grpmax_joint", &["AN_grpmax_joint"]),
+    optional("AF_grpmax_joint", &["AF_grpmax_joint"]),
+    optional("nhomalt_grpmax_joint", &["nhomalt_grpmax_joint"]),
+    optional("vep", &["vep"]),
+];
+
+/// Fields of gnomAD-genomes v4, without the UK Biobank cohort of the exomes.
+static FIELDS_V4_GENOMES: &[FieldSpec] = &[
+    required("AC", &["AC"]),
+    required("AN", &["AN"]),
+    required("AF", &["AF"]),
+    required("nhomalt", &["nhomalt"]),
+    optional("grpmax", &["grpmax", "popmax"]),
+    optional("AC_grpmax", &["AC_grpmax", "AC_popmax"]),
+    optional("AN_grpmax", &["AN_grpmax", "AN_popmax"]),
+    optional("AF_grpmax", &["AF_grpmax", "AF_popmax"]),
+    optional("nhomalt_grpmax", &["nhomalt_grpmax", "nhomalt_popmax"]),
+    optional("grpmax_joint", &["grpmax_joint"]),
+    optional("AC_grpmax_joint", &["AC_grpmax_joint"]),
+    optional("AN_grpmax_joint", &["AN_grpmax_joint"]),
+    optional("AF_grpmax_joint", &["AF_grpmax_joint"]),
+    optional("nhomalt_grpmax_joint", &["nhomalt_grpmax_joint"]),
+    optional("vep", &["vep"]),
+];
+
+/// Return the fields read for `version` and `kind`.
+pub fn field_specs(
+    version: GnomadVersion,
+    kind: GnomadKind,
+) -> Result<&'static [FieldSpec], anyhow::Error> {
+    Ok(match (version, kind) {
+        (GnomadVersion::Two | GnomadVersion::Three, _) => FIELDS_V2_V3,
+        (GnomadVersion::Four, GnomadKind::Exomes) => FIELDS_V4_EXOMES,
+        (GnomadVersion::Four, GnomadKind::Genomes) => FIELDS_V4_GENOMES,
+        (GnomadVersion::One, _) => anyhow::bail!("gnomAD version must be either 2, 3, or 4"),
+    })
+}
+
+/// INFO fields of one VCF header resolved against a table of `FieldSpec`s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldMapping {
+    /// Names read by the extraction and in the header of the fields found.
+    found: Vec<(&'static str, String)>,
+    /// Names read by the extraction of the optional fields not found.
+    missing: Vec<&'static str>,
+}
+
+impl FieldMapping {
+    /// Resolve `specs` against the INFO fields of `header`.
+    ///
+    /// Fails with the list of required fields missing from the header, if any.
+    pub fn new(header: &noodles::vcf::Header, specs: &[FieldSpec]) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        let mut missing_required = Vec::new();
+        for spec in specs {
+            match spec
+                .candidates
+                .iter()
+                .find(|candidate| header.infos().contains_key(**candidate))
+            {
+                Some(candidate) => result.found.push((spec.name, candidate.to_string())),
+                None if spec.required => missing_required.push(format!(
+                    "{} (tried {})",
+                    spec.name,
+                    spec.candidates.join(", ")
+                )),
+                None => result.missing.push(spec.name),
+            }
+        }
+
+        if missing_required.is_empty() {
+            Ok(result)
+        } else {
+            Err(crate::ErrorCategory::InputParse.error(format!(
+                "required INFO fields missing from VCF header: {}",
+                missing_required.join("; ")
+            )))
+        }
+    }
+
+    /// Return the header name of the field read by the extraction as `name`, if found.
+    pub fn header_name(&self, name: &str) -> Option<&str> {
+        self.found
+            .iter()
+            .find(|(found_name, _)| *found_name == name)
+            .map(|(_, header_name)| header_name.as_str())
+    }
+
+    /// Return the names read by the extraction of the optional fields not found.
+    pub fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+
+    /// Log the fields found and missing.
+    pub fn report(&self) {
+        tracing::info!(
+            "  INFO fields found: {}",
+            self.found
+                .iter()
+                .map(|(name, header_name)| if *name == header_name.as_str() {
+                    name.to_string()
+                } else {
+                    format!("{} (as {})", name, header_name)
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if !self.missing.is_empty() {
+            tracing::warn!(
+                "  optional INFO fields missing, will be empty: {}",
+                self.missing.join(", ")
+            );
+        }
+    }
+
+    /// Rename the INFO fields of `record` found under another name to the names read by the
+    /// extraction.
+    pub fn apply(&self, record: &mut RecordBuf) {
+        let info = record.info_mut().as_mut();
+        for (name, header_name) in &self.found {
+            if *name != header_name.as_str() {
+                if let Some(value) = info.shift_remove(header_name.as_str()) {
+                    info.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Read the header of the VCF file at `path`.
+    fn read_header(path: &str) -> Result<noodles::vcf::Header, anyhow::Error> {
+        Ok(noodles::vcf::io::reader::Builder::default()
+            .build_from_path(path)?
+            .read_header()?)
+    }
+
+    #[rstest::rstest]
+    #[case::v2_1_exomes(
+        "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf",
+        GnomadVersion::Two,
+        GnomadKind::Exomes,
+        &[]
+    )]
+    #[case::v2_1_genomes(
+        "tests/gnomad-nuclear/example-genomes-grch37/v2.1/gnomad-genomes.vcf",
+        GnomadVersion::Two,
+        GnomadKind::Genomes,
+        &[]
+    )]
+    #[case::v3_1_genomes(
+        "tests/gnomad-nuclear/example-genomes-grch38/v3.1/gnomad-genomes.vcf",
+        GnomadVersion::Three,
+        GnomadKind::Genomes,
+        &[]
+    )]
+    #[case::v4_0_exomes(
+        "tests/gnomad-nuclear/example-exomes-grch38/v4.0/gnomad-exomes.vcf",
+        GnomadVersion::Four,
+        GnomadKind::Exomes,
+        &[]
+    )]
+    #[case::v4_0_genomes(
+        "tests/gnomad-nuclear/example-genomes-grch38/v4.0/gnomad-genomes.vcf",
+        GnomadVersion::Four,
+        GnomadKind::Genomes,
+        &[]
+    )]
+    #[case::v4_1_exomes(
+        "tests/gnomad-nuclear/headers/gnomad-exomes-v4.1.vcf",
+        GnomadVersion::Four,
+        GnomadKind::Exomes,
+        &[
+            "grpmax_joint",
+            "AC_grpmax_joint",
+            "AN_grpmax_joint",
+            "AF_grpmax_joint",
+            "nhomalt_grpmax_joint",
+        ]
+    )]
+    fn field_mapping_headers(
+        #[case] path: &str,
+        #[case] version: GnomadVersion,
+        #[case] kind: GnomadKind,
+        #[case] expected_missing: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let header = read_header(path)?;
+
+        let mapping = FieldMapping::new(&header, field_specs(version, kind)?)?;
+
+        assert_eq!(mapping.missing(), expected_missing);
+        for (name, header_name) in &mapping.found {
+            assert_eq!(*name, header_name.as_str());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_mapping_fallback() -> Result<(), anyhow::Error> {
+        // Header with the v3 names imported as v4.
+        let header =
+            read_header("tests/gnomad-nuclear/example-genomes-grch38/v3.1/gnomad-genomes.vcf")?;
+
+        let mapping = FieldMapping::new(
+            &header,
+            field_specs(GnomadVersion::Four, GnomadKind::Genomes)?,
+        )?;
+
+        assert_eq!(mapping.header_name("AF"), Some("AF"));
+        assert_eq!(mapping.header_name("grpmax"), Some("popmax"));
+        assert_eq!(mapping.header_name("AF_grpmax"), Some("AF_popmax"));
+        assert_eq!(mapping.header_name("grpmax_joint"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_mapping_missing_required() -> Result<(), anyhow::Error> {
+        let header = noodles::vcf::io::Reader::new(
+            "##fileformat=VCFv4.2\n\
+            ##INFO=<ID=AC,Number=A,Type=Integer,Description=\"Alternate allele count\">\n\
+            ##INFO=<ID=AF,Number=A,Type=Float,Description=\"Alternate allele frequency\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n"
+                .as_bytes(),
+        )
+        .read_header()?;
+
+        let err = FieldMapping::new(
+            &header,
+            field_specs(GnomadVersion::Four, GnomadKind::Exomes)?,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "required INFO fields missing from VCF header: AN (tried AN); \
+            nhomalt (tried nhomalt)"
+        );
+        assert_eq!(
+            crate::ErrorCategory::of(&err),
+            crate::ErrorCategory::InputParse
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_mapping_apply() -> Result<(), anyhow::Error> {
+        let path = "tests/gnomad-nuclear/example-genomes-grch38/v3.1/gnomad-genomes.vcf";
+        let mut reader = noodles::vcf::io::reader::Builder::default().build_from_path(path)?;
+        let header = reader.read_header()?;
+        let mapping = FieldMapping::new(
+            &header,
+            field_specs(GnomadVersion::Four, GnomadKind::Genomes)?,
+        )?;
+
+        let mut record = reader
+            .record_bufs(&header)
+            .find(|record| {
+                record
+                    .as_ref()
+                    .map(|record| record.info().get("AF_popmax").is_some())
+                    .unwrap_or(true)
+            })
+            .expect("no record with AF_popmax")?;
+        let af_popmax = record.info().get("AF_popmax").map(|value| value.cloned());
+        mapping.apply(&mut record);
+
+        assert!(af_popmax.is_some());
+        assert_eq!(record.info().get("AF_popmax"), None);
+        assert_eq!(
+            record.info().get("AF_grpmax").map(|value| value.cloned()),
+            af_popmax
+        );
+        assert!(record.info().get("AF").is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+//! Normalized popmax (gnomAD v2/v3) / grpmax (gnomAD v4) frequency of a gnomAD record.
+//!
+//! The ACMG BS1/BA1 criteria are assessed on the allele frequency of the genetic ancestry
+//! group with the highest frequency.  The records store it with version-dependent field names
+//! on the global cohort.  For records without this information, e.g., filtered variants, it is
+//! computed from the per-population counts, considering the same groups as gnomAD does, i.e.,
+//! excluding bottlenecked populations and the remaining individuals.
+
+use crate::pbs::gnomad::{gnomad2, gnomad3, gnomad4};
+
+/// Populations / genetic ancestry groups that are considered for computing the grpmax.
+pub static GRPMAX_POPULATIONS: &[&str] = &["afr", "amr", "eas", "nfe", "sas"];
+
+/// Allele counts of the population with the maximum allele frequency.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Grpmax {
+    /// Name of the population / genetic ancestry group.
+    pub population: String,
+    /// Number of alternate alleles in the population.
+    pub ac: i32,
+    /// Total number of alleles in the population.
+    pub an: i32,
+    /// Alternate allele frequency in the population.
+    pub af: f32,
+    /// Number of homozygous alternate individuals in the population.
+    pub nhomalt: i32,
+    /// Whether this has been computed from the per-population counts as the record has no
+    /// popmax / grpmax information.
+    pub computed: bool,
+}
+
+/// Record with its `Grpmax` for the query output.
+#[derive(Debug, serde::Serialize)]
+pub struct WithGrpmax<'a, T: serde::Serialize + ?Sized> {
+    /// The record itself.
+    #[serde(flatten)]
+    pub record: &'a T,
+    /// The grpmax, `None` if no population has the alternate allele.
+    pub grpmax: Option<Grpmax>,
+}
+
+/// Return the stored grpmax if all of its values are present.
+fn stored(
+    population: Option<&str>,
+    ac: Option<i32>,
+    an: Option<i32>,
+    af: Option<f32>,
+    nhomalt: Option<i32>,
+) -> Option<Grpmax> {
+    Some(Grpmax {
+        population: population
+            .filter(|population| !population.is_empty())?
+            .to_string(),
+        ac: ac?,
+        an: an?,
+        af: af?,
+        nhomalt: nhomalt?,
+        computed: false,
+    })
+}
+
+/// Return the grpmax computed from the `(population, ac, an, af, nhomalt)` of each population.
+///
+/// On ties, the first population wins.
+fn computed<'a>(
+    populations: impl IntoIterator<Item = (&'a str, i32, i32, f32, i32)>,
+) -> Option<Grpmax> {
+    populations
+        .into_iter()
+        .filter(|(population, ac, ..)| *ac > 0 && GRPMAX_POPULATIONS.contains(population))
+        .fold(
+            None,
+            |best: Option<Grpmax>, (population, ac, an, af, nhomalt)| match best {
+                Some(best) if best.af >= af => Some(best),
+                _ => Some(Grpmax {
+                    population: population.to_string(),
+                    ac,
+                    an,
+                    af,
+                    nhomalt,
+                    computed: true,
+                }),
+            },
+        )
+}
+
+/// Return the grpmax of a gnomAD v2 record.
+pub fn from_gnomad2(record: &gnomad2::Record) -> Option<Grpmax> {
+    let global = record
+        .allele_counts
+        .iter()
+        .find(|counts| counts.cohort.is_none())?;
+    stored(
+        global.popmax.as_deref(),
+        global.ac_popmax,
+        global.an_popmax,
+        global.af_popmax,
+        global.nhomalt_popmax,
+    )
+    .or_else(|| {
+        computed(global.by_population.iter().filter_map(|pop| {
+            let overall = pop.counts.as_ref()?.overall.as_ref()?;
+            Some((
+                pop.population.as_str(),
+                overall.ac,
+                overall.an,
+                overall.af,
+                overall.nhomalt,
+            ))
+        }))
+    })
+}
+
+/// Return the grpmax of a gnomAD v3 record.
+pub fn from_gnomad3(record: &gnomad3::Record) -> Option<Grpmax> {
+    let global = record
+        .allele_counts
+        .iter()
+        .find(|counts| counts.cohort.is_none())?;
+    stored(
+        global.popmax.as_deref(),
+        global.ac_popmax,
+        global.an_popmax,
+        global.af_popmax,
+        global.nhomalt_popmax,
+    )
+    .or_else(|| {
+        computed(global.by_population.iter().filter_map(|pop| {
+            let overall = pop.counts.as_ref()?.overall.as_ref()?;
+            Some((
+                pop.population.as_str(),
+                overall.ac,
+                overall.an,
+                overall.af,
+                overall.nhomalt,
+            ))
+        }))
+    })
+}
+
+/// Return the grpmax of a gnomAD v4 record.
+pub fn from_gnomad4(record: &gnomad4::Record) -> Option<Grpmax> {
+    let global = record
+        .allele_counts
+        .iter()
+        .find(|counts| counts.cohort.is_none())?;
+    stored(
+        global.grpmax.as_deref(),
+        global.ac_grpmax,
+        global.an_grpmax,
+        global.af_grpmax,
+        global.nhomalt_grpmax,
+    )
+    .or_else(|| {
+        computed(global.by_ancestry_group.iter().filter_map(|grp| {
+            let overall = grp.counts.as_ref()?.overall.as_ref()?;
+            Some((
+                grp.ancestry_group.as_str(),
+                overall.ac,
+                overall.an,
+                overall.af,
+                overall.nhomalt,
+            ))
+        }))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The `(population, ac, an, af, nhomalt)` counts used for the computed grpmax tests; `asj`
+    /// has the highest frequency but is excluded.
+    const POPULATIONS: &[(&str, i32, i32, f32, i32)] = &[
+        ("afr", 1, 1000, 0.001, 0),
+        ("asj", 5, 100, 0.05, 1),
+        ("nfe", 6, 2000, 0.003, 0),
+        ("sas", 0, 500, 0.0, 0),
+    ];
+
+    fn expected_computed() -> Option<Grpmax> {
+        Some(Grpmax {
+            population: "nfe".into(),
+            ac: 6,
+            an: 2000,
+            af: 0.003,
+            nhomalt: 0,
+            computed: true,
+        })
+    }
+
+    fn expected_stored() -> Option<Grpmax> {
+        Some(Grpmax {
+            population: "afr".into(),
+            ac: 3,
+            an: 1000,
+            af: 0.003,
+            nhomalt: 1,
+            computed: false,
+        })
+    }
+
+    #[test]
+    fn computed_excludes_populations_and_zero_counts() {
+        assert_eq!(computed(POPULATIONS.iter().copied()), expected_computed());
+        assert_eq!(
+            computed([("asj", 5, 100, 0.05, 1), ("fin", 1, 10, 0.1, 0)]),
+            None
+        );
+        assert_eq!(computed([("afr", 0, 1000, 0.0, 0)]), None);
+        assert_eq!(computed([]), None);
+    }
+
+    fn gnomad2_counts(popmax: bool) -> gnomad2::CohortAlleleCounts {
+        gnomad2::CohortAlleleCounts {
+            cohort: None,
+            by_population: POPULATIONS
+                .iter()
+                .map(
+                    |(population, ac, an, af, nhomalt)| gnomad2::PopulationAlleleCounts {
+                        population: population.to_string(),
+                        counts: Some(gnomad2::AlleleCountsBySex {
+                            overall: Some(gnomad2::AlleleCounts {
+                                ac: *ac,
+                                an: *an,
+                                nhomalt: *nhomalt,
+                                af: *af,
+                            }),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            popmax: popmax.then(|| "afr".into()),
+            af_popmax: popmax.then_some(0.003),
+            ac_popmax: popmax.then_some(3),
+            an_popmax: popmax.then_some(1000),
+            nhomalt_popmax: popmax.then_some(1),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::stored(true, expected_stored())]
+    #[case::computed(false, expected_computed())]
+    fn grpmax_gnomad2(#[case] popmax: bool, #[case] expected: Option<Grpmax>) {
+        let record = gnomad2::Record {
+            allele_counts: vec![
+                gnomad2::CohortAlleleCounts {
+                    cohort: Some("controls".into()),
+                    ..Default::default()
+                },
+                gnomad2_counts(popmax),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(from_gnomad2(&record), expected);
+        assert_eq!(from_gnomad2(&gnomad2::Record::default()), None);
+    }
+
+    fn gnomad3_overall(ac: i32, an: i32, af: f32, nhomalt: i32) -> gnomad3::AlleleCountsBySex {
+        gnomad3::AlleleCountsBySex {
+            overall: Some(gnomad3::AlleleCounts {
+                ac,
+                an,
+                nhomalt,
+                af,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::stored(true, expected_stored())]
+    #[case::computed(false, expected_computed())]
+    fn grpmax_gnomad3(#[case] popmax: bool, #[case] expected: Option<Grpmax>) {
+        let record = gnomad3::Record {
+            allele_counts: vec![gnomad3::CohortAlleleCounts {
+                cohort: None,
+                by_population: POPULATIONS
+                    .iter()
+                    .map(
+                        |(population, ac, an, af, nhomalt)| gnomad3::PopulationAlleleCounts {
+                            population: population.to_string(),
+                            counts: Some(gnomad3_overall(*ac, *an, *af, *nhomalt)),
+                            ..Default::default()
+                        },
+                    )
+                    .collect(),
+                popmax: popmax.then(|| "afr".into()),
+                af_popmax: popmax.then_some(0.003),
+                ac_popmax: popmax.then_some(3),
+                an_popmax: popmax.then_some(1000),
+                nhomalt_popmax: popmax.then_some(1),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(from_gnomad3(&record), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::stored(true, expected_stored())]
+    #[case::computed(false, expected_computed())]
+    fn grpmax_gnomad4(#[case] grpmax: bool, #[case] expected: Option<Grpmax>) {
+        let global = gnomad4::CohortAlleleCounts {
+            cohort: None,
+            by_ancestry_group: POPULATIONS
+                .iter()
+                .map(
+                    |(population, ac, an, af, nhomalt)| gnomad4::AncestryGroupAlleleCounts {
+                        ancestry_group: population.to_string(),
+                        counts: Some(gnomad3_overall(*ac, *an, *af, *nhomalt)),
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            grpmax: grpmax.then(|| "afr".into()),
+            af_grpmax: grpmax.then_some(0.003),
+            ac_grpmax: grpmax.then_some(3),
+            an_grpmax: grpmax.then_some(1000),
+            nhomalt_grpmax: grpmax.then_some(1),
+            ..Default::default()
+        };
+        // The "joint" cohort comes first in gnomAD v4 records.
+        let record = gnomad4::Record {
+            allele_counts: vec![
+                gnomad4::CohortAlleleCounts {
+                    cohort: Some("joint".into()),
+                    grpmax: Some("eas".into()),
+                    af_grpmax: Some(0.5),
+                    ac_grpmax: Some(1),
+                    an_grpmax: Some(2),
+                    nhomalt_grpmax: Some(0),
+                    ..Default::default()
+                },
+                global,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(from_gnomad4(&record), expected);
+    }
+
+    #[test]
+    fn with_grpmax_serialize() -> Result<(), anyhow::Error> {
+        let record = serde_json::json!({"chrom": "1", "pos": 55516888});
+
+        assert_eq!(
+            serde_json::to_value(WithGrpmax {
+                record: &record,
+                grpmax: expected_stored(),
+            })?,
+            serde_json::json!({
+                "chrom": "1",
+                "pos": 55516888,
+                "grpmax": {
+                    "population": "afr",
+                    "ac": 3,
+                    "an": 1000,
+                    "af": 0.003f32,
+                    "nhomalt": 1,
+                    "computed": false
+                }
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(WithGrpmax {
+                record: &record,
+                grpmax: None,
+            })?,
+            serde_json::json!({"chrom": "1", "pos": 55516888, "grpmax": null})
+        );
+
+        Ok(())
+    }
+}
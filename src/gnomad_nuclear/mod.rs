@@ -1,3 +1,5 @@
 //! Annotation using gnomAD-exomes and genomes data from VCF.
 
 pub mod cli;
+pub mod fields;
+pub mod grpmax;
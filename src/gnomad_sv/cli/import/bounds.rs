@@ -0,0 +1,236 @@
+//! Validation of contigs and positions against the declared genome release.
+//!
+//! Feeding, e.g., a GRCh38 liftover file into a GRCh37 import otherwise silently writes
+//! records with positions beyond the contig ends.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::common::{self, cli::GenomeRelease, noodles::get_i32, noodles::get_string};
+
+/// Fraction of violating records above which the import fails even without `--strict`.
+pub const MAX_VIOLATION_FRACTION: f64 = 0.01;
+
+/// Checks records against the canonical contigs and their lengths of a genome release.
+///
+/// Can be shared between threads, e.g., when importing several files in parallel.
+#[derive(Debug)]
+pub struct BoundsCheck {
+    /// The genome release to check against.
+    genome_release: GenomeRelease,
+    /// Length of each canonical contig, by canonical name.
+    lengths: HashMap<String, usize>,
+    /// Whether to fail on the first violation.
+    strict: bool,
+    /// Number of records checked.
+    n_records: AtomicUsize,
+    /// Number of records violating the bounds.
+    n_violations: AtomicUsize,
+    /// Description of the first violating record.
+    first_violation: Mutex<Option<String>>,
+}
+
+impl BoundsCheck {
+    /// Create a new check for `genome_release`; with `strict`, the first violation fails.
+    pub fn new(genome_release: GenomeRelease, strict: bool) -> Self {
+        Self {
+            genome_release,
+            lengths: common::cli::chrom_lengths(genome_release),
+            strict,
+            n_records: AtomicUsize::new(0),
+            n_violations: AtomicUsize::new(0),
+            first_violation: Mutex::new(None),
+        }
+    }
+
+    /// Check the record `id` on `chrom` with the 1-based positions `start` and `end`.
+    ///
+    /// Returns an error on a violation with `strict`, otherwise the violation is counted.
+    pub fn check(
+        &self,
+        id: &str,
+        chrom: &str,
+        start: i64,
+        end: Option<i64>,
+    ) -> Result<(), anyhow::Error> {
+        self.n_records.fetch_add(1, Ordering::Relaxed);
+        let violation = match self.lengths.get(&common::cli::canonicalize(chrom)) {
+            None => Some(format!(
+                "record {} is on contig {} which is not a canonical contig of {}",
+                id, chrom, self.genome_release
+            )),
+            Some(&length) => [Some(start), end]
+                .into_iter()
+                .flatten()
+                .find(|pos| *pos < 1 || *pos > length as i64)
+                .map(|pos| {
+                    format!(
+                        "record {} has position {}:{} outside of the contig length {} for {}",
+                        id, chrom, pos, length, self.genome_release
+                    )
+                }),
+        };
+
+        if let Some(violation) = violation {
+            if self.strict {
+                anyhow::bail!("{}", violation);
+            }
+            if self.n_violations.fetch_add(1, Ordering::Relaxed) == 0 {
+                tracing::warn!("{}", &violation);
+            }
+            self.first_violation
+                .lock()
+                .expect("bounds check lock poisoned")
+                .get_or_insert(violation);
+        }
+
+        Ok(())
+    }
+
+    /// Check the VCF record `record` with its `END`, unless it is a breakend or translocation.
+    pub fn check_vcf_record(
+        &self,
+        record: &noodles::vcf::variant::RecordBuf,
+    ) -> Result<(), anyhow::Error> {
+        let id = record
+            .ids()
+            .as_ref()
+            .iter()
+            .next()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| String::from("."));
+        let start = record
+            .variant_start()
+            .map(|pos| pos.get() as i64)
+            .unwrap_or_default();
+        let end = match get_string(record, "SVTYPE").ok().as_deref() {
+            Some("BND") | Some("CTX") => None,
+            _ => get_i32(record, "END").ok().map(i64::from),
+        };
+        self.check(&id, record.reference_sequence_name(), start, end)
+    }
+
+    /// Return the number of checked records.
+    pub fn n_records(&self) -> usize {
+        self.n_records.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of records violating the bounds.
+    pub fn n_violations(&self) -> usize {
+        self.n_violations.load(Ordering::Relaxed)
+    }
+
+    /// Report the violations after all records were checked.
+    ///
+    /// Fails if more than `MAX_VIOLATION_FRACTION` of the records violate the bounds, as this
+    /// almost certainly means that the input is for another genome release.
+    pub fn finish(&self) -> Result<(), anyhow::Error> {
+        let n_records = self.n_records();
+        let n_violations = self.n_violations();
+        if n_violations == 0 {
+            tracing::info!(
+                "  all {} records are within the contigs of {}",
+                n_records,
+                self.genome_release
+            );
+            return Ok(());
+        }
+
+        let first_violation = self
+            .first_violation
+            .lock()
+            .expect("bounds check lock poisoned")
+            .clone()
+            .unwrap_or_default();
+        if n_violations as f64 > MAX_VIOLATION_FRACTION * n_records as f64 {
+            anyhow::bail!(
+                "{} of {} records are outside of the contigs of {}, is the input for another \
+                genome release? first: {}",
+                n_violations,
+                n_records,
+                self.genome_release,
+                first_violation
+            );
+        }
+        tracing::warn!(
+            "{} of {} records are outside of the contigs of {}, first: {}",
+            n_violations,
+            n_records,
+            self.genome_release,
+            first_violation
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_within_bounds() -> Result<(), anyhow::Error> {
+        let bounds = BoundsCheck::new(GenomeRelease::Grch37, true);
+
+        bounds.check("a", "1", 1, Some(249250621))?;
+        bounds.check("b", "chrX", 155270560, None)?;
+        bounds.check("c", "chrM", 16569, None)?;
+        bounds.finish()?;
+
+        assert_eq!(bounds.n_records(), 3);
+        assert_eq!(bounds.n_violations(), 0);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("1", 249250622, "outside of the contig length 249250621 for grch37")]
+    #[case("X", 155800000, "outside of the contig length 155270560 for grch37")]
+    #[case("1", 0, "outside of the contig length")]
+    #[case("chr1_KI270706v1_random", 1, "not a canonical contig of grch37")]
+    fn check_strict_fails(#[case] chrom: &str, #[case] pos: i64, #[case] message: &str) {
+        let bounds = BoundsCheck::new(GenomeRelease::Grch37, true);
+
+        let err = bounds.check("sv_1", chrom, pos, None).unwrap_err();
+
+        assert!(err.to_string().starts_with("record sv_1 "), "{}", err);
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+
+    #[test]
+    fn finish_warns_below_threshold() -> Result<(), anyhow::Error> {
+        let bounds = BoundsCheck::new(GenomeRelease::Grch37, false);
+
+        for i in 0..100 {
+            bounds.check(&format!("sv_{}", i), "1", 1000 + i, Some(2000 + i))?;
+        }
+        bounds.check("sv_bad", "X", 155800000, None)?;
+        bounds.finish()?;
+
+        assert_eq!(bounds.n_records(), 101);
+        assert_eq!(bounds.n_violations(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn finish_fails_above_threshold() -> Result<(), anyhow::Error> {
+        let bounds = BoundsCheck::new(GenomeRelease::Grch37, false);
+
+        for i in 0..50 {
+            bounds.check(&format!("sv_{}", i), "1", 1000 + i, None)?;
+        }
+        bounds.check("sv_bad", "X", 155800000, None)?;
+
+        let err = bounds.finish().unwrap_err();
+
+        assert!(err.to_string().contains("1 of 51 records"), "{}", err);
+        assert!(err.to_string().contains("record sv_bad"), "{}", err);
+
+        Ok(())
+    }
+}
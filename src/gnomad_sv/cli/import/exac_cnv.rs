@@ -48,6 +48,7 @@ impl FromStr for Population {
 /// * `db` - Database connection.
 /// * `cf_data` - Column family for data.
 /// * `path_in_tsv` - Path to input TSV file.
+/// * `bounds` - Check of the contigs and positions.
 ///
 /// # Errors
 ///
@@ -56,6 +57,7 @@ pub fn import(
     db: &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
     path_in_tsv: &str,
+    bounds: &super::bounds::BoundsCheck,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("- selected ExAC CNV import for GRCh37");
 
@@ -92,6 +94,12 @@ pub fn import(
                 } as i32,
                 population: arr[3].parse::<Population>()? as i32,
             };
+            bounds.check(
+                &format!("{}:{}-{}", record.chrom, record.start, record.stop),
+                &record.chrom,
+                record.start as i64,
+                Some(record.stop as i64),
+            )?;
             let buf = record.encode_to_vec();
 
             let mut key = [0; 4];
@@ -204,6 +204,7 @@ impl Record {
 /// * `db` - Database connection.
 /// * `cf_data` - Column family for data.
 /// * `path_in_tsv` - Path to input TSV file.
+/// * `bounds` - Check of the contigs and positions.
 ///
 /// # Errors
 ///
@@ -212,6 +213,7 @@ pub fn import(
     db: &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
     path_in_vcf: &str,
+    bounds: &super::bounds::BoundsCheck,
 ) -> Result<(), anyhow::Error> {
     let cohort_name = if path_in_vcf.contains("non_neuro_controls") {
         "non_neuro_controls"
@@ -227,6 +229,7 @@ pub fn import(
 
     for result in reader.record_bufs(&header) {
         let vcf_record = result?;
+        bounds.check_vcf_record(&vcf_record)?;
         // TODO make sure this doesn't change anything
         let key = vcf_record.ids().as_ref().iter().join(",").into_bytes();
 
@@ -10,9 +10,9 @@ use noodles::vcf::variant::record::Ids;
 use prost::Message;
 
 use crate::{
-    common::noodles::{get_f32, get_i32, get_string},
+    common::noodles::{get_f32, get_i32, get_string, get_vec_str},
     pbs::gnomad::gnomad_sv2::{
-        AlleleCounts, AlleleCountsBySex, CohortAlleleCounts, CpxType, Filter, Population,
+        AlleleCounts, AlleleCountsBySex, CohortAlleleCounts, CpxType, Evidence, Filter, Population,
         PopulationAlleleCounts, Record, SvType,
     },
 };
@@ -23,7 +23,7 @@ impl FromStr for Filter {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "LOW_CALL_RATE" => Filter::LowCallRate,
-            "MULTIALLELIC" => Filter::LowCallRate,
+            "MULTIALLELIC" => Filter::Multiallelic,
             "PASS" => Filter::Pass,
             "PCRPLUS_ENRICHED" => Filter::PcrplusEnriched,
             "UNRESOLVED" => Filter::Unresolved,
@@ -33,6 +33,20 @@ impl FromStr for Filter {
     }
 }
 
+impl FromStr for Evidence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "BAF" => Evidence::Baf,
+            "PE" => Evidence::Pe,
+            "RD" => Evidence::Rd,
+            "SR" => Evidence::Sr,
+            _ => anyhow::bail!("unknown EVIDENCE: {}", s),
+        })
+    }
+}
+
 impl FromStr for Population {
     type Err = anyhow::Error;
 
@@ -144,19 +158,17 @@ impl Record {
             .next()
             .map(|s| s.to_string())
             .ok_or_else(|| anyhow::anyhow!("no ID found in VCF record"))?;
-        let filters = if record.filters().is_pass() {
-            vec![Filter::Pass as i32]
+        let (filters, filters_raw) = if record.filters().is_pass() {
+            (vec![Filter::Pass as i32], Vec::new())
         } else {
-            let mut result = record
-                .filters()
-                .as_ref()
-                .iter()
-                .map(|s| s.parse::<Filter>().map(|f| f as i32))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| anyhow::anyhow!("problem parsing FILTER: {}", e))?;
-            result.sort();
-            result
+            super::split_known::<Filter>(record.filters().as_ref().iter().map(String::as_str))
         };
+        let (evidence, evidence_raw) = super::split_known::<Evidence>(
+            get_vec_str(record, "EVIDENCE")
+                .unwrap_or_default()
+                .iter()
+                .map(String::as_str),
+        );
 
         let sv_type = get_string(record, "SVTYPE")?
             .parse::<SvType>()
@@ -178,6 +190,9 @@ impl Record {
             sv_type,
             cpx_type,
             allele_counts,
+            filters_raw,
+            evidence,
+            evidence_raw,
         })
     }
 
@@ -317,6 +332,7 @@ impl Record {
 /// * `db` - Database connection.
 /// * `cf_data` - Column family for data.
 /// * `path_in_tsv` - Path to input TSV file.
+/// * `bounds` - Check of the contigs and positions.
 ///
 /// # Errors
 ///
@@ -325,6 +341,7 @@ pub fn import(
     db: &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
     path_in_vcf: &str,
+    bounds: &super::bounds::BoundsCheck,
 ) -> Result<(), anyhow::Error> {
     let cohort_name = if path_in_vcf.contains("controls") {
         "controls"
@@ -340,6 +357,7 @@ pub fn import(
 
     for result in reader.record_bufs(&header) {
         let vcf_record = result?;
+        bounds.check_vcf_record(&vcf_record)?;
         let key = vcf_record.ids().as_ref().iter().join(",").into_bytes();
 
         // Build record for VCF record.
@@ -13,9 +13,9 @@ use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use crate::{
     common::{
         self,
-        noodles::{get_f32, get_i32, get_string},
+        noodles::{get_f32, get_i32, get_string, get_vec_str},
     },
-    pbs::gnomad::gnomad_sv2::CpxType,
+    pbs::gnomad::gnomad_sv2::{CpxType, Evidence},
     pbs::gnomad::gnomad_sv4::{
         AlleleCounts, AlleleCountsBySex, CohortAlleleCounts, Filter, Population,
         PopulationAlleleCounts, Record, SvType,
@@ -31,6 +31,7 @@ impl FromStr for Filter {
             "FAIL" => Filter::Fail,
             "FAIL_MANUAL_REVIEW" => Filter::FailManualReview,
             "HIGH_NCR" => Filter::HighNcr,
+            "HIGH_SR_BACKGROUND" => Filter::HighSrBackground,
             "IGH_MHC_OVERLAP" => Filter::IghMhcOverlap,
             "LOWQUAL_WHAM_SR_DEL" => Filter::LowqualWhamSrDel,
             "MULTIALLELIC" => Filter::Multiallelic,
@@ -138,19 +139,17 @@ impl Record {
             .next()
             .map(|s| s.to_string())
             .ok_or_else(|| anyhow::anyhow!("no ID found in VCF record"))?;
-        let filters = if record.filters().is_pass() {
-            vec![Filter::Pass as i32]
+        let (filters, filters_raw) = if record.filters().is_pass() {
+            (vec![Filter::Pass as i32], Vec::new())
         } else {
-            let mut result = record
-                .filters()
-                .as_ref()
-                .iter()
-                .map(|s| s.parse::<Filter>().map(|f| f as i32))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| anyhow::anyhow!("problem parsing FILTER: {}", e))?;
-            result.sort();
-            result
+            super::split_known::<Filter>(record.filters().as_ref().iter().map(String::as_str))
         };
+        let (evidence, evidence_raw) = super::split_known::<Evidence>(
+            get_vec_str(record, "EVIDENCE")
+                .unwrap_or_default()
+                .iter()
+                .map(String::as_str),
+        );
 
         let sv_type = get_string(record, "SVTYPE")?
             .parse::<SvType>()
@@ -175,6 +174,9 @@ impl Record {
             sv_type,
             cpx_type,
             allele_counts,
+            filters_raw,
+            evidence,
+            evidence_raw,
         })
     }
 
@@ -316,6 +318,7 @@ fn import_file(
     db: &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     cf_data_name: &str,
     path_in_vcf: &str,
+    bounds: &super::bounds::BoundsCheck,
 ) -> Result<(), anyhow::Error> {
     let mut reader = noodles::vcf::io::reader::Builder::default().build_from_path(path_in_vcf)?;
     let header = reader.read_header()?;
@@ -323,6 +326,7 @@ fn import_file(
 
     for result in reader.record_bufs(&header) {
         let vcf_record = result?;
+        bounds.check_vcf_record(&vcf_record)?;
         // TODO check if this key is the same as before
         use itertools::Itertools;
         let key = vcf_record.ids().as_ref().iter().join(",").into_bytes();
@@ -356,6 +360,7 @@ fn import_file(
 /// * `db` - Database connection.
 /// * `cf_data_name` - Data column family name.
 /// * `path_in_tsv` - Path to input TSV file.
+/// * `bounds` - Check of the contigs and positions.
 ///
 /// # Errors
 ///
@@ -364,10 +369,11 @@ pub fn import(
     db: &Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     cf_data_name: &str,
     paths_in_vcf: &[String],
+    bounds: &super::bounds::BoundsCheck,
 ) -> Result<(), anyhow::Error> {
     paths_in_vcf
         .par_iter()
         .progress_with(common::cli::progress_bar(paths_in_vcf.len()))
-        .map(|path_in_tsv| import_file(db, cf_data_name, path_in_tsv))
+        .map(|path_in_tsv| import_file(db, cf_data_name, path_in_tsv, bounds))
         .collect::<Result<(), _>>()
 }
@@ -1,11 +1,12 @@
 //! CLI for importing gnomad-SV data.
 
+pub mod bounds;
 pub mod exac_cnv;
 pub mod gnomad_cnv4;
 pub mod gnomad_sv2;
 pub mod gnomad_sv4;
 
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use crate::{
     common::{self},
@@ -30,9 +31,9 @@ pub struct Args {
     /// The data version to write out.
     #[arg(long)]
     pub gnomad_version: String,
-    /// Genome build to use in the build.
+    /// Genome build to use in the build, `auto` detects it from the VCF headers.
     #[arg(long, value_enum)]
-    pub genome_release: common::cli::GenomeRelease,
+    pub genome_release: common::assembly_detect::GenomeReleaseArg,
 
     /// Data column family to import into.
     #[arg(long, default_value = "gnomad_sv")]
@@ -40,16 +41,109 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Fail on the first record on a non-canonical contig or outside of the contig length.
+    ///
+    /// Otherwise, such records are counted and reported; the import fails regardless if
+    /// more than 1% of the records are affected.
+    #[arg(long)]
+    pub strict: bool,
+}
+
+/// Detect the genome release, ExAC is GRCh37 only and has no VCF header.
+fn detect_genome_release(
+    args: &Args,
+    gnomad_version: Option<GnomadVersion>,
+) -> Result<common::cli::GenomeRelease, anyhow::Error> {
+    if gnomad_version == Some(GnomadVersion::One) {
+        Ok(common::cli::GenomeRelease::Grch37)
+    } else {
+        common::assembly_detect::from_vcf_paths(&args.path_in_vcf)
+    }
+}
+
+/// Split the FILTER or EVIDENCE `values` into the ones known to the enum `T` and the others.
+///
+/// The known values are returned sorted as `i32`, the others as raw strings in input order
+/// such that values introduced by future gnomAD releases are not lost.
+pub fn split_known<'a, T>(values: impl IntoIterator<Item = &'a str>) -> (Vec<i32>, Vec<String>)
+where
+    T: FromStr + Into<i32>,
+{
+    let mut known = Vec::new();
+    let mut raw = Vec::new();
+    for value in values {
+        match value.parse::<T>() {
+            Ok(parsed) => known.push(parsed.into()),
+            Err(_) => raw.push(value.to_string()),
+        }
+    }
+    known.sort();
+    (known, raw)
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    let gnomad_version = validation.check(
+        format!("gnomAD version {} is supported", &args.gnomad_version),
+        args.gnomad_version.parse::<GnomadVersion>(),
+    );
+    let is_exac = gnomad_version == Some(GnomadVersion::One);
+    if is_exac {
+        validation.check(
+            "ExAC CNV import has exactly one input file",
+            if args.path_in_vcf.len() == 1 {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "got {} input files",
+                    args.path_in_vcf.len()
+                ))
+            },
+        );
+    }
+    let genome_release = validation.genome_release(args.genome_release, || {
+        detect_genome_release(args, gnomad_version)
+    });
+    for path_in_vcf in &args.path_in_vcf {
+        if validation.input_file(path_in_vcf) && !is_exac {
+            if let Some(genome_release) = genome_release {
+                validation.vcf_header(path_in_vcf, genome_release);
+            }
+        }
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
 }
 
 /// Implementation of `gnomad-sv import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    if args.dry_run {
+        tracing::info!("Validating inputs for 'gnomad-sv import' command");
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     let gnomad_version: GnomadVersion = args.gnomad_version.parse()?;
 
     tracing::info!("Starting 'gnomad-sv import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    let genome_release = args
+        .genome_release
+        .resolve(|| detect_genome_release(args, Some(gnomad_version)))?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -69,11 +163,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
-    db.put_cf(
-        &cf_meta,
-        "genome-release",
-        format!("{}", args.genome_release),
-    )?;
+    db.put_cf(&cf_meta, "genome-release", format!("{}", genome_release))?;
     db.put_cf(
         &cf_meta,
         "gnomad-kind",
@@ -88,30 +178,31 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("Loading gnomad-SV file into RocksDB...");
     let before_loading = std::time::Instant::now();
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
-    match (gnomad_version, args.gnomad_kind, args.genome_release) {
+    let bounds = bounds::BoundsCheck::new(genome_release, args.strict);
+    match (gnomad_version, args.gnomad_kind, genome_release) {
         (GnomadVersion::One, GnomadKind::Exomes, common::cli::GenomeRelease::Grch37) => {
             if args.path_in_vcf.len() != 1 {
                 anyhow::bail!("ExAC CNV import requires exactly one input file");
             }
-            exac_cnv::import(&db, &cf_data, &args.path_in_vcf[0])?;
+            exac_cnv::import(&db, &cf_data, &args.path_in_vcf[0], &bounds)?;
         }
         (GnomadVersion::Two, GnomadKind::Genomes, common::cli::GenomeRelease::Grch37) => {
             tracing::info!("- selected gnomAD SV import for GRCh37");
             for path_in_vcf in &args.path_in_vcf {
                 tracing::info!("  - file {}", &path_in_vcf);
-                gnomad_sv2::import(&db, &cf_data, path_in_vcf)?;
+                gnomad_sv2::import(&db, &cf_data, path_in_vcf, &bounds)?;
             }
         }
         (GnomadVersion::Four, GnomadKind::Exomes, common::cli::GenomeRelease::Grch38) => {
             tracing::info!("- selected gnomAD CNV v4 import for GRCh38");
             for path_in_vcf in &args.path_in_vcf {
                 tracing::info!("  - file {}", &path_in_vcf);
-                gnomad_cnv4::import(&db, &cf_data, path_in_vcf)?;
+                gnomad_cnv4::import(&db, &cf_data, path_in_vcf, &bounds)?;
             }
         }
         (GnomadVersion::Four, GnomadKind::Genomes, common::cli::GenomeRelease::Grch38) => {
             tracing::info!("- selected gnomAD SV v4 import for GRCh38");
-            gnomad_sv4::import(&db, &args.cf_name, &args.path_in_vcf)?;
+            gnomad_sv4::import(&db, &args.cf_name, &args.path_in_vcf, &bounds)?;
         }
         _ => anyhow::bail!(
             "invalid combination of gnomAD version, kind and genome release, valid ones \
@@ -119,6 +210,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             v4 (gnomAD) for genomes/exomes and GRCh38"
         ),
     }
+    bounds.finish()?;
     tracing::info!(
         "... done loading gnomAD-SV file into RocksDB in {:?}",
         before_loading.elapsed()
@@ -132,6 +224,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: format!("gnomad-sv-{}", args.gnomad_kind),
+            title: format!("gnomAD SV {}", args.gnomad_kind),
+            genome_release: Some(genome_release),
+            created_from: vec![(
+                format!("gnomAD SV {}", args.gnomad_kind),
+                args.gnomad_version.clone(),
+            )],
+            paths_in: args.path_in_vcf.clone(),
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -141,6 +247,46 @@ mod test {
     use clap_verbosity_flag::Verbosity;
     use temp_testdir::TempDir;
 
+    use crate::pbs::gnomad::{gnomad_sv2::Evidence, gnomad_sv4::Filter};
+
+    #[rstest::rstest]
+    #[case::empty(&[], &[], &[])]
+    #[case::known(&["UNRESOLVED", "PASS"], &[Filter::Pass, Filter::Unresolved], &[])]
+    #[case::unknown(
+        &["HIGH_SR_BACKGROUND", "UNRESOLVED", "NEW_FILTER"],
+        &[Filter::Unresolved, Filter::HighSrBackground],
+        &["NEW_FILTER"]
+    )]
+    fn split_known_filters(
+        #[case] values: &[&str],
+        #[case] known: &[Filter],
+        #[case] raw: &[&str],
+    ) {
+        assert_eq!(
+            super::split_known::<Filter>(values.iter().copied()),
+            (
+                known.iter().map(|f| *f as i32).collect::<Vec<_>>(),
+                raw.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+            )
+        );
+    }
+
+    #[test]
+    fn split_known_evidence() {
+        assert_eq!(
+            super::split_known::<Evidence>(["SR", "PE", "XX", "RD", "BAF"]),
+            (
+                vec![
+                    Evidence::Baf as i32,
+                    Evidence::Pe as i32,
+                    Evidence::Rd as i32,
+                    Evidence::Sr as i32
+                ],
+                vec![String::from("XX")]
+            )
+        );
+    }
+
     /// Fixture with temporary directory.
     #[rstest::fixture]
     fn tmp_dir() -> TempDir {
@@ -172,9 +318,10 @@ mod test {
     ) -> Result<(), anyhow::Error> {
         let common = crate::common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
-            genome_release: crate::common::cli::GenomeRelease::Grch37,
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch37,
             gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Exomes,
             gnomad_version: String::from("1.0"),
             path_in_vcf: vec![String::from(
@@ -195,9 +342,10 @@ mod test {
     ) -> Result<(), anyhow::Error> {
         let common = crate::common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
-            genome_release: crate::common::cli::GenomeRelease::Grch37,
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch37,
             gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
             gnomad_version: String::from("2.1"),
             path_in_vcf: vec![
@@ -220,9 +368,10 @@ mod test {
     ) -> Result<(), anyhow::Error> {
         let common = crate::common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
-            genome_release: crate::common::cli::GenomeRelease::Grch38,
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch38,
             gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
             gnomad_version: String::from("4.0"),
             path_in_vcf: vec![String::from(
@@ -243,9 +392,10 @@ mod test {
     ) -> Result<(), anyhow::Error> {
         let common = crate::common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
-            genome_release: crate::common::cli::GenomeRelease::Grch38,
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch38,
             gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
             gnomad_version: String::from("4.0"),
             path_in_vcf: vec![
@@ -257,4 +407,163 @@ mod test {
 
         super::run(&common, &args)
     }
+
+    /// Unknown FILTER and EVIDENCE values are kept as raw strings.
+    #[tracing_test::traced_test]
+    #[rstest::rstest]
+    #[test]
+    fn import_gnomad_sv4_unknown_values(
+        args_tmpdir: (super::Args, TempDir),
+    ) -> Result<(), anyhow::Error> {
+        use prost::Message as _;
+
+        use crate::pbs::gnomad::{gnomad_sv2::Evidence, gnomad_sv4::Filter};
+
+        let common = crate::common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = super::Args {
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch38,
+            gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
+            gnomad_version: String::from("4.0"),
+            path_in_vcf: vec![String::from(
+                "tests/gnomad-sv/gnomad-sv4-unknown/unknown-values.vcf",
+            )],
+            ..args_tmpdir.0
+        };
+
+        super::run(&common, &args)?;
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &args.path_out_rocksdb,
+            ["meta", "gnomad_sv"],
+            false,
+        )?;
+        let cf_data = db.cf_handle("gnomad_sv").unwrap();
+        let data = db
+            .get_cf(&cf_data, "gnomAD-SV_v3_BND_chr1_c21f3781")?
+            .expect("record must exist");
+        let record = crate::pbs::gnomad::gnomad_sv4::Record::decode(&data[..])?;
+
+        assert_eq!(
+            record.filters,
+            vec![Filter::Unresolved as i32, Filter::HighSrBackground as i32]
+        );
+        assert_eq!(record.filters_raw, vec![String::from("NEW_FILTER")]);
+        assert_eq!(
+            record.evidence,
+            vec![Evidence::Pe as i32, Evidence::Sr as i32]
+        );
+        assert_eq!(record.evidence_raw, vec![String::from("LR")]);
+
+        Ok(())
+    }
+
+    /// Arguments for importing the GRCh38 coordinates in `path_in_vcf` as GRCh37 gnomAD SV v2.
+    fn args_mismatch(args: super::Args, path_in_vcf: &str, strict: bool) -> super::Args {
+        super::Args {
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch37,
+            gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
+            gnomad_version: String::from("2.1"),
+            path_in_vcf: vec![format!(
+                "tests/gnomad-sv/gnomad-sv2-mismatch/{}",
+                path_in_vcf
+            )],
+            strict,
+            ..args
+        }
+    }
+
+    /// Few records outside of the contigs are only reported.
+    #[tracing_test::traced_test]
+    #[rstest::rstest]
+    #[test]
+    fn import_gnomad_sv2_mismatch_warn(
+        args_tmpdir: (super::Args, TempDir),
+    ) -> Result<(), anyhow::Error> {
+        let common = crate::common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = args_mismatch(args_tmpdir.0, "one-violation.vcf", false);
+
+        super::run(&common, &args)?;
+
+        assert!(logs_contain(
+            "1 of 101 records are outside of the contigs of grch37"
+        ));
+
+        Ok(())
+    }
+
+    /// With `--strict`, the first record outside of the contigs fails the import.
+    #[rstest::rstest]
+    #[test]
+    fn import_gnomad_sv2_mismatch_strict(args_tmpdir: (super::Args, TempDir)) {
+        let common = crate::common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = args_mismatch(args_tmpdir.0, "one-violation.vcf", true);
+
+        let err = super::run(&common, &args).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "record gnomAD-SV_v2.1_DEL_X_1 has position X:155800000 outside of the contig \
+            length 155270560 for grch37"
+        );
+    }
+
+    /// More than 1% of the records outside of the contigs fail the import even without
+    /// `--strict`.
+    #[rstest::rstest]
+    #[test]
+    fn import_gnomad_sv2_mismatch_many(args_tmpdir: (super::Args, TempDir)) {
+        let common = crate::common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = args_mismatch(args_tmpdir.0, "many-violations.vcf", false);
+
+        let err = super::run(&common, &args).unwrap_err();
+
+        assert!(err
+            .to_string()
+            .starts_with("2 of 3 records are outside of the contigs of grch37"));
+    }
+
+    /// Dry run must not create the database and must report all problems.
+    #[rstest::rstest]
+    #[test]
+    fn dry_run_import_gnomad_sv2(args_tmpdir: (super::Args, TempDir)) {
+        let common = crate::common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = super::Args {
+            genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch37,
+            gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Genomes,
+            gnomad_version: String::from("2.1"),
+            path_in_vcf: vec![String::from(
+                "tests/gnomad-sv/gnomad-sv2/gnomad_v2.1_sv.sites.vcf",
+            )],
+            dry_run: true,
+            ..args_tmpdir.0
+        };
+
+        super::run(&common, &args).unwrap();
+        assert!(!args_tmpdir.1.join("out-rocksdb").exists());
+
+        let args = super::Args {
+            gnomad_version: String::from("0.1"),
+            path_in_vcf: vec![String::from("tests/gnomad-sv/gnomad-sv2/missing.vcf")],
+            ..args
+        };
+        let validation = super::validate(&args);
+        assert_eq!(validation.problems.len(), 2, "{:?}", validation.problems);
+        assert!(super::run(&common, &args).is_err());
+    }
 }
@@ -1,6 +1,6 @@
 //! Query gnomAD-SV RocksDB
 
-use std::{io::Write, sync::Arc};
+use std::sync::Arc;
 
 use bio::data_structures::interval_tree::ArrayBackedIntervalTree;
 use prost::Message;
@@ -36,12 +36,17 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// Only output records with FILTER=PASS.
+    ///
+    /// ExAC and gnomAD-CNV records have no FILTER information and are always output.
+    #[arg(long)]
+    pub pass_only: bool,
 }
 
 /// Meta information as read from database.
@@ -66,25 +71,25 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
-        let gnomad_kind = String::from_utf8(
-            db.get_cf(&cf_meta, "gnomad-kind")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:gnomad-kind"))?,
-        )?;
-        let gnomad_version = String::from_utf8(
-            db.get_cf(&cf_meta, "gnomad-version")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:gnomad-version"))?,
-        )?;
+        let genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
+        let gnomad_kind =
+            String::from_utf8(db.get_cf(&cf_meta, "gnomad-kind")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:gnomad-kind")
+            })?)?;
+        let gnomad_version =
+            String::from_utf8(db.get_cf(&cf_meta, "gnomad-version")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:gnomad-version")
+            })?)?;
         Meta {
             genome_release,
             gnomad_kind,
@@ -136,6 +141,19 @@ pub struct TreeData {
 }
 
 impl Record {
+    /// Return whether the record passed all filters, records without filters pass.
+    pub fn is_pass(&self) -> bool {
+        match self {
+            Record::ExacCnv(_) | Record::GnomadCnv4(_) => true,
+            Record::GnomadSv2(record) => record
+                .filters
+                .contains(&(crate::pbs::gnomad::gnomad_sv2::Filter::Pass as i32)),
+            Record::GnomadSv4(record) => record
+                .filters
+                .contains(&(crate::pbs::gnomad::gnomad_sv4::Filter::Pass as i32)),
+        }
+    }
+
     fn tree_data(&self) -> TreeData {
         match self {
             Record::ExacCnv(record) => TreeData {
@@ -168,28 +186,23 @@ impl Record {
     }
 }
 
-/// Write a single record to `out_writer`.
+/// Write a single record to `out_writer` with `CHROM:START-STOP` as the key.
 fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     value: &Record,
 ) -> Result<(), anyhow::Error> {
-    match (output_format, value) {
-        (common::cli::OutputFormat::Jsonl, Record::ExacCnv(record)) => {
-            writeln!(out_writer, "{}", serde_json::to_string(record)?)?
-        }
-        (common::cli::OutputFormat::Jsonl, Record::GnomadSv2(record)) => {
-            writeln!(out_writer, "{}", serde_json::to_string(record)?)?
-        }
-        (common::cli::OutputFormat::Jsonl, Record::GnomadCnv4(record)) => {
-            writeln!(out_writer, "{}", serde_json::to_string(record)?)?
-        }
-        (common::cli::OutputFormat::Jsonl, Record::GnomadSv4(record)) => {
-            writeln!(out_writer, "{}", serde_json::to_string(record)?)?
-        }
+    let TreeData {
+        chromosome,
+        start,
+        stop,
+    } = value.tree_data();
+    let key = format!("{}:{}-{}", chromosome, start, stop);
+    match value {
+        Record::ExacCnv(record) => out_writer.write(&key, record),
+        Record::GnomadSv2(record) => out_writer.write(&key, record),
+        Record::GnomadCnv4(record) => out_writer.write(&key, record),
+        Record::GnomadSv4(record) => out_writer.write(&key, record),
     }
-
-    Ok(())
 }
 
 /// Decode a record with `Meta` data as help.
@@ -224,13 +237,13 @@ fn decode_record(data: &[u8], meta: &Meta) -> Result<Record, anyhow::Error> {
     )
 }
 
-/// Query all variants and print to `out_writer`.
+/// Query all variants and print to `out_writer`, only the passing ones if `pass_only`.
 fn print_all(
-    out_writer: &mut Box<dyn std::io::Write>,
-    out_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
     meta: &Meta,
+    pass_only: bool,
 ) -> Result<(), anyhow::Error> {
     tracing::info!("dumping all records...");
 
@@ -238,7 +251,10 @@ fn print_all(
     iter.seek(b"");
     while iter.valid() {
         if let Some(raw_value) = iter.value() {
-            print_record(out_writer, out_format, &decode_record(raw_value, meta)?)?;
+            let record = decode_record(raw_value, meta)?;
+            if !pass_only || record.is_pass() {
+                print_record(out_writer, &record)?;
+            }
             iter.next();
         } else {
             break;
@@ -344,7 +360,8 @@ impl IntervalTrees {
     /// Query for a range.
     pub fn query(&self, range: &spdi::Range) -> Result<Vec<Record>, anyhow::Error> {
         tracing::trace!("query for {:?}", &range);
-        let contig = extract_chrom::from_range(range, Some(&self.meta.genome_release))?;
+        let range = extract_chrom::range(range, Some(&self.meta.genome_release))?;
+        let contig = range.sequence.clone();
         let cf_data = self.db.cf_handle(&self.cf_data_name).ok_or_else(|| {
             anyhow::anyhow!("no column family with name {:?} found", &self.cf_data_name)
         })?;
@@ -375,13 +392,12 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "gnomad-sv",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
@@ -395,8 +411,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         let records = trees
             .query(range)
             .map_err(|e| anyhow::anyhow!("failed to query interval trees: {}", e))?;
-        for record in &records {
-            print_record(&mut out_writer, args.out_format, record)?;
+        for record in records
+            .iter()
+            .filter(|record| !args.pass_only || record.is_pass())
+        {
+            print_record(&mut out_writer, record)?;
         }
         tracing::info!("... done running query");
     } else if let Some(accession) = args.query.accession.as_ref() {
@@ -406,14 +425,18 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             .map_err(|e| anyhow::anyhow!("failed to query RocksDB: {}", e))?;
         if let Some(buf) = buf {
             let record = decode_record(&buf, &meta)?;
-            print_record(&mut out_writer, args.out_format, &record)?;
+            if !args.pass_only || record.is_pass() {
+                print_record(&mut out_writer, &record)?;
+            } else {
+                tracing::info!("record for accession {} does not pass filters", accession);
+            }
         } else {
             tracing::warn!("no record found for accession {}", accession);
         }
         tracing::info!("... done running query");
     } else if args.query.all {
         tracing::info!("for all");
-        print_all(&mut out_writer, args.out_format, &db, &cf_data, &meta)?;
+        print_all(&mut out_writer, &db, &cf_data, &meta, args.pass_only)?;
     } else {
         unreachable!();
     }
@@ -435,11 +458,12 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
             cf_name: String::from("gnomad_sv"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             ..Default::default()
         };
 
@@ -475,6 +499,52 @@ mod test {
         Ok(())
     }
 
+    #[tracing_test::traced_test]
+    #[rstest::rstest]
+    #[case("tests/gnomad-sv/gnomad-sv2/rocksdb")]
+    #[case("tests/gnomad-sv/gnomad-sv4/rocksdb")]
+    fn query_var_all_pass_only(
+        #[case] path_rocksdb: &str,
+        args_args_temp: (common::cli::Args, super::Args, TempDir),
+    ) -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args_args_temp;
+        let args = super::Args {
+            path_rocksdb: path_rocksdb.to_string(),
+            query: super::ArgsQuery {
+                all: true,
+                ..Default::default()
+            },
+            ..args
+        };
+        super::run(&common, &args)?;
+        let all = std::fs::read_to_string(&args.out_file)?.lines().count();
+        let args = super::Args {
+            pass_only: true,
+            ..args
+        };
+        super::run(&common, &args)?;
+        let out_data = std::fs::read_to_string(&args.out_file)?;
+
+        let records = out_data
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!records.is_empty());
+        assert!(records.len() < all);
+        for record in &records {
+            assert!(
+                record["filters"]
+                    .as_array()
+                    .expect("filters must be an array")
+                    .contains(&serde_json::Value::from("FILTER_PASS")),
+                "{}",
+                record
+            );
+        }
+
+        Ok(())
+    }
+
     #[tracing_test::traced_test]
     #[rstest::rstest]
     #[case(
@@ -12,6 +12,75 @@ use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::{common, helixmtdb};
 
+/// Helper data structures for reading the HelixMtDb TSV file.
+pub mod reading {
+    /// One line of the HelixMtDb TSV file.
+    ///
+    /// Further columns such as the allele frequencies and haplogroups are ignored.
+    #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    pub struct Record {
+        /// Locus, e.g., `chrM:5`.
+        pub locus: String,
+        /// JSON list with the reference and alternate alleles, e.g., `["A","C"]`.
+        pub alleles: String,
+        /// Feature type.
+        pub feature: String,
+        /// Gene name.
+        pub gene: String,
+        /// Number of homoplasmic carriers.
+        pub counts_hom: i32,
+        /// Number of heteroplasmic carriers.
+        pub counts_het: i32,
+    }
+
+    impl Record {
+        /// Convert into one `helixmtdb::pbs::Record` per alternate allele.
+        ///
+        /// The TSV file has no allele number, so `num_total` must be given.
+        pub fn into_records(
+            self,
+            num_total: i32,
+        ) -> Result<Vec<crate::helixmtdb::pbs::Record>, anyhow::Error> {
+            let (chrom, pos) = self
+                .locus
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("invalid locus: {}", &self.locus))?;
+            let pos: i32 = pos.parse()?;
+            let alleles: Vec<String> = serde_json::from_str(&self.alleles)
+                .map_err(|e| anyhow::anyhow!("invalid alleles {}: {}", &self.alleles, e))?;
+            let (ref_allele, alt_alleles) = alleles
+                .split_first()
+                .ok_or_else(|| anyhow::anyhow!("no alleles at {}", &self.locus))?;
+
+            Ok(alt_alleles
+                .iter()
+                .map(|alt_allele| crate::helixmtdb::pbs::Record {
+                    chrom: chrom.to_string(),
+                    pos,
+                    ref_allele: ref_allele.clone(),
+                    alt_allele: alt_allele.clone(),
+                    num_total,
+                    num_het: self.counts_het,
+                    num_hom: self.counts_hom,
+                    feature_type: self.feature.clone(),
+                    gene_name: self.gene.clone(),
+                })
+                .collect())
+        }
+    }
+}
+
+/// Format of the input file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum InputFormat {
+    /// Bgzip-compressed and tabix-indexed VCF file.
+    #[default]
+    Vcf,
+    /// TSV file as distributed by Helix, possibly gzip-compressed, see `reading::Record`.
+    Tsv,
+}
+
 /// Command line arguments for `helixmtdb import` sub command.
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import HelixMtDb data into RocksDB", long_about = None)]
@@ -19,12 +88,19 @@ pub struct Args {
     /// Genome build to use in the build.
     #[arg(long, value_enum)]
     pub genome_release: common::cli::GenomeRelease,
-    /// Path to input VCF file(s).
+    /// Path to input VCF or TSV file, see `--input-format`.
     #[arg(long, required = true)]
     pub path_in_vcf: String,
     /// Path to output RocksDB directory.
     #[arg(long)]
     pub path_out_rocksdb: String,
+    /// Format of the input file.
+    #[arg(long, value_enum, default_value_t = InputFormat::Vcf)]
+    pub input_format: InputFormat,
+    /// Total number of individuals for TSV input which, in contrast to the VCF file, does not
+    /// have the allele number.
+    #[arg(long, default_value_t = 196_554)]
+    pub tsv_num_total: i32,
 
     /// Windows size for TBI-based parallel import.
     #[arg(long, default_value = "100000")]
@@ -36,12 +112,25 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform TBI-parallel import of the data.
-fn tsv_import(
+fn vcf_import(
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
     args: &Args,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Load tabix header and create BGZF reader with tabix index.
     let tabix_src = format!("{}.tbi", args.path_in_vcf);
@@ -80,7 +169,7 @@ fn tsv_import(
     windows
         .par_iter()
         .progress_with(common::cli::progress_bar(windows.len()))
-        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args))
+        .map(|(chrom, begin, end)| process_window(db.clone(), chrom, *begin, *end, args, sampler))
         .collect::<Result<Vec<_>, _>>()?;
     tracing::info!(
         "... done loading HelixMtDB VCF file into RocksDB in {:?}",
@@ -90,6 +179,62 @@ fn tsv_import(
     Ok(())
 }
 
+/// Perform import of the data from the TSV file.
+fn tsv_import(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    args: &Args,
+    sampler: &common::sample::Sampler,
+) -> Result<(), anyhow::Error> {
+    let cf_helix = db.cf_handle(&args.cf_name).unwrap();
+
+    // Open reader, possibly decompressing gziped files.
+    let reader: Box<dyn std::io::Read> = if args.path_in_vcf.ends_with(".gz") {
+        Box::new(flate2::read::GzDecoder::new(std::fs::File::open(
+            &args.path_in_vcf,
+        )?))
+    } else {
+        Box::new(std::fs::File::open(&args.path_in_vcf)?)
+    };
+
+    // Construct CSV reader.
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .quoting(false)
+        .from_reader(reader);
+
+    tracing::info!("Loading HelixMtDB TSV file into RocksDB...");
+    let before_loading = std::time::Instant::now();
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
+    for result in csv_reader.deserialize() {
+        let tsv_record: reading::Record = result?;
+        for record in tsv_record.into_records(args.tsv_num_total)? {
+            let var = common::keys::Var::from(
+                &record.chrom,
+                record.pos,
+                &record.ref_allele,
+                &record.alt_allele,
+            );
+            let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+                continue;
+            };
+            let key_buf: Vec<u8> = var.into();
+            if !sampler.keep(&key_buf) {
+                continue;
+            }
+            tracing::trace!("  record: {:?}", &record);
+            db.put_cf(&cf_helix, &key_buf, record.encode_to_vec())?;
+        }
+    }
+    invalid_alleles.log(&args.path_in_vcf);
+    tracing::info!(
+        "... done loading HelixMtDB TSV file into RocksDB in {:?}",
+        before_loading.elapsed()
+    );
+
+    Ok(())
+}
+
 /// Process one window.
 fn process_window(
     db: Arc<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
@@ -97,6 +242,7 @@ fn process_window(
     begin: usize,
     end: usize,
     args: &Args,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     let cf_helix = db.cf_handle(&args.cf_name).unwrap();
     let mut reader =
@@ -124,6 +270,7 @@ fn process_window(
 
     // Process the result (skip if determined above that the sequence does not
     // exist).
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     if let Some(query) = query {
         for result in query {
             let vcf_record = result?;
@@ -131,8 +278,14 @@ fn process_window(
 
             // Process each alternate allele into one record.
             for allele_no in 0..vcf_record.alternate_bases().len() {
-                let key_buf: Vec<u8> =
-                    common::keys::Var::from_vcf_allele(&vcf_record, allele_no).into();
+                let var = common::keys::Var::from_vcf_allele(&vcf_record, allele_no);
+                let Some(var) = invalid_alleles.normalize(&var, args.allow_iupac) else {
+                    continue;
+                };
+                let key_buf: Vec<u8> = var.into();
+                if !sampler.keep(&key_buf) {
+                    continue;
+                }
                 let record = helixmtdb::pbs::Record::from_vcf_allele(&vcf_record, allele_no)?;
                 tracing::trace!("  record: {:?}", &record);
                 let record_buf = record.encode_to_vec();
@@ -141,15 +294,38 @@ fn process_window(
         }
     }
 
+    invalid_alleles.log(&raw_region);
+
     Ok(())
 }
 
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if validation.input_file(&args.path_in_vcf) && args.input_format == InputFormat::Vcf {
+        validation.tabix_index(&args.path_in_vcf);
+        validation.vcf_header(&args.path_in_vcf, args.genome_release);
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `helixmtdb import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'helixmtdb import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+    let sampler = common::sample::Sampler::new(&args.sample)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -169,17 +345,23 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
     db.put_cf(
         &cf_meta,
         "genome-release",
         format!("{}", args.genome_release),
     )?;
+    sampler.write_meta(&db, &cf_meta)?;
     tracing::info!(
         "... done opening RocksDB for writing in {:?}",
         before_opening_rocksdb.elapsed()
     );
 
-    tsv_import(db.clone(), args)?;
+    match args.input_format {
+        InputFormat::Vcf => vcf_import(db.clone(), args, &sampler)?,
+        InputFormat::Tsv => tsv_import(&db, args, &sampler)?,
+    }
+    sampler.log();
 
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
@@ -189,6 +371,19 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "helixmtdb".to_string(),
+            title: "HelixMtDb".to_string(),
+            genome_release: Some(args.genome_release),
+            created_from: vec![("HelixMtDb".to_string(), args.spec.data_version())],
+            paths_in: vec![args.path_in_vcf.clone()],
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -200,21 +395,158 @@ mod test {
     use clap_verbosity_flag::Verbosity;
     use temp_testdir::TempDir;
 
+    /// Return the arguments for importing `path_in` in the given format into `tmp_dir`.
+    fn import_args(tmp_dir: &TempDir, path_in: &str, input_format: InputFormat) -> Args {
+        Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_vcf: String::from(path_in),
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            input_format,
+            tsv_num_total: 196_554,
+            cf_name: String::from("helixmtdb_data"),
+            path_wal_dir: None,
+            allow_iupac: false,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            tbi_window_size: 1_000_000,
+        }
+    }
+
     #[test]
     fn smoke_test_import_helix() {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = import_args(
+            &tmp_dir,
+            "tests/helixmtdb/example/helixmtdb.vcf.bgz",
+            InputFormat::Vcf,
+        );
+
+        run(&common, &args).unwrap();
+
+        let spec =
+            crate::server::run::versions::schema::VersionSpec::from_path(tmp_dir.join("spec.yaml"))
+                .unwrap();
+        assert_eq!(spec.identifier, "annonars/helixmtdb:unknown+0.0.0-grch37");
+        assert_eq!(spec.genome_release.as_deref(), Some("grch37"));
+        assert_eq!(spec.source, vec![args.path_in_vcf.clone()]);
+        assert_eq!(spec.created_from[0].name, "HelixMtDb");
+    }
+
+    #[test]
+    fn import_tsv_same_as_vcf() -> Result<(), anyhow::Error> {
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let mut out_data = Vec::new();
+        for (path_in, input_format) in [
+            (
+                "tests/helixmtdb/example/helixmtdb.vcf.bgz",
+                InputFormat::Vcf,
+            ),
+            ("tests/helixmtdb/example/helixmtdb.tsv", InputFormat::Tsv),
+        ] {
+            let tmp_dir = TempDir::default();
+            let args = import_args(&tmp_dir, path_in, input_format);
+            run(&common, &args)?;
+
+            let out_file = format!("{}", tmp_dir.join("out.jsonl").display());
+            crate::helixmtdb::cli::query::run(
+                &common,
+                &crate::helixmtdb::cli::query::Args {
+                    path_rocksdb: Some(args.path_out_rocksdb.clone()),
+                    server_url: None,
+                    cf_name: args.cf_name.clone(),
+                    out_file: out_file.clone(),
+                    output: Default::default(),
+                    query: crate::cons::cli::args::vars::ArgsQuery {
+                        all: true,
+                        ..Default::default()
+                    },
+                },
+            )?;
+            out_data.push(std::fs::read_to_string(&out_file)?);
+        }
+
+        assert_eq!(out_data[0].lines().count(), 4);
+        assert_eq!(out_data[0], out_data[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tsv_record_multi_allelic() -> Result<(), anyhow::Error> {
+        let tsv_record = reading::Record {
+            locus: String::from("chrM:302"),
+            alleles: String::from(r#"["A","AC","ACC"]"#),
+            feature: String::from("non_coding"),
+            gene: String::from("MT-CRb"),
+            counts_hom: 3,
+            counts_het: 2,
+        };
+
+        let records = tsv_record.into_records(100)?;
+
+        assert_eq!(records.len(), 2);
+        for (record, alt_allele) in records.iter().zip(["AC", "ACC"]) {
+            assert_eq!(record.chrom, "chrM");
+            assert_eq!(record.pos, 302);
+            assert_eq!(record.ref_allele, "A");
+            assert_eq!(record.alt_allele, alt_allele);
+            assert_eq!(record.num_total, 100);
+            assert_eq!(record.num_hom, 3);
+            assert_eq!(record.num_het, 2);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn tsv_record_invalid_locus() {
+        let tsv_record = reading::Record {
+            locus: String::from("chrM"),
+            alleles: String::from(r#"["A","C"]"#),
+            feature: String::from("non_coding"),
+            gene: String::from("MT-CRb"),
+            counts_hom: 1,
+            counts_het: 0,
+        };
+
+        assert!(tsv_record.into_records(100).is_err());
+    }
+
+    /// Dry run must not create the database and must find the missing tabix index.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
-            genome_release: common::cli::GenomeRelease::Grch37,
-            path_in_vcf: String::from("tests/helixmtdb/example/helixmtdb.vcf.bgz"),
-            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
-            cf_name: String::from("helixmtdb_data"),
-            path_wal_dir: None,
-            tbi_window_size: 1_000_000,
+            dry_run: true,
+            ..import_args(
+                &tmp_dir,
+                "tests/helixmtdb/example/helixmtdb.vcf.bgz",
+                InputFormat::Vcf,
+            )
         };
 
         run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        let args = Args {
+            path_in_vcf: String::from("tests/helixmtdb/example/helixmtdb.vcf"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(validation.problems[0].starts_with("tabix index"));
+        assert!(run(&common, &args).is_err());
     }
 }
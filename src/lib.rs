@@ -3,6 +3,7 @@
 #![allow(clippy::module_name_repetitions)]
 #![warn(missing_docs)]
 
+pub mod alphamissense;
 pub mod clinvar_genes;
 pub mod clinvar_minimal;
 pub mod clinvar_sv;
@@ -14,6 +15,7 @@ mod error;
 pub mod freqs;
 pub mod functional;
 pub mod genes;
+pub mod gnomad_coverage;
 pub mod gnomad_mtdna;
 pub mod gnomad_nuclear;
 pub mod gnomad_sv;
@@ -1,9 +1,10 @@
 use annonars::{
-    clinvar_genes, clinvar_minimal, clinvar_sv, common, cons, db_utils, dbsnp, freqs, functional,
-    genes, gnomad_mtdna, gnomad_nuclear, gnomad_sv, helixmtdb, regions, server, tsv,
+    alphamissense, clinvar_genes, clinvar_minimal, clinvar_sv, common, cons, db_utils, dbsnp,
+    freqs, functional, genes, gnomad_coverage, gnomad_mtdna, gnomad_nuclear, gnomad_sv, helixmtdb,
+    regions, server, tsv,
 };
 use anyhow::Error;
-use clap::{command, Args, Parser, Subcommand};
+use clap::{command, Args, CommandFactory as _, FromArgMatches as _, Parser, Subcommand};
 
 /// CLI parser based on clap.
 #[derive(Debug, Clone, Parser)]
@@ -11,7 +12,8 @@ use clap::{command, Args, Parser, Subcommand};
     author,
     version,
     about = "RocksDB-based genome annotations",
-    long_about = "Genome annotation stored in RocksDB."
+    long_about = "Genome annotation stored in RocksDB.",
+    after_help = annonars::EXIT_CODES_HELP
 )]
 struct Cli {
     /// Commonly used arguments
@@ -46,6 +48,10 @@ enum Commands {
     Dbsnp(Dbsnp),
     /// "helixmtdb" sub commands
     Helixmtdb(Helixmtdb),
+    /// "alphamissense" sub commands
+    Alphamissense(Alphamissense),
+    /// "gnomad-coverage" sub commands
+    GnomadCoverage(GnomadCoverage),
     /// "gnomad-mtdna" sub commands
     GnomadMtdna(GnomadMtdna),
     /// "gnomad-nuclear" sub commands
@@ -56,6 +62,8 @@ enum Commands {
     Regions(Regions),
     /// "db-utils" sub commands
     DbUtils(DbUtils),
+    /// "report" sub commands
+    Report(Report),
     /// "server" sub command.
     Server(Server),
 }
@@ -75,6 +83,12 @@ enum GeneCommands {
     Import(Box<genes::cli::import::Args>),
     /// "query" sub command
     Query(Box<genes::cli::query::Args>),
+    /// "lookup" sub command
+    Lookup(Box<genes::cli::lookup::Args>),
+    /// "export-sqlite" sub command
+    ExportSqlite(Box<genes::cli::export_sqlite::Args>),
+    /// "export-search-index" sub command
+    ExportSearchIndex(Box<genes::cli::export_search_index::Args>),
 }
 
 /// Parsing of "tsv" subcommand
@@ -109,6 +123,8 @@ enum ClinvarGeneCommands {
     Import(clinvar_genes::cli::import::Args),
     /// "query" sub command
     Query(clinvar_genes::cli::query::Args),
+    /// "watchlist" sub command
+    Watchlist(clinvar_genes::cli::watchlist::Args),
 }
 
 /// Parsing of "clinvar-minimal" subcommand.
@@ -194,6 +210,8 @@ enum FreqsCommands {
     Import(freqs::cli::import::Args),
     /// "query" sub command
     Query(freqs::cli::query::Args),
+    /// "export-vcf" sub command
+    ExportVcf(freqs::cli::export_vcf::Args),
 }
 
 /// Parsing of "functional" subcommands.
@@ -230,6 +248,38 @@ enum HelixmtdbCommands {
     Query(helixmtdb::cli::query::Args),
 }
 
+/// Parsing of "alphamissense" subcommands.
+#[derive(Debug, Args, Clone)]
+struct Alphamissense {
+    /// The sub command to run
+    #[command(subcommand)]
+    command: AlphamissenseCommands,
+}
+
+/// Enum supporting the parsing of "alphamissense *" subcommands.
+#[derive(Debug, Subcommand, Clone)]
+enum AlphamissenseCommands {
+    /// "import" sub command
+    Import(alphamissense::cli::import::Args),
+    /// "query" sub command
+    Query(alphamissense::cli::query::Args),
+}
+
+/// Parsing of "gnomad-coverage" subcommands.
+#[derive(Debug, Args, Clone)]
+struct GnomadCoverage {
+    /// The sub command to run
+    #[command(subcommand)]
+    command: GnomadCoverageCommands,
+}
+
+/// Enum supporting the parsing of "gnomad-coverage *" subcommands.
+#[derive(Debug, Subcommand, Clone)]
+enum GnomadCoverageCommands {
+    /// "import" sub command
+    Import(gnomad_coverage::cli::import::Args),
+}
+
 /// Parsing of "gnomad-mtdna" subcommands.
 #[derive(Debug, Args, Clone)]
 struct GnomadMtdna {
@@ -313,6 +363,27 @@ enum DbUtilsCommands {
     Copy(db_utils::cli::copy::Args),
     /// "dump-meta" sub command
     DumpMeta(db_utils::cli::dump_meta::Args),
+    /// "export" sub command
+    Export(db_utils::cli::export::Args),
+    /// "import-archive" sub command
+    ImportArchive(db_utils::cli::import_archive::Args),
+    /// "upgrade" sub command
+    Upgrade(db_utils::cli::upgrade::Args),
+}
+
+/// Parsing of "report" subcommands.
+#[derive(Debug, Args, Clone)]
+struct Report {
+    /// The sub command to run
+    #[command(subcommand)]
+    command: ReportCommands,
+}
+
+/// Enum supporting the parsing of "report *" subcommands.
+#[derive(Debug, Subcommand, Clone)]
+enum ReportCommands {
+    /// "gene-panel" sub command
+    GenePanel(Box<genes::cli::panel_report::Args>),
 }
 
 /// Parsing of "server" subcommands.
@@ -332,8 +403,26 @@ enum ServerCommands {
     Schema(Box<crate::server::schema::Args>),
 }
 
-pub fn main() -> Result<(), anyhow::Error> {
-    let cli = Cli::parse();
+/// Run the command, the exit code is derived from the `ErrorCategory` of the error.
+///
+/// Invalid command line arguments are reported by `clap` with exit code 2 already.
+pub fn main() -> std::process::ExitCode {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    match run(&cli, &matches) {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        // The reader of the output has gone away, e.g., `head` after enough lines.
+        Err(err) if annonars::is_broken_pipe(&err) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            std::process::ExitCode::from(annonars::ErrorCategory::of(&err).exit_code())
+        }
+    }
+}
+
+/// Run the command selected in `cli`, parsed from `matches`.
+fn run(cli: &Cli, matches: &clap::ArgMatches) -> Result<(), anyhow::Error> {
+    common::cli::init_progress(&cli.common);
 
     // Build a tracing subscriber according to the configuration in `cli.common`.
     let collector = tracing_subscriber::fmt()
@@ -357,6 +446,13 @@ pub fn main() -> Result<(), anyhow::Error> {
             Commands::Gene(args) => match &args.command {
                 GeneCommands::Import(args) => genes::cli::import::run(&cli.common, args)?,
                 GeneCommands::Query(args) => genes::cli::query::run(&cli.common, args)?,
+                GeneCommands::Lookup(args) => genes::cli::lookup::run(&cli.common, args)?,
+                GeneCommands::ExportSqlite(args) => {
+                    genes::cli::export_sqlite::run(&cli.common, args)?
+                }
+                GeneCommands::ExportSearchIndex(args) => {
+                    genes::cli::export_search_index::run(&cli.common, args)?
+                }
             },
             Commands::Tsv(args) => match &args.command {
                 TsvCommands::Import(args) => tsv::cli::import::run(&cli.common, args)?,
@@ -369,6 +465,9 @@ pub fn main() -> Result<(), anyhow::Error> {
                 ClinvarGeneCommands::Query(args) => {
                     clinvar_genes::cli::query::run(&cli.common, args)?
                 }
+                ClinvarGeneCommands::Watchlist(args) => {
+                    clinvar_genes::cli::watchlist::run(&cli.common, args)?
+                }
             },
             Commands::ClinvarMinimal(args) => match &args.command {
                 ClinvarMinimalCommands::Import(args) => {
@@ -393,6 +492,7 @@ pub fn main() -> Result<(), anyhow::Error> {
             Commands::Freqs(args) => match &args.command {
                 FreqsCommands::Import(args) => freqs::cli::import::run(&cli.common, args)?,
                 FreqsCommands::Query(args) => freqs::cli::query::run(&cli.common, args)?,
+                FreqsCommands::ExportVcf(args) => freqs::cli::export_vcf::run(&cli.common, args)?,
             },
             Commands::Functional(args) => match &args.command {
                 FunctionalCommands::Import(args) => {
@@ -404,6 +504,19 @@ pub fn main() -> Result<(), anyhow::Error> {
                 HelixmtdbCommands::Import(args) => helixmtdb::cli::import::run(&cli.common, args)?,
                 HelixmtdbCommands::Query(args) => helixmtdb::cli::query::run(&cli.common, args)?,
             },
+            Commands::Alphamissense(args) => match &args.command {
+                AlphamissenseCommands::Import(args) => {
+                    alphamissense::cli::import::run(&cli.common, args)?
+                }
+                AlphamissenseCommands::Query(args) => {
+                    alphamissense::cli::query::run(&cli.common, args)?
+                }
+            },
+            Commands::GnomadCoverage(args) => match &args.command {
+                GnomadCoverageCommands::Import(args) => {
+                    gnomad_coverage::cli::import::run(&cli.common, args)?
+                }
+            },
             Commands::GnomadMtdna(args) => match &args.command {
                 GnomadMtdnaCommands::Import(args) => {
                     gnomad_mtdna::cli::import::run(&cli.common, args)?
@@ -433,9 +546,26 @@ pub fn main() -> Result<(), anyhow::Error> {
                 DbUtilsCommands::DumpMeta(args) => {
                     db_utils::cli::dump_meta::run(&cli.common, args)?
                 }
+                DbUtilsCommands::Export(args) => db_utils::cli::export::run(&cli.common, args)?,
+                DbUtilsCommands::ImportArchive(args) => {
+                    db_utils::cli::import_archive::run(&cli.common, args)?
+                }
+                DbUtilsCommands::Upgrade(args) => db_utils::cli::upgrade::run(&cli.common, args)?,
+            },
+            Commands::Report(args) => match &args.command {
+                ReportCommands::GenePanel(args) => {
+                    genes::cli::panel_report::run(&cli.common, args)?
+                }
             },
             Commands::Server(args) => match &args.command {
-                ServerCommands::Run(args) => server::run::run(&cli.common, args)?,
+                ServerCommands::Run(args) => {
+                    let matches = matches
+                        .subcommand_matches("server")
+                        .and_then(|matches| matches.subcommand_matches("run"))
+                        .expect("matches of server run");
+                    let args = server::run::config::resolve(args, matches)?;
+                    server::run::run(&cli.common, &args)?
+                }
                 ServerCommands::Schema(args) => {
                     server::schema::run(&cli.common, args)?;
                 }
@@ -0,0 +1,7 @@
+//! Code generate for protobufs by `prost-build`.
+
+include!(concat!(env!("OUT_DIR"), "/annonars.alphamissense.base.rs"));
+include!(concat!(
+    env!("OUT_DIR"),
+    "/annonars.alphamissense.base.serde.rs"
+));
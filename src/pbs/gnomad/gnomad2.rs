@@ -27,7 +27,8 @@ pub static POPS: &[&str] = &[
 /// Options struct that allows to specify which details fields are to be extracted from
 /// gnomAD-exomes/genomes VCF records.
 ///
-/// The fields that have `true` as its default are `vep`, `var_info`, and `pop_global_cohort`.
+/// The fields that have `true` as its default are `vep`, `var_info`, `pop_global_cohort`, and
+/// `liftover`.  The liftover info is only present in the GRCh38 liftover VCFs of gnomAD v2.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct DetailsOptions {
     /// Enable extraction of `Vep` records.
@@ -61,7 +62,7 @@ impl Default for DetailsOptions {
             quality: false,
             age_hists: false,
             depth_details: false,
-            liftover: false,
+            liftover: true,
         }
     }
 }
@@ -140,7 +141,7 @@ impl Record {
             .transpose()?;
         let liftover_info = options
             .liftover
-            .then(|| Self::extract_liftover(record))
+            .then(|| Self::extract_liftover(record, &ref_allele, &alt_allele))
             .transpose()?
             .flatten();
 
@@ -187,21 +188,63 @@ impl Record {
         }
     }
 
-    /// Extract the liftover related fields into gnomAD v2 `Vep` records.
+    /// Extract the liftover related fields into `LiftoverInfo`, if any.
+    ///
+    /// Both the fields written by Picard `LiftoverVcf` (`OriginalContig`, `OriginalStart`,
+    /// `OriginalAlleles`, ...) and by Hail (`original_locus` as `CONTIG:POS` and
+    /// `original_alleles`) are supported.  If the original alleles are not given but the
+    /// alleles were reverse complemented or swapped in the liftover, they are derived from
+    /// `ref_allele` and `alt_allele` for alleles of the same length.  For indels, the
+    /// padding base differs on the other strand, so they cannot be derived.
     fn extract_liftover(
         record: &noodles::vcf::variant::RecordBuf,
+        ref_allele: &str,
+        alt_allele: &str,
     ) -> Result<Option<LiftoverInfo>, anyhow::Error> {
-        let tmp = LiftoverInfo {
+        let original_locus = common::noodles::get_string(record, "original_locus")
+            .ok()
+            .map(|locus| match locus.rsplit_once(':') {
+                Some((contig, start)) => Ok((contig.to_string(), start.to_string())),
+                None => Err(anyhow::anyhow!("invalid INFO/original_locus: {}", locus)),
+            })
+            .transpose()?;
+        let (locus_contig, locus_start) = original_locus.unzip();
+        let mut tmp = LiftoverInfo {
             reverse_complemented_alleles: common::noodles::get_flag(
                 record,
                 "ReverseComplementedAlleles",
             )?,
             swapped_alleles: common::noodles::get_flag(record, "SwappedAlleles")?,
             original_alleles: common::noodles::get_vec_str(record, "OriginalAlleles")
+                .or_else(|_| common::noodles::get_vec_str(record, "original_alleles"))
                 .unwrap_or_default(),
-            original_contig: common::noodles::get_string(record, "OriginalContig").ok(),
-            original_start: common::noodles::get_string(record, "OriginalStart").ok(),
+            original_contig: common::noodles::get_string(record, "OriginalContig")
+                .ok()
+                .or(locus_contig),
+            original_start: common::noodles::get_string(record, "OriginalStart")
+                .ok()
+                .or(locus_start),
         };
+        if tmp.original_alleles.is_empty()
+            && (tmp.reverse_complemented_alleles || tmp.swapped_alleles)
+            && ref_allele.len() == alt_allele.len()
+        {
+            let (ref_allele, alt_allele) = if tmp.swapped_alleles {
+                (alt_allele, ref_allele)
+            } else {
+                (ref_allele, alt_allele)
+            };
+            tmp.original_alleles = [ref_allele, alt_allele]
+                .into_iter()
+                .map(|allele| {
+                    if tmp.reverse_complemented_alleles {
+                        reverse_complement(allele)
+                    } else {
+                        allele.to_string()
+                    }
+                })
+                .collect();
+        }
         if tmp.reverse_complemented_alleles
             || tmp.swapped_alleles
             || !tmp.original_alleles.is_empty()
@@ -446,6 +489,25 @@ impl Record {
     }
 }
 
+/// Return the reverse complement of `allele`, keeping IUPAC codes other than `ACGTN` as is.
+fn reverse_complement(allele: &str) -> String {
+    allele
+        .chars()
+        .rev()
+        .map(|base| match base {
+            'A' => 'T',
+            'C' => 'G',
+            'G' => 'C',
+            'T' => 'A',
+            'a' => 't',
+            'c' => 'g',
+            'g' => 'c',
+            't' => 'a',
+            base => base,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -509,4 +571,46 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_record_from_vcf_allele_gnomad_exomes_liftover() -> Result<(), anyhow::Error> {
+        let path_vcf = "tests/gnomad-nuclear/liftover/gnomad-exomes-liftover.vcf";
+        let mut reader_vcf =
+            noodles::vcf::io::reader::Builder::default().build_from_path(path_vcf)?;
+        let header = reader_vcf.read_header()?;
+
+        let mut liftover_infos = Vec::new();
+        for row in reader_vcf.record_bufs(&header) {
+            let vcf_record = row?;
+            let record = Record::from_vcf_allele(&vcf_record, 0, &DetailsOptions::default())?;
+            liftover_infos.push(record.liftover_info);
+        }
+
+        // The Hail fields are imported as the Picard ones.
+        let hail = liftover_infos[1].as_ref().expect("missing liftover info");
+        assert_eq!(hail.original_contig.as_deref(), Some("1"));
+        assert_eq!(hail.original_start.as_deref(), Some("138654"));
+        assert_eq!(hail.original_alleles, vec!["C", "T"]);
+        assert_eq!(liftover_infos[2], None);
+        // The original alleles are derived for reverse complemented and swapped alleles.
+        let reverse_complemented = liftover_infos[3].as_ref().expect("missing liftover info");
+        assert!(reverse_complemented.reverse_complemented_alleles);
+        assert_eq!(reverse_complemented.original_alleles, vec!["C", "G"]);
+        let swapped = liftover_infos[4].as_ref().expect("missing liftover info");
+        assert!(swapped.reverse_complemented_alleles && swapped.swapped_alleles);
+        assert_eq!(swapped.original_alleles, vec!["G", "C"]);
+
+        insta::assert_yaml_snapshot!(liftover_infos);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("ACGT", "ACGT")]
+    #[case("G", "C")]
+    #[case("AAC", "GTT")]
+    #[case("acN", "Ngt")]
+    fn reverse_complement_alleles(#[case] allele: &str, #[case] expected: &str) {
+        assert_eq!(reverse_complement(allele), expected);
+    }
 }
@@ -14,7 +14,7 @@ include!(concat!(env!("OUT_DIR"), "/annonars.gnomad.mtdna.serde.rs"));
 /// Options struct that allows to specify which details fields are to be extracted from
 /// gnomAD-mtDNA VCF records.
 ///
-/// The only field that has `true` as its default is `vep`.
+/// The only fields that have `true` as their default are `vep` and `haplogroup_counts`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct DetailsOptions {
     /// Enable extraction of `Vep` records.
@@ -29,12 +29,20 @@ pub struct DetailsOptions {
     pub pop_details: bool,
     /// Enable creation of `HaplogroupInfo`.
     pub haplogroups_details: bool,
+    /// Enable creation of the per-haplogroup `HaplogroupCounts`.
+    #[serde(default = "default_haplogroup_counts")]
+    pub haplogroup_counts: bool,
     /// Enable creation of `AgeInfo`.
     pub age_hists: bool,
     /// Enable creation of `DepthInfo`.
     pub depth_details: bool,
 }
 
+/// Default for `DetailsOptions::haplogroup_counts` in serialized options without the field.
+fn default_haplogroup_counts() -> bool {
+    true
+}
+
 impl Default for DetailsOptions {
     fn default() -> Self {
         Self {
@@ -44,6 +52,7 @@ impl Default for DetailsOptions {
             filter_hists: false,
             pop_details: false,
             haplogroups_details: false,
+            haplogroup_counts: true,
             age_hists: false,
             depth_details: false,
         }
@@ -60,13 +69,95 @@ impl DetailsOptions {
             filter_hists: true,
             pop_details: true,
             haplogroups_details: true,
+            haplogroup_counts: true,
             age_hists: true,
             depth_details: true,
         }
     }
 }
 
+/// Prefix of the haplogroup order in the description of the `hap_AN` INFO header.
+const HAPLOGROUP_ORDER_PREFIX: &str = "haplogroup order: [";
+
+/// Extract the order of the haplogroups from the description of the `hap_AN` INFO header.
+///
+/// Returns `None` if the header has no `hap_AN` field or it does not define the order.
+pub fn haplogroups_from_header(header: &noodles::vcf::Header) -> Option<Vec<String>> {
+    let description = header.infos().get("hap_AN")?.description();
+    let (_, order) = description.split_once(HAPLOGROUP_ORDER_PREFIX)?;
+    let (order, _) = order.split_once(']')?;
+    Some(
+        order
+            .split(',')
+            .map(|name| {
+                name.trim()
+                    .trim_matches(|c| c == '\'' || c == '"')
+                    .to_string()
+            })
+            .filter(|name| !name.is_empty())
+            .collect(),
+    )
+}
+
+impl HaplogroupCounts {
+    /// Creates the per-haplogroup counts of a VCF record, `haplogroups` giving their order.
+    ///
+    /// Returns an empty list if the record has no `hap_AN` field.
+    pub fn from_vcf(
+        record: &noodles::vcf::variant::RecordBuf,
+        haplogroups: &[String],
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        let Ok(hap_an) = common::noodles::get_vec::<i32>(record, "hap_AN") else {
+            return Ok(Vec::new());
+        };
+        let hap_ac_het = common::noodles::get_vec::<i32>(record, "hap_AC_het")?;
+        let hap_ac_hom = common::noodles::get_vec::<i32>(record, "hap_AC_hom")?;
+        let hap_af_het = common::noodles::get_vec::<f32>(record, "hap_AF_het")?;
+        let hap_af_hom = common::noodles::get_vec::<f32>(record, "hap_AF_hom")?;
+        let n = haplogroups.len();
+        if [
+            hap_an.len(),
+            hap_ac_het.len(),
+            hap_ac_hom.len(),
+            hap_af_het.len(),
+            hap_af_hom.len(),
+        ]
+        .iter()
+        .any(|len| *len != n)
+        {
+            anyhow::bail!(
+                "haplogroup fields do not match the {} haplogroups of the header at {}:{}",
+                n,
+                record.reference_sequence_name(),
+                record
+                    .variant_start()
+                    .map(|pos| pos.get())
+                    .unwrap_or_default()
+            );
+        }
+
+        Ok(haplogroups
+            .iter()
+            .enumerate()
+            .map(|(i, haplogroup)| HaplogroupCounts {
+                haplogroup: haplogroup.clone(),
+                an: hap_an[i],
+                ac_het: hap_ac_het[i],
+                ac_hom: hap_ac_hom[i],
+                af_het: hap_af_het[i],
+                af_hom: hap_af_hom[i],
+            })
+            .collect())
+    }
+}
+
 impl Record {
+    /// Keep only the counts of `haplogroup`, compared case-insensitively.
+    pub fn retain_haplogroup(&mut self, haplogroup: &str) {
+        self.haplogroup_counts
+            .retain(|counts| counts.haplogroup.eq_ignore_ascii_case(haplogroup));
+    }
+
     /// Creates a new `Record` from a VCF record and allele number.
     pub fn from_vcf_allele(
         record: &noodles::vcf::variant::RecordBuf,
@@ -165,6 +256,7 @@ impl Record {
             haplogroup_info,
             age_info,
             depth_info,
+            haplogroup_counts: Vec::new(),
         })
     }
 
@@ -336,4 +428,35 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_haplogroup_counts_from_vcf() -> Result<(), anyhow::Error> {
+        let path_vcf = "tests/gnomad-mtdna/example/gnomad-mtdna.vcf";
+        let mut reader_vcf =
+            noodles::vcf::io::reader::Builder::default().build_from_path(path_vcf)?;
+        let header = reader_vcf.read_header()?;
+        let haplogroups = haplogroups_from_header(&header).expect("no haplogroup order");
+        assert_eq!(haplogroups.len(), 29);
+        assert_eq!(&haplogroups[..3], &["A", "B", "C"]);
+        assert_eq!(haplogroups[8], "HV");
+
+        let vcf_record = reader_vcf.record_bufs(&header).next().expect("no record")?;
+        let counts = HaplogroupCounts::from_vcf(&vcf_record, &haplogroups)?;
+
+        assert_eq!(counts.len(), 29);
+        assert_eq!(
+            counts[22],
+            HaplogroupCounts {
+                haplogroup: String::from("T"),
+                an: 3080,
+                ac_het: 1,
+                ac_hom: 17,
+                af_het: 3.24675e-4,
+                af_hom: 5.51948e-3,
+            }
+        );
+        assert!(HaplogroupCounts::from_vcf(&vcf_record, &haplogroups[1..]).is_err());
+
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 //! Code generate for protobufs by `prost-build`.
 
+pub mod alphamissense;
 pub mod clinvar;
 pub mod clinvar_data;
 pub mod cons;
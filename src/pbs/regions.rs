@@ -1,5 +1,11 @@
 //! Code generate for protobufs by `prost-build`.
 
+/// Code generate for protobufs by `prost-build`.
+pub mod bed {
+    include!(concat!(env!("OUT_DIR"), "/annonars.regions.bed.rs"));
+    include!(concat!(env!("OUT_DIR"), "/annonars.regions.bed.serde.rs"));
+}
+
 /// Code generate for protobufs by `prost-build`.
 pub mod clingen {
     use crate::regions::cli::import::clingen::genomic_location_to_interval;
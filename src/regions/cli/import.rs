@@ -1,4 +1,9 @@
 //! Import of genomic region data.
+//!
+//! Besides the ClinGen region curations, regions from user-supplied BED files can be
+//! imported, e.g., segmental duplications or internal blacklists.  BED regions are keyed by
+//! `bed:CHROM:START-STOP:INDEX` with 1-based, inclusive coordinates and the index of the
+//! region in the input files, such that regions with the same coordinates are kept.
 
 use std::sync::Arc;
 
@@ -129,6 +134,87 @@ pub mod clingen {
     }
 }
 
+/// Reading of BED files.
+pub mod bed {
+    use std::io::BufRead as _;
+
+    use crate::pbs::regions::bed::Region;
+
+    /// Parse one BED `line`, `None` for comments, headers, and empty lines.
+    ///
+    /// The `name` and `score` columns are kept if present and not `.`.
+    pub fn parse_line(line: &str) -> Result<Option<Region>, anyhow::Error> {
+        if line.trim().is_empty()
+            || line.starts_with('#')
+            || line.starts_with("track")
+            || line.starts_with("browser")
+        {
+            return Ok(None);
+        }
+        let cols = line.trim_end().split('\t').collect::<Vec<_>>();
+        if cols.len() < 3 {
+            anyhow::bail!("expected at least 3 columns in BED line {:?}", line);
+        }
+        let start = cols[1]
+            .parse::<u32>()
+            .map_err(|e| anyhow::anyhow!("invalid start {:?}: {}", cols[1], e))?;
+        let stop = cols[2]
+            .parse::<u32>()
+            .map_err(|e| anyhow::anyhow!("invalid end {:?}: {}", cols[2], e))?;
+        if stop < start {
+            anyhow::bail!("end before start in BED line {:?}", line);
+        }
+        let column = |idx: usize| cols.get(idx).copied().filter(|value| *value != ".");
+        Ok(Some(Region {
+            chrom: cols[0].to_string(),
+            start,
+            stop,
+            name: column(3).map(ToString::to_string),
+            score: column(4)
+                .map(|score| {
+                    score
+                        .parse::<f32>()
+                        .map_err(|e| anyhow::anyhow!("invalid score {:?}: {}", score, e))
+                })
+                .transpose()?,
+        }))
+    }
+
+    /// Load the regions from the BED file at `path`, possibly gzip-compressed.
+    pub fn load_bed(path: &str) -> Result<Vec<Region>, anyhow::Error> {
+        tracing::info!("  loading BED regions from {}", path);
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow::anyhow!("problem opening file: {}", e))?;
+        let reader: Box<dyn std::io::Read> = if path.ends_with(".gz") {
+            Box::new(flate2::read::MultiGzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
+        let mut result = Vec::new();
+        for (line_no, line) in std::io::BufReader::new(reader).lines().enumerate() {
+            if let Some(region) = parse_line(&line?)
+                .map_err(|e| anyhow::anyhow!("problem in {} line {}: {}", path, line_no + 1, e))?
+            {
+                result.push(region);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Return the key of the `idx`-th `region`.
+    pub fn key(region: &Region, idx: usize) -> String {
+        format!(
+            "bed:{}:{}-{}:{}",
+            region.chrom,
+            region.start + 1,
+            region.stop,
+            idx
+        )
+    }
+}
+
 /// Command line arguments for `regions import` sub command.
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import region annotation data", long_about = None)]
@@ -137,8 +223,11 @@ pub struct Args {
     #[arg(long, value_enum)]
     pub genome_release: common::cli::GenomeRelease,
     /// Path to ClinGen region annotation file.
-    #[arg(long, required = true)]
-    pub path_in_clingen: String,
+    #[arg(long, required_unless_present = "path_in_bed")]
+    pub path_in_clingen: Option<String>,
+    /// Path(s) to BED file(s) with custom regions, the `name` and `score` columns are kept.
+    #[arg(long)]
+    pub path_in_bed: Vec<String>,
     /// Path to output RocksDB directory.
     #[arg(long)]
     pub path_out_rocksdb: String,
@@ -149,16 +238,23 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Perform import of the TSV file.
 fn tsv_import(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     args: &Args,
+    path_in_clingen: &str,
 ) -> Result<(), anyhow::Error> {
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
-    let regions = clingen::load_clingen(&args.path_in_clingen)?;
+    let regions = clingen::load_clingen(path_in_clingen)?;
 
     for (_, region) in regions {
         let clingen::Region {
@@ -194,12 +290,68 @@ fn tsv_import(
     Ok(())
 }
 
+/// Perform import of the BED files.
+fn bed_import(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    args: &Args,
+) -> Result<(), anyhow::Error> {
+    let cf_data = db.cf_handle(&args.cf_name).unwrap();
+
+    let mut idx = 0;
+    for path in &args.path_in_bed {
+        for region in bed::load_bed(path)? {
+            db.put_cf(
+                &cf_data,
+                bed::key(&region, idx).as_bytes(),
+                region.encode_to_vec(),
+            )?;
+            idx += 1;
+        }
+    }
+    tracing::info!("  imported {} BED regions", idx);
+
+    Ok(())
+}
+
+/// Return the paths of all input files.
+fn paths_in(args: &Args) -> Vec<String> {
+    args.path_in_clingen
+        .iter()
+        .chain(args.path_in_bed.iter())
+        .cloned()
+        .collect()
+}
+
+/// Validate the inputs and output path without writing the database.
+///
+/// The input files are loaded in the same way as in the import.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    if let Some(path_in_clingen) = args.path_in_clingen.as_ref() {
+        validation.read_input(path_in_clingen, clingen::load_clingen);
+    }
+    for path in &args.path_in_bed {
+        validation.read_input(path, bed::load_bed);
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
 /// Implementation of `cons import` sub command.
 pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
     tracing::info!("Starting 'regions import' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
 
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
     let before_opening_rocksdb = std::time::Instant::now();
@@ -230,13 +382,24 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_opening_rocksdb.elapsed()
     );
 
-    tracing::info!("Importing TSV files ...");
-    let before_import = std::time::Instant::now();
-    tsv_import(&db, args)?;
-    tracing::info!(
-        "... done importing TSV files in {:?}",
-        before_import.elapsed()
-    );
+    if let Some(path_in_clingen) = args.path_in_clingen.as_ref() {
+        tracing::info!("Importing TSV files ...");
+        let before_import = std::time::Instant::now();
+        tsv_import(&db, args, path_in_clingen)?;
+        tracing::info!(
+            "... done importing TSV files in {:?}",
+            before_import.elapsed()
+        );
+    }
+    if !args.path_in_bed.is_empty() {
+        tracing::info!("Importing BED files ...");
+        let before_import = std::time::Instant::now();
+        bed_import(&db, args)?;
+        tracing::info!(
+            "... done importing BED files in {:?}",
+            before_import.elapsed()
+        );
+    }
 
     tracing::info!("Running RocksDB compaction ...");
     let before_compaction = std::time::Instant::now();
@@ -246,6 +409,27 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         before_compaction.elapsed()
     );
 
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: "regions".to_string(),
+            title: if args.path_in_clingen.is_some() {
+                "ClinGen regions".to_string()
+            } else {
+                "Custom regions".to_string()
+            },
+            genome_release: Some(args.genome_release),
+            created_from: args
+                .path_in_clingen
+                .iter()
+                .map(|_| "ClinGen")
+                .chain(args.path_in_bed.iter().take(1).map(|_| "BED"))
+                .map(|source| (source.to_string(), args.spec.data_version()))
+                .collect(),
+            paths_in: paths_in(args),
+        },
+    )?;
+
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
@@ -262,17 +446,92 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             genome_release: common::cli::GenomeRelease::Grch37,
-            path_in_clingen: String::from(
+            path_in_clingen: Some(String::from(
                 "tests/regions/clingen/ClinGen_region_curation_list_GRCh37.tsv",
-            ),
+            )),
+            path_in_bed: Vec::new(),
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             cf_name: String::from("regions"),
             path_wal_dir: None,
+            dry_run: false,
+            spec: Default::default(),
         };
 
         run(&common, &args).unwrap();
     }
+
+    /// Dry run must not create the database and must find inputs in the wrong format.
+    #[test]
+    fn dry_run_import() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            genome_release: common::cli::GenomeRelease::Grch37,
+            path_in_clingen: Some(String::from(
+                "tests/regions/clingen/ClinGen_region_curation_list_GRCh37.tsv",
+            )),
+            path_in_bed: vec![String::from("tests/regions/bed/example.bed")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            cf_name: String::from("regions"),
+            path_wal_dir: None,
+            dry_run: true,
+            spec: Default::default(),
+        };
+
+        run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        // The ClinGen file is not a BED file.
+        let args = Args {
+            path_in_bed: vec![String::from(
+                "tests/regions/clingen/ClinGen_region_curation_list_GRCh37.tsv",
+            )],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1, "{:?}", validation.problems);
+        assert!(run(&common, &args).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case("chr1\t100\t200", Some(("chr1", 100, 200, None, None)))]
+    #[case("1\t100\t200\tsegdup_1\t0.98", Some(("1", 100, 200, Some("segdup_1"), Some(0.98))))]
+    #[case("1\t100\t200\t.\t.\t+", Some(("1", 100, 200, None, None)))]
+    #[case("# comment", None)]
+    #[case("track name=segdups", None)]
+    #[case("", None)]
+    fn parse_bed_line(
+        #[case] line: &str,
+        #[case] expected: Option<(&str, u32, u32, Option<&str>, Option<f32>)>,
+    ) -> Result<(), anyhow::Error> {
+        let region = bed::parse_line(line)?;
+        assert_eq!(
+            region.as_ref().map(|region| (
+                region.chrom.as_str(),
+                region.start,
+                region.stop,
+                region.name.as_deref(),
+                region.score
+            )),
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("1\t100")]
+    #[case("1\tabc\t200")]
+    #[case("1\t200\t100")]
+    #[case("1\t100\t200\tname\thigh")]
+    fn parse_bed_line_invalid(#[case] line: &str) {
+        assert!(bed::parse_line(line).is_err());
+    }
 }
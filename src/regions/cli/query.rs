@@ -1,6 +1,6 @@
 //! Query genomic regions.
 
-use std::{io::Write, sync::Arc};
+use std::sync::Arc;
 
 use bio::{
     bio_types::genome::AbstractInterval, data_structures::interval_tree::ArrayBackedIntervalTree,
@@ -38,8 +38,8 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
 
     /// Variant or position to query for.
     #[command(flatten)]
@@ -68,17 +68,17 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
+        let genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
         Meta { genome_release }
     };
 
@@ -104,6 +104,8 @@ pub fn open_rocksdb_from_args(
 pub enum Record {
     /// ClinGen dosage record.
     ClingenDosage(crate::pbs::regions::clingen::Region),
+    /// Region from a user-supplied BED file.
+    Bed(crate::pbs::regions::bed::Region),
 }
 
 /// The necessary data for the tree construction.
@@ -118,7 +120,8 @@ pub struct TreeData {
 }
 
 impl Record {
-    fn tree_data(&self) -> TreeData {
+    /// Return the 0-based, half-open interval of the record.
+    pub fn tree_data(&self) -> TreeData {
         match self {
             Record::ClingenDosage(record) => {
                 let interval =
@@ -130,23 +133,29 @@ impl Record {
                     stop: interval.range().end as u32,
                 }
             }
+            Record::Bed(record) => TreeData {
+                chromosome: record.chrom.clone(),
+                start: record.start,
+                stop: record.stop,
+            },
         }
     }
 }
 
 /// Write a single record to `out_writer`.
 fn print_record(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     value: &Record,
 ) -> Result<(), anyhow::Error> {
-    match (output_format, value) {
-        (common::cli::OutputFormat::Jsonl, Record::ClingenDosage(record)) => {
-            writeln!(out_writer, "{}", serde_json::to_string(record)?)?
+    match value {
+        Record::ClingenDosage(record) => {
+            out_writer.write(&format!("clingen:{}", &record.isca_id), record)
         }
+        Record::Bed(record) => out_writer.write(
+            &format!("{}:{}-{}", &record.chrom, record.start + 1, record.stop),
+            record,
+        ),
     }
-
-    Ok(())
 }
 
 /// Decode a key/record.
@@ -155,6 +164,10 @@ fn decode_record(key: &[u8], data: &[u8]) -> Result<Record, anyhow::Error> {
         Record::ClingenDosage(crate::pbs::regions::clingen::Region::decode(
             &mut std::io::Cursor::new(&data),
         )?)
+    } else if key.starts_with(b"bed:") {
+        Record::Bed(crate::pbs::regions::bed::Region::decode(
+            &mut std::io::Cursor::new(&data),
+        )?)
     } else {
         let key = std::str::from_utf8(key).unwrap_or("COULD_NOT_DECODE_KEY");
         anyhow::bail!("unknown record type from key: {}", key);
@@ -163,8 +176,7 @@ fn decode_record(key: &[u8], data: &[u8]) -> Result<Record, anyhow::Error> {
 
 /// Iterate all regions and print to `out_writer`.
 fn print_all(
-    out_writer: &mut Box<dyn std::io::Write>,
-    out_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_data: &Arc<rocksdb::BoundColumnFamily>,
 ) -> Result<(), anyhow::Error> {
@@ -174,7 +186,7 @@ fn print_all(
     iter.seek(b"");
     while iter.valid() {
         if let (Some(raw_key), Some(raw_value)) = (iter.key(), iter.value()) {
-            print_record(out_writer, out_format, &decode_record(raw_key, raw_value)?)?;
+            print_record(out_writer, &decode_record(raw_key, raw_value)?)?;
             iter.next();
         } else {
             break;
@@ -251,7 +263,6 @@ impl IntervalTrees {
                 let key = iter.key().unwrap().to_vec();
                 tracing::trace!("iterator at {:?} => {:?}", &key, &record);
 
-                println!("INSERTING {:?}", &record.tree_data());
                 let TreeData {
                     chromosome,
                     start,
@@ -281,11 +292,13 @@ impl IntervalTrees {
     /// Query for a range.
     pub fn query(&self, range: &spdi::Range) -> Result<Vec<Record>, anyhow::Error> {
         tracing::trace!("query for {:?}", &range);
-        let contig = extract_chrom::from_range(range, Some(&self.meta.genome_release))?;
+        let range = extract_chrom::range(range, Some(&self.meta.genome_release))?;
+        let contig = range.sequence.clone();
         let cf_data = self.db.cf_handle(&self.cf_data_name).ok_or_else(|| {
             anyhow::anyhow!("no column family with name {:?} found", &self.cf_data_name)
         })?;
-        let interval = (range.start as u64)..(range.end as u64);
+        // The trees hold 0-based, half-open intervals, the range is 1-based and inclusive.
+        let interval = (range.start as u64).saturating_sub(1)..(range.end as u64);
         let mut result = Vec::new();
         if let Some(tree) = self.trees.get(&contig) {
             for entry in tree.find(&interval) {
@@ -312,13 +325,12 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = common::output::RecordWriter::new(
+        &args.out_file,
+        args.output.output_format,
+        "regions",
+        Some(&meta.genome_release),
+    )?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
@@ -333,7 +345,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             .query(range)
             .map_err(|e| anyhow::anyhow!("failed to query interval trees: {}", e))?;
         for record in &records {
-            print_record(&mut out_writer, args.out_format, record)?;
+            print_record(&mut out_writer, record)?;
         }
         tracing::info!("... done running query");
     } else if let Some(accession) = args.query.accession.as_ref() {
@@ -343,14 +355,14 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             .map_err(|e| anyhow::anyhow!("failed to query RocksDB: {}", e))?;
         if let Some(buf) = buf {
             let record = decode_record(accession.as_bytes(), &buf)?;
-            print_record(&mut out_writer, args.out_format, &record)?;
+            print_record(&mut out_writer, &record)?;
         } else {
             tracing::warn!("no record found for accession {}", accession);
         }
         tracing::info!("... done running query");
     } else if args.query.all {
         tracing::info!("for all");
-        print_all(&mut out_writer, args.out_format, &db, &cf_data)?;
+        print_all(&mut out_writer, &db, &cf_data)?;
     } else {
         unreachable!();
     }
@@ -361,7 +373,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use std::str::FromStr as _;
     use temp_testdir::TempDir;
 
@@ -372,11 +384,12 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = super::Args {
             cf_name: String::from("regions"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
             ..Default::default()
         };
 
@@ -475,4 +488,61 @@ mod test {
 
         Ok(())
     }
+
+    /// Import `tests/regions/bed/example.bed` into a temporary database.
+    pub(crate) fn import_bed(temp: &TempDir) -> Result<String, anyhow::Error> {
+        let path_rocksdb = temp.join("bed-rocksdb").to_string_lossy().to_string();
+        crate::regions::cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::regions::cli::import::Args {
+                genome_release: common::cli::GenomeRelease::Grch37,
+                path_in_clingen: None,
+                path_in_bed: vec![String::from("tests/regions/bed/example.bed")],
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("regions"),
+                path_wal_dir: None,
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                dry_run: false,
+            },
+        )?;
+        Ok(path_rocksdb)
+    }
+
+    #[tracing_test::traced_test]
+    #[rstest::rstest]
+    #[case("1:1500:1600", &["segdup_1", "blacklist"])]
+    #[case("1:2001:2001", &["blacklist"])]
+    #[case("1:1000:1000", &[])]
+    #[case("1:1001:1001", &["segdup_1"])]
+    #[case("X:150000:150000", &[""])]
+    #[case("1:3001:4000", &[])]
+    #[case("2:1500:1600", &[])]
+    fn query_bed_range(
+        #[case] range: &str,
+        #[case] expected: &[&str],
+        args_args_temp: (common::cli::Args, super::Args, TempDir),
+    ) -> Result<(), anyhow::Error> {
+        let (_common, _args, temp) = args_args_temp;
+        let path_rocksdb = import_bed(&temp)?;
+
+        let (db, meta) = super::open_rocksdb(&path_rocksdb, "regions", "meta")?;
+        let trees = super::IntervalTrees::with_db(db, "regions", meta)?;
+        let names = trees
+            .query(&crate::common::spdi::Range::from_str(range)?)?
+            .into_iter()
+            .map(|record| match record {
+                super::Record::Bed(region) => region.name.unwrap_or_default(),
+                record => panic!("unexpected record {:?}", record),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, expected);
+
+        Ok(())
+    }
 }
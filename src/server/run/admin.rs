@@ -0,0 +1,297 @@
+//! Implementation of the admin endpoint `/admin/config`.
+//!
+//! Admin endpoints are disabled unless the server is started with `--admin-token` and then
+//! require the token in the `Authorization: Bearer <token>` header.  `/admin/config` returns
+//! the effective command line arguments with the token redacted, the opened databases, and
+//! the annonars version.
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+
+use crate::common::{cli::GenomeRelease, version};
+
+use super::{error::CustomError, Args, WebServerData};
+
+/// Replacement for secrets in `/admin/config`.
+pub const REDACTED: &str = "<redacted>";
+
+/// Token for the admin endpoints, see `--admin-token`.
+///
+/// `Debug` prints `REDACTED` instead of the token so that it does not end up in the logs,
+/// e.g., when `WebServerData` is printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AdminToken(String);
+
+impl AdminToken {
+    /// Return the token.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for AdminToken {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl std::fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AdminToken").field(&REDACTED).finish()
+    }
+}
+
+impl Args {
+    /// Return the arguments as JSON with secrets such as `admin_token` redacted.
+    pub fn sanitized(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(admin_token) = value.get_mut("admin_token") {
+            if !admin_token.is_null() {
+                *admin_token = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+        value
+    }
+}
+
+/// One opened database in `AdminConfigResponse`.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct AdminDatabase {
+    /// Name of the database, including the version for further databases of a kind.
+    pub name: String,
+    /// Genome release of the database, if release-specific.
+    pub genome_release: Option<GenomeRelease>,
+    /// Version of the database, if known.
+    pub db_version: Option<String>,
+}
+
+/// Result for `handle_config`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct AdminConfigResponse {
+    /// Version of the server code.
+    pub server_version: String,
+    /// The effective command line arguments with secrets redacted.
+    pub args: serde_json::Value,
+    /// The opened databases.
+    pub databases: Vec<AdminDatabase>,
+}
+
+/// Return whether the `given` token equals the `expected` one.
+///
+/// The comparison takes the same time for all tokens of the expected length.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Check the admin token of `req`, returning the error response if it is not accepted.
+///
+/// Admin endpoints are not found without `--admin-token`.
+pub(crate) fn check_token(req: &HttpRequest, data: &WebServerData) -> Option<HttpResponse> {
+    let Some(admin_token) = data.admin_token.as_ref().map(AdminToken::as_str) else {
+        return Some(HttpResponse::NotFound().finish());
+    };
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if given.is_some_and(|given| token_matches(given, admin_token)) {
+        None
+    } else {
+        Some(
+            HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .finish(),
+        )
+    }
+}
+
+/// Return the opened databases of `data`.
+fn databases(data: &WebServerData) -> Vec<AdminDatabase> {
+    let mut result = Vec::new();
+    let database = |name: &str, genome_release, db_version: Option<&String>| AdminDatabase {
+        name: name.to_string(),
+        genome_release,
+        db_version: db_version.cloned(),
+    };
+
+    if let Some(genes) = data.genes.as_ref() {
+        result.push(database("genes", None, None));
+        if genes.data.db_clinvar.is_some() {
+            result.push(database("clinvar_genes", None, None));
+        }
+    }
+    for (genome_release, clinvar_sv) in data.clinvar_svs.iter() {
+        if clinvar_sv.is_some() {
+            result.push(database("clinvar_sv", Some(genome_release), None));
+        }
+    }
+    for (genome_release, db_infos) in data.db_infos.iter() {
+        for (anno_db, db_info) in db_infos.iter() {
+            if let Some(db_info) = db_info.as_ref() {
+                result.push(database(
+                    &anno_db.to_string(),
+                    Some(genome_release),
+                    db_info.db_version.as_ref(),
+                ));
+            }
+        }
+    }
+    for (genome_release, additional_annos) in data.additional_annos.iter() {
+        for additional in additional_annos.values().flatten() {
+            result.push(database(
+                &additional.db_info.versioned_key(genome_release),
+                Some(genome_release),
+                additional.db_info.db_version.as_ref(),
+            ));
+        }
+    }
+
+    result
+}
+
+/// Effective configuration of the server.
+#[get("/admin/config")]
+async fn handle_config(
+    req: HttpRequest,
+    data: Data<WebServerData>,
+    _path: Path<()>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    if let Some(response) = check_token(&req, &data) {
+        return Ok(response);
+    }
+
+    Ok(HttpResponse::Ok().json(AdminConfigResponse {
+        server_version: version().to_string(),
+        args: data.config.clone(),
+        databases: databases(&data),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use clap::Parser as _;
+
+    use super::*;
+    use crate::server::run::{AnnoDb, DbInfo};
+
+    /// Return the server data as started with `args`.
+    fn data_with_args(args: &[&str]) -> WebServerData {
+        let args =
+            Args::try_parse_from(std::iter::once("server").chain(args.iter().copied())).unwrap();
+        let mut data = WebServerData {
+            admin_token: args.admin_token.clone().map(AdminToken::from),
+            config: args.sanitized(),
+            ..Default::default()
+        };
+        data.db_infos[GenomeRelease::Grch37][AnnoDb::Cadd] = Some(DbInfo {
+            name: AnnoDb::Cadd,
+            db_version: Some("1.6".into()),
+            ..Default::default()
+        });
+        data
+    }
+
+    async fn call_config(
+        data: WebServerData,
+        token: Option<&str>,
+    ) -> (StatusCode, actix_web::web::Bytes) {
+        let app =
+            test::init_service(App::new().app_data(Data::new(data)).service(handle_config)).await;
+        let mut req = test::TestRequest::get().uri("/admin/config");
+        if let Some(token) = token {
+            req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        (resp.status(), test::read_body(resp).await)
+    }
+
+    #[test]
+    fn sanitized_redacts_token() {
+        let args = Args::try_parse_from(["server", "--admin-token", "s3cr3t"]).unwrap();
+        let value = args.sanitized();
+
+        assert_eq!(value["admin_token"], REDACTED);
+        assert!(!value.to_string().contains("s3cr3t"));
+
+        let args = Args::try_parse_from(["server"]).unwrap();
+        assert_eq!(args.sanitized()["admin_token"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn debug_redacts_token() {
+        let data = data_with_args(&["--admin-token", "s3cr3t"]);
+
+        assert!(!format!("{:?}", data).contains("s3cr3t"));
+        assert!(format!("{:?}", data.admin_token).contains(REDACTED));
+    }
+
+    #[actix_web::test]
+    async fn config_with_token() -> Result<(), anyhow::Error> {
+        let data = data_with_args(&[
+            "--admin-token",
+            "s3cr3t",
+            "--path-cadd",
+            "/data/cadd/rocksdb",
+            "--listen-port",
+            "8082",
+            "--variant-cache-size",
+            "1000",
+        ]);
+
+        let (status, body) = call_config(data, Some("s3cr3t")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(!String::from_utf8_lossy(&body).contains("s3cr3t"));
+        let response: AdminConfigResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.server_version, version());
+        assert_eq!(response.args["admin_token"], REDACTED);
+        assert_eq!(
+            response.args["path_cadd"],
+            serde_json::json!(["/data/cadd/rocksdb"])
+        );
+        assert_eq!(response.args["listen_host"], "127.0.0.1");
+        assert_eq!(response.args["listen_port"], 8082);
+        assert_eq!(response.args["variant_cache_size"], 1000);
+        assert_eq!(
+            response.databases,
+            vec![AdminDatabase {
+                name: "cadd".into(),
+                genome_release: Some(GenomeRelease::Grch37),
+                db_version: Some("1.6".into()),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn config_unauthorized() {
+        for token in [None, Some("guess"), Some("s3cr")] {
+            let data = data_with_args(&["--admin-token", "s3cr3t"]);
+
+            let (status, _) = call_config(data, token).await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{:?}", token);
+        }
+    }
+
+    #[actix_web::test]
+    async fn config_disabled_without_admin_token() {
+        let data = data_with_args(&[]);
+
+        let (status, _) = call_config(data, Some("s3cr3t")).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
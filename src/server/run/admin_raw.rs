@@ -0,0 +1,438 @@
+//! Implementation of the admin endpoints `/admin/raw-get` and `/admin/raw-key` for debugging.
+//!
+//! `/admin/raw-get` returns the raw value stored under a key of an annotation database,
+//! base64-encoded, and the value decoded into JSON if the value type of the database is
+//! known.  `/admin/raw-key` returns the key of a variant or position without querying, e.g.,
+//! to look up the key of a record that fails to decode.
+//!
+//! Both endpoints are disabled unless the server is started with `--enable-admin-raw` and
+//! require the admin token, see `admin::check_token()`.  They read the databases directly,
+//! bypassing the circuit breakers such that degraded databases can be inspected, and are
+//! deliberately not part of the OpenAPI specification.
+use actix_web::{
+    get,
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use base64::Engine as _;
+
+use crate::common::{cli::GenomeRelease, decode::decode_protobuf, keys};
+
+use super::{admin::check_token, error::CustomError, params, AnnoDb, DbInfo, WebServerData};
+
+/// Check that the raw endpoints are enabled and the admin token of `req` is accepted,
+/// returning the error response otherwise.
+fn check_raw_enabled(req: &HttpRequest, data: &WebServerData) -> Option<HttpResponse> {
+    if !data.enable_admin_raw {
+        return Some(HttpResponse::NotFound().finish());
+    }
+    check_token(req, data)
+}
+
+/// Query parameters of `handle_raw_get`.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct RawGetParams {
+    /// Name of the annotation database, e.g., `gnomad_genomes`.
+    pub db: Option<String>,
+    /// Genome release of the database.
+    pub genome_release: Option<String>,
+    /// The key as hex string, see `keys::to_hex()`.
+    pub key_hex: Option<String>,
+}
+
+/// Result for `handle_raw_get`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct RawGetResponse {
+    /// Name of the annotation database.
+    pub db: AnnoDb,
+    /// Genome release of the database.
+    pub genome_release: GenomeRelease,
+    /// The key as lowercase hex string.
+    pub key_hex: String,
+    /// The raw value, base64-encoded; `None` if there is no value for the key.
+    pub value_base64: Option<String>,
+    /// The value decoded into JSON; `None` if there is no value or its type is not known.
+    pub decoded: Option<serde_json::Value>,
+    /// Error message if the value could not be decoded.
+    pub decode_error: Option<String>,
+}
+
+/// Decode `raw_value` stored under `key` in `cf_name` as `T` into JSON.
+fn to_json<T>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    key: &[u8],
+    raw_value: &[u8],
+) -> Result<Option<serde_json::Value>, anyhow::Error>
+where
+    T: prost::Message + serde::Serialize + Default,
+{
+    let msg: T = decode_protobuf(db, cf_name, key, raw_value)?;
+    Ok(Some(serde_json::to_value(msg)?))
+}
+
+/// Decode `raw_value` stored under `key` in the database described by `db_info` into JSON.
+///
+/// Returns `None` for databases with TSV values, per-base conservation scores, and coverage.
+fn decode_value(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    db_info: &DbInfo,
+    key: &[u8],
+    raw_value: &[u8],
+) -> Result<Option<serde_json::Value>, anyhow::Error> {
+    let cf_name = db_info.name.cf_name();
+    let db_version = db_info.db_version.as_deref().unwrap_or_default();
+    match db_info.name {
+        AnnoDb::Other
+        | AnnoDb::Cadd
+        | AnnoDb::Dbnsfp
+        | AnnoDb::Dbscsnv
+        | AnnoDb::GnomadCoverage => Ok(None),
+        AnnoDb::Dbsnp => to_json::<crate::dbsnp::pbs::Record>(db, cf_name, key, raw_value),
+        AnnoDb::GnomadMtdna => {
+            to_json::<crate::pbs::gnomad::mtdna::Record>(db, cf_name, key, raw_value)
+        }
+        AnnoDb::GnomadExomes | AnnoDb::GnomadGenomes => {
+            if db_version.starts_with("2.") {
+                to_json::<crate::pbs::gnomad::gnomad2::Record>(db, cf_name, key, raw_value)
+            } else if db_version.starts_with("3.") {
+                to_json::<crate::pbs::gnomad::gnomad3::Record>(db, cf_name, key, raw_value)
+            } else {
+                to_json::<crate::pbs::gnomad::gnomad4::Record>(db, cf_name, key, raw_value)
+            }
+        }
+        AnnoDb::Helixmtdb => to_json::<crate::pbs::helixmtdb::Record>(db, cf_name, key, raw_value),
+        AnnoDb::Alphamissense => {
+            to_json::<crate::pbs::alphamissense::RecordList>(db, cf_name, key, raw_value)
+        }
+        AnnoDb::UcscConservation if crate::cons::per_base::is_per_base(db) => Ok(None),
+        AnnoDb::UcscConservation => {
+            to_json::<crate::pbs::cons::RecordList>(db, cf_name, key, raw_value)
+        }
+        AnnoDb::Clinvar => to_json::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
+            db, cf_name, key, raw_value,
+        ),
+    }
+}
+
+/// Raw value of a key of an annotation database.
+#[get("/admin/raw-get")]
+async fn handle_raw_get(
+    req: HttpRequest,
+    data: Data<WebServerData>,
+    _path: Path<()>,
+    query: Query<RawGetParams>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    if let Some(response) = check_raw_enabled(&req, &data) {
+        return Ok(response);
+    }
+
+    let db_name = params::required("db", &query.db)?;
+    let anno_db = match params::parse("db", db_name)? {
+        AnnoDb::Other => {
+            return Err(CustomError::invalid_param(
+                "db",
+                Some(db_name),
+                "not an annotation database",
+            ))
+        }
+        anno_db => anno_db,
+    };
+    let genome_release = params::genome_release(
+        "genome_release",
+        params::required("genome_release", &query.genome_release)?,
+    )?;
+    let key_hex = params::required("key_hex", &query.key_hex)?;
+    let key = keys::from_hex(key_hex)
+        .map_err(|e| CustomError::invalid_param("key_hex", Some(key_hex), &e.to_string()))?;
+
+    let (Some(db), Some(db_info)) = (
+        data.annos[genome_release][anno_db].as_ref(),
+        data.db_infos[genome_release][anno_db].as_ref(),
+    ) else {
+        return Err(CustomError::invalid_param(
+            "db",
+            Some(db_name),
+            &format!("database not opened for {}", genome_release),
+        ));
+    };
+    let cf_data = db
+        .data
+        .cf_handle(anno_db.cf_name())
+        .ok_or_else(|| CustomError::new(anyhow::anyhow!("unknown column family")))?;
+    let raw_value = db
+        .data
+        .get_cf(&cf_data, &key)
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
+
+    let decoded = raw_value
+        .as_ref()
+        .map(|raw_value| decode_value(&db.data, db_info, &key, raw_value))
+        .transpose();
+    let (decoded, decode_error) = match decoded {
+        Ok(decoded) => (decoded.flatten(), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    Ok(HttpResponse::Ok().json(RawGetResponse {
+        db: anno_db,
+        genome_release,
+        key_hex: keys::to_hex(&key),
+        value_base64: raw_value
+            .map(|raw_value| base64::engine::general_purpose::STANDARD.encode(raw_value)),
+        decoded,
+        decode_error,
+    }))
+}
+
+/// Query parameters of `handle_raw_key`.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct RawKeyParams {
+    /// Chromosome name.
+    pub chromosome: Option<String>,
+    /// 1-based position.
+    pub pos: Option<String>,
+    /// Reference allele bases, for a variant key.
+    pub reference: Option<String>,
+    /// Alternative allele bases, for a variant key.
+    pub alternative: Option<String>,
+}
+
+/// Result for `handle_raw_key`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct RawKeyResponse {
+    /// The key as lowercase hex string.
+    pub key_hex: String,
+}
+
+/// Key of a variant, or of a position without alleles, as used in the databases.
+#[get("/admin/raw-key")]
+async fn handle_raw_key(
+    req: HttpRequest,
+    data: Data<WebServerData>,
+    _path: Path<()>,
+    query: Query<RawKeyParams>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    if let Some(response) = check_raw_enabled(&req, &data) {
+        return Ok(response);
+    }
+
+    let chrom = params::chromosome(
+        "chromosome",
+        params::required("chromosome", &query.chromosome)?,
+    )?;
+    let pos = params::position("pos", params::required("pos", &query.pos)?)? as i32;
+    let key: Vec<u8> = match (query.reference.as_deref(), query.alternative.as_deref()) {
+        (None, None) => keys::Pos { chrom, pos }.into(),
+        (Some(reference), Some(alternative)) => keys::Var {
+            chrom,
+            pos,
+            reference: params::allele("reference", reference)?,
+            alternative: params::allele("alternative", alternative)?,
+        }
+        .into(),
+        (None, Some(_)) => {
+            return Err(CustomError::invalid_param(
+                "reference",
+                None,
+                "parameter is required with alternative",
+            ))
+        }
+        (Some(_), None) => {
+            return Err(CustomError::invalid_param(
+                "alternative",
+                None,
+                "parameter is required with reference",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(RawKeyResponse {
+        key_hex: keys::to_hex(&key),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::header, http::StatusCode, test, App};
+    use clap::Parser as _;
+
+    use super::*;
+    use crate::server::run::{admin::AdminToken, Args};
+
+    /// Return the server data with the dbSNP and gnomAD exomes fixtures as started with `args`.
+    fn data_with_args(args: &[&str]) -> Result<WebServerData, anyhow::Error> {
+        let args = Args::try_parse_from(
+            [
+                "server",
+                "--path-dbsnp",
+                "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+                "--path-gnomad-exomes",
+                "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz.db",
+            ]
+            .iter()
+            .chain(args),
+        )?;
+        let mut data = WebServerData {
+            admin_token: args.admin_token.clone().map(AdminToken::from),
+            enable_admin_raw: args.enable_admin_raw,
+            ..Default::default()
+        };
+        crate::server::run::open_databases(&args, &mut data)?;
+        Ok(data)
+    }
+
+    async fn call(
+        data: WebServerData,
+        uri: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, actix_web::web::Bytes) {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data))
+                .service(handle_raw_get)
+                .service(handle_raw_key),
+        )
+        .await;
+        let mut req = test::TestRequest::get().uri(uri);
+        if let Some(token) = token {
+            req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        (resp.status(), test::read_body(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn raw_get_dbsnp() -> Result<(), anyhow::Error> {
+        let data = data_with_args(&["--admin-token", "s3cr3t", "--enable-admin-raw"])?;
+
+        let (status, body) = call(
+            data,
+            "/admin/raw-get?db=dbsnp&genome_release=grch37&key_hex=31370275B222433E41",
+            Some("s3cr3t"),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: RawGetResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.key_hex, "31370275b222433e41");
+        let raw_value = base64::engine::general_purpose::STANDARD
+            .decode(response.value_base64.expect("must have value"))?;
+        let record = <crate::dbsnp::pbs::Record as prost::Message>::decode(raw_value.as_slice())?;
+        assert_eq!(record.rs_id, 80357446);
+        assert_eq!(response.decoded, Some(serde_json::to_value(&record)?));
+        assert_eq!(response.decode_error, None);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn raw_get_gnomad_exomes_missing_key() -> Result<(), anyhow::Error> {
+        let data = data_with_args(&["--admin-token", "s3cr3t", "--enable-admin-raw"])?;
+
+        let (status, body) = call(
+            data,
+            "/admin/raw-get?db=gnomad_exomes&genome_release=grch37&key_hex=2058000000014e3e41",
+            Some("s3cr3t"),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: RawGetResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.db, AnnoDb::GnomadExomes);
+        assert_eq!(response.value_base64, None);
+        assert_eq!(response.decoded, None);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::invalid_key("db=dbsnp&genome_release=grch37&key_hex=zz", "key_hex")]
+    #[case::invalid_db("db=cadd37&genome_release=grch37&key_hex=3137", "db")]
+    #[case::other_db("db=other&genome_release=grch37&key_hex=3137", "db")]
+    #[case::not_opened("db=dbsnp&genome_release=grch38&key_hex=3137", "db")]
+    #[case::missing_release("db=dbsnp&key_hex=3137", "genome_release")]
+    #[actix_web::test]
+    async fn raw_get_invalid(
+        #[case] query: &str,
+        #[case] param: &str,
+    ) -> Result<(), anyhow::Error> {
+        let data = data_with_args(&["--admin-token", "s3cr3t", "--enable-admin-raw"])?;
+
+        let (status, body) = call(data, &format!("/admin/raw-get?{}", query), Some("s3cr3t")).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let err: CustomError = serde_json::from_slice(&body)?;
+        assert_eq!(err.param().map(|param| param.name.as_str()), Some(param));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::var(
+        "chromosome=chr17&pos=41267746&reference=c&alternative=A",
+        "31370275b222433e41"
+    )]
+    #[case::pos("chromosome=17&pos=41267746", "31370275b222")]
+    #[actix_web::test]
+    async fn raw_key(#[case] query: &str, #[case] expected: &str) -> Result<(), anyhow::Error> {
+        let data = data_with_args(&["--admin-token", "s3cr3t", "--enable-admin-raw"])?;
+
+        let (status, body) = call(data, &format!("/admin/raw-key?{}", query), Some("s3cr3t")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: RawKeyResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.key_hex, expected);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn raw_key_reference_without_alternative() -> Result<(), anyhow::Error> {
+        let data = data_with_args(&["--admin-token", "s3cr3t", "--enable-admin-raw"])?;
+
+        let (status, _) = call(
+            data,
+            "/admin/raw-key?chromosome=17&pos=41267746&reference=C",
+            Some("s3cr3t"),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::disabled(&["--admin-token", "s3cr3t"], Some("s3cr3t"), StatusCode::NOT_FOUND)]
+    #[case::without_admin_token(&["--enable-admin-raw"], Some("s3cr3t"), StatusCode::NOT_FOUND)]
+    #[case::without_token(
+        &["--admin-token", "s3cr3t", "--enable-admin-raw"],
+        None,
+        StatusCode::UNAUTHORIZED
+    )]
+    #[case::wrong_token(
+        &["--admin-token", "s3cr3t", "--enable-admin-raw"],
+        Some("guess"),
+        StatusCode::UNAUTHORIZED
+    )]
+    #[actix_web::test]
+    async fn raw_guarded(
+        #[case] args: &[&str],
+        #[case] token: Option<&str>,
+        #[case] expected: StatusCode,
+    ) -> Result<(), anyhow::Error> {
+        for uri in [
+            "/admin/raw-get?db=dbsnp&genome_release=grch37&key_hex=31370275b222433e41",
+            "/admin/raw-key?chromosome=17&pos=41267746",
+        ] {
+            let data = data_with_args(args)?;
+
+            let (status, _) = call(data, uri, token).await;
+
+            assert_eq!(status, expected, "{}", uri);
+        }
+
+        Ok(())
+    }
+}
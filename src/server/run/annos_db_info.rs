@@ -7,15 +7,39 @@ use actix_web::{
 };
 use serde::Deserialize;
 
-use super::{error::CustomError, WebServerData};
+use crate::common::cli::GenomeRelease;
+
+use super::{error::CustomError, params, DbInfo, WebServerData};
 
 /// Parameters for `variant_annos::handle`.
-#[serde_with::skip_serializing_none]
-#[serde_with::serde_as]
+///
+/// The genome release is kept as string so `validate()` can report invalid values together
+/// with the parameter name.
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 struct Request {
-    pub genome_release: String,
+    pub genome_release: Option<String>,
+}
+
+impl Request {
+    /// Validate the parameters and return the genome release.
+    fn validate(&self) -> Result<GenomeRelease, CustomError> {
+        params::genome_release(
+            "genome_release",
+            params::required("genome_release", &self.genome_release)?,
+        )
+    }
+}
+
+/// Result for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct Response {
+    /// The database information by name, see `db_infos`.
+    #[serde(flatten)]
+    pub db_infos: indexmap::IndexMap<String, Option<DbInfo>>,
+    /// The contigs that queries are answered for, see `--contig-allowlist`; omitted if all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contig_allowlist: Vec<String>,
 }
 
 /// Query for annotations for one variant.
@@ -25,13 +49,83 @@ async fn handle(
     _path: Path<()>,
     query: web::Query<Request>,
 ) -> actix_web::Result<impl Responder, CustomError> {
-    let genome_release =
-        query
-            .into_inner()
-            .genome_release
-            .parse()
-            .map_err(|e: strum::ParseError| {
-                CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
-            })?;
-    Ok(Json(data.db_infos[genome_release].clone()))
+    let genome_release = query.validate()?;
+    Ok(Json(Response {
+        db_infos: db_infos(&data, genome_release),
+        contig_allowlist: data.contig_allowlist.clone(),
+    }))
+}
+
+/// Collect the database information for `genome_release`.
+///
+/// Kinds with more than one database, e.g., gnomAD v2 liftover and v4 exomes, are listed
+/// once more for each database by `DbInfo::versioned_key()`.
+fn db_infos(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+) -> indexmap::IndexMap<String, Option<DbInfo>> {
+    let mut result = indexmap::IndexMap::new();
+    for (anno_db, db_info) in data.db_infos[genome_release].iter() {
+        result.insert(anno_db.to_string(), db_info.clone());
+        for (versioned_key, db_info, _) in data.versioned_annos(genome_release, anno_db) {
+            result.insert(versioned_key, Some(db_info.clone()));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(&[])]
+    #[case(&["1", "X"])]
+    fn response_contig_allowlist(#[case] contig_allowlist: &[&str]) -> Result<(), anyhow::Error> {
+        let data = WebServerData {
+            contig_allowlist: contig_allowlist.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+        let response = Response {
+            db_infos: db_infos(&data, GenomeRelease::Grch37),
+            contig_allowlist: data.contig_allowlist.clone(),
+        };
+
+        let value = serde_json::to_value(&response)?;
+        assert_eq!(value["cadd"], serde_json::Value::Null);
+        if contig_allowlist.is_empty() {
+            assert!(value.get("contig_allowlist").is_none());
+        } else {
+            assert_eq!(
+                value["contig_allowlist"],
+                serde_json::json!(contig_allowlist)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(None)]
+    #[case(Some("hg19"))]
+    #[case(Some("grch37,grch38"))]
+    fn request_invalid(#[case] genome_release: Option<&str>) {
+        let err = Request {
+            genome_release: genome_release.map(ToString::to_string),
+        }
+        .validate()
+        .unwrap_err();
+
+        assert_eq!(
+            actix_web::ResponseError::status_code(&err),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            err.param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: "genome_release".into(),
+                value: genome_release.map(ToString::to_string),
+            })
+        );
+    }
 }
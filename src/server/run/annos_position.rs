@@ -0,0 +1,303 @@
+//! Implementation of endpoint `/annos/position`.
+//!
+//! Returns the annotations of all alleles at one position for position-centric views.  The
+//! alleles are found by scanning the keys at the position in each variant database, see
+//! `fetch::fetch_pos_alleles`, and are then annotated as by `/annos/variant`.
+
+use std::collections::BTreeSet;
+
+use actix_web::{
+    get,
+    web::{self, Data, Json, Path},
+};
+use strum::IntoEnumIterator as _;
+
+use crate::common::{cli::GenomeRelease, keys, version};
+
+use super::{
+    annos_variant::{fetch_annos, response::SeqvarsAnnoResponseRecord, SeqvarsAnnosQuery},
+    error::CustomError,
+    fetch::fetch_pos_alleles,
+    params, AnnoDb, WebServerData,
+};
+
+/// Parameters for `handle`.
+#[serde_with::skip_serializing_none]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+pub struct AnnosPositionQuery {
+    /// Genome release specification.
+    pub genome_release: String,
+    /// Chromosome name.
+    pub chromosome: String,
+    /// 1-based position.
+    pub pos: u32,
+    /// Optional ClinVar release as `YYYY-MM`, defaults to the newest loaded one.
+    #[serde(default)]
+    pub clinvar_release: Option<String>,
+}
+
+/// Query parameters of `handle` as received.
+///
+/// All parameters are kept as strings so `validate()` can report invalid values together
+/// with the parameter name.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct AnnosPositionParams {
+    /// Genome release specification.
+    pub genome_release: Option<String>,
+    /// Chromosome name.
+    pub chromosome: Option<String>,
+    /// 1-based position.
+    pub pos: Option<String>,
+    /// ClinVar release as `YYYY-MM`.
+    pub clinvar_release: Option<String>,
+}
+
+impl AnnosPositionParams {
+    /// Validate the parameters and convert into an `AnnosPositionQuery` with its genome
+    /// release.
+    pub fn validate(&self) -> Result<(GenomeRelease, AnnosPositionQuery), CustomError> {
+        let genome_release = params::required("genome_release", &self.genome_release)?;
+        let parsed_genome_release = params::genome_release("genome_release", genome_release)?;
+        let query = AnnosPositionQuery {
+            genome_release: genome_release.to_string(),
+            chromosome: params::chromosome(
+                "chromosome",
+                params::required("chromosome", &self.chromosome)?,
+            )?,
+            pos: params::position("pos", params::required("pos", &self.pos)?)?,
+            clinvar_release: self
+                .clinvar_release
+                .as_deref()
+                .map(|value| params::year_month("clinvar_release", value))
+                .transpose()?,
+        };
+        params::position_in_contig("pos", query.pos, parsed_genome_release, &query.chromosome)?;
+        Ok((parsed_genome_release, query))
+    }
+}
+
+/// Annotations for one allele of `AnnosPositionResponse`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosPositionAllele {
+    /// Reference allele bases.
+    pub reference: String,
+    /// Alternative allele bases.
+    pub alternative: String,
+    /// Annotations for the allele from each database.
+    pub result: SeqvarsAnnoResponseRecord,
+}
+
+/// Result for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosPositionResponse {
+    /// Version of the server code.
+    pub server_version: String,
+    /// The query parameters.
+    pub query: AnnosPositionQuery,
+    /// The alleles found at the position in any database, sorted by reference and
+    /// alternative allele.
+    pub alleles: Vec<AnnosPositionAllele>,
+    /// Warnings about the query, e.g., databases without records on the chromosome.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Return the `(reference, alternative)` pairs of all variants at the position of `query`.
+///
+/// The alleles are collected from all variant databases, including the additional gnomAD
+/// and the selected ClinVar databases.  Degraded databases are skipped.
+fn fetch_alleles(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    query: &AnnosPositionQuery,
+) -> Result<BTreeSet<(String, String)>, CustomError> {
+    let pos = keys::Pos {
+        chrom: query.chromosome.clone(),
+        pos: query.pos as i32,
+    };
+    let mut result = BTreeSet::new();
+    for anno_db in AnnoDb::iter().filter(|anno_db| anno_db.is_variant_db()) {
+        if anno_db == AnnoDb::Clinvar {
+            if let Some((_, db)) =
+                data.clinvar_db(genome_release, query.clinvar_release.as_deref())?
+            {
+                result.extend(
+                    data.with_breaker(genome_release, anno_db, &db.data, || {
+                        fetch_pos_alleles(&db.data, anno_db.cf_name(), pos.clone())
+                    })?
+                    .unwrap_or_default(),
+                );
+            }
+            continue;
+        }
+        result.extend(
+            data.guarded(genome_release, anno_db, |db| {
+                fetch_pos_alleles(&db.data, anno_db.cf_name(), pos.clone())
+            })?
+            .unwrap_or_default(),
+        );
+        for (_, _, db) in data.versioned_annos(genome_release, anno_db) {
+            result.extend(fetch_pos_alleles(&db.data, anno_db.cf_name(), pos.clone())?);
+        }
+    }
+    Ok(result)
+}
+
+/// Fetch the annotations for all alleles at the position of `query`.
+///
+/// Positions on contigs outside of the contig allowlist have no alleles.
+fn fetch_position_annos(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    query: &AnnosPositionQuery,
+) -> Result<Vec<AnnosPositionAllele>, CustomError> {
+    if data.contig_allowed(&query.chromosome).is_err() {
+        return Ok(Vec::new());
+    }
+    fetch_alleles(data, genome_release, query)?
+        .into_iter()
+        .map(|(reference, alternative)| {
+            let result = fetch_annos(
+                data,
+                &SeqvarsAnnosQuery {
+                    genome_release: query.genome_release.clone(),
+                    chromosome: query.chromosome.clone(),
+                    pos: query.pos,
+                    reference: reference.clone(),
+                    alternative: alternative.clone(),
+                    clinvar_release: query.clinvar_release.clone(),
+                    haplogroup: None,
+                },
+            )?;
+            Ok(AnnosPositionAllele {
+                reference,
+                alternative,
+                result,
+            })
+        })
+        .collect()
+}
+
+/// Query for annotations of all alleles at one position.
+///
+/// Each variant database is scanned for the variants at the position.  The alleles found in
+/// any database are annotated with all databases as by `/annos/variant`.  Positions without
+/// variants in any database have no alleles.
+#[utoipa::path(
+    get,
+    operation_id = "annosPosition",
+    params(AnnosPositionQuery),
+    responses(
+        (status = 200, description = "Annotations of all alleles at the position.", body = AnnosPositionResponse),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/annos/position")]
+async fn handle(
+    data: Data<WebServerData>,
+    _path: Path<()>,
+    query: web::Query<AnnosPositionParams>,
+) -> actix_web::Result<Json<AnnosPositionResponse>, CustomError> {
+    let (genome_release, query) = query.validate()?;
+
+    let alleles = fetch_position_annos(&data, genome_release, &query)?;
+    let warnings = if let Err(not_in_allowlist) = data.contig_allowed(&query.chromosome) {
+        vec![not_in_allowlist.to_string()]
+    } else {
+        data.contig_not_in_databases(
+            genome_release,
+            &query.chromosome,
+            query.clinvar_release.as_deref(),
+        )?
+        .values()
+        .map(|not_in_database| not_in_database.to_string())
+        .chain(
+            data.circuit_breakers
+                .warnings(genome_release, AnnoDb::iter()),
+        )
+        .collect()
+    };
+
+    Ok(Json(AnnosPositionResponse {
+        server_version: version().to_string(),
+        query,
+        alleles,
+        warnings,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::server::run::annos_variant::test::data_with_alphamissense;
+
+    /// Call `/annos/position` with `query` on the AlphaMissense example data.
+    async fn call_position(query: &str) -> (StatusCode, serde_json::Value) {
+        let tmp_dir = TempDir::default();
+        let data = data_with_alphamissense(&tmp_dir);
+        let app = test::init_service(App::new().app_data(Data::new(data)).service(handle)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/annos/position?{}", query))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn position_multi_allelic() -> Result<(), anyhow::Error> {
+        // chr1:69094 has G>A, G>C, and G>T, chr1:69095 has T>C.
+        let (status, body) = call_position("genome_release=grch37&chromosome=1&pos=69094").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let alleles = body["alleles"].as_array().expect("no alleles");
+        assert_eq!(
+            alleles
+                .iter()
+                .map(|allele| (
+                    allele["reference"].as_str().unwrap_or_default(),
+                    allele["alternative"].as_str().unwrap_or_default(),
+                ))
+                .collect::<Vec<_>>(),
+            vec![("G", "A"), ("G", "C"), ("G", "T")]
+        );
+        for allele in alleles {
+            assert_eq!(
+                allele["result"]["alphamissense"]["records"]
+                    .as_array()
+                    .map(Vec::len),
+                Some(1),
+                "{}",
+                allele
+            );
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn position_without_data() -> Result<(), anyhow::Error> {
+        let (status, body) = call_position("genome_release=grch37&chromosome=1&pos=69096").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["alleles"], serde_json::json!([]));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn position_invalid_pos() -> Result<(), anyhow::Error> {
+        let (status, body) = call_position("genome_release=grch37&chromosome=1&pos=0").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["param"]["name"], "pos");
+
+        Ok(())
+    }
+}
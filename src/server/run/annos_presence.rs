@@ -0,0 +1,185 @@
+//! Presence of a variant in each database for `/annos/variant?presence_only=true`.
+//!
+//! Only the existence of the keys is checked, the values are not decoded.  The exception is
+//! gnomAD where the overall allele frequency is decoded from the allele counts while
+//! skipping the large remainder of the record, e.g., the VEP annotations.
+
+use crate::common::decode::decode_protobuf;
+
+use super::{
+    annos_variant::{db_var, prepare_query, SeqvarsAnnosQuery},
+    error::CustomError,
+    fetch::fetch_var_exists,
+    AnnoDb, DbInfo, WebServerData,
+};
+
+/// Presence of a variant in a gnomAD exomes or genomes database.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct SeqvarsGnomadPresence {
+    /// Whether the variant is in the database.
+    pub present: bool,
+    /// Overall allele frequency, if the variant is in the database.
+    pub af: Option<f32>,
+}
+
+/// Presence of a variant in each database, `null` for databases that are not configured.
+///
+/// UCSC conservation is position-based and thus not included.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct SeqvarsPresenceRecord {
+    /// Whether the variant is in CADD.
+    pub cadd: Option<bool>,
+    /// Whether the variant is in dbSNP.
+    pub dbsnp: Option<bool>,
+    /// Whether the variant is in dbNSFP.
+    pub dbnsfp: Option<bool>,
+    /// Whether the variant is in dbscSNV.
+    pub dbscsnv: Option<bool>,
+    /// Whether the variant is in gnomAD-mtDNA.
+    pub gnomad_mtdna: Option<bool>,
+    /// Presence in gnomAD-exomes.
+    pub gnomad_exomes: Option<SeqvarsGnomadPresence>,
+    /// Presence in gnomAD-genomes.
+    pub gnomad_genomes: Option<SeqvarsGnomadPresence>,
+    /// Presence in each gnomAD-exomes and gnomAD-genomes database by version if there is
+    /// more than one database of the kind, see `SeqvarsAnnoResponseRecord::gnomad_versions`.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub gnomad_versions: std::collections::BTreeMap<String, SeqvarsGnomadPresence>,
+    /// Whether the variant is in HelixMTdb.
+    pub helixmtdb: Option<bool>,
+    /// Whether the variant is in AlphaMissense.
+    pub alphamissense: Option<bool>,
+    /// Whether the variant is in ClinVar.
+    pub clinvar: Option<bool>,
+}
+
+/// Prefix of the gnomAD v2, v3, and v4 records with only the allele counts.
+///
+/// The three versions share the field numbers of the allele counts, all other fields are
+/// skipped when decoding.
+#[derive(Clone, PartialEq, prost::Message)]
+struct GnomadFreqsRecord {
+    /// Allele counts for each cohort.
+    #[prost(message, repeated, tag = "7")]
+    allele_counts: Vec<GnomadFreqsCohort>,
+}
+
+/// Allele counts of one cohort in `GnomadFreqsRecord`.
+#[derive(Clone, PartialEq, prost::Message)]
+struct GnomadFreqsCohort {
+    /// Name of the cohort, empty for all samples.
+    #[prost(string, optional, tag = "1")]
+    cohort: Option<String>,
+    /// Allele counts by sex.
+    #[prost(message, optional, tag = "3")]
+    by_sex: Option<crate::pbs::gnomad::gnomad3::AlleleCountsBySex>,
+}
+
+/// Fetch the presence of `query` in the gnomAD exomes or genomes database `db`.
+fn fetch_gnomad_presence(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    db_info: &DbInfo,
+    query: &SeqvarsAnnosQuery,
+) -> Result<SeqvarsGnomadPresence, CustomError> {
+    let cf_name = db_info.name.cf_name();
+    let cf_data = db
+        .cf_handle(cf_name)
+        .unwrap_or_else(|| panic!("unknown column family: {}", cf_name));
+    let key: Vec<u8> = db_var(query, Some(db_info)).into();
+
+    let raw_data = db
+        .get_pinned_cf(&cf_data, &key)
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
+    let Some(raw_data) = raw_data else {
+        return Ok(SeqvarsGnomadPresence::default());
+    };
+    Ok(SeqvarsGnomadPresence {
+        present: true,
+        af: decode_gnomad_af(db, cf_name, &key, &raw_data)?,
+    })
+}
+
+/// Decode the overall allele frequency from the gnomAD v2, v3, or v4 record `raw_data`.
+pub(crate) fn decode_gnomad_af(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    key: &[u8],
+    raw_data: &[u8],
+) -> Result<Option<f32>, CustomError> {
+    let record: GnomadFreqsRecord = decode_protobuf(db, cf_name, key, raw_data)?;
+    Ok(record
+        .allele_counts
+        .iter()
+        .find(|cohort| cohort.cohort.is_none())
+        .and_then(|cohort| cohort.by_sex.as_ref())
+        .and_then(|by_sex| by_sex.overall.as_ref())
+        .map(|overall| overall.af))
+}
+
+/// Fetch the presence of the variant of `query` in all databases.
+///
+/// Variants on contigs outside of the contig allowlist are not present in any database.
+pub fn fetch_presence(
+    data: &WebServerData,
+    query: &SeqvarsAnnosQuery,
+) -> Result<SeqvarsPresenceRecord, CustomError> {
+    let (genome_release, query) = prepare_query(query)?;
+    if data.contig_allowed(&query.chromosome).is_err() {
+        return Ok(SeqvarsPresenceRecord::default());
+    }
+    let exists = |anno_db: AnnoDb| -> Result<Option<bool>, CustomError> {
+        data.annos[genome_release][anno_db]
+            .as_ref()
+            .map(|db| {
+                fetch_var_exists(
+                    &db.data,
+                    anno_db.cf_name(),
+                    db_var(&query, data.db_infos[genome_release][anno_db].as_ref()),
+                )
+            })
+            .transpose()
+    };
+    let gnomad = |anno_db: AnnoDb| -> Result<Option<SeqvarsGnomadPresence>, CustomError> {
+        data.annos[genome_release][anno_db]
+            .as_ref()
+            .zip(data.db_infos[genome_release][anno_db].as_ref())
+            .map(|(db, db_info)| fetch_gnomad_presence(&db.data, db_info, &query))
+            .transpose()
+    };
+
+    Ok(SeqvarsPresenceRecord {
+        cadd: exists(AnnoDb::Cadd)?,
+        dbsnp: exists(AnnoDb::Dbsnp)?,
+        dbnsfp: exists(AnnoDb::Dbnsfp)?,
+        dbscsnv: exists(AnnoDb::Dbscsnv)?,
+        gnomad_mtdna: exists(AnnoDb::GnomadMtdna)?,
+        gnomad_exomes: gnomad(AnnoDb::GnomadExomes)?,
+        gnomad_genomes: gnomad(AnnoDb::GnomadGenomes)?,
+        gnomad_versions: [AnnoDb::GnomadExomes, AnnoDb::GnomadGenomes]
+            .into_iter()
+            .flat_map(|anno_db| data.versioned_annos(genome_release, anno_db))
+            .map(|(versioned_key, db_info, db)| {
+                Ok((
+                    versioned_key,
+                    fetch_gnomad_presence(&db.data, db_info, &query)?,
+                ))
+            })
+            .collect::<Result<_, CustomError>>()?,
+        helixmtdb: exists(AnnoDb::Helixmtdb)?,
+        alphamissense: exists(AnnoDb::Alphamissense)?,
+        clinvar: data
+            .clinvar_db(genome_release, query.clinvar_release.as_deref())?
+            .map(|(db_info, db)| {
+                fetch_var_exists(
+                    &db.data,
+                    AnnoDb::Clinvar.cf_name(),
+                    db_var(&query, Some(db_info)),
+                )
+            })
+            .transpose()?,
+    })
+}
@@ -8,21 +8,37 @@ use actix_web::{
 use strum::IntoEnumIterator;
 
 use crate::{
-    common::{keys, version},
+    common::{
+        cli::{self, GenomeRelease},
+        keys, version,
+    },
+    cons::per_base,
+    genes::mane::{ManeAnnotation, WithMane},
+    gnomad_coverage,
     server::{
-        run::fetch::{fetch_pos_protobuf_json, fetch_pos_tsv_json},
+        run::fetch::{
+            fetch_pos_protobuf, fetch_pos_protobuf_json_with_decoder, fetch_pos_tsv_json_filtered,
+        },
+        run::range_filter::Filter,
         run::AnnoDb,
     },
 };
 
-use super::{error::CustomError, WebServerData};
+use super::{
+    cadd::fetch_pos_cadd,
+    clinvar_sv::{self, response::StrucvarsClinvarResponseRecord},
+    error::CustomError,
+    params, regions, GeneInfoDb, WebServerData,
+};
 
-/// Parameters for `variant_annos::handle`.
+/// Parameters for `handle`.
 #[serde_with::skip_serializing_none]
 #[serde_with::serde_as]
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema, utoipa::IntoParams,
+)]
 #[serde(rename_all = "snake_case")]
-struct Request {
+pub(crate) struct AnnosRangeQuery {
     /// Genome release version.
     pub genome_release: String,
     /// Chromosome name.
@@ -31,9 +47,158 @@ struct Request {
     pub start: u32,
     /// 1-based stop position.
     pub stop: u32,
+    /// Whether to include overlapping ClinVar SVs in `clinvar_sv`.
+    pub include_sv: Option<bool>,
+    /// Minimal reciprocal overlap of ClinVar SVs, defaults to that of the ClinVar SV endpoint.
+    pub sv_min_overlap: Option<f64>,
+    /// ClinVar release as `YYYY-MM`, defaults to the newest loaded one.
+    pub clinvar_release: Option<String>,
+    /// Minimal CADD PHRED score of the variants in `cadd`, requires the typed CADD view.
+    pub min_cadd_phred: Option<f32>,
+    /// Number of positions to summarize into one bin of `gnomad_coverage`, defaults to `1`
+    /// for per-position values, see `gnomad_coverage::summarize`.
+    pub coverage_resolution: Option<u32>,
+    /// Whether to annotate the records of `ucsc_conservation` with the MANE Select status of
+    /// their transcript, keeping only MANE Select records with `true`; requires the genes
+    /// database, see `genes::mane`.
+    pub mane_only: Option<bool>,
+    /// Numeric column filters of the TSV databases, see `range_filter`.
+    #[serde_as(as = "Vec<serde_with::DisplayFromStr>")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    #[param(value_type = Vec<String>)]
+    pub filter: Vec<Filter>,
 }
 
-impl Request {
+/// Query parameters of `handle` as received.
+///
+/// All parameters are kept as strings so `validate()` can report invalid values together
+/// with the parameter name.
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+struct RequestParams {
+    /// Genome release version.
+    pub genome_release: Option<String>,
+    /// Chromosome name.
+    pub chromosome: Option<String>,
+    /// 1-based start position.
+    pub start: Option<String>,
+    /// 1-based stop position.
+    pub stop: Option<String>,
+    /// Whether to include overlapping ClinVar SVs in `clinvar_sv`.
+    pub include_sv: Option<String>,
+    /// Minimal reciprocal overlap of ClinVar SVs.
+    pub sv_min_overlap: Option<String>,
+    /// ClinVar release as `YYYY-MM`.
+    pub clinvar_release: Option<String>,
+    /// Minimal CADD PHRED score.
+    pub min_cadd_phred: Option<String>,
+    /// Number of positions per bin of the gnomAD coverage.
+    pub coverage_resolution: Option<String>,
+    /// Whether to annotate and keep only MANE Select conservation records.
+    pub mane_only: Option<String>,
+    /// Numeric column filters, from the repeated `filter` parameter, see `with_filters()`.
+    #[serde(skip)]
+    pub filter: Vec<String>,
+}
+
+impl RequestParams {
+    /// Set `filter` from the values of all `filter` parameters in `pairs`.
+    fn with_filters(self, pairs: &[(String, String)]) -> Self {
+        Self {
+            filter: pairs
+                .iter()
+                .filter(|(name, _)| name == "filter")
+                .map(|(_, value)| value.clone())
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Validate the parameters and convert into an `AnnosRangeQuery` with its genome release.
+    fn validate(&self) -> std::result::Result<(GenomeRelease, AnnosRangeQuery), CustomError> {
+        let genome_release = params::required("genome_release", &self.genome_release)?;
+        let parsed_genome_release = params::genome_release("genome_release", genome_release)?;
+        let start = params::required("start", &self.start)?;
+        let stop = params::required("stop", &self.stop)?;
+        let request = AnnosRangeQuery {
+            genome_release: genome_release.to_string(),
+            chromosome: params::chromosome(
+                "chromosome",
+                params::required("chromosome", &self.chromosome)?,
+            )?,
+            start: params::position("start", start)?,
+            stop: params::position("stop", stop)?,
+            include_sv: self
+                .include_sv
+                .as_deref()
+                .map(|value| params::parse("include_sv", value))
+                .transpose()?,
+            sv_min_overlap: self
+                .sv_min_overlap
+                .as_deref()
+                .map(|value| params::parse("sv_min_overlap", value))
+                .transpose()?,
+            clinvar_release: self
+                .clinvar_release
+                .as_deref()
+                .map(|value| params::year_month("clinvar_release", value))
+                .transpose()?,
+            min_cadd_phred: self
+                .min_cadd_phred
+                .as_deref()
+                .map(|value| params::parse("min_cadd_phred", value))
+                .transpose()?,
+            coverage_resolution: self
+                .coverage_resolution
+                .as_deref()
+                .map(|value| params::parse("coverage_resolution", value))
+                .transpose()?,
+            mane_only: self
+                .mane_only
+                .as_deref()
+                .map(|value| params::parse("mane_only", value))
+                .transpose()?,
+            filter: self
+                .filter
+                .iter()
+                .map(|value| params::parse("filter", value))
+                .collect::<std::result::Result<_, _>>()?,
+        };
+        params::position_in_contig(
+            "start",
+            request.start,
+            parsed_genome_release,
+            &request.chromosome,
+        )?;
+        if request.start > request.stop {
+            return Err(CustomError::invalid_param(
+                "stop",
+                Some(stop),
+                &format!("must not be less than start ({})", start),
+            ));
+        }
+        if request.coverage_resolution == Some(0) {
+            return Err(CustomError::invalid_param(
+                "coverage_resolution",
+                self.coverage_resolution.as_deref(),
+                "must be at least 1",
+            ));
+        }
+        if let Some(sv_min_overlap) = request.sv_min_overlap {
+            if !(0.0..=1.0).contains(&sv_min_overlap) {
+                return Err(CustomError::invalid_param(
+                    "sv_min_overlap",
+                    self.sv_min_overlap.as_deref(),
+                    "must be between 0 and 1",
+                ));
+            }
+        }
+
+        Ok((parsed_genome_release, request))
+    }
+}
+
+impl AnnosRangeQuery {
     /// Conver to start `keys::Pos`.
     pub fn start_pos(&self) -> keys::Pos {
         keys::Pos {
@@ -49,6 +214,15 @@ impl Request {
             pos: self.stop as i32,
         }
     }
+
+    /// Return the filters of `anno_db`.
+    pub fn filters(&self, anno_db: AnnoDb) -> Vec<Filter> {
+        self.filter
+            .iter()
+            .filter(|filter| filter.anno_db == anno_db)
+            .cloned()
+            .collect()
+    }
 }
 
 /// Result for `handle`.
@@ -61,208 +235,1148 @@ struct Result {
 }
 
 /// Result for `handle`.
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
 #[serde_with::skip_serializing_none]
-struct Container {
+pub(crate) struct AnnosRangeResponse {
     /// Version of the server code.
     pub server_version: String,
     /// The query parameters.
-    pub query: Request,
-    /// Annotations for the variant from each database.
+    pub query: AnnosRangeQuery,
+    /// Annotations for the positions of the range from each database.
     pub result: std::collections::BTreeMap<AnnoDb, Option<serde_json::Value>>,
+    /// ClinVar SVs overlapping the range, with `include_sv=true` and a loaded database.
+    ///
+    /// This is a separate list rather than being merged into the per-position records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clinvar_sv: Option<Vec<StrucvarsClinvarResponseRecord>>,
+    /// Regions of the custom region databases overlapping the range, by database name;
+    /// databases without overlapping regions are omitted.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub regions: std::collections::BTreeMap<String, Vec<regions::RegionsInterval>>,
+    /// Warnings about the query, e.g., databases without records on the chromosome.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Return the genes database for the `mane_only` parameter.
+///
+/// Fails if the server has no genes database or per-base conservation scores without
+/// transcripts for `genome_release`.
+fn mane_genes(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    mane_only: bool,
+) -> std::result::Result<&GeneInfoDb, CustomError> {
+    let genes = data.genes.as_ref().ok_or_else(|| {
+        CustomError::invalid_param(
+            "mane_only",
+            Some(&mane_only.to_string()),
+            "requires the genes database",
+        )
+    })?;
+    if data.annos[genome_release][AnnoDb::UcscConservation]
+        .as_ref()
+        .is_some_and(|db| per_base::is_per_base(&db.data))
+    {
+        return Err(CustomError::invalid_param(
+            "mane_only",
+            Some(&mane_only.to_string()),
+            "not supported for per-base conservation scores",
+        ));
+    }
+    Ok(&genes.data)
 }
 
-/// Query for annotations for one variant.
+/// Fetch the conservation records of `query` annotated with the MANE Select status of their
+/// transcript, only keeping those of MANE Select transcripts with `mane_only`.
+///
+/// Positions without remaining records are skipped.
+fn fetch_cons_with_mane(
+    genes: &GeneInfoDb,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    query: &AnnosRangeQuery,
+    mane_only: bool,
+) -> std::result::Result<Option<serde_json::Value>, CustomError> {
+    let record_lists = fetch_pos_protobuf::<crate::pbs::cons::RecordList>(
+        db,
+        AnnoDb::UcscConservation.cf_name(),
+        query.start_pos(),
+        query.stop_pos(),
+    )?;
+    let result = record_lists
+        .into_iter()
+        .filter_map(|record_list| {
+            let records = record_list
+                .records
+                .into_iter()
+                .filter_map(|record| {
+                    let mane_select = genes
+                        .name_to_hgnc_idx
+                        .get(&record.hgnc_id)
+                        .map(|idx| genes.gene_names[*idx].mane_select.as_slice())
+                        .unwrap_or_default();
+                    let mane = ManeAnnotation::new(&record.enst_id, mane_select);
+                    (!mane_only || mane.mane_select).then_some(WithMane { record, mane })
+                })
+                .collect::<Vec<_>>();
+            (!records.is_empty()).then(|| serde_json::json!({ "records": records }))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Some(serde_json::Value::Array(result)))
+}
+
+/// Query for annotations of all positions in a range.
+///
+/// The records of each database between `start` and `stop` are returned as stored, with
+/// ClinVar SVs overlapping the range in `clinvar_sv` with `include_sv=true`.  Stops beyond
+/// the end of the chromosome are clamped with a warning.
+#[utoipa::path(
+    get,
+    operation_id = "annosRange",
+    params(AnnosRangeQuery),
+    responses(
+        (status = 200, description = "Annotations of the range.", body = AnnosRangeResponse),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
 #[get("/annos/range")]
 async fn handle(
     data: Data<WebServerData>,
     _path: Path<()>,
-    query: web::Query<Request>,
+    query: web::Query<RequestParams>,
+    pairs: web::Query<Vec<(String, String)>>,
 ) -> actix_web::Result<impl Responder, CustomError> {
-    let genome_release =
-        query
-            .clone()
-            .into_inner()
-            .genome_release
-            .parse()
-            .map_err(|e: strum::ParseError| {
-                CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
-            })?;
+    let (genome_release, query) = query.into_inner().with_filters(&pairs).validate()?;
+
+    // Stops beyond the end of the chromosome are clamped with a warning.
+    let (stop, clamped) = cli::clamp_stop(genome_release, &query.chromosome, query.stop as usize);
+    let clamped =
+        clamped.map(|out_of_bounds| format!("{}, clamped stop to {}", out_of_bounds, stop));
+    let query = AnnosRangeQuery {
+        stop: stop as u32,
+        ..query
+    };
+
+    // Ranges on contigs outside of the contig allowlist get an empty result and a warning
+    // without accessing the databases.
+    if let Err(not_in_allowlist) = data.contig_allowed(&query.chromosome) {
+        return Ok(Json(AnnosRangeResponse {
+            server_version: version().to_string(),
+            query,
+            result: AnnoDb::iter()
+                .filter(|anno_db| *anno_db != AnnoDb::Other)
+                .map(|anno_db| (anno_db, None))
+                .collect(),
+            clinvar_sv: None,
+            regions: Default::default(),
+            warnings: clamped
+                .into_iter()
+                .chain(std::iter::once(not_in_allowlist.to_string()))
+                .collect(),
+        }));
+    }
+
+    // Databases without records on the chromosome get an empty section and a warning,
+    // degraded databases are omitted with a warning, see `circuit_breaker`.
+    let not_in_databases = data.contig_not_in_databases(
+        genome_release,
+        &query.chromosome,
+        query.clinvar_release.as_deref(),
+    )?;
 
     let mut annotations = std::collections::BTreeMap::default();
     for anno_db in AnnoDb::iter() {
+        if not_in_databases.contains_key(&anno_db) {
+            annotations.insert(anno_db, None);
+            continue;
+        }
         match anno_db {
             AnnoDb::Other => (),
             AnnoDb::Clinvar => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_protobuf_json::<
+                if let Some((_, db)) =
+                    data.clinvar_db(genome_release, query.clinvar_release.as_deref())?
+                {
+                    data.with_breaker(genome_release, anno_db, &db.data, || {
+                        fetch_pos_protobuf_json_with_decoder::<
                             crate::pbs::clinvar::minimal::ExtractedVcvRecordList,
                         >(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
+                    })?
                     .map(|v| annotations.insert(anno_db, v));
+                }
+            }
+            AnnoDb::Cadd => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_cadd(&data, genome_release, &db.data, &query)
+                })?
+                .map(|v| annotations.insert(anno_db, v));
+            }
+            AnnoDb::Dbnsfp | AnnoDb::Dbscsnv => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_pos_tsv_json_filtered(
+                        &db.data,
+                        anno_db.cf_name(),
+                        query.start_pos(),
+                        query.stop_pos(),
+                        &query.filters(anno_db),
+                    )
+                })?
+                .map(|v| annotations.insert(anno_db, v));
+            }
+            AnnoDb::Dbsnp => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_pos_protobuf_json_with_decoder::<crate::dbsnp::pbs::Record>(
+                        &db.data,
+                        anno_db.cf_name(),
+                        query.start_pos(),
+                        query.stop_pos(),
+                        &data.range_decoder,
+                    )
+                })?
+                .map(|v| annotations.insert(anno_db, v));
+            }
+            AnnoDb::Helixmtdb => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_pos_protobuf_json_with_decoder::<crate::helixmtdb::pbs::Record>(
+                        &db.data,
+                        anno_db.cf_name(),
+                        query.start_pos(),
+                        query.stop_pos(),
+                        &data.range_decoder,
+                    )
+                })?
+                .map(|v| annotations.insert(anno_db, v));
             }
-            AnnoDb::Cadd | AnnoDb::Dbnsfp | AnnoDb::Dbscsnv => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_tsv_json(
+            AnnoDb::Alphamissense => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_pos_protobuf_json_with_decoder::<crate::alphamissense::pbs::RecordList>(
+                        &db.data,
+                        anno_db.cf_name(),
+                        query.start_pos(),
+                        query.stop_pos(),
+                        &data.range_decoder,
+                    )
+                })?
+                .map(|v| annotations.insert(anno_db, v));
+            }
+            AnnoDb::GnomadMtdna => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::mtdna::Record>(
+                        &db.data,
+                        anno_db.cf_name(),
+                        query.start_pos(),
+                        query.stop_pos(),
+                        &data.range_decoder,
+                    )
+                })?
+                .map(|v| annotations.insert(anno_db, v));
+            }
+            AnnoDb::GnomadExomes => {
+                data.guarded(genome_release, anno_db, |db| {
+                    let db_version = data.db_infos[genome_release][anno_db]
+                        .as_ref()
+                        .expect("must have db info here")
+                        .db_version
+                        .as_ref()
+                        .expect("gnomAD must have db version");
+
+                    if db_version.starts_with("2.") {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::gnomad2::Record>(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::Dbsnp => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_protobuf_json::<crate::dbsnp::pbs::Record>(
+                    } else if db_version.starts_with("4.") {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::gnomad4::Record>(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
+                    } else {
+                        Err(CustomError::new(anyhow::anyhow!(
+                            "don't know how to handle gnomAD version {}",
+                            db_version
+                        )))
+                    }
+                })?
+                .map(|v| annotations.insert(anno_db, v));
             }
-            AnnoDb::Helixmtdb => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_protobuf_json::<crate::helixmtdb::pbs::Record>(
+            AnnoDb::GnomadGenomes => {
+                data.guarded(genome_release, anno_db, |db| {
+                    let db_version = data.db_infos[genome_release][anno_db]
+                        .as_ref()
+                        .expect("must have db info here")
+                        .db_version
+                        .as_ref()
+                        .expect("gnomAD must have db version");
+                    if db_version.starts_with("2.") {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::gnomad2::Record>(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::GnomadMtdna => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_protobuf_json::<crate::pbs::gnomad::mtdna::Record>(
+                    } else if db_version.starts_with("3.") {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::gnomad3::Record>(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::GnomadExomes => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        let db_version = data.db_infos[genome_release][anno_db]
-                            .as_ref()
-                            .expect("must have db info here")
-                            .db_version
-                            .as_ref()
-                            .expect("gnomAD must have db version");
-
-                        if db_version.starts_with("2.") {
-                            fetch_pos_protobuf_json::<crate::pbs::gnomad::gnomad2::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.start_pos(),
-                                query.stop_pos(),
-                            )
-                        } else if db_version.starts_with("4.") {
-                            fetch_pos_protobuf_json::<crate::pbs::gnomad::gnomad4::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.start_pos(),
-                                query.stop_pos(),
-                            )
-                        } else {
-                            Err(CustomError::new(anyhow::anyhow!(
-                                "don't know how to handle gnomAD version {}",
-                                db_version
-                            )))
-                        }
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
+                    } else if db_version.starts_with("4.") {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::gnomad::gnomad4::Record>(
+                            &db.data,
+                            anno_db.cf_name(),
+                            query.start_pos(),
+                            query.stop_pos(),
+                            &data.range_decoder,
+                        )
+                    } else {
+                        Err(CustomError::new(anyhow::anyhow!(
+                            "don't know how to handle gnomAD version {}",
+                            db_version
+                        )))
+                    }
+                })?
+                .map(|v| annotations.insert(anno_db, v));
             }
-            AnnoDb::GnomadGenomes => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        let db_version = data.db_infos[genome_release][anno_db]
-                            .as_ref()
-                            .expect("must have db info here")
-                            .db_version
-                            .as_ref()
-                            .expect("gnomAD must have db version");
-                        if db_version.starts_with("2.") {
-                            fetch_pos_protobuf_json::<crate::pbs::gnomad::gnomad2::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.start_pos(),
-                                query.stop_pos(),
-                            )
-                        } else if db_version.starts_with("3.") {
-                            fetch_pos_protobuf_json::<crate::pbs::gnomad::gnomad3::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.start_pos(),
-                                query.stop_pos(),
-                            )
-                        } else if db_version.starts_with("4.") {
-                            fetch_pos_protobuf_json::<crate::pbs::gnomad::gnomad4::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.start_pos(),
-                                query.stop_pos(),
-                            )
-                        } else {
-                            Err(CustomError::new(anyhow::anyhow!(
-                                "don't know how to handle gnomAD version {}",
-                                db_version
-                            )))
-                        }
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
+            AnnoDb::GnomadCoverage => {
+                data.guarded(genome_release, anno_db, |db| {
+                    fetch_gnomad_coverage(&db.data, &query)
+                })?
+                .map(|v| annotations.insert(anno_db, v));
             }
             AnnoDb::UcscConservation => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_pos_protobuf_json::<crate::pbs::cons::RecordList>(
+                let mane_genes = query
+                    .mane_only
+                    .map(|mane_only| mane_genes(&data, genome_release, mane_only))
+                    .transpose()?;
+                data.guarded(genome_release, anno_db, |db| {
+                    if let Some((genes, mane_only)) = mane_genes.zip(query.mane_only) {
+                        fetch_cons_with_mane(genes, &db.data, &query, mane_only)
+                    } else if per_base::is_per_base(&db.data) {
+                        per_base::fetch_range(
+                            &db.data,
+                            per_base::CF_NAME,
+                            query.start_pos(),
+                            query.stop_pos(),
+                        )
+                        .and_then(|records| Ok(Some(serde_json::to_value(records)?)))
+                        .map_err(CustomError::new)
+                    } else {
+                        fetch_pos_protobuf_json_with_decoder::<crate::pbs::cons::RecordList>(
                             &db.data,
                             anno_db.cf_name(),
                             query.start_pos(),
                             query.stop_pos(),
+                            &data.range_decoder,
                         )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
+                    }
+                })?
+                .map(|v| annotations.insert(anno_db, v));
             }
         }
     }
 
-    let result = Container {
+    let clinvar_sv = if query.include_sv.unwrap_or(false) {
+        data.clinvar_svs[genome_release]
+            .as_ref()
+            .map(|trees| {
+                clinvar_sv::query_overlapping(
+                    trees,
+                    &query.chromosome,
+                    query.start,
+                    query.stop,
+                    &[],
+                    query
+                        .sv_min_overlap
+                        .unwrap_or(clinvar_sv::DEFAULT_MIN_OVERLAP),
+                )
+            })
+            .transpose()?
+            .map(|records| {
+                records
+                    .into_iter()
+                    .map(StrucvarsClinvarResponseRecord::try_from)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(CustomError::new)
+            })
+            .transpose()?
+    } else {
+        None
+    };
+
+    let regions = regions::query_overlapping(
+        &data.regions[genome_release],
+        &query.chromosome,
+        query.start,
+        query.stop,
+    )?;
+
+    let result = AnnosRangeResponse {
         server_version: version().to_string(),
-        query: query.into_inner(),
+        query,
         result: annotations,
+        clinvar_sv,
+        regions,
+        warnings: clamped
+            .into_iter()
+            .chain(
+                not_in_databases
+                    .values()
+                    .map(|not_in_database| not_in_database.to_string()),
+            )
+            .chain(
+                data.circuit_breakers
+                    .warnings(genome_release, AnnoDb::iter()),
+            )
+            .collect(),
     };
 
     Ok(Json(result))
 }
+
+/// Fetch the CADD annotations of the range of `query` from `db`.
+///
+/// The records are returned with the typed scores if the score columns are known and with
+/// all columns otherwise, where `min_cadd_phred` is rejected.
+fn fetch_cadd(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    query: &AnnosRangeQuery,
+) -> std::result::Result<Option<serde_json::Value>, CustomError> {
+    let cf_name = AnnoDb::Cadd.cf_name();
+    match data.cadd_columns[genome_release] {
+        Some(columns) => {
+            let records = fetch_pos_cadd(
+                db,
+                cf_name,
+                &columns,
+                query.start_pos(),
+                query.stop_pos(),
+                query.min_cadd_phred,
+                &query.filters(AnnoDb::Cadd),
+            )?;
+            serde_json::to_value(records).map(Some).map_err(|e| {
+                CustomError::new(anyhow::anyhow!("problem encoding CADD scores: {}", e))
+            })
+        }
+        None if query.min_cadd_phred.is_some() => Err(CustomError::invalid_param(
+            "min_cadd_phred",
+            query
+                .min_cadd_phred
+                .map(|value| value.to_string())
+                .as_deref(),
+            "typed CADD scores are not available for this database",
+        )),
+        None => fetch_pos_tsv_json_filtered(
+            db,
+            cf_name,
+            query.start_pos(),
+            query.stop_pos(),
+            &query.filters(AnnoDb::Cadd),
+        ),
+    }
+}
+
+/// Fetch the gnomAD coverage of the range of `query` from `db`.
+///
+/// The coverage is returned per position or summarized into bins of `coverage_resolution`
+/// positions, for plotting large ranges.
+fn fetch_gnomad_coverage(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    query: &AnnosRangeQuery,
+) -> std::result::Result<Option<serde_json::Value>, CustomError> {
+    let records = gnomad_coverage::fetch_range(
+        db,
+        AnnoDb::GnomadCoverage.cf_name(),
+        query.start_pos(),
+        query.stop_pos(),
+    )
+    .map_err(CustomError::new)?;
+    let value = match query.coverage_resolution.unwrap_or(1) {
+        1 => serde_json::to_value(records),
+        resolution => serde_json::to_value(gnomad_coverage::summarize(
+            &records,
+            query.start as i32,
+            query.stop as i32,
+            resolution,
+        )),
+    };
+    value
+        .map(Some)
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem encoding coverage: {}", e)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Valid query parameters for `RequestParams`.
+    fn valid_params() -> RequestParams {
+        RequestParams {
+            genome_release: Some("grch37".into()),
+            chromosome: Some("1".into()),
+            start: Some("1".into()),
+            stop: Some("55516888".into()),
+            include_sv: Some("true".into()),
+            sv_min_overlap: None,
+            clinvar_release: None,
+            min_cadd_phred: None,
+            coverage_resolution: None,
+            mane_only: None,
+            filter: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn params_valid() -> std::result::Result<(), anyhow::Error> {
+        let (genome_release, request) = valid_params()
+            .validate()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        assert_eq!(genome_release, GenomeRelease::Grch37);
+        assert_eq!((request.start, request.stop), (1, 55516888));
+        assert_eq!(request.include_sv, Some(true));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("genome_release", None)]
+    #[case("genome_release", Some("hg19"))]
+    #[case("chromosome", None)]
+    #[case("start", Some("0"))]
+    #[case("start", Some("abc"))]
+    #[case("start", Some("249250622"))]
+    #[case("stop", None)]
+    #[case("stop", Some("-5"))]
+    #[case("include_sv", Some("yes"))]
+    #[case("sv_min_overlap", Some("1.5"))]
+    #[case("clinvar_release", Some("202401"))]
+    #[case("min_cadd_phred", Some("high"))]
+    #[case("coverage_resolution", Some("0"))]
+    #[case("mane_only", Some("yes"))]
+    #[case("filter", Some("dbscsnv.ada_score=>0.6"))]
+    #[case("filter", Some("dbsnp.rs_id>1"))]
+    fn params_invalid(#[case] name: &str, #[case] value: Option<&str>) {
+        let mut params = valid_params();
+        let value_string = value.map(ToString::to_string);
+        match name {
+            "genome_release" => params.genome_release = value_string,
+            "chromosome" => params.chromosome = value_string,
+            "start" => params.start = value_string,
+            "stop" => params.stop = value_string,
+            "include_sv" => params.include_sv = value_string,
+            "sv_min_overlap" => params.sv_min_overlap = value_string,
+            "clinvar_release" => params.clinvar_release = value_string,
+            "min_cadd_phred" => params.min_cadd_phred = value_string,
+            "coverage_resolution" => params.coverage_resolution = value_string,
+            "mane_only" => params.mane_only = value_string,
+            "filter" => params.filter = value_string.into_iter().collect(),
+            _ => unreachable!(),
+        }
+
+        let err = params.validate().unwrap_err();
+
+        assert_eq!(
+            actix_web::ResponseError::status_code(&err),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            err.param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: name.into(),
+                value: value.map(ToString::to_string),
+            })
+        );
+    }
+
+    #[test]
+    fn params_filters() -> std::result::Result<(), anyhow::Error> {
+        let pairs = web::Query::<Vec<(String, String)>>::from_query(
+            "genome_release=grch37&filter=dbscsnv.ada_score%3E0.6&start=1&filter=cadd.PHRED%3E%3D20",
+        )?;
+        let (_, request) = valid_params()
+            .with_filters(&pairs)
+            .validate()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        assert_eq!(
+            request
+                .filter
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["dbscsnv.ada_score>0.6", "cadd.PHRED>=20"]
+        );
+        assert_eq!(request.filters(AnnoDb::Cadd).len(), 1);
+        assert_eq!(request.filters(AnnoDb::Dbnsfp), Vec::new());
+        assert_eq!(
+            serde_json::to_value(&request)?["filter"],
+            serde_json::json!(["dbscsnv.ada_score>0.6", "cadd.PHRED>=20"])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn params_stop_before_start() {
+        let err = RequestParams {
+            start: Some("100".into()),
+            stop: Some("99".into()),
+            ..valid_params()
+        }
+        .validate()
+        .unwrap_err();
+
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "err": "invalid value \"99\" for parameter stop: must not be less than start (100)",
+                "param": {"name": "stop", "value": "99"},
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::typed(true, Some("20"), Ok(2))]
+    #[case::typed_unfiltered(true, None, Ok(5))]
+    #[case::untyped(false, Some("20"), Err(()))]
+    fn fetch_cadd_min_phred(
+        #[case] typed: bool,
+        #[case] min_cadd_phred: Option<&str>,
+        #[case] expected: std::result::Result<usize, ()>,
+    ) -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let mut data = WebServerData::default();
+        data.cadd_columns[GenomeRelease::Grch37] =
+            typed.then_some(crate::server::run::cadd::CaddColumns { raw: 4, phred: 5 });
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            crate::server::run::cadd::test::import_cadd(&tmp_dir),
+            ["meta", AnnoDb::Cadd.cf_name()],
+            false,
+        )?;
+        let (genome_release, query) = RequestParams {
+            start: Some("1000".into()),
+            stop: Some("1002".into()),
+            min_cadd_phred: min_cadd_phred.map(ToString::to_string),
+            ..valid_params()
+        }
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let result = fetch_cadd(&data, genome_release, &db, &query);
+
+        match expected {
+            Ok(count) => {
+                let value = result.map_err(|e| anyhow::anyhow!("{}", e))?.unwrap();
+                assert_eq!(value.as_array().map(Vec::len), Some(count));
+            }
+            Err(()) => {
+                let err = result.unwrap_err();
+                assert_eq!(
+                    err.param().map(|param| param.name.as_str()),
+                    Some("min_cadd_phred")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::typed(true)]
+    #[case::untyped(false)]
+    fn fetch_cadd_filter(#[case] typed: bool) -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let mut data = WebServerData::default();
+        data.cadd_columns[GenomeRelease::Grch37] =
+            typed.then_some(crate::server::run::cadd::CaddColumns { raw: 4, phred: 5 });
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            crate::server::run::cadd::test::import_cadd(&tmp_dir),
+            ["meta", AnnoDb::Cadd.cf_name()],
+            false,
+        )?;
+        let (genome_release, query) = RequestParams {
+            start: Some("1000".into()),
+            stop: Some("1002".into()),
+            filter: vec!["cadd.PHRED>22".into(), "dbscsnv.ada_score>0.6".into()],
+            ..valid_params()
+        }
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let value = fetch_cadd(&data, genome_release, &db, &query)
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .unwrap();
+
+        if typed {
+            assert_eq!(value.as_array().map(Vec::len), Some(1));
+            assert_eq!(value[0]["phred"], serde_json::json!(25.1f32));
+        } else {
+            assert_eq!(value["PHRED"], serde_json::json!(25.1));
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::per_position(None, serde_json::json!([
+        {"chrom": "1", "pos": 55505598, "mean": 31.25, "fraction_over_20x": 0.9375},
+        {"chrom": "1", "pos": 55505599, "mean": 31.5, "fraction_over_20x": 0.9375},
+        {"chrom": "1", "pos": 55505600, "mean": 30.75, "fraction_over_20x": 0.875},
+    ]))]
+    #[case::binned(Some("5"), serde_json::json!([
+        {
+            "chrom": "1",
+            "start": 55505595,
+            "stop": 55505599,
+            "positions": 5,
+            "mean": 31.0,
+            "min_mean": 30.5,
+            "fraction_over_20x": 0.9125f32,
+        },
+        {
+            "chrom": "1",
+            "start": 55505600,
+            "stop": 55505604,
+            "positions": 5,
+            "mean": 22.25,
+            "min_mean": 8.25,
+            "fraction_over_20x": 0.55f32,
+        },
+    ]))]
+    fn fetch_gnomad_coverage_range(
+        #[case] coverage_resolution: Option<&str>,
+        #[case] expected: serde_json::Value,
+    ) -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            gnomad_coverage::test::import_coverage(&tmp_dir),
+            ["meta", AnnoDb::GnomadCoverage.cf_name()],
+            false,
+        )?;
+        let (start, stop) = match coverage_resolution {
+            None => ("55505598", "55505600"),
+            Some(_) => ("55505595", "55505610"),
+        };
+        let (_, query) = RequestParams {
+            start: Some(start.into()),
+            stop: Some(stop.into()),
+            coverage_resolution: coverage_resolution.map(ToString::to_string),
+            ..valid_params()
+        }
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let value = fetch_gnomad_coverage(&db, &query)
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .unwrap();
+
+        assert_eq!(value, expected);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("17", &["contig 17 not present in gnomad_exomes"])]
+    #[case("chr1", &["contig 1 not present in dbsnp"])]
+    #[case("chrY", &["contig Y not present in dbsnp"])]
+    #[case(
+        "22",
+        &["contig 22 not present in dbsnp", "contig 22 not present in gnomad_exomes"]
+    )]
+    fn contig_not_in_databases(
+        #[case] chromosome: &str,
+        #[case] expected: &[&str],
+    ) -> std::result::Result<(), anyhow::Error> {
+        let mut data = WebServerData::default();
+        for (anno_db, path) in [
+            (AnnoDb::Dbsnp, "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"),
+            (
+                AnnoDb::GnomadExomes,
+                "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz.db",
+            ),
+        ] {
+            data.annos[GenomeRelease::Grch37][anno_db] =
+                Some(crate::server::run::WithVersionSpec {
+                    data: rocksdb::DB::open_cf_for_read_only(
+                        &rocksdb::Options::default(),
+                        path,
+                        ["meta", anno_db.cf_name()],
+                        false,
+                    )?,
+                    version_spec: None,
+                });
+        }
+
+        let warnings = data
+            .contig_not_in_databases(GenomeRelease::Grch37, chromosome, None)
+            .map_err(|e| anyhow::anyhow!("{}", e))?
+            .values()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(warnings, expected);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::allowed("17", false)]
+    #[case::not_allowed("X", true)]
+    #[actix_web::test]
+    async fn handle_contig_allowlist(
+        #[case] allowlist: &str,
+        #[case] not_in_allowlist: bool,
+    ) -> std::result::Result<(), anyhow::Error> {
+        let mut data = WebServerData {
+            contig_allowlist: vec![allowlist.to_string()],
+            ..Default::default()
+        };
+        data.annos[GenomeRelease::Grch37][AnnoDb::Dbsnp] =
+            Some(crate::server::run::WithVersionSpec {
+                data: rocksdb::DB::open_cf_for_read_only(
+                    &rocksdb::Options::default(),
+                    "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+                    ["meta", AnnoDb::Dbsnp.cf_name()],
+                    false,
+                )?,
+                version_spec: None,
+            });
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/annos/range?genome_release=grch37&chromosome=chr17&start=41267746&stop=41267746")
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        let dbsnp = response.result.get(&AnnoDb::Dbsnp).cloned().flatten();
+        if not_in_allowlist {
+            assert_eq!(dbsnp, None);
+            assert_eq!(
+                response.warnings,
+                vec!["contig 17 not in the contig allowlist of the server: X"]
+            );
+        } else {
+            assert!(dbsnp.and_then(|dbsnp| dbsnp.as_array().map(Vec::len)) > Some(0));
+            assert!(response.warnings.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_stop_clamped() -> std::result::Result<(), anyhow::Error> {
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::Dbsnp] =
+            Some(crate::server::run::WithVersionSpec {
+                data: rocksdb::DB::open_cf_for_read_only(
+                    &rocksdb::Options::default(),
+                    "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+                    ["meta", AnnoDb::Dbsnp.cf_name()],
+                    false,
+                )?,
+                version_spec: None,
+            });
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/annos/range?genome_release=grch37&chromosome=chr17&start=41267746&stop=812675200",
+            )
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response.query.stop, 81195210);
+        assert_eq!(
+            response.warnings,
+            vec![
+                "position 812,675,200 exceeds length of chr17 (81,195,210) for GRCh37, \
+                clamped stop to 81195210"
+            ]
+        );
+        let dbsnp = response.result.get(&AnnoDb::Dbsnp).cloned().flatten();
+        assert!(dbsnp.and_then(|dbsnp| dbsnp.as_array().map(Vec::len)) > Some(0));
+
+        // Starts beyond the end of the chromosome are rejected.
+        let req = actix_web::test::TestRequest::get()
+            .uri("/annos/range?genome_release=grch37&chromosome=chr17&start=81195211&stop=81195300")
+            .to_request();
+        let response = actix_web::test::call_service(&app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_regions() -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let mut data = WebServerData::default();
+        data.regions[GenomeRelease::Grch37] =
+            crate::server::run::regions::test::example_regions(&tmp_dir);
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/annos/range?genome_release=grch37&chromosome=chr1&start=1990&stop=2010")
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        let names = response.regions["example"]
+            .iter()
+            .map(|region| region.name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![Some("segdup_1"), Some("blacklist")]);
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/annos/range?genome_release=grch37&chromosome=chr1&start=3001&stop=3010")
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        assert!(response.regions.is_empty());
+
+        Ok(())
+    }
+
+    /// Return the server data with the conservation example and TGDS with the MANE Select
+    /// accessions `mane_select` in the genes.
+    fn data_cons_mane(tmp_dir: &temp_testdir::TempDir, mane_select: &[&str]) -> WebServerData {
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::UcscConservation] =
+            Some(crate::server::run::WithVersionSpec {
+                data: rocksdb::DB::open_cf_for_read_only(
+                    &rocksdb::Options::default(),
+                    "tests/cons/example/tgds.tsv.db",
+                    ["meta", AnnoDb::UcscConservation.cf_name()],
+                    false,
+                )
+                .unwrap(),
+                version_spec: None,
+            });
+        let gene_names: Vec<crate::server::run::GeneNames> =
+            serde_json::from_value(serde_json::json!([{
+                "hgnc_id": "HGNC:20324",
+                "symbol": "TGDS",
+                "name": "TDP-glucose 4,6-dehydratase",
+                "alias_symbol": [],
+                "alias_name": [],
+                "ensembl_gene_id": null,
+                "ncbi_gene_id": null,
+                "mane_select": mane_select,
+            }]))
+            .unwrap();
+        data.genes = Some(crate::server::run::WithVersionSpec {
+            data: crate::server::run::GeneInfoDb {
+                db: rocksdb::DB::open_default(tmp_dir.join("genes")).unwrap(),
+                db_clinvar: None,
+                name_to_hgnc_idx: crate::server::run::build_name_to_hgnc_idx(&gene_names),
+                other_symbol_to_hgnc_idxs: Default::default(),
+                transcript_to_hgnc_idxs: Default::default(),
+                gene_names,
+                acmg_sf: Vec::new(),
+                source_versions: Default::default(),
+            },
+            version_spec: None,
+        });
+        data
+    }
+
+    // The records of the example all have the transcript ENST00000261296 of TGDS.
+    #[rstest::rstest]
+    #[case::annotate(&["ENST00000261296.7"], "false", 4, true)]
+    #[case::mane_only(&["ENST00000261296.7"], "true", 4, true)]
+    #[case::other_transcript(&["ENST00000999999.1"], "false", 4, false)]
+    #[case::other_transcript_mane_only(&["ENST00000999999.1"], "true", 0, false)]
+    #[actix_web::test]
+    async fn handle_cons_mane(
+        #[case] mane_select: &[&str],
+        #[case] mane_only: &str,
+        #[case] expected_count: usize,
+        #[case] expected_mane_select: bool,
+    ) -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data_cons_mane(&tmp_dir, mane_select)))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!(
+                "/annos/range?genome_release=grch37&chromosome=13&start=95248336&stop=95248351\
+                &mane_only={}",
+                mane_only
+            ))
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        let records = response.result[&AnnoDb::UcscConservation]
+            .as_ref()
+            .and_then(|value| value.as_array())
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|record_list| {
+                record_list["records"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), expected_count);
+        for record in &records {
+            assert_eq!(record["enstId"], "ENST00000261296");
+            assert_eq!(record["maneSelect"], expected_mane_select);
+            assert_eq!(record["maneSelectAccession"], mane_select[0]);
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_cons_mane_without_genes() -> std::result::Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let data = WebServerData {
+            genes: None,
+            ..data_cons_mane(&tmp_dir, &[])
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/annos/range?genome_release=grch37&chromosome=13&start=95248336&stop=95248351\
+                &mane_only=true",
+            )
+            .to_request();
+        let response = actix_web::test::call_service(&app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    /// Return `WebServerData` with the GRCh37 ClinVar SV fixture database.
+    fn data_clinvar_sv() -> std::result::Result<WebServerData, anyhow::Error> {
+        let (db, meta) = crate::clinvar_sv::cli::query::open_rocksdb(
+            "tests/clinvar-sv/clinvar-sv-grch37.db",
+            "clinvar_sv",
+            "meta",
+            "clinvar_sv_by_rcv",
+            crate::clinvar_sv::index::CF_NAME,
+        )?;
+        let mut data = WebServerData::default();
+        data.clinvar_svs[GenomeRelease::Grch37] =
+            Some(crate::clinvar_sv::cli::query::IntervalTrees::with_db(
+                db,
+                "clinvar_sv",
+                crate::clinvar_sv::index::CF_NAME,
+                meta,
+            )?);
+        Ok(data)
+    }
+
+    /// The SV VCV000057627 spans 22:34150132-34182300, so the first half of it has a
+    /// reciprocal overlap just above 0.5 and the first 69 bp have one of about 0.002.
+    #[rstest::rstest]
+    #[case::not_included("false", "34150132", "34182300", None, None)]
+    #[case::exact("true", "34150132", "34182300", None, Some(vec![("VCV000057627", 1.0)]))]
+    #[case::half_default("true", "34150132", "34166216", None, Some(vec![("VCV000057627", 0.5)]))]
+    #[case::half_filtered("true", "34150132", "34166216", Some("0.6"), Some(vec![]))]
+    #[case::small_default("true", "34150132", "34150200", None, Some(vec![]))]
+    #[case::small_no_min("true", "34150132", "34150200", Some("0"), Some(vec![("VCV000057627", 0.0)]))]
+    #[case::no_overlap("true", "34100000", "34150000", Some("0"), Some(vec![]))]
+    #[actix_web::test]
+    async fn handle_include_sv(
+        #[case] include_sv: &str,
+        #[case] start: &str,
+        #[case] stop: &str,
+        #[case] sv_min_overlap: Option<&str>,
+        #[case] expected: Option<Vec<(&str, f64)>>,
+    ) -> std::result::Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data_clinvar_sv()?))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!(
+                "/annos/range?genome_release=grch37&chromosome=22&start={}&stop={}\
+                &include_sv={}{}",
+                start,
+                stop,
+                include_sv,
+                sv_min_overlap
+                    .map(|value| format!("&sv_min_overlap={}", value))
+                    .unwrap_or_default()
+            ))
+            .to_request();
+        let response: AnnosRangeResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        // Compare the overlaps to two decimal places.
+        let clinvar_sv = response.clinvar_sv.map(|records| {
+            records
+                .into_iter()
+                .map(|record| {
+                    let accession = record
+                        .record
+                        .and_then(|record| record.accession)
+                        .map(|accession| accession.accession)
+                        .unwrap_or_default();
+                    (accession, (record.overlap * 100.0).floor() / 100.0)
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            clinvar_sv,
+            expected.map(|expected| expected
+                .into_iter()
+                .map(|(accession, overlap)| (accession.to_string(), overlap))
+                .collect::<Vec<_>>())
+        );
+
+        Ok(())
+    }
+}
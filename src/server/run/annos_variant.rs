@@ -4,19 +4,29 @@
 
 use actix_web::{
     get,
+    http::header::ContentType,
+    post,
     web::{self, Data, Json, Path},
+    Either, HttpResponse,
 };
-use strum::IntoEnumIterator;
+use strum::IntoEnumIterator as _;
 
 use crate::{
-    common::{keys, version},
-    server::run::{fetch::fetch_pos_protobuf, AnnoDb},
+    common::{cli::GenomeRelease, keys, pos_index, version},
+    freqs::computed::ComputedFrequencies,
+    gnomad_nuclear::grpmax,
+    server::run::{fetch::fetch_pos_protobuf, AnnoDb, DbInfo},
 };
 
+use super::annos_presence::{fetch_presence, SeqvarsPresenceRecord};
+use super::cadd::{fetch_var_cadd, CaddColumns};
+use super::clinvar_data::ClinvarVcvDetails;
+use super::context::{self, VariantContext};
 use super::error::CustomError;
-use super::fetch::{
-    fetch_pos_protobuf_json, fetch_var_protobuf, fetch_var_protobuf_json, fetch_var_tsv_json,
-};
+use super::fetch::{fetch_var_protobuf, fetch_var_tsv_json};
+use super::params;
+use super::regions::{self, RegionsInterval};
+use super::variant_cache::VariantCacheKey;
 
 /// Parameters for `variant_annos::handle`.
 ///
@@ -37,6 +47,116 @@ pub struct SeqvarsAnnosQuery {
     pub reference: String,
     /// Alterantive allele bases.
     pub alternative: String,
+    /// Optional ClinVar release as `YYYY-MM`, defaults to the newest loaded one.
+    #[serde(default)]
+    pub clinvar_release: Option<String>,
+    /// Optional mtDNA haplogroup, e.g., `L3`, to restrict the gnomAD-mtDNA haplogroup counts to.
+    #[serde(default)]
+    pub haplogroup: Option<String>,
+}
+
+/// Query parameters of `handle` and `handle_with_openapi` as received.
+///
+/// All parameters are kept as strings so `validate()` can report invalid values together
+/// with the parameter name.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct SeqvarsAnnosParams {
+    /// Genome release specification.
+    pub genome_release: Option<String>,
+    /// Chromosome name.
+    pub chromosome: Option<String>,
+    /// 1-based position for VCF-style variant.
+    pub pos: Option<String>,
+    /// Reference allele bases.
+    pub reference: Option<String>,
+    /// Alternative allele bases.
+    pub alternative: Option<String>,
+    /// ClinVar release as `YYYY-MM`.
+    pub clinvar_release: Option<String>,
+    /// mtDNA haplogroup to restrict the gnomAD-mtDNA haplogroup counts to.
+    pub haplogroup: Option<String>,
+    /// Whether to only return the presence in each database.
+    pub presence_only: Option<String>,
+    /// Whether to return the ClinVar submission-level details.
+    pub clinvar_details: Option<String>,
+    /// Whether to return the nearest genes and functional elements.
+    pub context: Option<String>,
+    /// Maximal distance to search for the nearest genes and functional elements.
+    pub context_max_window: Option<String>,
+}
+
+impl SeqvarsAnnosParams {
+    /// Validate the parameters and convert into a `SeqvarsAnnosQuery`.
+    ///
+    /// The alleles are normalized to uppercase.
+    pub fn validate(&self) -> Result<SeqvarsAnnosQuery, CustomError> {
+        let genome_release = params::required("genome_release", &self.genome_release)?;
+        parse_genome_release(genome_release)?;
+        Ok(SeqvarsAnnosQuery {
+            genome_release: genome_release.to_string(),
+            chromosome: params::chromosome(
+                "chromosome",
+                params::required("chromosome", &self.chromosome)?,
+            )?,
+            pos: params::position("pos", params::required("pos", &self.pos)?)?,
+            reference: params::allele(
+                "reference",
+                params::required("reference", &self.reference)?,
+            )?,
+            alternative: params::allele(
+                "alternative",
+                params::required("alternative", &self.alternative)?,
+            )?,
+            clinvar_release: self
+                .clinvar_release
+                .as_deref()
+                .map(|value| params::year_month("clinvar_release", value))
+                .transpose()?,
+            haplogroup: self
+                .haplogroup
+                .as_deref()
+                .map(|value| params::haplogroup("haplogroup", value))
+                .transpose()?,
+        })
+    }
+
+    /// Validate the `presence_only` parameter, `false` if missing.
+    pub fn presence_only(&self) -> Result<bool, CustomError> {
+        Ok(self
+            .presence_only
+            .as_deref()
+            .map(|value| params::parse("presence_only", value))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    /// Validate the `clinvar_details` parameter, `false` if missing.
+    pub fn clinvar_details(&self) -> Result<bool, CustomError> {
+        Ok(self
+            .clinvar_details
+            .as_deref()
+            .map(|value| params::parse("clinvar_details", value))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    /// Validate the `context` and `context_max_window` parameters, the maximal window with
+    /// `context=true` and `None` otherwise.
+    pub fn context(&self) -> Result<Option<u32>, CustomError> {
+        let context: bool = self
+            .context
+            .as_deref()
+            .map(|value| params::parse("context", value))
+            .transpose()?
+            .unwrap_or_default();
+        let max_window = self
+            .context_max_window
+            .as_deref()
+            .map(|value| params::parse("context_max_window", value))
+            .transpose()?
+            .unwrap_or(pos_index::DEFAULT_MAX_WINDOW);
+        Ok(context.then_some(max_window))
+    }
 }
 
 impl From<SeqvarsAnnosQuery> for keys::Var {
@@ -60,211 +180,346 @@ impl From<SeqvarsAnnosQuery> for keys::Pos {
 }
 
 /// Result for `handle`.
+///
+/// The `result` is serialized through the same typed records as `/api/v1/seqvars/annos`
+/// rather than the raw protobuf JSON.  Compared to earlier versions, this implies the
+/// following changes on the wire:
+///
+/// - Fields of the nested records use `snake_case` rather than the protobuf JSON
+///   `lowerCamelCase` (e.g., `ref_allele` rather than `refAllele`).  Where feasible, the
+///   old names are accepted as serde aliases on deserialization.
+/// - gnomAD records are wrapped by their version next to their normalized popmax / grpmax,
+///   e.g., `{"gnomad4": {...}, "grpmax": {...}}`.
+/// - `ucsc_conservation` is a single record list rather than an array of record lists.
+/// - Databases that are not configured on the server are serialized as `null` rather than
+///   being omitted from `result`.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
-#[serde_with::skip_serializing_none]
-struct Container {
+pub struct AnnosVariantResponse {
     /// Version of the server code.
     pub server_version: String,
     /// The query parameters.
     pub query: SeqvarsAnnosQuery,
     /// Annotations for the variant from each database.
-    pub result: std::collections::BTreeMap<AnnoDb, Option<serde_json::Value>>,
+    pub result: SeqvarsAnnoResponseRecord,
+    /// Best available population frequency combined from the frequency databases in
+    /// `result`, see `freqs::computed`.
+    #[serde(default)]
+    pub computed_frequencies: ComputedFrequencies,
+    /// Regions of the custom region databases overlapping the reference allele, by database
+    /// name; databases without overlapping regions are omitted.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub regions: std::collections::BTreeMap<String, Vec<RegionsInterval>>,
+    /// Nearest genes and functional elements with `context=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<VariantContext>,
+    /// Warnings about the query, e.g., databases without records on the chromosome.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Result for `handle` with `presence_only=true`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantPresenceResponse {
+    /// Version of the server code.
+    pub server_version: String,
+    /// The query parameters.
+    pub query: SeqvarsAnnosQuery,
+    /// Presence of the variant in each database.
+    pub result: SeqvarsPresenceRecord,
+    /// Warnings about the query, e.g., the chromosome not being in the contig allowlist.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 /// Query for annotations for one variant.
+///
+/// With `presence_only=true`, only the presence of the variant in each database is returned
+/// as `AnnosVariantPresenceResponse`, which avoids decoding the records.  With
+/// `clinvar_details=true`, the ClinVar record list also has the submission-level details if
+/// the ClinVar database has been imported with them.  With `context=true`, the nearest genes
+/// and functional elements up- and downstream of the variant within `context_max_window`
+/// bases (default 1 Mb) are returned in `context`.
+#[utoipa::path(
+    get,
+    operation_id = "annosVariant",
+    params(
+        SeqvarsAnnosQuery,
+        ("presence_only" = Option<bool>, Query, description = "Whether to only return the presence in each database."),
+        ("clinvar_details" = Option<bool>, Query, description = "Whether to return the ClinVar submission-level details."),
+        ("context" = Option<bool>, Query, description = "Whether to return the nearest genes and functional elements."),
+        ("context_max_window" = Option<u32>, Query, description = "Maximal distance to search for the nearest genes and functional elements, default 1000000.")
+    ),
+    responses(
+        (status = 200, description = "Annotation for a single variant.", content(
+            ("application/json" = AnnosVariantResponse),
+            ("application/json" = AnnosVariantPresenceResponse)
+        )),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
 #[get("/annos/variant")]
 async fn handle(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
-    query: web::Query<SeqvarsAnnosQuery>,
-) -> actix_web::Result<Json<Container>, CustomError> {
-    let genome_release =
-        query
-            .clone()
-            .into_inner()
-            .genome_release
-            .parse()
-            .map_err(|e: strum::ParseError| {
-                CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
-            })?;
-
-    let mut annotations = std::collections::BTreeMap::default();
-    for anno_db in AnnoDb::iter() {
-        match anno_db {
-            AnnoDb::Other => (),
-            AnnoDb::Clinvar => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_var_protobuf_json::<
-                            crate::pbs::clinvar::minimal::ExtractedVcvRecordList,
-                        >(
-                            &db.data,
-                            anno_db.cf_name(),
-                            query.clone().into_inner().into(),
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::Cadd | AnnoDb::Dbnsfp | AnnoDb::Dbscsnv => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_var_tsv_json(
-                            &db.data,
-                            anno_db.cf_name(),
-                            query.clone().into_inner().into(),
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::Dbsnp => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_var_protobuf_json::<crate::dbsnp::pbs::Record>(
-                            &db.data,
-                            anno_db.cf_name(),
-                            query.clone().into_inner().into(),
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::Helixmtdb => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_var_protobuf_json::<crate::helixmtdb::pbs::Record>(
-                            &db.data,
-                            anno_db.cf_name(),
-                            query.clone().into_inner().into(),
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::GnomadMtdna => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        fetch_var_protobuf_json::<crate::pbs::gnomad::mtdna::Record>(
-                            &db.data,
-                            anno_db.cf_name(),
-                            query.clone().into_inner().into(),
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::GnomadExomes => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        let db_version = data.db_infos[genome_release][anno_db]
-                            .as_ref()
-                            .expect("must have db info here")
-                            .db_version
-                            .as_ref()
-                            .expect("gnomAD must have db version");
-
-                        if db_version.starts_with("2.") {
-                            fetch_var_protobuf_json::<crate::pbs::gnomad::gnomad2::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.clone().into_inner().into(),
-                            )
-                        } else if db_version.starts_with("4.") {
-                            fetch_var_protobuf_json::<crate::pbs::gnomad::gnomad4::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.clone().into_inner().into(),
-                            )
-                        } else {
-                            Err(CustomError::new(anyhow::anyhow!(
-                                "don't know how to handle gnomAD version {}",
-                                db_version
-                            )))
-                        }
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::GnomadGenomes => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        let db_version = data.db_infos[genome_release][anno_db]
-                            .as_ref()
-                            .expect("must have db info here")
-                            .db_version
-                            .as_ref()
-                            .expect("gnomAD must have db version");
-                        if db_version.starts_with("2.") {
-                            fetch_var_protobuf_json::<crate::pbs::gnomad::gnomad2::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.clone().into_inner().into(),
-                            )
-                        } else if db_version.starts_with("3.") {
-                            fetch_var_protobuf_json::<crate::pbs::gnomad::gnomad3::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.clone().into_inner().into(),
-                            )
-                        } else if db_version.starts_with("4.") {
-                            fetch_var_protobuf_json::<crate::pbs::gnomad::gnomad4::Record>(
-                                &db.data,
-                                anno_db.cf_name(),
-                                query.clone().into_inner().into(),
-                            )
-                        } else {
-                            Err(CustomError::new(anyhow::anyhow!(
-                                "don't know how to handle gnomAD version {}",
-                                db_version
-                            )))
-                        }
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
-            }
-            AnnoDb::UcscConservation => {
-                data.annos[genome_release][anno_db]
-                    .as_ref()
-                    .map(|db| {
-                        let start: keys::Pos = query.clone().into_inner().into();
-                        let start = keys::Pos {
-                            chrom: start.chrom,
-                            pos: start.pos - 2,
-                        };
-                        let stop = query.clone().into_inner().into();
-                        fetch_pos_protobuf_json::<crate::pbs::cons::RecordList>(
-                            &db.data,
-                            anno_db.cf_name(),
-                            start,
-                            stop,
-                        )
-                    })
-                    .transpose()?
-                    .map(|v| annotations.insert(anno_db, v));
+    query: web::Query<SeqvarsAnnosParams>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let presence_only = query.presence_only()?;
+    let clinvar_details = query.clinvar_details()?;
+    let context_max_window = query.context()?;
+    let query = query.validate()?;
+    // Cache hits skip all database access.
+    let cache_key = VariantCacheKey {
+        presence_only,
+        clinvar_details,
+        context_max_window,
+        ..VariantCacheKey::from(&query)
+    };
+    if let Some(body) = data
+        .variant_cache
+        .as_ref()
+        .and_then(|cache| cache.get(&cache_key))
+    {
+        return Ok(HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(body));
+    }
+
+    // Responses with degraded databases omitted are not cached.
+    let mut degraded = false;
+    let not_in_allowlist = data.contig_allowed(&query.chromosome).err();
+    let body = if presence_only {
+        let result = fetch_presence(&data, &query)?;
+        serde_json::to_string(&AnnosVariantPresenceResponse {
+            server_version: version().to_string(),
+            query,
+            result,
+            warnings: not_in_allowlist.iter().map(ToString::to_string).collect(),
+        })
+    } else {
+        let mut result = fetch_annos(&data, &query)?;
+        if clinvar_details {
+            if let Some(clinvar) = result.clinvar.as_mut() {
+                clinvar.details = fetch_clinvar_details(&data, &query)?;
             }
         }
+        let genome_release = parse_genome_release(&query.genome_release)?;
+        let regions = if not_in_allowlist.is_some() {
+            Default::default()
+        } else {
+            regions::query_overlapping(
+                &data.regions[genome_release],
+                &query.chromosome,
+                query.pos,
+                query.pos + (query.reference.len().max(1) as u32) - 1,
+            )?
+        };
+        let context = match context_max_window {
+            Some(max_window) if not_in_allowlist.is_none() => Some(context::query_context(
+                &data,
+                genome_release,
+                &query.chromosome,
+                query.pos,
+                max_window,
+            )?),
+            _ => None,
+        };
+        let warnings = if let Some(not_in_allowlist) = not_in_allowlist {
+            vec![not_in_allowlist.to_string()]
+        } else {
+            let degraded_warnings = data
+                .circuit_breakers
+                .warnings(genome_release, AnnoDb::iter());
+            degraded = !degraded_warnings.is_empty();
+            data.contig_not_in_databases(
+                genome_release,
+                &query.chromosome,
+                query.clinvar_release.as_deref(),
+            )?
+            .values()
+            .map(|not_in_database| not_in_database.to_string())
+            .chain(degraded_warnings)
+            .collect()
+        };
+        serde_json::to_string(&AnnosVariantResponse {
+            server_version: version().to_string(),
+            query,
+            computed_frequencies: result.computed_frequencies(),
+            result,
+            regions,
+            context,
+            warnings,
+        })
+    }
+    .map_err(|e| CustomError::new(anyhow::anyhow!("problem serializing response: {}", e)))?;
+    if let Some(cache) = data.variant_cache.as_ref().filter(|_| !degraded) {
+        cache.insert(cache_key, body.clone());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(body))
+}
+
+/// Request body for `handle_multi`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantMultiQuery {
+    /// The variants to query for, each with its own genome release.
+    pub variants: Vec<SeqvarsAnnosQuery>,
+    /// Whether to only return the presence in each database, see
+    /// `AnnosVariantMultiPresenceResponse`.
+    #[serde(default)]
+    pub presence_only: bool,
+}
+
+/// Annotations for one variant of `AnnosVariantMultiResponse`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantMultiEntry {
+    /// The query parameters.
+    pub query: SeqvarsAnnosQuery,
+    /// Annotations for the variant from each database.
+    pub result: SeqvarsAnnoResponseRecord,
+    /// Best available population frequency, see `AnnosVariantResponse`.
+    #[serde(default)]
+    pub computed_frequencies: ComputedFrequencies,
+}
+
+/// Result for `handle_multi`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantMultiResponse {
+    /// Version of the server code.
+    pub server_version: String,
+    /// Annotations keyed by the lowercase genome release, in request order per release.
+    pub results: std::collections::BTreeMap<String, Vec<AnnosVariantMultiEntry>>,
+}
+
+/// Presence for one variant of `AnnosVariantMultiPresenceResponse`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantMultiPresenceEntry {
+    /// The query parameters.
+    pub query: SeqvarsAnnosQuery,
+    /// Presence of the variant in each database.
+    pub result: SeqvarsPresenceRecord,
+}
+
+/// Result for `handle_multi` with `presence_only`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AnnosVariantMultiPresenceResponse {
+    /// Version of the server code.
+    pub server_version: String,
+    /// Presence keyed by the lowercase genome release, in request order per release.
+    pub results: std::collections::BTreeMap<String, Vec<AnnosVariantMultiPresenceEntry>>,
+}
+
+/// Query for annotations for variants on one or more genome releases.
+///
+/// This allows to fetch, e.g., the GRCh37 and GRCh38 annotations of a variant with one
+/// request.  With `presence_only`, only the presence of the variants in each database is
+/// returned as `AnnosVariantMultiPresenceResponse`.  Variants on contigs outside of the
+/// contig allowlist of the server get empty results.
+#[utoipa::path(
+    post,
+    operation_id = "annosVariantMulti",
+    request_body = AnnosVariantMultiQuery,
+    responses(
+        (status = 200, description = "Annotations keyed by genome release.", content(
+            ("application/json" = AnnosVariantMultiResponse),
+            ("application/json" = AnnosVariantMultiPresenceResponse)
+        )),
+        (status = 400, description = "Invalid genome release.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[post("/annos/variant")]
+async fn handle_multi(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    body: Json<AnnosVariantMultiQuery>,
+) -> actix_web::Result<
+    Either<Json<AnnosVariantMultiResponse>, Json<AnnosVariantMultiPresenceResponse>>,
+    CustomError,
+> {
+    let query = body.into_inner();
+    Ok(if query.presence_only {
+        Either::Right(Json(fetch_presence_multi(&data, query)?))
+    } else {
+        Either::Left(Json(fetch_annos_multi(&data, query)?))
+    })
+}
+
+/// Apply `fetch` to all variants of `query` and group the results by genome release.
+fn fetch_multi<R>(
+    query: AnnosVariantMultiQuery,
+    fetch: impl Fn(&SeqvarsAnnosQuery) -> Result<R, CustomError>,
+) -> Result<std::collections::BTreeMap<String, Vec<(SeqvarsAnnosQuery, R)>>, CustomError> {
+    let mut results = std::collections::BTreeMap::<String, Vec<_>>::new();
+    for query in query.variants {
+        let genome_release = parse_genome_release(&query.genome_release)?;
+        let result = fetch(&query)?;
+        results
+            .entry(genome_release.to_string())
+            .or_default()
+            .push((query, result));
     }
+    Ok(results)
+}
+
+/// Fetch the annotations for all variants of `query`, keyed by genome release.
+fn fetch_annos_multi(
+    data: &crate::server::run::WebServerData,
+    query: AnnosVariantMultiQuery,
+) -> Result<AnnosVariantMultiResponse, CustomError> {
+    let results = fetch_multi(query, |query| fetch_annos(data, query))?
+        .into_iter()
+        .map(|(genome_release, entries)| {
+            let entries = entries
+                .into_iter()
+                .map(|(query, result)| AnnosVariantMultiEntry {
+                    query,
+                    computed_frequencies: result.computed_frequencies(),
+                    result,
+                })
+                .collect();
+            (genome_release, entries)
+        })
+        .collect();
 
-    let result = Container {
+    Ok(AnnosVariantMultiResponse {
         server_version: version().to_string(),
-        query: query.into_inner(),
-        result: annotations,
-    };
+        results,
+    })
+}
+
+/// Fetch the presence of all variants of `query`, keyed by genome release.
+fn fetch_presence_multi(
+    data: &crate::server::run::WebServerData,
+    query: AnnosVariantMultiQuery,
+) -> Result<AnnosVariantMultiPresenceResponse, CustomError> {
+    let results = fetch_multi(query, |query| fetch_presence(data, query))?
+        .into_iter()
+        .map(|(genome_release, entries)| {
+            let entries = entries
+                .into_iter()
+                .map(|(query, result)| AnnosVariantMultiPresenceEntry { query, result })
+                .collect();
+            (genome_release, entries)
+        })
+        .collect();
 
-    Ok(Json(result))
+    Ok(AnnosVariantMultiPresenceResponse {
+        server_version: version().to_string(),
+        results,
+    })
 }
 
 /// `SeqvarsAnnosResponse` and related types.
 pub mod response {
-    use crate::{pbs, server::run::clinvar_data::ClinvarExtractedVcvRecord};
+    use crate::{
+        freqs::computed,
+        gnomad_nuclear::grpmax::Grpmax,
+        pbs,
+        server::run::clinvar_data::{ClinvarExtractedVcvRecord, ClinvarVcvDetails},
+    };
 
     /// Protocol buffer for `Vep.domains`
     #[derive(
@@ -1027,6 +1282,11 @@ pub mod response {
                 quality_info: value.quality_info.map(Into::into),
                 age_info: value.age_info.map(Into::into),
                 depth_info: value.depth_info.map(Into::into),
+                haplogroup_counts: value
+                    .haplogroup_counts
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
             })
         }
     }
@@ -2179,6 +2439,58 @@ pub mod response {
         Gnomad4(Gnomad4Record),
     }
 
+    impl GnomadRecord {
+        /// Return the overall allele counts of `cohort`, `None` for the global one.
+        ///
+        /// The gnomAD v4 exomes and genomes records have the joint counts as the `joint` cohort.
+        pub fn allele_counts(&self, cohort: Option<&str>) -> Option<computed::AlleleCounts> {
+            let (ac, an) = match self {
+                GnomadRecord::Gnomad2(record) => record
+                    .allele_counts
+                    .iter()
+                    .find(|counts| counts.cohort.as_deref() == cohort)?
+                    .by_sex
+                    .as_ref()?
+                    .overall
+                    .as_ref()
+                    .map(|overall| (overall.ac, overall.an))?,
+                GnomadRecord::Gnomad3(record) => record
+                    .allele_counts
+                    .iter()
+                    .find(|counts| counts.cohort.as_deref() == cohort)?
+                    .by_sex
+                    .as_ref()?
+                    .overall
+                    .as_ref()
+                    .map(|overall| (overall.ac, overall.an))?,
+                GnomadRecord::Gnomad4(record) => record
+                    .allele_counts
+                    .iter()
+                    .find(|counts| counts.cohort.as_deref() == cohort)?
+                    .by_sex
+                    .as_ref()?
+                    .overall
+                    .as_ref()
+                    .map(|overall| (overall.ac, overall.an))?,
+            };
+            Some(computed::AlleleCounts {
+                ac: ac.max(0) as u64,
+                an: an.max(0) as u64,
+            })
+        }
+    }
+
+    /// A gnomAD record with its normalized popmax / grpmax.
+    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct GnomadRecordWithGrpmax {
+        /// The gnomAD record.
+        #[serde(flatten)]
+        pub record: GnomadRecord,
+        /// Allele counts of the population with the maximum allele frequency, `None` if no
+        /// population has the alternate allele.
+        pub grpmax: Option<Grpmax>,
+    }
+
     /// Encapsulate VCF INFO fields related to quality.
     #[derive(
         Debug,
@@ -2393,6 +2705,48 @@ pub mod response {
         pub faf_hapmax_hom: Option<f32>,
     }
 
+    /// Allele counts and frequencies of one haplogroup.
+    #[derive(
+        Debug,
+        Default,
+        Clone,
+        PartialEq,
+        serde::Serialize,
+        serde::Deserialize,
+        utoipa::ToSchema,
+        utoipa::ToResponse,
+    )]
+    pub struct GnomadMtdnaHaplogroupCounts {
+        /// Name of the haplogroup, e.g., `H`.
+        pub haplogroup: String,
+        /// Overall allele number in the haplogroup.
+        pub an: i32,
+        /// Allele count in the haplogroup restricted to variants with a heteroplasmy level
+        /// >= 0.10 and < 0.95.
+        pub ac_het: i32,
+        /// Allele count in the haplogroup restricted to variants with a heteroplasmy level >= 0.95.
+        pub ac_hom: i32,
+        /// Allele frequency in the haplogroup restricted to variants with a heteroplasmy level
+        /// >= 0.10 and < 0.95.
+        pub af_het: f32,
+        /// Allele frequency in the haplogroup restricted to variants with a heteroplasmy level
+        /// >= 0.95.
+        pub af_hom: f32,
+    }
+
+    impl From<pbs::gnomad::mtdna::HaplogroupCounts> for GnomadMtdnaHaplogroupCounts {
+        fn from(value: pbs::gnomad::mtdna::HaplogroupCounts) -> Self {
+            GnomadMtdnaHaplogroupCounts {
+                haplogroup: value.haplogroup,
+                an: value.an,
+                ac_het: value.ac_het,
+                ac_hom: value.ac_hom,
+                af_het: value.af_het,
+                af_hom: value.af_hom,
+            }
+        }
+    }
+
     impl From<pbs::gnomad::mtdna::HaplogroupInfo> for GnomadMtdnaHaplogroupInfo {
         fn from(value: pbs::gnomad::mtdna::HaplogroupInfo) -> Self {
             GnomadMtdnaHaplogroupInfo {
@@ -2552,6 +2906,9 @@ pub mod response {
         pub age_info: Option<GnomadMtdnaAgeInfo>,
         /// Depth of coverage-related information.
         pub depth_info: Option<GnomadMtdnaDepthInfo>,
+        /// Counts for each haplogroup, only the requested one with `haplogroup=`.
+        #[serde(default)]
+        pub haplogroup_counts: Vec<GnomadMtdnaHaplogroupCounts>,
     }
 
     impl TryFrom<pbs::gnomad::mtdna::Record> for GnomadMtdnaRecord {
@@ -2647,10 +3004,13 @@ pub mod response {
         /// 1-based start position.
         pub pos: i32,
         /// Reference allele.
+        #[serde(alias = "refAllele")]
         pub ref_allele: String,
         /// Alternate allele.
+        #[serde(alias = "altAllele")]
         pub alt_allele: String,
         /// The rs ID.
+        #[serde(alias = "rsId")]
         pub rs_id: i32,
     }
 
@@ -2674,18 +3034,25 @@ pub mod response {
         /// 1-based start position.
         pub pos: i32,
         /// Reference allele.
+        #[serde(alias = "refAllele")]
         pub ref_allele: String,
         /// / Alternate allele.
+        #[serde(alias = "altAllele")]
         pub alt_allele: String,
         /// Total number of individuals.
+        #[serde(alias = "numTotal")]
         pub num_total: i32,
         /// Number of homoplasmic carriers.
+        #[serde(alias = "numHet")]
         pub num_het: i32,
         /// Number of heteroplasmic carriers.
+        #[serde(alias = "numHom")]
         pub num_hom: i32,
         /// Feature type.
+        #[serde(alias = "featureType")]
         pub feature_type: String,
         /// Gene name.
+        #[serde(alias = "geneName")]
         pub gene_name: String,
     }
 
@@ -2705,6 +3072,118 @@ pub mod response {
         }
     }
 
+    /// AlphaMissense pathogenicity class.
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        serde::Serialize,
+        serde::Deserialize,
+        strum::Display,
+        strum::EnumString,
+        utoipa::ToSchema,
+    )]
+    #[serde(rename_all = "snake_case")]
+    #[strum(serialize_all = "snake_case")]
+    pub enum AlphaMissenseClass {
+        /// Likely benign.
+        LikelyBenign,
+        /// Ambiguous.
+        Ambiguous,
+        /// Likely pathogenic.
+        LikelyPathogenic,
+    }
+
+    impl TryFrom<pbs::alphamissense::AmClass> for AlphaMissenseClass {
+        type Error = anyhow::Error;
+
+        fn try_from(value: pbs::alphamissense::AmClass) -> Result<Self, Self::Error> {
+            Ok(match value {
+                pbs::alphamissense::AmClass::LikelyBenign => AlphaMissenseClass::LikelyBenign,
+                pbs::alphamissense::AmClass::Ambiguous => AlphaMissenseClass::Ambiguous,
+                pbs::alphamissense::AmClass::LikelyPathogenic => {
+                    AlphaMissenseClass::LikelyPathogenic
+                }
+                _ => anyhow::bail!("unknown alphamissense::AmClass: {:?}", value),
+            })
+        }
+    }
+
+    /// An AlphaMissense record for one transcript.
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct AlphaMissenseRecord {
+        /// Chromosome name.
+        pub chrom: String,
+        /// 1-based start position.
+        pub pos: i32,
+        /// Reference allele.
+        #[serde(alias = "refAllele")]
+        pub ref_allele: String,
+        /// Alternate allele.
+        #[serde(alias = "altAllele")]
+        pub alt_allele: String,
+        /// UniProt accession.
+        #[serde(alias = "uniprotId")]
+        pub uniprot_id: String,
+        /// Ensembl transcript identifier.
+        #[serde(alias = "transcriptId")]
+        pub transcript_id: String,
+        /// Protein change, e.g., `V2L`.
+        #[serde(alias = "proteinVariant")]
+        pub protein_variant: String,
+        /// AlphaMissense pathogenicity score.
+        #[serde(alias = "amPathogenicity")]
+        pub am_pathogenicity: f32,
+        /// AlphaMissense classification.
+        #[serde(alias = "amClass")]
+        pub am_class: AlphaMissenseClass,
+    }
+
+    impl TryFrom<crate::pbs::alphamissense::Record> for AlphaMissenseRecord {
+        type Error = anyhow::Error;
+
+        fn try_from(value: crate::pbs::alphamissense::Record) -> Result<Self, Self::Error> {
+            let am_class = value.am_class().try_into()?;
+            Ok(AlphaMissenseRecord {
+                chrom: value.chrom,
+                pos: value.pos,
+                ref_allele: value.ref_allele,
+                alt_allele: value.alt_allele,
+                uniprot_id: value.uniprot_id,
+                transcript_id: value.transcript_id,
+                protein_variant: value.protein_variant,
+                am_pathogenicity: value.am_pathogenicity,
+                am_class,
+            })
+        }
+    }
+
+    /// List of `AlphaMissenseRecord`s, one per transcript.
+    #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct AlphaMissenseRecordList {
+        /// The records in the list.
+        pub records: Vec<AlphaMissenseRecord>,
+    }
+
+    impl TryFrom<crate::pbs::alphamissense::RecordList> for AlphaMissenseRecordList {
+        type Error = anyhow::Error;
+
+        fn try_from(value: crate::pbs::alphamissense::RecordList) -> Result<Self, Self::Error> {
+            Ok(AlphaMissenseRecordList {
+                records: value
+                    .records
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+    }
+
     /// A UCSC conservation record.
     #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct UcscConservationRecord {
@@ -2715,12 +3194,16 @@ pub mod response {
         /// 1-based, inclusive stop position.
         pub stop: i32,
         /// HGNC identifier.
+        #[serde(alias = "hgncId")]
         pub hgnc_id: String,
         /// ENST identifier.
+        #[serde(alias = "enstId")]
         pub enst_id: String,
         /// Exon number (1-based).
+        #[serde(alias = "exonNum")]
         pub exon_num: i32,
         /// Exon count.
+        #[serde(alias = "exonCount")]
         pub exon_count: i32,
         /// Alignment.
         pub alignment: String,
@@ -2746,20 +3229,49 @@ pub mod response {
     pub struct UcscConservationRecordList {
         /// The records in the list.
         pub records: Vec<UcscConservationRecord>,
+        /// The per-base score at the position for databases imported with
+        /// `--input-format per-base`, which have no records.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub score: Option<f32>,
     }
 
     impl From<crate::pbs::cons::RecordList> for UcscConservationRecordList {
         fn from(value: crate::pbs::cons::RecordList) -> Self {
             UcscConservationRecordList {
                 records: value.records.into_iter().map(Into::into).collect(),
+                score: None,
+            }
+        }
+    }
+    /// The gnomAD coverage at the position of a variant.
+    #[derive(
+        Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+    )]
+    pub struct GnomadCoverageRecord {
+        /// Mean depth of coverage.
+        pub mean: f32,
+        /// Fraction of samples with a depth of coverage over 20x.
+        pub fraction_over_20x: f32,
+    }
+
+    impl From<crate::gnomad_coverage::Record> for GnomadCoverageRecord {
+        fn from(value: crate::gnomad_coverage::Record) -> Self {
+            GnomadCoverageRecord {
+                mean: value.mean,
+                fraction_over_20x: value.fraction_over_20x,
             }
         }
     }
+
     /// List of `ClinvarExtractedVcvRecord`s.
     #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct ExtractedVcvRecordList {
         /// The list of VCV records that may share a global variant.
         pub records: Vec<ClinvarExtractedVcvRecord>,
+        /// Submission-level details of the VCV records with `clinvar_details=true`; empty
+        /// for databases imported without `--with-details`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub details: Vec<ClinvarVcvDetails>,
     }
 
     impl TryFrom<crate::pbs::clinvar::minimal::ExtractedVcvRecordList> for ExtractedVcvRecordList {
@@ -2774,15 +3286,38 @@ pub mod response {
                     .into_iter()
                     .map(TryInto::try_into)
                     .collect::<Result<_, _>>()?,
+                details: Vec::new(),
             })
         }
     }
 
+    /// CADD scores of a variant.
+    #[derive(
+        Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+    )]
+    pub struct CaddScores {
+        /// Raw CADD score.
+        pub raw: f32,
+        /// PHRED-scaled CADD score.
+        pub phred: f32,
+    }
+
+    /// Annotation from CADD, typed if the score columns are known (see `server::run::cadd`).
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    #[serde(untagged)]
+    pub enum CaddRecord {
+        /// The raw and PHRED scores.
+        Scores(CaddScores),
+        /// All columns of the TSV annotation file.
+        Columns(indexmap::IndexMap<String, serde_json::Value>),
+    }
+
     /// Annotation for a sinngle variant.
     #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct SeqvarsAnnoResponseRecord {
-        /// Annotations from CADD (TSV annotation file).
-        pub cadd: Option<indexmap::IndexMap<String, serde_json::Value>>,
+        /// Annotations from CADD, the scores if the typed view is enabled and all columns
+        /// of the TSV annotation file otherwise.
+        pub cadd: Option<CaddRecord>,
         /// Annotations from dbSNP.
         pub dbsnp: Option<DbsnpRecord>,
         /// Annotations from dbNSFP (TSV annotation file).
@@ -2792,17 +3327,55 @@ pub mod response {
         /// Annotations from gnomAD-mtDNA.
         pub gnomad_mtdna: Option<GnomadMtdnaRecord>,
         /// Annotations from gnomAD-exomes.
-        pub gnomad_exomes: Option<GnomadRecord>,
+        pub gnomad_exomes: Option<GnomadRecordWithGrpmax>,
         /// Annotations from gnomAD-genomes.
-        pub gnomad_genomes: Option<GnomadRecord>,
+        pub gnomad_genomes: Option<GnomadRecordWithGrpmax>,
+        /// Annotations from each gnomAD-exomes and gnomAD-genomes database by version if
+        /// there is more than one database of the kind, e.g., `gnomad_exomes_2.1_liftover`
+        /// and `gnomad_exomes_4.0`.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        pub gnomad_versions: std::collections::BTreeMap<String, Option<GnomadRecordWithGrpmax>>,
+        /// gnomAD coverage at the position, to tell variants absent from gnomAD at covered
+        /// positions from those at positions without coverage.
+        pub gnomad_coverage: Option<GnomadCoverageRecord>,
         /// Annotations from HelixMTdb.
         pub helixmtdb: Option<HelixMtDbRecord>,
+        /// Annotations from AlphaMissense.
+        pub alphamissense: Option<AlphaMissenseRecordList>,
         /// Annotations from UCSC conservation.
         pub ucsc_conservation: Option<UcscConservationRecordList>,
         /// Minimal extracted data from ClinVar.
         pub clinvar: Option<ExtractedVcvRecordList>,
     }
 
+    impl SeqvarsAnnoResponseRecord {
+        /// Return the best available population frequency from the gnomAD, gnomAD-mtDNA, and
+        /// HelixMTdb records, see `freqs::computed`.
+        pub fn computed_frequencies(&self) -> computed::ComputedFrequencies {
+            let gnomad = |record: &Option<GnomadRecordWithGrpmax>, cohort: Option<&str>| {
+                record
+                    .as_ref()
+                    .and_then(|record| record.record.allele_counts(cohort))
+            };
+            computed::compute(&computed::Input {
+                gnomad_joint: gnomad(&self.gnomad_exomes, Some("joint"))
+                    .or_else(|| gnomad(&self.gnomad_genomes, Some("joint"))),
+                gnomad_exomes: gnomad(&self.gnomad_exomes, None),
+                gnomad_genomes: gnomad(&self.gnomad_genomes, None),
+                gnomad_mtdna: self.gnomad_mtdna.as_ref().map(|record| computed::MtCounts {
+                    an: record.an.max(0) as u64,
+                    ac_hom: record.ac_hom.max(0) as u64,
+                    ac_het: record.ac_het.max(0) as u64,
+                }),
+                helixmtdb: self.helixmtdb.as_ref().map(|record| computed::MtCounts {
+                    an: record.num_total.max(0) as u64,
+                    ac_hom: record.num_hom.max(0) as u64,
+                    ac_het: record.num_het.max(0) as u64,
+                }),
+            })
+        }
+    }
+
     /// Query response for `handle_with_openapi()`.
     #[derive(
         Debug,
@@ -2821,227 +3394,213 @@ pub mod response {
 
 use response::*;
 
-/// Query for annotations for a single variant.
-#[utoipa::path(
-    get,
-    operation_id = "seqvarsAnnosQuery",
-    params(SeqvarsAnnosQuery),
-    responses(
-        (status = 200, description = "Annotation for a single variant.", body = SeqvarsAnnosResponse),
-        (status = 500, description = "Internal server error.", body = CustomError)
-    )
-)]
-#[get("/api/v1/seqvars/annos")]
-pub async fn handle_with_openapi(
-    data: Data<crate::server::run::WebServerData>,
-    _path: Path<()>,
-    query: web::Query<SeqvarsAnnosQuery>,
-) -> actix_web::Result<Json<SeqvarsAnnosResponse>, CustomError> {
-    let genome_release = query
-        .genome_release
-        .parse()
-        .map_err(|e: strum::ParseError| {
-            CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
-        })?;
-
-    fn json_value_to_indexmap(
-        value: serde_json::Value,
-    ) -> Result<indexmap::IndexMap<String, serde_json::Value>, CustomError> {
-        value
-            .as_object()
-            .map(|v| {
-                Ok(v.iter()
-                    .map(|(k, v)| (k.clone(), v.clone()))
-                    .collect::<indexmap::IndexMap<_, _>>())
-            })
-            .unwrap_or_else(|| Err(CustomError::new(anyhow::anyhow!("expected object"))))
-    }
+/// Convert a JSON object into an `IndexMap`.
+fn json_value_to_indexmap(
+    value: serde_json::Value,
+) -> Result<indexmap::IndexMap<String, serde_json::Value>, CustomError> {
+    value
+        .as_object()
+        .map(|v| {
+            Ok(v.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<indexmap::IndexMap<_, _>>())
+        })
+        .unwrap_or_else(|| Err(CustomError::new(anyhow::anyhow!("expected object"))))
+}
+
+/// Parse the genome release of a single-variant query.
+///
+/// Lists of releases such as `grch37,grch38` are rejected with a pointer to the `POST`
+/// form of `/annos/variant`.
+fn parse_genome_release(genome_release: &str) -> Result<GenomeRelease, CustomError> {
+    if genome_release.contains(',') {
+        return Err(CustomError::invalid_param(
+            "genome_release",
+            Some(genome_release),
+            "multiple genome releases are not supported in GET requests, use \
+            POST /annos/variant with one variant per genome release instead",
+        ));
+    }
+    params::genome_release("genome_release", genome_release)
+}
+
+/// Parse the genome release of `query` and reject invalid alleles, shared by all endpoints.
+///
+/// IUPAC codes are accepted as databases may have been imported with `--allow-iupac`.
+pub(crate) fn prepare_query(
+    query: &SeqvarsAnnosQuery,
+) -> Result<(GenomeRelease, SeqvarsAnnosQuery), CustomError> {
+    let genome_release = parse_genome_release(&query.genome_release)?;
+    params::position_in_contig("pos", query.pos, genome_release, &query.chromosome)?;
+    let query = SeqvarsAnnosQuery {
+        reference: keys::normalize_allele(&query.reference, true).map_err(CustomError::new)?,
+        alternative: keys::normalize_allele(&query.alternative, true).map_err(CustomError::new)?,
+        ..query.clone()
+    };
+    Ok((genome_release, query))
+}
+
+/// Return the variant of `query` for the database described by `db_info`.
+///
+/// The alleles are trimmed for databases with normalized keys, see `common::norm`.
+pub(crate) fn db_var(query: &SeqvarsAnnosQuery, db_info: Option<&DbInfo>) -> keys::Var {
+    let var: keys::Var = query.clone().into();
+    let keys_normalized = db_info
+        .map(|db_info| db_info.keys_normalized)
+        .unwrap_or_default();
+    if keys_normalized {
+        var.trimmed()
+    } else {
+        var
+    }
+}
+
+/// Fetch the annotations for one variant from all databases, shared by all endpoints.
+///
+/// Variants on contigs outside of the contig allowlist get empty annotations.
+pub fn fetch_annos(
+    data: &crate::server::run::WebServerData,
+    query: &SeqvarsAnnosQuery,
+) -> Result<SeqvarsAnnoResponseRecord, CustomError> {
+    let (genome_release, query) = prepare_query(query)?;
+    if data.contig_allowed(&query.chromosome).is_err() {
+        return Ok(SeqvarsAnnoResponseRecord::default());
+    }
+    let query = &query;
+    let var_for = |db_info: Option<&DbInfo>| -> keys::Var { db_var(query, db_info) };
+    let var =
+        |anno_db: AnnoDb| -> keys::Var { var_for(data.db_infos[genome_release][anno_db].as_ref()) };
 
     let result = SeqvarsAnnoResponseRecord {
-        cadd: data.annos[genome_release][AnnoDb::Cadd]
-            .as_ref()
-            .map(|db| {
-                fetch_var_tsv_json(
+        cadd: data
+            .guarded(genome_release, AnnoDb::Cadd, |db| {
+                fetch_cadd(
                     &db.data,
-                    AnnoDb::Cadd.cf_name(),
-                    query.clone().into_inner().into(),
+                    data.cadd_columns[genome_release],
+                    var(AnnoDb::Cadd),
                 )
-            })
-            .transpose()?
-            .flatten()
-            .map(json_value_to_indexmap)
-            .transpose()?,
-        dbsnp: data.annos[genome_release][AnnoDb::Dbsnp]
-            .as_ref()
-            .map(|db| {
+            })?
+            .flatten(),
+        dbsnp: data
+            .guarded(genome_release, AnnoDb::Dbsnp, |db| {
                 fetch_var_protobuf::<crate::dbsnp::pbs::Record>(
                     &db.data,
                     AnnoDb::Dbsnp.cf_name(),
-                    query.clone().into_inner().into(),
+                    var(AnnoDb::Dbsnp),
                 )
-            })
-            .transpose()?
+            })?
             .flatten()
             .map(Into::into),
-        dbnsfp: data.annos[genome_release][AnnoDb::Dbnsfp]
-            .as_ref()
-            .map(|db| {
-                fetch_var_tsv_json(
-                    &db.data,
-                    AnnoDb::Dbnsfp.cf_name(),
-                    query.clone().into_inner().into(),
-                )
-            })
-            .transpose()?
+        dbnsfp: data
+            .guarded(genome_release, AnnoDb::Dbnsfp, |db| {
+                fetch_var_tsv_json(&db.data, AnnoDb::Dbnsfp.cf_name(), var(AnnoDb::Dbnsfp))
+            })?
             .flatten()
             .map(json_value_to_indexmap)
             .transpose()?,
-        dbscsnv: data.annos[genome_release][AnnoDb::Dbscsnv]
-            .as_ref()
-            .map(|db| {
-                fetch_var_tsv_json(
-                    &db.data,
-                    AnnoDb::Dbscsnv.cf_name(),
-                    query.clone().into_inner().into(),
-                )
-            })
-            .transpose()?
+        dbscsnv: data
+            .guarded(genome_release, AnnoDb::Dbscsnv, |db| {
+                fetch_var_tsv_json(&db.data, AnnoDb::Dbscsnv.cf_name(), var(AnnoDb::Dbscsnv))
+            })?
             .flatten()
             .map(json_value_to_indexmap)
             .transpose()?,
-        gnomad_mtdna: data.annos[genome_release][AnnoDb::GnomadMtdna]
-            .as_ref()
-            .map(|db| {
+        gnomad_mtdna: data
+            .guarded(genome_release, AnnoDb::GnomadMtdna, |db| {
                 fetch_var_protobuf::<crate::pbs::gnomad::mtdna::Record>(
                     &db.data,
                     AnnoDb::GnomadMtdna.cf_name(),
-                    query.clone().into_inner().into(),
+                    var(AnnoDb::GnomadMtdna),
                 )?
+                .map(|mut record| {
+                    if let Some(haplogroup) = query.haplogroup.as_deref() {
+                        record.retain_haplogroup(haplogroup);
+                    }
+                    record
+                })
                 .map(TryInto::<GnomadMtdnaRecord>::try_into)
                 .transpose()
                 .map_err(CustomError::new)
-            })
-            .transpose()?
+            })?
             .flatten()
             .map(Into::into),
-        gnomad_exomes: data.annos[genome_release][AnnoDb::GnomadExomes]
-            .as_ref()
-            .map(|db| {
-                let db_version = data.db_infos[genome_release][AnnoDb::GnomadExomes]
-                    .as_ref()
-                    .expect("must have db info here")
-                    .db_version
+        gnomad_exomes: data
+            .guarded(genome_release, AnnoDb::GnomadExomes, |db| {
+                let db_info = data.db_infos[genome_release][AnnoDb::GnomadExomes]
                     .as_ref()
-                    .expect("gnomAD must have db version");
-                if db_version.starts_with("2.") {
-                    Ok(fetch_var_protobuf::<crate::pbs::gnomad::gnomad2::Record>(
-                        &db.data,
-                        AnnoDb::GnomadExomes.cf_name(),
-                        query.clone().into_inner().into(),
-                    )?
-                    .map(|record| {
-                        Ok(GnomadRecord::Gnomad2(
-                            record.try_into().map_err(CustomError::new)?,
-                        ))
-                    })
-                    .transpose()?)
-                } else if db_version.starts_with("3.") {
-                    Ok(fetch_var_protobuf::<crate::pbs::gnomad::gnomad3::Record>(
-                        &db.data,
-                        AnnoDb::GnomadExomes.cf_name(),
-                        query.clone().into_inner().into(),
-                    )?
-                    .map(|record| {
-                        Ok(GnomadRecord::Gnomad3(
-                            record.try_into().map_err(CustomError::new)?,
-                        ))
-                    })
-                    .transpose()?)
-                } else if db_version.starts_with("4.") {
-                    Ok(fetch_var_protobuf::<crate::pbs::gnomad::gnomad4::Record>(
-                        &db.data,
-                        AnnoDb::GnomadExomes.cf_name(),
-                        query.clone().into_inner().into(),
-                    )?
-                    .map(|record| {
-                        Ok(GnomadRecord::Gnomad4(
-                            record.try_into().map_err(CustomError::new)?,
-                        ))
-                    })
-                    .transpose()?)
-                } else {
-                    Err(CustomError::new(anyhow::anyhow!(
-                        "don't know how to handle gnomAD version {}",
-                        db_version
-                    )))
-                }
-            })
-            .transpose()?
+                    .expect("must have db info here");
+                fetch_gnomad(&db.data, db_info, var_for(Some(db_info)))
+            })?
             .flatten(),
-        gnomad_genomes: data.annos[genome_release][AnnoDb::GnomadGenomes]
-            .as_ref()
-            .map(|db| {
-                let db_version = data.db_infos[genome_release][AnnoDb::GnomadGenomes]
-                    .as_ref()
-                    .expect("must have db info here")
-                    .db_version
+        gnomad_genomes: data
+            .guarded(genome_release, AnnoDb::GnomadGenomes, |db| {
+                let db_info = data.db_infos[genome_release][AnnoDb::GnomadGenomes]
                     .as_ref()
-                    .expect("gnomAD must have db version");
-
-                if db_version.starts_with("2.") {
-                    Ok(fetch_var_protobuf::<crate::pbs::gnomad::gnomad2::Record>(
-                        &db.data,
-                        AnnoDb::GnomadGenomes.cf_name(),
-                        query.clone().into_inner().into(),
-                    )?
-                    .map(|record| {
-                        Ok(GnomadRecord::Gnomad2(
-                            record.try_into().map_err(CustomError::new)?,
-                        ))
-                    })
-                    .transpose()?)
-                } else if db_version.starts_with("4.") {
-                    Ok(fetch_var_protobuf::<crate::pbs::gnomad::gnomad4::Record>(
-                        &db.data,
-                        AnnoDb::GnomadGenomes.cf_name(),
-                        query.clone().into_inner().into(),
-                    )?
-                    .map(|record| {
-                        Ok(GnomadRecord::Gnomad4(
-                            record.try_into().map_err(CustomError::new)?,
-                        ))
-                    })
-                    .transpose()?)
-                } else {
-                    Err(CustomError::new(anyhow::anyhow!(
-                        "don't know how to handle gnomAD version {}",
-                        db_version
-                    )))
-                }
-            })
-            .transpose()?
+                    .expect("must have db info here");
+                fetch_gnomad(&db.data, db_info, var_for(Some(db_info)))
+            })?
             .flatten(),
-        helixmtdb: data.annos[genome_release][AnnoDb::Helixmtdb]
-            .as_ref()
-            .map(|db| {
+        gnomad_coverage: data
+            .guarded(genome_release, AnnoDb::GnomadCoverage, |db| {
+                let pos: keys::Pos = query.clone().into();
+                Ok(crate::gnomad_coverage::fetch_range(
+                    &db.data,
+                    AnnoDb::GnomadCoverage.cf_name(),
+                    pos.clone(),
+                    pos,
+                )
+                .map_err(CustomError::new)?
+                .into_iter()
+                .next()
+                .map(Into::into))
+            })?
+            .flatten(),
+        helixmtdb: data
+            .guarded(genome_release, AnnoDb::Helixmtdb, |db| {
                 Ok(fetch_var_protobuf::<crate::pbs::helixmtdb::Record>(
                     &db.data,
                     AnnoDb::Helixmtdb.cf_name(),
-                    query.clone().into_inner().into(),
+                    var(AnnoDb::Helixmtdb),
                 )?
                 .map(Into::into))
-            })
-            .transpose()?
+            })?
             .flatten(),
-        ucsc_conservation: data.annos[genome_release][AnnoDb::UcscConservation]
-            .as_ref()
-            .map(|db| {
-                let start: keys::Pos = query.clone().into_inner().into();
+        alphamissense: data
+            .guarded(genome_release, AnnoDb::Alphamissense, |db| {
+                fetch_var_protobuf::<crate::pbs::alphamissense::RecordList>(
+                    &db.data,
+                    AnnoDb::Alphamissense.cf_name(),
+                    var(AnnoDb::Alphamissense),
+                )?
+                .map(TryInto::<AlphaMissenseRecordList>::try_into)
+                .transpose()
+                .map_err(CustomError::new)
+            })?
+            .flatten(),
+        ucsc_conservation: data
+            .guarded(genome_release, AnnoDb::UcscConservation, |db| {
+                if crate::cons::per_base::is_per_base(&db.data) {
+                    let pos: keys::Pos = query.clone().into();
+                    return Ok(crate::cons::per_base::fetch_range(
+                        &db.data,
+                        crate::cons::per_base::CF_NAME,
+                        pos.clone(),
+                        pos,
+                    )
+                    .map_err(CustomError::new)?
+                    .into_iter()
+                    .next()
+                    .map(|record| UcscConservationRecordList {
+                        records: Vec::new(),
+                        score: Some(record.score),
+                    }));
+                }
+                let start: keys::Pos = query.clone().into();
                 let start = keys::Pos {
                     chrom: start.chrom,
                     pos: start.pos - 2,
                 };
-                let stop = query.clone().into_inner().into();
+                let stop = query.clone().into();
                 Ok(fetch_pos_protobuf::<crate::pbs::cons::RecordList>(
                     &db.data,
                     AnnoDb::UcscConservation.cf_name(),
@@ -3051,24 +3610,1434 @@ pub async fn handle_with_openapi(
                 .into_iter()
                 .next()
                 .map(Into::into))
-            })
-            .transpose()?
+            })?
             .flatten(),
-        clinvar: data.annos[genome_release][AnnoDb::Clinvar]
-            .as_ref()
-            .map(|db| {
-                fetch_var_protobuf::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
-                    &db.data,
-                    AnnoDb::Clinvar.cf_name(),
-                    query.clone().into_inner().into(),
-                )?
-                .map(TryInto::<ExtractedVcvRecordList>::try_into)
-                .transpose()
-                .map_err(CustomError::new)
+        clinvar: match data.clinvar_db(genome_release, query.clinvar_release.as_deref())? {
+            Some((db_info, db)) => data
+                .with_breaker(genome_release, AnnoDb::Clinvar, &db.data, || {
+                    fetch_var_protobuf::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
+                        &db.data,
+                        AnnoDb::Clinvar.cf_name(),
+                        var_for(Some(db_info)),
+                    )?
+                    .map(TryInto::<ExtractedVcvRecordList>::try_into)
+                    .transpose()
+                    .map_err(CustomError::new)
+                })?
+                .flatten(),
+            None => None,
+        },
+        gnomad_versions: [AnnoDb::GnomadExomes, AnnoDb::GnomadGenomes]
+            .into_iter()
+            .flat_map(|anno_db| data.versioned_annos(genome_release, anno_db))
+            .map(|(versioned_key, db_info, db)| {
+                Ok((
+                    versioned_key,
+                    fetch_gnomad(&db.data, db_info, var_for(Some(db_info)))?,
+                ))
             })
-            .transpose()?
-            .flatten(),
+            .collect::<Result<_, CustomError>>()?,
+    };
+
+    Ok(result)
+}
+
+/// Fetch the ClinVar submission-level details for one variant.
+///
+/// Returns no details for ClinVar databases imported without `--with-details`.
+fn fetch_clinvar_details(
+    data: &crate::server::run::WebServerData,
+    query: &SeqvarsAnnosQuery,
+) -> Result<Vec<ClinvarVcvDetails>, CustomError> {
+    let (genome_release, query) = prepare_query(query)?;
+    let Some((db_info, db)) = data.clinvar_db(genome_release, query.clinvar_release.as_deref())?
+    else {
+        return Ok(Vec::new());
     };
+    if db
+        .data
+        .cf_handle(crate::clinvar_minimal::DETAILS_CF_NAME)
+        .is_none()
+    {
+        return Ok(Vec::new());
+    }
+    Ok(
+        fetch_var_protobuf::<crate::pbs::clinvar::minimal::VcvDetailsList>(
+            &db.data,
+            crate::clinvar_minimal::DETAILS_CF_NAME,
+            db_var(&query, Some(db_info)),
+        )?
+        .map(|details| {
+            details
+                .records
+                .into_iter()
+                .map(ClinvarVcvDetails::try_from)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(CustomError::new)?
+        .unwrap_or_default(),
+    )
+}
+
+/// Fetch the record of CADD database `db` for `var`, typed if `columns` is given.
+fn fetch_cadd(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    columns: Option<CaddColumns>,
+    var: keys::Var,
+) -> Result<Option<CaddRecord>, CustomError> {
+    let cf_name = AnnoDb::Cadd.cf_name();
+    match columns {
+        Some(columns) => Ok(fetch_var_cadd(db, cf_name, &columns, var)?.map(CaddRecord::Scores)),
+        None => fetch_var_tsv_json(db, cf_name, var)?
+            .map(|value| Ok(CaddRecord::Columns(json_value_to_indexmap(value)?)))
+            .transpose(),
+    }
+}
+
+/// Fetch the record of gnomAD exomes or genomes database `db` for `var`.
+fn fetch_gnomad(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    db_info: &DbInfo,
+    var: keys::Var,
+) -> Result<Option<GnomadRecordWithGrpmax>, CustomError> {
+    let cf_name = db_info.name.cf_name();
+    let db_version = db_info
+        .db_version
+        .as_ref()
+        .expect("gnomAD must have db version");
+    if db_version.starts_with("2.") {
+        fetch_var_protobuf::<crate::pbs::gnomad::gnomad2::Record>(db, cf_name, var)?
+            .map(|record| {
+                Ok(GnomadRecordWithGrpmax {
+                    grpmax: grpmax::from_gnomad2(&record),
+                    record: GnomadRecord::Gnomad2(record.try_into().map_err(CustomError::new)?),
+                })
+            })
+            .transpose()
+    } else if db_version.starts_with("3.") {
+        fetch_var_protobuf::<crate::pbs::gnomad::gnomad3::Record>(db, cf_name, var)?
+            .map(|record| {
+                Ok(GnomadRecordWithGrpmax {
+                    grpmax: grpmax::from_gnomad3(&record),
+                    record: GnomadRecord::Gnomad3(record.try_into().map_err(CustomError::new)?),
+                })
+            })
+            .transpose()
+    } else if db_version.starts_with("4.") {
+        fetch_var_protobuf::<crate::pbs::gnomad::gnomad4::Record>(db, cf_name, var)?
+            .map(|record| {
+                Ok(GnomadRecordWithGrpmax {
+                    grpmax: grpmax::from_gnomad4(&record),
+                    record: GnomadRecord::Gnomad4(record.try_into().map_err(CustomError::new)?),
+                })
+            })
+            .transpose()
+    } else {
+        Err(CustomError::new(anyhow::anyhow!(
+            "don't know how to handle gnomAD version {}",
+            db_version
+        )))
+    }
+}
+
+/// Query for annotations for a single variant.
+#[utoipa::path(
+    get,
+    operation_id = "seqvarsAnnosQuery",
+    params(SeqvarsAnnosQuery),
+    responses(
+        (status = 200, description = "Annotation for a single variant.", body = SeqvarsAnnosResponse),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/seqvars/annos")]
+pub async fn handle_with_openapi(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<SeqvarsAnnosParams>,
+) -> actix_web::Result<Json<SeqvarsAnnosResponse>, CustomError> {
+    let result = fetch_annos(&data, &query.validate()?)?;
 
     Ok(Json(SeqvarsAnnosResponse { result }))
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::{
+        common::cli::GenomeRelease,
+        server::run::{WebServerData, WithVersionSpec},
+    };
+
+    /// Import the AlphaMissense example data and return the path to the database.
+    fn import_alphamissense(tmp_dir: &TempDir) -> String {
+        let path_rocksdb = format!("{}", tmp_dir.join("alphamissense").display());
+        crate::alphamissense::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::alphamissense::cli::import::Args {
+                genome_release: GenomeRelease::Grch37,
+                path_in_tsv: String::from("tests/alphamissense/example/alphamissense.tsv.bgz"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                db_version: String::from("2023"),
+                tbi_window_size: 1_000_000,
+                cf_name: String::from("alphamissense_data"),
+                path_wal_dir: None,
+                allow_iupac: false,
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        path_rocksdb
+    }
+
+    /// Import the AlphaMissense example data and open it as for the server.
+    pub(crate) fn data_with_alphamissense(tmp_dir: &TempDir) -> WebServerData {
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::Alphamissense] = Some(WithVersionSpec {
+            data: rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                import_alphamissense(tmp_dir),
+                ["meta", AnnoDb::Alphamissense.cf_name()],
+                false,
+            )
+            .unwrap(),
+            version_spec: None,
+        });
+        data
+    }
+
+    #[test]
+    fn fetch_annos_alphamissense() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_alphamissense(&tmp_dir);
+
+        let result = fetch_annos(
+            &data,
+            &SeqvarsAnnosQuery {
+                genome_release: "grch37".into(),
+                chromosome: "1".into(),
+                pos: 861332,
+                reference: "G".into(),
+                alternative: "A".into(),
+                clinvar_release: None,
+                haplogroup: None,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        insta::assert_yaml_snapshot!(result.alphamissense);
+
+        Ok(())
+    }
+
+    /// Import the gnomAD-mtDNA example data and open it as for the server.
+    fn data_with_gnomad_mtdna(tmp_dir: &TempDir) -> WebServerData {
+        let path_rocksdb = format!("{}", tmp_dir.join("gnomad-mtdna").display());
+        crate::gnomad_mtdna::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::gnomad_mtdna::cli::import::Args {
+                genome_release: GenomeRelease::Grch37,
+                path_in_vcf: String::from("tests/gnomad-mtdna/example/gnomad-mtdna.vcf.bgz"),
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("gnomad_mtdna_data"),
+                gnomad_version: String::from("3.1.1"),
+                path_wal_dir: None,
+                allow_iupac: false,
+                dry_run: false,
+                sample: Default::default(),
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                tbi_window_size: 1_000_000,
+                import_fields_json: None,
+            },
+        )
+        .unwrap();
+
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::GnomadMtdna] = Some(WithVersionSpec {
+            data: rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                path_rocksdb,
+                ["meta", AnnoDb::GnomadMtdna.cf_name()],
+                false,
+            )
+            .unwrap(),
+            version_spec: None,
+        });
+        data
+    }
+
+    #[test]
+    fn fetch_annos_gnomad_mtdna_haplogroup() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_gnomad_mtdna(&tmp_dir);
+        let haplogroup_counts = |haplogroup: Option<&str>| {
+            fetch_annos(
+                &data,
+                &SeqvarsAnnosQuery {
+                    genome_release: "grch37".into(),
+                    chromosome: "MT".into(),
+                    pos: 12544,
+                    reference: "A".into(),
+                    alternative: "G".into(),
+                    clinvar_release: None,
+                    haplogroup: haplogroup.map(ToString::to_string),
+                },
+            )
+            .map_err(|e| anyhow::anyhow!("{:?}", e))
+            .map(|result| result.gnomad_mtdna.expect("no record").haplogroup_counts)
+        };
+
+        let all = haplogroup_counts(None)?;
+        assert_eq!(all.len(), 29);
+        assert_eq!(all[0].haplogroup, "A");
+        assert_eq!(all[28].haplogroup, "Z");
+        assert_eq!(
+            haplogroup_counts(Some("u"))?,
+            vec![response::GnomadMtdnaHaplogroupCounts {
+                haplogroup: "U".into(),
+                an: 6037,
+                ac_het: 1,
+                ac_hom: 8,
+                af_het: 1.65645e-4,
+                af_hom: 1.32516e-3,
+            }]
+        );
+        assert_eq!(haplogroup_counts(Some("Q"))?, vec![]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_computed_frequencies_mtdna() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data_with_gnomad_mtdna(&tmp_dir)))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/annos/variant?genome_release=grch37&chromosome=MT&pos=12544&reference=A&\
+                alternative=G",
+            )
+            .to_request();
+        let response: AnnosVariantResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        let record = response.result.gnomad_mtdna.expect("no record");
+        let computed = response.computed_frequencies;
+        assert_eq!(
+            computed.tier,
+            crate::freqs::computed::QualityTier::SingleSource
+        );
+        assert_eq!(
+            computed.sources,
+            vec![crate::freqs::computed::FrequencySource::GnomadMtdna]
+        );
+        assert_eq!(computed.an, record.an as u64);
+        assert_eq!(computed.ac, record.ac_hom as u64);
+        assert_eq!(
+            computed.af_heteroplasmic,
+            Some(record.ac_het as f64 / record.an as f64)
+        );
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/annos/variant?genome_release=grch37&chromosome=MT&pos=12544&reference=A&\
+                alternative=T",
+            )
+            .to_request();
+        let response: AnnosVariantResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(
+            response.computed_frequencies,
+            ComputedFrequencies::default()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_alphamissense_block_cache() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let block_cache = crate::server::run::BlockCache::new(1);
+        let mut data = WebServerData {
+            block_cache: Some(block_cache.clone()),
+            ..Default::default()
+        };
+        data.annos[GenomeRelease::Grch37][AnnoDb::Alphamissense] = Some(WithVersionSpec {
+            data: crate::server::run::open_db(
+                &import_alphamissense(&tmp_dir),
+                AnnoDb::Alphamissense.cf_name(),
+                data.block_cache.as_ref(),
+            )?,
+            version_spec: None,
+        });
+        let query = SeqvarsAnnosQuery {
+            genome_release: "grch37".into(),
+            chromosome: "1".into(),
+            pos: 861332,
+            reference: "G".into(),
+            alternative: "A".into(),
+            clinvar_release: None,
+            haplogroup: None,
+        };
+        let expected = fetch_annos(&data_with_alphamissense(&TempDir::default()), &query)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        let result = fetch_annos(&data, &query).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        assert!(result.alphamissense.is_some());
+        assert_eq!(result.alphamissense, expected.alphamissense);
+        assert!(block_cache.cache.get_usage() > 0);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::typed(true)]
+    #[case::untyped(false)]
+    fn fetch_annos_cadd(#[case] typed: bool) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            crate::server::run::cadd::test::import_cadd(&tmp_dir),
+            ["meta", AnnoDb::Cadd.cf_name()],
+            false,
+        )?;
+        data.cadd_columns[GenomeRelease::Grch37] = crate::server::run::cadd::CaddColumns::from_db(
+            &db,
+            AnnoDb::Cadd.cf_name(),
+            "RawScore",
+            if typed { "PHRED" } else { "CADD_PHRED" },
+        )?;
+        data.annos[GenomeRelease::Grch37][AnnoDb::Cadd] = Some(WithVersionSpec {
+            data: db,
+            version_spec: None,
+        });
+
+        let result = fetch_annos(
+            &data,
+            &SeqvarsAnnosQuery {
+                genome_release: "grch37".into(),
+                chromosome: "1".into(),
+                pos: 1000,
+                reference: "A".into(),
+                alternative: "T".into(),
+                clinvar_release: None,
+                haplogroup: None,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        let record = result.cadd.expect("must have CADD record");
+        match &record {
+            CaddRecord::Scores(scores) if typed => assert_eq!(
+                *scores,
+                CaddScores {
+                    raw: 3.407,
+                    phred: 25.1
+                }
+            ),
+            CaddRecord::Columns(columns) if !typed => {
+                assert_eq!(columns["RawScore"], serde_json::json!(3.407));
+                assert_eq!(columns["PHRED"], serde_json::json!(25.1));
+            }
+            _ => panic!("unexpected CADD record: {:?}", record),
+        }
+        // The untagged record must deserialize into the same variant.
+        let round_trip: CaddRecord = serde_json::from_value(serde_json::to_value(&record)?)?;
+        assert_eq!(round_trip, record);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_untrimmed_alleles() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = data_with_alphamissense(&tmp_dir);
+        let (_, db_info) = crate::server::run::fetch_db_info(
+            &data.annos[GenomeRelease::Grch37][AnnoDb::Alphamissense]
+                .as_ref()
+                .unwrap()
+                .data,
+            AnnoDb::Alphamissense,
+        )?;
+        assert!(db_info.keys_normalized);
+        assert_eq!(db_info.record_count, Some(6));
+        assert_eq!(db_info.key_range_first.as_deref(), Some("1:69094:G:A"));
+        assert_eq!(db_info.key_range_last.as_deref(), Some("2:41612:C:T"));
+        data.db_infos[GenomeRelease::Grch37][AnnoDb::Alphamissense] = Some(db_info);
+
+        let result = fetch_annos(
+            &data,
+            &SeqvarsAnnosQuery {
+                genome_release: "grch37".into(),
+                chromosome: "1".into(),
+                pos: 861331,
+                reference: "CGT".into(),
+                alternative: "CAT".into(),
+                clinvar_release: None,
+                haplogroup: None,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        assert_eq!(
+            result.alphamissense.map(|records| records.records.len()),
+            Some(2)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_multi_keyed_by_release() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_alphamissense(&tmp_dir);
+
+        let query = |genome_release: &str, pos: u32| SeqvarsAnnosQuery {
+            genome_release: genome_release.into(),
+            chromosome: "1".into(),
+            pos,
+            reference: "G".into(),
+            alternative: "A".into(),
+            clinvar_release: None,
+            haplogroup: None,
+        };
+        let response = fetch_annos_multi(
+            &data,
+            AnnosVariantMultiQuery {
+                variants: vec![query("grch38", 925952), query("grch37", 861332)],
+                presence_only: false,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        assert_eq!(
+            response.results.keys().collect::<Vec<_>>(),
+            vec!["grch37", "grch38"]
+        );
+        let grch37 = &response.results["grch37"];
+        assert_eq!(grch37.len(), 1);
+        assert_eq!(grch37[0].query.pos, 861332);
+        assert!(grch37[0].result.alphamissense.is_some());
+        let grch38 = &response.results["grch38"];
+        assert_eq!(grch38.len(), 1);
+        assert_eq!(grch38[0].query.pos, 925952);
+        assert!(grch38[0].result.alphamissense.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_multiple_releases_rejected() {
+        let data = WebServerData::default();
+
+        let err = fetch_annos(
+            &data,
+            &SeqvarsAnnosQuery {
+                genome_release: "grch37,grch38".into(),
+                chromosome: "1".into(),
+                pos: 861332,
+                reference: "G".into(),
+                alternative: "A".into(),
+                clinvar_release: None,
+                haplogroup: None,
+            },
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("POST /annos/variant"));
+    }
+
+    /// Valid query parameters for `SeqvarsAnnosParams`.
+    fn valid_params() -> SeqvarsAnnosParams {
+        SeqvarsAnnosParams {
+            genome_release: Some("grch37".into()),
+            chromosome: Some("1".into()),
+            pos: Some("861332".into()),
+            reference: Some("g".into()),
+            alternative: Some("A".into()),
+            clinvar_release: None,
+            haplogroup: None,
+            presence_only: None,
+            clinvar_details: None,
+            context: None,
+            context_max_window: None,
+        }
+    }
+
+    #[test]
+    fn params_valid() -> Result<(), anyhow::Error> {
+        let query = valid_params()
+            .validate()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        assert_eq!(query.pos, 861332);
+        assert_eq!(query.reference, "G");
+
+        Ok(())
+    }
+
+    #[test]
+    fn params_presence_only() -> Result<(), anyhow::Error> {
+        let presence_only = |value: Option<&str>| {
+            SeqvarsAnnosParams {
+                presence_only: value.map(ToString::to_string),
+                ..valid_params()
+            }
+            .presence_only()
+        };
+
+        assert!(!presence_only(None).map_err(|e| anyhow::anyhow!("{}", e))?);
+        assert!(presence_only(Some("true")).map_err(|e| anyhow::anyhow!("{}", e))?);
+        assert_eq!(
+            presence_only(Some("yes")).unwrap_err().param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: "presence_only".into(),
+                value: Some("yes".into()),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::missing(None, None, Some(None))]
+    #[case::disabled(Some("false"), Some("1000"), Some(None))]
+    #[case::default_window(Some("true"), None, Some(Some(pos_index::DEFAULT_MAX_WINDOW)))]
+    #[case::window(Some("true"), Some("5000"), Some(Some(5000)))]
+    #[case::invalid_window(Some("true"), Some("-1"), None)]
+    #[case::invalid_context(Some("yes"), None, None)]
+    fn params_context(
+        #[case] context: Option<&str>,
+        #[case] context_max_window: Option<&str>,
+        #[case] expected: Option<Option<u32>>,
+    ) {
+        let result = SeqvarsAnnosParams {
+            context: context.map(ToString::to_string),
+            context_max_window: context_max_window.map(ToString::to_string),
+            ..valid_params()
+        }
+        .context();
+
+        assert_eq!(result.ok(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case("genome_release", None)]
+    #[case("genome_release", Some("hg19"))]
+    #[case("genome_release", Some("grch37,grch38"))]
+    #[case("chromosome", None)]
+    #[case("chromosome", Some("chr123"))]
+    #[case("pos", None)]
+    #[case("pos", Some("abc"))]
+    #[case("pos", Some("0"))]
+    #[case("reference", Some(""))]
+    #[case("reference", Some("GX"))]
+    #[case("alternative", Some("R"))]
+    #[case("clinvar_release", Some("2024-1"))]
+    #[case("clinvar_release", Some("2024-13"))]
+    #[case("haplogroup", Some(""))]
+    #[case("haplogroup", Some("H,U"))]
+    fn params_invalid(#[case] name: &str, #[case] value: Option<&str>) {
+        let mut params = valid_params();
+        let value_string = value.map(ToString::to_string);
+        match name {
+            "genome_release" => params.genome_release = value_string,
+            "chromosome" => params.chromosome = value_string,
+            "pos" => params.pos = value_string,
+            "reference" => params.reference = value_string,
+            "alternative" => params.alternative = value_string,
+            "clinvar_release" => params.clinvar_release = value_string,
+            "haplogroup" => params.haplogroup = value_string,
+            _ => unreachable!(),
+        }
+
+        let err = params.validate().unwrap_err();
+
+        assert_eq!(
+            actix_web::ResponseError::status_code(&err),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            err.param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: name.into(),
+                value: value.map(ToString::to_string),
+            })
+        );
+        let prefix = match value {
+            Some(value) => format!("invalid value {:?} for parameter {}: ", value, name),
+            None => format!("missing parameter {}: ", name),
+        };
+        assert!(
+            serde_json::to_value(&err).unwrap()["err"]
+                .as_str()
+                .unwrap()
+                .starts_with(&prefix),
+            "{}",
+            err
+        );
+    }
+
+    /// Import the GRCh38 gnomAD exomes example data of `gnomad_version` and return the path.
+    fn import_gnomad_exomes_grch38(
+        tmp_dir: &TempDir,
+        gnomad_version: &str,
+        prefix_bloom: bool,
+    ) -> Result<String, anyhow::Error> {
+        let path_rocksdb = format!(
+            "{}",
+            tmp_dir
+                .join(format!("gnomad-exomes-{}", gnomad_version))
+                .display()
+        );
+        crate::gnomad_nuclear::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::gnomad_nuclear::cli::import::Args {
+                genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch38,
+                path_in_vcf: vec![format!(
+                    "tests/gnomad-nuclear/example-exomes-grch38/v{}/gnomad-exomes.vcf.bgz",
+                    gnomad_version
+                )],
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("gnomad_nuclear_data"),
+                path_wal_dir: None,
+                incremental_compaction: false,
+                prefix_bloom,
+                allow_iupac: false,
+                dry_run: false,
+                sample: Default::default(),
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                tbi_window_size: 1_000_000,
+                import_fields_json: None,
+                gnomad_kind: crate::gnomad_nuclear::cli::import::GnomadKind::Exomes,
+                gnomad_version: gnomad_version.to_string(),
+            },
+        )?;
+        Ok(path_rocksdb)
+    }
+
+    /// Import the GRCh38 gnomAD exomes example data of `gnomad_version` and register it with
+    /// `data` as for the server.
+    fn insert_gnomad_exomes_grch38(
+        tmp_dir: &TempDir,
+        data: &mut WebServerData,
+        gnomad_version: &str,
+    ) -> Result<(), anyhow::Error> {
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            import_gnomad_exomes_grch38(tmp_dir, gnomad_version, false)?,
+            ["meta", AnnoDb::GnomadExomes.cf_name()],
+            false,
+        )?;
+        let (genome_release, db_info) =
+            crate::server::run::fetch_db_info(&db, AnnoDb::GnomadExomes)?;
+        data.insert_anno_db(
+            genome_release,
+            db_info,
+            WithVersionSpec {
+                data: db,
+                version_spec: None,
+            },
+        )
+    }
+
+    #[test]
+    fn fetch_annos_gnomad_exomes_liftover_and_native() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut data, "2.1")?;
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut data, "4.0")?;
+
+        let result = fetch_annos(
+            &data,
+            &SeqvarsAnnosQuery {
+                genome_release: "grch38".into(),
+                chromosome: "1".into(),
+                pos: 138653,
+                reference: "C".into(),
+                alternative: "G".into(),
+                clinvar_release: None,
+                haplogroup: None,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        // The first database given is the one in `gnomad_exomes`.
+        assert!(matches!(
+            result.gnomad_exomes,
+            Some(GnomadRecordWithGrpmax {
+                record: GnomadRecord::Gnomad2(_),
+                ..
+            })
+        ));
+        assert_eq!(
+            result.gnomad_versions.keys().collect::<Vec<_>>(),
+            vec!["gnomad_exomes_2.1_liftover", "gnomad_exomes_4.0"]
+        );
+        assert!(matches!(
+            result.gnomad_versions["gnomad_exomes_2.1_liftover"],
+            Some(GnomadRecordWithGrpmax {
+                record: GnomadRecord::Gnomad2(_),
+                ..
+            })
+        ));
+        assert!(result.gnomad_versions["gnomad_exomes_4.0"].is_none());
+
+        Ok(())
+    }
+
+    /// Databases imported with `--prefix-bloom` are opened with the filters and give the same
+    /// results as without.
+    #[test]
+    fn fetch_annos_gnomad_exomes_grpmax() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut data, "2.1")?;
+
+        let result =
+            fetch_annos(&data, &gnomad_exomes_query()).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        let gnomad_exomes = serde_json::to_value(result.gnomad_exomes)?;
+
+        assert!(gnomad_exomes["gnomad2"].is_object());
+        assert_eq!(
+            gnomad_exomes["grpmax"],
+            serde_json::to_value(grpmax::Grpmax {
+                population: "sas".into(),
+                ac: 4,
+                an: 6086,
+                af: 6.57246e-4,
+                nhomalt: 0,
+                computed: false,
+            })?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_gnomad_exomes_prefix_bloom() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut expected_data = WebServerData::default();
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut expected_data, "2.1")?;
+        let db = crate::server::run::open_db(
+            &import_gnomad_exomes_grch38(&TempDir::default(), "2.1", true)?,
+            AnnoDb::GnomadExomes.cf_name(),
+            None,
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        assert!(crate::common::prefix_bloom::read_meta(&db, &cf_meta)?);
+        drop(cf_meta);
+        let mut data = WebServerData::default();
+        let (genome_release, db_info) =
+            crate::server::run::fetch_db_info(&db, AnnoDb::GnomadExomes)?;
+        data.insert_anno_db(
+            genome_release,
+            db_info,
+            WithVersionSpec {
+                data: db,
+                version_spec: None,
+            },
+        )?;
+
+        for query in [
+            gnomad_exomes_query(),
+            SeqvarsAnnosQuery {
+                alternative: "T".into(),
+                ..gnomad_exomes_query()
+            },
+        ] {
+            let expected = fetch_annos(&expected_data, &query)
+                .map_err(|e| anyhow::anyhow!("{:?}", e))?
+                .gnomad_exomes;
+            let result = fetch_annos(&data, &query)
+                .map_err(|e| anyhow::anyhow!("{:?}", e))?
+                .gnomad_exomes;
+            assert_eq!(
+                serde_json::to_value(&result)?,
+                serde_json::to_value(&expected)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn insert_gnomad_exomes_same_version_rejected() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut data, "4.0")?;
+
+        let tmp_dir_2 = TempDir::default();
+        let err = insert_gnomad_exomes_grch38(&tmp_dir_2, &mut data, "4.0").unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("more than one gnomad_exomes database with version 4.0 given for grch38"));
+
+        Ok(())
+    }
+
+    /// Import the ClinVar JSONL file `path_in_jsonl` as release `db_version` into `data`.
+    fn insert_clinvar_grch37(
+        tmp_dir: &TempDir,
+        data: &mut WebServerData,
+        path_in_jsonl: &str,
+        db_version: &str,
+        with_details: bool,
+    ) -> Result<(), anyhow::Error> {
+        let path_rocksdb = format!(
+            "{}",
+            tmp_dir.join(format!("clinvar-{}", db_version)).display()
+        );
+        crate::clinvar_minimal::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::clinvar_minimal::cli::import::Args {
+                genome_release: crate::common::cli::GenomeRelease::Grch37,
+                path_in_jsonl: path_in_jsonl.into(),
+                path_out_rocksdb: path_rocksdb.clone(),
+                db_version: Some(db_version.into()),
+                cf_name: String::from("clinvar"),
+                cf_name_by_accession: String::from("clinvar_by_accession"),
+                with_details,
+                cf_name_details: String::from(crate::clinvar_minimal::DETAILS_CF_NAME),
+                path_wal_dir: None,
+                dry_run: false,
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+            },
+        )?;
+
+        let db = crate::server::run::open_db_with_optional(
+            &path_rocksdb,
+            AnnoDb::Clinvar.cf_name(),
+            &[crate::clinvar_minimal::DETAILS_CF_NAME],
+            None,
+            false,
+        )?;
+        let (genome_release, db_info) = crate::server::run::fetch_db_info(&db, AnnoDb::Clinvar)?;
+        data.insert_anno_db(
+            genome_release,
+            db_info,
+            WithVersionSpec {
+                data: db,
+                version_spec: None,
+            },
+        )
+    }
+
+    /// Query for the TGDS variant that is only in the 2023-06 ClinVar release.
+    fn tgds_query(clinvar_release: Option<&str>) -> SeqvarsAnnosQuery {
+        SeqvarsAnnosQuery {
+            genome_release: "grch37".into(),
+            chromosome: "13".into(),
+            pos: 95243151,
+            reference: "T".into(),
+            alternative: "C".into(),
+            clinvar_release: clinvar_release.map(ToString::to_string),
+            haplogroup: None,
+        }
+    }
+
+    #[test]
+    fn fetch_annos_clinvar_releases() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_clinvar_grch37(
+            &tmp_dir,
+            &mut data,
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.jsonl",
+            "2023-06-01",
+            false,
+        )?;
+        insert_clinvar_grch37(
+            &tmp_dir,
+            &mut data,
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-flagged.jsonl",
+            "2024-01-07",
+            false,
+        )?;
+
+        assert_eq!(
+            data.clinvar_releases(GenomeRelease::Grch37),
+            vec!["2024-01", "2023-06"]
+        );
+
+        // The newest release is used by default, even if given last.
+        let result =
+            fetch_annos(&data, &tgds_query(None)).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert!(result.clinvar.is_none());
+        let result = fetch_annos(&data, &tgds_query(Some("2024-01")))
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert!(result.clinvar.is_none());
+        let result = fetch_annos(&data, &tgds_query(Some("2023-06")))
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert_eq!(result.clinvar.map(|clinvar| clinvar.records.len()), Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_clinvar_release_unknown() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_clinvar_grch37(
+            &tmp_dir,
+            &mut data,
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.jsonl",
+            "2023-06-01",
+            false,
+        )?;
+
+        let err = fetch_annos(&data, &tgds_query(Some("2022-01"))).unwrap_err();
+
+        assert_eq!(
+            actix_web::ResponseError::status_code(&err),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+        assert!(err.to_string().contains("available: 2023-06"));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::with_details(true)]
+    #[case::without_details(false)]
+    #[actix_web::test]
+    async fn handle_clinvar_details(#[case] with_details: bool) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_clinvar_grch37(
+            &tmp_dir,
+            &mut data,
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-details.jsonl",
+            "2023-06-01",
+            with_details,
+        )?;
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let uri = "/annos/variant?genome_release=grch37&chromosome=13&pos=95243151\
+            &reference=T&alternative=C";
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let response: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            response["result"]["clinvar"]["records"]
+                .as_array()
+                .map(Vec::len),
+            Some(1)
+        );
+        assert!(response["result"]["clinvar"].get("details").is_none());
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!("{}&clinvar_details=true", uri))
+            .to_request();
+        let response: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        let clinvar = &response["result"]["clinvar"];
+        assert_eq!(clinvar["records"].as_array().map(Vec::len), Some(1));
+        if with_details {
+            let submissions = &clinvar["details"][0]["submissions"];
+            assert_eq!(
+                clinvar["details"][0]["accession"]["accession"],
+                "VCV000162457"
+            );
+            assert_eq!(submissions.as_array().map(Vec::len), Some(2));
+            assert_eq!(submissions[1]["accession"]["accession"], "SCV001479341");
+            assert_eq!(
+                submissions[1]["submitter_identifiers"]["submitter_name"],
+                "Example Diagnostic Laboratory"
+            );
+            assert_eq!(submissions[1]["classification"], "Likely pathogenic");
+            assert_eq!(
+                submissions[1]["review_status"],
+                "criteria_provided_single_submitter"
+            );
+            assert_eq!(
+                submissions[1]["date_last_evaluated"],
+                "2020-03-17T00:00:00Z"
+            );
+        } else {
+            assert!(clinvar.get("details").is_none());
+        }
+
+        Ok(())
+    }
+
+    /// Query for the variant 1:138653 C>G in the GRCh38 gnomAD exomes example data.
+    fn gnomad_exomes_query() -> SeqvarsAnnosQuery {
+        SeqvarsAnnosQuery {
+            genome_release: "grch38".into(),
+            chromosome: "1".into(),
+            pos: 138653,
+            reference: "C".into(),
+            alternative: "G".into(),
+            clinvar_release: None,
+            haplogroup: None,
+        }
+    }
+
+    #[test]
+    fn fetch_presence_alphamissense() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_alphamissense(&tmp_dir);
+
+        let response = fetch_presence_multi(
+            &data,
+            AnnosVariantMultiQuery {
+                variants: vec![
+                    SeqvarsAnnosQuery {
+                        genome_release: "grch37".into(),
+                        chromosome: "1".into(),
+                        pos: 861332,
+                        reference: "G".into(),
+                        alternative: "A".into(),
+                        clinvar_release: None,
+                        haplogroup: None,
+                    },
+                    SeqvarsAnnosQuery {
+                        genome_release: "grch37".into(),
+                        chromosome: "1".into(),
+                        pos: 861332,
+                        reference: "G".into(),
+                        alternative: "T".into(),
+                        clinvar_release: None,
+                        haplogroup: None,
+                    },
+                ],
+                presence_only: true,
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        let grch37 = &response.results["grch37"];
+        assert_eq!(grch37[0].result.alphamissense, Some(true));
+        assert_eq!(grch37[1].result.alphamissense, Some(false));
+        // Databases that are not configured are `null`.
+        assert_eq!(grch37[0].result.clinvar, None);
+        assert_eq!(grch37[0].result.gnomad_exomes, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_presence_gnomad_exomes_af() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        insert_gnomad_exomes_grch38(&tmp_dir, &mut data, "2.1")?;
+
+        let result = fetch_presence(&data, &gnomad_exomes_query())
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        // The partially decoded frequency must match the one of the full record.
+        let db = data.annos[GenomeRelease::Grch38][AnnoDb::GnomadExomes]
+            .as_ref()
+            .unwrap();
+        let record = fetch_var_protobuf::<crate::pbs::gnomad::gnomad2::Record>(
+            &db.data,
+            AnnoDb::GnomadExomes.cf_name(),
+            gnomad_exomes_query().into(),
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?
+        .expect("record must exist");
+        let af = record
+            .allele_counts
+            .iter()
+            .find(|cohort| cohort.cohort.is_none())
+            .and_then(|cohort| cohort.by_sex.as_ref())
+            .and_then(|by_sex| by_sex.overall.as_ref())
+            .map(|overall| overall.af);
+        assert!(af.is_some());
+        assert_eq!(
+            result.gnomad_exomes,
+            Some(crate::server::run::annos_presence::SeqvarsGnomadPresence { present: true, af })
+        );
+
+        let result = fetch_presence(
+            &data,
+            &SeqvarsAnnosQuery {
+                alternative: "T".into(),
+                ..gnomad_exomes_query()
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert_eq!(
+            result.gnomad_exomes,
+            Some(crate::server::run::annos_presence::SeqvarsGnomadPresence {
+                present: false,
+                af: None
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_ucsc_conservation_per_base() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::UcscConservation] = Some(WithVersionSpec {
+            data: crate::server::run::open_db(
+                &crate::cons::per_base::test::import_per_base(&tmp_dir),
+                crate::cons::per_base::CF_NAME,
+                None,
+            )?,
+            version_spec: None,
+        });
+        let query = |pos: u32| SeqvarsAnnosQuery {
+            genome_release: "grch37".into(),
+            chromosome: "13".into(),
+            pos,
+            reference: "G".into(),
+            alternative: "A".into(),
+            clinvar_release: None,
+            haplogroup: None,
+        };
+
+        let result =
+            fetch_annos(&data, &query(95248340)).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        let ucsc_conservation = result.ucsc_conservation.unwrap();
+        assert!(ucsc_conservation.records.is_empty());
+        assert_eq!(ucsc_conservation.score, Some(5.62));
+
+        let result =
+            fetch_annos(&data, &query(95248343)).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert!(result.ucsc_conservation.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_annos_gnomad_coverage() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::GnomadCoverage] = Some(WithVersionSpec {
+            data: crate::server::run::open_db(
+                &crate::gnomad_coverage::test::import_coverage(&tmp_dir),
+                AnnoDb::GnomadCoverage.cf_name(),
+                None,
+            )?,
+            version_spec: None,
+        });
+        let query = |pos: u32| SeqvarsAnnosQuery {
+            genome_release: "grch37".into(),
+            chromosome: "1".into(),
+            pos,
+            reference: "G".into(),
+            alternative: "T".into(),
+            clinvar_release: None,
+            haplogroup: None,
+        };
+
+        let result =
+            fetch_annos(&data, &query(55505599)).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert_eq!(
+            result.gnomad_coverage,
+            Some(GnomadCoverageRecord {
+                mean: 31.5,
+                fraction_over_20x: 0.9375,
+            })
+        );
+
+        let result =
+            fetch_annos(&data, &query(55505605)).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert!(result.gnomad_coverage.is_none());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::allowed("1", false)]
+    #[case::allowed_with_prefix("chr1", false)]
+    #[case::not_allowed("X", true)]
+    #[actix_web::test]
+    async fn handle_contig_allowlist(
+        #[case] allowlist: &str,
+        #[case] not_in_allowlist: bool,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = WebServerData {
+            contig_allowlist: vec![crate::common::cli::canonicalize(allowlist)],
+            ..data_with_alphamissense(&tmp_dir)
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+        let query = "genome_release=grch37&chromosome=chr1&pos=861332&reference=G&alternative=A";
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!("/annos/variant?{}", query))
+            .to_request();
+        let response: AnnosVariantResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!("/annos/variant?{}&presence_only=true", query))
+            .to_request();
+        let presence: AnnosVariantPresenceResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        if not_in_allowlist {
+            let warning = "contig 1 not in the contig allowlist of the server: X";
+            assert!(response.result.alphamissense.is_none());
+            assert_eq!(response.warnings, vec![warning]);
+            assert_eq!(presence.result, SeqvarsPresenceRecord::default());
+            assert_eq!(presence.warnings, vec![warning]);
+        } else {
+            assert!(response.result.alphamissense.is_some());
+            assert!(response.warnings.is_empty());
+            assert_eq!(presence.result.alphamissense, Some(true));
+            assert!(presence.warnings.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_pos_out_of_bounds() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data_with_alphamissense(&tmp_dir)))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/annos/variant?genome_release=grch37&chromosome=chr1&pos=2491553345\
+                &reference=G&alternative=A",
+            )
+            .to_request();
+        let response = actix_web::test::call_service(&app, req).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = String::from_utf8(actix_web::test::read_body(response).await.to_vec())?;
+        assert!(
+            body.contains("position 2,491,553,345 exceeds length of chr1 (249,250,621) for GRCh37"),
+            "{}",
+            body
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::inside(1500, false)]
+    #[case::outside(5000, true)]
+    #[actix_web::test]
+    async fn handle_regions(#[case] pos: u32, #[case] is_empty: bool) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        data.regions[GenomeRelease::Grch37] =
+            crate::server::run::regions::test::example_regions(&tmp_dir);
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!(
+                "/annos/variant?genome_release=grch37&chromosome=chr1&pos={}&reference=G&alternative=A",
+                pos
+            ))
+            .to_request();
+        let response: AnnosVariantResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        if is_empty {
+            assert!(response.regions.is_empty());
+        } else {
+            insta::assert_yaml_snapshot!(response.regions);
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::without_context("", false)]
+    #[case::with_context("&context=true&context_max_window=100000", true)]
+    #[actix_web::test]
+    async fn handle_context(
+        #[case] params: &str,
+        #[case] has_context: bool,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        data.functional[GenomeRelease::Grch37] =
+            Some(crate::server::run::context::test::functional_37(&tmp_dir)?);
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(Data::new(data))
+                .service(handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!(
+                "/annos/variant?genome_release=grch37&chromosome=chr7&pos=140000000\
+                &reference=G&alternative=A{}",
+                params
+            ))
+            .to_request();
+        let response: AnnosVariantResponse =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        if has_context {
+            let context = response.context.unwrap();
+            assert_eq!(context.max_window, 100000);
+            assert_eq!(context.genes, None);
+            let functional = context.functional.unwrap();
+            assert_eq!(
+                functional.upstream.map(|feature| feature.distance),
+                Some(-85753)
+            );
+            assert_eq!(functional.downstream, None);
+        } else {
+            assert_eq!(response.context, None);
+        }
+
+        Ok(())
+    }
+}
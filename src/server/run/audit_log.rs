@@ -0,0 +1,432 @@
+//! Optional audit log of the variant queries, see `--audit-log-path`.
+//!
+//! Each request to `/annos/variant`, `/api/v1/seqvars/annos`, and `/annos/range` is appended
+//! as one JSON line with the time, the endpoint, the normalized query parameters, the response
+//! status, and the duration until the response head was ready.  The client is not logged, we
+//! leave authentication to the reverse proxy.
+//!
+//! The request handlers only send the entries into a channel; a dedicated thread writes them
+//! to the file, such that requests never wait for disk I/O.  Once the file would grow beyond
+//! `--audit-log-max-mb`, it is renamed to `PATH.1` (`PATH.1` to `PATH.2`, etc.) and a new file
+//! is started; only `--audit-log-keep` rotated files are kept.  Without `--audit-log-path`,
+//! the middleware is not installed at all.
+
+use std::{
+    collections::BTreeMap,
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Instant,
+};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web::{self, Data},
+};
+
+use super::WebServerData;
+
+/// Default maximal size of the audit log file in MiB before it is rotated.
+pub const DEFAULT_MAX_MB: u64 = 100;
+/// Default number of rotated audit log files to keep.
+pub const DEFAULT_KEEP: usize = 5;
+
+/// Paths of the endpoints that are logged.
+pub const AUDITED_PATHS: &[&str] = &["/annos/variant", "/api/v1/seqvars/annos", "/annos/range"];
+
+/// Configuration of the `AuditLog`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Path of the current log file.
+    pub path: PathBuf,
+    /// Size in bytes beyond which the file is rotated.
+    pub max_bytes: u64,
+    /// Number of rotated files to keep.
+    pub keep: usize,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AuditLogEntry {
+    /// Time the request was received.
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// HTTP method of the request.
+    pub method: String,
+    /// Path of the endpoint.
+    pub endpoint: String,
+    /// Query parameters sorted by name, repeated parameters joined with `,`.
+    pub query: BTreeMap<String, String>,
+    /// HTTP status of the response.
+    pub status: u16,
+    /// Time in milliseconds until the response head was ready.
+    pub duration_ms: f64,
+}
+
+/// Return the normalized parameters of the `query_string`.
+///
+/// Parameters that cannot be decoded are logged as the raw query string under the empty name.
+pub fn normalize_query(query_string: &str) -> BTreeMap<String, String> {
+    let mut result = BTreeMap::<String, String>::new();
+    match web::Query::<Vec<(String, String)>>::from_query(query_string) {
+        Ok(pairs) => {
+            for (name, value) in pairs.into_inner() {
+                result
+                    .entry(name)
+                    .and_modify(|values| {
+                        values.push(',');
+                        values.push_str(&value);
+                    })
+                    .or_insert(value);
+            }
+        }
+        Err(_) => {
+            result.insert(String::new(), query_string.to_string());
+        }
+    }
+    result
+}
+
+/// Writer of the audit log file with size-based rotation.
+struct RotatingWriter {
+    /// The configuration.
+    config: Config,
+    /// The current file.
+    file: std::io::BufWriter<std::fs::File>,
+    /// Size of the current file in bytes.
+    size: u64,
+}
+
+/// Return the path of the `index`-th rotated file of `path`.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut result = path.as_os_str().to_owned();
+    result.push(format!(".{}", index));
+    result.into()
+}
+
+impl RotatingWriter {
+    /// Open the log file, appending to an existing file.
+    fn open(config: Config) -> Result<Self, anyhow::Error> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)
+            .map_err(|e| {
+                anyhow::anyhow!("could not open audit log {}: {}", config.path.display(), e)
+            })?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            config,
+            file: std::io::BufWriter::new(file),
+            size,
+        })
+    }
+
+    /// Append `line`, rotating the file before if it would exceed the maximal size.
+    fn write_line(&mut self, line: &[u8]) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.config.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.file.write_all(b"\n")?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// Shift the rotated files by one, move the current file to `PATH.1`, and start a new file.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let path = &self.config.path;
+        if self.config.keep == 0 {
+            std::fs::remove_file(path)?;
+        } else {
+            for index in (1..self.config.keep).rev() {
+                let from = rotated_path(path, index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(path, index + 1))?;
+                }
+            }
+            std::fs::rename(path, rotated_path(path, 1))?;
+        }
+        self.file = std::io::BufWriter::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?,
+        );
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Write the entries received from `receiver` until all senders are gone.
+    ///
+    /// The file is flushed whenever there are no further entries waiting.
+    fn run(mut self, receiver: mpsc::Receiver<AuditLogEntry>) {
+        while let Ok(entry) = receiver.recv() {
+            for entry in std::iter::once(entry).chain(receiver.try_iter()) {
+                let result = serde_json::to_vec(&entry)
+                    .map_err(std::io::Error::from)
+                    .and_then(|line| self.write_line(&line));
+                if let Err(e) = result {
+                    tracing::warn!("could not write audit log entry: {}", e);
+                }
+            }
+            if let Err(e) = self.file.flush() {
+                tracing::warn!("could not flush audit log: {}", e);
+            }
+        }
+    }
+}
+
+/// The audit log.
+///
+/// Dropping it writes the remaining entries and waits for the writer thread.
+pub struct AuditLog {
+    /// Path of the current log file.
+    path: PathBuf,
+    /// Sender to the writer thread, `None` once dropped.
+    sender: Option<mpsc::Sender<AuditLogEntry>>,
+    /// Handle of the writer thread, `None` once joined.
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    /// Open the log file and start the writer thread.
+    pub fn new(config: Config) -> Result<Self, anyhow::Error> {
+        let path = config.path.clone();
+        let writer = RotatingWriter::open(config)?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("audit-log".into())
+            .spawn(move || writer.run(receiver))?;
+        Ok(Self {
+            path,
+            sender: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    /// Queue `entry` for writing; never blocks.
+    pub fn log(&self, entry: AuditLogEntry) {
+        if let Some(sender) = self.sender.as_ref() {
+            if sender.send(entry).is_err() {
+                tracing::warn!("audit log writer has stopped, dropping entry");
+            }
+        }
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                tracing::warn!("audit log writer panicked");
+            }
+        }
+    }
+}
+
+/// Middleware that logs the requests to `AUDITED_PATHS` to `WebServerData::audit_log`.
+///
+/// Install with `actix_web::middleware::from_fn` and only if the audit log is enabled.
+pub async fn middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let data = req
+        .app_data::<Data<WebServerData>>()
+        .filter(|data| data.audit_log.is_some())
+        .cloned();
+    let Some(data) = data.filter(|_| AUDITED_PATHS.contains(&req.path())) else {
+        return next.call(req).await;
+    };
+
+    let timestamp = chrono::Utc::now();
+    let before = Instant::now();
+    let method = req.method().to_string();
+    let endpoint = req.path().to_string();
+    let query = normalize_query(req.query_string());
+
+    let result = next.call(req).await;
+
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+    if let Some(audit_log) = data.audit_log.as_ref() {
+        audit_log.log(AuditLogEntry {
+            timestamp,
+            method,
+            endpoint,
+            query,
+            status: status.as_u16(),
+            duration_ms: before.elapsed().as_secs_f64() * 1000.0,
+        });
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{get, middleware::from_fn, test, App, HttpResponse};
+    use temp_testdir::TempDir;
+
+    use super::*;
+
+    #[get("/annos/variant")]
+    async fn handle_variant() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[get("/annos/range")]
+    async fn handle_range() -> HttpResponse {
+        HttpResponse::BadRequest().finish()
+    }
+
+    #[get("/genes/info")]
+    async fn handle_genes() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    /// Return the lines of the file at `path`.
+    fn read_lines(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn entry(index: usize) -> AuditLogEntry {
+        AuditLogEntry {
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            method: "GET".into(),
+            endpoint: "/annos/variant".into(),
+            query: normalize_query(&format!("genome_release=grch37&pos={}", index)),
+            status: 200,
+            duration_ms: 1.5,
+        }
+    }
+
+    #[rstest::rstest]
+    #[case("", &[])]
+    #[case("b=2&a=1", &[("a", "1"), ("b", "2")])]
+    #[case(
+        "chromosome=chr%31&database=cadd&database=dbsnp",
+        &[("chromosome", "chr1"), ("database", "cadd,dbsnp")]
+    )]
+    fn normalize_query_cases(#[case] query_string: &str, #[case] expected: &[(&str, &str)]) {
+        let expected = expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>();
+
+        assert_eq!(normalize_query(query_string), expected);
+    }
+
+    #[test]
+    fn entry_line_format() -> Result<(), anyhow::Error> {
+        let line = serde_json::to_string(&entry(1000))?;
+
+        assert_eq!(
+            line,
+            r#"{"timestamp":"2023-11-14T22:13:20Z","method":"GET","endpoint":"/annos/variant","query":{"genome_release":"grch37","pos":"1000"},"status":200,"duration_ms":1.5}"#
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn middleware_logs_audited_paths() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path = tmp_dir.join("audit.jsonl");
+        let data = Data::new(WebServerData {
+            audit_log: Some(AuditLog::new(Config {
+                path: path.clone(),
+                max_bytes: 1024 * 1024,
+                keep: 1,
+            })?),
+            ..Default::default()
+        });
+
+        {
+            let app = test::init_service(
+                App::new()
+                    .app_data(data.clone())
+                    .service(handle_variant)
+                    .service(handle_range)
+                    .service(handle_genes)
+                    .wrap(from_fn(middleware)),
+            )
+            .await;
+            for uri in [
+                "/annos/variant?pos=100&genome_release=grch37&chromosome=1",
+                "/genes/info?hgnc_id=HGNC:1100",
+                "/annos/range?genome_release=grch37&chromosome=1&start=1&stop=0",
+            ] {
+                test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            }
+        }
+        drop(data);
+
+        let entries = read_lines(&path)
+            .iter()
+            .map(|line| serde_json::from_str::<AuditLogEntry>(line))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].method, "GET");
+        assert_eq!(entries[0].endpoint, "/annos/variant");
+        assert_eq!(
+            entries[0].query,
+            normalize_query("chromosome=1&genome_release=grch37&pos=100")
+        );
+        assert_eq!(entries[0].status, 200);
+        assert!(entries[0].duration_ms >= 0.0);
+        assert_eq!(entries[1].endpoint, "/annos/range");
+        assert_eq!(entries[1].status, 400);
+        assert!(entries[0].timestamp <= entries[1].timestamp);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rotation() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let path = tmp_dir.join("audit.jsonl");
+        let line_len = serde_json::to_vec(&entry(1000))?.len() as u64 + 1;
+
+        let audit_log = AuditLog::new(Config {
+            path: path.clone(),
+            max_bytes: 3 * line_len,
+            keep: 2,
+        })?;
+        for index in 1000..1010 {
+            audit_log.log(entry(index));
+        }
+        drop(audit_log);
+
+        // Three lines per file, the file with the first three lines has been removed.
+        assert_eq!(read_lines(&path).len(), 1);
+        assert_eq!(read_lines(&rotated_path(&path, 1)).len(), 3);
+        assert_eq!(read_lines(&rotated_path(&path, 2)).len(), 3);
+        assert!(!rotated_path(&path, 3).exists());
+        let oldest: AuditLogEntry = serde_json::from_str(&read_lines(&rotated_path(&path, 2))[0])?;
+        assert_eq!(oldest.query["pos"], "1003");
+        let newest: AuditLogEntry = serde_json::from_str(&read_lines(&path)[0])?;
+        assert_eq!(newest.query["pos"], "1009");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+//! Typed view of the CADD scores.
+//!
+//! CADD databases are imported with `tsv import`, such that the generic TSV code returns all
+//! columns of a record as JSON values.  If the raw score and PHRED columns (by default
+//! `RawScore` and `PHRED`, see `--cadd-col-raw` and `--cadd-col-phred`) are found in the
+//! schema of the database, the server returns `CaddScores` instead and `/annos/range`
+//! supports filtering by `min_cadd_phred`.  Otherwise, the typed view is disabled with a
+//! warning at startup and the records are returned with all columns as before.
+
+use crate::{
+    common::keys,
+    server::run::annos_variant::response::CaddScores,
+    tsv::{coding::Context, schema::ColumnType},
+};
+
+use super::{error::CustomError, fetch::fetch_tsv_json_prepare_db, range_filter};
+
+/// Default name of the column with the raw score.
+pub const DEFAULT_COL_RAW: &str = "RawScore";
+
+/// Default name of the column with the PHRED-scaled score.
+pub const DEFAULT_COL_PHRED: &str = "PHRED";
+
+/// Indices of the score columns in the schema of a CADD database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaddColumns {
+    /// Index of the raw score column.
+    pub raw: usize,
+    /// Index of the PHRED score column.
+    pub phred: usize,
+}
+
+/// CADD scores of one variant from `/annos/range`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CaddRangeRecord {
+    /// Chromosome name.
+    pub chromosome: String,
+    /// 1-based position.
+    pub pos: i32,
+    /// Reference allele.
+    pub reference: String,
+    /// Alternative allele.
+    pub alternative: String,
+    /// The scores.
+    #[serde(flatten)]
+    pub scores: CaddScores,
+}
+
+impl CaddColumns {
+    /// Find the columns `col_raw` and `col_phred` in the schema of the CADD database `db`.
+    ///
+    /// Returns `None` with a warning if one of the columns is missing or not numeric, which
+    /// disables the typed view for the database.
+    pub fn from_db(
+        db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+        cf_name: &str,
+        col_raw: &str,
+        col_phred: &str,
+    ) -> Result<Option<Self>, anyhow::Error> {
+        let (db_schema, _) = fetch_tsv_json_prepare_db(db, cf_name)
+            .map_err(|e| anyhow::anyhow!("problem loading CADD schema: {}", e))?;
+        let find = |name: &str| {
+            let idx = db_schema
+                .columns
+                .iter()
+                .position(|column| column.name == name);
+            match idx.map(|idx| (idx, &db_schema.columns[idx].typ)) {
+                Some((idx, ColumnType::Float | ColumnType::Integer)) => Some(idx),
+                Some((_, typ)) => {
+                    tracing::warn!(
+                        "CADD column {:?} in {} has type {:?} rather than a number; \
+                        typed CADD scores are disabled",
+                        name,
+                        db.path().display(),
+                        typ
+                    );
+                    None
+                }
+                None => {
+                    tracing::warn!(
+                        "CADD column {:?} not found in {}; typed CADD scores are disabled",
+                        name,
+                        db.path().display()
+                    );
+                    None
+                }
+            }
+        };
+
+        Ok(find(col_raw)
+            .zip(find(col_phred))
+            .map(|(raw, phred)| Self { raw, phred }))
+    }
+
+    /// Extract the scores from the decoded `values` of one record.
+    fn scores(&self, values: &[serde_json::Value]) -> Result<CaddScores, CustomError> {
+        let score = |idx: usize| {
+            values
+                .get(idx)
+                .and_then(serde_json::Value::as_f64)
+                .map(|value| value as f32)
+                .ok_or_else(|| {
+                    CustomError::new(anyhow::anyhow!(
+                        "invalid CADD score in database: {:?}",
+                        values.get(idx)
+                    ))
+                })
+        };
+        Ok(CaddScores {
+            raw: score(self.raw)?,
+            phred: score(self.phred)?,
+        })
+    }
+}
+
+/// Decode the TSV `raw_value` of a CADD record.
+fn line_to_values(ctx: &Context, raw_value: &[u8]) -> Result<Vec<serde_json::Value>, CustomError> {
+    let line = std::str::from_utf8(raw_value).map_err(|e| {
+        CustomError::new(anyhow::anyhow!(
+            "problem decoding value from database: {}",
+            e
+        ))
+    })?;
+    ctx.line_to_values(line).map_err(|e| {
+        CustomError::new(anyhow::anyhow!(
+            "problem decoding value from database: {}",
+            e
+        ))
+    })
+}
+
+/// Fetch the CADD scores of the variant `key`.
+pub fn fetch_var_cadd(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    columns: &CaddColumns,
+    key: keys::Var,
+) -> Result<Option<CaddScores>, CustomError> {
+    let (_, ctx) = fetch_tsv_json_prepare_db(db, cf_name)?;
+    let cf_data = db
+        .cf_handle(cf_name)
+        .ok_or(CustomError::new(anyhow::anyhow!(
+            "TSV data does not have a column family named {}",
+            cf_name
+        )))?;
+
+    let key: Vec<u8> = key.into();
+    db.get_cf(&cf_data, key)
+        .map_err(|e| {
+            CustomError::new(anyhow::anyhow!(
+                "problem querying database (cf_name={}): {}",
+                cf_name,
+                e
+            ))
+        })?
+        .map(|raw_value| columns.scores(&line_to_values(&ctx, &raw_value)?))
+        .transpose()
+}
+
+/// Fetch the CADD scores of all variants from `start` to `stop` with a PHRED score of at
+/// least `min_phred`, if given, and passing any of `filters`, if any.
+pub fn fetch_pos_cadd(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    columns: &CaddColumns,
+    start: keys::Pos,
+    stop: keys::Pos,
+    min_phred: Option<f32>,
+    filters: &[range_filter::Filter],
+) -> Result<Vec<CaddRangeRecord>, CustomError> {
+    let (db_schema, ctx) = fetch_tsv_json_prepare_db(db, cf_name)?;
+    let filters = range_filter::resolve_all(filters, &db_schema)?;
+    let cf_data = db
+        .cf_handle(cf_name)
+        .ok_or(CustomError::new(anyhow::anyhow!(
+            "TSV data does not have a column family named {}",
+            cf_name
+        )))?;
+
+    let mut iter = db.raw_iterator_cf_opt(
+        &cf_data,
+        crate::common::prefix_bloom::scan_read_options(&start, &stop),
+    );
+    let start: Vec<u8> = start.into();
+    iter.seek(&start);
+
+    let mut result = Vec::new();
+    while let (Some(iter_key), Some(raw_value)) = (iter.key(), iter.value()) {
+        let iter_pos: keys::Pos = iter_key.into();
+        if iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos {
+            break;
+        }
+
+        let values = line_to_values(&ctx, raw_value)?;
+        let scores = columns.scores(&values)?;
+        if !min_phred.is_some_and(|min_phred| scores.phred < min_phred)
+            && range_filter::keep(&filters, &values)
+        {
+            let alleles = String::from_utf8_lossy(&iter_key[6..]);
+            let (reference, alternative) = alleles.split_once('>').unwrap_or_default();
+            result.push(CaddRangeRecord {
+                chromosome: iter_pos.chrom,
+                pos: iter_pos.pos,
+                reference: reference.to_string(),
+                alternative: alternative.to_string(),
+                scores,
+            });
+        }
+
+        iter.next();
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::tsv::cli::import::{ErrorBehavior, OnDuplicate};
+
+    /// Import the CADD example data and return the path to the database.
+    pub(crate) fn import_cadd(tmp_dir: &TempDir) -> String {
+        let path_rocksdb = format!("{}", tmp_dir.join("cadd").display());
+        crate::tsv::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::tsv::cli::import::Args {
+                path_in_tsv: vec![String::from("tests/tsv/cadd/cadd.tsv")],
+                path_out_rocksdb: path_rocksdb.clone(),
+                path_wal_dir: None,
+                incremental_compaction: false,
+                prefix_bloom: false,
+                allow_iupac: false,
+                on_duplicate: OnDuplicate::Overwrite,
+                error_behavior: ErrorBehavior::Abort,
+                dry_run: false,
+                sample: Default::default(),
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                genome_release: crate::common::assembly_detect::GenomeReleaseArg::Grch37,
+                db_name: String::from("cadd"),
+                db_version: String::from("1.6"),
+                cf_name: String::from("tsv_data"),
+                skip_row_count: 0,
+                path_schema_json: None,
+                strict_schema: false,
+                inference_row_count: 100,
+                tbi_window_size: 1000000,
+                col_chrom: String::from("Chrom"),
+                col_start: String::from("Pos"),
+                col_ref: String::from("Ref"),
+                col_alt: String::from("Alt"),
+                null_values: Vec::new(),
+                add_default_null_values: true,
+            },
+        )
+        .unwrap();
+        path_rocksdb
+    }
+
+    /// Import the CADD example data and open it read-only.
+    fn open_cadd(tmp_dir: &TempDir) -> rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> {
+        rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            import_cadd(tmp_dir),
+            ["meta", "tsv_data"],
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn columns_from_db() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = open_cadd(&tmp_dir);
+
+        assert_eq!(
+            CaddColumns::from_db(&db, "tsv_data", DEFAULT_COL_RAW, DEFAULT_COL_PHRED)?,
+            Some(CaddColumns { raw: 4, phred: 5 })
+        );
+        assert_eq!(
+            CaddColumns::from_db(&db, "tsv_data", "RawScore", "CADD_PHRED")?,
+            None
+        );
+        // The reference allele is not a score.
+        assert_eq!(
+            CaddColumns::from_db(&db, "tsv_data", "Ref", DEFAULT_COL_PHRED)?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_var() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = open_cadd(&tmp_dir);
+        let columns = CaddColumns { raw: 4, phred: 5 };
+        let fetch = |pos: i32, alternative: &str| {
+            fetch_var_cadd(
+                &db,
+                "tsv_data",
+                &columns,
+                keys::Var::from("1", pos, "A", alternative),
+            )
+            .map_err(|e| anyhow::anyhow!("{}", e))
+        };
+
+        assert_eq!(
+            fetch(1000, "G")?,
+            Some(CaddScores {
+                raw: 1.204,
+                phred: 12.31
+            })
+        );
+        assert_eq!(fetch(1000, "N")?, None);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::all(None, &["1000:A>C", "1000:A>G", "1000:A>T", "1001:C>A", "1002:G>A"])]
+    #[case::min_phred(Some(20.0), &["1000:A>T", "1002:G>A"])]
+    #[case::none(Some(30.0), &[])]
+    fn fetch_pos(
+        #[case] min_phred: Option<f32>,
+        #[case] expected: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = open_cadd(&tmp_dir);
+
+        let records = fetch_pos_cadd(
+            &db,
+            "tsv_data",
+            &CaddColumns { raw: 4, phred: 5 },
+            keys::Pos::from("1", 1000),
+            keys::Pos::from("1", 1002),
+            min_phred,
+            &[],
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| format!("{}:{}>{}", record.pos, record.reference, record.alternative))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(records
+            .iter()
+            .all(|record| record.scores.phred >= min_phred.unwrap_or_default()));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::one(&["cadd.PHRED>22"], Ok(&["1000:A>T"][..]))]
+    #[case::any(&["cadd.PHRED>22", "cadd.RawScore<0.2"], Ok(&["1000:A>C", "1000:A>T"][..]))]
+    #[case::string_column(&["cadd.Ref==1"], Err(()))]
+    fn fetch_pos_filters(
+        #[case] filters: &[&str],
+        #[case] expected: Result<&[&str], ()>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = open_cadd(&tmp_dir);
+        let filters = filters
+            .iter()
+            .map(|filter| filter.parse())
+            .collect::<Result<Vec<range_filter::Filter>, _>>()?;
+
+        let result = fetch_pos_cadd(
+            &db,
+            "tsv_data",
+            &CaddColumns { raw: 4, phred: 5 },
+            keys::Pos::from("1", 1000),
+            keys::Pos::from("1", 1002),
+            None,
+            &filters,
+        );
+
+        match expected {
+            Ok(expected) => assert_eq!(
+                result
+                    .map_err(|e| anyhow::anyhow!("{}", e))?
+                    .iter()
+                    .map(|record| format!(
+                        "{}:{}>{}",
+                        record.pos, record.reference, record.alternative
+                    ))
+                    .collect::<Vec<_>>(),
+                expected
+            ),
+            Err(()) => assert_eq!(
+                result.unwrap_err().param().map(|param| param.name.as_str()),
+                Some("filter")
+            ),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn range_record_json() -> Result<(), anyhow::Error> {
+        let record = CaddRangeRecord {
+            chromosome: String::from("1"),
+            pos: 1000,
+            reference: String::from("A"),
+            alternative: String::from("T"),
+            scores: CaddScores {
+                raw: 0.5,
+                phred: 25.0,
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&record)?,
+            serde_json::json!({
+                "chromosome": "1",
+                "pos": 1000,
+                "reference": "A",
+                "alternative": "T",
+                "raw": 0.5,
+                "phred": 25.0,
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+//! Per-database circuit breakers of the annotation endpoints.
+//!
+//! After `--circuit-breaker-threshold` consecutive errors when reading from an annotation
+//! database, e.g., because of a corrupted SST file, the database is marked as degraded.
+//! `/annos/variant` and `/annos/range` then omit its section and add a warning instead of
+//! failing every request.  Once `--circuit-breaker-cooldown-secs` have passed, the next
+//! request performs a cheap read from the `meta` column family; if it succeeds, the request
+//! is served from the database again and the first successful read closes the breaker, while
+//! another error reopens it for the next cool-down.  Errors from invalid request parameters
+//! are not counted.  The state of each breaker is reported by `/health/ready`.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::common::cli::GenomeRelease;
+
+use super::{error::CustomError, AnnoDb};
+
+/// Default number of consecutive errors after which a database is marked as degraded.
+pub const DEFAULT_THRESHOLD: u32 = 5;
+/// Default cool-down in seconds before a degraded database is probed again.
+pub const DEFAULT_COOLDOWN_SECS: u64 = 30;
+
+/// State of a `CircuitBreaker`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitBreakerState {
+    /// Requests are served from the database.
+    #[default]
+    Closed,
+    /// The database is degraded and skipped until the cool-down has passed.
+    Open,
+    /// The probe after the cool-down succeeded; the next error reopens the breaker and the
+    /// next success closes it.
+    HalfOpen,
+}
+
+/// Decision of `CircuitBreaker::decide` for one request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Read from the database.
+    Allow,
+    /// Probe the database with a cheap read before reading from it.
+    Probe,
+    /// Skip the database.
+    Reject,
+}
+
+/// Configuration shared by all circuit breakers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Number of consecutive errors after which the breaker opens, disabled with `0`.
+    pub threshold: u32,
+    /// Time to wait before probing an open breaker.
+    pub cooldown: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            cooldown: Duration::from_secs(DEFAULT_COOLDOWN_SECS),
+        }
+    }
+}
+
+/// Mutable state of a `CircuitBreaker`.
+#[derive(Debug, Default)]
+struct Inner {
+    /// The current state.
+    state: CircuitBreakerState,
+    /// Number of consecutive errors.
+    consecutive_errors: u32,
+    /// When the breaker was opened or last probed, if open.
+    opened_at: Option<Instant>,
+    /// Number of times the breaker opened.
+    trips: u64,
+    /// Message of the last error, if any.
+    last_error: Option<String>,
+}
+
+/// Circuit breaker of one database.
+///
+/// All methods take the current time such that the state machine can be tested without
+/// waiting.
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    /// The mutable state.
+    inner: Mutex<Inner>,
+}
+
+/// Status of a `CircuitBreaker` for `/health/ready`.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct CircuitBreakerStatus {
+    /// The current state.
+    pub state: CircuitBreakerState,
+    /// Number of consecutive errors.
+    pub consecutive_errors: u32,
+    /// Number of times the database was marked as degraded since the server started.
+    pub trips: u64,
+    /// Seconds until the next probe if the breaker is open.
+    pub retry_in_secs: Option<f64>,
+    /// Message of the last error, if any.
+    pub last_error: Option<String>,
+}
+
+impl CircuitBreaker {
+    /// Decide whether a request at `now` may read from the database.
+    ///
+    /// Only one request per cool-down gets `Decision::Probe` such that concurrent requests
+    /// do not all probe a degraded database.
+    pub fn decide(&self, config: &Config, now: Instant) -> Decision {
+        let mut inner = self.inner.lock().expect("poisoned circuit breaker");
+        match (inner.state, inner.opened_at) {
+            (CircuitBreakerState::Open, Some(opened_at)) => {
+                if now.saturating_duration_since(opened_at) >= config.cooldown {
+                    inner.opened_at = Some(now);
+                    Decision::Probe
+                } else {
+                    Decision::Reject
+                }
+            }
+            _ => Decision::Allow,
+        }
+    }
+
+    /// Record the result of the probe at `now`.
+    pub fn record_probe(&self, now: Instant, result: Result<(), String>) {
+        let mut inner = self.inner.lock().expect("poisoned circuit breaker");
+        match result {
+            Ok(()) => {
+                inner.state = CircuitBreakerState::HalfOpen;
+                inner.opened_at = None;
+            }
+            Err(message) => {
+                inner.opened_at = Some(now);
+                inner.last_error = Some(message);
+            }
+        }
+    }
+
+    /// Record a successful read.
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().expect("poisoned circuit breaker");
+        inner.state = CircuitBreakerState::Closed;
+        inner.consecutive_errors = 0;
+        inner.opened_at = None;
+    }
+
+    /// Record a failed read at `now`, return whether the breaker opened.
+    pub fn record_error(&self, config: &Config, now: Instant, message: String) -> bool {
+        let mut inner = self.inner.lock().expect("poisoned circuit breaker");
+        inner.consecutive_errors = inner.consecutive_errors.saturating_add(1);
+        inner.last_error = Some(message);
+        let open = match inner.state {
+            CircuitBreakerState::Closed => {
+                config.threshold > 0 && inner.consecutive_errors >= config.threshold
+            }
+            CircuitBreakerState::HalfOpen => true,
+            CircuitBreakerState::Open => false,
+        };
+        if open {
+            inner.state = CircuitBreakerState::Open;
+            inner.opened_at = Some(now);
+            inner.trips += 1;
+        }
+        open
+    }
+
+    /// Return the status at `now`.
+    pub fn status(&self, config: &Config, now: Instant) -> CircuitBreakerStatus {
+        let inner = self.inner.lock().expect("poisoned circuit breaker");
+        CircuitBreakerStatus {
+            state: inner.state,
+            consecutive_errors: inner.consecutive_errors,
+            trips: inner.trips,
+            retry_in_secs: match (inner.state, inner.opened_at) {
+                (CircuitBreakerState::Open, Some(opened_at)) => Some(
+                    (opened_at + config.cooldown)
+                        .saturating_duration_since(now)
+                        .as_secs_f64(),
+                ),
+                _ => None,
+            },
+            last_error: inner.last_error.clone(),
+        }
+    }
+
+    /// Return whether the database is degraded, i.e., the breaker is open.
+    pub fn is_open(&self) -> bool {
+        self.inner.lock().expect("poisoned circuit breaker").state == CircuitBreakerState::Open
+    }
+}
+
+/// Circuit breakers of all annotation databases.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    /// Configuration shared by all breakers.
+    pub config: Config,
+    /// The breaker of each database.
+    breakers: enum_map::EnumMap<GenomeRelease, enum_map::EnumMap<AnnoDb, CircuitBreaker>>,
+}
+
+impl CircuitBreakers {
+    /// Create with the given `config`.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Return the breaker of `anno_db` for `genome_release`.
+    pub fn get(&self, genome_release: GenomeRelease, anno_db: AnnoDb) -> &CircuitBreaker {
+        &self.breakers[genome_release][anno_db]
+    }
+
+    /// Run `fetch` on the database `anno_db` for `genome_release` unless it is degraded.
+    ///
+    /// Returns `None` if the database is degraded.  `probe` performs the cheap read once the
+    /// cool-down has passed.  Errors of `fetch` are returned and counted unless they are
+    /// caused by invalid request parameters.
+    pub fn call<T>(
+        &self,
+        genome_release: GenomeRelease,
+        anno_db: AnnoDb,
+        probe: impl FnOnce() -> Result<(), anyhow::Error>,
+        fetch: impl FnOnce() -> Result<T, CustomError>,
+    ) -> Result<Option<T>, CustomError> {
+        let breaker = self.get(genome_release, anno_db);
+        match breaker.decide(&self.config, Instant::now()) {
+            Decision::Allow => (),
+            Decision::Probe => {
+                let result = probe().map_err(|e| e.to_string());
+                let ok = result.is_ok();
+                breaker.record_probe(Instant::now(), result);
+                if !ok {
+                    return Ok(None);
+                }
+                tracing::info!(
+                    "probe of degraded database {} ({}) succeeded, retrying",
+                    anno_db,
+                    genome_release
+                );
+            }
+            Decision::Reject => return Ok(None),
+        }
+
+        match fetch() {
+            Ok(value) => {
+                breaker.record_success();
+                Ok(Some(value))
+            }
+            Err(err) if err.param().is_some() => Err(err),
+            Err(err) => {
+                if breaker.record_error(&self.config, Instant::now(), err.to_string()) {
+                    tracing::warn!(
+                        "marking database {} ({}) as degraded after repeated errors: {}",
+                        anno_db,
+                        genome_release,
+                        err
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Return warnings for the degraded databases of `genome_release` among `anno_dbs`.
+    pub fn warnings(
+        &self,
+        genome_release: GenomeRelease,
+        anno_dbs: impl IntoIterator<Item = AnnoDb>,
+    ) -> Vec<String> {
+        anno_dbs
+            .into_iter()
+            .filter(|anno_db| self.get(genome_release, *anno_db).is_open())
+            .map(|anno_db| {
+                format!(
+                    "database {} is degraded after repeated errors and omitted from the response",
+                    anno_db
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            threshold: 3,
+            cooldown: Duration::from_secs(10),
+        }
+    }
+
+    fn error(breaker: &CircuitBreaker, now: Instant) -> bool {
+        breaker.record_error(&config(), now, "corrupted".into())
+    }
+
+    #[test]
+    fn opens_after_threshold() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+
+        assert!(!error(&breaker, now));
+        assert!(!error(&breaker, now));
+        breaker.record_success();
+        assert!(!error(&breaker, now));
+        assert!(!error(&breaker, now));
+        assert_eq!(breaker.decide(&config(), now), Decision::Allow);
+        assert!(error(&breaker, now));
+
+        assert!(breaker.is_open());
+        assert_eq!(breaker.decide(&config(), now), Decision::Reject);
+        assert_eq!(
+            breaker.status(&config(), now + Duration::from_secs(4)),
+            CircuitBreakerStatus {
+                state: CircuitBreakerState::Open,
+                consecutive_errors: 3,
+                trips: 1,
+                retry_in_secs: Some(6.0),
+                last_error: Some("corrupted".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn disabled_with_zero_threshold() {
+        let breaker = CircuitBreaker::default();
+        let config = Config {
+            threshold: 0,
+            ..config()
+        };
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert!(!breaker.record_error(&config, now, "corrupted".into()));
+        }
+        assert_eq!(breaker.decide(&config, now), Decision::Allow);
+    }
+
+    #[test]
+    fn probe_once_per_cooldown() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        (0..3).for_each(|_| {
+            error(&breaker, now);
+        });
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(breaker.decide(&config(), later), Decision::Probe);
+        assert_eq!(breaker.decide(&config(), later), Decision::Reject);
+
+        breaker.record_probe(later, Err("still corrupted".into()));
+        assert!(breaker.is_open());
+        assert_eq!(
+            breaker.decide(&config(), later + Duration::from_secs(9)),
+            Decision::Reject
+        );
+        assert_eq!(
+            breaker.decide(&config(), later + Duration::from_secs(10)),
+            Decision::Probe
+        );
+    }
+
+    #[test]
+    fn recovers_after_probe() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        (0..3).for_each(|_| {
+            error(&breaker, now);
+        });
+        let later = now + Duration::from_secs(10);
+        assert_eq!(breaker.decide(&config(), later), Decision::Probe);
+        breaker.record_probe(later, Ok(()));
+
+        assert_eq!(
+            breaker.status(&config(), later).state,
+            CircuitBreakerState::HalfOpen
+        );
+        assert_eq!(breaker.decide(&config(), later), Decision::Allow);
+        breaker.record_success();
+
+        let status = breaker.status(&config(), later);
+        assert_eq!(status.state, CircuitBreakerState::Closed);
+        assert_eq!(status.consecutive_errors, 0);
+        assert_eq!(status.trips, 1);
+    }
+
+    #[test]
+    fn reopens_on_error_when_half_open() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        (0..3).for_each(|_| {
+            error(&breaker, now);
+        });
+        let later = now + Duration::from_secs(10);
+        breaker.decide(&config(), later);
+        breaker.record_probe(later, Ok(()));
+
+        assert!(error(&breaker, later));
+        assert!(breaker.is_open());
+        assert_eq!(breaker.status(&config(), later).trips, 2);
+    }
+
+    #[test]
+    fn call_skips_degraded_and_ignores_param_errors() {
+        let breakers = CircuitBreakers::new(Config {
+            threshold: 2,
+            cooldown: Duration::from_secs(3600),
+        });
+        let call = |result: Result<u32, CustomError>| {
+            breakers.call(GenomeRelease::Grch37, AnnoDb::Cadd, || Ok(()), || result)
+        };
+
+        for _ in 0..3 {
+            assert!(call(Err(CustomError::invalid_param("pos", None, "missing"))).is_err());
+        }
+        assert_eq!(call(Ok(1)).unwrap(), Some(1));
+        assert!(breakers
+            .warnings(GenomeRelease::Grch37, [AnnoDb::Cadd])
+            .is_empty());
+
+        for _ in 0..2 {
+            assert!(call(Err(CustomError::new(anyhow::anyhow!("corrupted")))).is_err());
+        }
+        assert_eq!(call(Ok(1)).unwrap(), None);
+        assert_eq!(
+            breakers.warnings(GenomeRelease::Grch37, [AnnoDb::Cadd, AnnoDb::Dbsnp]),
+            vec![String::from(
+                "database cadd is degraded after repeated errors and omitted from the response"
+            )]
+        );
+        assert!(!breakers.get(GenomeRelease::Grch38, AnnoDb::Cadd).is_open());
+    }
+}
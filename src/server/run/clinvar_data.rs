@@ -6986,6 +6986,73 @@ impl TryFrom<pbs::clinvar_data::extracted_vars::ExtractedVcvRecord> for ClinvarE
     }
 }
 
+/// Submission-level details of one SCV.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ClinvarSubmissionDetails {
+    /// The SCV accession.
+    pub accession: Option<ClinvarVersionedAccession>,
+    /// The identifiers of the submitter.
+    pub submitter_identifiers: Option<ClinvarSubmitterIdentifiers>,
+    /// The germline or oncogenicity classification or the somatic clinical impact.
+    pub classification: Option<String>,
+    /// The review status, if specified.
+    pub review_status: Option<ClinvarSubmitterReviewStatus>,
+    /// Date of last evaluation.
+    pub date_last_evaluated: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl TryFrom<pbs::clinvar::minimal::SubmissionDetails> for ClinvarSubmissionDetails {
+    type Error = anyhow::Error;
+
+    fn try_from(value: pbs::clinvar::minimal::SubmissionDetails) -> Result<Self, Self::Error> {
+        let review_status = pbs::clinvar_data::clinvar_public::SubmitterReviewStatus::try_from(
+            value.review_status,
+        )?;
+        Ok(Self {
+            accession: value.accession.map(ClinvarVersionedAccession::from),
+            submitter_identifiers: value
+                .submitter_identifiers
+                .map(ClinvarSubmitterIdentifiers::from),
+            classification: value.classification,
+            review_status: if review_status
+                == pbs::clinvar_data::clinvar_public::SubmitterReviewStatus::Unspecified
+            {
+                None
+            } else {
+                Some(ClinvarSubmitterReviewStatus::try_from(review_status)?)
+            },
+            date_last_evaluated: value.date_last_evaluated.map(|x| {
+                chrono::DateTime::<chrono::Utc>::from_timestamp(x.seconds, x.nanos as u32)
+                    .unwrap_or_default()
+            }),
+        })
+    }
+}
+
+/// Submission-level details of one VCV.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ClinvarVcvDetails {
+    /// The VCV accession.
+    pub accession: Option<ClinvarVersionedAccession>,
+    /// The submissions (SCVs).
+    pub submissions: Vec<ClinvarSubmissionDetails>,
+}
+
+impl TryFrom<pbs::clinvar::minimal::VcvDetails> for ClinvarVcvDetails {
+    type Error = anyhow::Error;
+
+    fn try_from(value: pbs::clinvar::minimal::VcvDetails) -> Result<Self, Self::Error> {
+        Ok(Self {
+            accession: value.accession.map(ClinvarVersionedAccession::from),
+            submissions: value
+                .submissions
+                .into_iter()
+                .map(ClinvarSubmissionDetails::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
 /// Enumeration for the type of the variant.
 #[derive(
     Clone,
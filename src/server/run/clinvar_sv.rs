@@ -7,9 +7,16 @@ use actix_web::{
     web::{self, Data, Json, Path},
 };
 
-use crate::common::{cli::GenomeRelease, spdi};
+use crate::{
+    clinvar_sv::index::record_interval,
+    common::{
+        cli::{self, GenomeRelease},
+        decode::DecodeError,
+        spdi,
+    },
+};
 
-use super::error::CustomError;
+use super::{error::CustomError, params};
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
 
 use crate::pbs::clinvar_data::extracted_vars::VariationType as PbVariationType;
@@ -18,7 +25,7 @@ use crate::server::run::clinvar_data::ClinvarExtractedVariationType;
 /// The default page size to use.
 const DEFAULT_PAGE_SIZE: u32 = 100;
 /// The default minimal overlap.
-const DEFAULT_MIN_OVERLAP: f64 = 0.5;
+pub(crate) const DEFAULT_MIN_OVERLAP: f64 = 0.5;
 
 /// Parameters for `handle()`.
 ///
@@ -70,80 +77,59 @@ where
     }
 }
 
-/// Implementation of both endpoints.
-async fn handle_impl(
-    data: Data<crate::server::run::WebServerData>,
-    _path: Path<()>,
-    query: Request,
-) -> actix_web::Result<crate::pbs::clinvar::sv::ResponsePage, CustomError> {
-    // Parse out genome release.
-    let genome_release: GenomeRelease =
-        query
-            .clone()
-            .genome_release
-            .parse()
-            .map_err(|e: strum::ParseError| {
-                CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
-            })?;
-    // Obtain handle of interval trees datastructure for genome release.
-    let trees = if let Some(trees) = data.clinvar_svs[genome_release].as_ref() {
-        trees
-    } else {
-        Err(anyhow::anyhow!(
-            "no clinvar-sv database for genome release {}",
-            genome_release
-        ))
-        .map_err(CustomError::new)?
-    };
+/// Query `trees` for records overlapping `chromosome:start-stop`.
+///
+/// The records are filtered by variation type or, if no types are given, by the minimal
+/// reciprocal overlap, and sorted by decreasing overlap.
+pub(crate) fn query_overlapping(
+    trees: &crate::clinvar_sv::cli::query::IntervalTrees,
+    chromosome: &str,
+    start: u32,
+    stop: u32,
+    variation_types: &[i32],
+    min_overlap: f64,
+) -> Result<Vec<crate::pbs::clinvar::sv::ResponseRecord>, CustomError> {
     // Create SPDI range to query for and query the tree with this.
     let spdi_range = spdi::Range {
-        sequence: query.chromosome.replace("chr", "").to_string(),
-        start: query.start as i32,
-        end: query.stop as i32,
+        sequence: chromosome.replace("chr", "").to_string(),
+        start: start as i32,
+        end: stop as i32,
     };
-    let records = trees.query(&spdi_range).map_err(|e| {
-        CustomError::new(anyhow::anyhow!(
-            "problem querying clinvar-sv database: {}",
-            e
-        ))
-    })?;
+    let records = trees
+        .query(&spdi_range)
+        .map_err(|e| match e.downcast::<DecodeError>() {
+            Ok(e) => CustomError::from(e),
+            Err(e) => CustomError::new(anyhow::anyhow!(
+                "problem querying clinvar-sv database: {}",
+                e
+            )),
+        })?;
     // Filter the records.
-    let variation_types = query
-        .variation_types
-        .as_ref()
-        .map(|vs| vs.iter().map(|v| *v as i32).collect::<Vec<_>>())
-        .unwrap_or_default();
     let records = {
         let mut records = records
             .into_iter()
             .filter_map(|record| {
-                let crate::pbs::clinvar_data::clinvar_public::location::SequenceLocation {
-                    start,
-                    stop,
-                    inner_start,
-                    inner_stop,
-                    outer_start,
-                    outer_stop,
-                    ..
-                } = record
-                    .sequence_location
-                    .clone()
-                    .expect("missing sequence_location");
-                let (start, stop) = if let (Some(start), Some(stop)) = (start, stop) {
-                    (start, stop)
-                } else if let (Some(inner_start), Some(inner_stop)) = (inner_start, inner_stop) {
-                    (inner_start, inner_stop)
-                } else if let (Some(outer_start), Some(outer_stop)) = (outer_start, outer_stop) {
-                    (outer_start, outer_stop)
-                } else {
-                    let accession = record.accession.clone().expect("missing accession");
-                    let vcv = format!("{}.{}", &accession.accession, &accession.version);
-                    tracing::warn!("skipping record because no start/stop: {}", &vcv);
-                    return None;
+                let (_, interval, _) = match record_interval(&record) {
+                    Ok(Some(interval)) => interval,
+                    Ok(None) => {
+                        let accession = record.accession.as_ref().expect("missing accession");
+                        tracing::warn!(
+                            "skipping record because no start/stop: {}.{}",
+                            &accession.accession,
+                            &accession.version
+                        );
+                        return None;
+                    }
+                    Err(e) => {
+                        tracing::warn!("skipping record: {}", e);
+                        return None;
+                    }
                 };
 
-                let overlap =
-                    reciprocal_overlap(&((query.start - 1)..query.stop), &((start - 1)..stop));
+                let overlap = reciprocal_overlap(
+                    &((start - 1)..stop),
+                    &((interval.start as u32)..(interval.end as u32)),
+                );
                 Some(crate::pbs::clinvar::sv::ResponseRecord {
                     record: Some(record),
                     overlap,
@@ -156,7 +142,6 @@ async fn handle_impl(
                         .contains(&record.record.as_ref().expect("no record").variation_type);
                 }
                 // filter by overlap if specified
-                let min_overlap = query.min_overlap.unwrap_or(DEFAULT_MIN_OVERLAP);
                 if record.overlap < min_overlap {
                     return false;
                 }
@@ -167,6 +152,52 @@ async fn handle_impl(
         records.sort_by(|a, b| b.overlap.partial_cmp(&a.overlap).unwrap());
         records
     };
+
+    Ok(records)
+}
+
+/// Implementation of both endpoints.
+async fn handle_impl(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: Request,
+) -> actix_web::Result<crate::pbs::clinvar::sv::ResponsePage, CustomError> {
+    // Parse out genome release.
+    let genome_release: GenomeRelease =
+        query
+            .clone()
+            .genome_release
+            .parse()
+            .map_err(|e: strum::ParseError| {
+                CustomError::new(anyhow::anyhow!("problem getting genome release: {}", e))
+            })?;
+    // Starts beyond the end of the chromosome are rejected, stops are clamped.
+    params::position_in_contig("start", query.start, genome_release, &query.chromosome)?;
+    let (stop, _) = cli::clamp_stop(genome_release, &query.chromosome, query.stop as usize);
+    // Obtain handle of interval trees datastructure for genome release.
+    let trees = if let Some(trees) = data.clinvar_svs[genome_release].as_ref() {
+        trees
+    } else {
+        Err(anyhow::anyhow!(
+            "no clinvar-sv database for genome release {}",
+            genome_release
+        ))
+        .map_err(CustomError::new)?
+    };
+    // Query the tree and filter the records.
+    let variation_types = query
+        .variation_types
+        .as_ref()
+        .map(|vs| vs.iter().map(|v| *v as i32).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let records = query_overlapping(
+        trees,
+        &query.chromosome,
+        query.start,
+        stop as u32,
+        &variation_types,
+        query.min_overlap.unwrap_or(DEFAULT_MIN_OVERLAP),
+    )?;
     // Compute pagination information.
     let per_page = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
     let total_pages = (records.len() as u32 + 1) / per_page;
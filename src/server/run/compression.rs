@@ -0,0 +1,148 @@
+//! Optional response compression, see `--enable-compression`.
+//!
+//! The JSON responses, in particular of `/annos/range`, compress 10-20x.  The compression is
+//! negotiated with the `Accept-Encoding` request header; responses to clients that do not
+//! accept any supported encoding are sent uncompressed.  Streamed bodies of unknown size are
+//! compressed chunk by chunk and sent with chunked transfer encoding.
+
+use actix_web::middleware::{Compress, Condition};
+
+/// Return the response compression middleware, a no-op unless `enable_compression`.
+pub fn middleware(enable_compression: bool) -> Condition<Compress> {
+    Condition::new(enable_compression, Compress::default())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read as _;
+
+    use actix_web::{
+        body::{BodySize, MessageBody},
+        get,
+        http::header,
+        web::{Bytes, Data},
+        App, HttpResponse,
+    };
+
+    use crate::{
+        common::cli::GenomeRelease,
+        server::run::{annos_range, AnnoDb, WebServerData, WithVersionSpec},
+    };
+
+    /// NDJSON body of unknown size that is streamed line by line.
+    struct NdjsonBody(std::collections::VecDeque<serde_json::Value>);
+
+    impl MessageBody for NdjsonBody {
+        type Error = std::convert::Infallible;
+
+        fn size(&self) -> BodySize {
+            BodySize::Stream
+        }
+
+        fn poll_next(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<Bytes, Self::Error>>> {
+            std::task::Poll::Ready(
+                self.0
+                    .pop_front()
+                    .map(|value| Ok(Bytes::from(format!("{}\n", value)))),
+            )
+        }
+    }
+
+    #[get("/ndjson")]
+    async fn handle_ndjson() -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type("application/x-ndjson")
+            .body(NdjsonBody(
+                (0..1000)
+                    .map(|i| serde_json::json!({"index": i, "chrom": "1", "pos": 1000 + i}))
+                    .collect(),
+            ))
+    }
+
+    /// Return the server data with the dbSNP example database.
+    fn data_with_dbsnp() -> Result<WebServerData, anyhow::Error> {
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::Dbsnp] = Some(WithVersionSpec {
+            data: rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+                ["meta", AnnoDb::Dbsnp.cf_name()],
+                false,
+            )?,
+            version_spec: None,
+        });
+        Ok(data)
+    }
+
+    /// Request `uri` with and without `Accept-Encoding: gzip` and return the content encoding
+    /// of the first response and both decoded bodies.
+    async fn get_with_and_without_gzip(
+        enable_compression: bool,
+        uri: &str,
+    ) -> Result<(Option<String>, Vec<u8>, Vec<u8>), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(Data::new(data_with_dbsnp()?))
+                .service(annos_range::handle)
+                .service(handle_ndjson)
+                .wrap(super::middleware(enable_compression)),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(uri)
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = actix_web::test::call_service(&app, req).await;
+        assert!(response.status().is_success());
+        let content_encoding = response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = actix_web::test::read_body(response).await.to_vec();
+        let body = if content_encoding.as_deref() == Some("gzip") {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(body.as_slice()).read_to_end(&mut decoded)?;
+            decoded
+        } else {
+            body
+        };
+
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let response = actix_web::test::call_service(&app, req).await;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let uncompressed = actix_web::test::read_body(response).await.to_vec();
+
+        Ok((content_encoding, body, uncompressed))
+    }
+
+    #[rstest::rstest]
+    #[case::range(
+        "/annos/range?genome_release=grch37&chromosome=chr17&start=41260000&stop=41280000"
+    )]
+    #[case::ndjson("/ndjson")]
+    #[actix_web::test]
+    async fn gzip_same_as_uncompressed(#[case] uri: &str) -> Result<(), anyhow::Error> {
+        let (content_encoding, body, uncompressed) = get_with_and_without_gzip(true, uri).await?;
+
+        assert_eq!(content_encoding.as_deref(), Some("gzip"));
+        assert!(!uncompressed.is_empty());
+        assert_eq!(body, uncompressed);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn disabled_not_compressed() -> Result<(), anyhow::Error> {
+        let (content_encoding, body, uncompressed) =
+            get_with_and_without_gzip(false, "/ndjson").await?;
+
+        assert_eq!(content_encoding, None);
+        assert_eq!(body, uncompressed);
+
+        Ok(())
+    }
+}
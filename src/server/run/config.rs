@@ -0,0 +1,494 @@
+//! Reading the arguments of `server run` from a config file and the environment.
+//!
+//! Each argument of `server run` can also be given as a key of the TOML file passed with
+//! `--config` and as an environment variable with the prefix `ANNONARS_`.  Both are named
+//! like the argument with underscores, e.g., `listen_port = 8082` in the file or
+//! `ANNONARS_LISTEN_PORT=8082` for `--listen-port 8082`.  In the file, arguments that can be
+//! given more than once are arrays and flags are booleans; in the environment, such values
+//! are separated by commas and flags are `true` or `false`.
+//!
+//! For each argument, the value is taken from the first of these that has it:
+//!
+//! 1. the command line,
+//! 2. the environment,
+//! 3. the config file,
+//! 4. the default of the argument.
+//!
+//! Lists are not merged, e.g., `--path-cadd` on the command line replaces all `path_cadd`
+//! of the file.  The values from the file and the environment are validated per key with
+//! the same parsers as the command line and errors name the source of the offending value.
+
+use std::collections::BTreeMap;
+
+use clap::{parser::ValueSource, ArgAction, CommandFactory as _, Parser as _};
+
+use super::Args;
+
+/// Prefix of the environment variables.
+pub const ENV_PREFIX: &str = "ANNONARS_";
+
+/// Source of a value of the arguments other than the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The config file with the given path.
+    File(String),
+    /// The environment variable with the given name.
+    Env(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "config file {}", path),
+            Source::Env(name) => write!(f, "environment variable {}", name),
+        }
+    }
+}
+
+/// An argument of `server run` that can be set in the config file and the environment.
+#[derive(Debug, Clone)]
+struct ArgSpec {
+    /// The id, i.e., the name of the field in `Args` and the key in the config file.
+    id: String,
+    /// The long flag without the leading dashes.
+    long: String,
+    /// Whether the argument is a flag without value.
+    is_flag: bool,
+    /// Whether the argument can be given more than once.
+    is_multiple: bool,
+}
+
+impl ArgSpec {
+    /// Return the name of the environment variable.
+    fn env_name(&self) -> String {
+        format!("{}{}", ENV_PREFIX, self.id.to_uppercase())
+    }
+
+    /// Return the command line arguments for the config file or command line `value`.
+    fn argv_from_value(&self, value: &serde_json::Value) -> Result<Vec<String>, String> {
+        let flag = format!("--{}", self.long);
+        match value {
+            serde_json::Value::Null => Ok(Vec::new()),
+            serde_json::Value::Bool(value) if self.is_flag => {
+                Ok(value.then_some(flag).into_iter().collect())
+            }
+            _ if self.is_flag => Err(String::from("expected a boolean")),
+            serde_json::Value::Array(values) if self.is_multiple => values
+                .iter()
+                .map(|value| match value {
+                    serde_json::Value::String(value) => Ok([flag.clone(), value.clone()]),
+                    serde_json::Value::Number(value) => Ok([flag.clone(), value.to_string()]),
+                    _ => Err(String::from("expected an array of strings or numbers")),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|pairs| pairs.concat()),
+            serde_json::Value::String(value) => Ok(vec![flag, value.clone()]),
+            serde_json::Value::Number(value) => Ok(vec![flag, value.to_string()]),
+            _ if self.is_multiple => Err(String::from("expected an array")),
+            _ => Err(String::from("expected a string or number")),
+        }
+    }
+
+    /// Return the command line arguments for the environment variable `value`.
+    fn argv_from_env(&self, value: &str) -> Result<Vec<String>, String> {
+        let flag = format!("--{}", self.long);
+        if self.is_flag {
+            match value {
+                "true" | "1" => Ok(vec![flag]),
+                "false" | "0" | "" => Ok(Vec::new()),
+                _ => Err(format!("invalid value {:?}, expected true or false", value)),
+            }
+        } else if self.is_multiple {
+            Ok(value
+                .split(',')
+                .filter(|value| !value.is_empty())
+                .flat_map(|value| [flag.clone(), value.to_string()])
+                .collect())
+        } else {
+            Ok(vec![flag, value.to_string()])
+        }
+    }
+}
+
+/// Return the arguments of `server run` that can be set in the config file and environment.
+fn arg_specs() -> Vec<ArgSpec> {
+    Args::command()
+        .get_arguments()
+        .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "help" | "version"))
+        .filter_map(|arg| {
+            Some(ArgSpec {
+                id: arg.get_id().to_string(),
+                long: arg.get_long()?.to_string(),
+                is_flag: matches!(arg.get_action(), ArgAction::SetTrue),
+                is_multiple: matches!(arg.get_action(), ArgAction::Append),
+            })
+        })
+        .collect()
+}
+
+/// Command line arguments for each argument id from one source.
+type Layer = BTreeMap<String, Vec<String>>;
+
+/// Check that `argv` for the argument `spec` from `source` is accepted by `Args`.
+fn validate(spec: &ArgSpec, argv: &[String], source: &Source) -> Result<(), anyhow::Error> {
+    Args::try_parse_from(std::iter::once("run").chain(argv.iter().map(String::as_str)))
+        .map(|_| ())
+        .map_err(|e| {
+            crate::ErrorCategory::Usage.error(format!(
+                "invalid value for {} in {}: {}",
+                &spec.id,
+                source,
+                e.to_string()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ")
+            ))
+        })
+}
+
+/// Read the config file `path` into a layer.
+fn file_layer(path: &str, specs: &[ArgSpec]) -> Result<Layer, anyhow::Error> {
+    let source = Source::File(path.to_string());
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        crate::ErrorCategory::NotFound.error(format!("could not read {}: {}", &source, e))
+    })?;
+    let table: serde_json::Map<String, serde_json::Value> =
+        toml::from_str(&content).map_err(|e| {
+            crate::ErrorCategory::InputParse.error(format!("invalid {}: {}", &source, e))
+        })?;
+
+    let mut result = Layer::new();
+    for (key, value) in table {
+        let spec = specs.iter().find(|spec| spec.id == key).ok_or_else(|| {
+            crate::ErrorCategory::Usage.error(format!("unknown key {} in {}", &key, &source))
+        })?;
+        let argv = spec.argv_from_value(&value).map_err(|e| {
+            crate::ErrorCategory::Usage
+                .error(format!("invalid value for {} in {}: {}", &key, &source, e))
+        })?;
+        validate(spec, &argv, &source)?;
+        result.insert(key, argv);
+    }
+    Ok(result)
+}
+
+/// Read the environment variables `vars` into a layer, ignoring unknown names.
+fn env_layer(
+    vars: impl IntoIterator<Item = (String, String)>,
+    specs: &[ArgSpec],
+) -> Result<Layer, anyhow::Error> {
+    let vars = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut result = Layer::new();
+    for spec in specs {
+        let name = spec.env_name();
+        if let Some(value) = vars.get(&name) {
+            let source = Source::Env(name);
+            let argv = spec.argv_from_env(value).map_err(|e| {
+                crate::ErrorCategory::Usage.error(format!(
+                    "invalid value for {} in {}: {}",
+                    &spec.id, &source, e
+                ))
+            })?;
+            validate(spec, &argv, &source)?;
+            result.insert(spec.id.clone(), argv);
+        }
+    }
+    Ok(result)
+}
+
+/// Return the layer of the arguments in `cli` given on the command line as per `matches`.
+fn cli_layer(
+    cli: &Args,
+    matches: &clap::ArgMatches,
+    specs: &[ArgSpec],
+) -> Result<Layer, anyhow::Error> {
+    let values = serde_json::to_value(cli)?;
+    let mut result = Layer::new();
+    for spec in specs {
+        if matches.value_source(&spec.id) == Some(ValueSource::CommandLine) {
+            let argv = spec
+                .argv_from_value(&values[&spec.id])
+                .map_err(|e| anyhow::anyhow!("invalid value for {}: {}", &spec.id, e))?;
+            result.insert(spec.id.clone(), argv);
+        }
+    }
+    Ok(result)
+}
+
+/// Merge the arguments `cli` given on the command line as per `matches` with the config
+/// file of `--config` and the environment variables `vars`, see the module documentation.
+pub fn resolve_with_env(
+    cli: &Args,
+    matches: &clap::ArgMatches,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Args, anyhow::Error> {
+    let specs = arg_specs();
+    let file = cli
+        .config
+        .as_deref()
+        .map(|path| file_layer(path, &specs))
+        .transpose()?
+        .unwrap_or_default();
+    let env = env_layer(vars, &specs)?;
+    if file.is_empty() && env.is_empty() {
+        return Ok(cli.clone());
+    }
+    let cli_values = cli_layer(cli, matches, &specs)?;
+
+    let mut argv = vec![String::from("run")];
+    for spec in &specs {
+        for (layer, source) in [
+            (&cli_values, "command line"),
+            (&env, "environment"),
+            (&file, "config file"),
+        ] {
+            if let Some(values) = layer.get(&spec.id) {
+                tracing::debug!("{} from {}", &spec.id, source);
+                argv.extend(values.iter().cloned());
+                break;
+            }
+        }
+    }
+    let mut result = Args::try_parse_from(&argv)
+        .map_err(|e| crate::ErrorCategory::Usage.error(format!("invalid arguments: {}", e)))?;
+    result.config = cli.config.clone();
+    Ok(result)
+}
+
+/// Merge the arguments `cli` given on the command line as per `matches` with the config
+/// file of `--config` and the environment, see the module documentation.
+pub fn resolve(cli: &Args, matches: &clap::ArgMatches) -> Result<Args, anyhow::Error> {
+    resolve_with_env(cli, matches, std::env::vars())
+}
+
+#[cfg(test)]
+mod test {
+    use clap::FromArgMatches as _;
+    use temp_testdir::TempDir;
+
+    use super::*;
+
+    /// Parse the command line `args` of `server run` and resolve them with `vars`.
+    fn resolve_args(args: &[&str], vars: &[(&str, &str)]) -> Result<Args, anyhow::Error> {
+        let matches = Args::command()
+            .try_get_matches_from(std::iter::once("run").chain(args.iter().copied()))?;
+        let cli = Args::from_arg_matches(&matches)?;
+        resolve_with_env(
+            &cli,
+            &matches,
+            vars.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+    }
+
+    /// Write the config file `content` to `temp` and return its path.
+    fn config_file(temp: &TempDir, content: &str) -> String {
+        let path = temp.join("server.toml");
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Return `Args` with all arguments set to values other than their defaults.
+    fn fully_populated() -> Args {
+        Args {
+            config: None,
+            path_genes: Some("genes".into()),
+            path_clinvar_genes: Some("clinvar-genes".into()),
+            path_clinvar: vec!["clinvar-1".into(), "clinvar-2".into()],
+            path_clinvar_sv: vec!["clinvar-sv".into()],
+            path_regions: vec![("panel".into(), "regions".into())],
+            path_functional: vec!["functional".into()],
+            path_cadd: vec!["cadd-37".into(), "cadd-38".into()],
+            cadd_col_raw: "raw".into(),
+            cadd_col_phred: "phred".into(),
+            path_dbsnp: vec!["dbsnp".into()],
+            path_dbnsfp: vec!["dbnsfp".into()],
+            path_dbscsnv: vec!["dbscsnv".into()],
+            path_gnomad_mtdna: vec!["gnomad-mtdna".into()],
+            path_gnomad_exomes: vec!["gnomad-exomes".into()],
+            path_gnomad_genomes: vec!["gnomad-genomes".into()],
+            path_helixmtdb: vec!["helixmtdb".into()],
+            path_alphamissense: vec!["alphamissense".into()],
+            path_ucsc_conservation: vec!["ucsc-conservation".into()],
+            path_gnomad_coverage: vec!["gnomad-coverage".into()],
+            listen_host: "0.0.0.0".into(),
+            listen_port: 8082,
+            enable_compression: true,
+            readiness_exclude: vec!["cadd".into()],
+            variant_cache_size: 1000,
+            variant_cache_ttl_secs: 60,
+            rocksdb_block_cache_mb: Some(512),
+            rocksdb_mmap_reads: vec!["gnomad_genomes=true".into()],
+            admin_token: Some("s3cr3t".into()),
+            enable_admin_raw: true,
+            range_decode_threads: 4,
+            circuit_breaker_threshold: 10,
+            circuit_breaker_cooldown_secs: 120,
+            contig_allowlist: vec!["1".into(), "X".into()],
+            cache_dir: Some("cache".into()),
+            audit_log_path: Some("audit.jsonl".into()),
+            audit_log_max_mb: 10,
+            audit_log_keep: 2,
+            startup_timeout_secs: Some(300),
+            strict_specs: true,
+        }
+    }
+
+    #[test]
+    fn round_trip_toml() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let expected = fully_populated();
+        let path = config_file(&temp, &toml::to_string(&expected)?);
+
+        let mut args = resolve_args(&["--config", &path], &[])?;
+
+        assert_eq!(args.config, Some(path));
+        args.config = None;
+        assert_eq!(
+            serde_json::to_value(&args)?,
+            serde_json::to_value(&expected)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn every_argument_has_a_key() -> Result<(), anyhow::Error> {
+        let values = serde_json::to_value(fully_populated())?;
+        let keys = arg_specs()
+            .into_iter()
+            .map(|spec| spec.id)
+            .collect::<Vec<_>>();
+
+        for key in &keys {
+            assert!(values.get(key).is_some(), "{}", key);
+        }
+        assert_eq!(keys.len() + 1, values.as_object().unwrap().len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn precedence() -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let path = config_file(
+            &temp,
+            r#"
+            listen_host = "file-host"
+            listen_port = 1
+            variant_cache_size = 10
+            path_cadd = ["file-cadd-37", "file-cadd-38"]
+            path_dbsnp = ["file-dbsnp"]
+            "#,
+        );
+
+        let args = resolve_args(
+            &[
+                "--config",
+                &path,
+                "--listen-port",
+                "3",
+                "--path-cadd",
+                "cli-cadd",
+            ],
+            &[
+                ("ANNONARS_LISTEN_PORT", "2"),
+                ("ANNONARS_VARIANT_CACHE_SIZE", "20"),
+                ("ANNONARS_PATH_DBSNP", "env-dbsnp-37,env-dbsnp-38"),
+                ("ANNONARS_ENABLE_COMPRESSION", "true"),
+                ("ANNONARS_UNRELATED", "ignored"),
+                ("OTHER_LISTEN_HOST", "ignored"),
+            ],
+        )?;
+
+        // command line > environment > config file > default
+        assert_eq!(args.listen_port, 3);
+        assert_eq!(args.path_cadd, vec![String::from("cli-cadd")]);
+        assert_eq!(args.variant_cache_size, 20);
+        assert_eq!(
+            args.path_dbsnp,
+            vec![String::from("env-dbsnp-37"), String::from("env-dbsnp-38")]
+        );
+        assert!(args.enable_compression);
+        assert_eq!(args.listen_host, "file-host");
+        assert_eq!(args.variant_cache_ttl_secs, 3600);
+
+        Ok(())
+    }
+
+    #[test]
+    fn without_config_and_env() -> Result<(), anyhow::Error> {
+        let args = resolve_args(&["--listen-port", "3"], &[("OTHER", "ignored")])?;
+
+        assert_eq!(args.listen_port, 3);
+        assert_eq!(args.listen_host, "127.0.0.1");
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::file_invalid_port(
+        "listen_port = 123456",
+        &[],
+        "invalid value for listen_port in config file"
+    )]
+    #[case::file_wrong_type("listen_port = [1]", &[], "invalid value for listen_port in config file")]
+    #[case::file_flag_not_bool(
+        "enable_compression = \"yes\"",
+        &[],
+        "invalid value for enable_compression in config file"
+    )]
+    #[case::file_invalid_regions(
+        "path_regions = [\"no-name\"]",
+        &[],
+        "invalid value for path_regions in config file"
+    )]
+    #[case::file_unknown_key("no_such_key = 1", &[], "unknown key no_such_key in config file")]
+    #[case::file_nested_config("config = \"other.toml\"", &[], "unknown key config in config file")]
+    #[case::env_invalid_port(
+        "",
+        &[("ANNONARS_LISTEN_PORT", "port")],
+        "invalid value for listen_port in environment variable ANNONARS_LISTEN_PORT"
+    )]
+    #[case::env_flag_not_bool(
+        "",
+        &[("ANNONARS_ENABLE_COMPRESSION", "yes")],
+        "invalid value for enable_compression in environment variable ANNONARS_ENABLE_COMPRESSION"
+    )]
+    fn invalid_values(#[case] content: &str, #[case] vars: &[(&str, &str)], #[case] message: &str) {
+        let temp = TempDir::default();
+        let path = config_file(&temp, content);
+
+        let err = resolve_args(&["--config", &path], vars).unwrap_err();
+
+        assert!(err.to_string().starts_with(message), "{}", err);
+        assert_eq!(crate::ErrorCategory::of(&err), crate::ErrorCategory::Usage);
+    }
+
+    #[test]
+    fn invalid_cli_value() {
+        let err = resolve_args(&["--listen-port", "port"], &[]).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<clap::Error>().map(clap::Error::kind),
+            Some(clap::error::ErrorKind::ValueValidation)
+        );
+    }
+
+    #[test]
+    fn missing_config_file() {
+        let err = resolve_args(&["--config", "/no/such/server.toml"], &[]).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("could not read config file /no/such/server.toml"),
+            "{}",
+            err
+        );
+    }
+}
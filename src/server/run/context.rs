@@ -0,0 +1,182 @@
+//! Genomic context of the queries of `/annos/variant` with `context=true`.
+//!
+//! The nearest genes and functional elements up- and downstream of the variant are found
+//! with the positional indices written by `genes import` and `functional import`, see
+//! `common::pos_index`.  Functional element databases are given to the server with
+//! `--path-functional`; databases imported without the index are opened with a warning and
+//! give no context.
+
+use crate::common::{cli::GenomeRelease, pos_index};
+
+use super::{error::CustomError, WebServerData};
+
+/// Nearest features of a variant from each database.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct VariantContext {
+    /// Maximal distance that has been searched for features.
+    pub max_window: u32,
+    /// Nearest genes by HGNC ID, `None` if the genes database is not configured or has no
+    /// positional index.
+    pub genes: Option<pos_index::NearestFeatures>,
+    /// Nearest functional elements by accession, `None` if the functional elements database
+    /// is not configured or has no positional index.
+    pub functional: Option<pos_index::NearestFeatures>,
+}
+
+/// Return the nearest features in the index `cf_name` of `db`, `None` without the index.
+fn nearest(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    chrom: &str,
+    pos: u32,
+    max_window: u32,
+) -> Result<Option<pos_index::NearestFeatures>, CustomError> {
+    db.cf_handle(cf_name)
+        .map(|cf| pos_index::nearest(db, &cf, chrom, pos as i32, max_window))
+        .transpose()
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying {}: {}", cf_name, e)))
+}
+
+/// Return the context of `chrom:pos` on `genome_release`.
+pub fn query_context(
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    chrom: &str,
+    pos: u32,
+    max_window: u32,
+) -> Result<VariantContext, CustomError> {
+    let genes = data
+        .genes
+        .as_ref()
+        .map(|genes| {
+            nearest(
+                &genes.data.db,
+                crate::genes::cli::import::cf_name_pos_index(genome_release),
+                chrom,
+                pos,
+                max_window,
+            )
+        })
+        .transpose()?
+        .flatten();
+    let functional = data.functional[genome_release]
+        .as_ref()
+        .map(|db| {
+            nearest(
+                db,
+                crate::functional::cli::import::CF_NAME_POS_INDEX,
+                chrom,
+                pos,
+                max_window,
+            )
+        })
+        .transpose()?
+        .flatten();
+
+    Ok(VariantContext {
+        max_window,
+        genes,
+        functional,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+
+    /// Import the GRCh37 functional elements example data and open it as for the server.
+    pub(crate) fn functional_37(
+        tmp_dir: &TempDir,
+    ) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
+        let path_rocksdb = format!("{}", tmp_dir.join("functional").display());
+        crate::functional::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::functional::cli::import::Args {
+                genome_release: GenomeRelease::Grch37,
+                path_in_gff: vec![String::from(
+                    "tests/functional/GCF_000001405.25_GRCh37.p13_genomic.functional.gff",
+                )],
+                path_out_rocksdb: path_rocksdb.clone(),
+                cf_name: String::from("functional"),
+                cf_name_pos_index: String::from(crate::functional::cli::import::CF_NAME_POS_INDEX),
+                path_wal_dir: None,
+                dry_run: false,
+                spec: crate::common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+            },
+        )?;
+        super::super::open_functional_db(&path_rocksdb, None, false).map(|(_, db)| db)
+    }
+
+    #[rstest::rstest]
+    #[case::between(
+        140000000,
+        1_000_000,
+        Some(("id-GeneID:110121255-2", -85753)),
+        Some(("id-GeneID:113687198", 294129))
+    )]
+    #[case::window_too_small(140000000, 50_000, None, None)]
+    #[case::contig_start(1, 1_000_000, None, Some(("id-GeneID:113687202", 149168)))]
+    fn query_context_functional(
+        #[case] pos: u32,
+        #[case] max_window: u32,
+        #[case] upstream: Option<(&str, i32)>,
+        #[case] downstream: Option<(&str, i32)>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = WebServerData::default();
+        data.functional[GenomeRelease::Grch37] = Some(functional_37(&tmp_dir)?);
+
+        let context = query_context(&data, GenomeRelease::Grch37, "7", pos, max_window)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        assert_eq!(context.genes, None);
+        let functional = context.functional.unwrap();
+        assert_eq!(
+            functional
+                .upstream
+                .as_ref()
+                .map(|feature| (feature.id.as_str(), feature.distance)),
+            upstream
+        );
+        assert_eq!(
+            functional
+                .downstream
+                .as_ref()
+                .map(|feature| (feature.id.as_str(), feature.distance)),
+            downstream
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_context_no_databases() -> Result<(), anyhow::Error> {
+        let context = query_context(
+            &WebServerData::default(),
+            GenomeRelease::Grch37,
+            "7",
+            140000000,
+            pos_index::DEFAULT_MAX_WINDOW,
+        )
+        .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        assert_eq!(
+            context,
+            VariantContext {
+                max_window: pos_index::DEFAULT_MAX_WINDOW,
+                genes: None,
+                functional: None,
+            }
+        );
+
+        Ok(())
+    }
+}
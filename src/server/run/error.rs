@@ -1,11 +1,29 @@
 //! Errors for the Actix servers.
 
-use actix_web::ResponseError;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+
+use crate::common::decode::DecodeError;
 
 /// Custom error type for the Actix server.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct CustomError {
     err: String,
+    /// Details if a database value could not be decoded, e.g., because the database was
+    /// built by a newer annonars.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decode: Option<DecodeError>,
+    /// Details if a request parameter is missing or invalid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    param: Option<InvalidParam>,
+}
+
+/// Details on a missing or invalid request parameter.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct InvalidParam {
+    /// Name of the parameter.
+    pub name: String,
+    /// The offending value, `null` if the parameter is missing.
+    pub value: Option<String>,
 }
 
 impl std::fmt::Display for CustomError {
@@ -19,8 +37,117 @@ impl CustomError {
     pub fn new(err: anyhow::Error) -> Self {
         CustomError {
             err: err.to_string(),
+            decode: None,
+            param: None,
+        }
+    }
+
+    /// Create for the request parameter `name` with `value`, or missing if `None`.
+    pub fn invalid_param(name: &str, value: Option<&str>, message: &str) -> Self {
+        CustomError {
+            err: match value {
+                Some(value) => format!(
+                    "invalid value {:?} for parameter {}: {}",
+                    value, name, message
+                ),
+                None => format!("missing parameter {}: {}", name, message),
+            },
+            decode: None,
+            param: Some(InvalidParam {
+                name: name.to_string(),
+                value: value.map(ToString::to_string),
+            }),
+        }
+    }
+
+    /// Return the details on the invalid request parameter, if any.
+    pub fn param(&self) -> Option<&InvalidParam> {
+        self.param.as_ref()
+    }
+
+    /// Return the error message.
+    pub fn message(&self) -> &str {
+        &self.err
+    }
+}
+
+impl From<DecodeError> for CustomError {
+    fn from(err: DecodeError) -> Self {
+        CustomError {
+            err: err.to_string(),
+            decode: Some(err),
+            param: None,
+        }
+    }
+}
+
+impl ResponseError for CustomError {
+    fn status_code(&self) -> StatusCode {
+        if self.decode.is_some() {
+            StatusCode::BAD_GATEWAY
+        } else if self.param.is_some() {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if self.decode.is_some() || self.param.is_some() {
+            HttpResponse::build(self.status_code()).json(self)
+        } else {
+            HttpResponse::build(self.status_code())
+                .content_type(actix_web::http::header::ContentType::plaintext())
+                .body(self.to_string())
         }
     }
 }
 
-impl ResponseError for CustomError {}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_error_response() -> Result<(), anyhow::Error> {
+        let err = CustomError::from(DecodeError {
+            db_name: "helixmtdb".into(),
+            cf_name: "helixmtdb_data".into(),
+            key: "4d5400000001".into(),
+            db_annonars_version: Some("999.0.0".into()),
+            annonars_version: "0.42.0".into(),
+            message: "buffer underflow".into(),
+        });
+
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&err)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_param_response() -> Result<(), anyhow::Error> {
+        let err = CustomError::invalid_param("pos", Some("abc"), "not a number");
+
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::to_value(&err)?,
+            serde_json::json!({
+                "err": "invalid value \"abc\" for parameter pos: not a number",
+                "param": {"name": "pos", "value": "abc"},
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn other_error_response() {
+        let err = CustomError::new(anyhow::anyhow!("something went wrong"));
+
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            serde_json::to_string(&err).unwrap(),
+            r#"{"err":"something went wrong"}"#
+        );
+    }
+}
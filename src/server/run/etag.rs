@@ -0,0 +1,147 @@
+//! ETags for responses that only change with the databases.
+//!
+//! The responses of `/genes/info`, `/genes/clinvar`, and `/api/v1/versionsInfo` only depend
+//! on the request parameters and the opened databases.  Their ETag is a hash of the server
+//! version, a fingerprint of the databases, and the parsed request parameters.  Responses
+//! carry the ETag with `Cache-Control: no-cache` such that clients may keep them but have to
+//! revalidate; requests with a matching `If-None-Match` get `304 Not Modified` before the
+//! response is computed.
+//!
+//! The fingerprint is computed per request from the databases in `WebServerData`, see
+//! `genes_fingerprint()`, so that the ETags change as soon as a database is replaced by
+//! another build, even of the same version.
+
+use std::hash::Hasher as _;
+
+use actix_web::{
+    http::header::{self, CacheControl, CacheDirective, EntityTag, IfNoneMatch},
+    HttpMessage as _, HttpRequest, HttpResponse,
+};
+
+use super::{error::CustomError, GeneInfoDb, WebServerData, WithVersionSpec};
+
+/// ETag of a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(EntityTag);
+
+impl ETag {
+    /// Compute the ETag from the `fingerprint` of the databases and the request `params`.
+    pub fn new(fingerprint: &impl serde::Serialize, params: &impl serde::Serialize) -> Self {
+        let input = serde_json::json!({
+            "server_version": crate::common::version(),
+            "fingerprint": fingerprint,
+            "params": params,
+        });
+        let mut hasher = rustc_hash::FxHasher::default();
+        hasher.write(input.to_string().as_bytes());
+        Self(EntityTag::new_strong(format!("{:016x}", hasher.finish())))
+    }
+
+    /// Return the ETag as in the `ETag` header, i.e., quoted.
+    pub fn header_value(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Return whether `req` has this ETag (or `*`) in `If-None-Match`.
+    pub fn matches(&self, req: &HttpRequest) -> bool {
+        match req.get_header::<IfNoneMatch>() {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(etags)) => etags.iter().any(|etag| etag.weak_eq(&self.0)),
+            None => false,
+        }
+    }
+
+    /// Return `304 Not Modified` with this ETag if `req` matches it.
+    pub fn not_modified(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        self.matches(req).then(|| {
+            HttpResponse::NotModified()
+                .insert_header(header::ETag(self.0.clone()))
+                .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+                .finish()
+        })
+    }
+
+    /// Return `200 OK` with `body` as JSON and this ETag.
+    pub fn json(&self, body: impl serde::Serialize) -> HttpResponse {
+        HttpResponse::Ok()
+            .insert_header(header::ETag(self.0.clone()))
+            .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+            .json(body)
+    }
+}
+
+/// Return the fingerprint of the genes database and the ClinVar genes database for `ETag`.
+///
+/// This has the version of the data and the latest sequence number of each database, which
+/// differs between builds.
+pub fn genes_fingerprint(genes: &WithVersionSpec<GeneInfoDb>) -> serde_json::Value {
+    serde_json::json!({
+        "version": genes.version_spec.as_ref().map(|version_spec| &version_spec.version),
+        "source_versions": genes.data.source_versions,
+        "sequence_number": genes.data.db.latest_sequence_number(),
+        "clinvar_sequence_number": genes
+            .data
+            .db_clinvar
+            .as_ref()
+            .map(|db_clinvar| db_clinvar.latest_sequence_number()),
+    })
+}
+
+/// Return the ETag of the response of a genes endpoint to `params`.
+pub fn genes_etag(
+    data: &WebServerData,
+    params: &impl serde::Serialize,
+) -> Result<ETag, CustomError> {
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+    Ok(ETag::new(&genes_fingerprint(genes_db), params))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test};
+
+    use super::*;
+
+    #[test]
+    fn etag_stable() {
+        let etag = ETag::new(&"fingerprint", &["HGNC:1100"]);
+
+        assert_eq!(etag, ETag::new(&"fingerprint", &["HGNC:1100"]));
+        assert_ne!(etag, ETag::new(&"fingerprint", &["HGNC:1101"]));
+        assert_ne!(etag, ETag::new(&"other fingerprint", &["HGNC:1100"]));
+        assert!(etag.header_value().starts_with('"'));
+    }
+
+    #[rstest::rstest]
+    #[case::none(None, StatusCode::OK)]
+    #[case::matching(Some("MATCHING"), StatusCode::NOT_MODIFIED)]
+    #[case::matching_weak(Some("W/MATCHING"), StatusCode::NOT_MODIFIED)]
+    #[case::matching_in_list(Some("\"other\", MATCHING"), StatusCode::NOT_MODIFIED)]
+    #[case::any(Some("*"), StatusCode::NOT_MODIFIED)]
+    #[case::other(Some("\"other\""), StatusCode::OK)]
+    fn not_modified(#[case] if_none_match: Option<&str>, #[case] expected: StatusCode) {
+        let etag = ETag::new(&"fingerprint", &());
+        let mut req = test::TestRequest::get();
+        if let Some(if_none_match) = if_none_match {
+            let value = if_none_match.replace("MATCHING", &etag.header_value());
+            req = req.insert_header((header::IF_NONE_MATCH, value));
+        }
+        let req = req.to_http_request();
+
+        let response = etag
+            .not_modified(&req)
+            .unwrap_or_else(|| etag.json(serde_json::json!({"key": "value"})));
+
+        assert_eq!(response.status(), expected);
+        assert_eq!(
+            response.headers().get(header::ETAG).unwrap(),
+            etag.header_value().as_str()
+        );
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+    }
+}
@@ -1,8 +1,11 @@
 //! Fetching of data for the Actix server.
 
-use crate::common::keys;
+use crate::{
+    common::{cli::GenomeRelease, decode::decode_protobuf, keys, prefix_bloom},
+    genes::ids::HgncId,
+};
 
-use super::error::CustomError;
+use super::{error::CustomError, range_decode::RangeDecoder, range_filter};
 
 /// Function to fetch prost Message from a variant database into JSOn.
 pub fn fetch_var_protobuf_json<T>(
@@ -19,17 +22,11 @@ where
     let key: Vec<u8> = key.into();
 
     let raw_data = db
-        .get_cf(&cf_data, key)
+        .get_cf(&cf_data, &key)
         .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
     raw_data
         .map(|raw_data| {
-            let msg: T = prost::Message::decode(&raw_data[..]).map_err(|e| {
-                CustomError::new(anyhow::anyhow!(
-                    "problem decoding protobuf from database (cf_name={}): {}",
-                    cf_name,
-                    e
-                ))
-            })?;
+            let msg: T = decode_protobuf(db, cf_name, &key, &raw_data)?;
             serde_json::to_value(msg).map_err(|e| {
                 CustomError::new(anyhow::anyhow!("problem decoding JSON from database: {e}",))
             })
@@ -52,19 +49,67 @@ where
     let key: Vec<u8> = key.into();
 
     let raw_data = db
-        .get_cf(&cf_data, key)
+        .get_cf(&cf_data, &key)
         .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
     raw_data
-        .map(|raw_data| {
-            prost::Message::decode(&raw_data[..]).map_err(|e| {
+        .map(|raw_data| decode_protobuf(db, cf_name, &key, &raw_data).map_err(CustomError::from))
+        .transpose()
+}
+
+/// Function to check whether a variant database has a value for `key` without decoding it.
+pub fn fetch_var_exists(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    key: keys::Var,
+) -> Result<bool, CustomError> {
+    let cf_data = db
+        .cf_handle(cf_name)
+        .unwrap_or_else(|| panic!("unknown column family: {}", cf_name));
+    let key: Vec<u8> = key.into();
+
+    db.get_pinned_cf(&cf_data, &key)
+        .map(|raw_data| raw_data.is_some())
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))
+}
+
+/// Function to fetch the alleles of all variants at `pos` from a variant database.
+///
+/// Only the keys with the chromosome and position prefix of `pos` are visited and the
+/// values are not read.  Returns the `(reference, alternative)` pairs in key order.
+pub fn fetch_pos_alleles(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    pos: keys::Pos,
+) -> Result<Vec<(String, String)>, CustomError> {
+    let cf_data = db
+        .cf_handle(cf_name)
+        .unwrap_or_else(|| panic!("unknown column family: {}", cf_name));
+    let mut iter = db.raw_iterator_cf_opt(&cf_data, prefix_bloom::scan_read_options(&pos, &pos));
+    let prefix: Vec<u8> = pos.into();
+    iter.seek(&prefix);
+
+    let mut result = Vec::new();
+    while let Some(iter_key) = iter.key() {
+        let Some(alleles) = iter_key.strip_prefix(prefix.as_slice()) else {
+            break;
+        };
+        let (reference, alternative) = std::str::from_utf8(alleles)
+            .ok()
+            .and_then(|alleles| alleles.split_once('>'))
+            .ok_or_else(|| {
                 CustomError::new(anyhow::anyhow!(
-                    "problem decoding protobuf from database (cf_name={}): {}",
-                    cf_name,
-                    e
+                    "invalid variant key {} in {}",
+                    keys::to_hex(iter_key),
+                    cf_name
                 ))
-            })
-        })
-        .transpose()
+            })?;
+        result.push((reference.to_string(), alternative.to_string()));
+        iter.next();
+    }
+    iter.status()
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
+
+    Ok(result)
 }
 
 /// Function to fetch prost Message from a position database.
@@ -77,42 +122,59 @@ pub fn fetch_pos_protobuf_json<T>(
 where
     T: prost::Message + serde::Serialize + Default,
 {
-    let stop = crate::common::keys::Pos {
-        chrom: stop.chrom.clone(),
-        pos: stop.pos,
-    };
+    fetch_pos_protobuf_json_with_decoder::<T>(db, cf_name, start, stop, &RangeDecoder::default())
+}
 
+/// Function to fetch prost Message from a position database, decoding with `decoder`.
+///
+/// See `range_decode` for the parallel decoding of large ranges.
+pub fn fetch_pos_protobuf_json_with_decoder<T>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    start: keys::Pos,
+    stop: keys::Pos,
+    decoder: &RangeDecoder,
+) -> Result<Option<serde_json::Value>, CustomError>
+where
+    T: prost::Message + serde::Serialize + Default,
+{
+    let raw = fetch_pos_raw(db, cf_name, start, stop)?;
+    let result = decoder.decode(&raw, |key, value| {
+        let msg: T = decode_protobuf(db, cf_name, key, value)?;
+        serde_json::to_value(msg).map_err(|e| {
+            CustomError::new(anyhow::anyhow!("problem decoding JSON from database: {e}",))
+        })
+    })?;
+
+    Ok(Some(serde_json::Value::Array(result)))
+}
+
+/// Function to fetch the raw keys and values from `start` to `stop` of a position database.
+pub fn fetch_pos_raw(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    start: keys::Pos,
+    stop: keys::Pos,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, CustomError> {
     let cf_data = db.cf_handle(cf_name).unwrap();
-    let mut iter = db.raw_iterator_cf(&cf_data);
+    let mut iter = db.raw_iterator_cf_opt(&cf_data, prefix_bloom::scan_read_options(&start, &stop));
     let start: Vec<u8> = start.into();
     iter.seek(&start);
 
     let mut result = Vec::new();
-    while iter.valid() {
-        if let Some(raw_value) = iter.value() {
-            let iter_key = iter.key().unwrap();
-            let iter_pos: crate::common::keys::Pos = iter_key.into();
-
-            if iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos {
-                break;
-            }
-
-            let msg: T = prost::Message::decode(raw_value).map_err(|e| {
-                CustomError::new(anyhow::anyhow!(
-                    "problem decoding protobuf from database (cf_name={}): {}",
-                    cf_name,
-                    e
-                ))
-            })?;
-            result.push(serde_json::to_value(msg).map_err(|e| {
-                CustomError::new(anyhow::anyhow!("problem decoding JSON from database: {e}",))
-            })?);
-
-            iter.next();
+    while let (Some(iter_key), Some(raw_value)) = (iter.key(), iter.value()) {
+        let iter_pos: keys::Pos = iter_key.into();
+        if iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos {
+            break;
         }
+
+        result.push((iter_key.to_vec(), raw_value.to_vec()));
+        iter.next();
     }
+    iter.status()
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
 
-    Ok(Some(serde_json::Value::Array(result)))
+    Ok(result)
 }
 
 /// Function to fetch prost Message from a position database.
@@ -131,7 +193,7 @@ where
     };
 
     let cf_data = db.cf_handle(cf_name).unwrap();
-    let mut iter = db.raw_iterator_cf(&cf_data);
+    let mut iter = db.raw_iterator_cf_opt(&cf_data, prefix_bloom::scan_read_options(&start, &stop));
     let start: Vec<u8> = start.into();
     iter.seek(&start);
 
@@ -145,13 +207,7 @@ where
                 break;
             }
 
-            result.push(prost::Message::decode(raw_value).map_err(|e| {
-                CustomError::new(anyhow::anyhow!(
-                    "problem decoding protobuf from database (cf_name={}): {}",
-                    cf_name,
-                    e
-                ))
-            })?);
+            result.push(decode_protobuf(db, cf_name, iter_key, raw_value)?);
 
             iter.next();
         }
@@ -160,6 +216,30 @@ where
     Ok(result)
 }
 
+/// Fetch the gene with `hgnc_id` from the genes database and return its 1-based, inclusive
+/// interval.
+///
+/// See `genes::cli::query::gene_range` for details.
+pub fn fetch_gene_interval(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    hgnc_id: &HgncId,
+    genome_release: GenomeRelease,
+) -> Result<(keys::Pos, keys::Pos), CustomError> {
+    let cf_genes = db.cf_handle("genes").expect("no 'genes' column family");
+    let raw_buf = db
+        .get_cf(&cf_genes, hgnc_id.as_str())
+        .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?
+        .ok_or_else(|| CustomError::new(anyhow::anyhow!("no such gene: {}", hgnc_id)))?;
+    let record: crate::pbs::genes::base::Record =
+        decode_protobuf(db, "genes", hgnc_id.as_str().as_bytes(), &raw_buf)?;
+    let range =
+        crate::genes::cli::query::gene_range(&record, genome_release).map_err(CustomError::new)?;
+    Ok((
+        keys::Pos::new(range.sequence.clone(), range.start),
+        keys::Pos::new(range.sequence, range.end),
+    ))
+}
+
 /// Function to fetch a crate::tsv record from a database by variant.
 pub fn fetch_var_tsv_json(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
@@ -208,8 +288,20 @@ pub fn fetch_pos_tsv_json(
     cf_name: &str,
     start: keys::Pos,
     stop: keys::Pos,
+) -> Result<Option<serde_json::Value>, CustomError> {
+    fetch_pos_tsv_json_filtered(db, cf_name, start, stop, &[])
+}
+
+/// Like `fetch_pos_tsv_json()` but only keep the records passing any of `filters`, if any.
+pub fn fetch_pos_tsv_json_filtered(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    cf_name: &str,
+    start: keys::Pos,
+    stop: keys::Pos,
+    filters: &[range_filter::Filter],
 ) -> Result<Option<serde_json::Value>, CustomError> {
     let (db_schema, ctx) = fetch_tsv_json_prepare_db(db, cf_name)?;
+    let filters = range_filter::resolve_all(filters, &db_schema)?;
     let cf_data = db
         .cf_handle(cf_name)
         .ok_or(CustomError::new(anyhow::anyhow!(
@@ -218,7 +310,7 @@ pub fn fetch_pos_tsv_json(
         )))?;
 
     // Obtain iterator and seek to start.
-    let mut iter = db.raw_iterator_cf(&cf_data);
+    let mut iter = db.raw_iterator_cf_opt(&cf_data, prefix_bloom::scan_read_options(&start, &stop));
     let pos: keys::Pos = start;
     let key: Vec<u8> = pos.into();
     tracing::debug!("seeking to key {:?}", &key);
@@ -252,7 +344,9 @@ pub fn fetch_pos_tsv_json(
                     e
                 ))
             })?;
-            values.append(&mut tmp);
+            if range_filter::keep(&filters, &tmp) {
+                values.append(&mut tmp);
+            }
 
             iter.next();
         } else {
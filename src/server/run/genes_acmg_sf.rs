@@ -0,0 +1,174 @@
+//! Implementation of endpoint `/genes/acmg-sf`.
+//!
+//! Lists all genes on the ACMG secondary findings (SF) list as contained in the genes database.
+//! The list is small and built once when the genes database is opened.
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+
+use crate::{common::decode::decode_protobuf, pbs::genes};
+
+use super::error::CustomError;
+
+/// One gene on the ACMG SF list.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct GenesAcmgSfRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// HGNC gene symbol.
+    pub symbol: String,
+    /// The version of the ACMG SF list of first appearance.
+    pub sf_list_version: String,
+    /// The disease phenotype.
+    pub disease_phenotype: String,
+    /// The mode of inheritance.
+    pub inheritance: String,
+    /// The variants to report according to ACMG SF.
+    pub variants_to_report: String,
+}
+
+/// Result for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesAcmgSfResponse {
+    /// Version of the genes database, if known.
+    pub genes_db_version: Option<String>,
+    /// The genes on the ACMG SF list, sorted by symbol.
+    pub genes: Vec<GenesAcmgSfRecord>,
+}
+
+/// Extract the genes with ACMG SF information from the genes database, sorted by symbol.
+pub fn extract_acmg_sf(
+    genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<Vec<GenesAcmgSfRecord>, anyhow::Error> {
+    let mut result = Vec::new();
+
+    let cf_read = genes_db.cf_handle("genes").unwrap();
+    let mut iter = genes_db.raw_iterator_cf(&cf_read);
+    iter.seek(b"");
+    while iter.valid() {
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let genes::base::Record { hgnc, acmg_sf, .. } =
+                decode_protobuf(genes_db, "genes", iter_key, iter_value)?;
+            if let Some(acmg_sf) = acmg_sf {
+                let genes::base::AcmgSecondaryFindingRecord {
+                    hgnc_id,
+                    gene_symbol,
+                    disease_phenotype,
+                    inheritance,
+                    sf_list_version,
+                    variants_to_report,
+                    ..
+                } = acmg_sf;
+                let (hgnc_id, symbol) = match hgnc {
+                    Some(hgnc) => (hgnc.hgnc_id, hgnc.symbol),
+                    None => (hgnc_id, gene_symbol),
+                };
+                result.push(GenesAcmgSfRecord {
+                    hgnc_id,
+                    symbol,
+                    sf_list_version,
+                    disease_phenotype,
+                    inheritance,
+                    variants_to_report,
+                });
+            }
+        }
+        iter.next();
+    }
+
+    result.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    Ok(result)
+}
+
+/// List the genes on the ACMG secondary findings list.
+#[utoipa::path(
+    get,
+    operation_id = "genesAcmgSf",
+    responses(
+        (status = 200, description = "Genes on the ACMG SF list.", body = GenesAcmgSfResponse),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/acmg-sf")]
+async fn handle(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+) -> actix_web::Result<Json<GenesAcmgSfResponse>, CustomError> {
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+
+    Ok(Json(GenesAcmgSfResponse {
+        genes_db_version: genes_db
+            .version_spec
+            .as_ref()
+            .map(|version_spec| version_spec.version.clone()),
+        genes: genes_db.data.acmg_sf.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use temp_testdir::TempDir;
+
+    /// Build a genes database with three genes, two of them on the ACMG SF list.
+    fn genes_db(tmp_dir: &TempDir) -> Result<rocksdb::DB, anyhow::Error> {
+        let path = format!("{}", tmp_dir.join("genes").display());
+        let entries = [
+            (
+                "HGNC:12403",
+                "TTN",
+                Some(("Dilated cardiomyopathy", "3.0", "Truncating variants only")),
+            ),
+            ("HGNC:1097", "BRAF", None),
+            (
+                "HGNC:130",
+                "ACTA2",
+                Some(("Familial thoracic aortic aneurysm", "1.0", "All P and LP")),
+            ),
+        ];
+        let records = entries
+            .into_iter()
+            .map(|(hgnc_id, symbol, acmg_sf)| genes::base::Record {
+                acmg_sf: acmg_sf.map(|(disease_phenotype, sf_list_version, variants_to_report)| {
+                    genes::base::AcmgSecondaryFindingRecord {
+                        hgnc_id: hgnc_id.into(),
+                        gene_symbol: symbol.into(),
+                        disease_phenotype: disease_phenotype.into(),
+                        inheritance: "AD".into(),
+                        sf_list_version: sf_list_version.into(),
+                        variants_to_report: variants_to_report.into(),
+                        ..Default::default()
+                    }
+                }),
+                ..crate::genes::cli::import::test::record(hgnc_id, symbol)
+            })
+            .collect::<Vec<_>>();
+        crate::genes::cli::import::test::write_records(&path, &records)?;
+
+        Ok(rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            path,
+            ["meta", "genes"],
+            false,
+        )?)
+    }
+
+    #[test]
+    fn extract_acmg_sf_sorted_by_symbol() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let db = genes_db(&tmp_dir)?;
+
+        let response = GenesAcmgSfResponse {
+            genes_db_version: Some("1.0".into()),
+            genes: extract_acmg_sf(&db)?,
+        };
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&response)?);
+
+        Ok(())
+    }
+}
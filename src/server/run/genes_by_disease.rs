@@ -0,0 +1,155 @@
+//! Implementation of endpoint `/genes/by-disease`.
+//!
+//! Returns the genes associated with OMIM or Orphanet diseases from the disease index of the
+//! genes database, see `genes::diseases`.
+use std::collections::BTreeMap;
+
+use actix_web::{
+    get,
+    web::{self, Data, Json, Path},
+};
+use serde_with::{formats::CommaSeparator, StringWithSeparator};
+
+use crate::genes::diseases::{self, DiseaseGene, DiseaseId};
+
+use super::error::CustomError;
+
+/// Parameters for `handle`.
+#[serde_with::serde_as]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) struct GenesByDiseaseQuery {
+    /// The OMIM or Orphanet disease IDs, e.g., `OMIM:143890,ORPHA:406`.
+    #[serde_as(as = "StringWithSeparator::<CommaSeparator, String>")]
+    pub id: Vec<String>,
+}
+
+/// Result for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesByDiseaseResponse {
+    /// Version of the genes database, if known.
+    pub genes_db_version: Option<String>,
+    /// The associated genes by disease ID, sorted by HGNC ID; empty for unknown diseases.
+    pub results: BTreeMap<String, Vec<DiseaseGene>>,
+}
+
+/// Query for the genes associated with diseases.
+#[utoipa::path(
+    get,
+    operation_id = "genesByDisease",
+    params(GenesByDiseaseQuery),
+    responses(
+        (status = 200, description = "Genes by disease.", body = GenesByDiseaseResponse),
+        (status = 400, description = "Invalid disease ID.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/by-disease")]
+async fn handle(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesByDiseaseQuery>,
+) -> actix_web::Result<Json<GenesByDiseaseResponse>, CustomError> {
+    let disease_ids = query
+        .id
+        .iter()
+        .map(|id| {
+            id.parse::<DiseaseId>()
+                .map_err(|e| CustomError::invalid_param("id", Some(id), &e.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+
+    let mut results = BTreeMap::new();
+    for disease_id in disease_ids {
+        let genes = diseases::query_genes(&genes_db.data.db, "genes", &disease_id)
+            .map_err(CustomError::new)?;
+        results.insert(disease_id.to_string(), genes);
+    }
+
+    Ok(Json(GenesByDiseaseResponse {
+        genes_db_version: genes_db
+            .version_spec
+            .as_ref()
+            .map(|version_spec| version_spec.version.clone()),
+        results,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::server::run::genes_info::test::data_with_genes_from;
+
+    /// Call `/genes/by-disease` with `query` on the example genes database with the disease
+    /// associations of `tests/genes/by_disease`.
+    async fn call_by_disease(query: &str) -> (StatusCode, serde_json::Value) {
+        let tmp_dir = TempDir::default();
+        let data = data_with_genes_from(
+            &tmp_dir,
+            "tests/genes/by_disease/omim_diseases.tsv",
+            "tests/genes/by_disease/orphanet_diseases.tsv",
+        );
+        let app = test::init_service(App::new().app_data(Data::new(data)).service(handle)).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/genes/by-disease?{}", query))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn by_disease() -> Result<(), anyhow::Error> {
+        let (status, body) =
+            call_by_disease("id=OMIM:115150,orpha:217569,OMIM:613287,ORPHA:1").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["results"],
+            serde_json::json!({
+                "OMIM:115150": [
+                    {
+                        "hgnc_id": "HGNC:1097",
+                        "symbol": "BRAF",
+                        "disease_labels": ["Cardiofaciocutaneous syndrome 1"],
+                    },
+                ],
+                "ORPHA:217569": [
+                    {
+                        "hgnc_id": "HGNC:1097",
+                        "symbol": "BRAF",
+                        "disease_labels": ["Hypertrophic cardiomyopathy"],
+                    },
+                    {
+                        "hgnc_id": "HGNC:12403",
+                        "symbol": "TTN",
+                        "disease_labels": ["Hypertrophic cardiomyopathy"],
+                    },
+                ],
+                "OMIM:613287": [],
+                "ORPHA:1": [],
+            })
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn by_disease_invalid_id() -> Result<(), anyhow::Error> {
+        let (status, body) = call_by_disease("id=OMIM:115150,HP:0001250").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["param"]["name"], "id");
+        assert_eq!(body["param"]["value"], "HP:0001250");
+
+        Ok(())
+    }
+}
@@ -2,13 +2,16 @@
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    web::{self, Data, Path},
+    HttpRequest, HttpResponse,
 };
-use prost::Message;
 
-use crate::pbs::clinvar::per_gene::ClinvarPerGeneRecord;
+use crate::{
+    common::decode::decode_protobuf, genes::ids::HgncId,
+    pbs::clinvar::per_gene::ClinvarPerGeneRecord,
+};
 
-use super::error::CustomError;
+use super::{error::CustomError, etag};
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
 
 /// Parameters for `handle`.
@@ -20,8 +23,10 @@ use serde_with::{formats::CommaSeparator, StringWithSeparator};
 #[serde(rename_all = "snake_case")]
 pub struct GenesClinvarQuery {
     /// The HGNC IDs to search for.
-    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
-    pub hgnc_id: Option<Vec<String>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, HgncId>>")]
+    #[schema(value_type = Option<Vec<String>>)]
+    #[param(value_type = Option<Vec<String>>)]
+    pub hgnc_id: Option<Vec<HgncId>>,
 }
 
 /// Result for `handle`.
@@ -55,13 +60,18 @@ async fn handle_impl(
     let mut genes = indexmap::IndexMap::new();
     if let Some(hgnc_id) = query.hgnc_id.as_ref() {
         for hgnc_id in hgnc_id {
-            if let Some(raw_buf) = db_clinvar.get_cf(&cf_genes, hgnc_id).map_err(|e| {
-                CustomError::new(anyhow::anyhow!("problem querying database: {}", e))
-            })? {
-                let record = crate::pbs::clinvar::per_gene::ClinvarPerGeneRecord::decode(
-                    std::io::Cursor::new(raw_buf),
-                )
-                .map_err(|e| CustomError::new(anyhow::anyhow!("problem decoding value: {}", e)))?;
+            if let Some(raw_buf) = db_clinvar
+                .get_cf(&cf_genes, hgnc_id.as_str())
+                .map_err(|e| {
+                    CustomError::new(anyhow::anyhow!("problem querying database: {}", e))
+                })?
+            {
+                let record: crate::pbs::clinvar::per_gene::ClinvarPerGeneRecord = decode_protobuf(
+                    db_clinvar,
+                    "clinvar-genes",
+                    hgnc_id.as_str().as_bytes(),
+                    &raw_buf,
+                )?;
                 genes.insert(hgnc_id.to_string(), record);
             } else {
                 tracing::debug!("no such gene: {}", hgnc_id);
@@ -89,8 +99,13 @@ async fn handle(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
     query: web::Query<GenesClinvarQuery>,
-) -> actix_web::Result<Json<Container>, CustomError> {
-    Ok(Json(handle_impl(data, _path, query).await?))
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let etag = etag::genes_etag(&data, &*query)?;
+    if let Some(response) = etag.not_modified(&req) {
+        return Ok(response);
+    }
+    Ok(etag.json(handle_impl(data, _path, query).await?))
 }
 
 /// Types used in the response.
@@ -424,6 +439,7 @@ use response::*;
     params(GenesClinvarQuery),
     responses(
         (status = 200, description = "Per-gene ClinVar information.", body = GenesClinvarResponse),
+        (status = 304, description = "Not modified since the response with the ETag in `If-None-Match`."),
         (status = 500, description = "Internal server error.", body = CustomError)
     )
 )]
@@ -432,10 +448,15 @@ async fn handle_with_openapi(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
     query: web::Query<GenesClinvarQuery>,
-) -> actix_web::Result<Json<GenesClinvarResponse>, CustomError> {
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let etag = etag::genes_etag(&data, &*query)?;
+    if let Some(response) = etag.not_modified(&req) {
+        return Ok(response);
+    }
     let container = handle_impl(data, _path, query).await?;
-    let response = container
+    let response: GenesClinvarResponse = container
         .try_into()
         .map_err(|e| CustomError::new(anyhow::anyhow!("Failed to convert response: {}", e)))?;
-    Ok(Json(response))
+    Ok(etag.json(response))
 }
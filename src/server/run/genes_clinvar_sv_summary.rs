@@ -0,0 +1,450 @@
+//! Implementation of endpoint `/genes/clinvar-sv-summary`.
+//!
+//! Aggregates the ClinVar structural variants overlapping the genomic interval of each gene
+//! by variation type and germline classification, e.g., for CNV interpretation.  The
+//! interval is resolved from the gene's ClinGen genomic location as for
+//! `/genes/clinvar-variants`, the variants are read from the ClinVar SV interval trees of the
+//! requested genome release.
+use actix_web::{
+    get,
+    web::{self, Data, Json, Path},
+};
+use serde_with::{formats::CommaSeparator, StringWithSeparator};
+
+use crate::{
+    clinvar_sv::index::record_interval,
+    common::cli::GenomeRelease,
+    genes::ids::HgncId,
+    pbs::clinvar_data::extracted_vars::{ExtractedVcvRecord, VariationType},
+    server::run::{
+        clinvar_data::ClinvarExtractedVariationType, clinvar_sv::query_overlapping,
+        fetch::fetch_gene_interval,
+    },
+};
+
+use super::error::CustomError;
+
+/// The maximal number of genes per request.
+const MAX_HGNC_IDS: usize = 100;
+
+/// Germline classifications that count as pathogenic for the largest deletion/duplication.
+pub(crate) const PATHOGENIC_CLASSIFICATIONS: &[&str] = &[
+    "pathogenic",
+    "likely pathogenic",
+    "pathogenic/likely pathogenic",
+];
+
+/// Parameters for `handle`.
+#[serde_with::skip_serializing_none]
+#[serde_with::serde_as]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) struct GenesClinvarSvSummaryQuery {
+    /// The HGNC IDs of the genes, comma-separated, at most 100.
+    #[serde_as(as = "StringWithSeparator::<CommaSeparator, HgncId>")]
+    #[schema(value_type = Vec<String>)]
+    #[param(value_type = Vec<String>)]
+    pub hgnc_id: Vec<HgncId>,
+    /// The genome release, defaults to GRCh37.
+    pub genome_release: Option<GenomeRelease>,
+}
+
+/// Number of ClinVar SVs with one variation type and germline classification.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarSvCount {
+    /// The variation type.
+    pub variation_type: ClinvarExtractedVariationType,
+    /// The aggregate germline classification description, if any.
+    pub germline_classification: Option<String>,
+    /// The number of SVs.
+    pub count: usize,
+}
+
+/// A ClinVar SV with its coordinates.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarSvRecord {
+    /// The VCV accession, including version.
+    pub vcv: String,
+    /// The variation type.
+    pub variation_type: ClinvarExtractedVariationType,
+    /// The aggregate germline classification description.
+    pub germline_classification: Option<String>,
+    /// The chromosome.
+    pub chromosome: String,
+    /// The 1-based start position.
+    pub start: u32,
+    /// The 1-based stop position.
+    pub stop: u32,
+}
+
+impl GenesClinvarSvRecord {
+    /// Return the length of the SV.
+    fn length(&self) -> u32 {
+        (self.stop + 1).saturating_sub(self.start)
+    }
+}
+
+/// Summary of the ClinVar SVs overlapping one gene.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub(crate) struct GenesClinvarSvSummary {
+    /// The total number of overlapping SVs.
+    pub total: usize,
+    /// The number of SVs by variation type and germline classification.
+    pub counts: Vec<GenesClinvarSvCount>,
+    /// The largest (likely) pathogenic deletion or copy number loss.
+    pub largest_pathogenic_deletion: Option<GenesClinvarSvRecord>,
+    /// The largest (likely) pathogenic duplication or copy number gain.
+    pub largest_pathogenic_duplication: Option<GenesClinvarSvRecord>,
+}
+
+/// Result for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarSvSummaryResponse {
+    /// The genome release.
+    pub genome_release: GenomeRelease,
+    /// The summaries keyed by HGNC ID.
+    pub genes: std::collections::BTreeMap<String, GenesClinvarSvSummary>,
+}
+
+/// Return the variation type of `record`, unspecified types are counted as other.
+fn variation_type(record: &ExtractedVcvRecord) -> ClinvarExtractedVariationType {
+    VariationType::try_from(record.variation_type)
+        .ok()
+        .and_then(|variation_type| ClinvarExtractedVariationType::try_from(variation_type).ok())
+        .unwrap_or(ClinvarExtractedVariationType::Other)
+}
+
+/// Convert `record` for reporting, `None` if it has no coordinates.
+fn to_sv_record(record: &ExtractedVcvRecord) -> Option<GenesClinvarSvRecord> {
+    let (chromosome, interval, vcv) = record_interval(record).ok().flatten()?;
+    Some(GenesClinvarSvRecord {
+        vcv,
+        variation_type: variation_type(record),
+        germline_classification: germline_classification(record),
+        chromosome,
+        start: interval.start as u32 + 1,
+        stop: interval.end as u32,
+    })
+}
+
+/// Return the aggregate germline classification description of `record`.
+fn germline_classification(record: &ExtractedVcvRecord) -> Option<String> {
+    record
+        .classifications
+        .as_ref()
+        .and_then(|classifications| classifications.germline_classification.as_ref())
+        .and_then(|germline| germline.description.clone())
+}
+
+/// Aggregate the SVs in `records` by variation type and germline classification.
+///
+/// The largest pathogenic deletion and duplication are determined from the records with
+/// coordinates only; on ties, the first record is kept.
+pub(crate) fn summarize(records: &[ExtractedVcvRecord]) -> GenesClinvarSvSummary {
+    let mut counts = std::collections::BTreeMap::<_, usize>::new();
+    let mut largest_deletion: Option<GenesClinvarSvRecord> = None;
+    let mut largest_duplication: Option<GenesClinvarSvRecord> = None;
+
+    for record in records {
+        let variation_type = variation_type(record);
+        let classification = germline_classification(record);
+        let is_pathogenic = classification.as_deref().is_some_and(|classification| {
+            PATHOGENIC_CLASSIFICATIONS.contains(&classification.to_lowercase().as_str())
+        });
+        *counts.entry((variation_type, classification)).or_default() += 1;
+
+        let largest = match variation_type {
+            ClinvarExtractedVariationType::Deletion
+            | ClinvarExtractedVariationType::CopyNumberLoss => &mut largest_deletion,
+            ClinvarExtractedVariationType::Duplication
+            | ClinvarExtractedVariationType::TandemDuplication
+            | ClinvarExtractedVariationType::CopyNumberGain => &mut largest_duplication,
+            _ => continue,
+        };
+        if !is_pathogenic {
+            continue;
+        }
+        if let Some(sv_record) = to_sv_record(record) {
+            let is_larger = match largest {
+                Some(current) => sv_record.length() > current.length(),
+                None => true,
+            };
+            if is_larger {
+                *largest = Some(sv_record);
+            }
+        }
+    }
+
+    GenesClinvarSvSummary {
+        total: records.len(),
+        counts: counts
+            .into_iter()
+            .map(
+                |((variation_type, germline_classification), count)| GenesClinvarSvCount {
+                    variation_type,
+                    germline_classification,
+                    count,
+                },
+            )
+            .collect(),
+        largest_pathogenic_deletion: largest_deletion,
+        largest_pathogenic_duplication: largest_duplication,
+    }
+}
+
+/// Check the number of HGNC IDs in `hgnc_ids`.
+fn check_hgnc_ids(hgnc_ids: &[HgncId]) -> Result<(), CustomError> {
+    if hgnc_ids.is_empty() {
+        Err(CustomError::invalid_param(
+            "hgnc_id",
+            Some(""),
+            "must not be empty",
+        ))
+    } else if hgnc_ids.len() > MAX_HGNC_IDS {
+        Err(CustomError::invalid_param(
+            "hgnc_id",
+            Some(
+                &hgnc_ids
+                    .iter()
+                    .map(HgncId::as_str)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            &format!("at most {} HGNC IDs are allowed", MAX_HGNC_IDS),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Summarize the ClinVar SVs overlapping one or more genes.
+#[utoipa::path(
+    get,
+    operation_id = "genesClinvarSvSummary",
+    params(GenesClinvarSvSummaryQuery),
+    responses(
+        (status = 200, description = "ClinVar SV summaries keyed by HGNC ID.", body = GenesClinvarSvSummaryResponse),
+        (status = 400, description = "No or too many HGNC IDs.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/clinvar-sv-summary")]
+async fn handle(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesClinvarSvSummaryQuery>,
+) -> actix_web::Result<Json<GenesClinvarSvSummaryResponse>, CustomError> {
+    check_hgnc_ids(&query.hgnc_id)?;
+    let genome_release = query.genome_release.unwrap_or_default();
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+    let trees = data.clinvar_svs[genome_release]
+        .as_ref()
+        .ok_or(CustomError::new(anyhow::anyhow!(
+            "no clinvar-sv database for genome release {}",
+            genome_release
+        )))?;
+
+    let mut genes = std::collections::BTreeMap::new();
+    for hgnc_id in &query.hgnc_id {
+        let (start, stop) = fetch_gene_interval(&genes_db.data.db, hgnc_id, genome_release)?;
+        let records = query_overlapping(
+            trees,
+            &start.chrom,
+            start.pos as u32,
+            stop.pos as u32,
+            &[],
+            0.0,
+        )?
+        .into_iter()
+        .filter_map(|record| record.record)
+        .collect::<Vec<_>>();
+        genes.insert(hgnc_id.to_string(), summarize(&records));
+    }
+
+    Ok(Json(GenesClinvarSvSummaryResponse {
+        genome_release,
+        genes,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pbs::clinvar_data::{
+        clinvar_public::{
+            location::SequenceLocation, AggregateClassificationSet,
+            AggregatedGermlineClassification, Chromosome,
+        },
+        extracted_vars::VersionedAccession,
+    };
+
+    use super::*;
+
+    /// Create a synthetic SV on chromosome 1.
+    fn sv(
+        vcv: &str,
+        variation_type: VariationType,
+        classification: Option<&str>,
+        interval: Option<(u32, u32)>,
+    ) -> ExtractedVcvRecord {
+        ExtractedVcvRecord {
+            accession: Some(VersionedAccession {
+                accession: vcv.into(),
+                version: 1,
+            }),
+            variation_type: variation_type as i32,
+            classifications: classification.map(|description| AggregateClassificationSet {
+                germline_classification: Some(AggregatedGermlineClassification {
+                    description: Some(description.into()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            sequence_location: Some(SequenceLocation {
+                chr: Chromosome::Chromosome1 as i32,
+                start: interval.map(|(start, _)| start),
+                stop: interval.map(|(_, stop)| stop),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summarize_empty() {
+        assert_eq!(summarize(&[]), GenesClinvarSvSummary::default());
+    }
+
+    #[test]
+    fn summarize_counts() {
+        let records = vec![
+            sv("VCV1", VariationType::Deletion, Some("Pathogenic"), None),
+            sv("VCV2", VariationType::Deletion, Some("Pathogenic"), None),
+            sv("VCV3", VariationType::Deletion, Some("Benign"), None),
+            sv("VCV4", VariationType::CopyNumberGain, None, None),
+            sv("VCV5", VariationType::Unspecified, Some("Benign"), None),
+        ];
+
+        let summary = summarize(&records);
+
+        assert_eq!(summary.total, 5);
+        assert_eq!(
+            summary.counts,
+            vec![
+                GenesClinvarSvCount {
+                    variation_type: ClinvarExtractedVariationType::Deletion,
+                    germline_classification: Some("Benign".into()),
+                    count: 1,
+                },
+                GenesClinvarSvCount {
+                    variation_type: ClinvarExtractedVariationType::Deletion,
+                    germline_classification: Some("Pathogenic".into()),
+                    count: 2,
+                },
+                GenesClinvarSvCount {
+                    variation_type: ClinvarExtractedVariationType::CopyNumberGain,
+                    germline_classification: None,
+                    count: 1,
+                },
+                GenesClinvarSvCount {
+                    variation_type: ClinvarExtractedVariationType::Other,
+                    germline_classification: Some("Benign".into()),
+                    count: 1,
+                },
+            ]
+        );
+        // Without coordinates, there is no largest SV.
+        assert_eq!(summary.largest_pathogenic_deletion, None);
+        assert_eq!(summary.largest_pathogenic_duplication, None);
+    }
+
+    #[test]
+    fn summarize_largest_pathogenic() {
+        let records = vec![
+            sv(
+                "VCV1",
+                VariationType::Deletion,
+                Some("Pathogenic"),
+                Some((1_000, 2_000)),
+            ),
+            sv(
+                "VCV2",
+                VariationType::CopyNumberLoss,
+                Some("Likely pathogenic"),
+                Some((1_000, 5_000)),
+            ),
+            sv(
+                "VCV3",
+                VariationType::Deletion,
+                Some("Benign"),
+                Some((1_000, 9_000)),
+            ),
+            sv(
+                "VCV4",
+                VariationType::CopyNumberGain,
+                Some("Pathogenic/Likely pathogenic"),
+                Some((500, 1_500)),
+            ),
+            sv(
+                "VCV5",
+                VariationType::Duplication,
+                Some("Pathogenic"),
+                Some((600, 1_600)),
+            ),
+            sv(
+                "VCV6",
+                VariationType::Inversion,
+                Some("Pathogenic"),
+                Some((1, 100_000)),
+            ),
+        ];
+
+        let summary = summarize(&records);
+
+        assert_eq!(summary.total, 6);
+        assert_eq!(
+            summary.largest_pathogenic_deletion,
+            Some(GenesClinvarSvRecord {
+                vcv: "VCV2.1".into(),
+                variation_type: ClinvarExtractedVariationType::CopyNumberLoss,
+                germline_classification: Some("Likely pathogenic".into()),
+                chromosome: "1".into(),
+                start: 1_000,
+                stop: 5_000,
+            })
+        );
+        // On ties, the first record is kept.
+        assert_eq!(
+            summary
+                .largest_pathogenic_duplication
+                .map(|record| record.vcv),
+            Some("VCV4.1".into())
+        );
+    }
+
+    #[test]
+    fn check_hgnc_ids_limit() {
+        assert!(check_hgnc_ids(&["HGNC:1100".parse().unwrap()]).is_ok());
+
+        let err = check_hgnc_ids(&[]).unwrap_err();
+        assert_eq!(
+            err.param().map(|param| param.name.as_str()),
+            Some("hgnc_id")
+        );
+
+        let hgnc_ids = (0..=MAX_HGNC_IDS)
+            .map(|i| format!("HGNC:{}", i).parse().unwrap())
+            .collect::<Vec<_>>();
+        let err = check_hgnc_ids(&hgnc_ids).unwrap_err();
+        assert!(
+            err.to_string().contains("at most 100 HGNC IDs are allowed"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Implementation of endpoint `/genes/clinvar-variants`.
+//!
+//! Lists the ClinVar variants within the genomic interval of a gene.  The interval is resolved
+//! from the gene's ClinGen genomic location in the genes database, the variants are read from
+//! the ClinVar minimal database of the requested genome release.
+use actix_web::{
+    get,
+    web::{self, Data, Json, Path},
+};
+
+use crate::{
+    common::{cli::GenomeRelease, keys},
+    genes::ids::HgncId,
+    pbs::{self, clinvar_data::extracted_vars::ExtractedVcvRecord},
+    server::run::{
+        clinvar_data::ClinvarAggregateGermlineReviewStatus,
+        fetch::{fetch_gene_interval, fetch_pos_protobuf},
+        AnnoDb,
+    },
+};
+
+use super::error::CustomError;
+
+/// The default page size to use.
+const DEFAULT_PER_PAGE: u32 = 100;
+
+/// Sort order of the variants.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::Display,
+    strum::EnumString,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GenesClinvarVariantsSort {
+    /// Sort by genomic position.
+    #[default]
+    Position,
+    /// Sort by germline significance, most pathogenic first, then by position.
+    Significance,
+}
+
+/// Parameters for `handle`.
+#[serde_with::skip_serializing_none]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) struct GenesClinvarVariantsQuery {
+    /// The HGNC ID of the gene.
+    #[schema(value_type = String)]
+    #[param(value_type = String)]
+    pub hgnc_id: HgncId,
+    /// The genome release, defaults to GRCh37.
+    pub genome_release: Option<GenomeRelease>,
+    /// Optional 1-based page number.
+    pub page: Option<u32>,
+    /// Optional page size.
+    pub per_page: Option<u32>,
+    /// The sort order, defaults to position.
+    pub sort: Option<GenesClinvarVariantsSort>,
+}
+
+/// One ClinVar variant in the gene.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarVariantRecord {
+    /// The VCV accession, including version.
+    pub vcv: String,
+    /// The chromosome.
+    pub chromosome: String,
+    /// The 1-based VCF position.
+    pub position: u32,
+    /// The VCF reference allele.
+    pub reference: String,
+    /// The VCF alternate allele.
+    pub alternative: String,
+    /// The aggregate germline significance description.
+    pub germline_significance: Option<String>,
+    /// The aggregate germline review status.
+    pub review_status: Option<ClinvarAggregateGermlineReviewStatus>,
+}
+
+impl TryFrom<ExtractedVcvRecord> for GenesClinvarVariantRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(record: ExtractedVcvRecord) -> Result<Self, Self::Error> {
+        let germline = record
+            .classifications
+            .and_then(|classifications| classifications.germline_classification);
+        let review_status = germline
+            .as_ref()
+            .map(|germline| {
+                ClinvarAggregateGermlineReviewStatus::try_from(
+                    pbs::clinvar_data::clinvar_public::AggregateGermlineReviewStatus::try_from(
+                        germline.review_status,
+                    )?,
+                )
+            })
+            .transpose()?;
+        let location = record
+            .sequence_location
+            .ok_or_else(|| anyhow::anyhow!("missing sequence_location"))?;
+        Ok(Self {
+            vcv: record
+                .accession
+                .map(|accession| format!("{}.{}", accession.accession, accession.version))
+                .unwrap_or_default(),
+            chromosome: pbs::clinvar_data::clinvar_public::Chromosome::try_from(location.chr)?
+                .as_chr_name(),
+            position: location.position_vcf.or(location.start).unwrap_or_default(),
+            reference: location.reference_allele_vcf.unwrap_or_default(),
+            alternative: location.alternate_allele_vcf.unwrap_or_default(),
+            germline_significance: germline.and_then(|germline| germline.description),
+            review_status,
+        })
+    }
+}
+
+/// Pagination information.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarVariantsPageInfo {
+    /// The total number of records.
+    pub total: u32,
+    /// The number of records per page.
+    pub per_page: u32,
+    /// The current page number.
+    pub current_page: u32,
+    /// The total number of pages.
+    pub total_pages: u32,
+}
+
+/// Result for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesClinvarVariantsResponse {
+    /// The HGNC ID of the gene.
+    pub hgnc_id: String,
+    /// The genome release.
+    pub genome_release: GenomeRelease,
+    /// The variants in this page.
+    pub records: Vec<GenesClinvarVariantRecord>,
+    /// Pagination information.
+    pub page_info: GenesClinvarVariantsPageInfo,
+}
+
+/// Rank of a germline significance description for sorting, most pathogenic first.
+fn significance_rank(description: Option<&str>) -> usize {
+    const ORDER: &[&str] = &[
+        "pathogenic",
+        "pathogenic/likely pathogenic",
+        "likely pathogenic",
+        "conflicting classifications of pathogenicity",
+        "uncertain significance",
+        "likely benign",
+        "benign/likely benign",
+        "benign",
+    ];
+    description
+        .and_then(|description| {
+            let description = description.to_lowercase();
+            ORDER.iter().position(|value| *value == description)
+        })
+        .unwrap_or(ORDER.len())
+}
+
+/// Load all ClinVar variants between `start` and `stop` (inclusive).
+pub(crate) fn fetch_variants(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    start: keys::Pos,
+    stop: keys::Pos,
+) -> Result<Vec<GenesClinvarVariantRecord>, CustomError> {
+    let lists = fetch_pos_protobuf::<pbs::clinvar::minimal::ExtractedVcvRecordList>(
+        db,
+        AnnoDb::Clinvar.cf_name(),
+        start,
+        stop,
+    )?;
+    lists
+        .into_iter()
+        .flat_map(|list| list.records)
+        .map(|record| {
+            GenesClinvarVariantRecord::try_from(record)
+                .map_err(|e| CustomError::new(anyhow::anyhow!("problem converting record: {}", e)))
+        })
+        .collect()
+}
+
+/// Sort and paginate the variants.
+fn build_response(
+    mut records: Vec<GenesClinvarVariantRecord>,
+    genome_release: GenomeRelease,
+    query: &GenesClinvarVariantsQuery,
+) -> GenesClinvarVariantsResponse {
+    match query.sort.unwrap_or_default() {
+        GenesClinvarVariantsSort::Position => records.sort_by_key(|record| record.position),
+        GenesClinvarVariantsSort::Significance => records.sort_by_key(|record| {
+            (
+                significance_rank(record.germline_significance.as_deref()),
+                record.position,
+            )
+        }),
+    }
+
+    let total = records.len() as u32;
+    let per_page = std::cmp::max(query.per_page.unwrap_or(DEFAULT_PER_PAGE), 1);
+    let total_pages = total.div_ceil(per_page);
+    let current_page = std::cmp::max(query.page.unwrap_or(1), 1);
+    let begin = std::cmp::min((current_page - 1).saturating_mul(per_page), total) as usize;
+    let end = std::cmp::min(begin as u32 + per_page, total) as usize;
+
+    GenesClinvarVariantsResponse {
+        hgnc_id: query.hgnc_id.to_string(),
+        genome_release,
+        records: records.drain(begin..end).collect(),
+        page_info: GenesClinvarVariantsPageInfo {
+            total,
+            per_page,
+            current_page,
+            total_pages,
+        },
+    }
+}
+
+/// List the ClinVar variants within a gene.
+#[utoipa::path(
+    get,
+    operation_id = "genesClinvarVariants",
+    params(GenesClinvarVariantsQuery),
+    responses(
+        (status = 200, description = "ClinVar variants in the gene.", body = GenesClinvarVariantsResponse),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/clinvar-variants")]
+async fn handle(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesClinvarVariantsQuery>,
+) -> actix_web::Result<Json<GenesClinvarVariantsResponse>, CustomError> {
+    let genome_release = query.genome_release.unwrap_or_default();
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+    let clinvar_db =
+        data.annos[genome_release][AnnoDb::Clinvar]
+            .as_ref()
+            .ok_or(CustomError::new(anyhow::anyhow!(
+                "ClinVar database not available for {}",
+                genome_release
+            )))?;
+
+    let (start, stop) = fetch_gene_interval(&genes_db.data.db, &query.hgnc_id, genome_release)?;
+    let records = fetch_variants(&clinvar_db.data, start, stop)?;
+
+    Ok(Json(build_response(records, genome_release, &query)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clinvar_db() -> rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> {
+        rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            "tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.db",
+            ["meta", AnnoDb::Clinvar.cf_name()],
+            false,
+        )
+        .unwrap()
+    }
+
+    fn query(
+        page: Option<u32>,
+        per_page: Option<u32>,
+        sort: Option<GenesClinvarVariantsSort>,
+    ) -> GenesClinvarVariantsQuery {
+        GenesClinvarVariantsQuery {
+            hgnc_id: "HGNC:20324".parse().unwrap(),
+            genome_release: None,
+            page,
+            per_page,
+            sort,
+        }
+    }
+
+    #[test]
+    fn fetch_variants_tgds() -> Result<(), CustomError> {
+        let records = fetch_variants(
+            &clinvar_db(),
+            keys::Pos::from("13", 95_226_000),
+            keys::Pos::from("13", 95_249_000),
+        )?;
+
+        assert_eq!(records.len(), 90);
+        assert!(records.iter().all(|record| record.chromosome == "13"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_response_sort_by_significance() -> Result<(), CustomError> {
+        let records = fetch_variants(
+            &clinvar_db(),
+            keys::Pos::from("13", 95_226_000),
+            keys::Pos::from("13", 95_249_000),
+        )?;
+
+        let response = build_response(
+            records,
+            GenomeRelease::Grch37,
+            &query(
+                Some(1),
+                Some(4),
+                Some(GenesClinvarVariantsSort::Significance),
+            ),
+        );
+
+        assert_eq!(response.page_info.total, 90);
+        assert_eq!(response.page_info.total_pages, 23);
+        assert_eq!(
+            response
+                .records
+                .iter()
+                .map(|record| record.vcv.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "VCV000162460.1",
+                "VCV000162458.1",
+                "VCV000162457.1",
+                "VCV000162456.3"
+            ]
+        );
+        assert_eq!(
+            response.records[0].review_status,
+            Some(ClinvarAggregateGermlineReviewStatus::NoAssertionCriteriaProvided)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_response_sort_by_position() -> Result<(), CustomError> {
+        let records = fetch_variants(
+            &clinvar_db(),
+            keys::Pos::from("13", 95_226_000),
+            keys::Pos::from("13", 95_249_000),
+        )?;
+
+        let response = build_response(
+            records,
+            GenomeRelease::Grch37,
+            &query(Some(23), Some(4), None),
+        );
+
+        assert_eq!(response.page_info.current_page, 23);
+        assert_eq!(response.records.len(), 2);
+        assert!(response.records[0].position <= response.records[1].position);
+        assert_eq!(response.records[1].position, 95_248_751);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gene_range_requires_clingen() {
+        let mut record = crate::pbs::genes::base::Record::default();
+        assert!(crate::genes::cli::query::gene_range(&record, GenomeRelease::Grch37).is_err());
+
+        // An empty location for the genome release is rejected as well.
+        record.clingen = Some(crate::pbs::genes::base::ClingenDosageRecord {
+            genomic_location_38: "chr13:94574053-94596257".into(),
+            ..Default::default()
+        });
+        assert!(crate::genes::cli::query::gene_range(&record, GenomeRelease::Grch37).is_err());
+
+        record.clingen = Some(crate::pbs::genes::base::ClingenDosageRecord {
+            genomic_location_37: "chr13:95226307-95248511".into(),
+            ..Default::default()
+        });
+        let range = crate::genes::cli::query::gene_range(&record, GenomeRelease::Grch37).unwrap();
+        assert_eq!(
+            range,
+            crate::common::spdi::Range::new("13".into(), 95_226_307, 95_248_511)
+        );
+    }
+}
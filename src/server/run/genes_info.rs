@@ -1,16 +1,26 @@
 //! Implementation of endpoint `/api/v1/genes/info`.
 //!
 //! Also includes the implementation of the `/genes/info` endpoint (deprecated).
+//!
+//! Genes are given by HGNC ID or by symbol.  Symbols are resolved as for `/genes/lookup`, so
+//! that renamed genes are also found by their previous or alias symbols.  The response then
+//! has a `symbol_notice` for the gene, see `genes::symbols`.
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    web::{self, Data, Path},
+    HttpRequest, HttpResponse,
 };
-use prost::Message;
 
-use crate::pbs::genes;
+use strum::IntoEnumIterator as _;
+
+use crate::{common::decode::decode_protobuf, genes::ids::HgncId, pbs::genes};
 
-use super::error::CustomError;
+use super::{
+    error::CustomError,
+    etag,
+    genes_lookup::{self, GenesLookupMatchedBy},
+};
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
 
 /// Parameters for `handle`.
@@ -19,8 +29,103 @@ use serde_with::{formats::CommaSeparator, StringWithSeparator};
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::IntoParams)]
 struct GenesInfoQuery {
     /// The HGNC IDs to search for.
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, HgncId>>")]
+    #[param(value_type = Option<Vec<String>>)]
+    pub hgnc_id: Option<Vec<HgncId>>,
+    /// The gene symbols to search for, approved, previous, or alias ones.
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
+    pub symbol: Option<Vec<String>>,
+    /// The record sections to return, e.g., `gnomad_constraints,omim`; all if not given.
+    /// The `hgnc` section is always returned.
     #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
-    pub hgnc_id: Option<Vec<String>>,
+    pub fields: Option<Vec<String>>,
+}
+
+/// Top-level section of a gene record that can be selected with `fields`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString, strum::EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum GenesInfoSection {
+    /// Information from the ACMG secondary finding list.
+    AcmgSf,
+    /// Information from ClinGen dosage curation.
+    Clingen,
+    /// Information from dbNSFP.
+    Dbnsfp,
+    /// Information from the gnomAD constraints database.
+    GnomadConstraints,
+    /// Information from the HGNC database.
+    Hgnc,
+    /// Information from the NCBI gene database.
+    Ncbi,
+    /// Gene to OMIM term annotation.
+    Omim,
+    /// Gene to Orphanet annotation.
+    Orpha,
+    /// rCNV dosage sensitivity scores.
+    Rcnv,
+    /// sHet score.
+    Shet,
+    /// GTEx expression data.
+    Gtex,
+    /// DOMINO score.
+    Domino,
+    /// DECIPHER HI score.
+    DecipherHi,
+    /// PanelApp gene information.
+    Panelapp,
+    /// Conditions record.
+    Conditions,
+}
+
+/// Parse the `fields` parameter into sections, rejecting unknown section names.
+pub fn parse_sections(fields: &[String]) -> Result<Vec<GenesInfoSection>, CustomError> {
+    fields
+        .iter()
+        .map(|field| {
+            field.trim().parse().map_err(|_| {
+                CustomError::invalid_param(
+                    "fields",
+                    Some(field),
+                    &format!(
+                        "unknown section, valid sections are: {}",
+                        GenesInfoSection::iter()
+                            .map(|section| section.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Clear the sections of `record` that are not in `sections`.
+///
+/// The `hgnc` section identifies the gene and is always kept.
+pub fn retain_sections(record: &mut genes::base::Record, sections: &[GenesInfoSection]) {
+    for section in GenesInfoSection::iter().filter(|section| !sections.contains(section)) {
+        match section {
+            GenesInfoSection::AcmgSf => record.acmg_sf = None,
+            GenesInfoSection::Clingen => {
+                record.clingen = None;
+                record.clingen_regions_37.clear();
+                record.clingen_regions_38.clear();
+            }
+            GenesInfoSection::Dbnsfp => record.dbnsfp = None,
+            GenesInfoSection::GnomadConstraints => record.gnomad_constraints = None,
+            GenesInfoSection::Hgnc => (),
+            GenesInfoSection::Ncbi => record.ncbi = None,
+            GenesInfoSection::Omim => record.omim = None,
+            GenesInfoSection::Orpha => record.orpha = None,
+            GenesInfoSection::Rcnv => record.rcnv = None,
+            GenesInfoSection::Shet => record.shet = None,
+            GenesInfoSection::Gtex => record.gtex = None,
+            GenesInfoSection::Domino => record.domino = None,
+            GenesInfoSection::DecipherHi => record.decipher_hi = None,
+            GenesInfoSection::Panelapp => record.panelapp.clear(),
+            GenesInfoSection::Conditions => record.conditions = None,
+        }
+    }
 }
 
 /// Result for `handle`.
@@ -29,6 +134,12 @@ struct GenesInfoQuery {
 struct Container {
     /// The resulting gene information.
     pub genes: indexmap::IndexMap<String, genes::base::Record>,
+    /// Versions of the data sources of the genes database.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub source_versions: std::collections::BTreeMap<String, String>,
+    /// Notices for the genes found by a previous or alias symbol, by HGNC ID.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub symbol_notice: std::collections::BTreeMap<String, String>,
 }
 
 /// Implementation of both endpoints.
@@ -37,6 +148,7 @@ async fn handle_impl(
     _path: Path<()>,
     query: web::Query<GenesInfoQuery>,
 ) -> actix_web::Result<Container, CustomError> {
+    let sections = query.fields.as_deref().map(parse_sections).transpose()?;
     let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
         "genes database not available"
     )))?;
@@ -45,20 +157,58 @@ async fn handle_impl(
         .db
         .cf_handle("genes")
         .expect("no 'genes' column family");
+    // Resolve the symbols to HGNC IDs, with the notices for previous and alias symbols.
+    let mut hgnc_ids = query.hgnc_id.clone().unwrap_or_default();
+    let mut symbol_notice = std::collections::BTreeMap::new();
+    for symbol in query.symbol.iter().flatten() {
+        // Transcripts are not indexed here, gene identifiers are skipped below.
+        let matches = genes_lookup::lookup(
+            &genes_db.data.gene_names,
+            &genes_db.data.name_to_hgnc_idx,
+            &genes_db.data.other_symbol_to_hgnc_idxs,
+            &Default::default(),
+            symbol,
+        );
+        if matches.is_empty() {
+            tracing::debug!("no such gene symbol: {}", symbol);
+        }
+        for (matched_by, idx) in matches {
+            if !matches!(
+                matched_by,
+                GenesLookupMatchedBy::Symbol
+                    | GenesLookupMatchedBy::PrevSymbol
+                    | GenesLookupMatchedBy::AliasSymbol
+            ) {
+                continue;
+            }
+            let gene_name = &genes_db.data.gene_names[idx];
+            if let Some(notice) = genes_lookup::symbol_notice(symbol, matched_by, gene_name) {
+                symbol_notice.insert(gene_name.hgnc_id.to_string(), notice);
+            }
+            hgnc_ids.push(gene_name.hgnc_id.clone());
+        }
+    }
+
     let mut genes = indexmap::IndexMap::new();
-    if let Some(hgnc_id) = query.hgnc_id.as_ref() {
-        for hgnc_id in hgnc_id {
-            if let Some(raw_buf) = genes_db.data.db.get_cf(&cf_genes, hgnc_id).map_err(|e| {
-                CustomError::new(anyhow::anyhow!("problem querying database: {}", e))
-            })? {
-                let record =
-                    genes::base::Record::decode(std::io::Cursor::new(raw_buf)).map_err(|e| {
-                        CustomError::new(anyhow::anyhow!("problem decoding value: {}", e))
-                    })?;
-                genes.insert(hgnc_id.to_string(), record);
-            } else {
-                tracing::debug!("no such gene: {}", hgnc_id);
+    for hgnc_id in &hgnc_ids {
+        if let Some(raw_buf) = genes_db
+            .data
+            .db
+            .get_cf(&cf_genes, hgnc_id.as_str())
+            .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?
+        {
+            let mut record: genes::base::Record = decode_protobuf(
+                &genes_db.data.db,
+                "genes",
+                hgnc_id.as_str().as_bytes(),
+                &raw_buf,
+            )?;
+            if let Some(sections) = sections.as_ref() {
+                retain_sections(&mut record, sections);
             }
+            genes.insert(hgnc_id.to_string(), record);
+        } else {
+            tracing::debug!("no such gene: {}", hgnc_id);
         }
     }
 
@@ -77,7 +227,11 @@ async fn handle_impl(
         .map_err(|e| CustomError::new(anyhow::anyhow!("problem decoding value: {}", e)))?
         .to_string();
 
-    Ok(Container { genes })
+    Ok(Container {
+        genes,
+        source_versions: genes_db.data.source_versions.clone(),
+        symbol_notice,
+    })
 }
 
 /// Query for annotations for one or more genes.
@@ -86,8 +240,13 @@ async fn handle(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
     query: web::Query<GenesInfoQuery>,
-) -> actix_web::Result<Json<Container>, CustomError> {
-    Ok(Json(handle_impl(data, _path, query).await?))
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let etag = etag::genes_etag(&data, &*query)?;
+    if let Some(response) = etag.not_modified(&req) {
+        return Ok(response);
+    }
+    Ok(etag.json(handle_impl(data, _path, query).await?))
 }
 
 /// Query parameters for `handle_with_openapi()`.
@@ -98,8 +257,10 @@ async fn handle(
 )]
 pub struct VersionsInfoQuery {
     /// The HGNC IDs to search for.
-    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
-    pub hgnc_id: Option<Vec<String>>,
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, HgncId>>")]
+    #[schema(value_type = Option<Vec<String>>)]
+    #[param(value_type = Option<Vec<String>>)]
+    pub hgnc_id: Option<Vec<HgncId>>,
 }
 
 /// `GenesInfoResponse` and related types.
@@ -250,6 +411,41 @@ pub mod response {
         }
     }
 
+    /// `ClinGen` dosage sensitivity region overlapping with a gene.
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct GenesClingenRegionOverlap {
+        /// ISCA ID of the region.
+        pub isca_id: String,
+        /// ISCA region name.
+        pub isca_region_name: String,
+        /// Genomic location of the region.
+        pub genomic_location: String,
+        /// Haploinsufficiency score of the region.
+        pub haploinsufficiency_score: Option<GenesClingenDosageScore>,
+        /// Triplosensitivity score of the region.
+        pub triplosensitivity_score: Option<GenesClingenDosageScore>,
+    }
+
+    impl TryFrom<pbs::genes::base::ClingenRegionOverlap> for GenesClingenRegionOverlap {
+        type Error = anyhow::Error;
+
+        fn try_from(record: pbs::genes::base::ClingenRegionOverlap) -> Result<Self, Self::Error> {
+            Ok(Self {
+                isca_id: record.isca_id,
+                isca_region_name: record.isca_region_name,
+                genomic_location: record.genomic_location,
+                haploinsufficiency_score: pbs::genes::base::ClingenDosageScore::try_from(
+                    record.haploinsufficiency_score,
+                )?
+                .into(),
+                triplosensitivity_score: pbs::genes::base::ClingenDosageScore::try_from(
+                    record.triplosensitivity_score,
+                )?
+                .into(),
+            })
+        }
+    }
+
     /// Decipher HI Predictions
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
     pub struct GenesDecipherHiRecord {
@@ -722,6 +918,8 @@ pub mod response {
         pub exac_exp_lof: Option<f64>,
         /// The loss-of-function observed/expected ratio from ExAC.
         pub exac_oe_lof: Option<f64>,
+        /// Per-transcript metrics, if the input has transcript-level rows.
+        pub transcripts: Vec<GenesGnomadConstraintsTranscriptRecord>,
     }
 
     impl From<pbs::genes::base::GnomadConstraintsRecord> for GenesGnomadConstraintsRecord {
@@ -752,6 +950,46 @@ pub mod response {
                 exac_obs_lof: record.exac_obs_lof,
                 exac_exp_lof: record.exac_exp_lof,
                 exac_oe_lof: record.exac_oe_lof,
+                transcripts: record
+                    .transcripts
+                    .into_iter()
+                    .map(GenesGnomadConstraintsTranscriptRecord::from)
+                    .collect(),
+            }
+        }
+    }
+
+    /// Transcript-level metrics from the gnomAD constraints.
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct GenesGnomadConstraintsTranscriptRecord {
+        /// The Ensembl transcript ID.
+        pub transcript_id: String,
+        /// Whether the transcript is the canonical transcript of the gene.
+        pub canonical: bool,
+        /// Whether the transcript is the MANE Select transcript of the gene.
+        pub mane_select: bool,
+        /// The probability of loss-of-function intolerance (pLI score).
+        pub pli: Option<f64>,
+        /// The upper bound of the loss-of-function observed/expected ratio (LOEUF).
+        pub oe_lof_upper: Option<f64>,
+        /// The missense-related Z-score.
+        pub mis_z: Option<f64>,
+        /// The synonymous-related Z-score.
+        pub syn_z: Option<f64>,
+    }
+
+    impl From<pbs::genes::base::GnomadConstraintsTranscriptRecord>
+        for GenesGnomadConstraintsTranscriptRecord
+    {
+        fn from(record: pbs::genes::base::GnomadConstraintsTranscriptRecord) -> Self {
+            Self {
+                transcript_id: record.transcript_id,
+                canonical: record.canonical,
+                mane_select: record.mane_select,
+                pli: record.pli,
+                oe_lof_upper: record.oe_lof_upper,
+                mis_z: record.mis_z,
+                syn_z: record.syn_z,
             }
         }
     }
@@ -1914,6 +2152,12 @@ pub mod response {
         pub panelapp: Vec<GenesPanelAppRecord>,
         /// Conditions record.
         pub conditions: Option<GenesConditionsRecord>,
+        /// ClinGen dosage sensitivity regions overlapping the gene on GRCh37.
+        #[serde(default)]
+        pub clingen_regions_37: Vec<GenesClingenRegionOverlap>,
+        /// ClinGen dosage sensitivity regions overlapping the gene on GRCh38.
+        #[serde(default)]
+        pub clingen_regions_38: Vec<GenesClingenRegionOverlap>,
     }
 
     impl TryFrom<pbs::genes::base::Record> for GenesGeneRecord {
@@ -1948,6 +2192,16 @@ pub mod response {
                     .conditions
                     .map(GenesConditionsRecord::try_from)
                     .transpose()?,
+                clingen_regions_37: value
+                    .clingen_regions_37
+                    .into_iter()
+                    .map(GenesClingenRegionOverlap::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+                clingen_regions_38: value
+                    .clingen_regions_38
+                    .into_iter()
+                    .map(GenesClingenRegionOverlap::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
             })
         }
     }
@@ -2425,6 +2679,12 @@ pub mod response {
         pub panelapp: Vec<GenesPanelAppRecord>,
         /// Conditions record.
         pub conditions: Option<GenesConditionsRecord>,
+        /// ClinGen dosage sensitivity regions overlapping the gene on GRCh37.
+        #[serde(default)]
+        pub clingen_regions_37: Vec<GenesClingenRegionOverlap>,
+        /// ClinGen dosage sensitivity regions overlapping the gene on GRCh38.
+        #[serde(default)]
+        pub clingen_regions_38: Vec<GenesClingenRegionOverlap>,
     }
 
     impl TryFrom<pbs::genes::base::Record> for GenesGeneInfoRecord {
@@ -2454,6 +2714,16 @@ pub mod response {
                     .map(TryInto::try_into)
                     .collect::<Result<_, _>>()?,
                 conditions: value.conditions.map(TryInto::try_into).transpose()?,
+                clingen_regions_37: value
+                    .clingen_regions_37
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+                clingen_regions_38: value
+                    .clingen_regions_38
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
             })
         }
     }
@@ -2463,6 +2733,12 @@ pub mod response {
     pub struct GenesInfoResponse {
         /// Version information of the genes.
         pub genes: Vec<GenesGeneInfoRecord>,
+        /// Versions of the data sources of the genes database, by source name.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        pub source_versions: std::collections::BTreeMap<String, String>,
+        /// Notices for the genes found by a previous or alias symbol, by HGNC ID.
+        #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+        pub symbol_notice: std::collections::BTreeMap<String, String>,
     }
 
     impl TryFrom<super::Container> for GenesInfoResponse {
@@ -2475,6 +2751,8 @@ pub mod response {
                     .into_values()
                     .map(TryInto::try_into)
                     .collect::<Result<_, _>>()?,
+                source_versions: value.source_versions,
+                symbol_notice: value.symbol_notice,
             })
         }
     }
@@ -2489,6 +2767,8 @@ use response::*;
     params(GenesInfoQuery),
     responses(
         (status = 200, description = "Per-gene information.", body = GenesInfoResponse),
+        (status = 304, description = "Not modified since the response with the ETag in `If-None-Match`."),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
         (status = 500, description = "Internal server error.", body = CustomError)
     )
 )]
@@ -2497,10 +2777,414 @@ async fn handle_with_openapi(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
     query: web::Query<GenesInfoQuery>,
-) -> actix_web::Result<Json<GenesInfoResponse>, CustomError> {
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let etag = etag::genes_etag(&data, &*query)?;
+    if let Some(response) = etag.not_modified(&req) {
+        return Ok(response);
+    }
     let container = handle_impl(data, _path, query).await?;
-    let response = container
+    let response: GenesInfoResponse = container
         .try_into()
         .map_err(|e| CustomError::new(anyhow::anyhow!("Failed to convert response: {}", e)))?;
-    Ok(Json(response))
+    Ok(etag.json(response))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::server::run::{GeneInfoDb, WebServerData, WithVersionSpec};
+
+    /// Import the genes example data and open it as for the server.
+    pub(crate) fn data_with_genes(tmp_dir: &TempDir) -> WebServerData {
+        data_with_genes_from(
+            tmp_dir,
+            "tests/genes/omim/omim_diseases.tsv",
+            "tests/genes/orphanet/orphanet_diseases.tsv",
+        )
+    }
+
+    /// Import the example genes database with the given OMIM and Orphanet disease associations.
+    pub(crate) fn data_with_genes_from(
+        tmp_dir: &TempDir,
+        path_in_omim: &str,
+        path_in_orpha: &str,
+    ) -> WebServerData {
+        let path_rocksdb = format!("{}", tmp_dir.join("genes").display());
+        crate::genes::cli::import::run(
+            &crate::common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::genes::cli::import::Args {
+                path_in_gnomad_constraints: String::from(
+                    "tests/genes/gnomad_constraints/v4.1/gnomad_constraints.tsv",
+                ),
+                path_in_omim: String::from(path_in_omim),
+                path_in_orpha: String::from(path_in_orpha),
+                path_in_clingen_regions_37: None,
+                path_source_versions: Some(String::from("tests/genes/source_versions.yaml")),
+                ..crate::genes::cli::import::test::example_args(&path_rocksdb)
+            },
+        )
+        .unwrap();
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            path_rocksdb,
+            ["meta", "genes", crate::genes::diseases::CF_NAME_BY_DISEASE],
+            false,
+        )
+        .unwrap();
+        let gene_names = crate::server::run::extract_gene_names(&db).unwrap();
+        WebServerData {
+            genes: Some(WithVersionSpec {
+                data: GeneInfoDb {
+                    source_versions: crate::genes::cli::import::read_source_versions(&db).unwrap(),
+                    db,
+                    db_clinvar: None,
+                    name_to_hgnc_idx: crate::server::run::build_name_to_hgnc_idx(&gene_names),
+                    other_symbol_to_hgnc_idxs: crate::server::run::build_other_symbol_to_hgnc_idxs(
+                        &gene_names,
+                    ),
+                    transcript_to_hgnc_idxs: Default::default(),
+                    gene_names,
+                    acmg_sf: Vec::new(),
+                },
+                version_spec: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn call_info(data: WebServerData, query: &str) -> (StatusCode, actix_web::web::Bytes) {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data))
+                .service(handle_with_openapi),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/genes/info?{}", query))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        (resp.status(), test::read_body(resp).await)
+    }
+
+    #[test]
+    fn parse_sections_values() {
+        assert_eq!(
+            parse_sections(&[String::from("hgnc"), String::from(" gnomad_constraints")])
+                .map_err(|e| e.to_string()),
+            Ok(vec![
+                GenesInfoSection::Hgnc,
+                GenesInfoSection::GnomadConstraints
+            ])
+        );
+
+        let err =
+            parse_sections(&[String::from("omim"), String::from("interactions")]).unwrap_err();
+        assert_eq!(
+            err.param().and_then(|param| param.value.as_deref()),
+            Some("interactions")
+        );
+        assert!(err.to_string().contains("acmg_sf, clingen, dbnsfp"));
+    }
+
+    #[test]
+    fn retain_sections_keeps_hgnc() {
+        let mut record = genes::base::Record {
+            hgnc: Some(Default::default()),
+            dbnsfp: Some(Default::default()),
+            omim: Some(Default::default()),
+            panelapp: vec![Default::default()],
+            ..Default::default()
+        };
+
+        retain_sections(&mut record, &[GenesInfoSection::Omim]);
+
+        assert!(record.hgnc.is_some());
+        assert!(record.omim.is_some());
+        assert!(record.dbnsfp.is_none());
+        assert!(record.panelapp.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn info_fields() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_genes(&tmp_dir);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data))
+                .service(handle_with_openapi),
+        )
+        .await;
+        let call = |query: &'static str| {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/v1/genes/info?hgnc_id=HGNC:1097{}", query))
+                .to_request();
+            test::call_and_read_body(&app, req)
+        };
+
+        let full = call("").await;
+        let projected = call("&fields=gnomad_constraints").await;
+
+        assert!(projected.len() < full.len());
+        let full: GenesInfoResponse = serde_json::from_slice(&full)?;
+        let projected: GenesInfoResponse = serde_json::from_slice(&projected)?;
+        assert!(full.genes[0].dbnsfp.is_some());
+        assert_eq!(projected.genes.len(), 1);
+        let gene = &projected.genes[0];
+        assert_eq!(
+            gene.hgnc.as_ref().map(|hgnc| hgnc.symbol.as_str()),
+            Some("BRAF")
+        );
+        assert!(gene.gnomad_constraints.is_some());
+        assert!(gene.dbnsfp.is_none());
+        assert!(gene.ncbi.is_none());
+        assert!(gene.panelapp.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn info_source_versions() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+
+        let (status, body) = call_info(data_with_genes(&tmp_dir), "hgnc_id=HGNC:1097").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: GenesInfoResponse = serde_json::from_slice(&body)?;
+        assert_eq!(
+            response.source_versions,
+            std::collections::BTreeMap::from([
+                (String::from("hgnc"), String::from("2024-01-02")),
+                (String::from("omim"), String::from("2023-12-18")),
+                (String::from("panelapp"), String::from("2024-01-05")),
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn info_symbol() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = Data::new(data_with_genes(&tmp_dir));
+        let app = test::init_service(App::new().app_data(data).service(handle_with_openapi)).await;
+
+        // The approved, alias, and previous symbol match paths.
+        for (symbol, hgnc_id, notice) in [
+            ("TTN", "HGNC:12403", None),
+            (
+                "BRAF1",
+                "HGNC:1097",
+                Some("queried symbol BRAF1 is an alias of BRAF; returning current record"),
+            ),
+            (
+                "A2MP",
+                "HGNC:8",
+                Some(
+                    "queried symbol A2MP was replaced by A2MP1 on 2010-02-24; \
+                    returning current record",
+                ),
+            ),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/v1/genes/info?symbol={}&fields=hgnc", symbol))
+                .to_request();
+            let response: GenesInfoResponse = test::call_and_read_body_json(&app, req).await;
+
+            assert_eq!(
+                response
+                    .genes
+                    .iter()
+                    .map(|gene| gene.hgnc.as_ref().map(|hgnc| hgnc.hgnc_id.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![Some(hgnc_id)],
+                "{}",
+                symbol
+            );
+            assert_eq!(
+                response.symbol_notice,
+                notice
+                    .map(|notice| (hgnc_id.to_string(), notice.to_string()))
+                    .into_iter()
+                    .collect(),
+                "{}",
+                symbol
+            );
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn info_fields_unknown() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+
+        let (status, body) = call_info(
+            data_with_genes(&tmp_dir),
+            "hgnc_id=HGNC:1097&fields=hgnc,go_terms",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let err: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(err["param"]["name"], "fields");
+        assert_eq!(err["param"]["value"], "go_terms");
+        assert!(err["err"].as_str().unwrap().contains("gnomad_constraints"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn info_invalid_hgnc_id() -> Result<(), anyhow::Error> {
+        let (status, body) = call_info(
+            WebServerData::default(),
+            "hgnc_id=HGNC:1097,ENSG00000141510",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let body = String::from_utf8(body.to_vec())?;
+        assert!(
+            body.contains("expected HGNC:<number>, got ENSG00000141510"),
+            "{}",
+            body
+        );
+
+        Ok(())
+    }
+
+    /// Return the request of `/api/v1/genes/info` with `query` and `if_none_match`.
+    fn info_etag_request(query: &str, if_none_match: Option<&str>) -> test::TestRequest {
+        let req = test::TestRequest::get().uri(&format!("/api/v1/genes/info?{}", query));
+        match if_none_match {
+            Some(if_none_match) => {
+                req.insert_header((actix_web::http::header::IF_NONE_MATCH, if_none_match))
+            }
+            None => req,
+        }
+    }
+
+    /// Return the status, ETag, and body of `resp`.
+    async fn status_etag_body(
+        resp: actix_web::dev::ServiceResponse,
+    ) -> (StatusCode, String, actix_web::web::Bytes) {
+        let etag = resp
+            .headers()
+            .get(actix_web::http::header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        (resp.status(), etag, test::read_body(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn info_etag() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data_with_genes(&tmp_dir)))
+                .service(handle_with_openapi),
+        )
+        .await;
+
+        let (status, etag, body) = status_etag_body(
+            test::call_service(
+                &app,
+                info_etag_request("hgnc_id=HGNC:1097", None).to_request(),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!etag.is_empty());
+        assert!(!body.is_empty());
+
+        let (status, etag_again, body) = status_etag_body(
+            test::call_service(
+                &app,
+                info_etag_request("hgnc_id=HGNC:1097", Some(&etag)).to_request(),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert_eq!(etag_again, etag);
+        assert!(body.is_empty());
+
+        let (status, etag_other, _) = status_etag_body(
+            test::call_service(
+                &app,
+                info_etag_request("hgnc_id=HGNC:12403", Some(&etag)).to_request(),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(etag_other, etag);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn info_etag_changes_with_database() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data_with_genes(&tmp_dir)))
+                .service(handle_with_openapi),
+        )
+        .await;
+        let (_, etag, _) = status_etag_body(
+            test::call_service(
+                &app,
+                info_etag_request("hgnc_id=HGNC:1097", None).to_request(),
+            )
+            .await,
+        )
+        .await;
+
+        // Another build of the genes database with a new version.
+        let tmp_dir_swapped = TempDir::default();
+        let mut data_swapped = data_with_genes(&tmp_dir_swapped);
+        data_swapped.genes.as_mut().unwrap().version_spec =
+            Some(crate::server::run::versions::schema::VersionSpec {
+                identifier: String::from("genes"),
+                title: String::from("genes"),
+                creator: String::from("annonars"),
+                contributor: None,
+                format: String::from("application/x-rocksdb"),
+                date: String::from("20240105"),
+                version: String::from("20240105+0.0.0"),
+                genome_release: None,
+                description: String::from("genes"),
+                source: Vec::new(),
+                created_from: Vec::new(),
+            });
+        let app_swapped = test::init_service(
+            App::new()
+                .app_data(Data::new(data_swapped))
+                .service(handle_with_openapi),
+        )
+        .await;
+
+        let (status, etag_swapped, _) = status_etag_body(
+            test::call_service(
+                &app_swapped,
+                info_etag_request("hgnc_id=HGNC:1097", Some(&etag)).to_request(),
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(etag_swapped, etag);
+
+        Ok(())
+    }
 }
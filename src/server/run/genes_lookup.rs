@@ -3,13 +3,26 @@
 //! Also includes the implementation of the `/genes/lookup` endpoint (deprecated).
 //!
 //! In contrast to gene search, more than one query may be given but this must match exactly
-//! the symbol or HGNC/NCBI/ENSEMBL identifier.
+//! the symbol or HGNC/NCBI/ENSEMBL identifier, a previous or alias symbol, or a transcript
+//! accession from the MANE Select or RefSeq accession lists of HGNC.  Transcript accessions
+//! are matched with the version if given and found, and without the version otherwise.  A
+//! previous or alias symbol or a transcript accession may match several genes, all of which
+//! are returned by `/api/v1/genes/lookup`.  Matches by previous or alias symbol come with a
+//! `symbol_notice`, see `genes::symbols`.
+use std::collections::HashMap;
+
 use actix_web::{
     get,
     web::{self, Data, Json, Path},
 };
 
-use crate::server::run::GeneNames;
+use crate::{
+    genes::{
+        mane::accession_without_version,
+        symbols::{self, SymbolMatch},
+    },
+    server::run::GeneNames,
+};
 
 use super::error::CustomError;
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
@@ -27,39 +40,212 @@ pub(crate) struct GenesLookupQuery {
     pub q: Vec<String>,
 }
 
+/// How a query matched a gene.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GenesLookupMatchedBy {
+    /// HGNC gene ID.
+    HgncId,
+    /// HGNC gene symbol.
+    Symbol,
+    /// Previous HGNC gene symbol.
+    PrevSymbol,
+    /// HGNC alias symbol.
+    AliasSymbol,
+    /// ENSEMBL gene ID.
+    EnsemblGeneId,
+    /// NCBI gene ID.
+    NcbiGeneId,
+    /// Ensembl or RefSeq transcript accession.
+    Transcript,
+}
+
+/// Build the mapping from transcript accession to indices in `gene_names`.
+///
+/// The MANE Select and RefSeq accessions are inserted both as given and without version.
+pub(crate) fn build_transcript_index(gene_names: &[GeneNames]) -> HashMap<String, Vec<usize>> {
+    let mut result: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, gene_name) in gene_names.iter().enumerate() {
+        for accession in gene_name
+            .mane_select
+            .iter()
+            .chain(gene_name.refseq_accession.iter())
+        {
+            for key in [accession.as_str(), accession_without_version(accession)] {
+                let idxs = result.entry(key.to_string()).or_default();
+                if !idxs.contains(&idx) {
+                    idxs.push(idx);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Return the notice for the query `q` that matched `gene_name` by `matched_by`, if it matched
+/// by previous or alias symbol.
+pub(crate) fn symbol_notice(
+    q: &str,
+    matched_by: GenesLookupMatchedBy,
+    gene_name: &GeneNames,
+) -> Option<String> {
+    let symbol_match = match matched_by {
+        GenesLookupMatchedBy::PrevSymbol => SymbolMatch::Previous,
+        GenesLookupMatchedBy::AliasSymbol => SymbolMatch::Alias,
+        _ => return None,
+    };
+    symbols::symbol_notice(
+        q,
+        symbol_match,
+        &gene_name.symbol,
+        gene_name.date_symbol_changed.as_deref(),
+    )
+}
+
+/// Look up the genes for the query `q`.
+///
+/// Gene identifiers and approved symbols take precedence over previous and alias symbols,
+/// which take precedence over transcript accessions.  The result is empty if nothing matches
+/// and has more than one entry for ambiguous symbols and transcript accessions.
+pub(crate) fn lookup(
+    gene_names: &[GeneNames],
+    name_to_hgnc_idx: &HashMap<String, usize>,
+    other_symbol_to_hgnc_idxs: &HashMap<String, Vec<usize>>,
+    transcript_to_hgnc_idxs: &HashMap<String, Vec<usize>>,
+    q: &str,
+) -> Vec<(GenesLookupMatchedBy, usize)> {
+    if let Some(idx) = name_to_hgnc_idx.get(q) {
+        let gene_name = &gene_names[*idx];
+        let matched_by = if gene_name.hgnc_id == q {
+            GenesLookupMatchedBy::HgncId
+        } else if gene_name.ensembl_gene_id.as_ref().is_some_and(|id| id == q) {
+            GenesLookupMatchedBy::EnsemblGeneId
+        } else if gene_name.ncbi_gene_id.as_ref().is_some_and(|id| id == q) {
+            GenesLookupMatchedBy::NcbiGeneId
+        } else {
+            GenesLookupMatchedBy::Symbol
+        };
+        return vec![(matched_by, *idx)];
+    }
+
+    if let Some(idxs) = other_symbol_to_hgnc_idxs.get(q) {
+        return idxs
+            .iter()
+            .filter_map(|idx| {
+                let gene_name = &gene_names[*idx];
+                let matched_by = match symbols::match_symbol(
+                    q,
+                    &gene_name.symbol,
+                    &gene_name.prev_symbol,
+                    &gene_name.alias_symbol,
+                )? {
+                    SymbolMatch::Approved => GenesLookupMatchedBy::Symbol,
+                    SymbolMatch::Previous => GenesLookupMatchedBy::PrevSymbol,
+                    SymbolMatch::Alias => GenesLookupMatchedBy::AliasSymbol,
+                };
+                Some((matched_by, *idx))
+            })
+            .collect();
+    }
+
+    transcript_to_hgnc_idxs
+        .get(q)
+        .or_else(|| transcript_to_hgnc_idxs.get(accession_without_version(q)))
+        .map(|idxs| {
+            idxs.iter()
+                .map(|idx| (GenesLookupMatchedBy::Transcript, *idx))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Result for `handle`.
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[serde_with::skip_serializing_none]
 struct Container {
     // TODO: add data version
-    /// The resulting gene information.
+    /// The resulting gene information, the first gene only for ambiguous transcripts.
     pub genes: indexmap::IndexMap<String, Option<GeneNames>>,
 }
 
+impl From<GenesLookupResponse> for Container {
+    fn from(response: GenesLookupResponse) -> Self {
+        let mut genes = indexmap::IndexMap::new();
+        for entry in response.genes {
+            genes.entry(entry.query).or_insert(entry.gene_names);
+        }
+        Self { genes }
+    }
+}
+
 /// Implementation of both endpoints.
 async fn handle_impl(
     data: Data<crate::server::run::WebServerData>,
     _path: Path<()>,
     query: web::Query<GenesLookupQuery>,
-) -> actix_web::Result<Container, CustomError> {
+) -> actix_web::Result<GenesLookupResponse, CustomError> {
     let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
         "genes database not available"
     )))?;
 
-    let genes = indexmap::IndexMap::from_iter(query.q.iter().map(|q| {
-        let v = genes_db
-            .data
-            .name_to_hgnc_idx
-            .get(q)
-            .map(|idx| genes_db.data.gene_names[*idx].clone());
-        (q.clone(), v)
-    }));
-
-    Ok(Container {
-        // server_version: VERSION.to_string(),
-        // builder_version,
-        genes,
-    })
+    Ok(build_response(
+        &genes_db.data.gene_names,
+        &genes_db.data.name_to_hgnc_idx,
+        &genes_db.data.other_symbol_to_hgnc_idxs,
+        &genes_db.data.transcript_to_hgnc_idxs,
+        &query.q,
+    ))
+}
+
+/// Build the response for the queries `qs`.
+///
+/// There is one entry for each match of each query and one without gene for queries that did
+/// not match.
+pub(crate) fn build_response(
+    gene_names: &[GeneNames],
+    name_to_hgnc_idx: &HashMap<String, usize>,
+    other_symbol_to_hgnc_idxs: &HashMap<String, Vec<usize>>,
+    transcript_to_hgnc_idxs: &HashMap<String, Vec<usize>>,
+    qs: &[String],
+) -> GenesLookupResponse {
+    let mut genes = Vec::new();
+    for q in qs {
+        let matches = lookup(
+            gene_names,
+            name_to_hgnc_idx,
+            other_symbol_to_hgnc_idxs,
+            transcript_to_hgnc_idxs,
+            q,
+        );
+        if matches.is_empty() {
+            genes.push(GenesLookupResultEntry {
+                query: q.clone(),
+                gene_names: None,
+                matched_by: None,
+                symbol_notice: None,
+            });
+        }
+        for (matched_by, idx) in matches {
+            genes.push(GenesLookupResultEntry {
+                query: q.clone(),
+                gene_names: Some(gene_names[idx].clone()),
+                matched_by: Some(matched_by),
+                symbol_notice: symbol_notice(q, matched_by, &gene_names[idx]),
+            });
+        }
+    }
+    GenesLookupResponse { genes }
 }
 
 /// Query for annotations for one variant.
@@ -69,7 +255,7 @@ async fn handle(
     path: Path<()>,
     query: web::Query<GenesLookupQuery>,
 ) -> actix_web::Result<Json<Container>, CustomError> {
-    Ok(Json(handle_impl(data, path, query).await?))
+    Ok(Json(handle_impl(data, path, query).await?.into()))
 }
 
 /// One result entry in the response.
@@ -81,6 +267,11 @@ pub(crate) struct GenesLookupResultEntry {
     pub query: String,
     /// The gene names information.
     pub gene_names: Option<GeneNames>,
+    /// How the query matched the gene.
+    pub matched_by: Option<GenesLookupMatchedBy>,
+    /// Notice that the query is a previous or alias symbol of the gene.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol_notice: Option<String>,
 }
 
 /// Result for `async fn handle_with_openapi(
@@ -92,18 +283,6 @@ pub(crate) struct GenesLookupResponse {
     pub genes: Vec<GenesLookupResultEntry>,
 }
 
-impl From<Container> for GenesLookupResponse {
-    fn from(container: Container) -> Self {
-        Self {
-            genes: container
-                .genes
-                .into_iter()
-                .map(|(query, gene_names)| GenesLookupResultEntry { query, gene_names })
-                .collect(),
-        }
-    }
-}
-
 /// Search for genes.
 #[utoipa::path(
     get,
@@ -120,5 +299,223 @@ async fn handle_with_openapi(
     path: Path<()>,
     query: web::Query<GenesLookupQuery>,
 ) -> actix_web::Result<Json<GenesLookupResponse>, CustomError> {
-    Ok(Json(handle_impl(data, path, query).await?.into()))
+    Ok(Json(handle_impl(data, path, query).await?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::server::run::{build_name_to_hgnc_idx, build_other_symbol_to_hgnc_idxs};
+
+    fn gene_names(
+        hgnc_id: &str,
+        symbol: &str,
+        mane_select: &[&str],
+        refseq_accession: &[&str],
+    ) -> GeneNames {
+        GeneNames {
+            hgnc_id: hgnc_id.parse().unwrap(),
+            symbol: symbol.into(),
+            name: format!("{} gene", symbol),
+            alias_symbol: vec![],
+            alias_name: vec![],
+            prev_symbol: vec![],
+            date_symbol_changed: None,
+            ensembl_gene_id: Some(format!("ENSG{}", &hgnc_id[5..]).parse().unwrap()),
+            ncbi_gene_id: Some(hgnc_id[5..].parse().unwrap()),
+            refseq_accession: refseq_accession.iter().map(|s| s.to_string()).collect(),
+            uniprot_ids: vec![],
+            omim_id: vec![],
+            mane_select: mane_select.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// BRAF, two genes sharing the RefSeq accession `NM_000999` (as for readthrough
+    /// transcripts) and the alias symbol `GENEAB`, and PCLAF, formerly KIAA0101.
+    fn all_gene_names() -> Vec<GeneNames> {
+        let mut braf = gene_names(
+            "HGNC:1097",
+            "BRAF",
+            &["ENST00000646891.2", "NM_004333.6"],
+            &["NM_004333"],
+        );
+        braf.alias_symbol = vec![String::from("BRAF1")];
+        let mut genea = gene_names("HGNC:1", "GENEA", &["NM_000999.2"], &["NM_000999"]);
+        genea.alias_symbol = vec![String::from("GENEAB")];
+        let mut geneb = gene_names("HGNC:2", "GENEB", &[], &["NM_000999"]);
+        geneb.alias_symbol = vec![String::from("GENEAB")];
+        let mut pclaf = gene_names("HGNC:28961", "PCLAF", &[], &[]);
+        pclaf.prev_symbol = vec![String::from("KIAA0101")];
+        pclaf.alias_symbol = vec![String::from("PAF15")];
+        pclaf.date_symbol_changed = Some(String::from("2015-06-12"));
+        vec![braf, genea, geneb, pclaf]
+    }
+
+    fn run_lookup(q: &str) -> Vec<(GenesLookupMatchedBy, String)> {
+        let gene_names = all_gene_names();
+        lookup(
+            &gene_names,
+            &build_name_to_hgnc_idx(&gene_names),
+            &build_other_symbol_to_hgnc_idxs(&gene_names),
+            &build_transcript_index(&gene_names),
+            q,
+        )
+        .into_iter()
+        .map(|(matched_by, idx)| (matched_by, gene_names[idx].symbol.clone()))
+        .collect()
+    }
+
+    #[rstest::rstest]
+    #[case::hgnc_id("HGNC:1097", GenesLookupMatchedBy::HgncId)]
+    #[case::symbol("BRAF", GenesLookupMatchedBy::Symbol)]
+    #[case::ensembl_gene_id("ENSG1097", GenesLookupMatchedBy::EnsemblGeneId)]
+    #[case::ncbi_gene_id("1097", GenesLookupMatchedBy::NcbiGeneId)]
+    #[case::mane_refseq_versioned("NM_004333.6", GenesLookupMatchedBy::Transcript)]
+    #[case::mane_refseq_unversioned("NM_004333", GenesLookupMatchedBy::Transcript)]
+    #[case::mane_refseq_other_version("NM_004333.4", GenesLookupMatchedBy::Transcript)]
+    #[case::mane_ensembl_versioned("ENST00000646891.2", GenesLookupMatchedBy::Transcript)]
+    #[case::mane_ensembl_unversioned("ENST00000646891", GenesLookupMatchedBy::Transcript)]
+    fn lookup_braf(#[case] q: &str, #[case] matched_by: GenesLookupMatchedBy) {
+        assert_eq!(run_lookup(q), vec![(matched_by, String::from("BRAF"))]);
+    }
+
+    #[test]
+    fn lookup_ambiguous_transcript() {
+        let expected = vec![
+            (GenesLookupMatchedBy::Transcript, String::from("GENEA")),
+            (GenesLookupMatchedBy::Transcript, String::from("GENEB")),
+        ];
+
+        assert_eq!(run_lookup("NM_000999"), expected);
+        // The versioned MANE Select accession is only known for GENEA.
+        assert_eq!(run_lookup("NM_000999.2"), expected[..1].to_vec());
+        // Unknown versions fall back to the accession without version.
+        assert_eq!(run_lookup("NM_000999.3"), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::alias("BRAF1", GenesLookupMatchedBy::AliasSymbol, "BRAF")]
+    #[case::prev("KIAA0101", GenesLookupMatchedBy::PrevSymbol, "PCLAF")]
+    #[case::approved_over_alias("PCLAF", GenesLookupMatchedBy::Symbol, "PCLAF")]
+    fn lookup_other_symbol(
+        #[case] q: &str,
+        #[case] matched_by: GenesLookupMatchedBy,
+        #[case] symbol: &str,
+    ) {
+        assert_eq!(run_lookup(q), vec![(matched_by, String::from(symbol))]);
+    }
+
+    #[test]
+    fn lookup_ambiguous_alias() {
+        assert_eq!(
+            run_lookup("GENEAB"),
+            vec![
+                (GenesLookupMatchedBy::AliasSymbol, String::from("GENEA")),
+                (GenesLookupMatchedBy::AliasSymbol, String::from("GENEB")),
+            ]
+        );
+    }
+
+    #[test]
+    fn build_response_symbol_notice() {
+        let gene_names = all_gene_names();
+        let qs = vec![
+            String::from("PCLAF"),
+            String::from("PAF15"),
+            String::from("KIAA0101"),
+        ];
+
+        let response = build_response(
+            &gene_names,
+            &build_name_to_hgnc_idx(&gene_names),
+            &build_other_symbol_to_hgnc_idxs(&gene_names),
+            &build_transcript_index(&gene_names),
+            &qs,
+        );
+
+        assert_eq!(
+            response
+                .genes
+                .iter()
+                .map(|entry| entry.symbol_notice.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                Some("queried symbol PAF15 is an alias of PCLAF; returning current record"),
+                Some(
+                    "queried symbol KIAA0101 was replaced by PCLAF on 2015-06-12; \
+                    returning current record"
+                ),
+            ]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("NM_123456.1")]
+    #[case("ENST00000646891x")]
+    #[case("braf")]
+    fn lookup_no_match(#[case] q: &str) {
+        assert_eq!(run_lookup(q), vec![]);
+    }
+
+    #[test]
+    fn build_response_and_legacy_container() {
+        let gene_names = all_gene_names();
+        let qs = vec![
+            String::from("NM_000999"),
+            String::from("BRAF"),
+            String::from("XXX"),
+        ];
+
+        let response = build_response(
+            &gene_names,
+            &build_name_to_hgnc_idx(&gene_names),
+            &build_other_symbol_to_hgnc_idxs(&gene_names),
+            &build_transcript_index(&gene_names),
+            &qs,
+        );
+
+        let entries = response
+            .genes
+            .iter()
+            .map(|entry| {
+                (
+                    entry.query.as_str(),
+                    entry.gene_names.as_ref().map(|g| g.symbol.as_str()),
+                    entry.matched_by,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "NM_000999",
+                    Some("GENEA"),
+                    Some(GenesLookupMatchedBy::Transcript)
+                ),
+                (
+                    "NM_000999",
+                    Some("GENEB"),
+                    Some(GenesLookupMatchedBy::Transcript)
+                ),
+                ("BRAF", Some("BRAF"), Some(GenesLookupMatchedBy::Symbol)),
+                ("XXX", None, None),
+            ]
+        );
+
+        let container: Container = response.into();
+        assert_eq!(
+            container
+                .genes
+                .iter()
+                .map(|(q, g)| (q.as_str(), g.as_ref().map(|g| g.symbol.as_str())))
+                .collect::<Vec<_>>(),
+            vec![
+                ("NM_000999", Some("GENEA")),
+                ("BRAF", Some("BRAF")),
+                ("XXX", None),
+            ]
+        );
+    }
 }
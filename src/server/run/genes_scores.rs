@@ -0,0 +1,553 @@
+//! Compact per-gene dosage sensitivity and constraint scores.
+//!
+//! Used for enriching the `/genes/search` results such that hits can be ranked without an
+//! additional `/genes/info` call per gene.
+//!
+//! Also includes the implementation of the `/genes/scores` endpoint that returns a matrix of
+//! selected scores for all genes.  The genes column family is read in chunks of
+//! `CHUNK_SIZE` genes while the response is streamed, one line per gene, such that the
+//! memory use does not grow with the number of genes.
+use actix_web::{
+    body::{BodySize, MessageBody},
+    get,
+    web::{self, Bytes, Data, Path},
+    HttpResponse,
+};
+use serde_with::{formats::CommaSeparator, StringWithSeparator};
+use strum::IntoEnumIterator as _;
+
+use crate::{common::decode::decode_protobuf, pbs::genes};
+
+use super::{error::CustomError, genes_xrefs::HEADER_GENES_DB_VERSION, WebServerData};
+
+/// Number of genes read from the database per streamed chunk of `/genes/scores`.
+const CHUNK_SIZE: usize = 1000;
+
+/// Compact dosage sensitivity and constraint scores of one gene.
+///
+/// Scores that are not available for the gene are `null`.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct GenesScores {
+    /// rCNV probability of haploinsufficiency (pHaplo).
+    pub p_haplo: Option<f64>,
+    /// rCNV probability of triplosensitivity (pTriplo).
+    pub p_triplo: Option<f64>,
+    /// Selection coefficient of heterozygous loss-of-function (sHet).
+    pub s_het: Option<f64>,
+    /// DOMINO score.
+    pub domino: Option<f64>,
+    /// DECIPHER probability of haploinsufficiency (P(HI)).
+    pub decipher_p_hi: Option<f64>,
+    /// gnomAD probability of loss-of-function intolerance (pLI).
+    pub gnomad_pli: Option<f64>,
+    /// gnomAD upper bound of the loss-of-function observed/expected ratio (LOEUF).
+    pub gnomad_loeuf: Option<f64>,
+}
+
+impl From<&genes::base::Record> for GenesScores {
+    fn from(record: &genes::base::Record) -> Self {
+        Self {
+            p_haplo: record.rcnv.as_ref().map(|rcnv| rcnv.p_haplo),
+            p_triplo: record.rcnv.as_ref().map(|rcnv| rcnv.p_triplo),
+            s_het: record.shet.as_ref().map(|shet| shet.s_het),
+            domino: record.domino.as_ref().map(|domino| domino.score),
+            decipher_p_hi: record.decipher_hi.as_ref().map(|decipher| decipher.p_hi),
+            gnomad_pli: record
+                .gnomad_constraints
+                .as_ref()
+                .and_then(|constraints| constraints.pli),
+            gnomad_loeuf: record
+                .gnomad_constraints
+                .as_ref()
+                .and_then(|constraints| constraints.oe_lof_upper),
+        }
+    }
+}
+
+/// Fetch the scores of the genes with the given HGNC IDs with one `multi_get_cf` call.
+///
+/// The result has one entry per HGNC ID; genes not in the database have all scores `null`.
+pub fn fetch_scores<S: AsRef<str>>(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    hgnc_ids: &[S],
+) -> Result<Vec<GenesScores>, CustomError> {
+    let cf_genes = db.cf_handle("genes").expect("no 'genes' column family");
+    db.multi_get_cf(
+        hgnc_ids
+            .iter()
+            .map(|hgnc_id| (&cf_genes, hgnc_id.as_ref().as_bytes())),
+    )
+    .into_iter()
+    .zip(hgnc_ids.iter())
+    .map(|(raw_buf, hgnc_id)| {
+        let raw_buf = raw_buf
+            .map_err(|e| CustomError::new(anyhow::anyhow!("problem querying database: {}", e)))?;
+        Ok(match raw_buf {
+            Some(raw_buf) => {
+                let record: genes::base::Record =
+                    decode_protobuf(db, "genes", hgnc_id.as_ref().as_bytes(), &raw_buf)?;
+                GenesScores::from(&record)
+            }
+            None => GenesScores::default(),
+        })
+    })
+    .collect()
+}
+
+/// A score that can be selected with the `fields` parameter of `/genes/scores`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString, strum::EnumIter)]
+pub enum GenesScoresField {
+    /// gnomAD probability of loss-of-function intolerance (pLI).
+    #[strum(to_string = "pli")]
+    Pli,
+    /// gnomAD upper bound of the loss-of-function observed/expected ratio (LOEUF).
+    #[strum(to_string = "oe_lof_upper", serialize = "loeuf")]
+    OeLofUpper,
+    /// gnomAD missense Z-score.
+    #[strum(to_string = "mis_z")]
+    MisZ,
+    /// Selection coefficient of heterozygous loss-of-function (sHet).
+    #[strum(to_string = "shet", serialize = "s_het")]
+    Shet,
+    /// rCNV probability of haploinsufficiency (pHaplo).
+    #[strum(to_string = "p_haplo", serialize = "phaplo")]
+    PHaplo,
+    /// rCNV probability of triplosensitivity (pTriplo).
+    #[strum(to_string = "p_triplo", serialize = "ptriplo")]
+    PTriplo,
+    /// DOMINO score.
+    #[strum(to_string = "domino")]
+    Domino,
+    /// DECIPHER probability of haploinsufficiency (P(HI)).
+    #[strum(to_string = "decipher_p_hi", serialize = "p_hi")]
+    DecipherPHi,
+}
+
+impl GenesScoresField {
+    /// Return the value of the score in `record`, if any.
+    pub fn value(&self, record: &genes::base::Record) -> Option<f64> {
+        match self {
+            GenesScoresField::Pli => record.gnomad_constraints.as_ref().and_then(|c| c.pli),
+            GenesScoresField::OeLofUpper => record
+                .gnomad_constraints
+                .as_ref()
+                .and_then(|c| c.oe_lof_upper),
+            GenesScoresField::MisZ => record.gnomad_constraints.as_ref().and_then(|c| c.mis_z),
+            GenesScoresField::Shet => record.shet.as_ref().map(|shet| shet.s_het),
+            GenesScoresField::PHaplo => record.rcnv.as_ref().map(|rcnv| rcnv.p_haplo),
+            GenesScoresField::PTriplo => record.rcnv.as_ref().map(|rcnv| rcnv.p_triplo),
+            GenesScoresField::Domino => record.domino.as_ref().map(|domino| domino.score),
+            GenesScoresField::DecipherPHi => {
+                record.decipher_hi.as_ref().map(|decipher| decipher.p_hi)
+            }
+        }
+    }
+}
+
+/// Parse the `fields` parameter into scores, rejecting unknown names.
+pub fn parse_fields(fields: &[String]) -> Result<Vec<GenesScoresField>, CustomError> {
+    fields
+        .iter()
+        .map(|field| {
+            field.trim().parse().map_err(|_| {
+                CustomError::invalid_param(
+                    "fields",
+                    Some(field),
+                    &format!(
+                        "unknown score, valid scores are: {}",
+                        GenesScoresField::iter()
+                            .map(|field| field.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Output format of `/genes/scores`.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::Display,
+    strum::EnumString,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GenesScoresFormat {
+    /// Newline-delimited JSON, one object per gene after one with the database version.
+    #[default]
+    Json,
+    /// Tab-separated values with a leading comment line giving the database version.
+    Tsv,
+}
+
+impl GenesScoresFormat {
+    /// Return the content type of the response.
+    fn content_type(&self) -> &'static str {
+        match self {
+            GenesScoresFormat::Json => "application/x-ndjson",
+            GenesScoresFormat::Tsv => "text/tab-separated-values",
+        }
+    }
+
+    /// Return the lines before the first gene.
+    fn header(&self, fields: &[GenesScoresField], genes_db_version: Option<&str>) -> String {
+        match self {
+            GenesScoresFormat::Json => format!(
+                "{}\n",
+                serde_json::json!({
+                    "genes_db_version": genes_db_version,
+                    "fields": fields.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                })
+            ),
+            GenesScoresFormat::Tsv => format!(
+                "# genes-db-version: {}\nhgnc_id\tsymbol{}\n",
+                genes_db_version.unwrap_or("unknown"),
+                fields
+                    .iter()
+                    .map(|field| format!("\t{}", field))
+                    .collect::<String>()
+            ),
+        }
+    }
+
+    /// Append the line with the `fields` of `record` to `out`.
+    fn write_line(
+        &self,
+        record: &genes::base::Record,
+        fields: &[GenesScoresField],
+        out: &mut String,
+    ) {
+        let (hgnc_id, symbol) = record
+            .hgnc
+            .as_ref()
+            .map(|hgnc| (hgnc.hgnc_id.as_str(), hgnc.symbol.as_str()))
+            .unwrap_or_default();
+        match self {
+            GenesScoresFormat::Json => {
+                let mut line = serde_json::Map::new();
+                line.insert("hgnc_id".into(), hgnc_id.into());
+                line.insert("symbol".into(), symbol.into());
+                for field in fields {
+                    line.insert(field.to_string(), field.value(record).into());
+                }
+                out.push_str(&serde_json::Value::Object(line).to_string());
+            }
+            GenesScoresFormat::Tsv => {
+                out.push_str(hgnc_id);
+                out.push('\t');
+                out.push_str(symbol);
+                for field in fields {
+                    out.push('\t');
+                    if let Some(value) = field.value(record) {
+                        out.push_str(&value.to_string());
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+}
+
+/// Read up to `chunk_size` genes starting at `start_key` and return their lines and the key
+/// to continue at, `None` after the last gene.
+fn read_chunk(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    fields: &[GenesScoresField],
+    format: GenesScoresFormat,
+    start_key: &[u8],
+    chunk_size: usize,
+) -> Result<(String, Option<Vec<u8>>), anyhow::Error> {
+    let cf_genes = db.cf_handle("genes").expect("no 'genes' column family");
+    let mut iter = db.raw_iterator_cf(&cf_genes);
+    iter.seek(start_key);
+    let mut result = String::new();
+    let mut count = 0;
+    while iter.valid() {
+        if count == chunk_size {
+            return Ok((result, iter.key().map(|key| key.to_vec())));
+        }
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let record: genes::base::Record = decode_protobuf(db, "genes", iter_key, iter_value)?;
+            format.write_line(&record, fields, &mut result);
+        }
+        count += 1;
+        iter.next();
+    }
+    iter.status()?;
+    Ok((result, None))
+}
+
+/// Body of `/genes/scores`, streamed chunk by chunk.
+struct ScoresBody {
+    /// The server data with the genes database.
+    data: Data<WebServerData>,
+    /// The selected scores.
+    fields: Vec<GenesScoresField>,
+    /// The output format.
+    format: GenesScoresFormat,
+    /// The lines before the first gene, until sent.
+    header: Option<String>,
+    /// The key to continue reading at, `None` when done.
+    next_key: Option<Vec<u8>>,
+}
+
+impl MessageBody for ScoresBody {
+    type Error = anyhow::Error;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = &mut *self;
+        if let Some(header) = this.header.take() {
+            return std::task::Poll::Ready(Some(Ok(Bytes::from(header))));
+        }
+        let Some(start_key) = this.next_key.take() else {
+            return std::task::Poll::Ready(None);
+        };
+        let result = this
+            .data
+            .genes
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("genes database not available"))
+            .and_then(|genes_db| {
+                read_chunk(
+                    &genes_db.data.db,
+                    &this.fields,
+                    this.format,
+                    &start_key,
+                    CHUNK_SIZE,
+                )
+            })
+            .map(|(chunk, next_key)| {
+                this.next_key = next_key;
+                Bytes::from(chunk)
+            });
+        std::task::Poll::Ready(Some(result))
+    }
+}
+
+/// Parameters for `handle`.
+#[serde_with::skip_serializing_none]
+#[serde_with::serde_as]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) struct GenesScoresQuery {
+    /// The scores to return, e.g., `pli,loeuf,shet`; all if not given.  Valid scores are
+    /// `pli`, `oe_lof_upper` (or `loeuf`), `mis_z`, `shet`, `p_haplo`, `p_triplo`, `domino`,
+    /// and `decipher_p_hi`.
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
+    pub fields: Option<Vec<String>>,
+    /// The output format, defaults to JSON.
+    pub format: Option<GenesScoresFormat>,
+}
+
+/// Export selected scores of all genes.
+#[utoipa::path(
+    get,
+    operation_id = "genesScores",
+    params(GenesScoresQuery),
+    responses(
+        (status = 200, description = "Scores of all genes, one line per gene.", body = String,
+         content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid query parameter.", body = CustomError),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/scores")]
+async fn handle(
+    data: Data<WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesScoresQuery>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let fields = match query.fields.as_deref() {
+        Some(fields) => parse_fields(fields)?,
+        None => GenesScoresField::iter().collect(),
+    };
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+    let genes_db_version = genes_db
+        .version_spec
+        .as_ref()
+        .map(|version_spec| version_spec.version.clone());
+    let format = query.format.unwrap_or_default();
+
+    let mut builder = HttpResponse::Ok();
+    if let Some(genes_db_version) = genes_db_version.clone() {
+        builder.insert_header((HEADER_GENES_DB_VERSION, genes_db_version));
+    }
+    Ok(builder
+        .content_type(format.content_type())
+        .body(ScoresBody {
+            header: Some(format.header(&fields, genes_db_version.as_deref())),
+            data,
+            fields,
+            format,
+            next_key: Some(Vec::new()),
+        }))
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::server::run::genes_info::test::data_with_genes;
+
+    /// Call `/genes/scores` with `query` on the example genes database.
+    async fn call_scores(query: &str) -> (StatusCode, String) {
+        let tmp_dir = TempDir::default();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(data_with_genes(&tmp_dir)))
+                .service(handle),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/genes/scores?{}", query))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        let body = test::read_body(resp).await;
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn parse_fields_aliases() {
+        assert_eq!(
+            parse_fields(&[String::from("loeuf"), String::from(" oe_lof_upper")])
+                .map_err(|e| e.to_string()),
+            Ok(vec![
+                GenesScoresField::OeLofUpper,
+                GenesScoresField::OeLofUpper
+            ])
+        );
+
+        let err = parse_fields(&[String::from("pli"), String::from("pLoF")]).unwrap_err();
+        assert_eq!(
+            err.param().and_then(|param| param.value.as_deref()),
+            Some("pLoF")
+        );
+        assert!(err
+            .to_string()
+            .contains("pli, oe_lof_upper, mis_z, shet, p_haplo, p_triplo, domino, decipher_p_hi"));
+    }
+
+    #[actix_web::test]
+    async fn scores_json() -> Result<(), anyhow::Error> {
+        let (status, body) = call_scores("fields=pli,loeuf,mis_z,shet").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let lines = body
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "genes_db_version": null,
+                "fields": ["pli", "oe_lof_upper", "mis_z", "shet"],
+            })
+        );
+        assert_eq!(lines.len(), 4);
+        let braf = lines
+            .iter()
+            .find(|line| line["symbol"] == "BRAF")
+            .expect("BRAF not found");
+        assert_eq!(
+            braf,
+            &serde_json::json!({
+                "hgnc_id": "HGNC:1097",
+                "symbol": "BRAF",
+                "pli": 0.99,
+                "oe_lof_upper": 0.19,
+                "mis_z": 3.75,
+                "shet": null,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scores_tsv() -> Result<(), anyhow::Error> {
+        let (status, body) = call_scores("fields=pli,oe_lof_upper&format=tsv").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[..2],
+            [
+                "# genes-db-version: unknown",
+                "hgnc_id\tsymbol\tpli\toe_lof_upper"
+            ]
+        );
+        assert_eq!(lines.len(), 5);
+        assert!(lines.contains(&"HGNC:12403\tTTN\t0.001\t0.64"));
+        assert!(lines.contains(&"HGNC:8\tA2MP1\t\t"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scores_unknown_field() -> Result<(), anyhow::Error> {
+        let (status, body) = call_scores("fields=pli,pLoF").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let err: serde_json::Value = serde_json::from_str(&body)?;
+        assert_eq!(err["param"]["name"], "fields");
+        assert_eq!(err["param"]["value"], "pLoF");
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_chunk_continues() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let data = data_with_genes(&tmp_dir);
+        let db = &data.genes.as_ref().unwrap().data.db;
+        let fields = [GenesScoresField::Pli];
+
+        let mut lines = Vec::new();
+        let mut next_key = Some(Vec::new());
+        let mut chunks = 0;
+        while let Some(start_key) = next_key {
+            let (chunk, key) = read_chunk(db, &fields, GenesScoresFormat::Tsv, &start_key, 2)?;
+            lines.extend(chunk.lines().map(ToString::to_string));
+            next_key = key;
+            chunks += 1;
+        }
+
+        assert_eq!(chunks, 2);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines,
+            read_chunk(db, &fields, GenesScoresFormat::Tsv, b"", 100)?
+                .0
+                .lines()
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
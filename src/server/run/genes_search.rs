@@ -9,7 +9,7 @@ use actix_web::{
     web::{self, Data, Json, Path},
 };
 
-use crate::server::run::GeneNames;
+use crate::server::run::{genes_scores, GeneNames};
 
 use super::error::CustomError;
 use serde_with::{formats::CommaSeparator, StringWithSeparator};
@@ -28,7 +28,7 @@ use serde_with::{formats::CommaSeparator, StringWithSeparator};
     utoipa::ToSchema,
 )]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum GenesFields {
+pub enum GenesFields {
     /// HGNC ID field
     HgncId,
     /// Symbol field
@@ -52,7 +52,7 @@ pub(crate) enum GenesFields {
     Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
 )]
 #[serde(rename_all = "snake_case")]
-pub(crate) struct GenesSearchQuery {
+pub struct GenesSearchQuery {
     /// The string to search for.
     pub q: String,
     /// The fields to search in.
@@ -60,19 +60,24 @@ pub(crate) struct GenesSearchQuery {
     pub fields: Option<Vec<GenesFields>>,
     /// Enable case sensitive search.
     pub case_sensitive: Option<bool>,
+    /// Enrich each hit with the gene's dosage sensitivity and constraint scores.
+    pub include_scores: Option<bool>,
 }
 
 /// A scored result.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
-pub(crate) struct Scored<T> {
+pub struct Scored<T> {
     /// The score.
     pub score: f32,
     /// The result.
     pub data: T,
+    /// Scores of the gene, only with `include_scores`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<genes_scores::GenesScores>,
 }
 
 /// Alias for scored genes names.
-pub(crate) type GenesScoredGeneNames = Scored<GeneNames>;
+pub type GenesScoredGeneNames = Scored<GeneNames>;
 
 /// Result for `handle`.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
@@ -82,24 +87,8 @@ pub(crate) struct GenesSearchResponse {
     pub genes: Vec<GenesScoredGeneNames>,
 }
 
-/// Implementation of both endpoints.
-async fn handle_impl(
-    data: Data<crate::server::run::WebServerData>,
-    _path: Path<()>,
-    query: web::Query<GenesSearchQuery>,
-) -> actix_web::Result<Json<GenesSearchResponse>, CustomError> {
-    if query.q.len() < 2 {
-        return Ok(Json(GenesSearchResponse {
-            // server_version: VERSION.to_string(),
-            // builder_version,
-            genes: Vec::new(),
-        }));
-    }
-
-    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
-        "genes database not available"
-    )))?;
-
+/// Search the in-memory gene names for `query`.
+pub fn search(gene_names: &[GeneNames], query: &GenesSearchQuery) -> Vec<GenesScoredGeneNames> {
     let max_items = 100;
 
     let case_sensitive: bool = query.case_sensitive.unwrap_or(false);
@@ -133,19 +122,18 @@ async fn handle_impl(
     let fields_contains =
         |field: &GenesFields| -> bool { fields.is_empty() || fields.contains(field) };
 
-    let mut genes = genes_db
-        .data
-        .gene_names
+    // Score by reference and only clone the genes that are returned.
+    let mut genes = gene_names
         .iter()
-        .map(|gn| -> Scored<GeneNames> {
-            let score = if (fields_contains(&GenesFields::HgncId) && equals_q(&gn.hgnc_id))
+        .map(|gn| -> (f32, &GeneNames) {
+            let score = if (fields_contains(&GenesFields::HgncId) && equals_q(gn.hgnc_id.as_str()))
                 || (fields_contains(&GenesFields::Symbol) && equals_q(&gn.symbol))
                 || (fields_contains(&GenesFields::Symbol) && equals_q(&gn.symbol))
                 || (fields_contains(&GenesFields::Name) && equals_q(&gn.name))
                 || (fields_contains(&GenesFields::EnsemblGeneId)
-                    && gn.ensembl_gene_id.iter().any(|s| equals_q(s)))
+                    && gn.ensembl_gene_id.iter().any(|s| equals_q(s.as_str())))
                 || (fields_contains(&GenesFields::NcbiGeneId)
-                    && gn.ncbi_gene_id.iter().any(|s| equals_q(s)))
+                    && gn.ncbi_gene_id.iter().any(|s| equals_q(s.as_str())))
             {
                 1f32
             } else if fields_contains(&GenesFields::Symbol) && contains_q(&gn.symbol) {
@@ -183,13 +171,15 @@ async fn handle_impl(
             } else {
                 0f32
             };
-            Scored {
-                score,
-                data: gn.clone(),
-            }
+            (score, gn)
         })
-        .filter(|s| s.score > 0.0)
+        .filter(|(score, _)| *score > 0.0)
         .take(max_items)
+        .map(|(score, gn)| Scored {
+            score,
+            data: gn.clone(),
+            scores: None,
+        })
         .collect::<Vec<_>>();
 
     genes.sort_by(|a, b| {
@@ -198,6 +188,48 @@ async fn handle_impl(
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
+    genes
+}
+
+/// Attach the scores from the genes database to the search results.
+fn add_scores(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    genes: &mut [GenesScoredGeneNames],
+) -> Result<(), CustomError> {
+    let hgnc_ids = genes
+        .iter()
+        .map(|gene| gene.data.hgnc_id.as_str())
+        .collect::<Vec<_>>();
+    let scores = genes_scores::fetch_scores(db, &hgnc_ids)?;
+    for (gene, scores) in genes.iter_mut().zip(scores) {
+        gene.scores = Some(scores);
+    }
+    Ok(())
+}
+
+/// Implementation of both endpoints.
+async fn handle_impl(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesSearchQuery>,
+) -> actix_web::Result<Json<GenesSearchResponse>, CustomError> {
+    if query.q.len() < 2 {
+        return Ok(Json(GenesSearchResponse {
+            // server_version: VERSION.to_string(),
+            // builder_version,
+            genes: Vec::new(),
+        }));
+    }
+
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+
+    let mut genes = search(&genes_db.data.gene_names, &query);
+    if query.include_scores.unwrap_or(false) {
+        add_scores(&genes_db.data.db, &mut genes)?;
+    }
+
     Ok(Json(GenesSearchResponse {
         // server_version: VERSION.to_string(),
         // builder_version,
@@ -233,3 +265,81 @@ async fn handle_with_openapi(
 ) -> actix_web::Result<Json<GenesSearchResponse>, CustomError> {
     handle_impl(data, path, query).await
 }
+
+#[cfg(test)]
+mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::pbs::genes;
+
+    fn gene_names(hgnc_id: &str, symbol: &str) -> GeneNames {
+        GeneNames {
+            hgnc_id: hgnc_id.parse().unwrap(),
+            symbol: symbol.into(),
+            name: String::new(),
+            alias_symbol: vec![],
+            alias_name: vec![],
+            prev_symbol: vec![],
+            date_symbol_changed: None,
+            ensembl_gene_id: None,
+            ncbi_gene_id: None,
+            refseq_accession: vec![],
+            uniprot_ids: vec![],
+            omim_id: vec![],
+            mane_select: vec![],
+        }
+    }
+
+    #[test]
+    fn search_include_scores() -> Result<(), anyhow::Error> {
+        // Genes database with scores for BRAF only; BRAFPSEU is not in the database.
+        let tmp_dir = TempDir::default();
+        let path = format!("{}", tmp_dir.join("genes").display());
+        let record = genes::base::Record {
+            rcnv: Some(genes::base::RcnvRecord {
+                hgnc_id: "HGNC:1097".into(),
+                p_haplo: 0.25,
+                p_triplo: 0.75,
+            }),
+            shet: Some(genes::base::ShetRecord {
+                hgnc_id: "HGNC:1097".into(),
+                s_het: 0.125,
+            }),
+            gnomad_constraints: Some(genes::base::GnomadConstraintsRecord {
+                pli: Some(0.99),
+                oe_lof_upper: Some(0.5),
+                ..Default::default()
+            }),
+            ..crate::genes::cli::import::test::record("HGNC:1097", "BRAF")
+        };
+        crate::genes::cli::import::test::write_records(&path, &[record])?;
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            path,
+            ["meta", "genes"],
+            false,
+        )?;
+
+        let query = GenesSearchQuery {
+            q: "BRAF".into(),
+            fields: Some(vec![GenesFields::Symbol]),
+            case_sensitive: None,
+            include_scores: Some(true),
+        };
+        let mut genes = search(
+            &[
+                gene_names("HGNC:1097", "BRAF"),
+                gene_names("HGNC:99999", "BRAFPSEU"),
+            ],
+            &query,
+        );
+        add_scores(&db, &mut genes)?;
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&GenesSearchResponse {
+            genes
+        })?);
+
+        Ok(())
+    }
+}
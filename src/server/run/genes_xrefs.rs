@@ -0,0 +1,387 @@
+//! Implementation of endpoint `/genes/xrefs`.
+//!
+//! Exports gene identifier cross-reference (ID mapping) tables from the in-memory gene names
+//! index.  Either the genes matching the given identifiers are returned or, without any
+//! identifiers, a page over all genes.  The result can be rendered as JSON or TSV.
+use actix_web::{
+    get,
+    http::header::ContentType,
+    web::{self, Data, Path},
+    HttpResponse,
+};
+
+use crate::server::run::GeneNames;
+
+use super::error::CustomError;
+use serde_with::{formats::CommaSeparator, StringWithSeparator};
+
+/// The default page size to use.
+const DEFAULT_PAGE_SIZE: u32 = 1000;
+
+/// Name of the response header with the genes database version.
+pub const HEADER_GENES_DB_VERSION: &str = "x-genes-db-version";
+
+/// Separator for list-valued columns in TSV output.
+const TSV_LIST_SEP: &str = "|";
+
+/// Output format of the cross-reference table.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::Display,
+    strum::EnumString,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GenesXrefsFormat {
+    /// JSON output.
+    #[default]
+    Json,
+    /// Tab-separated values with a leading comment line.
+    Tsv,
+}
+
+/// Parameters for `handle`.
+#[serde_with::skip_serializing_none]
+#[serde_with::serde_as]
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) struct GenesXrefsQuery {
+    /// Optional HGNC/NCBI/ENSEMBL identifiers or symbols to limit the result to.
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
+    pub q: Option<Vec<String>>,
+    /// The output format, defaults to JSON.
+    pub format: Option<GenesXrefsFormat>,
+    /// Optional 1-based page number.
+    pub page_no: Option<u32>,
+    /// Optional page size.
+    pub page_size: Option<u32>,
+}
+
+/// Identifier cross-references for one gene.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesXrefsRecord {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// HGNC gene symbol.
+    pub symbol: String,
+    /// ENSEMBL gene ID.
+    pub ensembl_gene_id: Option<String>,
+    /// NCBI gene ID.
+    pub ncbi_gene_id: Option<String>,
+    /// RefSeq accessions.
+    pub refseq_accession: Vec<String>,
+    /// UniProt IDs.
+    pub uniprot_ids: Vec<String>,
+    /// OMIM IDs.
+    pub omim_id: Vec<String>,
+}
+
+impl From<&GeneNames> for GenesXrefsRecord {
+    fn from(value: &GeneNames) -> Self {
+        Self {
+            hgnc_id: value.hgnc_id.to_string(),
+            symbol: value.symbol.clone(),
+            ensembl_gene_id: value.ensembl_gene_id.as_ref().map(ToString::to_string),
+            ncbi_gene_id: value.ncbi_gene_id.as_ref().map(ToString::to_string),
+            refseq_accession: value.refseq_accession.clone(),
+            uniprot_ids: value.uniprot_ids.clone(),
+            omim_id: value.omim_id.clone(),
+        }
+    }
+}
+
+/// Pagination information.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesXrefsPageInfo {
+    /// The total number of records.
+    pub total: u32,
+    /// The number of records per page.
+    pub per_page: u32,
+    /// The current page number.
+    pub current_page: u32,
+    /// The total number of pages.
+    pub total_pages: u32,
+}
+
+/// Result for `handle` in JSON format.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct GenesXrefsResponse {
+    /// Version of the genes database, if known.
+    pub genes_db_version: Option<String>,
+    /// The records in this page.
+    pub records: Vec<GenesXrefsRecord>,
+    /// Pagination information.
+    pub page_info: GenesXrefsPageInfo,
+}
+
+impl GenesXrefsResponse {
+    /// Render the response as TSV with a leading comment line giving the database version.
+    pub fn to_tsv(&self) -> String {
+        let mut result = format!(
+            "# genes-db-version: {}\n",
+            self.genes_db_version.as_deref().unwrap_or("unknown")
+        );
+        result.push_str(
+            "hgnc_id\tsymbol\tensembl_gene_id\tncbi_gene_id\trefseq_accession\tuniprot_ids\tomim_id\n",
+        );
+        for record in &self.records {
+            result.push_str(
+                &[
+                    record.hgnc_id.clone(),
+                    record.symbol.clone(),
+                    record.ensembl_gene_id.clone().unwrap_or_default(),
+                    record.ncbi_gene_id.clone().unwrap_or_default(),
+                    record.refseq_accession.join(TSV_LIST_SEP),
+                    record.uniprot_ids.join(TSV_LIST_SEP),
+                    record.omim_id.join(TSV_LIST_SEP),
+                ]
+                .join("\t"),
+            );
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// Build the response from the gene names index.
+fn build_response(
+    gene_names: &[GeneNames],
+    name_to_hgnc_idx: &std::collections::HashMap<String, usize>,
+    genes_db_version: Option<String>,
+    query: &GenesXrefsQuery,
+) -> GenesXrefsResponse {
+    let selected = match query.q.as_ref() {
+        Some(q) => {
+            let mut seen = std::collections::HashSet::new();
+            q.iter()
+                .filter_map(|q| name_to_hgnc_idx.get(q).copied())
+                .filter(|idx| seen.insert(*idx))
+                .map(|idx| &gene_names[idx])
+                .collect::<Vec<_>>()
+        }
+        None => gene_names.iter().collect::<Vec<_>>(),
+    };
+
+    let total = selected.len() as u32;
+    let per_page = std::cmp::max(query.page_size.unwrap_or(DEFAULT_PAGE_SIZE), 1);
+    let total_pages = total.div_ceil(per_page);
+    let current_page = std::cmp::max(query.page_no.unwrap_or(1), 1);
+    // Compute the page bounds in `usize` such that huge page sizes cannot overflow.
+    let begin = std::cmp::min(
+        ((current_page - 1) as usize).saturating_mul(per_page as usize),
+        selected.len(),
+    );
+    let end = std::cmp::min(begin.saturating_add(per_page as usize), selected.len());
+
+    GenesXrefsResponse {
+        genes_db_version,
+        records: selected[begin..end]
+            .iter()
+            .map(|gene_names| GenesXrefsRecord::from(*gene_names))
+            .collect(),
+        page_info: GenesXrefsPageInfo {
+            total,
+            per_page,
+            current_page,
+            total_pages,
+        },
+    }
+}
+
+/// Export gene identifier cross-references.
+#[utoipa::path(
+    get,
+    operation_id = "genesXrefs",
+    params(GenesXrefsQuery),
+    responses(
+        (status = 200, description = "Gene cross-references.", body = GenesXrefsResponse),
+        (status = 500, description = "Internal server error.", body = CustomError)
+    )
+)]
+#[get("/genes/xrefs")]
+async fn handle(
+    data: Data<crate::server::run::WebServerData>,
+    _path: Path<()>,
+    query: web::Query<GenesXrefsQuery>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let genes_db = data.genes.as_ref().ok_or(CustomError::new(anyhow::anyhow!(
+        "genes database not available"
+    )))?;
+    let genes_db_version = genes_db
+        .version_spec
+        .as_ref()
+        .map(|version_spec| version_spec.version.clone());
+
+    let response = build_response(
+        &genes_db.data.gene_names,
+        &genes_db.data.name_to_hgnc_idx,
+        genes_db_version.clone(),
+        &query,
+    );
+
+    let mut builder = HttpResponse::Ok();
+    if let Some(genes_db_version) = genes_db_version {
+        builder.insert_header((HEADER_GENES_DB_VERSION, genes_db_version));
+    }
+    match query.format.unwrap_or_default() {
+        GenesXrefsFormat::Json => Ok(builder.json(response)),
+        GenesXrefsFormat::Tsv => Ok(builder
+            .content_type(ContentType::plaintext())
+            .body(response.to_tsv())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn gene_names() -> Vec<GeneNames> {
+        vec![
+            GeneNames {
+                hgnc_id: "HGNC:1100".parse().unwrap(),
+                symbol: "BRCA1".into(),
+                name: "BRCA1 DNA repair associated".into(),
+                alias_symbol: vec![],
+                alias_name: vec![],
+                prev_symbol: vec![],
+                date_symbol_changed: None,
+                ensembl_gene_id: Some("ENSG00000012048".parse().unwrap()),
+                ncbi_gene_id: Some("672".parse().unwrap()),
+                refseq_accession: vec!["NM_007294".into()],
+                uniprot_ids: vec!["P38398".into()],
+                omim_id: vec!["113705".into()],
+                mane_select: vec![],
+            },
+            GeneNames {
+                hgnc_id: "HGNC:1101".parse().unwrap(),
+                symbol: "BRCA2".into(),
+                name: "BRCA2 DNA repair associated".into(),
+                alias_symbol: vec![],
+                alias_name: vec![],
+                prev_symbol: vec![],
+                date_symbol_changed: None,
+                ensembl_gene_id: None,
+                ncbi_gene_id: Some("675".parse().unwrap()),
+                refseq_accession: vec!["NM_000059".into(), "NM_000060".into()],
+                uniprot_ids: vec![],
+                omim_id: vec![],
+                mane_select: vec![],
+            },
+        ]
+    }
+
+    fn name_to_hgnc_idx(gene_names: &[GeneNames]) -> HashMap<String, usize> {
+        let mut result = HashMap::new();
+        for (idx, gene_name) in gene_names.iter().enumerate() {
+            result.insert(gene_name.hgnc_id.to_string(), idx);
+            result.insert(gene_name.symbol.clone(), idx);
+        }
+        result
+    }
+
+    /// The cross-references are only returned here, not with the gene names in
+    /// `/genes/search` and `/genes/lookup`.
+    #[test]
+    fn gene_names_without_xrefs() -> Result<(), anyhow::Error> {
+        let gene_names = gene_names();
+
+        let value = serde_json::to_value(&gene_names[0])?;
+        for key in ["refseq_accession", "uniprot_ids", "omim_id"] {
+            assert!(value.get(key).is_none(), "{} must not be serialized", key);
+        }
+        let value = serde_json::to_value(GenesXrefsRecord::from(&gene_names[0]))?;
+        assert_eq!(value["uniprot_ids"], serde_json::json!(["P38398"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_response_filtered() {
+        let gene_names = gene_names();
+        let query = GenesXrefsQuery {
+            q: Some(vec!["BRCA2".into(), "HGNC:1101".into(), "XXX".into()]),
+            format: None,
+            page_no: None,
+            page_size: None,
+        };
+
+        let response = build_response(
+            &gene_names,
+            &name_to_hgnc_idx(&gene_names),
+            Some("1.0".into()),
+            &query,
+        );
+
+        assert_eq!(response.records.len(), 1);
+        assert_eq!(response.records[0].hgnc_id, "HGNC:1101");
+        assert_eq!(response.page_info.total, 1);
+        assert_eq!(response.page_info.total_pages, 1);
+    }
+
+    #[test]
+    fn build_response_paginated() {
+        let gene_names = gene_names();
+        let query = GenesXrefsQuery {
+            q: None,
+            format: None,
+            page_no: Some(2),
+            page_size: Some(1),
+        };
+
+        let response = build_response(&gene_names, &name_to_hgnc_idx(&gene_names), None, &query);
+
+        assert_eq!(response.records.len(), 1);
+        assert_eq!(response.records[0].symbol, "BRCA2");
+        assert_eq!(response.page_info.total, 2);
+        assert_eq!(response.page_info.total_pages, 2);
+    }
+
+    /// A huge page size must not overflow the end of the page.
+    #[test]
+    fn build_response_huge_page_size() -> Result<(), anyhow::Error> {
+        let gene_names = gene_names();
+        let query = web::Query::<GenesXrefsQuery>::from_query("page_size=4294967295&page_no=2")?;
+
+        let response = build_response(&gene_names, &name_to_hgnc_idx(&gene_names), None, &query);
+
+        assert!(response.records.is_empty());
+        assert_eq!(response.page_info.total, 2);
+        assert_eq!(response.page_info.per_page, u32::MAX);
+        assert_eq!(response.page_info.current_page, 2);
+        assert_eq!(response.page_info.total_pages, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_tsv() {
+        let gene_names = gene_names();
+        let query = GenesXrefsQuery {
+            q: None,
+            format: Some(GenesXrefsFormat::Tsv),
+            page_no: None,
+            page_size: None,
+        };
+
+        let response = build_response(
+            &gene_names,
+            &name_to_hgnc_idx(&gene_names),
+            Some("1.0".into()),
+            &query,
+        );
+
+        insta::assert_snapshot!(response.to_tsv());
+    }
+}
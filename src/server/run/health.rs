@@ -0,0 +1,389 @@
+//! Implementation of endpoints `/health/live` and `/health/ready`.
+//!
+//! The liveness endpoint always succeeds once the HTTP server is up.  The readiness endpoint
+//! performs a cheap read from the `meta` column family of each configured database and only
+//! succeeds if all reads that are not excluded with `--readiness-exclude` succeed.  It also
+//! reports the usage of the shared block cache if `--rocksdb-block-cache-mb` is given and the
+//! circuit breaker state of each annotation database, see `circuit_breaker`.  Degraded
+//! databases are reported with `degraded: true` but do not affect the readiness result as
+//! the server keeps answering without them.
+use std::time::Instant;
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+    HttpResponse,
+};
+use strum::IntoEnumIterator as _;
+
+use crate::common::cli::GenomeRelease;
+
+use super::{circuit_breaker, error::CustomError, AnnoDb, WebServerData};
+
+/// Name of the genes database in readiness checks.
+pub(crate) const DB_NAME_GENES: &str = "genes";
+/// Name of the ClinVar genes database in readiness checks.
+pub(crate) const DB_NAME_CLINVAR_GENES: &str = "clinvar_genes";
+/// Name of the ClinVar SV database in readiness checks.
+const DB_NAME_CLINVAR_SV: &str = "clinvar_sv";
+
+/// Return the database names that may be given to `--readiness-exclude`.
+pub fn db_names() -> Vec<String> {
+    let mut result = vec![
+        DB_NAME_GENES.to_string(),
+        DB_NAME_CLINVAR_GENES.to_string(),
+        DB_NAME_CLINVAR_SV.to_string(),
+    ];
+    result.extend(
+        AnnoDb::iter()
+            .filter(|anno_db| *anno_db != AnnoDb::Other)
+            .map(|anno_db| anno_db.to_string()),
+    );
+    result
+}
+
+/// Result for `handle_live`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct HealthLiveResponse {
+    /// Status of the server, always `ok`.
+    pub status: String,
+}
+
+/// Readiness status of one database.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct HealthDbStatus {
+    /// Name of the database.
+    pub name: String,
+    /// Genome release of the database, if release-specific.
+    pub genome_release: Option<GenomeRelease>,
+    /// Whether the read from the database succeeded.
+    pub ok: bool,
+    /// Whether the database is ignored for the readiness result.
+    pub excluded: bool,
+    /// Latency of the read in milliseconds.
+    pub latency_ms: f64,
+    /// Error message if the read failed.
+    pub error: Option<String>,
+    /// Whether the database is omitted from responses after repeated errors.
+    #[serde(default)]
+    pub degraded: bool,
+    /// State of the circuit breaker of annotation databases.
+    pub circuit_breaker: Option<circuit_breaker::CircuitBreakerStatus>,
+}
+
+/// Size and usage of the shared RocksDB block cache.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct HealthBlockCache {
+    /// Configured capacity in bytes.
+    pub capacity_bytes: usize,
+    /// Memory used by the entries in the cache in bytes.
+    pub usage_bytes: usize,
+    /// Memory used by the entries in use by readers in bytes.
+    pub pinned_usage_bytes: usize,
+}
+
+/// Result for `handle_ready`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub(crate) struct HealthReadyResponse {
+    /// Whether all databases that are not excluded are ready.
+    pub ready: bool,
+    /// Status of each configured database.
+    pub databases: Vec<HealthDbStatus>,
+    /// The shared block cache, if configured with `--rocksdb-block-cache-mb`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_cache: Option<HealthBlockCache>,
+}
+
+/// Perform a cheap read from the `meta` column family of `db`.
+pub(crate) fn check_db(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+) -> Result<(), anyhow::Error> {
+    let cf_meta = db
+        .cf_handle("meta")
+        .ok_or_else(|| anyhow::anyhow!("column family meta not found"))?;
+    db.get_cf(&cf_meta, "annonars-version")
+        .map_err(|e| anyhow::anyhow!("problem reading from meta: {}", e))?;
+    Ok(())
+}
+
+/// Check one database and build its status.
+fn db_status(
+    name: &str,
+    genome_release: Option<GenomeRelease>,
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    exclude: &[String],
+) -> HealthDbStatus {
+    let before_check = Instant::now();
+    let result = check_db(db);
+    HealthDbStatus {
+        name: name.to_string(),
+        genome_release,
+        ok: result.is_ok(),
+        excluded: exclude.iter().any(|excluded| excluded == name),
+        latency_ms: before_check.elapsed().as_secs_f64() * 1000.0,
+        error: result.err().map(|e| e.to_string()),
+        degraded: false,
+        circuit_breaker: None,
+    }
+}
+
+/// Add the circuit breaker state of `anno_db` for `genome_release` to `status`.
+fn with_breaker(
+    mut status: HealthDbStatus,
+    data: &WebServerData,
+    genome_release: GenomeRelease,
+    anno_db: AnnoDb,
+) -> HealthDbStatus {
+    let breakers = &data.circuit_breakers;
+    let breaker_status = breakers
+        .get(genome_release, anno_db)
+        .status(&breakers.config, Instant::now());
+    status.degraded = breaker_status.state == circuit_breaker::CircuitBreakerState::Open;
+    status.circuit_breaker = Some(breaker_status);
+    status
+}
+
+/// Check all configured databases.
+fn check_readiness(data: &WebServerData) -> HealthReadyResponse {
+    let exclude = &data.readiness_exclude;
+    let mut databases = Vec::new();
+
+    if let Some(genes) = data.genes.as_ref() {
+        databases.push(db_status(DB_NAME_GENES, None, &genes.data.db, exclude));
+        if let Some(db_clinvar) = genes.data.db_clinvar.as_ref() {
+            databases.push(db_status(DB_NAME_CLINVAR_GENES, None, db_clinvar, exclude));
+        }
+    }
+    for (genome_release, clinvar_sv) in data.clinvar_svs.iter() {
+        if let Some(clinvar_sv) = clinvar_sv.as_ref() {
+            databases.push(db_status(
+                DB_NAME_CLINVAR_SV,
+                Some(genome_release),
+                clinvar_sv.db(),
+                exclude,
+            ));
+        }
+    }
+    for (genome_release, annos) in data.annos.iter() {
+        for (anno_db, db) in annos.iter() {
+            if let Some(db) = db.as_ref() {
+                databases.push(with_breaker(
+                    db_status(
+                        &anno_db.to_string(),
+                        Some(genome_release),
+                        &db.data,
+                        exclude,
+                    ),
+                    data,
+                    genome_release,
+                    anno_db,
+                ));
+            }
+        }
+    }
+    for (genome_release, additional_annos) in data.additional_annos.iter() {
+        for additional in additional_annos.values().flatten() {
+            databases.push(db_status(
+                &additional.db_info.versioned_key(genome_release),
+                Some(genome_release),
+                &additional.db.data,
+                exclude,
+            ));
+        }
+    }
+
+    HealthReadyResponse {
+        ready: databases.iter().all(|status| status.ok || status.excluded),
+        databases,
+        block_cache: data
+            .block_cache
+            .as_ref()
+            .map(|block_cache| HealthBlockCache {
+                capacity_bytes: block_cache.capacity,
+                usage_bytes: block_cache.cache.get_usage(),
+                pinned_usage_bytes: block_cache.cache.get_pinned_usage(),
+            }),
+    }
+}
+
+/// Liveness probe.
+#[utoipa::path(
+    get,
+    operation_id = "healthLive",
+    responses(
+        (status = 200, description = "The server is up.", body = HealthLiveResponse),
+    )
+)]
+#[get("/health/live")]
+async fn handle_live(_path: Path<()>) -> actix_web::Result<Json<HealthLiveResponse>, CustomError> {
+    Ok(Json(HealthLiveResponse {
+        status: "ok".to_string(),
+    }))
+}
+
+/// Readiness probe with per-database checks.
+#[utoipa::path(
+    get,
+    operation_id = "healthReady",
+    responses(
+        (status = 200, description = "All databases are ready.", body = HealthReadyResponse),
+        (status = 503, description = "At least one database is not ready.", body = HealthReadyResponse)
+    )
+)]
+#[get("/health/ready")]
+async fn handle_ready(
+    data: Data<WebServerData>,
+    _path: Path<()>,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let response = check_readiness(&data);
+    if response.ready {
+        Ok(HttpResponse::Ok().json(response))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(response))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+    use crate::server::run::WithVersionSpec;
+
+    /// Create a RocksDB with `meta` column family; drop the column family if `broken`.
+    fn create_db(
+        tmp_dir: &TempDir,
+        broken: bool,
+    ) -> rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> {
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = rocksdb::DBWithThreadMode::<rocksdb::MultiThreaded>::open_cf(
+            &options,
+            tmp_dir.join("rocksdb"),
+            ["meta", "tsv_data"],
+        )
+        .unwrap();
+        if broken {
+            db.drop_cf("meta").unwrap();
+        }
+        db
+    }
+
+    async fn call_ready(data: WebServerData) -> (StatusCode, HealthReadyResponse) {
+        let app =
+            test::init_service(App::new().app_data(Data::new(data)).service(handle_ready)).await;
+        let req = test::TestRequest::get().uri("/health/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    fn data_with_cadd(db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>) -> WebServerData {
+        let mut data = WebServerData::default();
+        data.annos[GenomeRelease::Grch37][AnnoDb::Cadd] = Some(WithVersionSpec {
+            data: db,
+            version_spec: None,
+        });
+        data
+    }
+
+    #[actix_web::test]
+    async fn live() {
+        let app = test::init_service(App::new().service(handle_live)).await;
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn ready_ok() {
+        let tmp_dir = TempDir::default();
+        let data = data_with_cadd(create_db(&tmp_dir, false));
+
+        let (status, response) = call_ready(data).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.ready);
+        assert_eq!(response.databases.len(), 1);
+        assert_eq!(response.databases[0].name, "cadd");
+        assert!(response.databases[0].ok);
+        assert!(response.block_cache.is_none());
+    }
+
+    #[actix_web::test]
+    async fn ready_block_cache() {
+        let tmp_dir = TempDir::default();
+        let mut data = data_with_cadd(create_db(&tmp_dir, false));
+        data.block_cache = Some(crate::server::run::BlockCache::new(2));
+
+        let (status, response) = call_ready(data).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let block_cache = response.block_cache.expect("block cache reported");
+        assert_eq!(block_cache.capacity_bytes, 2 * 1024 * 1024);
+        assert!(block_cache.usage_bytes <= block_cache.capacity_bytes);
+    }
+
+    #[actix_web::test]
+    async fn ready_broken_db() {
+        let tmp_dir = TempDir::default();
+        let data = data_with_cadd(create_db(&tmp_dir, true));
+
+        let (status, response) = call_ready(data).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!response.ready);
+        assert!(!response.databases[0].ok);
+        assert!(response.databases[0].error.is_some());
+    }
+
+    #[actix_web::test]
+    async fn ready_broken_db_excluded() {
+        let tmp_dir = TempDir::default();
+        let mut data = data_with_cadd(create_db(&tmp_dir, true));
+        data.readiness_exclude = vec!["cadd".to_string()];
+
+        let (status, response) = call_ready(data).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.ready);
+        assert!(response.databases[0].excluded);
+    }
+
+    #[actix_web::test]
+    async fn ready_degraded() {
+        let tmp_dir = TempDir::default();
+        let data = data_with_cadd(create_db(&tmp_dir, false));
+        let breakers = &data.circuit_breakers;
+        for _ in 0..breakers.config.threshold {
+            breakers
+                .get(GenomeRelease::Grch37, AnnoDb::Cadd)
+                .record_error(&breakers.config, Instant::now(), "corrupted".into());
+        }
+
+        let (status, response) = call_ready(data).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.ready);
+        assert!(response.databases[0].degraded);
+        let breaker = response.databases[0]
+            .circuit_breaker
+            .as_ref()
+            .expect("breaker reported");
+        assert_eq!(breaker.state, circuit_breaker::CircuitBreakerState::Open);
+        assert_eq!(breaker.last_error.as_deref(), Some("corrupted"));
+    }
+
+    #[test]
+    fn db_names_contains_all() {
+        let names = db_names();
+
+        assert!(names.contains(&"genes".to_string()));
+        assert!(names.contains(&"gnomad_exomes".to_string()));
+        assert!(!names.contains(&"other".to_string()));
+    }
+}
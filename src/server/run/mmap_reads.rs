@@ -0,0 +1,130 @@
+//! Memory-mapped reads of the databases, see `--rocksdb-mmap-reads`.
+//!
+//! With memory-mapped reads, RocksDB reads the SST files through the OS page cache instead
+//! of its block cache, which is disabled for such databases (the shared block cache of
+//! `--rocksdb-block-cache-mb` is only used by the other ones).  This suits large databases on
+//! fast NVMe on hosts with limited RAM: the kernel evicts the pages under memory pressure,
+//! while the block cache would hold its full capacity.  Blocks are decompressed on each read,
+//! so the latency of repeated reads of the same blocks is higher than with the block cache.
+//!
+//! The server opens all databases read-only, which is compatible with memory-mapped reads;
+//! direct reads, which bypass the page cache, are disabled for such databases.
+
+use std::collections::BTreeMap;
+
+use strum::IntoEnumIterator as _;
+
+use super::{health, AnnoDb};
+
+/// Databases to read with memory mapping, parsed from `--rocksdb-mmap-reads`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MmapReads {
+    /// Whether to use memory-mapped reads for the databases not in `by_name`.
+    all: bool,
+    /// Whether to use memory-mapped reads by database name.
+    by_name: BTreeMap<String, bool>,
+}
+
+impl MmapReads {
+    /// Parse the values of `--rocksdb-mmap-reads`.
+    ///
+    /// Each value is either `true`/`false` for all databases or `NAME=true`/`NAME=false` for
+    /// the database `NAME`, one of `db_names()`; later values override earlier ones.
+    pub fn parse(values: &[String]) -> Result<Self, anyhow::Error> {
+        let parse_bool = |value: &str| {
+            value.parse::<bool>().map_err(|_| {
+                anyhow::anyhow!(
+                    "invalid value {:?} for --rocksdb-mmap-reads, must be true or false",
+                    value
+                )
+            })
+        };
+        let db_names = db_names();
+        let mut result = Self::default();
+        for value in values {
+            if let Some((name, enabled)) = value.split_once('=') {
+                if !db_names.iter().any(|db_name| db_name == name) {
+                    anyhow::bail!(
+                        "invalid database name {:?} for --rocksdb-mmap-reads, must be one of: {}",
+                        name,
+                        db_names.join(", ")
+                    );
+                }
+                result
+                    .by_name
+                    .insert(name.to_string(), parse_bool(enabled)?);
+            } else {
+                result.all = parse_bool(value)?;
+                result.by_name.clear();
+            }
+        }
+        Ok(result)
+    }
+
+    /// Return whether to use memory-mapped reads for the database `name`.
+    pub fn enabled(&self, name: &str) -> bool {
+        self.by_name.get(name).copied().unwrap_or(self.all)
+    }
+}
+
+/// Return the names of the databases that can be given to `--rocksdb-mmap-reads`.
+///
+/// The ClinVar SV and region databases are read into interval trees at startup and are not
+/// included.
+pub fn db_names() -> Vec<String> {
+    let mut result = vec![
+        health::DB_NAME_GENES.to_string(),
+        health::DB_NAME_CLINVAR_GENES.to_string(),
+        String::from("functional"),
+    ];
+    result.extend(
+        AnnoDb::iter()
+            .filter(|anno_db| *anno_db != AnnoDb::Other)
+            .map(|anno_db| anno_db.to_string()),
+    );
+    result
+}
+
+/// Configure `options` and `block_opts` for memory-mapped reads without block cache.
+pub fn configure(options: &mut rocksdb::Options, block_opts: &mut rocksdb::BlockBasedOptions) {
+    options.set_allow_mmap_reads(true);
+    options.set_use_direct_reads(false);
+    block_opts.disable_cache();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(values: &[&str]) -> Result<MmapReads, anyhow::Error> {
+        MmapReads::parse(&values.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[rstest::rstest]
+    #[case::empty(&[], false, false)]
+    #[case::all(&["true"], true, true)]
+    #[case::single(&["gnomad_genomes=true"], true, false)]
+    #[case::all_but_one(&["true", "cadd=false"], true, false)]
+    #[case::all_overrides(&["cadd=false", "true"], true, true)]
+    fn parse_enabled(
+        #[case] values: &[&str],
+        #[case] gnomad_genomes: bool,
+        #[case] cadd: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mmap_reads = parse(values)?;
+
+        assert_eq!(mmap_reads.enabled("gnomad_genomes"), gnomad_genomes);
+        assert_eq!(mmap_reads.enabled("cadd"), cadd);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::value(&["yes"])]
+    #[case::name(&["gnomad=true"])]
+    #[case::clinvar_sv(&["clinvar_sv=true"])]
+    #[case::name_value(&["cadd=1"])]
+    fn parse_invalid(#[case] values: &[&str]) {
+        assert!(parse(values).is_err());
+    }
+}
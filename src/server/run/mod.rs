@@ -1,16 +1,62 @@
 //! Implementation of the actix server.
+//!
+//! # Sharing model
+//!
+//! All databases and settings are opened into one `WebServerData` in `run()` before the
+//! server starts.  It is wrapped in one `Data`, i.e., an `Arc`, that is cloned into each
+//! actix worker thread, so all workers share the same instance.  The data is never replaced
+//! or mutated through `&mut` after startup and there is no lock around it:
+//!
+//! - The RocksDB handles are opened read-only with `MultiThreaded` column family handles and
+//!   may be read concurrently, as may the read-only interval trees and gene name indexes.
+//! - The few pieces of mutable state have their own synchronization, are only locked for
+//!   short operations without I/O, and never hold two locks at once:
+//!   - `variant_cache::VariantCache` has one `Mutex` per shard of the entries and atomic
+//!     counters,
+//!   - `circuit_breaker::CircuitBreakers` has one `Mutex` per database,
+//!   - `range_decode::RangeDecoder` has an atomic number of permits,
+//!   - `audit_log::AuditLog` only sends into a channel to its writer thread.
+//!
+//! New mutable state must follow the same rules.  `WebServerData` must stay `Send + Sync`,
+//! which is checked at compile time, and its `Debug` output must not contain secrets, see
+//! `admin::AdminToken`.
 
+pub mod admin;
+pub mod admin_raw;
 pub mod annos_db_info;
+pub mod annos_position;
+pub mod annos_presence;
 pub mod annos_range;
 pub mod annos_variant;
+pub mod audit_log;
+pub mod cadd;
+pub mod circuit_breaker;
 pub mod clinvar_data;
 pub mod clinvar_sv;
+pub mod compression;
+pub mod config;
+pub mod context;
 pub mod error;
+pub mod etag;
 pub mod fetch;
+pub mod genes_acmg_sf;
+pub mod genes_by_disease;
 pub mod genes_clinvar;
+pub mod genes_clinvar_sv_summary;
+pub mod genes_clinvar_variants;
 pub mod genes_info;
 pub mod genes_lookup;
+pub mod genes_scores;
 pub mod genes_search;
+pub mod genes_xrefs;
+pub mod health;
+pub mod mmap_reads;
+pub mod params;
+pub mod range_decode;
+pub mod range_filter;
+pub mod regions;
+pub mod startup;
+pub mod variant_cache;
 pub mod versions;
 
 use std::{
@@ -21,37 +67,82 @@ use std::{
 };
 
 use clap::Parser;
-use indicatif::ParallelProgressIterator;
-use prost::Message;
-use rayon::prelude::*;
+use strum::IntoEnumIterator as _;
 use utoipa::OpenApi as _;
 
 use crate::{
     clinvar_sv::cli::query::{self as clinvarsv_query, IntervalTrees as ClinvarsvIntervalTrees},
     common::{self, cli::GenomeRelease},
+    genes::ids::{EnsemblGeneId, HgncId, InvalidGeneId, NcbiGeneId},
     pbs::genes,
+    regions::cli::query::{self as regions_query, IntervalTrees as RegionsIntervalTrees},
 };
 
-use actix_web::{middleware::Logger, web::Data, App, HttpServer};
+use actix_web::{
+    middleware::{from_fn, Condition, Logger},
+    web::Data,
+    App, HttpServer,
+};
 
 /// Module with OpenAPI documentation.
 pub mod openapi {
     use crate::{
-        common::cli::GenomeRelease,
-        server::run::annos_variant::{self, response::*, SeqvarsAnnosQuery},
+        common::{
+            cli::GenomeRelease,
+            decode::DecodeError,
+            pos_index::{NearestFeature, NearestFeatures},
+        },
+        freqs::computed::{ComputedFrequencies, FrequencySource, QualityTier},
+        genes::diseases::DiseaseGene,
+        server::run::annos_position::{
+            self, AnnosPositionAllele, AnnosPositionQuery, AnnosPositionResponse,
+        },
+        server::run::annos_presence::{SeqvarsGnomadPresence, SeqvarsPresenceRecord},
+        server::run::annos_range::{self, AnnosRangeQuery, AnnosRangeResponse},
+        server::run::annos_variant::{
+            self, response::*, AnnosVariantMultiEntry, AnnosVariantMultiPresenceEntry,
+            AnnosVariantMultiPresenceResponse, AnnosVariantMultiQuery, AnnosVariantMultiResponse,
+            AnnosVariantPresenceResponse, AnnosVariantResponse, SeqvarsAnnosQuery,
+        },
+        server::run::circuit_breaker::{CircuitBreakerState, CircuitBreakerStatus},
         server::run::clinvar_data::*,
         server::run::clinvar_sv::{self, response::*, StrucvarsClinvarQuery},
+        server::run::context::VariantContext,
+        server::run::genes_acmg_sf::{self, GenesAcmgSfRecord, GenesAcmgSfResponse},
+        server::run::genes_by_disease::{self, GenesByDiseaseQuery, GenesByDiseaseResponse},
         server::run::genes_clinvar::{self, response::*, GenesClinvarQuery},
+        server::run::genes_clinvar_sv_summary::{
+            self, GenesClinvarSvCount, GenesClinvarSvRecord, GenesClinvarSvSummary,
+            GenesClinvarSvSummaryQuery, GenesClinvarSvSummaryResponse,
+        },
+        server::run::genes_clinvar_variants::{
+            self, GenesClinvarVariantRecord, GenesClinvarVariantsPageInfo,
+            GenesClinvarVariantsQuery, GenesClinvarVariantsResponse, GenesClinvarVariantsSort,
+        },
         server::run::genes_info::{self, response::*},
-        server::run::genes_lookup::{self, GenesLookupResponse, GenesLookupResultEntry},
+        server::run::genes_lookup::{
+            self, GenesLookupMatchedBy, GenesLookupResponse, GenesLookupResultEntry,
+        },
+        server::run::genes_scores::{self, GenesScores, GenesScoresFormat, GenesScoresQuery},
         server::run::genes_search::{
             self, GenesFields, GenesScoredGeneNames, GenesSearchQuery, GenesSearchResponse,
         },
+        server::run::genes_xrefs::{
+            self, GenesXrefsFormat, GenesXrefsPageInfo, GenesXrefsQuery, GenesXrefsRecord,
+            GenesXrefsResponse,
+        },
+        server::run::health::{
+            self, HealthBlockCache, HealthDbStatus, HealthLiveResponse, HealthReadyResponse,
+        },
+        server::run::regions::RegionsInterval,
         server::run::versions::{
             self, VersionsAnnotationInfo, VersionsCreatedFrom, VersionsInfoQuery,
-            VersionsInfoResponse, VersionsPerRelease, VersionsVersionSpec,
+            VersionsInfoResponse, VersionsPerRelease, VersionsReleaseConflict, VersionsVersionSpec,
+        },
+        server::run::{
+            error::{CustomError, InvalidParam},
+            AnnoDb, GeneNames,
         },
-        server::run::{error::CustomError, AnnoDb, GeneNames},
     };
 
     /// Utoipa-based `OpenAPI` generation helper.
@@ -60,11 +151,23 @@ pub mod openapi {
         paths(
             versions::handle,
             clinvar_sv::handle_with_openapi,
+            annos_variant::handle,
+            annos_variant::handle_multi,
             annos_variant::handle_with_openapi,
+            annos_position::handle,
+            annos_range::handle,
+            genes_acmg_sf::handle,
+            genes_by_disease::handle,
             genes_clinvar::handle_with_openapi,
+            genes_clinvar_sv_summary::handle,
+            genes_clinvar_variants::handle,
             genes_info::handle_with_openapi,
             genes_lookup::handle_with_openapi,
-            genes_search::handle_with_openapi
+            genes_scores::handle,
+            genes_search::handle_with_openapi,
+            genes_xrefs::handle,
+            health::handle_live,
+            health::handle_ready
         ),
         components(schemas(
             VersionsInfoQuery,
@@ -72,17 +175,22 @@ pub mod openapi {
             VersionsVersionSpec,
             VersionsPerRelease,
             VersionsAnnotationInfo,
+            VersionsReleaseConflict,
             VersionsCreatedFrom,
             GenomeRelease,
             AnnoDb,
             CustomError,
+            DecodeError,
+            InvalidParam,
             GenesAcmgSecondaryFindingRecord,
             GenesClingenDosageScore,
             GenesClingenDosageRecord,
+            GenesClingenRegionOverlap,
             GenesDecipherHiRecord,
             GenesDominoRecord,
             GenesDbnsfpRecord,
             GenesGnomadConstraintsRecord,
+            GenesGnomadConstraintsTranscriptRecord,
             GenesHgncLsdb,
             GenesHgncRecord,
             GenesRifEntry,
@@ -124,9 +232,24 @@ pub mod openapi {
             GenesFields,
             GenesSearchResponse,
             GenesScoredGeneNames,
+            GenesScores,
+            GenesScoresFormat,
+            GenesScoresQuery,
             GeneNames,
+            GenesLookupMatchedBy,
             GenesLookupResponse,
             GenesLookupResultEntry,
+            GenesXrefsFormat,
+            GenesXrefsQuery,
+            GenesXrefsRecord,
+            GenesXrefsPageInfo,
+            GenesXrefsResponse,
+            HealthLiveResponse,
+            HealthDbStatus,
+            CircuitBreakerState,
+            CircuitBreakerStatus,
+            HealthBlockCache,
+            HealthReadyResponse,
             GenesClinvarQuery,
             GenesExtractedVariantsPerRelease,
             GenesCoarseClinsigFrequencyCounts,
@@ -136,6 +259,21 @@ pub mod openapi {
             GenesClinvarPerGeneRecord,
             GenesClinvarResponseEntry,
             GenesClinvarResponse,
+            GenesClinvarSvSummaryQuery,
+            GenesClinvarSvCount,
+            GenesClinvarSvRecord,
+            GenesClinvarSvSummary,
+            GenesClinvarSvSummaryResponse,
+            GenesClinvarVariantsSort,
+            GenesClinvarVariantsQuery,
+            GenesClinvarVariantRecord,
+            GenesClinvarVariantsPageInfo,
+            GenesClinvarVariantsResponse,
+            GenesAcmgSfRecord,
+            GenesAcmgSfResponse,
+            GenesByDiseaseQuery,
+            GenesByDiseaseResponse,
+            DiseaseGene,
             StrucvarsClinvarQuery,
             StrucvarsClinvarPageInfo,
             StrucvarsClinvarResponseRecord,
@@ -254,6 +392,7 @@ pub mod openapi {
             ClinvarSomaticVariantInNormalTissue,
             ClinvarSpecies,
             ClinvarStatus,
+            ClinvarSubmissionDetails,
             ClinvarSubmissionId,
             ClinvarSubmitter,
             ClinvarSubmitterIdentifiers,
@@ -269,11 +408,35 @@ pub mod openapi {
             ClinvarVariationArchiveRecordType,
             ClinvarVariationRelease,
             ClinvarVariationType,
+            ClinvarVcvDetails,
             ClinvarVersionedAccession,
             ClinvarXref,
             ClinvarZygosity,
             SeqvarsAnnosQuery,
             SeqvarsAnnosResponse,
+            CaddScores,
+            CaddRecord,
+            AnnosVariantResponse,
+            RegionsInterval,
+            VariantContext,
+            NearestFeatures,
+            NearestFeature,
+            AnnosVariantMultiQuery,
+            AnnosVariantMultiEntry,
+            AnnosVariantMultiResponse,
+            AnnosVariantPresenceResponse,
+            AnnosVariantMultiPresenceEntry,
+            AnnosVariantMultiPresenceResponse,
+            SeqvarsPresenceRecord,
+            SeqvarsGnomadPresence,
+            AnnosPositionQuery,
+            AnnosPositionAllele,
+            AnnosPositionResponse,
+            AnnosRangeQuery,
+            AnnosRangeResponse,
+            ComputedFrequencies,
+            FrequencySource,
+            QualityTier,
             // TODO: more here!
         ))
     )]
@@ -288,30 +451,50 @@ pub mod openapi {
 #[actix_web::main]
 pub async fn main(args: &Args, dbs: Data<WebServerData>) -> std::io::Result<()> {
     let openapi = openapi::ApiDoc::openapi();
+    let enable_compression = args.enable_compression;
+    let enable_audit_log = dbs.audit_log.is_some();
 
     HttpServer::new(move || {
         let app = App::new()
             .app_data(dbs.clone())
             .service(annos_variant::handle)
+            .service(annos_variant::handle_multi)
             .service(annos_variant::handle_with_openapi)
+            .service(annos_position::handle)
             .service(annos_range::handle)
             .service(annos_db_info::handle)
             .service(clinvar_sv::handle)
             .service(clinvar_sv::handle_with_openapi)
+            .service(genes_acmg_sf::handle)
+            .service(genes_by_disease::handle)
             .service(genes_clinvar::handle)
             .service(genes_clinvar::handle_with_openapi)
+            .service(genes_clinvar_sv_summary::handle)
+            .service(genes_clinvar_variants::handle)
             .service(genes_info::handle)
             .service(genes_info::handle_with_openapi)
             .service(genes_search::handle)
             .service(genes_search::handle_with_openapi)
+            .service(genes_scores::handle)
             .service(genes_lookup::handle)
             .service(genes_lookup::handle_with_openapi)
+            .service(genes_xrefs::handle)
+            .service(health::handle_live)
+            .service(health::handle_ready)
+            .service(admin::handle_config)
+            .service(admin_raw::handle_raw_get)
+            .service(admin_raw::handle_raw_key)
             .service(versions::handle)
             .service(
                 utoipa_swagger_ui::SwaggerUi::new("/swagger-ui/{_:.*}")
                     .url("/api-docs/openapi.json", openapi.clone()),
             );
-        app.wrap(Logger::default())
+        app.wrap(Condition::new(
+            enable_audit_log,
+            from_fn(audit_log::middleware),
+        ))
+        .wrap(compression::middleware(enable_compression))
+        .wrap(Logger::default())
     })
     .bind((args.listen_host.as_str(), args.listen_port))?
     .run()
@@ -357,8 +540,12 @@ pub enum AnnoDb {
     GnomadExomes,
     /// gnomAD genomes annotations.
     GnomadGenomes,
+    /// gnomAD coverage (mean depth and fraction over 20x per position).
+    GnomadCoverage,
     /// HelixMtDb annotations.
     Helixmtdb,
+    /// AlphaMissense annotations.
+    Alphamissense,
     /// UCSC conservation annotations.
     UcscConservation,
     /// ClinVar with minimal data extracted.
@@ -376,7 +563,9 @@ impl AnnoDb {
             AnnoDb::GnomadMtdna => "gnomad_mtdna_data",
             AnnoDb::GnomadExomes => "gnomad_nuclear_data",
             AnnoDb::GnomadGenomes => "gnomad_nuclear_data",
+            AnnoDb::GnomadCoverage => crate::gnomad_coverage::CF_NAME,
             AnnoDb::Helixmtdb => "helixmtdb_data",
+            AnnoDb::Alphamissense => "alphamissense_data",
             AnnoDb::UcscConservation => "ucsc_conservation",
             AnnoDb::Clinvar => "clinvar",
             AnnoDb::Other => panic!("cannot get CF name for 'Other'"),
@@ -384,7 +573,7 @@ impl AnnoDb {
     }
 
     /// Return the key for the database version.
-    fn db_version_meta(&self) -> Option<&'static str> {
+    pub fn db_version_meta(&self) -> Option<&'static str> {
         match self {
             AnnoDb::Cadd => Some("db-version"),
             AnnoDb::Dbsnp => Some("db-version"),
@@ -393,19 +582,35 @@ impl AnnoDb {
             AnnoDb::GnomadMtdna => Some("gnomad-version"),
             AnnoDb::GnomadExomes => Some("gnomad-version"),
             AnnoDb::GnomadGenomes => Some("gnomad-version"),
+            AnnoDb::GnomadCoverage => Some("gnomad-version"),
             AnnoDb::Helixmtdb => None,
+            AnnoDb::Alphamissense => Some("db-version"),
             AnnoDb::UcscConservation => None,
-            AnnoDb::Clinvar => None,
+            AnnoDb::Clinvar => Some("db-version"),
             AnnoDb::Other => panic!("cannot get meta version name name for 'Other'"),
         }
     }
+
+    /// Return whether the database is keyed by variant.
+    fn is_variant_db(&self) -> bool {
+        !matches!(
+            self,
+            AnnoDb::GnomadCoverage | AnnoDb::UcscConservation | AnnoDb::Other
+        )
+    }
+
+    /// Return whether the database only covers the mitochondrial genome.
+    fn is_mtdna_db(&self) -> bool {
+        matches!(self, AnnoDb::GnomadMtdna | AnnoDb::Helixmtdb)
+    }
 }
 
 /// Identifier / name information for one gene.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct GeneNames {
     /// HGNC gene ID.
-    pub hgnc_id: String,
+    #[schema(value_type = String)]
+    pub hgnc_id: HgncId,
     /// HGNC gene symbol.
     pub symbol: String,
     /// Gene name from HGNC.
@@ -414,10 +619,33 @@ pub struct GeneNames {
     pub alias_symbol: Vec<String>,
     /// HGNC alias names.
     pub alias_name: Vec<String>,
+    /// HGNC previous symbols.
+    #[serde(default)]
+    pub prev_symbol: Vec<String>,
+    /// Date of the last change of the HGNC symbol.
+    #[serde(default)]
+    pub date_symbol_changed: Option<String>,
     /// ENSEMBL gene ID.
-    pub ensembl_gene_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub ensembl_gene_id: Option<EnsemblGeneId>,
     /// NCBI gene ID.
-    pub ncbi_gene_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub ncbi_gene_id: Option<NcbiGeneId>,
+    /// RefSeq accessions.
+    ///
+    /// This and the further cross-references are only used for indexing and for
+    /// `/genes/xrefs` and are not part of the serialized gene names.
+    #[serde(skip)]
+    pub refseq_accession: Vec<String>,
+    /// UniProt IDs.
+    #[serde(skip)]
+    pub uniprot_ids: Vec<String>,
+    /// OMIM IDs.
+    #[serde(skip)]
+    pub omim_id: Vec<String>,
+    /// MANE Select transcript accessions (Ensembl and RefSeq) including the version.
+    #[serde(default)]
+    pub mane_select: Vec<String>,
 }
 
 /// Gene information database.
@@ -431,6 +659,17 @@ pub struct GeneInfoDb {
     pub gene_names: Vec<GeneNames>,
     /// Mapping from allowed gene name string to index in `gene_names`.
     pub name_to_hgnc_idx: HashMap<String, usize>,
+    /// Mapping from previous and alias symbol to indices in `gene_names` (for `/genes/info`
+    /// and `/genes/lookup`).
+    pub other_symbol_to_hgnc_idxs: HashMap<String, Vec<usize>>,
+    /// Mapping from transcript accession, with and without version, to indices in
+    /// `gene_names` (for `/genes/lookup`).
+    pub transcript_to_hgnc_idxs: HashMap<String, Vec<usize>>,
+    /// Genes on the ACMG SF list, sorted by symbol (for `/genes/acmg-sf`).
+    pub acmg_sf: Vec<genes_acmg_sf::GenesAcmgSfRecord>,
+    /// Versions of the data sources from `meta:source-version:*`, empty for databases built
+    /// by older versions.
+    pub source_versions: std::collections::BTreeMap<String, String>,
 }
 
 /// Genome-release specific annotation for each database.
@@ -439,6 +678,19 @@ pub type ReleaseAnnos = enum_map::EnumMap<
     Option<WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>>,
 >;
 
+/// A further database of a kind that already has a database in `ReleaseAnnos`.
+#[derive(Debug)]
+pub struct AdditionalAnnoDb {
+    /// Information on the database.
+    pub db_info: DbInfo,
+    /// The database.
+    pub db: WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+}
+
+/// Genome-release specific further databases of each kind, e.g., the gnomAD v2 liftover
+/// exomes next to the native gnomAD v4 exomes for GRCh38.
+pub type ReleaseAdditionalAnnos = enum_map::EnumMap<AnnoDb, Vec<AdditionalAnnoDb>>;
+
 /// Database information
 #[derive(serde::Serialize, Debug, Clone, Default)]
 pub struct DbInfo {
@@ -448,6 +700,59 @@ pub struct DbInfo {
     pub db_version: Option<String>,
     /// Version of the builder code.
     pub builder_version: String,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
+    /// Number of records, see `common::record_stats`; `None` for older databases.
+    pub record_count: Option<u64>,
+    /// First key as `CHROM:POS[:REF:ALT]`; `None` for older databases.
+    pub key_range_first: Option<String>,
+    /// Last key as `CHROM:POS[:REF:ALT]`; `None` for older databases.
+    pub key_range_last: Option<String>,
+}
+
+impl DbInfo {
+    /// Return the name of the database including its version, e.g., `gnomad_exomes_4.0`.
+    ///
+    /// gnomAD v2 only exists for GRCh37 natively, so the GRCh38 databases are liftovers and
+    /// get the suffix `_liftover`, e.g., `gnomad_exomes_2.1_liftover`.  ClinVar databases
+    /// use their release, e.g., `clinvar_2024-01`.
+    pub fn versioned_key(&self, genome_release: GenomeRelease) -> String {
+        if let Some(clinvar_release) = self.clinvar_release() {
+            return format!("{}_{}", self.name, clinvar_release);
+        }
+        let db_version = self.db_version.as_deref().unwrap_or_default();
+        let is_liftover = matches!(self.name, AnnoDb::GnomadExomes | AnnoDb::GnomadGenomes)
+            && genome_release == GenomeRelease::Grch38
+            && db_version.starts_with("2.");
+        format!(
+            "{}_{}{}",
+            self.name,
+            db_version,
+            if is_liftover { "_liftover" } else { "" }
+        )
+    }
+
+    /// Return the ClinVar release as `YYYY-MM` for ClinVar databases with a version.
+    ///
+    /// The version may be given as `YYYY-MM-DD`, `YYYYMMDD`, or with further suffixes, e.g.,
+    /// `20240107+0.33.0` from the `clinvar-data-jsonl` releases.
+    pub fn clinvar_release(&self) -> Option<String> {
+        if self.name != AnnoDb::Clinvar {
+            return None;
+        }
+        let digits = self
+            .db_version
+            .as_deref()?
+            .chars()
+            .filter(|c| *c != '-')
+            .take(6)
+            .collect::<String>();
+        if digits.len() == 6 && digits.chars().all(|c| c.is_ascii_digit()) {
+            Some(format!("{}-{}", &digits[..4], &digits[4..]))
+        } else {
+            None
+        }
+    }
 }
 
 /// Fetch database information from the given RocksDB.
@@ -459,28 +764,98 @@ fn fetch_db_info(
         .ok_or(anyhow::anyhow!("meta:genome-release not found in data"))?
         .as_str()
         .parse()?;
-    let db_version = name
-        .db_version_meta()
-        .map(|db_version_meta| {
-            rocksdb_utils_lookup::fetch_meta(db, db_version_meta)?.ok_or(anyhow::anyhow!(
-                "meta:{} not found in database",
-                db_version_meta
-            ))
-        })
-        .transpose()?;
+    let db_version = match name.db_version_meta() {
+        Some(db_version_meta) => {
+            let db_version = rocksdb_utils_lookup::fetch_meta(db, db_version_meta)?;
+            // Older ClinVar databases do not have a version.
+            if db_version.is_none() && name != AnnoDb::Clinvar {
+                anyhow::bail!("meta:{} not found in database", db_version_meta);
+            }
+            db_version
+        }
+        None => None,
+    };
     let builder_version =
         rocksdb_utils_lookup::fetch_meta(db, "annonars-version")?.ok_or(anyhow::anyhow!(
             "meta:annonars-version not found in database {}",
             db.path().display()
         ))?;
+    let keys_normalized = rocksdb_utils_lookup::fetch_meta(db, common::norm::META_KEYS_NORMALIZED)?
+        .map(|value| value == "true")
+        .unwrap_or_default();
+    if !keys_normalized && name.is_variant_db() {
+        tracing::warn!(
+            "database {} was imported without allele trimming; indels are only found in the \
+            representation used in the input files",
+            db.path().display()
+        );
+    }
+    let common::record_stats::RecordStats {
+        record_count,
+        key_range_first,
+        key_range_last,
+    } = common::record_stats::read_meta(db)?;
     let db_info = DbInfo {
         name,
         db_version,
         builder_version,
+        keys_normalized,
+        record_count,
+        key_range_first,
+        key_range_last,
     };
     Ok((genome_release, db_info))
 }
 
+/// Decode the first record of an annotation database to detect databases that cannot be
+/// read by this version of annonars before requests are served.
+///
+/// Databases with TSV values, per-base conservation scores, and coverage are not checked.
+fn check_first_record(
+    db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    db_info: &DbInfo,
+) -> Result<(), common::decode::DecodeError> {
+    use common::decode::check_first_record;
+
+    let db_version = db_info.db_version.as_deref().unwrap_or_default();
+    match db_info.name {
+        AnnoDb::Other
+        | AnnoDb::Cadd
+        | AnnoDb::Dbnsfp
+        | AnnoDb::Dbscsnv
+        | AnnoDb::GnomadCoverage => Ok(()),
+        AnnoDb::Dbsnp => check_first_record::<crate::dbsnp::pbs::Record>(db, "dbsnp_data"),
+        AnnoDb::GnomadMtdna => {
+            check_first_record::<crate::pbs::gnomad::mtdna::Record>(db, "gnomad_mtdna_data")
+        }
+        AnnoDb::GnomadExomes | AnnoDb::GnomadGenomes => {
+            let cf_name = db_info.name.cf_name();
+            if db_version.starts_with("2.") {
+                check_first_record::<crate::pbs::gnomad::gnomad2::Record>(db, cf_name)
+            } else if db_version.starts_with("3.") {
+                check_first_record::<crate::pbs::gnomad::gnomad3::Record>(db, cf_name)
+            } else {
+                check_first_record::<crate::pbs::gnomad::gnomad4::Record>(db, cf_name)
+            }
+        }
+        AnnoDb::Helixmtdb => {
+            check_first_record::<crate::pbs::helixmtdb::Record>(db, "helixmtdb_data")
+        }
+        AnnoDb::Alphamissense => {
+            check_first_record::<crate::pbs::alphamissense::RecordList>(db, "alphamissense_data")
+        }
+        AnnoDb::UcscConservation if crate::cons::per_base::is_per_base(db) => Ok(()),
+        AnnoDb::UcscConservation => {
+            check_first_record::<crate::pbs::cons::RecordList>(db, "ucsc_conservation")
+        }
+        AnnoDb::Clinvar => {
+            check_first_record::<crate::pbs::clinvar::minimal::ExtractedVcvRecordList>(
+                db, "clinvar",
+            )
+        }
+    }
+}
+
 /// Generic type to store a database together with version specification.
 #[derive(Debug)]
 pub struct WithVersionSpec<T: std::fmt::Debug> {
@@ -514,34 +889,374 @@ pub struct WebServerData {
     pub genes: Option<WithVersionSpec<GeneInfoDb>>,
     /// Release-specific annotations for each `GenomeRelease`.
     pub annos: enum_map::EnumMap<GenomeRelease, ReleaseAnnos>,
+    /// Release-specific further databases of a kind in `annos` with differing versions.
+    pub additional_annos: enum_map::EnumMap<GenomeRelease, ReleaseAdditionalAnnos>,
     /// Release-specific ClinVar SV interval tree indexed databased.
     pub clinvar_svs: enum_map::EnumMap<GenomeRelease, Option<ClinvarsvIntervalTrees>>,
+    /// Release-specific functional element databases for the context of `/annos/variant`, see
+    /// `--path-functional`.
+    pub functional:
+        enum_map::EnumMap<GenomeRelease, Option<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>>,
+    /// Release-specific custom region interval tree indexed databases by name, see
+    /// `--path-regions`.
+    pub regions:
+        enum_map::EnumMap<GenomeRelease, std::collections::BTreeMap<String, RegionsIntervalTrees>>,
     /// Version information for each database.
     pub db_infos: enum_map::EnumMap<GenomeRelease, enum_map::EnumMap<AnnoDb, Option<DbInfo>>>,
+    /// Score columns of the CADD database for each release, `None` for the untyped view.
+    pub cadd_columns: enum_map::EnumMap<GenomeRelease, Option<cadd::CaddColumns>>,
+    /// Names of databases to ignore in `/health/ready`.
+    pub readiness_exclude: Vec<String>,
+    /// Optional response cache for `/annos/variant`.
+    pub variant_cache: Option<variant_cache::VariantCache>,
+    /// Optional block cache shared by all databases.
+    pub block_cache: Option<BlockCache>,
+    /// Token for the admin endpoints, which are disabled if `None`.
+    pub admin_token: Option<admin::AdminToken>,
+    /// Whether the raw database endpoints of `admin_raw` are enabled.
+    pub enable_admin_raw: bool,
+    /// Effective command line arguments with secrets redacted, see `Args::sanitized`.
+    pub config: serde_json::Value,
+    /// Decoder of the records of `/annos/range`, see `--range-decode-threads`.
+    pub range_decoder: range_decode::RangeDecoder,
+    /// Circuit breakers of the annotation databases, see `--circuit-breaker-threshold`.
+    pub circuit_breakers: circuit_breaker::CircuitBreakers,
+    /// Canonical names of the contigs to answer queries for, all if empty, see
+    /// `--contig-allowlist`.
+    pub contig_allowlist: Vec<String>,
+    /// Optional audit log of the variant queries, see `--audit-log-path`.
+    pub audit_log: Option<audit_log::AuditLog>,
+}
+
+// `WebServerData` is shared by all workers, see the module documentation.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<WebServerData>();
+};
+
+/// RocksDB block cache shared by all databases, see `--rocksdb-block-cache-mb`.
+///
+/// Without it, each database has its own default block cache such that the total size grows
+/// with the number of databases.
+#[derive(Clone)]
+pub struct BlockCache {
+    /// Capacity of the cache in bytes.
+    pub capacity: usize,
+    /// The cache itself.
+    pub cache: rocksdb::Cache,
+}
+
+impl std::fmt::Debug for BlockCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockCache")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BlockCache {
+    /// Create a new LRU block cache of `size_mb` MiB.
+    pub fn new(size_mb: usize) -> Self {
+        let capacity = size_mb * 1024 * 1024;
+        Self {
+            capacity,
+            cache: rocksdb::Cache::new_lru_cache(capacity),
+        }
+    }
+}
+
+impl WebServerData {
+    /// Register the annotation database `db` described by `db_info` for `genome_release`.
+    ///
+    /// The first database of each kind goes to `annos`.  gnomAD exomes and genomes as well as
+    /// ClinVar may be given more than once per release if their versions differ, the further
+    /// ones go to `additional_annos`.  For ClinVar, the newest release is kept in `annos` such
+    /// that it is used by default.
+    pub fn insert_anno_db(
+        &mut self,
+        genome_release: GenomeRelease,
+        mut db_info: DbInfo,
+        mut db: WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    ) -> Result<(), anyhow::Error> {
+        let name = db_info.name;
+        if name == AnnoDb::Clinvar && db_info.db_version.is_none() {
+            // Fall back to the ClinVar version from the `spec.yaml` file.
+            db_info.db_version = db.version_spec.as_ref().and_then(|version_spec| {
+                version_spec
+                    .created_from
+                    .iter()
+                    .find(|created_from| created_from.name == "ClinVar")
+                    .map(|created_from| created_from.version.clone())
+            });
+        }
+        if self.db_infos[genome_release][name].is_none() {
+            self.db_infos[genome_release][name] = Some(db_info);
+            self.annos[genome_release][name] = Some(db);
+            return Ok(());
+        }
+
+        match name {
+            AnnoDb::GnomadExomes | AnnoDb::GnomadGenomes => (),
+            AnnoDb::Clinvar => {
+                if db_info.clinvar_release().is_none() {
+                    anyhow::bail!(
+                        "more than one ClinVar database given for {}; each needs a release in \
+                        meta:db-version or spec.yaml",
+                        genome_release
+                    );
+                }
+            }
+            _ => anyhow::bail!(
+                "more than one {} database given for {}; this is only supported for gnomAD \
+                exomes and genomes and ClinVar",
+                name,
+                genome_release
+            ),
+        }
+        let versioned_key = db_info.versioned_key(genome_release);
+        let is_duplicate = self.db_infos[genome_release][name]
+            .iter()
+            .chain(
+                self.additional_annos[genome_release][name]
+                    .iter()
+                    .map(|additional| &additional.db_info),
+            )
+            .any(|other| other.versioned_key(genome_release) == versioned_key);
+        if is_duplicate {
+            anyhow::bail!(
+                "more than one {} database with version {} given for {}",
+                name,
+                db_info.db_version.as_deref().unwrap_or_default(),
+                genome_release
+            );
+        }
+        if name == AnnoDb::Clinvar
+            && db_info.clinvar_release()
+                > self.db_infos[genome_release][name]
+                    .as_ref()
+                    .and_then(DbInfo::clinvar_release)
+        {
+            let primary_info = self.db_infos[genome_release][name].as_mut().unwrap();
+            let primary_db = self.annos[genome_release][name].as_mut().unwrap();
+            std::mem::swap(primary_info, &mut db_info);
+            std::mem::swap(primary_db, &mut db);
+        }
+        self.additional_annos[genome_release][name].push(AdditionalAnnoDb { db_info, db });
+
+        Ok(())
+    }
+
+    /// Return the databases of kind `anno_db` for `genome_release` together with their
+    /// `DbInfo::versioned_key()` if there is more than one, otherwise an empty list.
+    pub fn versioned_annos(
+        &self,
+        genome_release: GenomeRelease,
+        anno_db: AnnoDb,
+    ) -> Vec<(
+        String,
+        &DbInfo,
+        &WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    )> {
+        let additional = &self.additional_annos[genome_release][anno_db];
+        if additional.is_empty() {
+            return Vec::new();
+        }
+        self.db_infos[genome_release][anno_db]
+            .as_ref()
+            .zip(self.annos[genome_release][anno_db].as_ref())
+            .into_iter()
+            .chain(
+                additional
+                    .iter()
+                    .map(|additional| (&additional.db_info, &additional.db)),
+            )
+            .map(|(db_info, db)| (db_info.versioned_key(genome_release), db_info, db))
+            .collect()
+    }
+
+    /// Check that `chromosome` is in the contig allowlist, see `--contig-allowlist`.
+    pub fn contig_allowed(&self, chromosome: &str) -> Result<(), common::contig::NotInAllowlist> {
+        common::contig::check_allowlist(&self.contig_allowlist, chromosome)
+    }
+
+    /// Return the databases loaded for `genome_release` without records on `chromosome`.
+    ///
+    /// The mtDNA databases are only checked for the mitochondrial genome, they are not
+    /// expected to have records on other contigs.
+    pub fn contig_not_in_databases(
+        &self,
+        genome_release: GenomeRelease,
+        chromosome: &str,
+        clinvar_release: Option<&str>,
+    ) -> Result<std::collections::BTreeMap<AnnoDb, common::contig::NotInDatabase>, error::CustomError>
+    {
+        let is_mtdna = common::cli::canonicalize(chromosome) == "MT";
+        let mut result = std::collections::BTreeMap::new();
+        for anno_db in AnnoDb::iter() {
+            if anno_db == AnnoDb::Other || (anno_db.is_mtdna_db() && !is_mtdna) {
+                continue;
+            }
+            let db = if anno_db == AnnoDb::Clinvar {
+                self.clinvar_db(genome_release, clinvar_release)?
+                    .map(|(_, db)| db)
+            } else {
+                self.annos[genome_release][anno_db].as_ref()
+            };
+            if let Some(db) = db {
+                let cf_name = if crate::cons::per_base::is_per_base(&db.data) {
+                    crate::cons::per_base::CF_NAME
+                } else {
+                    anno_db.cf_name()
+                };
+                if let Err(e) =
+                    common::contig::lookup(&db.data, cf_name, chromosome, &anno_db.to_string())
+                {
+                    result.insert(anno_db, e);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Return the ClinVar database for `genome_release` and `clinvar_release`, or the
+    /// newest one if `None`.
+    ///
+    /// Returns `Ok(None)` if there is no ClinVar database for the release at all and an
+    /// error for the parameter `clinvar_release` if the given release is not loaded.
+    pub fn clinvar_db(
+        &self,
+        genome_release: GenomeRelease,
+        clinvar_release: Option<&str>,
+    ) -> Result<
+        Option<(
+            &DbInfo,
+            &WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+        )>,
+        error::CustomError,
+    > {
+        let primary = self.db_infos[genome_release][AnnoDb::Clinvar]
+            .as_ref()
+            .zip(self.annos[genome_release][AnnoDb::Clinvar].as_ref());
+        let Some(clinvar_release) = clinvar_release else {
+            return Ok(primary);
+        };
+        primary
+            .into_iter()
+            .chain(
+                self.additional_annos[genome_release][AnnoDb::Clinvar]
+                    .iter()
+                    .map(|additional| (&additional.db_info, &additional.db)),
+            )
+            .find(|(db_info, _)| db_info.clinvar_release().as_deref() == Some(clinvar_release))
+            .map(Some)
+            .ok_or_else(|| {
+                error::CustomError::invalid_param(
+                    "clinvar_release",
+                    Some(clinvar_release),
+                    &format!(
+                        "no such ClinVar release loaded for {}, available: {}",
+                        genome_release,
+                        self.clinvar_releases(genome_release).join(", ")
+                    ),
+                )
+            })
+    }
+
+    /// Run `fetch` on the database `db` of kind `anno_db` behind its circuit breaker.
+    ///
+    /// Returns `Ok(None)` if the database is degraded, see `circuit_breaker`.
+    pub fn with_breaker<T>(
+        &self,
+        genome_release: GenomeRelease,
+        anno_db: AnnoDb,
+        db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+        fetch: impl FnOnce() -> Result<T, error::CustomError>,
+    ) -> Result<Option<T>, error::CustomError> {
+        self.circuit_breakers
+            .call(genome_release, anno_db, || health::check_db(db), fetch)
+    }
+
+    /// Run `fetch` on the database `anno_db` for `genome_release` behind its circuit breaker.
+    ///
+    /// Returns `Ok(None)` if the database is not configured or degraded.
+    pub fn guarded<T>(
+        &self,
+        genome_release: GenomeRelease,
+        anno_db: AnnoDb,
+        fetch: impl FnOnce(
+            &WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+        ) -> Result<T, error::CustomError>,
+    ) -> Result<Option<T>, error::CustomError> {
+        match self.annos[genome_release][anno_db].as_ref() {
+            Some(db) => self.with_breaker(genome_release, anno_db, &db.data, || fetch(db)),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the releases of the ClinVar databases for `genome_release`, newest first.
+    pub fn clinvar_releases(&self, genome_release: GenomeRelease) -> Vec<String> {
+        let mut result = self.db_infos[genome_release][AnnoDb::Clinvar]
+            .iter()
+            .chain(
+                self.additional_annos[genome_release][AnnoDb::Clinvar]
+                    .iter()
+                    .map(|additional| &additional.db_info),
+            )
+            .filter_map(DbInfo::clinvar_release)
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| b.cmp(a));
+        result
+    }
 }
 
 /// Command line arguments for `server rest` sub command.
 ///
 /// Each path can be given more than one time to support multiple releases.  When the server
 /// is started, it needs to be given a file for each database with each release.
-#[derive(Parser, Debug, Clone)]
+///
+/// All arguments can also be given in the config file of `--config` and the environment,
+/// see `config`.
+#[derive(Parser, Debug, Clone, serde::Serialize)]
 #[command(author, version, about = "Run annonars REST API", long_about = None)]
 pub struct Args {
+    /// TOML file with further arguments, which are overridden by the environment variables
+    /// `ANNONARS_*` and the command line.
+    #[arg(long, env = "ANNONARS_CONFIG")]
+    pub config: Option<String>,
     /// Path to genes database.
     #[arg(long)]
     pub path_genes: Option<String>,
     /// ClinVar per-gene database(s), one for each release.
     #[arg(long)]
     pub path_clinvar_genes: Option<String>,
-    /// ClinVar database(s), one for each release.
+    /// ClinVar database(s), one or more for each release.
+    ///
+    /// More than one ClinVar database per release is distinguished by the release in
+    /// `meta:db-version` or `spec.yaml`; the newest is used unless `clinvar_release` is given.
     #[arg(long)]
     pub path_clinvar: Vec<String>,
     /// ClinVar SV database(s), one for each release.
     #[arg(long)]
     pub path_clinvar_sv: Vec<String>,
+    /// Custom region database(s) imported from BED files as `NAME=PATH`, the overlapping
+    /// regions are returned by name in `/annos/variant` and `/annos/range`.
+    #[arg(long, value_parser = regions::parse_named_path)]
+    #[serde(serialize_with = "regions::serialize_named_paths")]
+    pub path_regions: Vec<(String, String)>,
+    /// Functional element database(s), one for each release, for the nearest functional
+    /// elements returned by `/annos/variant` with `context=true`.
+    #[arg(long)]
+    pub path_functional: Vec<String>,
     /// CADD database(s), one for each release.
     #[arg(long)]
     pub path_cadd: Vec<String>,
+    /// Name of the raw score column of the CADD database(s).
+    #[arg(long, default_value = cadd::DEFAULT_COL_RAW)]
+    pub cadd_col_raw: String,
+    /// Name of the PHRED score column of the CADD database(s); the typed view of the CADD
+    /// scores is disabled with a warning if either column is missing.
+    #[arg(long, default_value = cadd::DEFAULT_COL_PHRED)]
+    pub cadd_col_phred: String,
     /// dbSNP database(s), one for each release.
     #[arg(long)]
     pub path_dbsnp: Vec<String>,
@@ -554,18 +1269,26 @@ pub struct Args {
     /// gnomAD mtDNA database(s), one for each release.
     #[arg(long)]
     pub path_gnomad_mtdna: Vec<String>,
-    /// gnomAD-exomes database(s), one for each release.
+    /// gnomAD-exomes database(s), one for each release and gnomAD version (e.g., the v2
+    /// liftover next to v4 for GRCh38).
     #[arg(long)]
     pub path_gnomad_exomes: Vec<String>,
-    /// gnomAD-genomes database(s), one for each release.
+    /// gnomAD-genomes database(s), one for each release and gnomAD version (e.g., the v2
+    /// liftover next to v4 for GRCh38).
     #[arg(long)]
     pub path_gnomad_genomes: Vec<String>,
     /// HelixMtDB database(s), one for each release.
     #[arg(long)]
     pub path_helixmtdb: Vec<String>,
+    /// AlphaMissense database(s), one for each release.
+    #[arg(long)]
+    pub path_alphamissense: Vec<String>,
     /// UCSC conservation database(s), one for each release.
     #[arg(long)]
     pub path_ucsc_conservation: Vec<String>,
+    /// gnomAD coverage database(s), one for each release.
+    #[arg(long)]
+    pub path_gnomad_coverage: Vec<String>,
 
     /// IP to listen on.
     #[arg(long, default_value = "127.0.0.1")]
@@ -573,6 +1296,76 @@ pub struct Args {
     /// Port to listen on.
     #[arg(long, default_value_t = 8081)]
     pub listen_port: u16,
+    /// Compress responses with gzip (or another encoding accepted by the client in
+    /// `Accept-Encoding`).  JSON responses shrink 10-20x, saving bandwidth to clients in
+    /// other networks at the cost of server CPU time per response; leave disabled if the
+    /// clients are close by or a reverse proxy compresses.
+    #[arg(long)]
+    pub enable_compression: bool,
+    /// Database(s) to ignore in `/health/ready`, e.g., `cadd` or `clinvar_genes`.
+    #[arg(long)]
+    pub readiness_exclude: Vec<String>,
+    /// Number of `/annos/variant` responses to cache, disabled with `0`.
+    #[arg(long, default_value_t = 0)]
+    pub variant_cache_size: usize,
+    /// Time to live of cached `/annos/variant` responses in seconds.
+    #[arg(long, default_value_t = 3600)]
+    pub variant_cache_ttl_secs: u64,
+    /// Size of the RocksDB block cache shared by all databases in MiB; each database has its
+    /// own default cache if not given.
+    #[arg(long)]
+    pub rocksdb_block_cache_mb: Option<usize>,
+    /// Read databases through memory-mapped files and the OS page cache instead of the
+    /// RocksDB block cache, e.g., large databases on NVMe with limited RAM; `true` for all
+    /// databases or `NAME=true` for single ones, e.g., `gnomad_genomes=true`, see `mmap_reads`.
+    #[arg(long, value_delimiter = ',')]
+    pub rocksdb_mmap_reads: Vec<String>,
+    /// Token to require for the admin endpoints such as `/admin/config`, which are disabled
+    /// if not given.
+    #[arg(long, env = "ANNONARS_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
+    /// Enable the admin endpoints `/admin/raw-get` and `/admin/raw-key` for debugging the
+    /// database contents; these also require `--admin-token`.
+    #[arg(long)]
+    pub enable_admin_raw: bool,
+    /// Number of threads for decoding the records of large `/annos/range` queries, shared by
+    /// all requests; `1` decodes on the request's thread.
+    #[arg(long, default_value_t = 1)]
+    pub range_decode_threads: usize,
+    /// Number of consecutive errors after which an annotation database is marked as degraded
+    /// and omitted from responses, disabled with `0`.
+    #[arg(long, default_value_t = circuit_breaker::DEFAULT_THRESHOLD)]
+    pub circuit_breaker_threshold: u32,
+    /// Time in seconds before a degraded annotation database is probed again.
+    #[arg(long, default_value_t = circuit_breaker::DEFAULT_COOLDOWN_SECS)]
+    pub circuit_breaker_cooldown_secs: u64,
+    /// Contigs to answer annotation queries for, e.g., `1,2,X` for a deployment with a subset
+    /// of the data; queries for other contigs get an empty result and a warning.  All contigs
+    /// are answered if not given.
+    #[arg(long, value_delimiter = ',')]
+    pub contig_allowlist: Vec<String>,
+    /// Directory for caching lists precomputed at startup such as the ACMG SF genes, see
+    /// `common::cache`.
+    #[arg(long)]
+    pub cache_dir: Option<String>,
+    /// Path of a file to append one JSON line to for each `/annos/variant` and `/annos/range`
+    /// request, see `audit_log`; disabled if not given.
+    #[arg(long)]
+    pub audit_log_path: Option<String>,
+    /// Size in MiB beyond which the audit log file is rotated.
+    #[arg(long, default_value_t = audit_log::DEFAULT_MAX_MB)]
+    pub audit_log_max_mb: u64,
+    /// Number of rotated audit log files to keep.
+    #[arg(long, default_value_t = audit_log::DEFAULT_KEEP)]
+    pub audit_log_keep: usize,
+    /// Time in seconds to wait for the databases to open at startup before failing, e.g., on
+    /// a hanging network file system; waits indefinitely if not given.
+    #[arg(long)]
+    pub startup_timeout_secs: Option<u64>,
+    /// Fail at startup if the genome release of a database in `meta` differs from the one in
+    /// its `spec.yaml` instead of only logging a warning.
+    #[arg(long)]
+    pub strict_specs: bool,
 }
 
 /// Open a RocksDB database.
@@ -581,25 +1374,140 @@ pub struct Args {
 ///
 /// * `path` - Path to the database.
 /// * `cf_name` - Name of the column family to open (besides the mandatory `meta` column family).
+/// * `block_cache` - Shared block cache to use for all column families, RocksDB's default
+///   per-database cache if `None`.
+///
+/// Databases imported with `--prefix-bloom` are reopened with the matching options.
 fn open_db(
     path: &str,
     cf_name: &str,
+    block_cache: Option<&BlockCache>,
+) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
+    open_db_with_optional(path, cf_name, &[], block_cache, false)
+}
+
+/// Open a RocksDB database as `open_db`, also opening those of the `optional_cf_names`
+/// column families that the database has, e.g., the ClinVar submission-level details.
+///
+/// With `mmap_reads`, the database is read through memory-mapped files without block cache,
+/// see `mmap_reads`.
+fn open_db_with_optional(
+    path: &str,
+    cf_name: &str,
+    optional_cf_names: &[&str],
+    block_cache: Option<&BlockCache>,
+    mmap_reads: bool,
 ) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
-    tracing::info!("Opening database {}...", path);
+    tracing::info!(
+        "Opening database {}{}...",
+        path,
+        if mmap_reads { " with mmap reads" } else { "" }
+    );
     let before_open = Instant::now();
-    let res = rocksdb::DB::open_cf_for_read_only(
-        &rocksdb::Options::default(),
-        common::readlink_f(path)?,
-        ["meta", cf_name],
-        true,
-    )
-    .map_err(|e| anyhow::anyhow!("problem opening database: {}", e));
+    let path = common::readlink_f_existing(path)?;
+    let mut cf_names = vec!["meta", cf_name];
+    if !optional_cf_names.is_empty() {
+        let existing = rocksdb::DB::list_cf(&rocksdb::Options::default(), &path)
+            .map_err(|e| anyhow::anyhow!("problem listing column families: {}", e))?;
+        cf_names.extend(
+            optional_cf_names
+                .iter()
+                .filter(|name| existing.iter().any(|existing| existing == *name)),
+        );
+    }
+    let open = |prefix_bloom: bool| {
+        let options = open_options(block_cache, prefix_bloom, mmap_reads);
+        rocksdb::DB::open_cf_descriptors_read_only(
+            &options,
+            &path,
+            cf_names
+                .iter()
+                .map(|name| rocksdb::ColumnFamilyDescriptor::new(*name, options.clone())),
+            true,
+        )
+        .map_err(|e| anyhow::anyhow!("problem opening database: {}", e))
+    };
+    let mut db = open(false)?;
+    let cf_meta = db.cf_handle("meta").expect("no 'meta' column family");
+    let prefix_bloom = common::prefix_bloom::read_meta(&db, &cf_meta)?;
+    drop(cf_meta);
+    if prefix_bloom {
+        // The filters are only used with the options that the database was written with.
+        tracing::info!("  reopening with prefix bloom filters");
+        drop(db);
+        db = open(true)?;
+    }
     tracing::info!("...done opening database in {:?}", before_open.elapsed());
-    res
+    Ok(db)
+}
+
+/// Open a functional element database with its positional index and return its genome
+/// release.
+///
+/// Databases imported without the index are opened with a warning.
+fn open_functional_db(
+    path: &str,
+    block_cache: Option<&BlockCache>,
+    mmap_reads: bool,
+) -> Result<
+    (
+        GenomeRelease,
+        rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    ),
+    anyhow::Error,
+> {
+    let cf_name_pos_index = crate::functional::cli::import::CF_NAME_POS_INDEX;
+    let db = open_db_with_optional(
+        path,
+        "functional",
+        &[cf_name_pos_index],
+        block_cache,
+        mmap_reads,
+    )?;
+    let cf_meta = db.cf_handle("meta").expect("no 'meta' column family");
+    let genome_release = String::from_utf8(
+        db.get_cf(&cf_meta, "genome-release")?
+            .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release in {}", path))?,
+    )?
+    .parse::<GenomeRelease>()?;
+    drop(cf_meta);
+    if db.cf_handle(cf_name_pos_index).is_none() {
+        tracing::warn!(
+            "no positional index in {}, re-import for the nearest functional elements",
+            path
+        );
+    }
+    Ok((genome_release, db))
+}
+
+/// Return the options for opening a database read-only.
+///
+/// * `block_cache` - Shared block cache to use, RocksDB's default per-database cache if `None`.
+/// * `prefix_bloom` - Whether to use the prefix extractor and bloom filters of databases
+///   imported with `--prefix-bloom`, see `common::prefix_bloom`.
+/// * `mmap_reads` - Whether to read through memory-mapped files without block cache, see
+///   `mmap_reads`; `block_cache` is not used then.
+fn open_options(
+    block_cache: Option<&BlockCache>,
+    prefix_bloom: bool,
+    mmap_reads: bool,
+) -> rocksdb::Options {
+    let mut options = rocksdb::Options::default();
+    let mut block_opts = rocksdb::BlockBasedOptions::default();
+    if mmap_reads {
+        mmap_reads::configure(&mut options, &mut block_opts);
+    } else if let Some(block_cache) = block_cache {
+        block_opts.set_block_cache(&block_cache.cache);
+    }
+    if prefix_bloom {
+        common::prefix_bloom::configure(&mut options, &mut block_opts);
+    }
+    options.set_block_based_table_factory(&block_opts);
+    options
 }
 
 /// Obtain gene names from the genes RocksDB.
-fn extract_gene_names(
+pub(crate) fn extract_gene_names(
     genes_db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
 ) -> Result<Vec<GeneNames>, anyhow::Error> {
     let mut result = Vec::new();
@@ -608,8 +1516,9 @@ fn extract_gene_names(
     let mut iter = genes_db.raw_iterator_cf(&cf_read);
     iter.seek(b"");
     while iter.valid() {
-        if let Some(iter_value) = iter.value() {
-            let record = genes::base::Record::decode(std::io::Cursor::new(iter_value))?;
+        if let (Some(iter_key), Some(iter_value)) = (iter.key(), iter.value()) {
+            let record: genes::base::Record =
+                common::decode::decode_protobuf(genes_db, "genes", iter_key, iter_value)?;
             // Useful snippet to ensure that all gene records can be converted into serializeable ones.
             // if !genes_info::response::GenesGeneInfoRecord::try_from(record.clone()).is_ok() {
             //     tracing::warn!("Skipping record: {:?}", record.clone().hgnc.unwrap().hgnc_id);
@@ -622,18 +1531,38 @@ fn extract_gene_names(
                     name,
                     alias_symbol,
                     alias_name,
+                    prev_symbol,
+                    date_symbol_changed,
                     ensembl_gene_id,
                     entrez_id,
+                    refseq_accession,
+                    uniprot_ids,
+                    omim_id,
+                    mane_select,
                     ..
                 } = hgnc;
+                let hgnc_id = match hgnc_id.parse() {
+                    Ok(hgnc_id) => hgnc_id,
+                    Err(e) => {
+                        tracing::warn!("skipping gene: {}", e);
+                        iter.next();
+                        continue;
+                    }
+                };
                 result.push(GeneNames {
+                    ensembl_gene_id: parse_gene_id(&hgnc_id, ensembl_gene_id),
+                    ncbi_gene_id: parse_gene_id(&hgnc_id, entrez_id),
                     hgnc_id,
                     symbol,
                     name,
                     alias_symbol,
                     alias_name,
-                    ensembl_gene_id,
-                    ncbi_gene_id: entrez_id,
+                    prev_symbol,
+                    date_symbol_changed,
+                    refseq_accession,
+                    uniprot_ids,
+                    omim_id,
+                    mane_select,
                 })
             }
         }
@@ -643,108 +1572,175 @@ fn extract_gene_names(
     Ok(result)
 }
 
-/// Main entry point for `server rest` sub command.
-pub fn run(args_common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("args_common = {:?}", &args_common);
-    tracing::info!("args = {:?}", &args);
+/// Parse the optional secondary gene identifier `value` of the gene `hgnc_id`.
+///
+/// Malformed identifiers are dropped with a warning rather than entering the lookup index.
+fn parse_gene_id<T>(hgnc_id: &HgncId, value: Option<String>) -> Option<T>
+where
+    T: std::str::FromStr<Err = InvalidGeneId>,
+{
+    value
+        .filter(|value| !value.is_empty())
+        .and_then(|value| match value.parse() {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("ignoring gene identifier of {}: {}", hgnc_id, e);
+                None
+            }
+        })
+}
 
-    if let Some(log::Level::Trace | log::Level::Debug) = args_common.verbose.log_level() {
-        std::env::set_var("RUST_LOG", "debug");
-        env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+/// Build the mapping from HGNC ID, symbol, and Ensembl/NCBI gene ID to index in `gene_names`.
+pub(crate) fn build_name_to_hgnc_idx(gene_names: &[GeneNames]) -> HashMap<String, usize> {
+    let mut result = HashMap::new();
+    for (idx, gene_name) in gene_names.iter().enumerate() {
+        result.insert(gene_name.hgnc_id.to_string(), idx);
+        if let Some(ensembl_gene_id) = gene_name.ensembl_gene_id.as_ref() {
+            result.insert(ensembl_gene_id.to_string(), idx);
+        }
+        if let Some(ncbi_gene_id) = gene_name.ncbi_gene_id.as_ref() {
+            result.insert(ncbi_gene_id.to_string(), idx);
+        }
+        result.insert(gene_name.symbol.clone(), idx);
     }
+    result
+}
 
-    tracing::info!("Opening databases...");
-    let mut data = WebServerData::default();
-    let before_opening = Instant::now();
+/// Build the mapping from previous and alias symbol to indices in `gene_names`.
+///
+/// A symbol may have been used for more than one gene.
+pub(crate) fn build_other_symbol_to_hgnc_idxs(
+    gene_names: &[GeneNames],
+) -> HashMap<String, Vec<usize>> {
+    let mut result: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, gene_name) in gene_names.iter().enumerate() {
+        for symbol in gene_name
+            .prev_symbol
+            .iter()
+            .chain(gene_name.alias_symbol.iter())
+        {
+            let idxs = result.entry(symbol.clone()).or_default();
+            if !idxs.contains(&idx) {
+                idxs.push(idx);
+            }
+        }
+    }
+    result
+}
 
-    if let Some(path_genes) = args.path_genes.as_ref() {
-        tracing::info!("Opening genes database {}...", path_genes);
-        let before_open = Instant::now();
-        let db = open_db(path_genes, "genes")?;
-        tracing::info!(
-            "...done opening genes database in {:?}",
-            before_open.elapsed()
-        );
+/// A database opened at startup by one of the `startup_tasks()`.
+enum Opened {
+    /// The genes database with its gene name indexes.
+    Genes(WithVersionSpec<GeneInfoDb>),
+    /// A ClinVar SV database with its interval trees.
+    ClinvarSv(GenomeRelease, ClinvarsvIntervalTrees),
+    /// A functional element database.
+    Functional(
+        GenomeRelease,
+        rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
+    ),
+    /// A region database with its name and interval trees.
+    Regions(String, GenomeRelease, RegionsIntervalTrees),
+    /// An annotation database with its path.
+    AnnoDb(
+        String,
+        GenomeRelease,
+        DbInfo,
+        WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    ),
+}
 
-        let db_clinvar = if let Some(path_clinvar_genes) = args.path_clinvar_genes.as_ref() {
-            tracing::info!("Opening ClinVar genes database {}...", path_clinvar_genes);
-            let before_open = Instant::now();
-            let clinvar_db = open_db(path_clinvar_genes, "clinvar-genes")?;
-            tracing::info!(
-                "...done opening ClinVar genes database in {:?}",
-                before_open.elapsed()
-            );
-            Some(clinvar_db)
-        } else {
-            None
-        };
+/// Return the tasks for opening the databases given in `args` in parallel, see `startup`.
+///
+/// The tasks are in the order of the arguments such that storing their results one after
+/// the other gives the same `WebServerData` as opening the databases sequentially.
+fn startup_tasks(
+    args: &Args,
+    block_cache: Option<&BlockCache>,
+    mmap_reads: &mmap_reads::MmapReads,
+) -> Vec<startup::Task<Opened>> {
+    let mut tasks = Vec::new();
 
-        tracing::info!("Building gene names...");
-        let before_open = Instant::now();
-        let gene_names = extract_gene_names(&db)?;
-        let name_to_hgnc_idx = {
-            let mut result = HashMap::new();
-            for (idx, gene_name) in gene_names.iter().enumerate() {
-                result.insert(gene_name.hgnc_id.clone(), idx);
-                if let Some(ensembl_gene_id) = gene_name.ensembl_gene_id.as_ref() {
-                    result.insert(ensembl_gene_id.clone(), idx);
-                }
-                if let Some(ncbi_gene_id) = gene_name.ncbi_gene_id.as_ref() {
-                    result.insert(ncbi_gene_id.clone(), idx);
-                }
-                result.insert(gene_name.symbol.clone(), idx);
-            }
-            result
-        };
-        tracing::info!("...done building genes names {:?}", before_open.elapsed());
-        let gene_info_db = GeneInfoDb {
-            db,
-            db_clinvar,
-            gene_names,
-            name_to_hgnc_idx,
-        };
-        let path_buf = PathBuf::from_str(path_genes)?
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("cannot get parent directory of path {}", path_genes))?
-            .join("spec.yaml");
-        let path_buf = path_buf.exists().then_some(path_buf);
-        data.genes = Some(
-            WithVersionSpec::from_data_and_path(gene_info_db, &path_buf).map_err(|e| {
-                anyhow::anyhow!(
-                    "problem loading gene info spec from {}: {}",
-                    if let Some(path_buf) = path_buf.as_ref() {
-                        format!("{}", path_buf.display())
-                    } else {
-                        "None".to_string()
-                    },
-                    e
+    if let Some(path_genes) = args.path_genes.clone() {
+        let path_clinvar_genes = args.path_clinvar_genes.clone();
+        let cache_dir = args.cache_dir.clone();
+        let block_cache = block_cache.cloned();
+        let mmap_reads = mmap_reads.clone();
+        tasks.push(startup::Task::new(
+            format!("genes database {}", path_genes),
+            move || {
+                open_genes(
+                    &path_genes,
+                    path_clinvar_genes.as_deref(),
+                    cache_dir.as_deref(),
+                    block_cache.as_ref(),
+                    &mmap_reads,
                 )
-            })?,
-        );
+                .map(Opened::Genes)
+            },
+        ));
     }
 
-    tracing::info!("Opening ClinVar SV databases...");
-    let before_clinvar_sv = Instant::now();
-    for path_clinvar_sv in &args.path_clinvar_sv {
-        tracing::info!("  - {}", path_clinvar_sv);
-        let (clinvar_sv_db, clinvar_sv_meta) = clinvarsv_query::open_rocksdb(
-            path_clinvar_sv,
-            "clinvar_sv",
-            "meta",
-            "clinvar_sv_by_rcv",
-        )
-        .map_err(|e| anyhow::anyhow!("problem opening RocksDB database: {}", e))?;
-        let genome_release: GenomeRelease = clinvar_sv_meta.genome_release.parse()?;
-        tracing::info!("    => {}", genome_release);
-        let clinvar_sv_interval_trees =
-            ClinvarsvIntervalTrees::with_db(clinvar_sv_db, "clinvar_sv", clinvar_sv_meta)
+    for path_clinvar_sv in args.path_clinvar_sv.iter().cloned() {
+        tasks.push(startup::Task::new(
+            format!("ClinVar SV database {}", path_clinvar_sv),
+            move || {
+                let (clinvar_sv_db, clinvar_sv_meta) = clinvarsv_query::open_rocksdb(
+                    &path_clinvar_sv,
+                    "clinvar_sv",
+                    "meta",
+                    "clinvar_sv_by_rcv",
+                    crate::clinvar_sv::index::CF_NAME,
+                )
+                .map_err(|e| anyhow::anyhow!("problem opening RocksDB database: {}", e))?;
+                let genome_release: GenomeRelease = clinvar_sv_meta.genome_release.parse()?;
+                tracing::info!("  {} => {}", path_clinvar_sv, genome_release);
+                let clinvar_sv_interval_trees = ClinvarsvIntervalTrees::with_db(
+                    clinvar_sv_db,
+                    "clinvar_sv",
+                    crate::clinvar_sv::index::CF_NAME,
+                    clinvar_sv_meta,
+                )
                 .map_err(|e| anyhow::anyhow!("problem building interval trees: {}", e))?;
-        data.clinvar_svs[genome_release] = Some(clinvar_sv_interval_trees);
+                Ok(Opened::ClinvarSv(genome_release, clinvar_sv_interval_trees))
+            },
+        ));
+    }
+
+    for path_functional in args.path_functional.iter().cloned() {
+        let block_cache = block_cache.cloned();
+        let mmap_reads = mmap_reads.enabled("functional");
+        tasks.push(startup::Task::new(
+            format!("functional element database {}", path_functional),
+            move || {
+                let (genome_release, db) =
+                    open_functional_db(&path_functional, block_cache.as_ref(), mmap_reads)?;
+                tracing::info!("  {} => {}", path_functional, genome_release);
+                Ok(Opened::Functional(genome_release, db))
+            },
+        ));
+    }
+
+    for (name, path_regions) in args.path_regions.iter().cloned() {
+        tasks.push(startup::Task::new(
+            format!("region database {}={}", name, path_regions),
+            move || {
+                let (regions_db, regions_meta) =
+                    regions_query::open_rocksdb(&path_regions, "regions", "meta")
+                        .map_err(|e| anyhow::anyhow!("problem opening RocksDB database: {}", e))?;
+                let genome_release: GenomeRelease = regions_meta.genome_release.parse()?;
+                tracing::info!("  {} => {}", path_regions, genome_release);
+                let regions_interval_trees =
+                    RegionsIntervalTrees::with_db(regions_db, "regions", regions_meta)
+                        .map_err(|e| anyhow::anyhow!("problem building interval trees: {}", e))?;
+                Ok(Opened::Regions(
+                    name,
+                    genome_release,
+                    regions_interval_trees,
+                ))
+            },
+        ));
     }
-    tracing::info!(
-        "...done opening ClinVar SV databases in {:?}",
-        before_clinvar_sv.elapsed()
-    );
 
     // Argument lists from the command line with the corresponding database enum value.
     let paths_db_pairs = [
@@ -757,67 +1753,302 @@ pub fn run(args_common: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
         (&args.path_gnomad_exomes, AnnoDb::GnomadExomes),
         (&args.path_gnomad_genomes, AnnoDb::GnomadGenomes),
         (&args.path_helixmtdb, AnnoDb::Helixmtdb),
+        (&args.path_alphamissense, AnnoDb::Alphamissense),
         (&args.path_ucsc_conservation, AnnoDb::UcscConservation),
+        (&args.path_gnomad_coverage, AnnoDb::GnomadCoverage),
     ];
-    // "Unpack" the list of paths to single paths.
-    let path_db_pairs = paths_db_pairs
-        .iter()
-        .map(|(paths, anno_db)| {
-            paths
-                .iter()
-                .map(|path| (path.clone(), *anno_db))
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
-    // Open the corresponding databases in parallel and extract database infos.  Store the
-    // resulting database infos in `data`.
-    path_db_pairs
-        .par_iter()
-        .progress_with(crate::common::cli::progress_bar(path_db_pairs.len()))
-        .map(|(path, anno_db)| -> Result<_, anyhow::Error> {
-            let db = open_db(path, anno_db.cf_name())?;
-            let (genome_release, db_info) = fetch_db_info(&db, *anno_db)?;
-
-            Ok((path, db_info, genome_release, db))
-        })
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .try_for_each(
-            |(path_rocksdb, db_info, genome_release, db)| -> Result<(), anyhow::Error> {
-                let spec_path = PathBuf::from_str(path_rocksdb)?
-                    .parent()
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("cannot get parent directory of path {}", path_rocksdb)
-                    })?
-                    .join("spec.yaml");
-                let spec_path = spec_path.exists().then_some(spec_path);
-                let name = db_info.name;
-                data.db_infos[genome_release][name] = Some(db_info);
-                data.annos[genome_release][name] = Some(
-                    WithVersionSpec::from_data_and_path(db, &spec_path).map_err(|e| {
-                        anyhow::anyhow!(
-                            "problem loading gene info spec from {}: {}",
-                            if let Some(spec_path) = spec_path.as_ref() {
-                                format!("{}", spec_path.display())
-                            } else {
-                                "None".to_string()
-                            },
-                            e
-                        )
-                    })?,
-                );
-
-                Ok(())
-            },
+    for (paths, anno_db) in paths_db_pairs {
+        for path in paths.iter().cloned() {
+            let block_cache = block_cache.cloned();
+            let mmap_reads = mmap_reads.enabled(&anno_db.to_string());
+            tasks.push(startup::Task::new(
+                format!("{} database {}", anno_db, path),
+                move || {
+                    let (genome_release, db_info, db) =
+                        open_anno_db(&path, anno_db, block_cache.as_ref(), mmap_reads)?;
+                    Ok(Opened::AnnoDb(path, genome_release, db_info, db))
+                },
+            ));
+        }
+    }
+
+    tasks
+}
+
+/// Open the genes database at `path_genes` and build its gene name indexes.
+///
+/// * `path_clinvar_genes` - Path to the ClinVar genes database to open along.
+/// * `cache_dir` - Directory for caching the ACMG SF gene list, see `common::cache`.
+/// * `mmap_reads` - Databases to read through memory-mapped files, see `mmap_reads`.
+fn open_genes(
+    path_genes: &str,
+    path_clinvar_genes: Option<&str>,
+    cache_dir: Option<&str>,
+    block_cache: Option<&BlockCache>,
+    mmap_reads: &mmap_reads::MmapReads,
+) -> Result<WithVersionSpec<GeneInfoDb>, anyhow::Error> {
+    let db = open_db_with_optional(
+        path_genes,
+        "genes",
+        &[
+            crate::genes::cli::import::cf_name_pos_index(GenomeRelease::Grch37),
+            crate::genes::cli::import::cf_name_pos_index(GenomeRelease::Grch38),
+            crate::genes::diseases::CF_NAME_BY_DISEASE,
+        ],
+        block_cache,
+        mmap_reads.enabled(health::DB_NAME_GENES),
+    )?;
+
+    let db_clinvar = if let Some(path_clinvar_genes) = path_clinvar_genes {
+        tracing::info!("Opening ClinVar genes database {}...", path_clinvar_genes);
+        let before_open = Instant::now();
+        let clinvar_db = open_db_with_optional(
+            path_clinvar_genes,
+            "clinvar-genes",
+            &[],
+            block_cache,
+            mmap_reads.enabled(health::DB_NAME_CLINVAR_GENES),
         )?;
+        tracing::info!(
+            "...done opening ClinVar genes database in {:?}",
+            before_open.elapsed()
+        );
+        Some(clinvar_db)
+    } else {
+        None
+    };
+
+    tracing::info!("Building gene names...");
+    let before_open = Instant::now();
+    let gene_names = extract_gene_names(&db)?;
+    let name_to_hgnc_idx = build_name_to_hgnc_idx(&gene_names);
+    let other_symbol_to_hgnc_idxs = build_other_symbol_to_hgnc_idxs(&gene_names);
+    let transcript_to_hgnc_idxs = genes_lookup::build_transcript_index(&gene_names);
+    tracing::info!("...done building genes names {:?}", before_open.elapsed());
+    tracing::info!("Building ACMG SF gene list...");
+    let before_acmg_sf = Instant::now();
+    let acmg_sf = if let Some(cache_dir) = cache_dir {
+        let db_versions = common::cache::DbVersions::from([(
+            String::from("genes"),
+            common::cache::db_meta(&db)?,
+        )]);
+        common::cache::ResultCache::new(cache_dir).get_or_compute(
+            &serde_json::json!({"command": "server run", "list": "acmg_sf"}),
+            &db_versions,
+            || genes_acmg_sf::extract_acmg_sf(&db),
+        )?
+    } else {
+        genes_acmg_sf::extract_acmg_sf(&db)?
+    };
+    tracing::info!(
+        "...done building ACMG SF gene list with {} genes in {:?}",
+        acmg_sf.len(),
+        before_acmg_sf.elapsed()
+    );
+    let source_versions = crate::genes::cli::import::read_source_versions(&db)?;
+    tracing::info!("  source versions: {:?}", &source_versions);
+    let gene_info_db = GeneInfoDb {
+        db,
+        db_clinvar,
+        gene_names,
+        name_to_hgnc_idx,
+        other_symbol_to_hgnc_idxs,
+        transcript_to_hgnc_idxs,
+        acmg_sf,
+        source_versions,
+    };
+    let path_buf = PathBuf::from_str(path_genes)?
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("cannot get parent directory of path {}", path_genes))?
+        .join("spec.yaml");
+    let path_buf = path_buf.exists().then_some(path_buf);
+    WithVersionSpec::from_data_and_path(gene_info_db, &path_buf).map_err(|e| {
+        anyhow::anyhow!(
+            "problem loading gene info spec from {}: {}",
+            if let Some(path_buf) = path_buf.as_ref() {
+                format!("{}", path_buf.display())
+            } else {
+                "None".to_string()
+            },
+            e
+        )
+    })
+}
+
+/// Open the annotation database `anno_db` at `path` and return it with its genome release
+/// and database info.
+pub fn open_anno_db(
+    path: &str,
+    anno_db: AnnoDb,
+    block_cache: Option<&BlockCache>,
+    mmap_reads: bool,
+) -> Result<
+    (
+        GenomeRelease,
+        DbInfo,
+        WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+    ),
+    anyhow::Error,
+> {
+    let cf_name = if anno_db == AnnoDb::UcscConservation {
+        crate::cons::per_base::data_cf_name(path, anno_db.cf_name())?
+    } else {
+        anno_db.cf_name().to_string()
+    };
+    let optional_cf_names: &[&str] = if anno_db == AnnoDb::Clinvar {
+        &[crate::clinvar_minimal::DETAILS_CF_NAME]
+    } else {
+        &[]
+    };
+    let db = open_db_with_optional(path, &cf_name, optional_cf_names, block_cache, mmap_reads)?;
+    let (genome_release, db_info) = fetch_db_info(&db, anno_db)?;
+    if let Err(e) = check_first_record(&db, &db_info) {
+        tracing::warn!("{}; requests to this database will fail", e);
+    }
+
+    let spec_path = PathBuf::from_str(path)?
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("cannot get parent directory of path {}", path))?
+        .join("spec.yaml");
+    let spec_path = spec_path.exists().then_some(spec_path);
+    let db = WithVersionSpec::from_data_and_path(db, &spec_path).map_err(|e| {
+        anyhow::anyhow!(
+            "problem loading gene info spec from {}: {}",
+            if let Some(spec_path) = spec_path.as_ref() {
+                format!("{}", spec_path.display())
+            } else {
+                "None".to_string()
+            },
+            e
+        )
+    })?;
+
+    Ok((genome_release, db_info, db))
+}
+
+/// Open the databases given in `args` into `data` with the `startup_tasks()`.
+fn open_databases(args: &Args, data: &mut WebServerData) -> Result<(), anyhow::Error> {
+    let before_opening = Instant::now();
+    let mmap_reads = mmap_reads::MmapReads::parse(&args.rocksdb_mmap_reads)?;
+    let tasks = startup_tasks(args, data.block_cache.as_ref(), &mmap_reads);
+    let timeout = args
+        .startup_timeout_secs
+        .map(std::time::Duration::from_secs);
+    for opened in startup::run_tasks(tasks, timeout)? {
+        match opened {
+            Opened::Genes(genes) => data.genes = Some(genes),
+            Opened::ClinvarSv(genome_release, clinvar_sv_interval_trees) => {
+                data.clinvar_svs[genome_release] = Some(clinvar_sv_interval_trees);
+            }
+            Opened::Functional(genome_release, db) => {
+                if data.functional[genome_release].is_some() {
+                    anyhow::bail!(
+                        "functional element database given more than once for {}",
+                        genome_release
+                    );
+                }
+                data.functional[genome_release] = Some(db);
+            }
+            Opened::Regions(name, genome_release, regions_interval_trees) => {
+                if data.regions[genome_release].contains_key(&name) {
+                    anyhow::bail!(
+                        "region database {} given more than once for {}",
+                        name,
+                        genome_release
+                    );
+                }
+                data.regions[genome_release].insert(name, regions_interval_trees);
+            }
+            Opened::AnnoDb(path_rocksdb, genome_release, db_info, db) => {
+                data.insert_anno_db(genome_release, db_info, db)
+                    .map_err(|e| anyhow::anyhow!("problem with {}: {}", path_rocksdb, e))?;
+            }
+        }
+    }
+    for (genome_release, annos) in data.annos.iter() {
+        if let Some(db) = annos[AnnoDb::Cadd].as_ref() {
+            data.cadd_columns[genome_release] = cadd::CaddColumns::from_db(
+                &db.data,
+                AnnoDb::Cadd.cf_name(),
+                &args.cadd_col_raw,
+                &args.cadd_col_phred,
+            )?;
+        }
+    }
     tracing::info!(
         "...done opening databases in {:?}",
         before_opening.elapsed()
     );
 
+    Ok(())
+}
+
+/// Main entry point for `server rest` sub command.
+pub fn run(args_common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {}", args.sanitized());
+
+    if let Some(log::Level::Trace | log::Level::Debug) = args_common.verbose.log_level() {
+        std::env::set_var("RUST_LOG", "debug");
+        env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    }
+
+    let db_names = health::db_names();
+    for name in &args.readiness_exclude {
+        if !db_names.contains(name) {
+            anyhow::bail!(
+                "invalid database name {:?} for --readiness-exclude, must be one of: {}",
+                name,
+                db_names.join(", ")
+            );
+        }
+    }
+
+    tracing::info!("Opening databases...");
+    let mut data = WebServerData {
+        readiness_exclude: args.readiness_exclude.clone(),
+        variant_cache: std::num::NonZeroUsize::new(args.variant_cache_size).map(|capacity| {
+            variant_cache::VariantCache::new(
+                capacity,
+                std::time::Duration::from_secs(args.variant_cache_ttl_secs),
+            )
+        }),
+        block_cache: args.rocksdb_block_cache_mb.map(BlockCache::new),
+        admin_token: args.admin_token.clone().map(admin::AdminToken::from),
+        enable_admin_raw: args.enable_admin_raw,
+        config: args.sanitized(),
+        range_decoder: range_decode::RangeDecoder::new(args.range_decode_threads)?,
+        circuit_breakers: circuit_breaker::CircuitBreakers::new(circuit_breaker::Config {
+            threshold: args.circuit_breaker_threshold,
+            cooldown: std::time::Duration::from_secs(args.circuit_breaker_cooldown_secs),
+        }),
+        contig_allowlist: args
+            .contig_allowlist
+            .iter()
+            .map(|contig| common::cli::canonicalize(contig))
+            .collect(),
+        audit_log: args
+            .audit_log_path
+            .as_ref()
+            .map(|path| {
+                audit_log::AuditLog::new(audit_log::Config {
+                    path: path.into(),
+                    max_bytes: args.audit_log_max_mb * 1024 * 1024,
+                    keep: args.audit_log_keep,
+                })
+            })
+            .transpose()?,
+        ..Default::default()
+    };
+    if let Some(block_cache) = data.block_cache.as_ref() {
+        tracing::info!(
+            "Using shared RocksDB block cache of {} bytes",
+            block_cache.capacity
+        );
+    }
+    open_databases(args, &mut data)?;
+    versions::check_release_conflicts(&data, args.strict_specs)?;
+
     tracing::info!(
         "Launching server main on http://{}:{} ...",
         args.listen_host.as_str(),
@@ -838,6 +2069,11 @@ pub fn run(args_common: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
         args.listen_host.as_str(),
         args.listen_port
     );
+    tracing::info!(
+        "  try: http://{}:{}/genes/xrefs?q=BRCA1,HGNC:1101&format=tsv",
+        args.listen_host.as_str(),
+        args.listen_port
+    );
     tracing::info!(
         "  try: http://{}:{}/genes/info?hgnc_id=HGNC:12403",
         args.listen_host.as_str(),
@@ -873,3 +2109,221 @@ pub fn run(args_common: &common::cli::Args, args: &Args) -> Result<(), anyhow::E
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::{num::NonZeroUsize, sync::mpsc, time::Duration};
+
+    use actix_web::{http::StatusCode, test, App};
+    use temp_testdir::TempDir;
+
+    use super::*;
+
+    /// Number of worker threads of `concurrent_workers`.
+    const WORKERS: usize = 8;
+    /// Number of rounds of requests per worker of `concurrent_workers`.
+    const ROUNDS: usize = 50;
+
+    /// Request URIs of `concurrent_workers`.
+    const URIS: [&str; 4] = [
+        "/genes/search?q=BRAF",
+        "/genes/search?q=tt",
+        "/annos/variant?genome_release=grch37&chromosome=1&pos=69094&reference=G&alternative=A",
+        "/annos/variant?genome_release=grch37&chromosome=1&pos=861332&reference=G&alternative=A",
+    ];
+
+    /// Call all `URIS` for `ROUNDS` rounds on `data` with an app of its own as an actix
+    /// worker does, and return the response bodies of the first round.
+    fn run_worker(data: Data<WebServerData>) -> Vec<serde_json::Value> {
+        actix_web::rt::System::new().block_on(async move {
+            let app = test::init_service(
+                App::new()
+                    .app_data(data)
+                    .service(genes_search::handle)
+                    .service(annos_variant::handle),
+            )
+            .await;
+            let mut first_round = Vec::new();
+            for round in 0..ROUNDS {
+                for (i, uri) in URIS.iter().enumerate() {
+                    let req = test::TestRequest::get().uri(uri).to_request();
+                    let resp = test::call_service(&app, req).await;
+                    assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+                    let body: serde_json::Value = test::read_body_json(resp).await;
+                    if round == 0 {
+                        first_round.push(body);
+                    } else {
+                        assert_eq!(body, first_round[i], "{}", uri);
+                    }
+                }
+            }
+            first_round
+        })
+    }
+
+    #[test]
+    fn concurrent_workers() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let mut data = annos_variant::test::data_with_alphamissense(&tmp_dir);
+        data.genes = genes_info::test::data_with_genes(&tmp_dir).genes;
+        // Small enough for a single shard to have the workers contend on one lock.
+        data.variant_cache = Some(variant_cache::VariantCache::new(
+            NonZeroUsize::new(16).unwrap(),
+            Duration::from_secs(60),
+        ));
+        let data = Data::new(data);
+        let expected = run_worker(data.clone());
+
+        // Report the results through a channel so that a deadlock fails the test instead of
+        // hanging it.
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..WORKERS {
+            let data = data.clone();
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                let result = std::panic::catch_unwind(|| run_worker(data));
+                let _ = sender.send(result);
+            });
+        }
+        for _ in 0..WORKERS {
+            let result = receiver
+                .recv_timeout(Duration::from_secs(60))
+                .map_err(|e| anyhow::anyhow!("worker did not finish: {}", e))?
+                .map_err(|_| anyhow::anyhow!("worker panicked"))?;
+            assert_eq!(result, expected);
+        }
+
+        let stats = data.variant_cache.as_ref().unwrap().stats();
+        assert_eq!(
+            stats.hits + stats.misses,
+            ((WORKERS + 1) * ROUNDS * 2) as u64
+        );
+        assert!(stats.hits >= stats.misses, "{:?}", stats);
+
+        Ok(())
+    }
+
+    /// Return the arguments for opening the fixture databases with `further` arguments.
+    fn fixture_args(further: &[&str]) -> Result<Args, anyhow::Error> {
+        Ok(Args::try_parse_from(
+            [
+                "run",
+                "--path-dbsnp",
+                "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+                "--path-gnomad-exomes",
+                "tests/gnomad-nuclear/example-exomes-grch37/v2.1/gnomad-exomes.vcf.bgz.db",
+                "--path-ucsc-conservation",
+                "tests/cons/example/tgds.tsv.db",
+                "--path-clinvar-sv",
+                "tests/clinvar-sv/clinvar-sv-grch37.db",
+            ]
+            .iter()
+            .chain(further),
+        )?)
+    }
+
+    #[test]
+    fn open_databases_fixtures() -> Result<(), anyhow::Error> {
+        let args = fixture_args(&["--startup-timeout-secs", "60"])?;
+        let mut data = WebServerData::default();
+
+        open_databases(&args, &mut data)?;
+
+        let opened = AnnoDb::iter()
+            .filter(|anno_db| data.annos[GenomeRelease::Grch37][*anno_db].is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            opened,
+            vec![
+                AnnoDb::Dbsnp,
+                AnnoDb::GnomadExomes,
+                AnnoDb::UcscConservation
+            ]
+        );
+        for anno_db in &opened {
+            assert_eq!(
+                data.db_infos[GenomeRelease::Grch37][*anno_db]
+                    .as_ref()
+                    .map(|db_info| db_info.name),
+                Some(*anno_db)
+            );
+        }
+        assert!(data.annos[GenomeRelease::Grch38]
+            .values()
+            .all(|db| db.is_none()));
+        assert!(data.clinvar_svs[GenomeRelease::Grch37].is_some());
+        assert!(data.clinvar_svs[GenomeRelease::Grch38].is_none());
+
+        Ok(())
+    }
+
+    /// The fixture databases give the same responses with `--rocksdb-mmap-reads`.
+    #[actix_web::test]
+    async fn open_databases_mmap_reads() -> Result<(), anyhow::Error> {
+        let uris = [
+            ("/annos/variant?genome_release=grch37&chromosome=17&pos=41267746&reference=C&alternative=A", "dbsnp"),
+            ("/annos/variant?genome_release=grch37&chromosome=1&pos=55505599&reference=C&alternative=G", "gnomad_exomes"),
+        ];
+        let mut responses = Vec::new();
+        for further in [
+            &[][..],
+            &["--rocksdb-mmap-reads", "true"][..],
+            &["--rocksdb-mmap-reads", "true,dbsnp=false"][..],
+        ] {
+            let args = fixture_args(further)?;
+            let mut data = WebServerData::default();
+            open_databases(&args, &mut data)?;
+            let app = test::init_service(
+                App::new()
+                    .app_data(actix_web::web::Data::new(data))
+                    .service(annos_variant::handle),
+            )
+            .await;
+
+            let mut bodies = Vec::new();
+            for (uri, db_name) in uris {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let resp = test::call_service(&app, req).await;
+                assert_eq!(resp.status(), StatusCode::OK, "{}", uri);
+                let body: serde_json::Value = test::read_body_json(resp).await;
+                assert!(body["result"][db_name].is_object(), "{}", body);
+                bodies.push(body);
+            }
+            responses.push(bodies);
+        }
+
+        assert_eq!(responses[1], responses[0]);
+        assert_eq!(responses[2], responses[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_databases_invalid_mmap_reads() -> Result<(), anyhow::Error> {
+        let args = fixture_args(&["--rocksdb-mmap-reads", "dbsnp=yes"])?;
+        let mut data = WebServerData::default();
+
+        let err = open_databases(&args, &mut data).unwrap_err();
+
+        assert!(err.to_string().contains("must be true or false"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_databases_twice() -> Result<(), anyhow::Error> {
+        let args = fixture_args(&["--path-dbsnp", "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db"])?;
+        let mut data = WebServerData::default();
+
+        let err = open_databases(&args, &mut data).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("more than one dbsnp database given for"),
+            "{}",
+            err
+        );
+
+        Ok(())
+    }
+}
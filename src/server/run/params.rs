@@ -0,0 +1,231 @@
+//! Validation of request parameters shared by the endpoints.
+//!
+//! The endpoints deserialize their query parameters as strings and convert them into typed
+//! structs with the functions here such that invalid values are reported with the name and
+//! value of the offending parameter, see `CustomError::invalid_param()`.
+
+use crate::common::{cli, cli::GenomeRelease, keys};
+
+use super::error::CustomError;
+
+/// Return the value of the required parameter `name`.
+pub fn required<'a>(name: &str, value: &'a Option<String>) -> Result<&'a str, CustomError> {
+    value
+        .as_deref()
+        .ok_or_else(|| CustomError::invalid_param(name, None, "parameter is required"))
+}
+
+/// Parse the value of the parameter `name` into `T`.
+pub fn parse<T>(name: &str, value: &str) -> Result<T, CustomError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e: T::Err| CustomError::invalid_param(name, Some(value), &e.to_string()))
+}
+
+/// Parse the genome release of the parameter `name`.
+pub fn genome_release(name: &str, value: &str) -> Result<GenomeRelease, CustomError> {
+    value
+        .parse()
+        .map_err(|_| CustomError::invalid_param(name, Some(value), "must be one of grch37, grch38"))
+}
+
+/// Parse the 1-based position of the parameter `name`.
+pub fn position(name: &str, value: &str) -> Result<u32, CustomError> {
+    let pos: u32 = parse(name, value)?;
+    if pos < 1 {
+        return Err(CustomError::invalid_param(
+            name,
+            Some(value),
+            "must be at least 1",
+        ));
+    }
+    Ok(pos)
+}
+
+/// Check that the 1-based position `value` of the parameter `name` is within `chromosome`.
+///
+/// Positions on non-canonical chromosomes are not checked.
+pub fn position_in_contig(
+    name: &str,
+    value: u32,
+    genome_release: GenomeRelease,
+    chromosome: &str,
+) -> Result<(), CustomError> {
+    cli::check_position(genome_release, chromosome, value as usize)
+        .map_err(|e| CustomError::invalid_param(name, Some(&value.to_string()), &e.to_string()))
+}
+
+/// Check the chromosome name of the parameter `name`, with or without `chr` prefix.
+pub fn chromosome(name: &str, value: &str) -> Result<String, CustomError> {
+    let stripped = value.strip_prefix("chr").unwrap_or(value);
+    if stripped.is_empty()
+        || stripped.len() > 2
+        || !stripped.bytes().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(CustomError::invalid_param(
+            name,
+            Some(value),
+            "not a valid chromosome name",
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Normalize the allele of the parameter `name` to uppercase `[ACGTN]+`.
+pub fn allele(name: &str, value: &str) -> Result<String, CustomError> {
+    if value.is_empty() {
+        return Err(CustomError::invalid_param(
+            name,
+            Some(value),
+            "must not be empty",
+        ));
+    }
+    keys::normalize_allele(value, false)
+        .map_err(|e| CustomError::invalid_param(name, Some(value), &e.to_string()))
+}
+
+/// Check the `YYYY-MM` year and month of the parameter `name`, e.g., a ClinVar release.
+pub fn year_month(name: &str, value: &str) -> Result<String, CustomError> {
+    let is_valid = match value.split_once('-') {
+        Some((year, month)) => {
+            year.len() == 4
+                && month.len() == 2
+                && year
+                    .bytes()
+                    .chain(month.bytes())
+                    .all(|c| c.is_ascii_digit())
+                && matches!(month.parse::<u8>(), Ok(1..=12))
+        }
+        None => false,
+    };
+    if !is_valid {
+        return Err(CustomError::invalid_param(
+            name,
+            Some(value),
+            "must be given as YYYY-MM",
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Check the mtDNA haplogroup name of the parameter `name`, e.g., `L3` or `HV`.
+pub fn haplogroup(name: &str, value: &str) -> Result<String, CustomError> {
+    if value.is_empty() || value.len() > 8 || !value.bytes().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(CustomError::invalid_param(
+            name,
+            Some(value),
+            "not a valid haplogroup name",
+        ));
+    }
+    Ok(value.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("0", "must be at least 1")]
+    #[case("-1", "invalid digit found in string")]
+    #[case("abc", "invalid digit found in string")]
+    fn position_invalid(#[case] value: &str, #[case] message: &str) {
+        let err = position("pos", value).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{:?}",
+                format!("invalid value {:?} for parameter pos: {}", value, message)
+            )
+        );
+        assert_eq!(err.param().map(|param| param.name.as_str()), Some("pos"));
+        assert_eq!(
+            err.param().and_then(|param| param.value.as_deref()),
+            Some(value)
+        );
+    }
+
+    #[test]
+    fn position_in_contig_bounds() {
+        assert!(position_in_contig("pos", 248_956_422, GenomeRelease::Grch38, "chr1").is_ok());
+        let err =
+            position_in_contig("pos", 2_491_553_345, GenomeRelease::Grch38, "chr1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{:?}",
+                "invalid value \"2491553345\" for parameter pos: position 2,491,553,345 \
+                exceeds length of chr1 (248,956,422) for GRCh38"
+            )
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("X-")]
+    #[case("chr")]
+    #[case("chr123")]
+    fn chromosome_invalid(#[case] value: &str) {
+        assert!(chromosome("chromosome", value).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("ACGU")]
+    #[case("R")]
+    fn allele_invalid(#[case] value: &str) {
+        let err = allele("reference", value).unwrap_err();
+
+        assert_eq!(
+            err.param().map(|param| param.name.as_str()),
+            Some("reference")
+        );
+    }
+
+    #[test]
+    fn allele_normalized() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            allele("reference", "acgtn").map_err(|e| anyhow::anyhow!("{}", e))?,
+            "ACGTN"
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("2024")]
+    #[case("2024-1")]
+    #[case("2024-00")]
+    #[case("2024-13")]
+    #[case("24-01")]
+    #[case("2024-+1")]
+    #[case("2024-01-07")]
+    fn year_month_invalid(#[case] value: &str) {
+        assert!(year_month("clinvar_release", value).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("L3'4")]
+    #[case("H,U")]
+    fn haplogroup_invalid(#[case] value: &str) {
+        assert!(haplogroup("haplogroup", value).is_err());
+    }
+
+    #[test]
+    fn required_missing() {
+        let err = required("chromosome", &None).unwrap_err();
+
+        assert_eq!(
+            err.param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: "chromosome".into(),
+                value: None,
+            })
+        );
+    }
+}
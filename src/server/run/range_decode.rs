@@ -0,0 +1,258 @@
+//! Parallel decoding of the records of `/annos/range` queries.
+//!
+//! Large range queries spend most of their time decoding the protocol buffers and converting
+//! them to JSON.  The keys are still scanned sequentially but the raw values are then decoded
+//! in batches of `BATCH_SIZE` on a thread pool shared by all requests, keeping the key order.
+//! The pool has `--range-decode-threads` threads, which bounds the parallelism per request
+//! and overall.  A semaphore limits the number of requests decoding on the pool at the same
+//! time so that concurrent requests do not queue up behind each other; requests that do not
+//! get a permit decode sequentially on their own thread as before.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+/// Number of records to decode in one batch.
+pub const BATCH_SIZE: usize = 1024;
+
+/// Decoder of range query records, sequential without a thread pool.
+#[derive(Debug, Default)]
+pub struct RangeDecoder {
+    /// Thread pool for decoding, `None` for sequential decoding.
+    pool: Option<rayon::ThreadPool>,
+    /// Number of requests that may currently decode on the pool.
+    permits: AtomicUsize,
+}
+
+/// Permit to decode on the pool, returned to the semaphore on drop.
+struct Permit<'a> {
+    /// The semaphore's permit counter.
+    permits: &'a AtomicUsize,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.permits.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl RangeDecoder {
+    /// Create a new decoder with `threads` decoding threads, sequential for up to one thread.
+    ///
+    /// The number of concurrent requests decoding on the pool is limited such that their
+    /// threads do not exceed the available cores, but is at least one.
+    pub fn new(threads: usize) -> Result<Self, anyhow::Error> {
+        if threads <= 1 {
+            return Ok(Self::default());
+        }
+        let cores = std::thread::available_parallelism()
+            .map(|cores| cores.get())
+            .unwrap_or(1);
+        Self::with_permits(threads, (cores / threads).max(1))
+    }
+
+    /// Create a new decoder with `threads` decoding threads and `permits` concurrent requests.
+    fn with_permits(threads: usize, permits: usize) -> Result<Self, anyhow::Error> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|idx| format!("range-decode-{}", idx))
+            .build()
+            .map_err(|e| anyhow::anyhow!("problem creating range decode thread pool: {}", e))?;
+        Ok(Self {
+            pool: Some(pool),
+            permits: AtomicUsize::new(permits),
+        })
+    }
+
+    /// Return the number of decoding threads, `1` for sequential decoding.
+    pub fn threads(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map(|pool| pool.current_num_threads())
+            .unwrap_or(1)
+    }
+
+    /// Try to obtain a permit for decoding on the pool.
+    fn try_acquire(&self) -> Option<Permit<'_>> {
+        self.permits
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |permits| {
+                permits.checked_sub(1)
+            })
+            .ok()
+            .map(|_| Permit {
+                permits: &self.permits,
+            })
+    }
+
+    /// Decode the `(key, value)` pairs in `raw` with `decode`, keeping their order.
+    ///
+    /// Decoding is parallel if there is a pool, more than one batch of records, and a permit.
+    pub fn decode<T, E, F>(&self, raw: &[(Vec<u8>, Vec<u8>)], decode: F) -> Result<Vec<T>, E>
+    where
+        T: Send,
+        E: Send,
+        F: Fn(&[u8], &[u8]) -> Result<T, E> + Sync,
+    {
+        if let Some(pool) = self.pool.as_ref().filter(|_| raw.len() > BATCH_SIZE) {
+            if let Some(_permit) = self.try_acquire() {
+                let batches = pool.install(|| {
+                    raw.par_chunks(BATCH_SIZE)
+                        .map(|batch| {
+                            batch
+                                .iter()
+                                .map(|(key, value)| decode(key, value))
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })?;
+                return Ok(batches.into_iter().flatten().collect());
+            }
+        }
+
+        raw.iter().map(|(key, value)| decode(key, value)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Raw records with increasing keys and values.
+    fn raw_records(n: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+        (0..n)
+            .map(|i| {
+                (
+                    (i as u32).to_be_bytes().to_vec(),
+                    format!("{}", i).into_bytes(),
+                )
+            })
+            .collect()
+    }
+
+    fn decode_record(key: &[u8], value: &[u8]) -> Result<(u32, String), anyhow::Error> {
+        Ok((
+            u32::from_be_bytes(key.try_into()?),
+            String::from_utf8(value.to_vec())?,
+        ))
+    }
+
+    #[rstest::rstest]
+    #[case(0)]
+    #[case(10)]
+    #[case(BATCH_SIZE)]
+    #[case(BATCH_SIZE * 10 + 7)]
+    fn decode_keeps_order(#[case] n: usize) -> Result<(), anyhow::Error> {
+        let raw = raw_records(n);
+        let sequential = RangeDecoder::default().decode(&raw, decode_record)?;
+        let parallel = RangeDecoder::with_permits(4, 1)?.decode(&raw, decode_record)?;
+
+        assert_eq!(sequential.len(), n);
+        assert_eq!(parallel, sequential);
+        assert!(sequential.windows(2).all(|w| w[0].0 < w[1].0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_error() -> Result<(), anyhow::Error> {
+        let mut raw = raw_records(BATCH_SIZE * 4);
+        raw[BATCH_SIZE * 3].0 = vec![0];
+
+        assert!(RangeDecoder::default().decode(&raw, decode_record).is_err());
+        assert!(RangeDecoder::with_permits(4, 1)?
+            .decode(&raw, decode_record)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn permits() -> Result<(), anyhow::Error> {
+        let decoder = RangeDecoder::with_permits(2, 1)?;
+        assert_eq!(decoder.threads(), 2);
+        assert_eq!(RangeDecoder::new(1)?.threads(), 1);
+
+        // Without a permit, the records are decoded sequentially with the same result.
+        let permit = decoder.try_acquire();
+        assert!(permit.is_some());
+        assert!(decoder.try_acquire().is_none());
+        let raw = raw_records(BATCH_SIZE * 2 + 1);
+        assert_eq!(
+            decoder.decode(&raw, decode_record)?,
+            RangeDecoder::default().decode(&raw, decode_record)?
+        );
+        drop(permit);
+        assert!(decoder.try_acquire().is_some());
+
+        Ok(())
+    }
+
+    /// Write a HelixMtDb-like database with one record at each of the first `n` positions of
+    /// chromosome 1 and return it.
+    fn write_db(
+        path: &std::path::Path,
+        n: i32,
+    ) -> Result<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>, anyhow::Error> {
+        use prost::Message as _;
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db: rocksdb::DBWithThreadMode<rocksdb::MultiThreaded> =
+            rocksdb::DB::open_cf(&options, path, ["meta", "data"])?;
+        let cf_data = db.cf_handle("data").unwrap();
+        let mut batch = rocksdb::WriteBatch::default();
+        for pos in 1..=n {
+            let record = crate::helixmtdb::pbs::Record {
+                chrom: String::from("1"),
+                pos,
+                ref_allele: String::from("A"),
+                alt_allele: String::from("G"),
+                num_total: 196_554,
+                num_het: pos % 7,
+                num_hom: pos % 11,
+                feature_type: String::from("protein_coding"),
+                gene_name: format!("GENE{}", pos / 1000),
+            };
+            let key: Vec<u8> = crate::common::keys::Var::from("1", pos, "A", "G").into();
+            batch.put_cf(&cf_data, key, record.encode_to_vec());
+        }
+        db.write(batch)?;
+        drop(cf_data);
+        Ok(db)
+    }
+
+    #[test]
+    fn fetch_parallel_same_as_sequential() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let db = write_db(tmp_dir.as_ref(), BATCH_SIZE as i32 * 5 + 3)?;
+        let decoder = RangeDecoder::with_permits(4, 1)?;
+        let fetch = |decoder: &RangeDecoder, start: i32, stop: i32| {
+            crate::server::run::fetch::fetch_pos_protobuf_json_with_decoder::<
+                crate::helixmtdb::pbs::Record,
+            >(
+                &db,
+                "data",
+                crate::common::keys::Pos::from("1", start),
+                crate::common::keys::Pos::from("1", stop),
+                decoder,
+            )
+            .map_err(|e| anyhow::anyhow!("{:?}", e))
+        };
+
+        for (start, stop) in [(1, 100), (10, 4000), (1, 1_000_000)] {
+            let sequential = fetch(&RangeDecoder::default(), start, stop)?;
+            let parallel = fetch(&decoder, start, stop)?;
+
+            assert_eq!(parallel, sequential, "{}-{}", start, stop);
+            let records = sequential.as_ref().and_then(|v| v.as_array()).unwrap();
+            assert_eq!(records[0]["pos"], start);
+            assert_eq!(
+                records.len() as i32,
+                stop.min(BATCH_SIZE as i32 * 5 + 3) - start + 1
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+//! Numeric column filters of `/annos/range`.
+//!
+//! A filter has the form `<database>.<column><operator><number>`, e.g.,
+//! `dbscsnv.ada_score>0.6` or `cadd.PHRED>=20`, with one of the operators `<`, `<=`, `>`,
+//! `>=`, `==`, and `!=`.  Filters are supported for the TSV databases (CADD, dbNSFP, and
+//! dbscSNV) and are checked against the database schema: the column must exist and be
+//! numeric.  A record of a database is kept if it passes any of the filters of the database,
+//! records with a null value in the column never pass.
+
+use crate::tsv::schema::{ColumnType, FileSchema};
+
+use super::{error::CustomError, AnnoDb};
+
+/// Comparison operator of a `Filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString, strum::EnumIter)]
+pub enum Operator {
+    /// Less than.
+    #[strum(serialize = "<")]
+    Lt,
+    /// Less than or equal.
+    #[strum(serialize = "<=")]
+    Le,
+    /// Greater than.
+    #[strum(serialize = ">")]
+    Gt,
+    /// Greater than or equal.
+    #[strum(serialize = ">=")]
+    Ge,
+    /// Equal.
+    #[strum(serialize = "==")]
+    Eq,
+    /// Not equal.
+    #[strum(serialize = "!=")]
+    Ne,
+}
+
+impl Operator {
+    /// Apply the operator to `lhs` and `rhs`.
+    pub fn apply(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Operator::Lt => lhs < rhs,
+            Operator::Le => lhs <= rhs,
+            Operator::Gt => lhs > rhs,
+            Operator::Ge => lhs >= rhs,
+            Operator::Eq => lhs == rhs,
+            Operator::Ne => lhs != rhs,
+        }
+    }
+}
+
+/// A filter on a numeric column of a TSV database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    /// The database of the column.
+    pub anno_db: AnnoDb,
+    /// Name of the column in the database schema.
+    pub column: String,
+    /// The comparison operator.
+    pub op: Operator,
+    /// The number to compare with.
+    pub value: f64,
+}
+
+impl std::str::FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let op_start = s
+            .find(['<', '>', '=', '!'])
+            .ok_or_else(|| anyhow::anyhow!("missing operator, expected one of {}", operators()))?;
+        let op_end = s[op_start..]
+            .find(|c| !matches!(c, '<' | '>' | '=' | '!'))
+            .map(|len| op_start + len)
+            .unwrap_or(s.len());
+        let (path, op, value) = (&s[..op_start], &s[op_start..op_end], &s[op_end..]);
+
+        let (db_name, column) = path
+            .split_once('.')
+            .ok_or_else(|| anyhow::anyhow!("column must be given as <database>.<column>"))?;
+        let anno_db = match db_name.parse() {
+            Ok(anno_db @ (AnnoDb::Cadd | AnnoDb::Dbnsfp | AnnoDb::Dbscsnv)) => anno_db,
+            _ => anyhow::bail!(
+                "unknown database {:?}, filters are supported for cadd, dbnsfp, dbscsnv",
+                db_name
+            ),
+        };
+        if column.is_empty() || column.contains(char::is_whitespace) {
+            anyhow::bail!("invalid column name {:?}", column);
+        }
+        let op = op.parse().map_err(|_| {
+            anyhow::anyhow!("invalid operator {:?}, expected one of {}", op, operators())
+        })?;
+        let value = value
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| anyhow::anyhow!("invalid number {:?}", value))?;
+
+        Ok(Self {
+            anno_db,
+            column: column.to_string(),
+            op,
+            value,
+        })
+    }
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}{}{}",
+            self.anno_db, self.column, self.op, self.value
+        )
+    }
+}
+
+/// Return the supported operators for error messages.
+fn operators() -> String {
+    use strum::IntoEnumIterator;
+
+    Operator::iter()
+        .map(|op| op.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A `Filter` resolved against the schema of its database.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnFilter {
+    /// Index of the column in the schema.
+    pub idx: usize,
+    /// The comparison operator.
+    pub op: Operator,
+    /// The number to compare with.
+    pub value: f64,
+}
+
+impl ColumnFilter {
+    /// Return whether the decoded `values` of one record pass the filter.
+    pub fn matches(&self, values: &[serde_json::Value]) -> bool {
+        values
+            .get(self.idx)
+            .and_then(serde_json::Value::as_f64)
+            .is_some_and(|lhs| self.op.apply(lhs, self.value))
+    }
+}
+
+impl Filter {
+    /// Resolve the column of the filter in `schema`, which must be numeric.
+    pub fn resolve(&self, schema: &FileSchema) -> Result<ColumnFilter, anyhow::Error> {
+        let idx = schema
+            .columns
+            .iter()
+            .position(|column| column.name == self.column)
+            .ok_or_else(|| {
+                anyhow::anyhow!("column {:?} not found in {}", self.column, self.anno_db)
+            })?;
+        match schema.columns[idx].typ {
+            ColumnType::Float | ColumnType::Integer => Ok(ColumnFilter {
+                idx,
+                op: self.op,
+                value: self.value,
+            }),
+            typ => anyhow::bail!(
+                "column {:?} of {} has type {:?} rather than a number",
+                self.column,
+                self.anno_db,
+                typ
+            ),
+        }
+    }
+}
+
+/// Resolve all `filters` in `schema`, reporting errors for the `filter` parameter.
+pub fn resolve_all(
+    filters: &[Filter],
+    schema: &FileSchema,
+) -> Result<Vec<ColumnFilter>, CustomError> {
+    filters
+        .iter()
+        .map(|filter| {
+            filter.resolve(schema).map_err(|e| {
+                CustomError::invalid_param("filter", Some(&filter.to_string()), &e.to_string())
+            })
+        })
+        .collect()
+}
+
+/// Return whether a record with the decoded `values` is kept, i.e., there are no `filters` or
+/// the record passes any of them.
+pub fn keep(filters: &[ColumnFilter], values: &[serde_json::Value]) -> bool {
+    filters.is_empty() || filters.iter().any(|filter| filter.matches(values))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tsv::schema::ColumnSchema;
+
+    #[rstest::rstest]
+    #[case(
+        "dbscsnv.ada_score>0.6",
+        AnnoDb::Dbscsnv,
+        "ada_score",
+        Operator::Gt,
+        0.6
+    )]
+    #[case("cadd.PHRED>=20", AnnoDb::Cadd, "PHRED", Operator::Ge, 20.0)]
+    #[case("dbnsfp.GERP++_RS<-1.5", AnnoDb::Dbnsfp, "GERP++_RS", Operator::Lt, -1.5)]
+    #[case(
+        "dbnsfp.SIFT_score<=0.05",
+        AnnoDb::Dbnsfp,
+        "SIFT_score",
+        Operator::Le,
+        0.05
+    )]
+    #[case("cadd.PHRED==1e1", AnnoDb::Cadd, "PHRED", Operator::Eq, 10.0)]
+    #[case("cadd.PHRED!=0", AnnoDb::Cadd, "PHRED", Operator::Ne, 0.0)]
+    fn parse_valid(
+        #[case] s: &str,
+        #[case] anno_db: AnnoDb,
+        #[case] column: &str,
+        #[case] op: Operator,
+        #[case] value: f64,
+    ) -> Result<(), anyhow::Error> {
+        let filter: Filter = s.parse()?;
+
+        assert_eq!(
+            filter,
+            Filter {
+                anno_db,
+                column: column.to_string(),
+                op,
+                value,
+            }
+        );
+        assert_eq!(filter.to_string().parse::<Filter>()?, filter);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::no_database("ada_score>0.6", "<database>.<column>")]
+    #[case::unknown_database("spliceai.delta>0.5", "unknown database \"spliceai\"")]
+    #[case::not_tsv("dbsnp.rs_id>1", "unknown database \"dbsnp\"")]
+    #[case::empty_column("dbscsnv.>0.6", "invalid column name \"\"")]
+    #[case::space_in_column("dbscsnv.ada score>0.6", "invalid column name \"ada score\"")]
+    #[case::no_operator("dbscsnv.ada_score", "missing operator")]
+    #[case::arrow("dbscsnv.ada_score=>0.6", "invalid operator \"=>\"")]
+    #[case::diamond("dbscsnv.ada_score<>0.6", "invalid operator \"<>\"")]
+    #[case::single_equals("dbscsnv.ada_score=0.6", "invalid operator \"=\"")]
+    #[case::no_number("dbscsnv.ada_score>", "invalid number \"\"")]
+    #[case::not_a_number("dbscsnv.ada_score>high", "invalid number \"high\"")]
+    #[case::nan("dbscsnv.ada_score>NaN", "invalid number \"NaN\"")]
+    fn parse_invalid(#[case] s: &str, #[case] expected: &str) {
+        let err = s.parse::<Filter>().unwrap_err().to_string();
+
+        assert!(err.contains(expected), "{}", err);
+    }
+
+    fn schema() -> FileSchema {
+        FileSchema::from(
+            vec![
+                ColumnSchema::from("CHROM", ColumnType::String),
+                ColumnSchema::from("POS", ColumnType::Integer),
+                ColumnSchema::from("ada_score", ColumnType::Float),
+                ColumnSchema::from("rf_score", ColumnType::Float),
+                ColumnSchema::from("empty", ColumnType::Unknown),
+            ],
+            Vec::new(),
+        )
+    }
+
+    #[rstest::rstest]
+    #[case("dbscsnv.ada_score>0.6", Ok(2))]
+    #[case("dbscsnv.POS<=1000", Ok(1))]
+    #[case(
+        "dbscsnv.spliceai>0.5",
+        Err("column \"spliceai\" not found in dbscsnv")
+    )]
+    #[case("dbscsnv.CHROM==1", Err("column \"CHROM\" of dbscsnv has type String"))]
+    #[case("dbscsnv.empty>0", Err("column \"empty\" of dbscsnv has type Unknown"))]
+    fn resolve(
+        #[case] s: &str,
+        #[case] expected: Result<usize, &str>,
+    ) -> Result<(), anyhow::Error> {
+        let result = s.parse::<Filter>()?.resolve(&schema());
+
+        match expected {
+            Ok(idx) => assert_eq!(result?.idx, idx),
+            Err(message) => {
+                let err = result.unwrap_err().to_string();
+                assert!(err.contains(message), "{}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_all_reports_param() {
+        let filters = vec![
+            "dbscsnv.ada_score>0.6".parse().unwrap(),
+            "dbscsnv.CHROM>1".parse().unwrap(),
+        ];
+
+        let err = resolve_all(&filters, &schema()).unwrap_err();
+
+        assert_eq!(
+            err.param(),
+            Some(&crate::server::run::error::InvalidParam {
+                name: "filter".into(),
+                value: Some("dbscsnv.CHROM>1".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn keep_any() -> Result<(), anyhow::Error> {
+        let schema = schema();
+        let filters = resolve_all(
+            &[
+                "dbscsnv.ada_score>0.6".parse()?,
+                "dbscsnv.rf_score>0.5".parse()?,
+            ],
+            &schema,
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let values = |ada_score: serde_json::Value, rf_score: serde_json::Value| {
+            vec![
+                serde_json::json!("1"),
+                serde_json::json!(1000),
+                ada_score,
+                rf_score,
+                serde_json::Value::Null,
+            ]
+        };
+
+        assert!(keep(&[], &values(0.0.into(), 0.0.into())));
+        assert!(keep(&filters, &values(0.9.into(), 0.0.into())));
+        assert!(keep(&filters, &values(0.0.into(), 0.9.into())));
+        assert!(!keep(&filters, &values(0.6.into(), 0.5.into())));
+        assert!(!keep(
+            &filters,
+            &values(serde_json::Value::Null, serde_json::Value::Null)
+        ));
+
+        Ok(())
+    }
+}
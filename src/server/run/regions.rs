@@ -0,0 +1,175 @@
+//! Custom regions overlapping the queries of `/annos/variant` and `/annos/range`.
+//!
+//! Region databases are imported from BED files with `regions import` and given to the
+//! server with `--path-regions NAME=PATH`.  As for the ClinVar SVs, their interval trees are
+//! built at startup.  The overlapping regions are returned by database name.
+
+use std::collections::BTreeMap;
+
+use crate::{common::spdi, regions::cli::query::IntervalTrees};
+
+use super::error::CustomError;
+
+/// A region overlapping the query.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct RegionsInterval {
+    /// Chromosome name.
+    pub chromosome: String,
+    /// 1-based start position.
+    pub start: u32,
+    /// 1-based, inclusive stop position.
+    pub stop: u32,
+    /// Name of the region, if any.
+    pub name: Option<String>,
+    /// Score of the region, if any.
+    pub score: Option<f32>,
+}
+
+impl From<crate::regions::cli::query::Record> for RegionsInterval {
+    fn from(record: crate::regions::cli::query::Record) -> Self {
+        use crate::regions::cli::query::Record;
+
+        let tree_data = record.tree_data();
+        let (name, score) = match record {
+            Record::ClingenDosage(region) => (Some(region.isca_id), None),
+            Record::Bed(region) => (region.name, region.score),
+        };
+        Self {
+            chromosome: tree_data.chromosome,
+            start: tree_data.start + 1,
+            stop: tree_data.stop,
+            name,
+            score,
+        }
+    }
+}
+
+/// Parse a named region database `NAME=PATH`.
+pub fn parse_named_path(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+            Ok((name.to_string(), path.to_string()))
+        }
+        _ => Err(format!(
+            "invalid region database {:?}, expected NAME=PATH",
+            s
+        )),
+    }
+}
+
+/// Serialize the `NAME=PATH` pairs of `parse_named_path` as such strings.
+pub fn serialize_named_paths<S>(
+    values: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_seq(
+        values
+            .iter()
+            .map(|(name, path)| format!("{}={}", name, path)),
+    )
+}
+
+/// Query the region databases `trees` for the regions overlapping the 1-based, inclusive
+/// interval from `start` to `stop`.
+///
+/// Databases without overlapping regions are omitted from the result.
+pub(crate) fn query_overlapping(
+    trees: &BTreeMap<String, IntervalTrees>,
+    chromosome: &str,
+    start: u32,
+    stop: u32,
+) -> Result<BTreeMap<String, Vec<RegionsInterval>>, CustomError> {
+    let spdi_range = spdi::Range {
+        sequence: chromosome.replace("chr", "").to_string(),
+        start: start as i32,
+        end: stop as i32,
+    };
+    let mut result = BTreeMap::new();
+    for (name, trees) in trees {
+        let records = trees.query(&spdi_range).map_err(|e| {
+            CustomError::new(anyhow::anyhow!(
+                "problem querying regions database {}: {}",
+                name,
+                e
+            ))
+        })?;
+        if !records.is_empty() {
+            result.insert(
+                name.clone(),
+                records.into_iter().map(RegionsInterval::from).collect(),
+            );
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use temp_testdir::TempDir;
+
+    use super::*;
+
+    /// Import the example BED file and return the interval trees as for `--path-regions
+    /// example=PATH`.
+    pub(crate) fn example_regions(tmp_dir: &TempDir) -> BTreeMap<String, IntervalTrees> {
+        let path_rocksdb = crate::regions::cli::query::test::import_bed(tmp_dir).unwrap();
+        let (db, meta) =
+            crate::regions::cli::query::open_rocksdb(&path_rocksdb, "regions", "meta").unwrap();
+        BTreeMap::from([(
+            String::from("example"),
+            IntervalTrees::with_db(db, "regions", meta).unwrap(),
+        )])
+    }
+
+    #[test]
+    fn parse_named_path_values() {
+        assert_eq!(
+            parse_named_path("segdups=/data/segdups"),
+            Ok((String::from("segdups"), String::from("/data/segdups")))
+        );
+        assert!(parse_named_path("segdups").is_err());
+        assert!(parse_named_path("=/data/segdups").is_err());
+        assert!(parse_named_path("segdups=").is_err());
+    }
+
+    #[test]
+    fn query_overlapping_example() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let trees = example_regions(&tmp_dir);
+
+        let result = query_overlapping(&trees, "chr1", 1990, 2010)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        assert_eq!(
+            result,
+            BTreeMap::from([(
+                String::from("example"),
+                vec![
+                    RegionsInterval {
+                        chromosome: String::from("1"),
+                        start: 1001,
+                        stop: 2000,
+                        name: Some(String::from("segdup_1")),
+                        score: Some(0.98),
+                    },
+                    RegionsInterval {
+                        chromosome: String::from("1"),
+                        start: 1501,
+                        stop: 3000,
+                        name: Some(String::from("blacklist")),
+                        score: None,
+                    },
+                ]
+            )])
+        );
+        assert!(query_overlapping(&trees, "1", 3001, 3010)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?
+            .is_empty());
+
+        Ok(())
+    }
+}
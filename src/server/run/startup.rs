@@ -0,0 +1,236 @@
+//! Parallel opening of the databases at server startup.
+//!
+//! The genes database with its gene name indexes, each ClinVar SV database with its interval
+//! trees, each functional element and region database, and each annotation database are
+//! opened by independent tasks.  `run_tasks` runs the tasks on a thread pool with at most one
+//! thread per core, logs their time, and returns the results in the order of the tasks so that
+//! `run()` stores them in `WebServerData` exactly as when opening one after the other.  With
+//! `--startup-timeout-secs`, startup fails with the names of the unfinished tasks instead of
+//! hanging, e.g., on a stale network mount.
+
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+/// A startup task with its name for logging, e.g., `genes database /data/genes`.
+pub struct Task<T> {
+    /// Name of the task.
+    pub name: String,
+    /// Function to run.
+    pub run: Box<dyn FnOnce() -> Result<T, anyhow::Error> + Send>,
+}
+
+impl<T> Task<T> {
+    /// Create a new task named `name` running `run`.
+    pub fn new(
+        name: impl Into<String>,
+        run: impl FnOnce() -> Result<T, anyhow::Error> + Send + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            run: Box::new(run),
+        }
+    }
+}
+
+/// Run `tasks` in parallel and return their results in the order of `tasks`.
+///
+/// The tasks are spawned onto a thread pool with at most one thread per available core rather
+/// than into a `rayon::scope()`, which would wait for all tasks and defeat the `timeout`.
+/// Fails with the error of the first failing task or, with `timeout`, if not all tasks are
+/// done in time.  The unfinished tasks are left running in this case, the caller is expected
+/// to exit.
+pub fn run_tasks<T: Send + 'static>(
+    tasks: Vec<Task<T>>,
+    timeout: Option<Duration>,
+) -> Result<Vec<T>, anyhow::Error> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let names = tasks
+        .iter()
+        .map(|task| task.name.clone())
+        .collect::<Vec<_>>();
+    // The tracing subscriber is only set for the current thread, see `main()`.
+    let dispatch = tracing::dispatcher::get_default(Clone::clone);
+
+    let cores = std::thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1);
+    // A panicking task drops its sender and is reported below, so only keep the pool alive.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(cores.min(tasks.len()).max(1))
+        .thread_name(|idx| format!("startup-{}", idx))
+        .panic_handler(|_| ())
+        .build()
+        .map_err(|e| anyhow::anyhow!("problem creating startup thread pool: {}", e))?;
+
+    let (sender, receiver) = mpsc::channel();
+    for (idx, task) in tasks.into_iter().enumerate() {
+        let sender = sender.clone();
+        let dispatch = dispatch.clone();
+        pool.spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || {
+                tracing::info!("Opening {}...", task.name);
+                let before = Instant::now();
+                let result = (task.run)();
+                if result.is_ok() {
+                    tracing::info!("...done opening {} in {:?}", task.name, before.elapsed());
+                }
+                // The receiver is gone if another task failed first.
+                let _ = sender.send((idx, result));
+            })
+        });
+    }
+    drop(sender);
+
+    let mut results = (0..names.len()).map(|_| None).collect::<Vec<_>>();
+    for _ in 0..names.len() {
+        let received = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let (idx, result) = received.map_err(|e| {
+            let pending = names
+                .iter()
+                .zip(results.iter())
+                .filter(|(_, result)| result.is_none())
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            match e {
+                RecvTimeoutError::Timeout => anyhow::anyhow!(
+                    "startup did not finish within --startup-timeout-secs, still opening: {}",
+                    pending
+                ),
+                RecvTimeoutError::Disconnected => {
+                    anyhow::anyhow!("startup task panicked while opening one of: {}", pending)
+                }
+            }
+        })?;
+        results[idx] = Some(result?);
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn run_tasks_in_order() -> Result<(), anyhow::Error> {
+        // The first task finishes last.
+        let tasks = (0..4)
+            .map(|i| {
+                Task::new(format!("task {}", i), move || {
+                    std::thread::sleep(Duration::from_millis(50 * (4 - i)));
+                    Ok(i)
+                })
+            })
+            .collect();
+
+        assert_eq!(run_tasks(tasks, None)?, vec![0, 1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_tasks_in_parallel() -> Result<(), anyhow::Error> {
+        let tasks = (0..8)
+            .map(|i| {
+                Task::new(format!("task {}", i), move || {
+                    std::thread::sleep(Duration::from_millis(200));
+                    Ok(i)
+                })
+            })
+            .collect();
+
+        let before = Instant::now();
+        run_tasks(tasks, None)?;
+        let threads = std::thread::available_parallelism()?.get().min(8);
+        assert!(
+            before.elapsed() < Duration::from_millis(200 * (8_u64.div_ceil(threads as u64) + 1))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_tasks_bounded() -> Result<(), anyhow::Error> {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let cores = std::thread::available_parallelism()?.get();
+        let tasks = (0..(2 * cores + 2))
+            .map(|i| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                Task::new(format!("task {}", i), move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(i)
+                })
+            })
+            .collect();
+
+        assert_eq!(run_tasks(tasks, None)?.len(), 2 * cores + 2);
+        assert!(max_running.load(Ordering::SeqCst) <= cores);
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_tasks_error() {
+        let tasks = vec![
+            Task::new("good", || Ok(1)),
+            Task::new("bad", || Err(anyhow::anyhow!("could not open bad"))),
+        ];
+
+        let err = run_tasks(tasks, None).unwrap_err();
+
+        assert_eq!(err.to_string(), "could not open bad");
+    }
+
+    #[test]
+    fn run_tasks_timeout() {
+        let tasks = vec![
+            Task::new("fast", || Ok(1)),
+            Task::new("hanging", || {
+                std::thread::sleep(Duration::from_secs(60));
+                Ok(2)
+            }),
+        ];
+
+        let before = Instant::now();
+        let err = run_tasks(tasks, Some(Duration::from_millis(100))).unwrap_err();
+
+        assert!(before.elapsed() < Duration::from_secs(10));
+        assert_eq!(
+            err.to_string(),
+            "startup did not finish within --startup-timeout-secs, still opening: hanging"
+        );
+    }
+
+    #[test]
+    fn run_tasks_panic() {
+        let tasks = vec![
+            Task::new("fast", || Ok(1)),
+            Task::new("panicking", || panic!("oops")),
+        ];
+
+        let err = run_tasks(tasks, Some(Duration::from_secs(10))).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "startup task panicked while opening one of: panicking"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Response cache for `/annos/variant`.
+//!
+//! Some variants are requested very often with identical responses.  The cache stores the
+//! serialized JSON response body per query so that cache hits skip all RocksDB access.
+//! Entries are evicted in LRU order once the capacity is reached and expire after the TTL.
+//!
+//! The cache is shared by all actix workers.  To keep the workers from serializing on one
+//! lock, the entries are split into shards by the hash of the key, each behind its own
+//! `Mutex`.  A lock is only held for a single LRU operation and never while another shard's
+//! lock is held, so there is no lock ordering to get wrong.  LRU order and the capacity are
+//! per shard; small caches use a single shard and thus behave as one exact LRU cache.
+
+use std::{
+    hash::{BuildHasher, Hash, Hasher},
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use super::annos_variant::SeqvarsAnnosQuery;
+
+/// Key of a cached response, built from all query parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VariantCacheKey {
+    /// Genome release specification.
+    pub genome_release: String,
+    /// Chromosome name.
+    pub chromosome: String,
+    /// 1-based position.
+    pub pos: u32,
+    /// Reference allele bases.
+    pub reference: String,
+    /// Alternative allele bases.
+    pub alternative: String,
+    /// ClinVar release, if any.
+    pub clinvar_release: Option<String>,
+    /// mtDNA haplogroup, if any.
+    pub haplogroup: Option<String>,
+    /// Whether only the presence in each database is returned.
+    pub presence_only: bool,
+    /// Whether the ClinVar submission-level details are returned.
+    pub clinvar_details: bool,
+    /// Maximal window of the nearest genes and functional elements with `context=true`.
+    pub context_max_window: Option<u32>,
+}
+
+impl From<&SeqvarsAnnosQuery> for VariantCacheKey {
+    fn from(value: &SeqvarsAnnosQuery) -> Self {
+        Self {
+            genome_release: value.genome_release.clone(),
+            chromosome: value.chromosome.clone(),
+            pos: value.pos,
+            reference: value.reference.clone(),
+            alternative: value.alternative.clone(),
+            clinvar_release: value.clinvar_release.clone(),
+            haplogroup: value.haplogroup.clone(),
+            presence_only: false,
+            clinvar_details: false,
+            context_max_window: None,
+        }
+    }
+}
+
+/// Hit and miss counters of the cache.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariantCacheStats {
+    /// Number of lookups answered from the cache.
+    pub hits: u64,
+    /// Number of lookups not answered from the cache.
+    pub misses: u64,
+}
+
+impl VariantCacheStats {
+    /// Return the ratio of hits among all lookups (zero without lookups).
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Smallest capacity of a shard, caches below twice this size use a single shard.
+const MIN_SHARD_CAPACITY: usize = 1024;
+
+/// Largest number of shards.
+const MAX_SHARDS: usize = 16;
+
+/// One shard of the cached response bodies with their insertion time.
+type Shard = Mutex<lru::LruCache<VariantCacheKey, (Instant, String)>>;
+
+/// LRU cache of serialized responses with a time to live.
+#[derive(Debug)]
+pub struct VariantCache {
+    /// Cached response bodies with their insertion time, sharded by key hash.
+    shards: Vec<Shard>,
+    /// Hasher for selecting the shard of a key.
+    hasher: std::collections::hash_map::RandomState,
+    /// Time to live of the entries.
+    ttl: Duration,
+    /// Number of cache hits.
+    hits: AtomicU64,
+    /// Number of cache misses.
+    misses: AtomicU64,
+}
+
+impl VariantCache {
+    /// Create a new cache with the given capacity and TTL.
+    ///
+    /// The capacity is split evenly among the shards.
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        let num_shards = (capacity.get() / MIN_SHARD_CAPACITY).clamp(1, MAX_SHARDS);
+        let shard_capacity =
+            NonZeroUsize::new(capacity.get().div_ceil(num_shards)).expect("non-zero capacity");
+        Self {
+            shards: (0..num_shards)
+                .map(|_| Mutex::new(lru::LruCache::new(shard_capacity)))
+                .collect(),
+            hasher: Default::default(),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the shard of `key`.
+    fn shard(&self, key: &VariantCacheKey) -> &Shard {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// Return the cached body for `key` if present and not expired.
+    pub fn get(&self, key: &VariantCacheKey) -> Option<String> {
+        let mut entries = self.shard(key).lock().expect("variant cache lock poisoned");
+        let result = match entries.get(key) {
+            Some((inserted, body)) if inserted.elapsed() < self.ttl => Some(body.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        if result.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Store the response `body` for `key`.
+    pub fn insert(&self, key: VariantCacheKey, body: String) {
+        self.shard(&key)
+            .lock()
+            .expect("variant cache lock poisoned")
+            .put(key, (Instant::now(), body));
+    }
+
+    /// Remove all entries, e.g., after the databases changed.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().expect("variant cache lock poisoned").clear();
+        }
+    }
+
+    /// Return the current hit and miss counters.
+    pub fn stats(&self) -> VariantCacheStats {
+        VariantCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(pos: u32) -> VariantCacheKey {
+        VariantCacheKey {
+            genome_release: "grch37".into(),
+            chromosome: "1".into(),
+            pos,
+            reference: "A".into(),
+            alternative: "G".into(),
+            clinvar_release: None,
+            haplogroup: None,
+            presence_only: false,
+            clinvar_details: false,
+            context_max_window: None,
+        }
+    }
+
+    fn cache(capacity: usize, ttl: Duration) -> VariantCache {
+        VariantCache::new(NonZeroUsize::new(capacity).unwrap(), ttl)
+    }
+
+    #[test]
+    fn get_insert_stats() {
+        let cache = cache(10, Duration::from_secs(60));
+
+        assert_eq!(cache.get(&key(1)), None);
+        cache.insert(key(1), "{}".into());
+        assert_eq!(cache.get(&key(1)), Some("{}".into()));
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache(2, Duration::from_secs(60));
+
+        cache.insert(key(1), "1".into());
+        cache.insert(key(2), "2".into());
+        assert!(cache.get(&key(1)).is_some());
+        cache.insert(key(3), "3".into());
+
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+    }
+
+    #[test]
+    fn sharded_capacity() {
+        assert_eq!(cache(2, Duration::from_secs(60)).shards.len(), 1);
+        assert_eq!(cache(4096, Duration::from_secs(60)).shards.len(), 4);
+        assert_eq!(cache(1_000_000, Duration::from_secs(60)).shards.len(), 16);
+
+        let cache = cache(4096, Duration::from_secs(60));
+        for pos in 0..4096 {
+            cache.insert(key(pos), pos.to_string());
+        }
+        for pos in 0..4096 {
+            if let Some(body) = cache.get(&key(pos)) {
+                assert_eq!(body, pos.to_string());
+            }
+        }
+        let len = cache
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum::<usize>();
+        assert!(len > 0 && len <= 4096, "{}", len);
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = cache(10, Duration::ZERO);
+
+        cache.insert(key(1), "1".into());
+
+        assert!(cache.get(&key(1)).is_none());
+    }
+
+    #[test]
+    fn clear() {
+        let cache = cache(10, Duration::from_secs(60));
+
+        cache.insert(key(1), "1".into());
+        cache.clear();
+
+        assert!(cache.get(&key(1)).is_none());
+    }
+}
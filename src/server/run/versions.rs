@@ -2,13 +2,13 @@
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
-    Responder,
+    web::{self, Data, Path},
+    HttpRequest, HttpResponse,
 };
 
 use crate::common::cli::GenomeRelease;
 
-use super::{error::CustomError, AnnoDb, WebServerData};
+use super::{error::CustomError, etag::ETag, AnnoDb, WebServerData, WithVersionSpec};
 
 /// Code for deserializing the version `spec.yaml` files.
 pub mod schema {
@@ -160,6 +160,15 @@ pub struct VersionsVersionSpec {
     pub created_from: Vec<VersionsCreatedFrom>,
 }
 
+/// Genome releases of a database that differ between `meta:genome-release` and `spec.yaml`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct VersionsReleaseConflict {
+    /// The genome release from `meta:genome-release`, which the server uses.
+    pub meta: GenomeRelease,
+    /// The genome release from `x-genome-release` in `spec.yaml`.
+    pub spec: String,
+}
+
 /// Version information for one database.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
@@ -168,6 +177,77 @@ pub struct VersionsAnnotationInfo {
     pub database: AnnoDb,
     /// Version information of the database.
     pub version_spec: Option<VersionsVersionSpec>,
+    /// Genome releases of `meta:genome-release` and `spec.yaml` if they differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_conflict: Option<VersionsReleaseConflict>,
+}
+
+/// Compare the genome release `meta` from `meta:genome-release` of a database with `spec`
+/// from its `spec.yaml`.
+///
+/// Returns the conflict if both are given and differ; the release names are compared
+/// case-insensitively such that `GRCh37` in `spec.yaml` agrees with `grch37` in meta.
+pub fn release_conflict(
+    meta: Option<GenomeRelease>,
+    spec: Option<&str>,
+) -> Option<VersionsReleaseConflict> {
+    let meta = meta?;
+    let spec = spec.map(str::trim).filter(|spec| !spec.is_empty())?;
+    if spec.eq_ignore_ascii_case(&meta.to_string()) {
+        None
+    } else {
+        Some(VersionsReleaseConflict {
+            meta,
+            spec: spec.to_string(),
+        })
+    }
+}
+
+/// Return the release conflict of the annotation database `db` loaded for `release`.
+fn db_release_conflict(
+    release: GenomeRelease,
+    db: &WithVersionSpec<rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>>,
+) -> Option<VersionsReleaseConflict> {
+    release_conflict(
+        Some(release),
+        db.version_spec
+            .as_ref()
+            .and_then(|version_spec| version_spec.genome_release.as_deref()),
+    )
+}
+
+/// Check the genome releases of all annotation databases in `data` against their `spec.yaml`.
+///
+/// Conflicts are logged as warnings, with `strict` the first conflict is an error.
+pub fn check_release_conflicts(data: &WebServerData, strict: bool) -> Result<(), anyhow::Error> {
+    let mut conflicts = Vec::new();
+    for (release, anno_dbs) in data.annos.iter() {
+        let dbs = anno_dbs.values().flatten().chain(
+            data.additional_annos[release]
+                .values()
+                .flatten()
+                .map(|additional| &additional.db),
+        );
+        for db in dbs {
+            if let Some(conflict) = db_release_conflict(release, db) {
+                tracing::warn!(
+                    "database {} has genome release {} in meta but {} in spec.yaml; using {}",
+                    db.data.path().display(),
+                    conflict.meta,
+                    conflict.spec,
+                    conflict.meta
+                );
+                conflicts.push(db.data.path().display().to_string());
+            }
+        }
+    }
+    if strict && !conflicts.is_empty() {
+        anyhow::bail!(
+            "genome release in meta and spec.yaml differ for {} (--strict-specs)",
+            conflicts.join(", ")
+        );
+    }
+    Ok(())
 }
 
 /// Version information for databases in a given release.
@@ -178,6 +258,9 @@ pub struct VersionsPerRelease {
     /// Version information of annotation databases.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub version_infos: Vec<VersionsAnnotationInfo>,
+    /// Releases of the loaded ClinVar databases as `YYYY-MM`, newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clinvar_releases: Vec<String>,
 }
 
 /// Response for `handle()`.
@@ -186,6 +269,9 @@ pub struct VersionsInfoResponse {
     /// Version information of the genes.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub genes: Option<VersionsVersionSpec>,
+    /// Versions of the data sources of the genes database, by source name.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub genes_source_versions: std::collections::BTreeMap<String, String>,
     /// Version information of annotation databases per release.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub seqvars: Vec<VersionsPerRelease>,
@@ -198,6 +284,7 @@ pub struct VersionsInfoResponse {
     params(VersionsInfoQuery),
     responses(
         (status = 200, description = "Version information.", body = VersionsInfoResponse),
+        (status = 304, description = "Not modified since the response with the ETag in `If-None-Match`."),
         (status = 500, description = "Internal server error.", body = CustomError)
     )
 )]
@@ -206,7 +293,8 @@ async fn handle(
     data: Data<WebServerData>,
     _path: Path<()>,
     _query: web::Query<VersionsInfoQuery>,
-) -> actix_web::Result<impl Responder, CustomError> {
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse, CustomError> {
     let mut seqvars = Vec::new();
     for (release, anno_dbs) in data.as_ref().annos.iter() {
         let mut version_infos = Vec::new();
@@ -215,12 +303,21 @@ async fn handle(
                 version_infos.push(VersionsAnnotationInfo {
                     database: anno_db,
                     version_spec: with_version.version_spec.clone().map(Into::into),
+                    release_conflict: db_release_conflict(release, with_version),
+                });
+            }
+            for additional in &data.as_ref().additional_annos[release][anno_db] {
+                version_infos.push(VersionsAnnotationInfo {
+                    database: anno_db,
+                    version_spec: additional.db.version_spec.clone().map(Into::into),
+                    release_conflict: db_release_conflict(release, &additional.db),
                 });
             }
         }
         seqvars.push(VersionsPerRelease {
             release,
             version_infos,
+            clinvar_releases: data.as_ref().clinvar_releases(release),
         });
     }
 
@@ -230,14 +327,145 @@ async fn handle(
             .genes
             .as_ref()
             .and_then(|genes| genes.version_spec.clone().map(Into::into)),
+        genes_source_versions: data
+            .as_ref()
+            .genes
+            .as_ref()
+            .map(|genes| genes.data.source_versions.clone())
+            .unwrap_or_default(),
         seqvars,
     };
 
-    Ok(Json(response))
+    // The response consists of the database versions, so it is its own fingerprint.
+    let etag = ETag::new(&response, &());
+    Ok(etag
+        .not_modified(&req)
+        .unwrap_or_else(|| etag.json(response)))
 }
 
 #[cfg(test)]
 pub mod test {
+    use actix_web::{test, web::Data, App};
+
+    use crate::common::cli::GenomeRelease;
+
+    #[actix_web::test]
+    async fn genes_source_versions() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let data = crate::server::run::genes_info::test::data_with_genes(&tmp_dir);
+        let app =
+            test::init_service(App::new().app_data(Data::new(data)).service(super::handle)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/versionsInfo")
+            .to_request();
+        let response: super::VersionsInfoResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(
+            response.genes_source_versions,
+            std::collections::BTreeMap::from([
+                (String::from("hgnc"), String::from("2024-01-02")),
+                (String::from("omim"), String::from("2023-12-18")),
+                (String::from("panelapp"), String::from("2024-01-05")),
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn etag_not_modified() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let data = crate::server::run::genes_info::test::data_with_genes(&tmp_dir);
+        let app =
+            test::init_service(App::new().app_data(Data::new(data)).service(super::handle)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/versionsInfo")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let etag = resp.headers().get(actix_web::http::header::ETAG).cloned();
+        assert!(etag.is_some());
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/versionsInfo")
+            .insert_header((
+                actix_web::http::header::IF_NONE_MATCH,
+                etag.clone().unwrap(),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            resp.headers().get(actix_web::http::header::ETAG),
+            etag.as_ref()
+        );
+        assert!(test::read_body(resp).await.is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case::missing_spec(Some(GenomeRelease::Grch37), None, None)]
+    #[case::empty_spec(Some(GenomeRelease::Grch37), Some(""), None)]
+    #[case::missing_meta(None, Some("grch38"), None)]
+    #[case::agreement(Some(GenomeRelease::Grch37), Some("grch37"), None)]
+    #[case::agreement_case(Some(GenomeRelease::Grch38), Some("GRCh38"), None)]
+    #[case::conflict(Some(GenomeRelease::Grch37), Some("grch38"), Some("grch38"))]
+    #[case::conflict_invalid(Some(GenomeRelease::Grch38), Some("hg19"), Some("hg19"))]
+    fn release_conflict(
+        #[case] meta: Option<GenomeRelease>,
+        #[case] spec: Option<&str>,
+        #[case] expected_spec: Option<&str>,
+    ) {
+        let expected = expected_spec.map(|spec| super::VersionsReleaseConflict {
+            meta: meta.unwrap(),
+            spec: spec.to_string(),
+        });
+
+        assert_eq!(super::release_conflict(meta, spec), expected);
+    }
+
+    #[actix_web::test]
+    async fn release_conflict_reported() -> Result<(), anyhow::Error> {
+        // The dbSNP fixture is GRCh37 and has no `spec.yaml`, so use one declaring GRCh38.
+        let (genome_release, db_info, mut db) = crate::server::run::open_anno_db(
+            "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+            crate::server::run::AnnoDb::Dbsnp,
+            None,
+            false,
+        )?;
+        db.version_spec = Some(super::schema::VersionSpec {
+            genome_release: Some(String::from("grch38")),
+            ..super::schema::VersionSpec::from_path(
+                "tests/server/annonars/helixmtdb-grch38-20200327+0.33.0/spec.yaml",
+            )?
+        });
+        let mut data = crate::server::run::WebServerData::default();
+        data.insert_anno_db(genome_release, db_info, db)?;
+
+        assert!(super::check_release_conflicts(&data, false).is_ok());
+        assert!(super::check_release_conflicts(&data, true).is_err());
+
+        let app =
+            test::init_service(App::new().app_data(Data::new(data)).service(super::handle)).await;
+        let req = test::TestRequest::get()
+            .uri("/api/v1/versionsInfo")
+            .to_request();
+        let response: super::VersionsInfoResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(
+            response.seqvars[0].version_infos[0].release_conflict,
+            Some(super::VersionsReleaseConflict {
+                meta: GenomeRelease::Grch37,
+                spec: String::from("grch38"),
+            })
+        );
+
+        Ok(())
+    }
+
     #[rstest::rstest]
     #[case("alphamissense-grch37-1+0.33.0")]
     #[case("alphamissense-grch38-1+0.33.0")]
@@ -1,6 +1,10 @@
 //! Implementation of `tsv import`.
 
-use std::sync::Arc;
+use std::io::BufRead;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
 use clap::Parser;
 use indicatif::ParallelProgressIterator;
@@ -11,13 +15,137 @@ use crate::{common, tsv};
 pub mod no_tbi;
 pub mod par_tbi;
 
+/// How to handle a variant key that is already present in the database.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum OnDuplicate {
+    /// Overwrite the existing value; the result depends on the processing order.
+    #[default]
+    Overwrite,
+    /// Fail the import.
+    Error,
+    /// Fill the null columns of the existing value from the new value.
+    MergeColumns,
+    /// Keep the existing value.
+    FirstWins,
+}
+
+/// How to handle malformed lines, e.g., with wrong column count or unparseable numbers.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ErrorBehavior {
+    /// Fail the import on the first malformed line.
+    #[default]
+    Abort,
+    /// Skip all malformed lines.
+    Skip,
+    /// Skip malformed lines but fail at the end if there are more than the given number.
+    SkipWithLimit(usize),
+}
+
+impl std::fmt::Display for ErrorBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorBehavior::Abort => write!(f, "abort"),
+            ErrorBehavior::Skip => write!(f, "skip"),
+            ErrorBehavior::SkipWithLimit(limit) => write!(f, "skip-with-limit={}", limit),
+        }
+    }
+}
+
+impl std::str::FromStr for ErrorBehavior {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abort" => Ok(ErrorBehavior::Abort),
+            "skip" => Ok(ErrorBehavior::Skip),
+            _ => match s.strip_prefix("skip-with-limit=") {
+                Some(limit) => Ok(ErrorBehavior::SkipWithLimit(limit.parse().map_err(
+                    |e| anyhow::anyhow!("invalid limit in error behavior {:?}: {}", s, e),
+                )?)),
+                None => anyhow::bail!(
+                    "invalid error behavior {:?}, must be abort, skip, or skip-with-limit=N",
+                    s
+                ),
+            },
+        }
+    }
+}
+
+/// Error for a line that cannot be decoded with the schema.
+#[derive(Debug, thiserror::Error)]
+#[error("malformed line: {0}")]
+pub struct MalformedLine(#[source] crate::error::Error);
+
+/// Thread-safe accounting of the malformed lines skipped according to `ErrorBehavior`.
+#[derive(Debug)]
+pub struct SkippedLines {
+    /// The configured behavior.
+    behavior: ErrorBehavior,
+    /// Number of skipped lines so far.
+    count: AtomicUsize,
+}
+
+impl SkippedLines {
+    /// Create new accounting for `behavior`.
+    pub fn new(behavior: ErrorBehavior) -> Self {
+        Self {
+            behavior,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Handle the `result` of processing the line at `location`, e.g., `path:line_no`.
+    ///
+    /// A `MalformedLine` error is counted and logged unless the behavior is to abort, all other
+    /// errors are returned with the location.
+    pub fn handle(
+        &self,
+        location: &str,
+        result: Result<(), anyhow::Error>,
+    ) -> Result<(), anyhow::Error> {
+        match result {
+            Err(e) if e.is::<MalformedLine>() && self.behavior != ErrorBehavior::Abort => {
+                self.count.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("skipping line {}: {}", location, e);
+                Ok(())
+            }
+            Err(e) => Err(crate::ErrorCategory::of(&e)
+                .error(format!("problem with line {}: {}", location, e))),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Return the number of skipped lines.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Fail if more lines than allowed by the behavior were skipped.
+    pub fn finish(&self) -> Result<(), anyhow::Error> {
+        let count = self.count();
+        if count > 0 {
+            tracing::warn!("skipped {} malformed line(s)", count);
+        }
+        match self.behavior {
+            ErrorBehavior::SkipWithLimit(limit) if count > limit => {
+                Err(crate::ErrorCategory::InputParse.error(format!(
+                    "skipped {} malformed line(s), more than the limit of {}",
+                    count, limit
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Command line arguments for `tsv import` sub command.
 #[derive(Parser, Debug, Clone)]
 #[command(about = "import tsv data into rocksdb", long_about = None)]
 pub struct Args {
-    /// Genome build to use in the build.
+    /// Genome build to use in the build, `auto` detects it from the coordinates.
     #[arg(long, value_enum)]
-    pub genome_release: common::cli::GenomeRelease,
+    pub genome_release: common::assembly_detect::GenomeReleaseArg,
     /// Path to input TSV file(s).
     #[arg(long, required = true)]
     pub path_in_tsv: Vec<String>,
@@ -38,6 +166,10 @@ pub struct Args {
     /// Number of rows for schema inference.
     #[arg(long, default_value = "1000")]
     pub inference_row_count: usize,
+    /// Fail the import if the schemas inferred from the input files differ in their column
+    /// types or columns, see `meta:schema-drift`.
+    #[arg(long)]
+    pub strict_schema: bool,
     /// Number of rows to skip.
     #[arg(long, default_value = "0")]
     pub skip_row_count: usize,
@@ -50,6 +182,13 @@ pub struct Args {
     /// Optional path to RocksDB WAL directory.
     #[arg(long)]
     pub path_wal_dir: Option<String>,
+    /// Compact each chromosome after its TBI-parallel import instead of running one full
+    /// compaction at the end (lowers peak disk usage, see `common::compaction`).
+    #[arg(long)]
+    pub incremental_compaction: bool,
+    /// Write prefix bloom filters for faster point lookups (see `common::prefix_bloom`).
+    #[arg(long)]
+    pub prefix_bloom: bool,
 
     /// Name of colum containing the chromosome.
     #[arg(long)]
@@ -70,30 +209,109 @@ pub struct Args {
     /// Whether to add the default set of NULL values (NA, ., -).
     #[arg(long)]
     pub add_default_null_values: bool,
+    /// Allow IUPAC ambiguity codes in alleles besides ACGTN (other alleles are skipped).
+    #[arg(long)]
+    pub allow_iupac: bool,
+    /// How to handle variants occurring more than once in the input files.  Except for
+    /// `overwrite`, the input files are processed one after the other in the given order.
+    #[arg(long, value_enum, default_value_t = OnDuplicate::Overwrite)]
+    pub on_duplicate: OnDuplicate,
+    /// How to handle malformed lines: `abort`, `skip`, or `skip-with-limit=N` to fail at the
+    /// end if more than `N` lines were skipped.  The number of skipped lines is written to
+    /// `meta:import-skipped-lines`.
+    #[arg(long, default_value_t = ErrorBehavior::Abort)]
+    pub error_behavior: ErrorBehavior,
+    /// Deterministic sampling of the imported variants (see `common::sample`).
+    #[command(flatten)]
+    pub sample: common::sample::ArgsSample,
+    /// Writing of `spec.yaml` after the import.
+    #[command(flatten)]
+    pub spec: common::spec::ArgsSpec,
+    /// Only validate inputs and output path, do not write the database.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Merge the columns of the `existing` and `new` lines.
+///
+/// Null columns of `existing` are filled from `new`, non-null columns of `existing` are kept.
+fn merge_columns(
+    ctx: &tsv::coding::Context,
+    existing: &str,
+    new: &[serde_json::Value],
+) -> Result<String, anyhow::Error> {
+    let merged = ctx
+        .line_to_values(existing)?
+        .into_iter()
+        .zip(new.iter())
+        .map(|(existing, new)| {
+            if existing.is_null() {
+                new.clone()
+            } else {
+                existing
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(ctx.values_to_line(&merged)?)
 }
 
 /// Process a single TSV line.
+///
+/// Lines that cannot be decoded with the schema yield a `MalformedLine` error, lines of
+/// variants not selected by `sampler` are skipped, as are lines with invalid alleles which
+/// are counted in `invalid_alleles`.
+#[allow(clippy::too_many_arguments)]
 pub fn process_tsv_line(
     line: &str,
     ctx: &tsv::coding::Context,
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     cf_data: &std::sync::Arc<rocksdb::BoundColumnFamily>,
+    allow_iupac: bool,
+    invalid_alleles: &mut common::keys::InvalidAlleles,
+    on_duplicate: OnDuplicate,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
-    let values = ctx.line_to_values(line)?;
-    let values = values.iter().collect::<Vec<_>>();
-    let var = ctx.values_to_var(&values)?;
+    let values = ctx.line_to_values(line).map_err(MalformedLine)?;
+    let var = ctx
+        .values_to_var(&values.iter().collect::<Vec<_>>())
+        .map_err(MalformedLine)?;
 
     if let Some(var) = var.as_ref() {
+        let Some(var) = invalid_alleles.normalize(var, allow_iupac) else {
+            return Ok(());
+        };
         let key: Vec<u8> = var.clone().into();
+        if !sampler.keep(&key) {
+            return Ok(());
+        }
+
+        let existing = match on_duplicate {
+            OnDuplicate::Overwrite => None,
+            _ => db.get_cf(cf_data, &key)?,
+        };
+        let value = match (existing, on_duplicate) {
+            (None, _) | (_, OnDuplicate::Overwrite) => line.to_string(),
+            (Some(_), OnDuplicate::Error) => anyhow::bail!(
+                "duplicate variant {}:{}:{}:{}",
+                &var.chrom,
+                var.pos,
+                &var.reference,
+                &var.alternative
+            ),
+            (Some(_), OnDuplicate::FirstWins) => return Ok(()),
+            (Some(existing), OnDuplicate::MergeColumns) => {
+                merge_columns(ctx, std::str::from_utf8(&existing)?, &values)?
+            }
+        };
 
         tracing::trace!(
             "putting for var = {:?}, key = {:?}, value = {:?}",
             &var,
             &key,
-            &line.as_bytes()
+            &value
         );
 
-        db.put_cf(cf_data, key, line.as_bytes())?;
+        db.put_cf(cf_data, key, value.as_bytes())?;
     } else {
         tracing::trace!("skipping line: {:?}", &line);
     }
@@ -104,21 +322,14 @@ pub fn process_tsv_line(
 /// Default null values.
 const DEFAULT_NULL_VALUES: &[&str] = &["NA", ".", "-"];
 
-/// Implementation of `tsv import` sub command.
-pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
-    tracing::info!("Starting 'tsv import' command");
-    tracing::info!("common = {:#?}", &common);
-    tracing::info!("args = {:#?}", &args);
-
-    // Infer the schema from the input TSV file.
-    tracing::info!("Inferring schema from TSV ...");
-    let before_inference = std::time::Instant::now();
+/// Build the schema inference configuration from the command line arguments.
+fn build_infer_config(args: &Args) -> tsv::schema::infer::Config {
     let mut null_values = Vec::new();
     if args.add_default_null_values {
         null_values.extend_from_slice(DEFAULT_NULL_VALUES);
     }
     args.null_values.iter().for_each(|s| null_values.push(s));
-    let infer_config = tsv::schema::infer::Config {
+    tsv::schema::infer::Config {
         null_values: null_values
             .iter()
             .map(|s| s.to_string())
@@ -130,33 +341,181 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         col_ref: args.col_ref.clone(),
         col_alt: args.col_alt.clone(),
         ..Default::default()
+    }
+}
+
+/// Infer the schema from all input TSV files, starting with the optional JSON schema.
+///
+/// Also returns the schema drift of each file compared to the merged schema of the files
+/// before it.  Drift is logged as a warning and fails the inference with `--strict-schema`.
+fn infer_schema(
+    args: &Args,
+    infer_config: &tsv::schema::infer::Config,
+) -> Result<(tsv::schema::FileSchema, Vec<tsv::schema::FileDrift>), anyhow::Error> {
+    let infer_ctx = tsv::schema::infer::Context::new(infer_config);
+    let mut schema: Option<tsv::schema::FileSchema> = if let Some(path_json_schema) =
+        &args.path_schema_json
+    {
+        tracing::info!("  loading initial schema from JSON: {}", path_json_schema);
+        let json_string = std::fs::read_to_string(path_json_schema)
+            .map_err(|e| anyhow::anyhow!("failed to read schema JSON: {}", e))?;
+        serde_json::from_str(&json_string).map_err(|e| {
+            crate::ErrorCategory::InputParse.error(format!("failed to parse schema JSON: {}", e))
+        })?
+    } else {
+        None
     };
-    tracing::info!("  using infer config: {:#?}", &infer_config);
-    let infer_ctx = tsv::schema::infer::Context::new(&infer_config);
-    let mut schema: Option<tsv::schema::FileSchema> =
-        if let Some(path_json_schema) = &args.path_schema_json {
-            tracing::info!("  loading initial schema from JSON: {}", path_json_schema);
-            let json_string = std::fs::read_to_string(path_json_schema)
-                .map_err(|e| anyhow::anyhow!("failed to read schema JSON: {}", e))?;
-            serde_json::from_str(&json_string)
-                .map_err(|e| anyhow::anyhow!("failed to parse schema JSON: {}", e))?
-        } else {
-            None
-        };
+    let mut drifts = Vec::new();
     for path_in_tsv in &args.path_in_tsv {
         tracing::info!("  infer schema from TSV: {}", path_in_tsv);
         let other = infer_ctx.infer_from_path(path_in_tsv)?;
         schema = if let Some(schema) = &schema {
+            let columns = schema.drift(&other);
+            if !columns.is_empty() {
+                for column in &columns {
+                    tracing::warn!("schema drift in {}: {}", path_in_tsv, column);
+                }
+                drifts.push(tsv::schema::FileDrift {
+                    path: path_in_tsv.clone(),
+                    columns,
+                });
+                if args.strict_schema {
+                    return Err(crate::ErrorCategory::InputParse.error(format!(
+                        "schema of {} differs from the previous input files (--strict-schema)",
+                        path_in_tsv
+                    )));
+                }
+            }
             Some(schema.merge(&other)?)
         } else {
             Some(other)
         }
     }
     let schema = schema.ok_or_else(|| anyhow::anyhow!("failed to infer schema"))?;
+    Ok((schema, drifts))
+}
+
+/// Detect the genome release from the coordinates in the input TSV files.
+fn detect_genome_release(
+    args: &Args,
+    infer_config: &tsv::schema::infer::Config,
+    schema: &tsv::schema::FileSchema,
+) -> Result<common::cli::GenomeRelease, anyhow::Error> {
+    let ctx = tsv::coding::Context::new(infer_config.clone(), schema.clone());
+    let readers = args
+        .path_in_tsv
+        .iter()
+        .map(|path_in_tsv| no_tbi::open_tsv(path_in_tsv))
+        .collect::<Result<Vec<_>, _>>()?;
+    let positions = readers
+        .into_iter()
+        .flat_map(|reader| reader.lines().skip(args.skip_row_count + 1))
+        .filter_map(|line| {
+            let var = line.map_err(anyhow::Error::from).and_then(|line| {
+                let values = ctx.line_to_values(&line)?;
+                Ok(ctx.values_to_var(&values.iter().collect::<Vec<_>>())?)
+            });
+            var.transpose()
+                .map(|var| var.map(|var| (var.chrom, var.pos as usize)))
+        });
+    common::assembly_detect::from_positions(positions)
+}
+
+/// Whether a tabix index exists for all input files, in which case they are imported in
+/// parallel windows.
+fn have_tbi(args: &Args) -> bool {
+    args.path_in_tsv
+        .iter()
+        .all(|p| std::path::Path::new(&format!("{}.tbi", &p)).exists())
+}
+
+/// Validate the inputs and output path without writing the database.
+pub fn validate(args: &Args) -> common::dry_run::Validation {
+    let mut validation = common::dry_run::Validation::default();
+    let mut all_inputs_ok = true;
+    for path_in_tsv in &args.path_in_tsv {
+        all_inputs_ok &= validation.input_file(path_in_tsv);
+    }
+    if all_inputs_ok {
+        let infer_config = build_infer_config(args);
+        let schema = validation.check(
+            "schema can be inferred from TSV file(s)",
+            infer_schema(args, &infer_config),
+        );
+        if let Some((schema, _)) = schema {
+            let genome_release = validation.genome_release(args.genome_release, || {
+                detect_genome_release(args, &infer_config, &schema)
+            });
+            if have_tbi(args) {
+                for path_in_tsv in &args.path_in_tsv {
+                    if let (true, Some(genome_release)) =
+                        (validation.tabix_index(path_in_tsv), genome_release)
+                    {
+                        validation.check(
+                            format!("import windows can be built for {}", path_in_tsv),
+                            noodles::tabix::read(format!("{}.tbi", path_in_tsv))
+                                .map_err(anyhow::Error::from)
+                                .and_then(|index| {
+                                    par_tbi::build_windows(
+                                        &index,
+                                        genome_release,
+                                        args.tbi_window_size,
+                                    )
+                                }),
+                        );
+                    }
+                }
+            }
+            for col_name in [
+                &args.col_chrom,
+                &args.col_start,
+                &args.col_ref,
+                &args.col_alt,
+            ] {
+                validation.check(
+                    format!("column {} is present in TSV header", col_name),
+                    if schema.columns.iter().any(|col| &col.name == col_name) {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("column not found"))
+                    },
+                );
+            }
+        }
+    }
+    validation.output_dir(&args.path_out_rocksdb);
+    validation.check(
+        "spec.yaml can be written",
+        args.spec.check(&args.path_out_rocksdb),
+    );
+    validation
+}
+
+/// Implementation of `tsv import` sub command.
+pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("Starting 'tsv import' command");
+    tracing::info!("common = {:#?}", &common);
+    tracing::info!("args = {:#?}", &args);
+
+    if args.dry_run {
+        return validate(args).finish();
+    }
+    args.spec.check(&args.path_out_rocksdb)?;
+    let sampler = common::sample::Sampler::new(&args.sample)?;
+
+    // Infer the schema from the input TSV file.
+    tracing::info!("Inferring schema from TSV ...");
+    let before_inference = std::time::Instant::now();
+    let infer_config = build_infer_config(args);
+    tracing::info!("  using infer config: {:#?}", &infer_config);
+    let (schema, schema_drift) = infer_schema(args, &infer_config)?;
     tracing::info!(
         "... done inferring schema from TSV in {:?}",
         before_inference.elapsed()
     );
+    let genome_release = args
+        .genome_release
+        .resolve(|| detect_genome_release(args, &infer_config, &schema))?;
 
     // Open the RocksDB for writing.
     tracing::info!("Opening RocksDB for writing ...");
@@ -165,6 +524,11 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         rocksdb::Options::default(),
         args.path_wal_dir.as_ref().map(|s| s.as_ref()),
     );
+    let options = if args.prefix_bloom {
+        common::prefix_bloom::tune_options(options)
+    } else {
+        options
+    };
     let cf_names = &["meta", &args.cf_name];
     let db = Arc::new(rocksdb::DB::open_cf_with_opts(
         &options,
@@ -177,14 +541,20 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("  writing meta information");
     let cf_meta = db.cf_handle("meta").unwrap();
     db.put_cf(&cf_meta, "annonars-version", crate::VERSION)?;
-    db.put_cf(
-        &cf_meta,
-        "genome-release",
-        format!("{}", args.genome_release),
-    )?;
+    crate::common::norm::write_meta(&db, &cf_meta)?;
+    if args.prefix_bloom {
+        crate::common::prefix_bloom::write_meta(&db, &cf_meta)?;
+    }
+    db.put_cf(&cf_meta, "genome-release", format!("{}", genome_release))?;
     db.put_cf(&cf_meta, "db-name", &args.db_name)?;
     db.put_cf(&cf_meta, "db-version", &args.db_version)?;
+    sampler.write_meta(&db, &cf_meta)?;
     db.put_cf(&cf_meta, "db-schema", serde_json::to_string(&schema)?)?;
+    db.put_cf(
+        &cf_meta,
+        "schema-drift",
+        serde_json::to_string(&schema_drift)?,
+    )?;
     db.put_cf(
         &cf_meta,
         "db-infer-config",
@@ -203,10 +573,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     // Check whether a TBI index file exists for all input files.
     tracing::info!("Checking whether TBI index files exist ...");
     let before_checking_tbi = std::time::Instant::now();
-    let have_tbi = args
-        .path_in_tsv
-        .iter()
-        .all(|p| std::path::Path::new(&format!("{}.tbi", &p)).exists());
+    let have_tbi = have_tbi(args);
     if have_tbi {
         tracing::info!(
             "  have TBI files, will import one after the other with parallel processing"
@@ -221,11 +588,35 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
     tracing::info!("Importing TSV files ...");
     let before_import = std::time::Instant::now();
+    let skipped = SkippedLines::new(args.error_behavior);
     if have_tbi {
         // If we have TBI files then we can import the files them using window-based
         // parallelism.  We should import them one after another, though.
         for path_in_tsv in &args.path_in_tsv {
-            par_tbi::tsv_import(&db, args, &infer_config, &schema, path_in_tsv)?;
+            par_tbi::tsv_import(
+                &db,
+                args,
+                &infer_config,
+                &schema,
+                path_in_tsv,
+                genome_release,
+                &skipped,
+                &sampler,
+            )?;
+        }
+    } else if args.on_duplicate != OnDuplicate::Overwrite {
+        // Handling duplicates requires a deterministic order, so import one file after
+        // the other.
+        for path_in_tsv in &args.path_in_tsv {
+            no_tbi::tsv_import(
+                &db,
+                args,
+                &infer_config,
+                &schema,
+                path_in_tsv,
+                &skipped,
+                &sampler,
+            )?;
         }
     } else {
         // If we don't have TBI files then we have to import them sequentially but
@@ -233,21 +624,55 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
         args.path_in_tsv
             .par_iter()
             .progress_with(common::cli::progress_bar(args.path_in_tsv.len()))
-            .map(|path_in_tsv| no_tbi::tsv_import(&db, args, &infer_config, &schema, path_in_tsv))
+            .map(|path_in_tsv| {
+                no_tbi::tsv_import(
+                    &db,
+                    args,
+                    &infer_config,
+                    &schema,
+                    path_in_tsv,
+                    &skipped,
+                    &sampler,
+                )
+            })
             .collect::<Result<Vec<_>, _>>()?;
     }
     tracing::info!(
         "... done importing TSV files in {:?}",
         before_import.elapsed()
     );
+    sampler.log();
+    db.put_cf(
+        &cf_meta,
+        "import-skipped-lines",
+        skipped.count().to_string(),
+    )?;
+    skipped.finish()?;
 
-    tracing::info!("Running RocksDB compaction ...");
-    let before_compaction = std::time::Instant::now();
-    rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
-    tracing::info!(
-        "... done compacting RocksDB in {:?}",
-        before_compaction.elapsed()
-    );
+    if args.incremental_compaction && have_tbi {
+        tracing::info!("Skipping final RocksDB compaction, chromosomes have been compacted");
+    } else {
+        tracing::info!("Running RocksDB compaction ...");
+        let before_compaction = std::time::Instant::now();
+        rocksdb_utils_lookup::force_compaction_cf(&db, cf_names, Some("  "), true)?;
+        tracing::info!(
+            "... done compacting RocksDB in {:?}",
+            before_compaction.elapsed()
+        );
+    }
+
+    common::record_stats::write_meta(&db, &[args.cf_name.as_str()])?;
+
+    args.spec.write(
+        &args.path_out_rocksdb,
+        &common::spec::ImportSpec {
+            db_name: args.db_name.clone(),
+            title: args.db_name.clone(),
+            genome_release: Some(genome_release),
+            created_from: vec![(args.db_name.clone(), args.db_version.clone())],
+            paths_in: args.path_in_tsv.clone(),
+        },
+    )?;
 
     tracing::info!("All done. Have a nice day!");
     Ok(())
@@ -266,17 +691,27 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in_tsv: vec![String::from("tests/tsv/example/data.tsv")],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             path_wal_dir: None,
-            genome_release: common::cli::GenomeRelease::Grch37,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
             db_name: String::from("test"),
             db_version: String::from("0.0.0"),
             cf_name: String::from("data"),
             skip_row_count: 0,
             path_schema_json: None,
+            strict_schema: false,
             inference_row_count: 100,
             tbi_window_size: 1000000,
             col_chrom: String::from("CHROM"),
@@ -296,17 +731,504 @@ mod test {
         let tmp_dir = TempDir::default();
         let common = common::cli::Args {
             verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv.bgz")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+
+        run(&common, &args).unwrap();
+    }
+
+    /// Incremental compaction must result in the same data as the final compaction.
+    #[test]
+    fn import_tsv_with_tbi_incremental_compaction() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv.bgz")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+        run(&common, &args)?;
+        let args_incremental = Args {
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb-incremental").display()),
+            incremental_compaction: true,
+            ..args.clone()
+        };
+        run(&common, &args_incremental)?;
+
+        let dump = |path: &str| -> Result<Vec<_>, anyhow::Error> {
+            let db = rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                path,
+                ["meta", "data"],
+                false,
+            )?;
+            let cf_data = db.cf_handle("data").unwrap();
+            Ok(db
+                .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+                .collect::<Result<Vec<_>, _>>()?)
+        };
+        let records = dump(&args.path_out_rocksdb)?;
+        assert!(!records.is_empty());
+        assert_eq!(records, dump(&args_incremental.path_out_rocksdb)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_tsv_with_prefix_bloom() -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv.bgz")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+        run(&common, &args)?;
+        let args_prefix_bloom = Args {
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb-prefix-bloom").display()),
+            prefix_bloom: true,
+            ..args.clone()
+        };
+        run(&common, &args_prefix_bloom)?;
+
+        let dump = |path: &str| -> Result<(bool, Vec<_>), anyhow::Error> {
+            let db = rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                path,
+                ["meta", "data"],
+                false,
+            )?;
+            let cf_meta = db.cf_handle("meta").unwrap();
+            let cf_data = db.cf_handle("data").unwrap();
+            Ok((
+                common::prefix_bloom::read_meta(&db, &cf_meta)?,
+                db.iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ))
+        };
+        let (prefix_bloom, records) = dump(&args.path_out_rocksdb)?;
+        assert!(!prefix_bloom);
+        assert!(!records.is_empty());
+        assert_eq!((true, records), dump(&args_prefix_bloom.path_out_rocksdb)?);
+
+        Ok(())
+    }
+
+    /// Windows without data in the index are skipped, the result is the same as without TBI.
+    #[test]
+    fn import_tsv_with_tbi_skips_empty_windows() -> Result<(), anyhow::Error> {
+        let index = noodles::tabix::read("tests/tsv/example/data.tsv.bgz.tbi")?;
+        let windows = par_tbi::build_windows(&index, common::cli::GenomeRelease::Grch37, 1000000)?;
+        assert_eq!(
+            windows
+                .iter()
+                .map(|(_, region)| region.to_string())
+                .collect::<Vec<_>>(),
+            vec![String::from("1:1-1000000")]
+        );
+
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_in_tsv: vec![String::from("tests/tsv/example/data.tsv.bgz")],
             path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
             path_wal_dir: None,
-            genome_release: common::cli::GenomeRelease::Grch37,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+        run(&common, &args)?;
+        let args_no_tbi = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb-no-tbi").display()),
+            ..args.clone()
+        };
+        run(&common, &args_no_tbi)?;
+
+        let dump = |path: &str| -> Result<Vec<_>, anyhow::Error> {
+            let db = rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                path,
+                ["meta", "data"],
+                false,
+            )?;
+            let cf_data = db.cf_handle("data").unwrap();
+            Ok(db
+                .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+                .collect::<Result<Vec<_>, _>>()?)
+        };
+        let records = dump(&args.path_out_rocksdb)?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records, dump(&args_no_tbi.path_out_rocksdb)?);
+
+        Ok(())
+    }
+
+    /// The example coordinates fit both releases, so auto-detection must fail.
+    #[test]
+    fn import_tsv_auto_genome_release_ambiguous() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Auto,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+
+        let err = run(&common, &args).unwrap_err();
+        assert!(err.to_string().contains("compatible with both"));
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+    }
+
+    /// Import the overlapping duplicates fixtures in the given order and dump the data.
+    fn import_duplicates(
+        tmp_dir: &TempDir,
+        names: &[&str],
+        on_duplicate: OnDuplicate,
+    ) -> Result<Vec<(Box<[u8]>, Box<[u8]>)>, anyhow::Error> {
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_out_rocksdb = format!(
+            "{}",
+            tmp_dir
+                .join(format!("out-rocksdb-{}-{}", on_duplicate, names.join("-")))
+                .display()
+        );
+        let args = Args {
+            path_in_tsv: names
+                .iter()
+                .map(|name| format!("tests/tsv/duplicates/{}.tsv", name))
+                .collect(),
+            path_out_rocksdb: path_out_rocksdb.clone(),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: common::spec::ArgsSpec {
+                no_write_spec_yaml: true,
+                ..Default::default()
+            },
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+        run(&common, &args)?;
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &path_out_rocksdb,
+            ["meta", "data"],
+            false,
+        )?;
+        let cf_data = db.cf_handle("data").unwrap();
+        Ok(db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Duplicate handling must not depend on the processing order.
+    #[rstest::rstest]
+    #[case::merge_columns(OnDuplicate::MergeColumns)]
+    #[case::error(OnDuplicate::Error)]
+    #[case::first_wins(OnDuplicate::FirstWins)]
+    fn import_tsv_on_duplicate(#[case] on_duplicate: OnDuplicate) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+
+        let values = |records: Vec<(Box<[u8]>, Box<[u8]>)>| {
+            records
+                .into_iter()
+                .map(|(_, value)| String::from_utf8(value.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let a_b = import_duplicates(&tmp_dir, &["a", "b"], on_duplicate);
+        let b_a = import_duplicates(&tmp_dir, &["b", "a"], on_duplicate);
+        match on_duplicate {
+            OnDuplicate::Error => {
+                assert!(a_b.unwrap_err().to_string().contains("duplicate variant"));
+                assert!(b_a.unwrap_err().to_string().contains("duplicate variant"));
+            }
+            OnDuplicate::MergeColumns => {
+                let a_b = values(a_b?);
+                assert_eq!(a_b.len(), 3);
+                assert_eq!(a_b[0], "1\t1000\tA\tT\t0.1\tbenign");
+                assert_eq!(a_b, values(b_a?));
+            }
+            OnDuplicate::FirstWins => {
+                // The first input file wins, independent of the processing order.
+                assert_eq!(values(a_b?)[0], "1\t1000\tA\tT\t0.1\tNA");
+                assert_eq!(values(b_a?)[0], "1\t1000\tA\tT\tNA\tbenign");
+                assert_eq!(
+                    import_duplicates(&tmp_dir, &["a", "a"], on_duplicate)?,
+                    import_duplicates(&tmp_dir, &["a"], on_duplicate)?
+                );
+            }
+            OnDuplicate::Overwrite => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    /// Schema drift between the input files is written to meta and fails `--strict-schema`.
+    #[rstest::rstest]
+    #[case::lenient(false)]
+    #[case::strict(true)]
+    fn import_tsv_schema_drift(#[case] strict_schema: bool) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_out_rocksdb = format!("{}", tmp_dir.join("out-rocksdb").display());
+        let args = Args {
+            path_in_tsv: vec![
+                String::from("tests/tsv/drift/a.tsv"),
+                String::from("tests/tsv/drift/b.tsv"),
+            ],
+            path_out_rocksdb: path_out_rocksdb.clone(),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema,
+            inference_row_count: 100,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+
+        if strict_schema {
+            let err = run(&common, &args).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "schema of tests/tsv/drift/b.tsv differs from the previous input files \
+                (--strict-schema)"
+            );
+            assert!(!tmp_dir.join("out-rocksdb").exists());
+        } else {
+            run(&common, &args)?;
+            let db = rocksdb::DB::open_cf_for_read_only(
+                &rocksdb::Options::default(),
+                &path_out_rocksdb,
+                ["meta", "data"],
+                false,
+            )?;
+            let schema_drift: Vec<tsv::schema::FileDrift> = serde_json::from_str(
+                &rocksdb_utils_lookup::fetch_meta(&db, "schema-drift")?.unwrap(),
+            )?;
+            assert_eq!(
+                schema_drift,
+                vec![tsv::schema::FileDrift {
+                    path: String::from("tests/tsv/drift/b.tsv"),
+                    columns: vec![tsv::schema::ColumnDrift::TypeChange {
+                        column: String::from("score"),
+                        typ: tsv::schema::ColumnType::Float,
+                        other_typ: tsv::schema::ColumnType::String,
+                    }],
+                }]
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Dry run must not create the database and must report all problems.
+    #[test]
+    fn dry_run_import_tsv() {
+        let tmp_dir = TempDir::default();
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv")],
+            path_out_rocksdb: format!("{}", tmp_dir.join("out-rocksdb").display()),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior: ErrorBehavior::Abort,
+            dry_run: true,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
             db_name: String::from("test"),
             db_version: String::from("0.0.0"),
             cf_name: String::from("data"),
             skip_row_count: 0,
             path_schema_json: None,
+            strict_schema: false,
             inference_row_count: 100,
             tbi_window_size: 1000000,
             col_chrom: String::from("CHROM"),
@@ -318,5 +1240,161 @@ mod test {
         };
 
         run(&common, &args).unwrap();
+        assert!(!tmp_dir.join("out-rocksdb").exists());
+
+        // With a tabix index, the index is read and the import windows are built.
+        let args_tbi = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv.bgz")],
+            ..args.clone()
+        };
+        let validation = validate(&args_tbi);
+        assert!(validation.problems.is_empty(), "{:?}", validation.problems);
+        assert!(validation.passed.contains(&String::from(
+            "import windows can be built for tests/tsv/example/data.tsv.bgz"
+        )));
+
+        let args = Args {
+            path_in_tsv: vec![
+                String::from("tests/tsv/example/data.tsv"),
+                String::from("tests/tsv/example/missing.tsv"),
+            ],
+            col_alt: String::from("MISSING"),
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1);
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/example/data.tsv")],
+            ..args
+        };
+        let validation = validate(&args);
+        assert_eq!(validation.problems.len(), 1);
+        assert!(run(&common, &args).is_err());
+    }
+
+    /// Import the malformed lines fixture with `error_behavior` and dump the data.
+    fn import_malformed(
+        tmp_dir: &TempDir,
+        error_behavior: ErrorBehavior,
+    ) -> Result<(String, Vec<(Box<[u8]>, Box<[u8]>)>), anyhow::Error> {
+        let common = common::cli::Args {
+            verbose: Verbosity::new(1, 0),
+            no_progress: false,
+        };
+        let path_out_rocksdb = format!("{}", tmp_dir.join("out-rocksdb").display());
+        let args = Args {
+            path_in_tsv: vec![String::from("tests/tsv/malformed/data.tsv")],
+            path_out_rocksdb: path_out_rocksdb.clone(),
+            path_wal_dir: None,
+            incremental_compaction: false,
+            prefix_bloom: false,
+            allow_iupac: false,
+            on_duplicate: OnDuplicate::Overwrite,
+            error_behavior,
+            dry_run: false,
+            sample: Default::default(),
+            spec: Default::default(),
+            genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+            db_name: String::from("test"),
+            db_version: String::from("0.0.0"),
+            cf_name: String::from("data"),
+            skip_row_count: 0,
+            path_schema_json: None,
+            strict_schema: false,
+            // Infer the schema from the well-formed lines at the top only.
+            inference_row_count: 3,
+            tbi_window_size: 1000000,
+            col_chrom: String::from("CHROM"),
+            col_start: String::from("POS"),
+            col_ref: String::from("REF"),
+            col_alt: String::from("ALT"),
+            null_values: Vec::new(),
+            add_default_null_values: true,
+        };
+        run(&common, &args)?;
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &path_out_rocksdb,
+            ["meta", "data"],
+            false,
+        )?;
+        let cf_meta = db.cf_handle("meta").unwrap();
+        let skipped_lines = String::from_utf8(
+            db.get_cf(&cf_meta, "import-skipped-lines")?
+                .expect("missing meta:import-skipped-lines"),
+        )?;
+        let cf_data = db.cf_handle("data").unwrap();
+        let records = db
+            .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((skipped_lines, records))
+    }
+
+    #[rstest::rstest]
+    #[case::abort(
+        ErrorBehavior::Abort,
+        Some("problem with line tests/tsv/malformed/data.tsv:5")
+    )]
+    #[case::skip(ErrorBehavior::Skip, None)]
+    #[case::skip_with_limit_ok(ErrorBehavior::SkipWithLimit(2), None)]
+    #[case::skip_with_limit_exceeded(
+        ErrorBehavior::SkipWithLimit(1),
+        Some("skipped 2 malformed line(s), more than the limit of 1")
+    )]
+    fn import_tsv_error_behavior(
+        #[case] error_behavior: ErrorBehavior,
+        #[case] expected_err: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = TempDir::default();
+
+        let result = import_malformed(&tmp_dir, error_behavior);
+
+        if let Some(expected_err) = expected_err {
+            let err = result.unwrap_err().to_string();
+            assert!(err.starts_with(expected_err), "{}", err);
+        } else {
+            let (skipped_lines, records) = result?;
+            assert_eq!(skipped_lines, "2");
+            assert_eq!(
+                records
+                    .into_iter()
+                    .map(|(_, value)| String::from_utf8(value.to_vec()).unwrap())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "1\t1000\tA\tT\t0.1\ta",
+                    "1\t2000\tC\tG\t0.5\tb",
+                    "1\t3000\tG\tA\t0.7\tc",
+                    "1\t6000\tC\tT\t0.3\tf",
+                ]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("abort", ErrorBehavior::Abort)]
+    #[case("skip", ErrorBehavior::Skip)]
+    #[case("skip-with-limit=10", ErrorBehavior::SkipWithLimit(10))]
+    fn error_behavior_from_str(
+        #[case] value: &str,
+        #[case] expected: ErrorBehavior,
+    ) -> Result<(), anyhow::Error> {
+        let error_behavior: ErrorBehavior = value.parse()?;
+
+        assert_eq!(error_behavior, expected);
+        assert_eq!(error_behavior.to_string(), value);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("skip-with-limit")]
+    #[case("skip-with-limit=")]
+    #[case("skip-with-limit=-1")]
+    fn error_behavior_from_str_invalid(#[case] value: &str) {
+        assert!(value.parse::<ErrorBehavior>().is_err());
     }
 }
@@ -8,26 +8,7 @@ use rayon::prelude::*;
 
 use crate::{common, tsv};
 
-use super::Args;
-
-/// Helper function to resolve regions.
-fn resolve_region(
-    header: &noodles::csi::binning_index::index::Header,
-    region: &noodles::core::Region,
-) -> std::io::Result<usize> {
-    header
-        .reference_sequence_names()
-        .get_index_of(
-            std::str::from_utf8(region.name())
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
-        )
-        .ok_or_else(|| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "missing reference sequence name",
-            )
-        })
-}
+use super::{Args, SkippedLines};
 
 /// Helper function for parsing start positions.
 pub fn parse_start_position(
@@ -78,6 +59,9 @@ pub fn intersects(
 }
 
 /// Perform the import of a single region.
+///
+/// Malformed lines are reported with the window as the line number in the file is not known.
+#[allow(clippy::too_many_arguments)]
 pub fn tsv_import_window(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     args: &Args,
@@ -85,6 +69,8 @@ pub fn tsv_import_window(
     schema: &tsv::schema::FileSchema,
     path_in_tsv: &str,
     window: &(usize, noodles::core::Region),
+    skipped: &SkippedLines,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
     // Get column family handle.
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
@@ -104,61 +90,117 @@ pub fn tsv_import_window(
 
     // Read through the overlapping lines.
     let ctx = tsv::coding::Context::new(config.clone(), schema.clone());
+    let mut invalid_alleles = common::keys::InvalidAlleles::default();
     for result in query.lines() {
         let line = result?;
 
         if intersects(header, &line, region)? {
-            super::process_tsv_line(&line, &ctx, db, &cf_data)?;
+            skipped.handle(
+                &format!("{} in window {}", path_in_tsv, region),
+                super::process_tsv_line(
+                    &line,
+                    &ctx,
+                    db,
+                    &cf_data,
+                    args.allow_iupac,
+                    &mut invalid_alleles,
+                    args.on_duplicate,
+                    sampler,
+                ),
+            )?;
         }
     }
+    invalid_alleles.log(&format!("{} in window {}", path_in_tsv, region));
 
     Ok(())
 }
 
+/// Build the genome windows of `genome_release` for the TBI-parallel import.
+///
+/// Only windows on reference sequences present in the tabix `index` are returned and windows
+/// for which the index has no data are skipped.
+pub fn build_windows(
+    index: &noodles::tabix::Index,
+    genome_release: common::cli::GenomeRelease,
+    window_size: usize,
+) -> Result<Vec<(usize, noodles::core::Region)>, anyhow::Error> {
+    let header = index.header().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing tabix header")
+    })?;
+
+    // Generate list of windows on canonical chromosomes, limited to those present in tbi index.
+    let windows = common::cli::build_genome_windows(genome_release.into(), Some(window_size))?
+        .into_iter()
+        .filter_map(|(chrom, begin, end)| {
+            header
+                .reference_sequence_names()
+                .get_index_of(chrom.as_str())
+                .map(|tid| (tid, chrom, begin, end))
+        })
+        .collect::<Vec<_>>();
+    let windows = common::cli::prune_genome_windows(windows, |(tid, _, begin, end)| {
+        common::cli::tbi_window_has_data(index, *tid, *begin, *end)
+    })?;
+
+    Ok(windows
+        .into_iter()
+        .map(|(tid, chrom, begin, end)| {
+            let start = noodles::core::Position::try_from(begin + 1)
+                .expect("could not convert to position");
+            let stop = noodles::core::Position::try_from(std::cmp::max(begin + 1, end))
+                .expect("could not convert to position");
+            (tid, noodles::core::Region::new(chrom, start..=stop))
+        })
+        .collect())
+}
+
 /// Perform the import of multiple TSV files in parallel using region-based parallelism.
+#[allow(clippy::too_many_arguments)]
 pub fn tsv_import(
     db: &rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>,
     args: &Args,
     config: &tsv::schema::infer::Config,
     schema: &tsv::schema::FileSchema,
     path_in_tsv: &str,
+    genome_release: common::cli::GenomeRelease,
+    skipped: &SkippedLines,
+    sampler: &common::sample::Sampler,
 ) -> Result<(), anyhow::Error> {
-    // Load tabix header and create BGZF reader with tabix index.
+    // Load tabix index and build the list of windows with data.
     let tabix_src = format!("{}.tbi", path_in_tsv);
     let index = noodles::tabix::read(tabix_src)?;
-    let header = index.header().ok_or_else(|| {
-        std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing tabix header")
-    })?;
-
-    // Generate list of regions on canonical chromosomes, limited to those present in tbi index.
-    let regions =
-        common::cli::build_genome_windows(args.genome_release.into(), Some(args.tbi_window_size))?
-            .into_iter()
-            .filter(|(chrom, _, _)| {
-                header
-                    .reference_sequence_names()
-                    .get_index_of(chrom)
-                    .is_some()
-            })
-            .map(|(chrom, begin, end)| {
-                let start = noodles::core::Position::try_from(begin + 1)
-                    .expect("could not convert to position");
-                let stop = noodles::core::Position::try_from(std::cmp::max(begin + 1, end))
-                    .expect("could not convert to position");
-                let region = noodles::core::Region::new(chrom, start..=stop);
-                let tid = resolve_region(header, &region)
-                    .unwrap_or_else(|e| panic!("could not resolve region {:?}: {}", region, e));
-                (tid, region)
-            })
-            .collect::<Vec<_>>();
+    let regions = build_windows(&index, genome_release, args.tbi_window_size)?;
 
     // Import each region in parallel.
     tracing::info!("  importing TBI-parallel: {}", path_in_tsv);
-    regions
-        .par_iter()
-        .progress_with(common::cli::progress_bar(regions.len()))
-        .map(|region| tsv_import_window(db, args, config, schema, path_in_tsv, region))
-        .collect::<Result<Vec<_>, _>>()?;
+    let import_regions = |regions: &[(usize, noodles::core::Region)]| {
+        regions
+            .par_iter()
+            .progress_with(common::cli::progress_bar(regions.len()))
+            .map(|region| {
+                tsv_import_window(
+                    db,
+                    args,
+                    config,
+                    schema,
+                    path_in_tsv,
+                    region,
+                    skipped,
+                    sampler,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    if args.incremental_compaction {
+        // Import one chromosome after the other and compact it right away.
+        for chrom_regions in regions.chunk_by(|(lhs, _), (rhs, _)| lhs == rhs) {
+            import_regions(chrom_regions)?;
+            let chrom = std::str::from_utf8(chrom_regions[0].1.name())?;
+            common::compaction::compact_chrom(db, &args.cf_name, chrom)?;
+        }
+    } else {
+        import_regions(&regions)?;
+    }
 
     Ok(())
 }
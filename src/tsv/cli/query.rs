@@ -1,6 +1,11 @@
 //! Query for variants, positions, or ranges.
+//!
+//! With `--all`, the whole database is exported in key order.  An interrupted export can
+//! be resumed with `--start-after-key` and the hex-encoded key of the last record written,
+//! which is logged at the end of each scan, e.g., together with `--limit` to export in
+//! chunks.
 
-use std::sync::Arc;
+use std::{io::Write as _, sync::Arc};
 
 use crate::{
     common::{self, cli::extract_chrom, keys, spdi},
@@ -22,12 +27,23 @@ pub struct Args {
     #[arg(long, default_value = "-")]
     pub out_file: String,
     /// Output format.
-    #[arg(long, default_value = "jsonl")]
-    pub out_format: common::cli::OutputFormat,
+    #[command(flatten)]
+    pub output: common::output::ArgsOutput,
+    /// Write the records as TSV with the header of the imported file rather than in
+    /// `--output-format`.
+    #[arg(long)]
+    pub output_tsv: bool,
 
     /// Variant or position to query for.
     #[command(flatten)]
     pub query: ArgsQuery,
+    /// With `--all`, start after the record with this hex-encoded key, i.e., the last
+    /// record written by an interrupted export.
+    #[arg(long, requires = "all")]
+    pub start_after_key: Option<String>,
+    /// Number of records to skip and write.
+    #[command(flatten)]
+    pub limit: common::cli::ArgsLimit,
 }
 
 /// Meta information as read from database.
@@ -43,6 +59,8 @@ pub struct Meta {
     pub db_schema: schema::FileSchema,
     /// Inference configuration.
     pub db_infer_config: schema::infer::Config,
+    /// Whether the alleles of the keys are trimmed, see `common::norm`.
+    pub keys_normalized: bool,
 }
 
 /// Open RocksDb given path and column family name for data and metadata.
@@ -56,39 +74,40 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
     let cf_names = &[cf_meta, cf_data];
     let db = Arc::new(rocksdb::DB::open_cf_for_read_only(
         &rocksdb::Options::default(),
-        common::readlink_f(&path_rocksdb)?,
+        common::readlink_f_existing(&path_rocksdb)?,
         cf_names,
         true,
     )?);
     tracing::info!("  reading meta information");
     let meta = {
         let cf_meta = db.cf_handle(cf_meta).unwrap();
-        let meta_db_name = String::from_utf8(
-            db.get_cf(&cf_meta, "db-name")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-schema"))?,
-        )?;
-        let meta_genome_release = String::from_utf8(
-            db.get_cf(&cf_meta, "genome-release")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:genome-release"))?,
-        )?;
-        let meta_db_version = String::from_utf8(
-            db.get_cf(&cf_meta, "db-version")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-schema"))?,
-        )?;
-        let meta_db_schema = String::from_utf8(
-            db.get_cf(&cf_meta, "db-schema")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-schema"))?,
-        )?;
-        let meta_db_infer_config = String::from_utf8(
-            db.get_cf(&cf_meta, "db-infer-config")?
-                .ok_or_else(|| anyhow::anyhow!("missing value meta:db-infer-config"))?,
-        )?;
+        let meta_db_name =
+            String::from_utf8(db.get_cf(&cf_meta, "db-name")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-schema")
+            })?)?;
+        let meta_genome_release =
+            String::from_utf8(db.get_cf(&cf_meta, "genome-release")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:genome-release")
+            })?)?;
+        let meta_db_version =
+            String::from_utf8(db.get_cf(&cf_meta, "db-version")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-schema")
+            })?)?;
+        let meta_db_schema =
+            String::from_utf8(db.get_cf(&cf_meta, "db-schema")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-schema")
+            })?)?;
+        let meta_db_infer_config =
+            String::from_utf8(db.get_cf(&cf_meta, "db-infer-config")?.ok_or_else(|| {
+                crate::ErrorCategory::Database.error("missing value meta:db-infer-config")
+            })?)?;
         Meta {
             genome_release: meta_genome_release,
             db_name: meta_db_name,
             db_version: meta_db_version,
             db_schema: serde_json::from_str(&meta_db_schema)?,
             db_infer_config: serde_json::from_str(&meta_db_infer_config)?,
+            keys_normalized: common::norm::read_meta(&db, &cf_meta)?,
         }
     };
 
@@ -103,6 +122,7 @@ pub fn open_rocksdb<P: AsRef<std::path::Path>>(
         "  meta:db-infer-config = {}",
         &serde_json::to_string(&meta.db_infer_config)?
     );
+    tracing::info!("  meta:keys-normalized = {}", &meta.keys_normalized);
     tracing::info!(
         "... opening RocksDB database took {:?}",
         before_open.elapsed()
@@ -118,30 +138,99 @@ pub fn open_rocksdb_from_args(
     open_rocksdb(&args.path_rocksdb, &args.cf_name, "meta")
 }
 
-/// Print values to `out_writer`.
+/// Print values with the given `key` to `out_writer`.
 fn print_values(
-    out_writer: &mut Box<dyn std::io::Write>,
-    output_format: common::cli::OutputFormat,
+    out_writer: &mut common::output::RecordWriter,
+    key: &str,
     meta: &Meta,
     values: &[serde_json::Value],
 ) -> Result<(), anyhow::Error> {
-    match output_format {
-        common::cli::OutputFormat::Jsonl => {
-            let mut map = serde_json::Map::new();
-            for (col, value) in meta.db_schema.columns.iter().zip(values.iter()) {
-                if !value.is_null() {
-                    map.insert(col.name.clone(), value.clone());
-                }
+    let mut map = serde_json::Map::new();
+    for (col, value) in meta.db_schema.columns.iter().zip(values.iter()) {
+        if !value.is_null() {
+            map.insert(col.name.clone(), value.clone());
+        }
+    }
+    out_writer.write(key, &serde_json::Value::Object(map))
+}
+
+/// Return the header line of the imported TSV file, reconstructed from the schema.
+pub fn tsv_header(meta: &Meta) -> String {
+    format!(
+        "{}{}",
+        meta.db_infer_config.header_prefix,
+        meta.db_schema
+            .columns
+            .iter()
+            .map(|col| col.name.as_str())
+            .collect::<Vec<_>>()
+            .join(&meta.db_infer_config.field_delimiter.to_string())
+    )
+}
+
+/// Writer of the records of `tsv query`.
+enum QueryWriter {
+    /// Records in the selected `--output-format`.
+    Records(common::output::RecordWriter),
+    /// Lines of the TSV file with `--output-tsv`.
+    Tsv(std::io::BufWriter<Box<dyn std::io::Write>>),
+}
+
+impl QueryWriter {
+    /// Create the writer to `out_file` for `args`, writing the header with `--output-tsv`.
+    fn new(args: &Args, meta: &Meta) -> Result<Self, anyhow::Error> {
+        if args.output_tsv {
+            let mut out_writer =
+                std::io::BufWriter::new(common::output::open_out_file(&args.out_file)?);
+            writeln!(out_writer, "{}", tsv_header(meta))?;
+            Ok(Self::Tsv(out_writer))
+        } else {
+            Ok(Self::Records(common::output::RecordWriter::new(
+                &args.out_file,
+                args.output.output_format,
+                &meta.db_name,
+                Some(&meta.genome_release),
+            )?))
+        }
+    }
+
+    /// Write the record with the given `key` and decoded `values`.
+    fn write_values(
+        &mut self,
+        key: &str,
+        meta: &Meta,
+        ctx: &coding::Context,
+        values: &[serde_json::Value],
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Records(out_writer) => print_values(out_writer, key, meta, values),
+            Self::Tsv(out_writer) => Ok(writeln!(out_writer, "{}", ctx.values_to_line(values)?)?),
+        }
+    }
+
+    /// Write the record with the given `key` and TSV `line` as stored in the database.
+    fn write_line(
+        &mut self,
+        key: &str,
+        meta: &Meta,
+        ctx: &coding::Context,
+        line: &str,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Records(out_writer) => {
+                print_values(out_writer, key, meta, &ctx.line_to_values(line)?)
             }
-            writeln!(
-                out_writer,
-                "{}",
-                serde_json::to_string(&serde_json::Value::Object(map))?
-            )?;
+            Self::Tsv(out_writer) => Ok(writeln!(out_writer, "{}", line)?),
         }
     }
 
-    Ok(())
+    /// Flush the output.
+    fn finish(self) -> Result<(), anyhow::Error> {
+        if let Self::Tsv(mut out_writer) = self {
+            out_writer.flush()?;
+        }
+        Ok(())
+    }
 }
 
 /// Query for a single variant in the RocksDB database.
@@ -158,8 +247,7 @@ pub fn query_for_variant(
         ..variant.clone()
     };
     tracing::debug!("query = {:?}", &query);
-    let var: keys::Var = query.into();
-    let key: Vec<u8> = var.into();
+    let key = common::norm::query_key(query, meta.keys_normalized);
     let raw_value = db
         .get_cf(cf_data, key)
         .map_err(|e| anyhow::anyhow!("problem querying RocksDB: {}", e))?;
@@ -177,25 +265,33 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
     tracing::info!("Starting 'tsv query' command");
     tracing::info!("common = {:#?}", &common);
     tracing::info!("args = {:#?}", &args);
+    common::batch::check_unsupported(&args.query, "tsv query")?;
 
+    let start_after_key = args
+        .start_after_key
+        .as_deref()
+        .map(keys::from_hex)
+        .transpose()?;
     let (db, meta) = open_rocksdb_from_args(args)?;
     let cf_data = db.cf_handle(&args.cf_name).unwrap();
     let ctx = coding::Context::new(meta.db_infer_config.clone(), meta.db_schema.clone());
 
     // Obtain writer to output.
-    let mut out_writer = match args.out_file.as_ref() {
-        "-" => Box::new(std::io::stdout()) as Box<dyn std::io::Write>,
-        out_file => {
-            let path = std::path::Path::new(out_file);
-            Box::new(std::fs::File::create(path).unwrap()) as Box<dyn std::io::Write>
-        }
-    };
+    let mut out_writer = QueryWriter::new(args, &meta)?;
 
     tracing::info!("Running query...");
     let before_query = std::time::Instant::now();
+    let mut counter = args.limit.counter();
     if let Some(variant) = args.query.variant.as_ref() {
-        if let Some(record) = query_for_variant(variant, &meta, &db, &cf_data, &ctx)? {
-            print_values(&mut out_writer, args.out_format, &meta, &record)?;
+        if let Some(record) =
+            query_for_variant(variant, &meta, &db, &cf_data, &ctx)?.filter(|_| counter.admit())
+        {
+            out_writer.write_values(
+                &common::output::var_key(variant, Some(&meta.genome_release))?,
+                &meta,
+                &ctx,
+                &record,
+            )?;
         }
     } else {
         let (start, stop) = if let Some(position) = args.query.position.as_ref() {
@@ -205,10 +301,7 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             };
             (Some(position.clone()), Some(position))
         } else if let Some(range) = args.query.range.as_ref() {
-            let range = spdi::Range {
-                sequence: extract_chrom::from_range(range, Some(&meta.genome_release))?,
-                ..range.clone()
-            };
+            let range = extract_chrom::range(range, Some(&meta.genome_release))?;
             let (start, stop) = range.into();
             (Some(start), Some(stop))
         } else if args.query.all {
@@ -221,7 +314,13 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
 
         // Obtain iterator and seek to start.
         let mut iter = db.raw_iterator_cf(&cf_data);
-        if let Some(start) = start {
+        if let Some(start_after_key) = start_after_key.as_ref() {
+            tracing::debug!("seeking after key {:?}", start_after_key);
+            iter.seek(start_after_key);
+            if iter.key() == Some(start_after_key.as_slice()) {
+                iter.next();
+            }
+        } else if let Some(start) = start {
             let pos: keys::Pos = start.into();
             let key: Vec<u8> = pos.into();
             tracing::debug!("seeking to key {:?}", &key);
@@ -237,12 +336,13 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
             tracing::debug!("stop = {:?}", &stop);
         }
 
-        // Iterate over all variants until we are behind stop.
-        while iter.valid() {
+        // Iterate over all variants until we are behind stop or reach the limit.
+        let mut last_key = None;
+        while iter.valid() && !counter.is_done() {
             if let Some(line_raw) = iter.value() {
                 tracing::trace!("iterator at {:?} => {:?}", &iter.key(), &line_raw);
+                let iter_key = iter.key().unwrap();
                 if let Some(stop) = stop.as_ref() {
-                    let iter_key = iter.key().unwrap();
                     let iter_pos: keys::Pos = iter_key.into();
 
                     if iter_pos.chrom != stop.chrom || iter_pos.pos > stop.pos {
@@ -250,15 +350,26 @@ pub fn run(common: &common::cli::Args, args: &Args) -> Result<(), anyhow::Error>
                     }
                 }
 
-                let line = std::str::from_utf8(line_raw)?;
-                let values = ctx.line_to_values(line)?;
-                print_values(&mut out_writer, args.out_format, &meta, &values)?;
+                if counter.admit() {
+                    let line = std::str::from_utf8(line_raw)?;
+                    out_writer.write_line(&common::output::db_key(iter_key), &meta, &ctx, line)?;
+                    last_key = Some(iter_key.to_vec());
+                }
                 iter.next();
             } else {
                 break;
             }
         }
+        iter.status()?;
+        if let Some(last_key) = last_key {
+            tracing::info!(
+                "  wrote {} records, resume after the last one with --start-after-key {}",
+                counter.written(),
+                keys::to_hex(&last_key)
+            );
+        }
     }
+    out_writer.finish()?;
     tracing::info!("... done querying in {:?}", before_query.elapsed());
 
     tracing::info!("All done. Have a nice day!");
@@ -277,18 +388,219 @@ mod test {
         let temp = TempDir::default();
         let common = common::cli::Args {
             verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+            no_progress: false,
         };
         let args = Args {
             path_rocksdb: String::from("tests/tsv/example/data.tsv.gz.db"),
             cf_name: String::from("tsv_data"),
             out_file: temp.join("out").to_string_lossy().to_string(),
-            out_format: common::cli::OutputFormat::Jsonl,
+            output: Default::default(),
+            output_tsv: false,
             query,
+            start_after_key: None,
+            limit: Default::default(),
         };
 
         (common, args, temp)
     }
 
+    /// Import a TSV file with `n` variants on each of chr1, chr2, and chrX and return the
+    /// arguments for exporting all of it.
+    fn args_all_imported(temp: &TempDir, n: usize) -> Result<Args, anyhow::Error> {
+        let path_tsv = temp.join("data.tsv");
+        let mut lines = vec![String::from("#CHROM\tPOS\tREF\tALT\tscore\tname")];
+        for chrom in ["chr1", "chr2", "chrX"] {
+            for i in 1..=n {
+                lines.push(format!("{}\t{}\tA\tT\t0.{}\tv{}", chrom, i * 100, i, i));
+            }
+        }
+        std::fs::write(&path_tsv, lines.join("\n") + "\n")?;
+        let path_rocksdb = temp.join("rocksdb").to_string_lossy().to_string();
+        crate::tsv::cli::import::run(
+            &common::cli::Args {
+                verbose: clap_verbosity_flag::Verbosity::new(1, 0),
+                no_progress: false,
+            },
+            &crate::tsv::cli::import::Args {
+                path_in_tsv: vec![path_tsv.to_string_lossy().to_string()],
+                path_out_rocksdb: path_rocksdb.clone(),
+                path_wal_dir: None,
+                incremental_compaction: false,
+                prefix_bloom: false,
+                allow_iupac: false,
+                on_duplicate: crate::tsv::cli::import::OnDuplicate::Overwrite,
+                error_behavior: crate::tsv::cli::import::ErrorBehavior::Abort,
+                dry_run: false,
+                sample: Default::default(),
+                spec: common::spec::ArgsSpec {
+                    no_write_spec_yaml: true,
+                    ..Default::default()
+                },
+                genome_release: common::assembly_detect::GenomeReleaseArg::Grch37,
+                db_name: String::from("test"),
+                db_version: String::from("0.0.0"),
+                cf_name: String::from("tsv_data"),
+                skip_row_count: 0,
+                path_schema_json: None,
+                strict_schema: false,
+                inference_row_count: 100,
+                tbi_window_size: 1000000,
+                col_chrom: String::from("CHROM"),
+                col_start: String::from("POS"),
+                col_ref: String::from("REF"),
+                col_alt: String::from("ALT"),
+                null_values: Vec::new(),
+                add_default_null_values: true,
+            },
+        )?;
+
+        Ok(Args {
+            path_rocksdb,
+            cf_name: String::from("tsv_data"),
+            out_file: temp.join("out-all").to_string_lossy().to_string(),
+            output: Default::default(),
+            output_tsv: false,
+            query: ArgsQuery {
+                all: true,
+                ..Default::default()
+            },
+            start_after_key: None,
+            limit: Default::default(),
+        })
+    }
+
+    #[rstest::rstest]
+    #[case::jsonl(false)]
+    #[case::tsv(true)]
+    fn query_all_resume(#[case] output_tsv: bool) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let (common, _, _temp) = args(Default::default());
+        let args_all = Args {
+            output_tsv,
+            ..args_all_imported(&temp, 5)?
+        };
+        run(&common, &args_all)?;
+        let out_all = std::fs::read_to_string(&args_all.out_file)?;
+        assert_eq!(out_all.lines().count(), 15 + usize::from(output_tsv));
+
+        // Export the first half and take the key of the last record written from the
+        // database as logged by `run`.
+        let args_first = Args {
+            out_file: temp.join("out-first").to_string_lossy().to_string(),
+            limit: common::cli::ArgsLimit {
+                limit: Some(7),
+                offset: 0,
+            },
+            ..args_all.clone()
+        };
+        run(&common, &args_first)?;
+        let last_key = {
+            let (db, _) = open_rocksdb_from_args(&args_all)?;
+            let cf_data = db.cf_handle("tsv_data").unwrap();
+            let (key, _) = db
+                .iterator_cf(&cf_data, rocksdb::IteratorMode::Start)
+                .nth(6)
+                .unwrap()?;
+            keys::to_hex(&key)
+        };
+        let args_rest = Args {
+            out_file: temp.join("out-rest").to_string_lossy().to_string(),
+            start_after_key: Some(last_key),
+            ..args_all.clone()
+        };
+        run(&common, &args_rest)?;
+
+        let out_first = std::fs::read_to_string(&args_first.out_file)?;
+        let out_rest = std::fs::read_to_string(&args_rest.out_file)?;
+        assert_eq!(out_first.lines().count(), 7 + usize::from(output_tsv));
+        let out_rest = if output_tsv {
+            // Both parts have the header.
+            out_rest.split_once('\n').unwrap().1.to_string()
+        } else {
+            out_rest
+        };
+        assert_eq!(out_first + &out_rest, out_all);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(None, 0, 15)]
+    #[case(Some(4), 0, 4)]
+    #[case(None, 10, 5)]
+    #[case(Some(4), 10, 4)]
+    #[case(Some(4), 13, 2)]
+    #[case(Some(0), 0, 0)]
+    #[case(None, 20, 0)]
+    fn query_all_limit_offset(
+        #[case] limit: Option<usize>,
+        #[case] offset: usize,
+        #[case] expected: usize,
+    ) -> Result<(), anyhow::Error> {
+        let temp = TempDir::default();
+        let (common, _, _temp) = args(Default::default());
+        let args_all = args_all_imported(&temp, 5)?;
+        run(&common, &args_all)?;
+        let out_all = std::fs::read_to_string(&args_all.out_file)?;
+        let args = Args {
+            out_file: temp.join("out-limit").to_string_lossy().to_string(),
+            limit: common::cli::ArgsLimit { limit, offset },
+            ..args_all
+        };
+        run(&common, &args)?;
+
+        let out = std::fs::read_to_string(&args.out_file)?;
+        assert_eq!(out.lines().count(), expected);
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            out_all
+                .lines()
+                .skip(offset)
+                .take(expected)
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_all_tsv() -> Result<(), anyhow::Error> {
+        let (common, args, _temp) = args(ArgsQuery {
+            all: true,
+            ..Default::default()
+        });
+        let args = Args {
+            output_tsv: true,
+            ..args
+        };
+        run(&common, &args)?;
+
+        // The header is written with the default header prefix.
+        assert_eq!(
+            std::fs::read_to_string(&args.out_file)?,
+            format!(
+                "#{}",
+                std::fs::read_to_string("tests/tsv/example/data.tsv")?
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_invalid_start_after_key() {
+        let (common, args, _temp) = args(ArgsQuery {
+            all: true,
+            ..Default::default()
+        });
+        let args = Args {
+            start_after_key: Some(String::from("0x")),
+            ..args
+        };
+
+        assert!(run(&common, &args).is_err());
+    }
+
     #[test]
     fn smoke_query_all() -> Result<(), anyhow::Error> {
         let (common, args, _temp) = args(ArgsQuery {
@@ -315,6 +627,32 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn smoke_query_var_lowercase() -> Result<(), anyhow::Error> {
+        let (common, args_upper, _temp_upper) = args(ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:1000:A:T")?),
+            ..Default::default()
+        });
+        run(&common, &args_upper)?;
+        let (common, args_lower, _temp_lower) = args(ArgsQuery {
+            variant: Some(spdi::Var::new(
+                String::from("GRCh37:1"),
+                1000,
+                String::from("a"),
+                String::from("t"),
+            )),
+            ..Default::default()
+        });
+        run(&common, &args_lower)?;
+
+        let out_upper = std::fs::read_to_string(&args_upper.out_file)?;
+        let out_lower = std::fs::read_to_string(&args_lower.out_file)?;
+        assert!(!out_upper.is_empty());
+        assert_eq!(out_upper, out_lower);
+
+        Ok(())
+    }
+
     #[test]
     fn smoke_query_pos() -> Result<(), anyhow::Error> {
         let (common, args, _temp) = args(ArgsQuery {
@@ -392,4 +730,46 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest::rstest]
+    #[case::variant(
+        ArgsQuery {
+            variant: Some(spdi::Var::from_str("GRCh37:1:1000:A:T").unwrap()),
+            ..Default::default()
+        },
+        &["1:1000:A:T"],
+    )]
+    #[case::range(
+        ArgsQuery {
+            range: Some(spdi::Range::from_str("GRCh37:1:1000:1001").unwrap()),
+            ..Default::default()
+        },
+        &["1:1000:A:T", "1:1001:A:T"],
+    )]
+    fn smoke_query_jsonl_v2(
+        #[case] query: ArgsQuery,
+        #[case] expected_keys: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        let (common, args_v1, _temp_v1) = args(query.clone());
+        run(&common, &args_v1)?;
+        let (common, args_v2, _temp_v2) = args(query);
+        let args_v2 = Args {
+            output: common::output::ArgsOutput {
+                output_format: common::cli::OutputFormat::JsonlV2,
+            },
+            ..args_v2
+        };
+        run(&common, &args_v2)?;
+        let (_db, meta) = open_rocksdb_from_args(&args_v2)?;
+
+        common::output::assert_envelopes(
+            &std::fs::read_to_string(&args_v1.out_file)?,
+            &std::fs::read_to_string(&args_v2.out_file)?,
+            &meta.db_name,
+            Some("grch37"),
+            expected_keys,
+        );
+
+        Ok(())
+    }
 }
@@ -132,6 +132,95 @@ impl FileSchema {
     }
 }
 
+/// Difference of one column between two schemas, see `FileSchema::drift`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ColumnDrift {
+    /// The column has a different type in the other schema.
+    TypeChange {
+        /// Column name.
+        column: String,
+        /// Column type in this schema.
+        typ: ColumnType,
+        /// Column type in the other schema.
+        other_typ: ColumnType,
+    },
+    /// The column is missing in the other schema.
+    Missing {
+        /// Column name.
+        column: String,
+    },
+    /// The column is only present in the other schema.
+    Added {
+        /// Column name.
+        column: String,
+    },
+}
+
+impl std::fmt::Display for ColumnDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnDrift::TypeChange {
+                column,
+                typ,
+                other_typ,
+            } => write!(
+                f,
+                "column {} changes from {:?} to {:?}",
+                column, typ, other_typ
+            ),
+            ColumnDrift::Missing { column } => write!(f, "column {} is missing", column),
+            ColumnDrift::Added { column } => write!(f, "column {} is added", column),
+        }
+    }
+}
+
+/// Schema drift of one input file compared to the files before it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FileDrift {
+    /// Path of the input file.
+    pub path: String,
+    /// The differing columns.
+    pub columns: Vec<ColumnDrift>,
+}
+
+impl FileSchema {
+    /// Return the column differences from `self` to `other`.
+    ///
+    /// Columns are matched by name.  Columns with only null values in either schema have
+    /// the `Unknown` type that is compatible with all types and thus not reported.
+    pub fn drift(&self, other: &FileSchema) -> Vec<ColumnDrift> {
+        let mut result = Vec::new();
+        for col in &self.columns {
+            match other.columns.iter().find(|other| other.name == col.name) {
+                Some(other)
+                    if col.typ != other.typ
+                        && col.typ != ColumnType::Unknown
+                        && other.typ != ColumnType::Unknown =>
+                {
+                    result.push(ColumnDrift::TypeChange {
+                        column: col.name.clone(),
+                        typ: col.typ,
+                        other_typ: other.typ,
+                    })
+                }
+                Some(_) => (),
+                None => result.push(ColumnDrift::Missing {
+                    column: col.name.clone(),
+                }),
+            }
+        }
+        for other in &other.columns {
+            if !self.columns.iter().any(|col| col.name == other.name) {
+                result.push(ColumnDrift::Added {
+                    column: other.name.clone(),
+                });
+            }
+        }
+        result
+    }
+}
+
 /// Schema description for a table.
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct FileSchema {
@@ -423,6 +512,78 @@ mod test {
         Ok(())
     }
 
+    /// Return a schema with the columns `(name, typ)`.
+    fn schema(columns: &[(&str, ColumnType)]) -> FileSchema {
+        FileSchema::from(
+            columns
+                .iter()
+                .map(|(name, typ)| ColumnSchema::from(name, *typ))
+                .collect(),
+            vec![String::from(".")],
+        )
+    }
+
+    #[test]
+    fn fileschema_drift_none() {
+        let schema1 = schema(&[("a", ColumnType::Integer), ("b", ColumnType::Unknown)]);
+        let schema2 = schema(&[("a", ColumnType::Integer), ("b", ColumnType::String)]);
+
+        assert_eq!(schema1.drift(&schema2), vec![]);
+        assert_eq!(schema2.drift(&schema1), vec![]);
+    }
+
+    #[test]
+    fn fileschema_drift_type_change() {
+        let schema1 = schema(&[("a", ColumnType::Float), ("b", ColumnType::Integer)]);
+        let schema2 = schema(&[("a", ColumnType::String), ("b", ColumnType::Integer)]);
+
+        let drift = schema1.drift(&schema2);
+
+        assert_eq!(
+            drift,
+            vec![ColumnDrift::TypeChange {
+                column: String::from("a"),
+                typ: ColumnType::Float,
+                other_typ: ColumnType::String,
+            }]
+        );
+        assert_eq!(
+            drift[0].to_string(),
+            "column a changes from Float to String"
+        );
+    }
+
+    #[test]
+    fn fileschema_drift_columns() -> Result<(), anyhow::Error> {
+        let schema1 = schema(&[("a", ColumnType::Integer), ("b", ColumnType::String)]);
+        let schema2 = schema(&[("a", ColumnType::Float), ("c", ColumnType::String)]);
+
+        let drift = schema1.drift(&schema2);
+
+        assert_eq!(
+            drift,
+            vec![
+                ColumnDrift::TypeChange {
+                    column: String::from("a"),
+                    typ: ColumnType::Integer,
+                    other_typ: ColumnType::Float,
+                },
+                ColumnDrift::Missing {
+                    column: String::from("b"),
+                },
+                ColumnDrift::Added {
+                    column: String::from("c"),
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&drift[1..])?,
+            r#"[{"kind":"missing","column":"b"},{"kind":"added","column":"c"}]"#
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn infer_schema_empty() -> Result<(), anyhow::Error> {
         let config = infer::Config {
@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+set -euo pipefail
+set -x
+
+bgzip -c tests/alphamissense/example/alphamissense.tsv \
+> tests/alphamissense/example/alphamissense.tsv.bgz
+tabix -f -s 1 -b 2 -e 2 -c '#' tests/alphamissense/example/alphamissense.tsv.bgz
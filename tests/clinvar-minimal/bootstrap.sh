@@ -15,6 +15,11 @@ zgrep -w HGNC:20324 $TMPDIR/clinvar-data-extract-vars-20240528+0.15.5/clinvar-va
 > tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.jsonl
 set -o pipefail
 
+# NB: clinvar-seqvars-grch37-somatic.jsonl is derived by hand from the first TGDS record,
+# replacing the germline classification by somatic clinical impact and oncogenicity ones.
+# NB: clinvar-seqvars-grch37-details.jsonl is derived by hand from the first TGDS record,
+# adding two clinical assertions (SCVs) for testing `--with-details`.
+
 rm -rf tests/clinvar-minimal/clinvar-seqvars-grch37-tgds.db
 cargo run --all-features -- \
     clinvar-minimal import -vvv \
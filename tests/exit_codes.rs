@@ -0,0 +1,174 @@
+//! Tests of the exit codes of the `annonars` binary, see `annonars::ErrorCategory`.
+
+use std::process::Command;
+
+/// Run `annonars` with `args` and return the exit code and stderr.
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_annonars"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[test]
+fn usage_invalid_arguments() {
+    let (code, stderr) = run(&["tsv", "query", "--no-such-argument"]);
+
+    assert_eq!(code, Some(2), "{}", stderr);
+}
+
+#[test]
+fn usage_genome_release_mismatch() {
+    let (code, stderr) = run(&[
+        "dbsnp",
+        "query",
+        "--path-rocksdb",
+        "tests/dbsnp/example/dbsnp.brca1.vcf.bgz.db",
+        "--variant",
+        "GRCh38:17:41197708:T:G",
+    ]);
+
+    assert_eq!(code, Some(2), "{}", stderr);
+    assert!(stderr.contains("genome release mismatch"), "{}", stderr);
+}
+
+#[test]
+fn not_found_database() {
+    let tmp_dir = temp_testdir::TempDir::default();
+    let path_rocksdb = tmp_dir.join("no-such-db");
+
+    let (code, stderr) = run(&[
+        "tsv",
+        "query",
+        "--path-rocksdb",
+        &path_rocksdb.to_string_lossy(),
+        "--all",
+    ]);
+
+    assert_eq!(code, Some(3), "{}", stderr);
+    assert!(stderr.contains("does not exist"), "{}", stderr);
+}
+
+#[test]
+fn input_parse_malformed_tsv() {
+    let tmp_dir = temp_testdir::TempDir::default();
+    let path_in_tsv = tmp_dir.join("malformed.tsv");
+    std::fs::write(
+        &path_in_tsv,
+        "CHROM\tPOS\tREF\tALT\tpayload\nchr1\t1000\tA\tT\t0.1\nchr1\t1001\tA\n",
+    )
+    .unwrap();
+    let path_out_rocksdb = tmp_dir.join("out-rocksdb");
+
+    let (code, stderr) = run(&[
+        "tsv",
+        "import",
+        "--genome-release",
+        "grch37",
+        "--path-in-tsv",
+        &path_in_tsv.to_string_lossy(),
+        "--path-out-rocksdb",
+        &path_out_rocksdb.to_string_lossy(),
+        "--db-name",
+        "test",
+        "--db-version",
+        "0.0.0",
+        "--col-chrom",
+        "CHROM",
+        "--col-start",
+        "POS",
+        "--col-ref",
+        "REF",
+        "--col-alt",
+        "ALT",
+    ]);
+
+    assert_eq!(code, Some(4), "{}", stderr);
+}
+
+#[test]
+fn database_not_a_database() {
+    let tmp_dir = temp_testdir::TempDir::default();
+
+    let (code, stderr) = run(&[
+        "tsv",
+        "query",
+        "--path-rocksdb",
+        &tmp_dir.to_string_lossy(),
+        "--all",
+    ]);
+
+    assert_eq!(code, Some(5), "{}", stderr);
+}
+
+/// Write a ClinVar genes database to `path` with the pathogenic counts of the genes.
+fn write_clinvar_genes_db(path: &std::path::Path, counts: &[(&str, u32)]) {
+    use annonars::pbs::clinvar::per_gene::ClinvarPerGeneRecord;
+    use annonars::pbs::clinvar_data::gene_impact::{
+        gene_impact_counts::ImpactCounts, GeneImpactCounts,
+    };
+    use prost::Message as _;
+
+    let mut options = rocksdb::Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+    let db = rocksdb::DB::open_cf(&options, path, ["meta", "clinvar-genes"]).unwrap();
+    let cf_data = db.cf_handle("clinvar-genes").unwrap();
+    for (hgnc_id, count_pathogenic) in counts {
+        let record = ClinvarPerGeneRecord {
+            per_impact_counts: Some(GeneImpactCounts {
+                hgnc_id: hgnc_id.to_string(),
+                impact_counts: vec![ImpactCounts {
+                    count_pathogenic: *count_pathogenic,
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        };
+        db.put_cf(&cf_data, hgnc_id, record.encode_to_vec())
+            .unwrap();
+    }
+}
+
+#[rstest::rstest]
+#[case::unchanged(2, Some(0))]
+#[case::changed(3, Some(1))]
+fn clinvar_genes_watchlist(#[case] new_count: u32, #[case] expected: Option<i32>) {
+    let tmp_dir = temp_testdir::TempDir::default();
+    let path_old = tmp_dir.join("old.db");
+    let path_new = tmp_dir.join("new.db");
+    let path_genes = tmp_dir.join("genes.txt");
+    write_clinvar_genes_db(&path_old, &[("HGNC:1100", 2), ("HGNC:1101", 1)]);
+    write_clinvar_genes_db(&path_new, &[("HGNC:1100", new_count), ("HGNC:1101", 5)]);
+    std::fs::write(&path_genes, "HGNC:1100\n").unwrap();
+
+    let (code, stderr) = run(&[
+        "clinvar-genes",
+        "watchlist",
+        "--path-old-rocksdb",
+        &path_old.to_string_lossy(),
+        "--path-new-rocksdb",
+        &path_new.to_string_lossy(),
+        "--path-genes",
+        &path_genes.to_string_lossy(),
+        "--out-file",
+        &tmp_dir.join("out.tsv").to_string_lossy(),
+    ]);
+
+    assert_eq!(code, expected, "{}", stderr);
+}
+
+#[test]
+fn help_documents_exit_codes() {
+    let output = Command::new(env!("CARGO_BIN_EXE_annonars"))
+        .arg("--help")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Exit codes:"));
+}
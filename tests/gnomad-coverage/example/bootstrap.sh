@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+
+# Excerpts of the gnomAD coverage summary files around PCSK9, a position on a
+# non-canonical contig was appended to each by hand.
+
+set -euo pipefail
+set -x
+
+(
+    zcat gnomad.genomes.coverage.summary.tsv.bgz | head -n 1
+    tabix gnomad.genomes.coverage.summary.tsv.bgz 1:55505595-55505604
+) > tests/gnomad-coverage/example/gnomad.genomes.r2.1.coverage.tsv
+
+(
+    zcat gnomad.genomes.r3.0.1.coverage.summary.tsv.bgz | head -n 1
+    tabix gnomad.genomes.r3.0.1.coverage.summary.tsv.bgz chr1:55039836-55039840
+) > tests/gnomad-coverage/example/gnomad.genomes.r3.0.1.coverage.tsv
@@ -0,0 +1,55 @@
+//! Tests of the progress bar handling of the `annonars` binary.
+
+use std::process::Command;
+
+/// Run `tsv import` on the example data with `--no-progress` and return stdout and stderr.
+fn tsv_import(tmp_dir: &temp_testdir::TempDir) -> (Vec<u8>, Vec<u8>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_annonars"))
+        .args([
+            "tsv",
+            "import",
+            "--no-progress",
+            "--genome-release",
+            "grch37",
+            "--path-in-tsv",
+            "tests/tsv/example/data.tsv",
+            "--path-out-rocksdb",
+        ])
+        .arg(tmp_dir.join("out-rocksdb"))
+        .args([
+            "--db-name",
+            "test",
+            "--db-version",
+            "0.0.0",
+            "--cf-name",
+            "data",
+            "--col-chrom",
+            "CHROM",
+            "--col-start",
+            "POS",
+            "--col-ref",
+            "REF",
+            "--col-alt",
+            "ALT",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (output.stdout, output.stderr)
+}
+
+#[test]
+fn tsv_import_no_progress() {
+    let tmp_dir = temp_testdir::TempDir::default();
+
+    let (stdout, stderr) = tsv_import(&tmp_dir);
+
+    assert!(String::from_utf8_lossy(&stderr).contains("All done"));
+    assert!(!stdout.contains(&b'\r'));
+    assert!(!stderr.contains(&b'\r'));
+}
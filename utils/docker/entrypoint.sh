@@ -30,8 +30,12 @@ set -euo pipefail
 #   PATH_DB_GNOMAD_EXOMES_38  -- path to gnomAD exomes database for GRCh38, defaults to $PATH_DB_BASE/grch38/gnomad-exomes/rocksdb
 #   PATH_DB_GNOMAD_GENOMES_37 -- path to gnomAD genomes database for GRCh37, defaults to $PATH_DB_BASE/grch37/gnomad-genomes/rocksdb
 #   PATH_DB_GNOMAD_GENOMES_38 -- path to gnomAD genomes database for GRCh38, defaults to $PATH_DB_BASE/grch38/gnomad-genomes/rocksdb
+#   PATH_DB_GNOMAD_COVERAGE_37 -- path to gnomAD coverage database for GRCh37, defaults to $PATH_DB_BASE/grch37/gnomad-coverage/rocksdb
+#   PATH_DB_GNOMAD_COVERAGE_38 -- path to gnomAD coverage database for GRCh38, defaults to $PATH_DB_BASE/grch38/gnomad-coverage/rocksdb
 #   PATH_DB_HELIXMTDB_37      -- path to HelixMTdb database for GRCh37, defaults to $PATH_DB_BASE/grch37/helixmtdb/rocksdb
 #   PATH_DB_HELIXMTDB_38      -- path to HelixMTdb database for GRCh38, defaults to $PATH_DB_BASE/grch38/helixmtdb/rocksdb
+#   PATH_DB_ALPHAMISSENSE_37  -- path to AlphaMissense database for GRCh37, defaults to $PATH_DB_BASE/grch37/alphamissense/rocksdb
+#   PATH_DB_ALPHAMISSENSE_38  -- path to AlphaMissense database for GRCh38, defaults to $PATH_DB_BASE/grch38/alphamissense/rocksdb
 #   PATH_DB_CONS_37           -- path to UCSC conservation database for GRCh37, defaults to $PATH_DB_BASE/grch37/cons/rocksdb
 #   PATH_DB_CONS_38           -- path to UCSC conservation database for GRCh38, defaults to $PATH_DB_BASE/grch38/cons/rocksdb
 #
@@ -59,8 +63,12 @@ PATH_DB_GNOMAD_EXOMES_37=${PATH_DB_GNOMAD_EXOMES_37-$PATH_DB_BASE/grch37/gnomad-
 PATH_DB_GNOMAD_EXOMES_38=${PATH_DB_GNOMAD_EXOMES_38-$PATH_DB_BASE/grch38/gnomad-exomes/rocksdb}
 PATH_DB_GNOMAD_GENOMES_37=${PATH_DB_GNOMAD_GENOMES_37-$PATH_DB_BASE/grch37/gnomad-genomes/rocksdb}
 PATH_DB_GNOMAD_GENOMES_38=${PATH_DB_GNOMAD_GENOMES_38-$PATH_DB_BASE/grch38/gnomad-genomes/rocksdb}
+PATH_DB_GNOMAD_COVERAGE_37=${PATH_DB_GNOMAD_COVERAGE_37-$PATH_DB_BASE/grch37/gnomad-coverage/rocksdb}
+PATH_DB_GNOMAD_COVERAGE_38=${PATH_DB_GNOMAD_COVERAGE_38-$PATH_DB_BASE/grch38/gnomad-coverage/rocksdb}
 PATH_DB_HELIXMTDB_37=${PATH_DB_HELIXMTDB_37-$PATH_DB_BASE/grch37/helixmtdb/rocksdb}
 PATH_DB_HELIXMTDB_38=${PATH_DB_HELIXMTDB_38-$PATH_DB_BASE/grch38/helixmtdb/rocksdb}
+PATH_DB_ALPHAMISSENSE_37=${PATH_DB_ALPHAMISSENSE_37-$PATH_DB_BASE/grch37/alphamissense/rocksdb}
+PATH_DB_ALPHAMISSENSE_38=${PATH_DB_ALPHAMISSENSE_38-$PATH_DB_BASE/grch38/alphamissense/rocksdb}
 PATH_DB_CONS_37=${PATH_DB_CONS_37-$PATH_DB_BASE/grch37/cons/rocksdb}
 PATH_DB_CONS_38=${PATH_DB_CONS_38-$PATH_DB_BASE/grch38/cons/rocksdb}
 PATH_GENES=${PATH_GENES-$PATH_DB_BASE/genes/rocksdb}
@@ -93,8 +101,12 @@ else
       $(test -e $PATH_DB_GNOMAD_EXOMES_38 && echo --path-gnomad-exomes $PATH_DB_GNOMAD_EXOMES_38) \
       $(test -e $PATH_DB_GNOMAD_GENOMES_37 && echo --path-gnomad-genomes $PATH_DB_GNOMAD_GENOMES_37) \
       $(test -e $PATH_DB_GNOMAD_GENOMES_38 && echo --path-gnomad-genomes $PATH_DB_GNOMAD_GENOMES_38) \
+      $(test -e $PATH_DB_GNOMAD_COVERAGE_37 && echo --path-gnomad-coverage $PATH_DB_GNOMAD_COVERAGE_37) \
+      $(test -e $PATH_DB_GNOMAD_COVERAGE_38 && echo --path-gnomad-coverage $PATH_DB_GNOMAD_COVERAGE_38) \
       $(test -e $PATH_DB_HELIXMTDB_37 && echo --path-helixmtdb $PATH_DB_HELIXMTDB_37) \
       $(test -e $PATH_DB_HELIXMTDB_38 && echo --path-helixmtdb $PATH_DB_HELIXMTDB_38) \
+      $(test -e $PATH_DB_ALPHAMISSENSE_37 && echo --path-alphamissense $PATH_DB_ALPHAMISSENSE_37) \
+      $(test -e $PATH_DB_ALPHAMISSENSE_38 && echo --path-alphamissense $PATH_DB_ALPHAMISSENSE_38) \
       $(test -e $PATH_DB_CONS_37 && echo --path-ucsc-conservation $PATH_DB_CONS_37) \
       $(test -e $PATH_DB_CONS_38 && echo --path-ucsc-conservation $PATH_DB_CONS_38) \
       $(test -e $PATH_GENES && echo --path-genes $PATH_GENES) \